@@ -0,0 +1,222 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Address books stored on disk.
+//!
+//! Hosts are stored in three separate books:
+//!  * private: hosts added by the user that are never published
+//!  * local: hosts added by the user that may be published as a `hosts.txt` feed
+//!  * router: hosts downloaded from subscriptions
+//!
+//! When a host is resolved, the private book takes precedence over the local book which in turn
+//! takes precedence over the router book.
+
+use emissary_core::{
+    crypto::{base32_encode, base64_decode},
+    primitives::Destination,
+};
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Maximum length of a hostname.
+const MAX_HOST_LEN: usize = 67usize;
+
+/// Address book kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Book {
+    /// Private address book.
+    Private,
+
+    /// Local address book.
+    Local,
+
+    /// Router address book.
+    Router,
+}
+
+impl Book {
+    /// Books in the order of precedence.
+    pub const ALL: [Book; 3] = [Book::Private, Book::Local, Book::Router];
+
+    /// Get path of the book, relative to `base_path`.
+    pub fn path(&self, base_path: &Path) -> PathBuf {
+        match self {
+            Self::Private => base_path.join("addressbook/private"),
+            Self::Local => base_path.join("addressbook/local"),
+            Self::Router => base_path.join("addressbook/addresses"),
+        }
+    }
+
+    /// Get name of the book.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Private => "private",
+            Self::Local => "local",
+            Self::Router => "router",
+        }
+    }
+}
+
+/// Check if `host` is a valid `.i2p` hostname.
+pub fn is_valid_host(host: &str) -> bool {
+    host.len() <= MAX_HOST_LEN
+        && host.ends_with(".i2p")
+        && !host.ends_with(".b32.i2p")
+        && !host.starts_with('.')
+        && !host.contains("..")
+        && host
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.' || c == '-')
+}
+
+/// Parse base64 `destination` and return its `.b32.i2p` address, without the suffix.
+pub fn b32_address(destination: &str) -> Option<String> {
+    base64_decode(destination)
+        .and_then(Destination::parse)
+        .map(|destination| base32_encode(destination.id().to_vec()))
+}
+
+/// Parse a single `line` of an address book into a host and a destination.
+///
+/// Returns `None` for comments and lines without an entry. The destination is empty if the
+/// entry removes the host.
+pub fn parse_entry(line: &str) -> Option<(String, String)> {
+    let line = line.split_once("#!").map_or(line, |(entry, _)| entry).trim();
+
+    if line.starts_with('#') {
+        return None;
+    }
+
+    line.split_once('=')
+        .map(|(host, destination)| (host.trim().to_string(), destination.trim().to_string()))
+}
+
+/// Parse `contents` of an address book into a map of hosts.
+///
+/// Empty lines and comments are ignored and if a host is listed multiple times, the last entry
+/// wins. An entry without a destination removes the host.
+pub fn parse(contents: &str) -> BTreeMap<String, String> {
+    contents.lines().filter_map(parse_entry).fold(
+        BTreeMap::new(),
+        |mut hosts, (host, destination)| {
+            match destination.is_empty() {
+                true => hosts.remove(&host),
+                false => hosts.insert(host, destination),
+            };
+            hosts
+        },
+    )
+}
+
+/// Serialize `hosts` into `hosts.txt` format.
+pub fn serialize<'a>(hosts: impl IntoIterator<Item = (&'a String, &'a String)>) -> String {
+    hosts.into_iter().fold(String::new(), |mut out, (host, destination)| {
+        out.push_str(&format!("{host}={destination}\n"));
+        out
+    })
+}
+
+/// Load `book` from disk.
+///
+/// A book that doesn't exist is treated as empty.
+pub fn load(base_path: &Path, book: Book) -> io::Result<BTreeMap<String, String>> {
+    match fs::read_to_string(book.path(base_path)) {
+        Ok(contents) => Ok(parse(&contents)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(error) => Err(error),
+    }
+}
+
+/// Store `hosts` into `book`, replacing its previous contents.
+///
+/// The book is first written into a temporary file which is then renamed so a concurrent reader
+/// never observes a partially written book.
+pub fn store(base_path: &Path, book: Book, hosts: &BTreeMap<String, String>) -> io::Result<()> {
+    let path = book.path(base_path);
+    let tmp = path.with_extension("tmp");
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(&tmp, serialize(hosts))?;
+    fs::rename(tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn valid_hosts() {
+        assert!(is_valid_host("zzz.i2p"));
+        assert!(is_valid_host("forum.i2p-projekt.i2p"));
+        assert!(!is_valid_host("zzz.i2p."));
+        assert!(!is_valid_host("ZZZ.i2p"));
+        assert!(!is_valid_host(".zzz.i2p"));
+        assert!(!is_valid_host("zzz..i2p"));
+        assert!(!is_valid_host("zzz=.i2p"));
+        assert!(!is_valid_host(
+            "lhbd7ojcaiofbfku7ixh47qj537g572zmhdc4oilvugzxdpdghua.b32.i2p"
+        ));
+        assert!(!is_valid_host("example.com"));
+    }
+
+    #[test]
+    fn last_entry_wins() {
+        let hosts = parse("# comment\n\nzzz.i2p=first\nstats.i2p=second#!sig=abc\nzzz.i2p=third\n");
+
+        assert_eq!(hosts.len(), 2);
+        assert_eq!(hosts.get("zzz.i2p"), Some(&String::from("third")));
+        assert_eq!(hosts.get("stats.i2p"), Some(&String::from("second")));
+    }
+
+    #[test]
+    fn empty_destination_removes_host() {
+        let hosts = parse("zzz.i2p=first\nstats.i2p=second\nzzz.i2p=\n");
+
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(hosts.get("zzz.i2p"), None);
+    }
+
+    #[test]
+    fn store_and_load() {
+        let dir = tempdir().unwrap();
+
+        // book that doesn't exist is empty
+        assert!(load(dir.path(), Book::Private).unwrap().is_empty());
+
+        let hosts = BTreeMap::from([
+            (String::from("zzz.i2p"), String::from("first")),
+            (String::from("stats.i2p"), String::from("second")),
+        ]);
+        store(dir.path(), Book::Private, &hosts).unwrap();
+
+        assert_eq!(load(dir.path(), Book::Private).unwrap(), hosts);
+        assert!(load(dir.path(), Book::Local).unwrap().is_empty());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("addressbook/private")).unwrap(),
+            "stats.i2p=second\nzzz.i2p=first\n",
+        );
+    }
+}
@@ -0,0 +1,231 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! `hosts.txt` feed of the local address book.
+//!
+//! The feed is served over HTTP on a local port which is exposed to the network through a server
+//! tunnel, allowing other routers to subscribe to the hosts the user has added to the local address
+//! book. Hosts of the private and router address books are never published.
+
+use crate::address_book::book::{self, Book};
+
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpListener,
+};
+
+use std::{io, path::PathBuf, time::Duration};
+
+/// Logging target for the file
+const LOG_TARGET: &str = "emissary::address-book::feed";
+
+/// Maximum size of a request.
+const MAX_REQUEST_SIZE: usize = 8192usize;
+
+/// How long is the client given to send the request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `hosts.txt` feed of the local address book.
+pub struct AddressBookFeed {
+    /// Base path of the router.
+    base_path: PathBuf,
+
+    /// TCP listener.
+    listener: TcpListener,
+}
+
+impl AddressBookFeed {
+    /// Create new [`AddressBookFeed`] listening on `127.0.0.1:<port>`.
+    pub async fn new(base_path: PathBuf, port: u16) -> io::Result<Self> {
+        Ok(Self {
+            base_path,
+            listener: TcpListener::bind(("127.0.0.1", port)).await?,
+        })
+    }
+
+    /// Read request from `stream` and respond with the contents of the local address book.
+    async fn on_connection<S: AsyncRead + AsyncWrite + Unpin>(
+        mut stream: S,
+        base_path: PathBuf,
+    ) -> io::Result<()> {
+        let mut buffer = vec![0u8; MAX_REQUEST_SIZE];
+        let mut nread = 0usize;
+
+        let (method, path) = loop {
+            if nread == MAX_REQUEST_SIZE {
+                return Err(io::Error::other("request too large"));
+            }
+
+            match stream.read(&mut buffer[nread..]).await? {
+                0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+                read => nread += read,
+            }
+
+            let mut headers = [httparse::EMPTY_HEADER; 64];
+            let mut request = httparse::Request::new(&mut headers);
+
+            match request.parse(&buffer[..nread]) {
+                Ok(httparse::Status::Complete(_)) =>
+                    break (
+                        request.method.unwrap_or_default().to_string(),
+                        request.path.unwrap_or_default().to_string(),
+                    ),
+                Ok(httparse::Status::Partial) => {}
+                Err(error) => return Err(io::Error::other(error)),
+            }
+        };
+        let path = path.split_once('?').map_or(path.as_str(), |(path, _)| path);
+
+        let (status, body) = match (method.as_str(), path) {
+            ("GET" | "HEAD", "/" | "/hosts.txt") => match book::load(&base_path, Book::Local) {
+                Ok(hosts) => ("200 OK", book::serialize(&hosts)),
+                Err(error) => {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        ?error,
+                        "failed to load local address book",
+                    );
+                    ("500 Internal Server Error", String::new())
+                }
+            },
+            ("GET" | "HEAD", _) => ("404 Not Found", String::new()),
+            _ => ("405 Method Not Allowed", String::new()),
+        };
+
+        let mut response = format!(
+            "HTTP/1.1 {status}\r\nConnection: close\r\nContent-Type: text/plain; charset=UTF-8\r\n\
+            Content-Length: {}\r\n\r\n",
+            body.len(),
+        );
+
+        if method != "HEAD" {
+            response.push_str(&body);
+        }
+
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+
+    /// Run the event loop of [`AddressBookFeed`].
+    pub async fn run(self) {
+        tracing::info!(
+            target: LOG_TARGET,
+            address = ?self.listener.local_addr().ok(),
+            "serving local address book",
+        );
+
+        loop {
+            let (stream, _) = match self.listener.accept().await {
+                Ok(connection) => connection,
+                Err(error) => {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        ?error,
+                        "failed to accept connection",
+                    );
+                    continue;
+                }
+            };
+            let base_path = self.base_path.clone();
+
+            tokio::spawn(async move {
+                match tokio::time::timeout(REQUEST_TIMEOUT, Self::on_connection(stream, base_path))
+                    .await
+                {
+                    Ok(Ok(())) => {}
+                    Ok(Err(error)) => tracing::debug!(
+                        target: LOG_TARGET,
+                        ?error,
+                        "failed to serve address book",
+                    ),
+                    Err(_) => tracing::debug!(
+                        target: LOG_TARGET,
+                        "address book request timed out",
+                    ),
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use tempfile::tempdir;
+
+    async fn request(base_path: PathBuf, request: &str) -> String {
+        let (mut client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(AddressBookFeed::on_connection(server, base_path));
+
+        client.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        handle.await.unwrap().unwrap();
+
+        response
+    }
+
+    #[tokio::test]
+    async fn only_local_book_served() {
+        let dir = tempdir().unwrap();
+
+        book::store(
+            dir.path(),
+            Book::Local,
+            &BTreeMap::from([(String::from("zzz.i2p"), String::from("local"))]),
+        )
+        .unwrap();
+        book::store(
+            dir.path(),
+            Book::Private,
+            &BTreeMap::from([(String::from("secret.i2p"), String::from("private"))]),
+        )
+        .unwrap();
+
+        let response = request(
+            dir.path().to_path_buf(),
+            "GET /hosts.txt HTTP/1.1\r\nHost: feed.i2p\r\n\r\n",
+        )
+        .await;
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\nzzz.i2p=local\n"));
+        assert!(!response.contains("secret.i2p"));
+    }
+
+    #[tokio::test]
+    async fn unknown_path() {
+        let dir = tempdir().unwrap();
+
+        let response = request(
+            dir.path().to_path_buf(),
+            "GET /private HTTP/1.1\r\nHost: feed.i2p\r\n\r\n",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        let response = request(
+            dir.path().to_path_buf(),
+            "POST /hosts.txt HTTP/1.1\r\nHost: feed.i2p\r\nContent-Length: 0\r\n\r\n",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+}
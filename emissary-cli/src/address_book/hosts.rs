@@ -0,0 +1,567 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! `hosts.txt` parser.
+//!
+//! Supports the extended format where an entry may be followed by `#!` and `#`-separated
+//! key-value pairs. Entries with key-value pairs must be signed by the destination of the entry
+//! and entries which replace an existing destination must also be signed by the old destination.
+//!
+//! Lines starting with `#!` are commands which don't add a new entry, such as `action=remove`.
+//!
+//! See https://geti2p.net/spec/subscription for more details.
+
+use emissary_core::{crypto::base64_decode, primitives::Destination};
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::address-book::hosts";
+
+/// Command parsed from a `hosts.txt` line.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Add new host.
+    Add {
+        /// Host name.
+        name: String,
+
+        /// Base64-encoded destination.
+        destination: String,
+    },
+
+    /// Add `name` as an alias for `destination` which is already known as `old_name`.
+    AddName {
+        /// New host name.
+        name: String,
+
+        /// Existing host name.
+        old_name: String,
+
+        /// Base64-encoded destination.
+        destination: String,
+    },
+
+    /// Add an additional destination for `name`, which was previously `old_destination`.
+    AddDest {
+        /// Host name.
+        name: String,
+
+        /// New base64-encoded destination.
+        destination: String,
+
+        /// Old base64-encoded destination.
+        old_destination: String,
+    },
+
+    /// Change the destination of `name` from `old_destination` to `destination`.
+    ChangeDest {
+        /// Host name.
+        name: String,
+
+        /// New base64-encoded destination.
+        destination: String,
+
+        /// Old base64-encoded destination.
+        old_destination: String,
+    },
+
+    /// Remove `name` if it points to `destination`.
+    Remove {
+        /// Host name.
+        name: String,
+
+        /// Base64-encoded destination.
+        destination: String,
+    },
+}
+
+impl Command {
+    /// Get the host name the command modifies.
+    pub fn host(&self) -> &str {
+        match self {
+            Self::Add { name, .. }
+            | Self::AddName { name, .. }
+            | Self::AddDest { name, .. }
+            | Self::ChangeDest { name, .. }
+            | Self::Remove { name, .. } => name,
+        }
+    }
+
+    /// Get the existing host name an alias is added for, if any.
+    pub fn old_name(&self) -> Option<&str> {
+        match self {
+            Self::AddName { old_name, .. } => Some(old_name),
+            _ => None,
+        }
+    }
+
+    /// Apply the command to `addresses`.
+    ///
+    /// Returns `true` if `addresses` was modified.
+    pub fn apply(self, addresses: &mut HashMap<String, String>) -> bool {
+        match self {
+            Command::Add { name, destination } => match addresses.contains_key(&name) {
+                true => {
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        %name,
+                        "skipping already-existing address",
+                    );
+                    false
+                }
+                false => {
+                    addresses.insert(name, destination);
+                    true
+                }
+            },
+            Command::AddName {
+                name,
+                old_name,
+                destination,
+            } => {
+                // alias must be signed by the destination of the existing host
+                if addresses.get(&old_name).is_some_and(|existing| existing != &destination) {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        %name,
+                        %old_name,
+                        "rejecting forged alias",
+                    );
+                    return false;
+                }
+
+                Command::Add { name, destination }.apply(addresses)
+            }
+            Command::AddDest {
+                name,
+                destination,
+                old_destination,
+            } => match addresses.get(&name) {
+                None => {
+                    addresses.insert(name, destination);
+                    true
+                }
+                // only one destination is stored per host so the existing destination is kept
+                Some(existing) if existing == &old_destination || existing == &destination => false,
+                Some(_) => {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        %name,
+                        "rejecting destination for host with an unknown old destination",
+                    );
+                    false
+                }
+            },
+            Command::ChangeDest {
+                name,
+                destination,
+                old_destination,
+            } => match addresses.get(&name) {
+                None => {
+                    addresses.insert(name, destination);
+                    true
+                }
+                Some(existing) if existing == &destination => false,
+                Some(existing) if existing == &old_destination => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        %name,
+                        "destination changed",
+                    );
+
+                    addresses.insert(name, destination);
+                    true
+                }
+                Some(_) => {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        %name,
+                        "rejecting destination change for host with an unknown old destination",
+                    );
+                    false
+                }
+            },
+            Command::Remove { name, destination } => match addresses.get(&name) {
+                Some(existing) if existing == &destination => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        %name,
+                        "host removed",
+                    );
+
+                    addresses.remove(&name);
+                    true
+                }
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Serialize `properties` for signing, skipping the signatures in `skip`.
+fn serialize_properties(properties: &BTreeMap<&str, &str>, skip: &[&str]) -> String {
+    let properties = properties
+        .iter()
+        .filter(|(key, _)| !skip.contains(key))
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>();
+
+    match properties.is_empty() {
+        true => String::new(),
+        false => format!("#!{}", properties.join("#")),
+    }
+}
+
+/// Verify that `signature` over `message` was created by `destination`.
+fn verify(message: &str, signature: Option<&&str>, destination: &str) -> bool {
+    let Some(signature) = signature.and_then(base64_decode) else {
+        return false;
+    };
+
+    base64_decode(destination)
+        .and_then(Destination::parse)
+        .is_some_and(|destination| {
+            destination.verifying_key().verify(message.as_bytes(), &signature).is_ok()
+        })
+}
+
+/// Parse `line` of a `hosts.txt` into a [`Command`].
+///
+/// Returns `None` if the line is empty, a comment, malformed, has an invalid signature or
+/// specifies an unsupported action.
+pub fn parse_line(line: &str) -> Option<Command> {
+    let line = line.trim();
+
+    if line.is_empty() || (line.starts_with('#') && !line.starts_with("#!")) {
+        return None;
+    }
+
+    let (entry, properties) = match line.split_once("#!") {
+        Some((entry, properties)) => (entry.trim(), Some(properties)),
+        None => (line, None),
+    };
+    let properties = properties
+        .map(|properties| {
+            properties
+                .split('#')
+                .filter_map(|property| property.split_once('='))
+                .collect::<BTreeMap<_, _>>()
+        })
+        .unwrap_or_default();
+
+    // commands which don't add a new entry are signed by the destination they target
+    if entry.is_empty() {
+        let destination = properties.get("dest")?;
+
+        if !verify(
+            &serialize_properties(&properties, &["sig"]),
+            properties.get("sig"),
+            destination,
+        ) {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?line,
+                "invalid signature for command",
+            );
+            return None;
+        }
+
+        return match properties.get("action") {
+            Some(&"remove") => Some(Command::Remove {
+                name: properties.get("name")?.to_string(),
+                destination: destination.to_string(),
+            }),
+            action => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?action,
+                    "unsupported command",
+                );
+                None
+            }
+        };
+    }
+
+    let (name, destination) = entry.split_once('=')?;
+    let (name, destination) = (name.trim().to_string(), destination.trim().to_string());
+
+    if name.is_empty() || destination.is_empty() {
+        return None;
+    }
+
+    if properties.is_empty() {
+        return Some(Command::Add { name, destination });
+    }
+
+    if !verify(
+        &format!(
+            "{name}={destination}{}",
+            serialize_properties(&properties, &["sig"])
+        ),
+        properties.get("sig"),
+        &destination,
+    ) {
+        tracing::warn!(
+            target: LOG_TARGET,
+            %name,
+            "invalid signature for host",
+        );
+        return None;
+    }
+
+    match properties.get("action") {
+        None => Some(Command::Add { name, destination }),
+        Some(&"addname") => Some(Command::AddName {
+            name,
+            old_name: properties.get("oldname")?.to_string(),
+            destination,
+        }),
+        Some(action @ (&"adddest" | &"changedest")) => {
+            let old_destination = properties.get("olddest")?.to_string();
+
+            // the old destination must also have signed the entry
+            if !verify(
+                &format!(
+                    "{name}={destination}{}",
+                    serialize_properties(&properties, &["sig", "oldsig"])
+                ),
+                properties.get("oldsig"),
+                &old_destination,
+            ) {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    %name,
+                    "invalid signature from old destination",
+                );
+                return None;
+            }
+
+            match *action {
+                "adddest" => Some(Command::AddDest {
+                    name,
+                    destination,
+                    old_destination,
+                }),
+                _ => Some(Command::ChangeDest {
+                    name,
+                    destination,
+                    old_destination,
+                }),
+            }
+        }
+        Some(action) => {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %name,
+                %action,
+                "unsupported action",
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use emissary_core::crypto::{base64_encode, SigningPrivateKey};
+    use emissary_util::runtime::tokio::Runtime;
+    use rand::rngs::OsRng;
+
+    /// Create new destination and return its signing key and base64-encoded destination.
+    fn destination() -> (SigningPrivateKey, String) {
+        let signing_key = SigningPrivateKey::random(OsRng);
+        let destination = Destination::new::<Runtime>(signing_key.public());
+
+        (signing_key, base64_encode(destination.serialize()))
+    }
+
+    /// Sign `entry` with `signing_key` and append the signature as `key`.
+    fn sign(entry: &str, signing_key: &SigningPrivateKey, key: &str) -> String {
+        format!(
+            "{entry}#{key}={}",
+            base64_encode(signing_key.sign(entry.as_bytes()))
+        )
+    }
+
+    #[test]
+    fn plain_entry_and_comments() {
+        assert_eq!(
+            parse_line(" host.i2p=destination "),
+            Some(Command::Add {
+                name: "host.i2p".to_string(),
+                destination: "destination".to_string(),
+            })
+        );
+        assert!(parse_line("# comment").is_none());
+        assert!(parse_line("").is_none());
+        assert!(parse_line("host.i2p").is_none());
+    }
+
+    #[test]
+    fn published_adddest_entries_verified() {
+        let Some(Command::AddDest { name, .. }) = parse_line("tracker2.postman.i2p=lnQ6yoBTxQuQU8EQ1FlF395ITIQF-HGJxUeFvzETLFnoczNjQvKDbtSB7aHhn853zjVXrJBgwlB9sO57KakBDaJ50lUZgVPhjlI19TgJ-CxyHhHSCeKx5JzURdEW-ucdONMynr-b2zwhsx8VQCJwCEkARvt21YkOyQDaB9IdV8aTAmP~PUJQxRwceaTMn96FcVenwdXqleE16fI8CVFOV18jbJKrhTOYpTtcZKV4l1wNYBDwKgwPx5c0kcrRzFyw5~bjuAKO~GJ5dR7BQsL7AwBoQUS4k1lwoYrG1kOIBeDD3XF8BWb6K3GOOoyjc1umYKpur3G~FxBuqtHAsDRICrsRuil8qK~whOvj8uNTv~ohZnTZHxTLgi~sDyo98BwJ-4Y4NMSuF4GLzcgLypcR1D1WY2tDqMKRYFVyLE~MTPVjRRgXfcKolykQ666~Go~A~~CNV4qc~zlO6F4bsUhVZDU7WJ7mxCAwqaMiJsL-NgIkb~SMHNxIzaE~oy0agHJMBQAEAAcAAA==#!oldsig=i02RMv3Hy86NGhVo2O3byIf6xXqWrzrRibSabe5dmNfRRQPZO9L25A==#date=1598641102#action=adddest#sig=cB-mY~sp1uuEmcQJqremV1D6EDWCe3IwPv4lBiGAXgKRYc5MLBBzYvJXtXmOawpfLKeNM~v5fWlXYsDfKf5nDA==#olddest=lnQ6yoBTxQuQU8EQ1FlF395ITIQF-HGJxUeFvzETLFnoczNjQvKDbtSB7aHhn853zjVXrJBgwlB9sO57KakBDaJ50lUZgVPhjlI19TgJ-CxyHhHSCeKx5JzURdEW-ucdONMynr-b2zwhsx8VQCJwCEkARvt21YkOyQDaB9IdV8aTAmP~PUJQxRwceaTMn96FcVenwdXqleE16fI8CVFOV18jbJKrhTOYpTtcZKV4l1wNYBDwKgwPx5c0kcrRzFyw5~bjuAKO~GJ5dR7BQsL7AwBoQUS4k1lwoYrG1kOIBeDD3XF8BWb6K3GOOoyjc1umYKpur3G~FxBuqtHAsDRICkEbKUqJ9mPYQlTSujhNxiRIW-oLwMtvayCFci99oX8MvazPS7~97x0Gsm-onEK1Td9nBdmq30OqDxpRtXBimbzkLbR1IKObbg9HvrKs3L-kSyGwTUmHG9rSQSoZEvFMA-S0EXO~o4g21q1oikmxPMhkeVwQ22VHB0-LZJfmLr4SAAAA") else {
+            panic!("invalid entry");
+        };
+        assert_eq!(name, "tracker2.postman.i2p");
+
+        let Some(Command::AddDest { name, .. }) = parse_line("zzz.i2p=GKapJ8koUcBj~jmQzHsTYxDg2tpfWj0xjQTzd8BhfC9c3OS5fwPBNajgF-eOD6eCjFTqTlorlh7Hnd8kXj1qblUGXT-tDoR9~YV8dmXl51cJn9MVTRrEqRWSJVXbUUz9t5Po6Xa247Vr0sJn27R4KoKP8QVj1GuH6dB3b6wTPbOamC3dkO18vkQkfZWUdRMDXk0d8AdjB0E0864nOT~J9Fpnd2pQE5uoFT6P0DqtQR2jsFvf9ME61aqLvKPPWpkgdn4z6Zkm-NJOcDz2Nv8Si7hli94E9SghMYRsdjU-knObKvxiagn84FIwcOpepxuG~kFXdD5NfsH0v6Uri3usE3XWD7Pw6P8qVYF39jUIq4OiNMwPnNYzy2N4mDMQdsdHO3LUVh~DEppOy9AAmEoHDjjJxt2BFBbGxfdpZCpENkwvmZeYUyNCCzASqTOOlNzdpne8cuesn3NDXIpNnqEE6Oe5Qm5YOJykrX~Vx~cFFT3QzDGkIjjxlFBsjUJyYkFjBQAEAAcAAA==#!action=adddest#date=1490103520#olddest=GKapJ8koUcBj~jmQzHsTYxDg2tpfWj0xjQTzd8BhfC9c3OS5fwPBNajgF-eOD6eCjFTqTlorlh7Hnd8kXj1qblUGXT-tDoR9~YV8dmXl51cJn9MVTRrEqRWSJVXbUUz9t5Po6Xa247Vr0sJn27R4KoKP8QVj1GuH6dB3b6wTPbOamC3dkO18vkQkfZWUdRMDXk0d8AdjB0E0864nOT~J9Fpnd2pQE5uoFT6P0DqtQR2jsFvf9ME61aqLvKPPWpkgdn4z6Zkm-NJOcDz2Nv8Si7hli94E9SghMYRsdjU-knObKvxiagn84FIwcOpepxuG~kFXdD5NfsH0v6Uri3usE3uSzpWS0EHmrlfoLr5uGGd9ZHwwCIcgfOATaPRMUEQxiK9q48PS0V3EXXO4-YLT0vIfk4xO~XqZpn8~PW1kFe2mQMHd7oO89yCk-3yizRG3UyFtI7-mO~eCI6-m1spYoigStgoupnC3G85gJkqEjMm49gUjbhfWKWI-6NwTj0ZnAAAA#oldsig=MbSvc9wsxSm37B65rUC~BCZzFsIJe0-CXCH8n97ZaMMizNUjeytgBQ==#sig=R2wREo~02liJmU4UGfVZr88XFMiHdYDXVfS~HtyxFxwYG~2o1guP~RocqmHBCE6yPg1Cm8m336d~jqijAVJzBA==") else {
+            panic!("invalid entry");
+        };
+        assert_eq!(name, "zzz.i2p");
+    }
+
+    #[test]
+    fn signed_entry() {
+        let (signing_key, dest) = destination();
+        let line = sign(
+            &format!("host.i2p={dest}#!date=1700000000"),
+            &signing_key,
+            "sig",
+        );
+
+        assert_eq!(
+            parse_line(&line),
+            Some(Command::Add {
+                name: "host.i2p".to_string(),
+                destination: dest.clone(),
+            })
+        );
+
+        // modified entry
+        assert!(parse_line(&line.replace("1700000000", "1700000001")).is_none());
+
+        // properties must be signed
+        assert!(parse_line(&format!("host.i2p={dest}#!date=1700000000")).is_none());
+
+        // entry signed by another destination
+        let (other_key, _) = destination();
+        let line = sign(
+            &format!("host.i2p={dest}#!date=1700000000"),
+            &other_key,
+            "sig",
+        );
+        assert!(parse_line(&line).is_none());
+    }
+
+    #[test]
+    fn change_destination() {
+        let (old_key, old_dest) = destination();
+        let (new_key, new_dest) = destination();
+        let mut addresses = HashMap::from_iter([("host.i2p".to_string(), old_dest.clone())]);
+
+        let entry =
+            format!("host.i2p={new_dest}#!action=changedest#date=1700000000#olddest={old_dest}");
+        let line = sign(&sign(&entry, &old_key, "oldsig"), &new_key, "sig");
+
+        // inner signature is created over the entry without the outer signature
+        let command = parse_line(&line).unwrap();
+        assert_eq!(
+            command,
+            Command::ChangeDest {
+                name: "host.i2p".to_string(),
+                destination: new_dest.clone(),
+                old_destination: old_dest.clone(),
+            }
+        );
+        assert!(command.apply(&mut addresses));
+        assert_eq!(addresses.get("host.i2p"), Some(&new_dest));
+
+        // entry not signed by the old destination
+        let (other_key, _) = destination();
+        let line = sign(&sign(&entry, &other_key, "oldsig"), &new_key, "sig");
+        assert!(parse_line(&line).is_none());
+    }
+
+    #[test]
+    fn change_destination_with_unknown_old_destination() {
+        let (_, existing) = destination();
+        let (old_key, old_dest) = destination();
+        let (new_key, new_dest) = destination();
+        let mut addresses = HashMap::from_iter([("host.i2p".to_string(), existing.clone())]);
+
+        let entry = format!("host.i2p={new_dest}#!action=changedest#olddest={old_dest}");
+        let line = sign(&sign(&entry, &old_key, "oldsig"), &new_key, "sig");
+
+        assert!(!parse_line(&line).unwrap().apply(&mut addresses));
+        assert_eq!(addresses.get("host.i2p"), Some(&existing));
+    }
+
+    #[test]
+    fn forged_alias_rejected() {
+        let (_, dest) = destination();
+        let (forged_key, forged_dest) = destination();
+        let mut addresses = HashMap::from_iter([("host.i2p".to_string(), dest.clone())]);
+
+        // alias signed by a destination other than the destination of the old name
+        let line = sign(
+            &format!("alias.i2p={forged_dest}#!action=addname#oldname=host.i2p"),
+            &forged_key,
+            "sig",
+        );
+        assert!(!parse_line(&line).unwrap().apply(&mut addresses));
+        assert!(!addresses.contains_key("alias.i2p"));
+    }
+
+    #[test]
+    fn alias_added() {
+        let (signing_key, dest) = destination();
+        let mut addresses = HashMap::from_iter([("host.i2p".to_string(), dest.clone())]);
+
+        let line = sign(
+            &format!("alias.i2p={dest}#!action=addname#oldname=host.i2p"),
+            &signing_key,
+            "sig",
+        );
+        assert!(parse_line(&line).unwrap().apply(&mut addresses));
+        assert_eq!(addresses.get("alias.i2p"), Some(&dest));
+    }
+
+    #[test]
+    fn remove_host() {
+        let (signing_key, dest) = destination();
+        let (other_key, other_dest) = destination();
+        let mut addresses = HashMap::from_iter([("host.i2p".to_string(), dest.clone())]);
+
+        // remove signed by another destination
+        let line = sign(
+            &format!("#!action=remove#dest={other_dest}#name=host.i2p"),
+            &other_key,
+            "sig",
+        );
+        assert!(!parse_line(&line).unwrap().apply(&mut addresses));
+        assert!(addresses.contains_key("host.i2p"));
+
+        // forged signature
+        let line = sign(
+            &format!("#!action=remove#dest={dest}#name=host.i2p"),
+            &other_key,
+            "sig",
+        );
+        assert!(parse_line(&line).is_none());
+
+        let line = sign(
+            &format!("#!action=remove#dest={dest}#name=host.i2p"),
+            &signing_key,
+            "sig",
+        );
+        assert!(parse_line(&line).unwrap().apply(&mut addresses));
+        assert!(addresses.is_empty());
+    }
+}
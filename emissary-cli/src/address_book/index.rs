@@ -0,0 +1,842 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! On-disk index of the router address book.
+//!
+//! The router address book is an append-only log of `host=destination` lines where a line
+//! without a destination removes the host. The index is stored next to the log and consists of
+//! two open-addressing hash tables, one keyed by host name and one by `.b32.i2p` address, which
+//! map to offsets of lines in the log. A lookup reads a few slots of a table and the line they
+//! point to, so neither the startup time nor the memory usage of the router grows with the size
+//! of the book.
+//!
+//! The index header records the length and modification time of the log. If they don't match
+//! the log when the index is opened, for example because the router was stopped in the middle of
+//! an update or the book was edited while the router wasn't running, the index is rebuilt from
+//! the log. The log should be compacted and the index rebuilt once the log contains more stale
+//! lines than live ones or the tables become too full. The compacted log and its index are first
+//! written into temporary files, which allows lookups to continue from the old log while the new
+//! one is being written, and then moved in place of the old log and index.
+
+use crate::address_book::book;
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+/// Logging target for the file
+const LOG_TARGET: &str = "emissary::address-book::index";
+
+/// Magic at the start of the index file, including the version of the format.
+const MAGIC: &[u8; 8] = b"emsidx01";
+
+/// Length of the index header.
+const HEADER_LEN: u64 = 48u64;
+
+/// Length of a table slot.
+const SLOT_LEN: u64 = 16u64;
+
+/// Minimum number of slots in a table.
+const MIN_SLOTS: u64 = 1024u64;
+
+/// Maximum length of a line in the log.
+const MAX_LINE_LEN: usize = 8192usize;
+
+/// Offset stored in a slot that has never been used.
+const EMPTY: u64 = 0u64;
+
+/// Offset stored in a slot whose entry has been removed.
+const REMOVED: u64 = u64::MAX;
+
+/// Index table.
+#[derive(Debug, Clone, Copy)]
+enum Table {
+    /// Host name -> line.
+    Host = 0,
+
+    /// `.b32.i2p` address -> line.
+    Address = 1,
+}
+
+/// Table slot.
+///
+/// `offset` is the offset of the line in the log plus one so that zero marks an empty slot.
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    /// Hash of the key.
+    hash: u64,
+
+    /// Offset of the line in the log, [`EMPTY`] or [`REMOVED`].
+    offset: u64,
+}
+
+/// Hash `key` with 64-bit FNV-1a.
+///
+/// The hash is stored on disk so it must not change between versions.
+fn hash(key: &str) -> u64 {
+    key.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3u64)
+    })
+}
+
+/// Get the number of slots for a table which must hold `num_lines` lines.
+fn num_slots(num_lines: u64) -> u64 {
+    num_lines.saturating_mul(4).max(MIN_SLOTS).next_power_of_two()
+}
+
+/// Read bytes from `file` at `offset` into `buf`, returning the number of bytes read.
+#[cfg(unix)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buf, offset)
+}
+
+/// Read bytes from `file` at `offset` into `buf`, returning the number of bytes read.
+#[cfg(windows)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_read(file, buf, offset)
+}
+
+/// Write bytes of `buf` into `file` at `offset`, returning the number of bytes written.
+#[cfg(unix)]
+fn write_at(file: &File, buf: &[u8], offset: u64) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::write_at(file, buf, offset)
+}
+
+/// Write bytes of `buf` into `file` at `offset`, returning the number of bytes written.
+#[cfg(windows)]
+fn write_at(file: &File, buf: &[u8], offset: u64) -> io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_write(file, buf, offset)
+}
+
+/// Write all of `buf` into `file` at `offset`.
+fn write_all_at(file: &File, mut buf: &[u8], mut offset: u64) -> io::Result<()> {
+    while !buf.is_empty() {
+        let written = write_at(file, buf, offset)?;
+
+        if written == 0 {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+
+        buf = &buf[written..];
+        offset += written as u64;
+    }
+
+    Ok(())
+}
+
+/// Get the modification time of `file` in nanoseconds since the Unix epoch.
+fn modified(file: &File) -> io::Result<u64> {
+    Ok(file
+        .metadata()?
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |modified| modified.as_nanos() as u64))
+}
+
+/// Call `f` for each entry of the log at `log_path`, with the offset of the entry.
+fn for_each_entry(
+    log_path: &Path,
+    mut f: impl FnMut(u64, String, String) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(File::open(log_path)?);
+    let mut line = Vec::new();
+    let mut offset = 0u64;
+
+    loop {
+        line.clear();
+
+        let nread = reader.read_until(b'\n', &mut line)?;
+        if nread == 0 {
+            return Ok(());
+        }
+
+        if let Some((host, destination)) =
+            std::str::from_utf8(&line).ok().and_then(book::parse_entry)
+        {
+            f(offset, host, destination)?;
+        }
+
+        offset += nread as u64;
+    }
+}
+
+/// On-disk index of the router address book.
+///
+/// The log and the index are only accessed at explicit offsets, except when the log is read
+/// sequentially using a handle of its own.
+pub struct RouterIndex {
+    /// Path of the log.
+    log_path: PathBuf,
+
+    /// Log.
+    log: File,
+
+    /// Index.
+    index: File,
+
+    /// Number of slots in each table.
+    num_slots: u64,
+
+    /// Number of live hosts.
+    num_hosts: u64,
+
+    /// Number of lines in the log.
+    num_lines: u64,
+
+    /// Length of the log.
+    log_len: u64,
+}
+
+impl RouterIndex {
+    /// Open the index of the log at `log_path`, building the index if needed.
+    pub fn open(log_path: PathBuf) -> io::Result<Self> {
+        if let Some(parent) = log_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        match Self::load(&log_path)? {
+            Some(index) => Ok(index),
+            None => {
+                tracing::info!(
+                    target: LOG_TARGET,
+                    path = ?log_path,
+                    "router address book index missing or outdated, rebuilding",
+                );
+
+                Self::rebuild(log_path)
+            }
+        }
+    }
+
+    /// Get path of the index of the log at `log_path`.
+    fn index_path(log_path: &Path) -> PathBuf {
+        log_path.with_extension("idx")
+    }
+
+    /// Open the log at `log_path`, creating it if it doesn't exist.
+    fn open_log(log_path: &Path) -> io::Result<File> {
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(log_path)
+    }
+
+    /// Load an existing index of the log at `log_path`.
+    ///
+    /// Returns `None` if the index doesn't exist or doesn't match the log.
+    fn load(log_path: &Path) -> io::Result<Option<Self>> {
+        let log = Self::open_log(log_path)?;
+        let index = match OpenOptions::new().read(true).write(true).open(Self::index_path(log_path))
+        {
+            Ok(index) => index,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        };
+
+        let mut header = [0u8; HEADER_LEN as usize];
+        if read_at(&index, &mut header, 0)? != header.len() || &header[..8] != MAGIC {
+            return Ok(None);
+        }
+
+        let field = |i: usize| u64::from_le_bytes(header[8 * i..8 * (i + 1)].try_into().unwrap());
+        let (num_slots, num_hosts, num_lines, log_len, log_modified) =
+            (field(1), field(2), field(3), field(4), field(5));
+
+        if !num_slots.is_power_of_two()
+            || num_slots < MIN_SLOTS
+            || index.metadata()?.len() != HEADER_LEN + 2 * num_slots * SLOT_LEN
+            || log.metadata()?.len() != log_len
+            || modified(&log)? != log_modified
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            log_path: log_path.to_path_buf(),
+            log,
+            index,
+            num_slots,
+            num_hosts,
+            num_lines,
+            log_len,
+        }))
+    }
+
+    /// Create an index at `index_path` from the entries of the log at `log_path`.
+    ///
+    /// `num_lines` is the number of lines in the log, used to size the tables.
+    fn replay(log_path: &Path, index_path: &Path, num_lines: u64) -> io::Result<Self> {
+        let log = Self::open_log(log_path)?;
+        let num_slots = num_slots(num_lines);
+        let index = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(index_path)?;
+        index.set_len(HEADER_LEN + 2 * num_slots * SLOT_LEN)?;
+
+        let mut this = Self {
+            log_path: log_path.to_path_buf(),
+            log_len: log.metadata()?.len(),
+            log,
+            index,
+            num_slots,
+            num_hosts: 0u64,
+            num_lines: 0u64,
+        };
+
+        for_each_entry(log_path, |offset, host, destination| {
+            this.apply(
+                &host,
+                Some(destination.as_str()).filter(|d| !d.is_empty()),
+                offset,
+            )
+        })?;
+
+        Ok(this)
+    }
+
+    /// Get path of the compacted copy of the log at `log_path`.
+    fn compacted_log_path(log_path: &Path) -> PathBuf {
+        log_path.with_extension("tmp")
+    }
+
+    /// Get path of the index of the compacted copy of the log at `log_path`.
+    fn compacted_index_path(log_path: &Path) -> PathBuf {
+        log_path.with_extension("idx.tmp")
+    }
+
+    /// Write a compacted copy of the log at `log_path` and the index of the copy.
+    ///
+    /// The log is first replayed into a temporary index which is used to find the live lines.
+    /// The live lines are then written into a new log and the index is built from the new log.
+    /// The log at `log_path` is only read, so it must not be modified until the copy has been
+    /// installed with [`RouterIndex::install_compacted()`].
+    pub fn write_compacted(log_path: &Path) -> io::Result<()> {
+        let stale_index_path = log_path.with_extension("idx.stale");
+        let compacted_log_path = Self::compacted_log_path(log_path);
+
+        let num_hosts = {
+            // create the log if it doesn't exist
+            Self::open_log(log_path)?;
+
+            let mut num_lines = 0u64;
+            for_each_entry(log_path, |_, _, _| {
+                num_lines += 1;
+                Ok(())
+            })?;
+
+            let stale = Self::replay(log_path, &stale_index_path, num_lines)?;
+            let mut out = BufWriter::new(File::create(&compacted_log_path)?);
+
+            for_each_entry(log_path, |offset, host, destination| {
+                if stale.find_host(&host)?.is_some_and(|(_, live, _)| live == offset) {
+                    out.write_all(format!("{host}={destination}\n").as_bytes())?;
+                }
+
+                Ok(())
+            })?;
+            out.flush()?;
+
+            stale.num_hosts
+        };
+        fs::remove_file(&stale_index_path)?;
+
+        let index = Self::replay(
+            &compacted_log_path,
+            &Self::compacted_index_path(log_path),
+            num_hosts,
+        )?;
+        index.sync()
+    }
+
+    /// Replace the log at `log_path` and its index with the copies written by
+    /// [`RouterIndex::write_compacted()`].
+    ///
+    /// Any open [`RouterIndex`] of the log should be dropped before calling this function.
+    pub fn install_compacted(log_path: PathBuf) -> io::Result<Self> {
+        fs::rename(Self::compacted_log_path(&log_path), &log_path)?;
+        fs::rename(
+            Self::compacted_index_path(&log_path),
+            Self::index_path(&log_path),
+        )?;
+
+        Self::load(&log_path)?.ok_or_else(|| io::Error::other("rebuilt index doesn't match log"))
+    }
+
+    /// Compact the log at `log_path` and rebuild its index.
+    fn rebuild(log_path: PathBuf) -> io::Result<Self> {
+        Self::write_compacted(&log_path)?;
+        Self::install_compacted(log_path)
+    }
+
+    /// Get position of slot `slot` of `table` in the index.
+    fn slot_position(&self, table: Table, slot: u64) -> u64 {
+        HEADER_LEN + (table as u64 * self.num_slots + slot) * SLOT_LEN
+    }
+
+    /// Read slot `slot` of `table`.
+    fn read_slot(&self, table: Table, slot: u64) -> io::Result<Slot> {
+        let mut buf = [0u8; SLOT_LEN as usize];
+
+        if read_at(&self.index, &mut buf, self.slot_position(table, slot))? != buf.len() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(Slot {
+            hash: u64::from_le_bytes(buf[..8].try_into().expect("to succeed")),
+            offset: u64::from_le_bytes(buf[8..].try_into().expect("to succeed")),
+        })
+    }
+
+    /// Write `value` into slot `slot` of `table`.
+    fn write_slot(&self, table: Table, slot: u64, value: Slot) -> io::Result<()> {
+        let mut buf = [0u8; SLOT_LEN as usize];
+        buf[..8].copy_from_slice(&value.hash.to_le_bytes());
+        buf[8..].copy_from_slice(&value.offset.to_le_bytes());
+
+        write_all_at(&self.index, &buf, self.slot_position(table, slot))
+    }
+
+    /// Iterate over slots of `table` in the probing order of `hash`, until an empty slot is found.
+    ///
+    /// `f` returns `Some` to stop the iteration.
+    fn probe<T>(
+        &self,
+        table: Table,
+        hash: u64,
+        mut f: impl FnMut(u64, Slot) -> io::Result<Option<T>>,
+    ) -> io::Result<Option<T>> {
+        let mask = self.num_slots - 1;
+
+        for i in 0..self.num_slots {
+            let index = hash.wrapping_add(i) & mask;
+            let slot = self.read_slot(table, index)?;
+
+            if slot.offset == EMPTY {
+                return Ok(None);
+            }
+
+            if slot.offset != REMOVED && slot.hash == hash {
+                if let Some(value) = f(index, slot)? {
+                    return Ok(Some(value));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Insert line at `offset` into `table` under `hash`.
+    fn insert_slot(&self, table: Table, hash: u64, offset: u64) -> io::Result<()> {
+        let mask = self.num_slots - 1;
+
+        for i in 0..self.num_slots {
+            let index = hash.wrapping_add(i) & mask;
+
+            if self.read_slot(table, index)?.offset == EMPTY {
+                return self.write_slot(
+                    table,
+                    index,
+                    Slot {
+                        hash,
+                        offset: offset + 1,
+                    },
+                );
+            }
+        }
+
+        Err(io::Error::other("index table is full"))
+    }
+
+    /// Read the entry of the log at `offset`.
+    fn read_entry(&self, offset: u64) -> io::Result<Option<(String, String)>> {
+        let mut line = Vec::new();
+        let mut buf = [0u8; 1024];
+
+        while line.len() < MAX_LINE_LEN {
+            let nread = read_at(&self.log, &mut buf, offset + line.len() as u64)?;
+
+            if nread == 0 {
+                break;
+            }
+
+            match buf[..nread].iter().position(|byte| byte == &b'\n') {
+                Some(end) => {
+                    line.extend_from_slice(&buf[..end]);
+                    break;
+                }
+                None => line.extend_from_slice(&buf[..nread]),
+            }
+        }
+
+        Ok(std::str::from_utf8(&line).ok().and_then(book::parse_entry))
+    }
+
+    /// Find `host` from the host table.
+    ///
+    /// Returns the slot of the host, the offset of its line and its destination.
+    fn find_host(&self, host: &str) -> io::Result<Option<(u64, u64, String)>> {
+        self.probe(Table::Host, hash(host), |index, slot| {
+            Ok(
+                self.read_entry(slot.offset - 1)?.and_then(|(entry, destination)| {
+                    (entry == host).then_some((index, slot.offset - 1, destination))
+                }),
+            )
+        })
+    }
+
+    /// Remove line at `offset` with `destination` from the address table.
+    fn remove_address(&self, destination: &str, offset: u64) -> io::Result<()> {
+        let Some(address) = book::b32_address(destination) else {
+            return Ok(());
+        };
+        let hash = hash(&address);
+
+        self.probe(Table::Address, hash, |index, slot| {
+            match slot.offset == offset + 1 {
+                true => self
+                    .write_slot(
+                        Table::Address,
+                        index,
+                        Slot {
+                            hash,
+                            offset: REMOVED,
+                        },
+                    )
+                    .map(Some),
+                false => Ok(None),
+            }
+        })
+        .map(|_| ())
+    }
+
+    /// Apply entry of the log at `offset` to the index.
+    ///
+    /// If `destination` is `None`, `host` is removed.
+    fn apply(&mut self, host: &str, destination: Option<&str>, offset: u64) -> io::Result<()> {
+        let existing = self.find_host(host)?;
+
+        if let Some((_, old_offset, old_destination)) = &existing {
+            self.remove_address(old_destination, *old_offset)?;
+        }
+
+        match (destination, existing) {
+            (Some(destination), existing) => {
+                match existing {
+                    Some((index, _, _)) => self.write_slot(
+                        Table::Host,
+                        index,
+                        Slot {
+                            hash: hash(host),
+                            offset: offset + 1,
+                        },
+                    )?,
+                    None => {
+                        self.insert_slot(Table::Host, hash(host), offset)?;
+                        self.num_hosts += 1;
+                    }
+                }
+
+                if let Some(address) = book::b32_address(destination) {
+                    self.insert_slot(Table::Address, hash(&address), offset)?;
+                }
+            }
+            (None, Some((index, _, _))) => {
+                self.write_slot(
+                    Table::Host,
+                    index,
+                    Slot {
+                        hash: hash(host),
+                        offset: REMOVED,
+                    },
+                )?;
+                self.num_hosts -= 1;
+            }
+            (None, None) => {}
+        }
+
+        self.num_lines += 1;
+        Ok(())
+    }
+
+    /// Write the header of the index.
+    ///
+    /// The header is written after the log and the tables have been updated so that if the
+    /// update is interrupted, the index doesn't match the log and is rebuilt.
+    fn sync(&self) -> io::Result<()> {
+        let mut header = Vec::with_capacity(HEADER_LEN as usize);
+        header.extend_from_slice(MAGIC);
+
+        for field in [
+            self.num_slots,
+            self.num_hosts,
+            self.num_lines,
+            self.log_len,
+            modified(&self.log)?,
+        ] {
+            header.extend_from_slice(&field.to_le_bytes());
+        }
+
+        write_all_at(&self.index, &header, 0)
+    }
+
+    /// Resolve `host` into a base64-encoded destination.
+    pub fn get(&self, host: &str) -> io::Result<Option<String>> {
+        Ok(self.find_host(host)?.map(|(_, _, destination)| destination))
+    }
+
+    /// Get host names which resolve to `address`, in the order they were added.
+    pub fn hosts(&self, address: &str) -> io::Result<Vec<String>> {
+        let mut hosts = Vec::new();
+
+        self.probe(Table::Address, hash(address), |_, slot| {
+            if let Some((host, destination)) = self.read_entry(slot.offset - 1)? {
+                if book::b32_address(&destination).is_some_and(|resolved| resolved == address) {
+                    hosts.push(host);
+                }
+            }
+
+            Ok(None::<()>)
+        })?;
+
+        Ok(hosts)
+    }
+
+    /// Get the number of hosts in the book.
+    pub fn num_hosts(&self) -> u64 {
+        self.num_hosts
+    }
+
+    /// Check if the log should be compacted before the next change is appended to it.
+    ///
+    /// The log should be compacted if it contains more stale lines than live ones or if another
+    /// line would take the load factor of the tables above one half.
+    pub fn needs_compaction(&self) -> bool {
+        self.num_lines > 2 * self.num_hosts || (self.num_lines + 1) * 2 > self.num_slots
+    }
+
+    /// Append a batch of changes to the log and update the index.
+    ///
+    /// Each change either sets the destination of a host or, if the destination is `None`,
+    /// removes the host. The log is compacted in the middle of the batch only if the tables would
+    /// otherwise become too full, see [`RouterIndex::needs_compaction()`].
+    pub fn update<'a>(
+        &mut self,
+        changes: impl IntoIterator<Item = (&'a str, Option<&'a str>)>,
+    ) -> io::Result<()> {
+        for (host, destination) in changes {
+            // keep the load factor of the tables below one half
+            if (self.num_lines + 1) * 2 > self.num_slots {
+                self.compact()?;
+            }
+
+            let line = format!("{host}={}\n", destination.unwrap_or(""));
+            let offset = self.log_len;
+
+            write_all_at(&self.log, line.as_bytes(), offset)?;
+            self.log_len += line.len() as u64;
+            self.apply(host, destination, offset)?;
+        }
+
+        self.sync()
+    }
+
+    /// Compact the log and rebuild the index.
+    fn compact(&mut self) -> io::Result<()> {
+        tracing::debug!(
+            target: LOG_TARGET,
+            num_hosts = ?self.num_hosts,
+            num_lines = ?self.num_lines,
+            "compacting router address book",
+        );
+
+        self.sync()?;
+        *self = Self::rebuild(self.log_path.clone())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use emissary_core::{
+        crypto::{base64_encode, SigningPrivateKey},
+        primitives::Destination,
+    };
+    use emissary_util::runtime::tokio::Runtime;
+    use rand::rngs::OsRng;
+    use tempfile::tempdir;
+
+    fn random_destination() -> (String, String) {
+        let destination = Destination::new::<Runtime>(SigningPrivateKey::random(OsRng).public());
+        let encoded = base64_encode(destination.serialize());
+        let address = book::b32_address(&encoded).unwrap();
+
+        (encoded, address)
+    }
+
+    #[test]
+    fn hosts_added_and_removed() {
+        let dir = tempdir().unwrap();
+        let (zzz, zzz_b32) = random_destination();
+        let (stats, stats_b32) = random_destination();
+        let mut index = RouterIndex::open(dir.path().join("addresses")).unwrap();
+
+        index
+            .update([
+                ("zzz.i2p", Some(zzz.as_str())),
+                ("stats.i2p", Some(stats.as_str())),
+                ("alias.i2p", Some(zzz.as_str())),
+            ])
+            .unwrap();
+
+        assert_eq!(index.get("zzz.i2p").unwrap(), Some(zzz.clone()));
+        assert_eq!(index.get("psi.i2p").unwrap(), None);
+        assert_eq!(
+            index.hosts(&zzz_b32).unwrap(),
+            vec!["zzz.i2p".to_string(), "alias.i2p".to_string()]
+        );
+
+        // remove one host and point the other one to a different destination
+        index.update([("zzz.i2p", None), ("alias.i2p", Some(stats.as_str()))]).unwrap();
+
+        assert_eq!(index.get("zzz.i2p").unwrap(), None);
+        assert!(index.hosts(&zzz_b32).unwrap().is_empty());
+        assert_eq!(
+            index.hosts(&stats_b32).unwrap(),
+            vec!["stats.i2p".to_string(), "alias.i2p".to_string()]
+        );
+        assert_eq!(index.num_hosts(), 2);
+
+        // five lines for two hosts
+        assert!(index.needs_compaction());
+    }
+
+    #[test]
+    fn compacted_copy_installed() {
+        let dir = tempdir().unwrap();
+        let (zzz, zzz_b32) = random_destination();
+        let (stats, _) = random_destination();
+        let path = dir.path().join("addresses");
+        let mut index = RouterIndex::open(path.clone()).unwrap();
+
+        index
+            .update([
+                ("zzz.i2p", Some(stats.as_str())),
+                ("stats.i2p", Some(stats.as_str())),
+                ("zzz.i2p", Some(zzz.as_str())),
+                ("stats.i2p", None),
+            ])
+            .unwrap();
+
+        // the old index can be used while the compacted copy is written
+        RouterIndex::write_compacted(&path).unwrap();
+        assert_eq!(index.get("zzz.i2p").unwrap(), Some(zzz.clone()));
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 4);
+
+        drop(index);
+        let index = RouterIndex::install_compacted(path.clone()).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("zzz.i2p={zzz}\n")
+        );
+        assert_eq!(index.get("zzz.i2p").unwrap(), Some(zzz));
+        assert_eq!(index.get("stats.i2p").unwrap(), None);
+        assert_eq!(index.hosts(&zzz_b32).unwrap(), vec!["zzz.i2p".to_string()]);
+        assert!(!index.needs_compaction());
+        assert!(!RouterIndex::compacted_log_path(&path).exists());
+        assert!(!path.with_extension("idx.stale").exists());
+
+        // index of the compacted log is reused after restart
+        assert!(RouterIndex::load(&path).unwrap().is_some());
+    }
+
+    #[test]
+    fn index_reused_after_restart() {
+        let dir = tempdir().unwrap();
+        let (zzz, zzz_b32) = random_destination();
+        let path = dir.path().join("addresses");
+
+        RouterIndex::open(path.clone())
+            .unwrap()
+            .update([("zzz.i2p", Some(zzz.as_str()))])
+            .unwrap();
+
+        // index matches the log and is loaded as-is
+        let index = RouterIndex::load(&path).unwrap().unwrap();
+        assert_eq!(index.get("zzz.i2p").unwrap(), Some(zzz));
+        assert_eq!(index.hosts(&zzz_b32).unwrap(), vec!["zzz.i2p".to_string()]);
+    }
+
+    #[test]
+    fn index_rebuilt_if_log_modified() {
+        let dir = tempdir().unwrap();
+        let (zzz, _) = random_destination();
+        let (stats, _) = random_destination();
+        let path = dir.path().join("addresses");
+
+        RouterIndex::open(path.clone())
+            .unwrap()
+            .update([("zzz.i2p", Some(zzz.as_str()))])
+            .unwrap();
+
+        // replace the log while the router is not running
+        fs::write(
+            &path,
+            format!("stats.i2p={stats}\nzzz.i2p={zzz}\nzzz.i2p=\n"),
+        )
+        .unwrap();
+        assert!(RouterIndex::load(&path).unwrap().is_none());
+
+        let index = RouterIndex::open(path.clone()).unwrap();
+        assert_eq!(index.get("zzz.i2p").unwrap(), None);
+        assert_eq!(index.get("stats.i2p").unwrap(), Some(stats.clone()));
+
+        // stale lines were removed while rebuilding the index
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("stats.i2p={stats}\n")
+        );
+    }
+
+    #[test]
+    fn tables_grow() {
+        let dir = tempdir().unwrap();
+        let mut index = RouterIndex::open(dir.path().join("addresses")).unwrap();
+        let hosts = (0..MIN_SLOTS).map(|i| format!("host{i}.i2p")).collect::<Vec<_>>();
+
+        index
+            .update(hosts.iter().map(|host| (host.as_str(), Some("destination"))))
+            .unwrap();
+
+        assert!(index.num_slots > MIN_SLOTS);
+        assert_eq!(index.num_hosts(), MIN_SLOTS);
+        assert!(hosts
+            .iter()
+            .all(|host| index.get(host).unwrap() == Some("destination".to_string())));
+    }
+}
@@ -0,0 +1,667 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Indexed address book store.
+//!
+//! The router address book, which holds the hosts received from subscriptions, is kept on disk
+//! and accessed through [`RouterIndex`]. Changes received from subscriptions are appended to the
+//! book instead of rewriting it. Lookups read from disk so they're executed on the blocking thread
+//! pool, and the book is compacted without holding the lock of the index.
+//!
+//! The private and local address books only contain hosts added by the user and are loaded into
+//! memory. They're reloaded if they're modified on disk, allowing hosts added with
+//! `emissary-cli address-book` to be used without restarting the router.
+
+use crate::address_book::{
+    book::{self, Book},
+    hosts::Command,
+    index::RouterIndex,
+};
+
+use parking_lot::{Mutex, RwLock};
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Logging target for the file
+const LOG_TARGET: &str = "emissary::address-book::store";
+
+/// How often are the private and local address books checked for modifications.
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Address book index.
+struct Index {
+    /// Hosts of the private and local address books, indexed by [`Book`].
+    books: [HashMap<String, String>; 2],
+
+    /// `.b32.i2p` address -> host name, for the private and local address books.
+    names: HashMap<String, String>,
+
+    /// Router address book, `None` if it couldn't be opened.
+    router: Option<RouterIndex>,
+
+    /// Modification times of the private and local address books.
+    modified: [Option<SystemTime>; 2],
+
+    /// When were the private and local address books last checked for modifications.
+    last_check: Instant,
+}
+
+impl Index {
+    /// Resolve `host` from the private and local address books.
+    fn resolve_user(&self, host: &str) -> Option<&String> {
+        self.books.iter().find_map(|book| book.get(host))
+    }
+
+    /// Resolve `host` into a base64-encoded destination.
+    fn resolve(&self, host: &str) -> Option<String> {
+        if let Some(destination) = self.resolve_user(host) {
+            return Some(destination.clone());
+        }
+
+        match self.router.as_ref()?.get(host) {
+            Ok(destination) => destination,
+            Err(error) => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    %host,
+                    ?error,
+                    "failed to read router address book",
+                );
+                None
+            }
+        }
+    }
+
+    /// Resolve `address` into a host name.
+    ///
+    /// Host names of the private and local address books take precedence over host names of the
+    /// router address book. Router hosts overridden by the user with another destination are
+    /// skipped.
+    fn resolve_name(&self, address: &str) -> Option<String> {
+        if let Some(host) = self.names.get(address) {
+            return Some(host.clone());
+        }
+
+        let hosts = match self.router.as_ref()?.hosts(address) {
+            Ok(hosts) => hosts,
+            Err(error) => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    %address,
+                    ?error,
+                    "failed to read router address book",
+                );
+                return None;
+            }
+        };
+
+        hosts.into_iter().find(|host| match self.resolve_user(host) {
+            None => true,
+            Some(destination) =>
+                book::b32_address(destination).is_some_and(|resolved| resolved == address),
+        })
+    }
+
+    /// Rebuild the reverse index of the private and local address books.
+    ///
+    /// The books only contain hosts added by the user so they're small enough to be reindexed
+    /// whenever they change. A host only resolves to the destination of the book with the
+    /// highest precedence, and if multiple hosts resolve to the same destination, the host of the
+    /// private address book is preferred.
+    fn reindex_names(&mut self) {
+        self.names.clear();
+
+        for book in [Book::Local, Book::Private] {
+            for (host, destination) in &self.books[book as usize] {
+                if self.resolve_user(host) != Some(destination) {
+                    continue;
+                }
+
+                if let Some(address) = book::b32_address(destination) {
+                    self.names.insert(address, host.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Indexed address book store.
+#[derive(Clone)]
+pub struct AddressBookStore {
+    /// Base path of the router.
+    base_path: Arc<PathBuf>,
+
+    /// Address book index.
+    index: Arc<RwLock<Index>>,
+
+    /// Lock held while the router address book is being modified.
+    ///
+    /// The router address book is compacted while the index is not locked, so the lock ensures
+    /// the book is not modified until the compacted book has replaced it.
+    writer: Arc<Mutex<()>>,
+}
+
+impl AddressBookStore {
+    /// Create new [`AddressBookStore`] and load address books from `base_path`.
+    pub fn new(base_path: PathBuf) -> Self {
+        let router = match RouterIndex::open(Book::Router.path(&base_path)) {
+            Ok(router) => Some(router),
+            Err(error) => {
+                tracing::error!(
+                    target: LOG_TARGET,
+                    ?error,
+                    "failed to open router address book",
+                );
+                None
+            }
+        };
+        let mut index = Index {
+            books: Default::default(),
+            names: HashMap::new(),
+            router,
+            modified: [None, None],
+            last_check: Instant::now(),
+        };
+
+        for book in [Book::Private, Book::Local] {
+            Self::load_book(&mut index, &base_path, book);
+        }
+        index.reindex_names();
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            num_private = %index.books[Book::Private as usize].len(),
+            num_local = %index.books[Book::Local as usize].len(),
+            num_router = ?index.router.as_ref().map(RouterIndex::num_hosts),
+            "address books loaded",
+        );
+
+        Self {
+            base_path: Arc::new(base_path),
+            index: Arc::new(RwLock::new(index)),
+            writer: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Get modification time of `path`.
+    fn modified(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    /// Load private or local address book `book` from disk into `index`.
+    fn load_book(index: &mut Index, base_path: &Path, book: Book) {
+        let path = book.path(base_path);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(error) => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    ?path,
+                    ?error,
+                    "failed to read address book",
+                );
+                return;
+            }
+        };
+
+        index.modified[book as usize] = Self::modified(&path);
+        index.books[book as usize] = book::parse(&contents).into_iter().collect();
+    }
+
+    /// Reload private and local address books if they have been modified on disk.
+    ///
+    /// The books are checked at most once every [`RELOAD_CHECK_INTERVAL`].
+    fn reload_if_modified(&self) {
+        if self.index.read().last_check.elapsed() < RELOAD_CHECK_INTERVAL {
+            return;
+        }
+        let modified = [Book::Private, Book::Local]
+            .map(|book| (book, Self::modified(&book.path(&self.base_path))));
+
+        let mut index = self.index.write();
+        if index.last_check.elapsed() < RELOAD_CHECK_INTERVAL {
+            return;
+        }
+        index.last_check = Instant::now();
+
+        let mut reloaded = false;
+
+        for (book, modified) in modified {
+            if modified != index.modified[book as usize] {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    book = %book.name(),
+                    "address book modified, reloading",
+                );
+
+                Self::load_book(&mut index, &self.base_path, book);
+                reloaded = true;
+            }
+        }
+
+        if reloaded {
+            index.reindex_names();
+        }
+    }
+
+    /// Resolve `host` into a base64-encoded destination.
+    pub async fn resolve(&self, host: String) -> Option<String> {
+        let store = self.clone();
+
+        tokio::task::spawn_blocking(move || store.resolve_blocking(&host))
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// Resolve `host` into a base64-encoded destination, blocking the calling thread.
+    pub fn resolve_blocking(&self, host: &str) -> Option<String> {
+        self.reload_if_modified();
+        self.index.read().resolve(host)
+    }
+
+    /// Resolve `host` from the private and local address books.
+    ///
+    /// The books are held in memory so the call doesn't block but hosts of the router address
+    /// book are not resolved.
+    pub fn resolve_user(&self, host: &str) -> Option<String> {
+        self.index.read().resolve_user(host).cloned()
+    }
+
+    /// Resolve `address` into a host name.
+    ///
+    /// `address` must not contain the `.b32.i2p` suffix.
+    pub async fn resolve_name(&self, address: String) -> Option<String> {
+        let store = self.clone();
+
+        tokio::task::spawn_blocking(move || store.resolve_name_blocking(&address))
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// Resolve `address` into a host name, blocking the calling thread.
+    ///
+    /// `address` must not contain the `.b32.i2p` suffix.
+    pub fn resolve_name_blocking(&self, address: &str) -> Option<String> {
+        self.reload_if_modified();
+        self.index.read().resolve_name(address)
+    }
+
+    /// Compact the router address book if [`RouterIndex::needs_compaction()`] says so.
+    ///
+    /// The compacted book is written while lookups continue from the old book and the index is
+    /// locked only for replacing the old book with the compacted one.
+    ///
+    /// Must be called while holding `writer`.
+    fn compact_router_if_needed(&self) -> io::Result<()> {
+        if !self.index.read().router.as_ref().is_some_and(RouterIndex::needs_compaction) {
+            return Ok(());
+        }
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            num_hosts = ?self.index.read().router.as_ref().map(RouterIndex::num_hosts),
+            "compacting router address book",
+        );
+
+        let path = Book::Router.path(&self.base_path);
+        RouterIndex::write_compacted(&path)?;
+
+        let mut index = self.index.write();
+
+        // close the old book before it's replaced
+        index.router = None;
+
+        match RouterIndex::install_compacted(path.clone()) {
+            Ok(router) => {
+                index.router = Some(router);
+                Ok(())
+            }
+            Err(error) => {
+                index.router = RouterIndex::open(path).ok();
+                Err(error)
+            }
+        }
+    }
+
+    /// Add `host` with `destination` into `book`.
+    ///
+    /// The entry is appended to the address book on disk.
+    pub async fn add(&self, book: Book, host: String, destination: String) -> io::Result<()> {
+        let store = self.clone();
+
+        tokio::task::spawn_blocking(move || store.add_blocking(book, host, destination))
+            .await
+            .map_err(io::Error::other)?
+    }
+
+    /// Add `host` with `destination` into `book`, blocking the calling thread.
+    fn add_blocking(&self, book: Book, host: String, destination: String) -> io::Result<()> {
+        if book == Book::Router {
+            let _writer = self.writer.lock();
+            self.compact_router_if_needed()?;
+
+            return match self.index.write().router.as_mut() {
+                Some(router) => router.update([(host.as_str(), Some(destination.as_str()))]),
+                None => Err(io::Error::other("router address book not available")),
+            };
+        }
+
+        let mut index = self.index.write();
+
+        let path = book.path(&self.base_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| {
+                io::Write::write_all(&mut file, format!("{host}={destination}\n").as_bytes())
+            })?;
+
+        index.modified[book as usize] = Self::modified(&path);
+        index.books[book as usize].insert(host, destination);
+        index.reindex_names();
+
+        Ok(())
+    }
+
+    /// Apply `commands` received from a subscription to the router address book.
+    ///
+    /// Modified entries are appended to the router address book.
+    ///
+    /// Returns the number of modified entries.
+    pub async fn merge(&self, commands: impl IntoIterator<Item = Command>) -> usize {
+        let store = self.clone();
+        let commands = commands.into_iter().collect::<Vec<_>>();
+
+        tokio::task::spawn_blocking(move || store.merge_blocking(commands))
+            .await
+            .unwrap_or_default()
+    }
+
+    /// Apply `commands` to the router address book, blocking the calling thread.
+    ///
+    /// The index is locked separately for each command so lookups are not blocked for the
+    /// duration of the whole merge.
+    fn merge_blocking(&self, commands: Vec<Command>) -> usize {
+        let _writer = self.writer.lock();
+        let mut num_changes = 0usize;
+
+        for command in commands {
+            if let Err(error) = self.compact_router_if_needed() {
+                tracing::error!(
+                    target: LOG_TARGET,
+                    ?error,
+                    "failed to compact router address book",
+                );
+                return num_changes;
+            }
+
+            let mut index = self.index.write();
+            let Some(router) = index.router.as_mut() else {
+                return num_changes;
+            };
+
+            let result = (|| {
+                // commands only read `host` and the host name an alias is added for, so fetch
+                // them from the router address book and apply the command on a copy
+                let host = command.host().to_string();
+                let mut hosts = HashMap::new();
+
+                for name in [Some(host.as_str()), command.old_name()].into_iter().flatten() {
+                    if let Some(destination) = router.get(name)? {
+                        hosts.insert(name.to_string(), destination);
+                    }
+                }
+
+                match command.apply(&mut hosts) {
+                    true => router
+                        .update([(host.as_str(), hosts.get(&host).map(String::as_str))])
+                        .map(|_| true),
+                    false => Ok(false),
+                }
+            })();
+
+            match result {
+                Ok(true) => num_changes += 1,
+                Ok(false) => {}
+                Err(error) => {
+                    tracing::error!(
+                        target: LOG_TARGET,
+                        ?error,
+                        "failed to write router address book",
+                    );
+                    return num_changes;
+                }
+            }
+        }
+
+        if let Err(error) = self.compact_router_if_needed() {
+            tracing::error!(
+                target: LOG_TARGET,
+                ?error,
+                "failed to compact router address book",
+            );
+        }
+
+        num_changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use emissary_core::{
+        crypto::{base64_encode, SigningPrivateKey},
+        primitives::Destination,
+    };
+    use emissary_util::runtime::tokio::Runtime;
+    use rand::rngs::OsRng;
+    use tempfile::tempdir;
+
+    fn random_destination() -> (String, String) {
+        let destination = Destination::new::<Runtime>(SigningPrivateKey::random(OsRng).public());
+        let encoded = base64_encode(destination.serialize());
+        let address = book::b32_address(&encoded).unwrap();
+
+        (encoded, address)
+    }
+
+    #[tokio::test]
+    async fn subscription_changes_appended() {
+        let dir = tempdir().unwrap();
+        let (zzz, zzz_b32) = random_destination();
+        let (stats, _) = random_destination();
+        let (psi, _) = random_destination();
+        let store = AddressBookStore::new(dir.path().to_path_buf());
+
+        let commands = vec![
+            Command::Add {
+                name: "zzz.i2p".to_string(),
+                destination: zzz.clone(),
+            },
+            Command::Add {
+                name: "stats.i2p".to_string(),
+                destination: stats.clone(),
+            },
+            Command::Add {
+                name: "psi.i2p".to_string(),
+                destination: psi.clone(),
+            },
+        ];
+        assert_eq!(store.merge(commands).await, 3);
+        assert_eq!(
+            store.resolve("zzz.i2p".to_string()).await,
+            Some(zzz.clone())
+        );
+        assert_eq!(
+            store.resolve_name(zzz_b32.clone()).await,
+            Some("zzz.i2p".to_string())
+        );
+
+        // already-existing host is not modified and nothing is written to disk
+        let commands = vec![Command::Add {
+            name: "zzz.i2p".to_string(),
+            destination: stats.clone(),
+        }];
+        assert_eq!(store.merge(commands).await, 0);
+        assert_eq!(
+            fs::read_to_string(Book::Router.path(dir.path())).unwrap().lines().count(),
+            3
+        );
+
+        // removal is appended to the router address book
+        let commands = vec![Command::Remove {
+            name: "zzz.i2p".to_string(),
+            destination: zzz.clone(),
+        }];
+        assert_eq!(store.merge(commands).await, 1);
+        assert_eq!(store.resolve("zzz.i2p".to_string()).await, None);
+        assert_eq!(store.resolve_name(zzz_b32.clone()).await, None);
+        assert_eq!(
+            fs::read_to_string(Book::Router.path(dir.path())).unwrap(),
+            format!("zzz.i2p={zzz}\nstats.i2p={stats}\npsi.i2p={psi}\nzzz.i2p=\n"),
+        );
+
+        // changes are visible after the store is reloaded from disk
+        let store = AddressBookStore::new(dir.path().to_path_buf());
+        assert_eq!(store.resolve("zzz.i2p".to_string()).await, None);
+        assert_eq!(store.resolve("stats.i2p".to_string()).await, Some(stats));
+    }
+
+    #[tokio::test]
+    async fn router_address_book_compacted() {
+        let dir = tempdir().unwrap();
+        let (zzz, _) = random_destination();
+        let store = AddressBookStore::new(dir.path().to_path_buf());
+
+        for _ in 0..2 {
+            let commands = vec![Command::Add {
+                name: "zzz.i2p".to_string(),
+                destination: zzz.clone(),
+            }];
+            assert_eq!(store.merge(commands).await, 1);
+
+            let commands = vec![Command::Remove {
+                name: "zzz.i2p".to_string(),
+                destination: zzz.clone(),
+            }];
+            assert_eq!(store.merge(commands).await, 1);
+        }
+
+        // the book has no live entries so it's compacted after each removal
+        assert!(fs::read_to_string(Book::Router.path(dir.path())).unwrap().is_empty());
+        assert_eq!(store.index.read().router.as_ref().unwrap().num_hosts(), 0);
+    }
+
+    #[tokio::test]
+    #[allow(clippy::await_holding_lock)]
+    async fn lookups_not_blocked_by_writer() {
+        let dir = tempdir().unwrap();
+        let (zzz, _) = random_destination();
+        let store = AddressBookStore::new(dir.path().to_path_buf());
+
+        let commands = vec![Command::Add {
+            name: "zzz.i2p".to_string(),
+            destination: zzz.clone(),
+        }];
+        assert_eq!(store.merge(commands).await, 1);
+
+        // the writer lock is held while the router address book is compacted
+        let _writer = store.writer.lock();
+
+        assert_eq!(
+            tokio::time::timeout(Duration::from_secs(5), store.resolve("zzz.i2p".to_string()))
+                .await
+                .unwrap(),
+            Some(zzz)
+        );
+    }
+
+    #[tokio::test]
+    async fn reverse_lookup_prefers_user_hosts() {
+        let dir = tempdir().unwrap();
+        let (destination, address) = random_destination();
+        let store = AddressBookStore::new(dir.path().to_path_buf());
+
+        let commands = vec![Command::Add {
+            name: "router.i2p".to_string(),
+            destination: destination.clone(),
+        }];
+        assert_eq!(store.merge(commands).await, 1);
+        assert_eq!(
+            store.resolve_name(address.clone()).await,
+            Some("router.i2p".to_string())
+        );
+
+        store
+            .add(Book::Local, "local.i2p".to_string(), destination.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            store.resolve_name(address.clone()).await,
+            Some("local.i2p".to_string())
+        );
+
+        // another host name is found if the indexed host is pointed to another destination
+        let (other, _) = random_destination();
+        store.add(Book::Private, "local.i2p".to_string(), other).await.unwrap();
+        assert_eq!(
+            store.resolve_name(address.clone()).await,
+            Some("router.i2p".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn modified_local_book_reloaded() {
+        let dir = tempdir().unwrap();
+        let (destination, address) = random_destination();
+        let store = AddressBookStore::new(dir.path().to_path_buf());
+
+        book::store(
+            dir.path(),
+            Book::Local,
+            &std::collections::BTreeMap::from([("zzz.i2p".to_string(), destination.clone())]),
+        )
+        .unwrap();
+
+        // modifications are not checked until the check interval has passed
+        assert_eq!(store.resolve("zzz.i2p".to_string()).await, None);
+
+        store.index.write().last_check = Instant::now() - RELOAD_CHECK_INTERVAL;
+        assert_eq!(
+            store.resolve("zzz.i2p".to_string()).await,
+            Some(destination)
+        );
+        assert_eq!(
+            store.resolve_name(address.clone()).await,
+            Some("zzz.i2p".to_string())
+        );
+    }
+}
@@ -0,0 +1,252 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Blocklist manager.
+//!
+//! Loads the local blocklist file and periodically downloads signed blocklist feeds, updating the
+//! [`Blocklist`] of the router. Feeds hosted on `.i2p` hosts are downloaded through the HTTP proxy
+//! and other feeds are downloaded directly.
+
+use crate::config::BlocklistConfig;
+
+use emissary_core::{Blocklist, BlocklistEntry};
+use emissary_util::{certificates::load_public_keys, su3::Su3};
+use reqwest::{Client, Proxy};
+use rsa::RsaPublicKey;
+
+use std::{collections::HashMap, path::Path, time::Duration};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::blocklist";
+
+/// Name of the blocklist file in the base path.
+const BLOCKLIST_FILE: &str = "blocklist.txt";
+
+/// How often are the blocklist feeds downloaded.
+const FEED_UPDATE_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// How many times is the download of a feed retried.
+const FEED_NUM_RETRIES: usize = 5usize;
+
+/// Backoff between download attempts.
+const RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Blocklist manager.
+pub struct BlocklistManager {
+    /// Blocklist.
+    blocklist: Blocklist,
+
+    /// URLs of the blocklist feeds.
+    feeds: Vec<String>,
+
+    /// Public keys of the feed signers, indexed by signer ID.
+    keys: HashMap<String, RsaPublicKey>,
+}
+
+impl BlocklistManager {
+    /// Create new [`BlocklistManager`].
+    ///
+    /// If `file` is not specified, `blocklist.txt` of `base_path` is used if it exists.
+    pub fn new(base_path: &Path, config: BlocklistConfig) -> anyhow::Result<Self> {
+        let blocklist = Blocklist::new();
+        let path = match config.file {
+            Some(file) => Some(file.into()),
+            None => Some(base_path.join(BLOCKLIST_FILE)).filter(|path| path.exists()),
+        };
+
+        if let Some(path) = path {
+            let entries = BlocklistEntry::parse_list(&std::fs::read_to_string(&path)?);
+
+            tracing::info!(
+                target: LOG_TARGET,
+                ?path,
+                num_entries = ?entries.len(),
+                "blocklist loaded",
+            );
+
+            blocklist.update("local", entries);
+        }
+
+        let keys = load_public_keys(&config.certificates.unwrap_or_default())?;
+
+        Ok(Self {
+            blocklist,
+            feeds: config.feeds.unwrap_or_default(),
+            keys,
+        })
+    }
+
+    /// Get handle to [`Blocklist`].
+    pub fn blocklist(&self) -> Blocklist {
+        self.blocklist.clone()
+    }
+
+    /// Download blocklist feed from `url` and verify it.
+    async fn download(&self, client: &Client, url: &str) -> Option<String> {
+        let response = match client.get(url).timeout(Duration::from_secs(60)).send().await {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    %url,
+                    status = ?response.status(),
+                    "failed to download blocklist feed",
+                );
+                return None;
+            }
+            Err(error) => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    %url,
+                    ?error,
+                    "failed to download blocklist feed",
+                );
+                return None;
+            }
+        };
+        let response = response.bytes().await.ok()?;
+
+        match Su3::parse_blocklist(&response, &self.keys) {
+            Some(blocklist) => Some(blocklist),
+            None => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    %url,
+                    "invalid blocklist feed",
+                );
+                None
+            }
+        }
+    }
+
+    /// Start event loop of [`BlocklistManager`].
+    ///
+    /// `http_proxy` is the address of the HTTP proxy, if it was enabled, which is used to download
+    /// feeds hosted on `.i2p` hosts.
+    pub async fn run(self, http_proxy: Option<(String, u16)>) {
+        if self.feeds.is_empty() {
+            return;
+        }
+
+        let direct = Client::new();
+        let proxied = http_proxy.map(|(host, port)| {
+            Client::builder()
+                .proxy(Proxy::http(format!("http://{host}:{port}")).expect("to succeed"))
+                .http1_title_case_headers()
+                .build()
+                .expect("to succeed")
+        });
+
+        loop {
+            for url in &self.feeds {
+                let is_i2p = url::Url::parse(url)
+                    .ok()
+                    .and_then(|url| url.host_str().map(|host| host.ends_with(".i2p")))
+                    .unwrap_or(false);
+
+                let client = match (is_i2p, &proxied) {
+                    (false, _) => &direct,
+                    (true, Some(client)) => client,
+                    (true, None) => {
+                        tracing::warn!(
+                            target: LOG_TARGET,
+                            %url,
+                            "http proxy is disabled, cannot download blocklist feed",
+                        );
+                        continue;
+                    }
+                };
+
+                for _ in 0..FEED_NUM_RETRIES {
+                    if let Some(blocklist) = self.download(client, url).await {
+                        let entries = BlocklistEntry::parse_list(&blocklist);
+
+                        tracing::info!(
+                            target: LOG_TARGET,
+                            %url,
+                            num_entries = ?entries.len(),
+                            "blocklist feed updated",
+                        );
+
+                        self.blocklist.update(url.clone(), entries);
+                        break;
+                    }
+
+                    tokio::time::sleep(RETRY_BACKOFF).await;
+                }
+            }
+
+            tokio::time::sleep(FEED_UPDATE_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+    use tempfile::tempdir;
+
+    const CERTIFICATE: &str = include_str!("../../emissary-util/assets/test/test_at_mail.i2p.crt");
+
+    #[test]
+    fn local_blocklist_loaded() {
+        let dir = tempdir().unwrap();
+        let certificate = dir.path().join("test_at_mail.i2p.crt");
+        std::fs::write(dir.path().join("blocklist.txt"), "1.2.3.4\n10.0.0.0/8\n").unwrap();
+        std::fs::write(&certificate, CERTIFICATE).unwrap();
+
+        let manager = BlocklistManager::new(
+            dir.path(),
+            BlocklistConfig {
+                file: None,
+                feeds: None,
+                certificates: Some(vec![certificate.display().to_string()]),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(manager.blocklist().num_entries(), 2);
+        assert!(manager.blocklist().is_address_blocked(&IpAddr::V4(Ipv4Addr::new(10, 1, 2, 3))));
+        assert!(manager.keys.contains_key("test@mail.i2p"));
+    }
+
+    #[test]
+    fn blocklist_file_doesnt_exist() {
+        let dir = tempdir().unwrap();
+        let config = || BlocklistConfig {
+            file: None,
+            feeds: None,
+            certificates: None,
+        };
+
+        // default blocklist file is optional
+        let manager = BlocklistManager::new(dir.path(), config()).unwrap();
+        assert_eq!(manager.blocklist().num_entries(), 0);
+
+        // explicitly configured blocklist file must exist
+        assert!(BlocklistManager::new(
+            dir.path(),
+            BlocklistConfig {
+                file: Some(dir.path().join("blocked.txt").display().to_string()),
+                ..config()
+            },
+        )
+        .is_err());
+    }
+}
@@ -47,6 +47,21 @@ pub struct TunnelOptions {
     pub insecure_tunnels: Option<bool>,
 }
 
+#[derive(Args)]
+pub struct BandwidthOptions {
+    /// Inbound bandwidth limit, in KBps.
+    #[arg(long, value_name = "KBPS")]
+    pub bandwidth_inbound: Option<usize>,
+
+    /// Outbound bandwidth limit, in KBps.
+    #[arg(long, value_name = "KBPS")]
+    pub bandwidth_outbound: Option<usize>,
+
+    /// Percentage of bandwidth shared with transit tunnels.
+    #[arg(long, value_name = "PERCENTAGE")]
+    pub bandwidth_share: Option<u8>,
+}
+
 #[derive(Args)]
 pub struct TransitOptions {
     /// Maximum number of transit tunnels.
@@ -82,6 +97,17 @@ pub struct ReseedOptions {
     /// Disable forcing of IPv4 when connecting to reseed hosts.
     #[arg(long, action = clap::ArgAction::SetTrue)]
     pub disable_force_ipv4: Option<bool>,
+
+    /// Reseed from a local .su3 or .zip file instead of reseed hosts
+    #[arg(long, value_name = "FILE")]
+    pub reseed_file: Option<std::path::PathBuf>,
+
+    /// HTTP or SOCKS proxy used to connect to reseed hosts
+    ///
+    /// Example:
+    ///   --reseed-proxy socks5h://127.0.0.1:9050
+    #[arg(long, value_name = "URL")]
+    pub reseed_proxy: Option<String>,
 }
 
 #[derive(Args)]
@@ -127,6 +153,10 @@ pub struct SocksProxyOptions {
     /// Defaults to 127.0.0.1
     #[arg(long, value_name = "HOST")]
     pub socks_proxy_host: Option<String>,
+
+    /// SOCKS outproxy.
+    #[arg(long, value_name = "OUTPROXY")]
+    pub socks_outproxy: Option<String>,
 }
 
 #[derive(Args)]
@@ -231,6 +261,10 @@ pub struct Arguments {
     #[clap(flatten)]
     pub socks_proxy: SocksProxyOptions,
 
+    /// Bandwidth options.
+    #[clap(flatten)]
+    pub bandwidth: BandwidthOptions,
+
     /// Transit tunnel options.
     #[clap(flatten)]
     pub transit: TransitOptions,
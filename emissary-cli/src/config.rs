@@ -25,16 +25,20 @@ use crate::{
 use home::home_dir;
 use rand::{rngs::OsRng, thread_rng, Rng, RngCore};
 use serde::{Deserialize, Serialize};
+use toml_edit::{DocumentMut, Item, Table, TableLike, Value};
 
 use std::{
     collections::HashSet,
     fs,
     io::{Read, Write},
-    net::Ipv4Addr,
+    net::{Ipv4Addr, Ipv6Addr},
     path::{Path, PathBuf},
     time::Duration,
 };
 
+/// Maximum number of member destinations in a meta lease set.
+const MAX_META_LEASE_SET_MEMBERS: usize = 16usize;
+
 /// Reserved ports.
 ///
 /// Taken from i2pd.
@@ -99,6 +103,9 @@ struct ExploratoryConfig {
 struct Ntcp2Config {
     port: u16,
     host: Option<Ipv4Addr>,
+    ipv6_host: Option<Ipv6Addr>,
+    ipv4: Option<bool>,
+    ipv6: Option<bool>,
     publish: Option<bool>,
 }
 
@@ -106,6 +113,9 @@ struct Ntcp2Config {
 struct Ssu2Config {
     port: u16,
     host: Option<Ipv4Addr>,
+    ipv6_host: Option<Ipv6Addr>,
+    ipv4: Option<bool>,
+    ipv6: Option<bool>,
     publish: Option<bool>,
 }
 
@@ -115,6 +125,14 @@ struct I2cpConfig {
     host: Option<String>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct I2pControlConfig {
+    pub host: Option<String>,
+    pub port: u16,
+    pub password: Option<String>,
+    pub allowed_hosts: Option<Vec<String>>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SamConfig {
     tcp_port: u16,
@@ -122,10 +140,12 @@ pub struct SamConfig {
     host: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReseedConfig {
     pub hosts: Option<Vec<String>>,
     pub reseed_threshold: usize,
+    pub proxy: Option<String>,
+    pub certificates: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -133,18 +153,55 @@ pub struct HttpProxyConfig {
     pub port: u16,
     pub host: String,
     pub outproxy: Option<String>,
+    pub jump_services: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SocksProxyConfig {
     pub port: u16,
     pub host: String,
+    pub outproxy: Option<String>,
+    pub users: Option<Vec<SocksUserConfig>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SocksUserConfig {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddressBookFeedConfig {
+    pub port: u16,
+    pub destination_path: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AddressBookConfig {
     pub default: Option<String>,
     pub subscriptions: Option<Vec<String>>,
+    pub feed: Option<AddressBookFeedConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlocklistConfig {
+    pub file: Option<String>,
+    pub feeds: Option<Vec<String>>,
+    pub certificates: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewsConfig {
+    pub url: String,
+    pub certificates: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientTunnelKind {
+    #[default]
+    Tcp,
+    Udp,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -154,6 +211,36 @@ pub struct ClientTunnelConfig {
     pub port: u16,
     pub destination: String,
     pub destination_port: Option<u16>,
+    #[serde(rename = "type", default)]
+    pub kind: ClientTunnelKind,
+}
+
+impl ClientTunnelConfig {
+    /// Validate the configuration of the tunnel.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.kind == ClientTunnelKind::Udp && self.destination_port.is_some() {
+            return Err(String::from(
+                "`destination_port` is not supported for udp client tunnels",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetaLeaseSetConfig {
+    pub destination_path: String,
+    pub members: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServerTunnelKind {
+    #[default]
+    Tcp,
+    Http,
+    Udp,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -161,6 +248,46 @@ pub struct ServerTunnelConfig {
     pub name: String,
     pub port: u16,
     pub destination_path: String,
+    #[serde(rename = "type", default)]
+    pub kind: ServerTunnelKind,
+    pub host: Option<String>,
+    pub requests_per_minute: Option<usize>,
+    pub meta_lease_set: Option<MetaLeaseSetConfig>,
+}
+
+impl ServerTunnelConfig {
+    /// Validate the configuration of the tunnel.
+    pub fn validate(&self) -> Result<(), String> {
+        // meta lease set must list between 1 and 16 member destinations
+        if self.meta_lease_set.as_ref().is_some_and(|config| {
+            config.members.is_empty() || config.members.len() > MAX_META_LEASE_SET_MEMBERS
+        }) {
+            return Err(format!(
+                "meta lease set must have 1-{MAX_META_LEASE_SET_MEMBERS} members"
+            ));
+        }
+
+        if self.requests_per_minute == Some(0) {
+            return Err(String::from("server tunnel rate limit must be non-zero"));
+        }
+
+        if self.kind != ServerTunnelKind::Http
+            && (self.host.is_some() || self.requests_per_minute.is_some())
+        {
+            return Err(String::from(
+                "`host` and `requests_per_minute` require an http server tunnel",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BandwidthConfig {
+    pub inbound: Option<usize>,
+    pub outbound: Option<usize>,
+    pub share: Option<u8>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -207,6 +334,8 @@ struct EmissaryConfig {
     address_book: Option<AddressBookConfig>,
     #[serde(default)]
     allow_local: bool,
+    bandwidth: Option<BandwidthConfig>,
+    blocklist: Option<BlocklistConfig>,
     caps: Option<String>,
     exploratory: Option<ExploratoryConfig>,
     #[serde(default)]
@@ -216,11 +345,13 @@ struct EmissaryConfig {
     #[serde(rename = "socks-proxy")]
     socks_proxy: Option<SocksProxyConfig>,
     i2cp: Option<I2cpConfig>,
+    i2pcontrol: Option<I2pControlConfig>,
     #[serde(default)]
     insecure_tunnels: bool,
     log: Option<String>,
     metrics: Option<MetricsConfig>,
     net_id: Option<u8>,
+    news: Option<NewsConfig>,
     ntcp2: Option<Ntcp2Config>,
     #[serde(rename = "port-forwarding")]
     port_forwarding: Option<PortForwardingConfig>,
@@ -244,12 +375,14 @@ impl Default for EmissaryConfig {
                     "http://udhdrtrcetjm5sxzskjyr5ztpeszydbh4dpl3pl4utgqqw2v4jna.b32.i2p/hosts.txt",
                 )),
                 subscriptions: None,
+                feed: None,
             }),
             caps: Some(String::from("XR")),
             http_proxy: Some(HttpProxyConfig {
                 host: "127.0.0.1".to_string(),
                 port: 4444u16,
                 outproxy: None,
+                jump_services: None,
             }),
             socks_proxy: None,
             i2cp: Some(I2cpConfig {
@@ -268,6 +401,9 @@ impl Default for EmissaryConfig {
                     }
                 },
                 host: None,
+                ipv6_host: None,
+                ipv4: Some(true),
+                ipv6: Some(false),
                 publish: Some(true),
             }),
             port_forwarding: Some(PortForwardingConfig {
@@ -278,6 +414,8 @@ impl Default for EmissaryConfig {
             reseed: Some(ReseedConfig {
                 reseed_threshold: 25usize,
                 hosts: None,
+                proxy: None,
+                certificates: None,
             }),
             router_ui: Some(RouterUiConfig {
                 theme: Theme::Dark,
@@ -293,11 +431,15 @@ impl Default for EmissaryConfig {
                 max_tunnels: Some(1000),
             }),
             allow_local: false,
+            bandwidth: None,
+            blocklist: None,
             exploratory: None,
             floodfill: false,
+            i2pcontrol: None,
             insecure_tunnels: false,
             log: None,
             net_id: None,
+            news: None,
             ssu2: None,
             client_tunnels: None,
             server_tunnels: None,
@@ -313,9 +455,15 @@ pub struct Config {
     /// Allow local addresses.
     pub allow_local: bool,
 
+    /// Bandwidth limits.
+    pub bandwidth: Option<emissary_core::BandwidthConfig>,
+
     /// Base path.
     pub base_path: PathBuf,
 
+    /// Blocklist config.
+    pub blocklist: Option<BlocklistConfig>,
+
     /// Router capabilities.
     pub caps: Option<String>,
 
@@ -334,6 +482,9 @@ pub struct Config {
     /// I2CP config.
     pub i2cp_config: Option<emissary_core::I2cpConfig>,
 
+    /// I2PControl config.
+    pub i2pcontrol: Option<I2pControlConfig>,
+
     /// Are tunnels allowed to be insecure.
     pub insecure_tunnels: bool,
 
@@ -346,6 +497,9 @@ pub struct Config {
     /// Network ID.
     pub net_id: Option<u8>,
 
+    /// News feed config.
+    pub news: Option<NewsConfig>,
+
     /// NTCP2 config.
     pub ntcp2_config: Option<emissary_core::Ntcp2Config>,
 
@@ -393,6 +547,8 @@ impl From<Config> for emissary_core::Config {
     fn from(val: Config) -> Self {
         emissary_core::Config {
             allow_local: val.allow_local,
+            bandwidth: val.bandwidth,
+            blocklist: None,
             caps: val.caps,
             exploratory: val.exploratory,
             floodfill: val.floodfill,
@@ -415,23 +571,29 @@ impl From<Config> for emissary_core::Config {
 }
 
 impl Config {
+    /// Resolve base path of the router.
+    ///
+    /// If `path` is not specified, defaults to `$HOME/.emissary`.
+    pub fn base_path(path: Option<PathBuf>) -> Result<PathBuf, Error> {
+        path.map_or_else(
+            || {
+                let mut path = home_dir()?;
+                (!path.as_os_str().is_empty()).then(|| {
+                    path.push(".emissary");
+                    path
+                })
+            },
+            Some,
+        )
+        .ok_or(Error::Custom(String::from("couldn't resolve base path")))
+    }
+
     /// Attemp to parse configuration from `path` and merge config with `arguments`.
     ///
     /// If the configuratin file exists but it's invalid, exit early, unless `--overwrite-config`
     /// has been passed in which case create new default configuration.
     pub fn parse(path: Option<PathBuf>, arguments: &Arguments) -> Result<Self, Error> {
-        let path = path
-            .map_or_else(
-                || {
-                    let mut path = home_dir()?;
-                    (!path.as_os_str().is_empty()).then(|| {
-                        path.push(".emissary");
-                        path
-                    })
-                },
-                Some,
-            )
-            .ok_or(Error::Custom(String::from("couldn't resolve base path")))?;
+        let path = Self::base_path(path)?;
 
         tracing::trace!(
             target: LOG_TARGET,
@@ -670,6 +832,98 @@ impl Config {
         })
     }
 
+    /// Load router configuration from `base_path`, patch it with `update` and store it back.
+    ///
+    /// The configuration is edited in place, meaning comments, formatting and keys that `update`
+    /// didn't touch are preserved.
+    fn update_router_config(
+        base_path: &Path,
+        update: impl FnOnce(&mut DocumentMut),
+    ) -> crate::Result<()> {
+        let path = base_path.join("router.toml");
+        let mut document = fs::read_to_string(&path)?.parse::<DocumentMut>().map_err(|error| {
+            tracing::warn!(
+                target: LOG_TARGET,
+                %error,
+                "failed to parse router config",
+            );
+
+            Error::InvalidData
+        })?;
+        update(&mut document);
+
+        fs::write(path, document.to_string())?;
+
+        Ok(())
+    }
+
+    /// Get mutable reference to table `name` of `document`, creating the table if it doesn't exist.
+    fn config_table<'a>(document: &'a mut DocumentMut, name: &str) -> &'a mut dyn TableLike {
+        if !document.get(name).is_some_and(Item::is_table_like) {
+            document.insert(name, Item::Table(Table::new()));
+        }
+
+        document.get_mut(name).and_then(Item::as_table_like_mut).expect("to exist")
+    }
+
+    /// Set `key` of `table` to `value` or remove the key if `value` is `None`.
+    ///
+    /// If the key already exists, its formatting and comments are kept.
+    fn set_config_value(table: &mut dyn TableLike, key: &str, value: Option<impl Into<Value>>) {
+        let Some(value) = value else {
+            table.remove(key);
+            return;
+        };
+        let mut value = value.into();
+
+        match table.get_mut(key).and_then(Item::as_value_mut) {
+            Some(old) => {
+                *value.decor_mut() = old.decor().clone();
+                *old = value;
+            }
+            None => {
+                table.insert(key, Item::Value(value));
+            }
+        }
+    }
+
+    /// Store bandwidth limits in the router configuration so they're used after restart.
+    pub fn save_bandwidth(
+        base_path: &Path,
+        bandwidth: Option<&emissary_core::BandwidthConfig>,
+    ) -> crate::Result<()> {
+        Self::update_router_config(base_path, |document| {
+            let Some(bandwidth) = bandwidth else {
+                document.remove("bandwidth");
+                return;
+            };
+            let table = Self::config_table(document, "bandwidth");
+
+            Self::set_config_value(
+                table,
+                "inbound",
+                bandwidth.inbound.map(|limit| limit as i64),
+            );
+            Self::set_config_value(
+                table,
+                "outbound",
+                bandwidth.outbound.map(|limit| limit as i64),
+            );
+            Self::set_config_value(table, "share", bandwidth.share.map(i64::from));
+        })
+    }
+
+    /// Store I2PControl configuration in the router configuration.
+    pub fn save_i2pcontrol(base_path: &Path, i2pcontrol: &I2pControlConfig) -> crate::Result<()> {
+        Self::update_router_config(base_path, |document| {
+            let table = Self::config_table(document, "i2pcontrol");
+
+            Self::set_config_value(table, "host", i2pcontrol.host.as_deref());
+            Self::set_config_value(table, "port", Some(i2pcontrol.port as i64));
+            Self::set_config_value(table, "password", i2pcontrol.password.as_deref());
+        })
+    }
+
     fn load_router_info(path: PathBuf) -> crate::Result<Vec<u8>> {
         // parse configuration, if it exists
         let mut file = fs::File::open(path.join("router.info"))?;
@@ -729,7 +983,13 @@ impl Config {
         Ok(Self {
             address_book: config.address_book,
             allow_local: config.allow_local,
+            bandwidth: config.bandwidth.map(|config| emissary_core::BandwidthConfig {
+                inbound: config.inbound,
+                outbound: config.outbound,
+                share: config.share,
+            }),
             base_path,
+            blocklist: config.blocklist,
             caps: config.caps,
             client_tunnels: config.client_tunnels.unwrap_or(Vec::new()),
             exploratory: config.exploratory.map(|config| emissary_core::ExploratoryConfig {
@@ -744,15 +1004,20 @@ impl Config {
                 port: config.port,
                 host: config.host.unwrap_or(String::from("127.0.0.1")),
             }),
+            i2pcontrol: config.i2pcontrol,
             insecure_tunnels: config.insecure_tunnels,
             log: config.log,
             metrics: config
                 .metrics
                 .map(|config| emissary_core::MetricsConfig { port: config.port }),
             net_id: config.net_id,
+            news: config.news,
             ntcp2_config: Some(emissary_core::Ntcp2Config {
                 port: config.ntcp2.as_ref().expect("ntcp").port,
-                host: None,
+                ipv4_host: None,
+                ipv6_host: None,
+                ipv4: true,
+                ipv6: false,
                 key: ntcp2_key,
                 iv: ntcp2_iv,
                 publish: true,
@@ -825,6 +1090,16 @@ impl Config {
                 );
                 return Err(Error::InvalidData);
             }
+
+            // ensure each client tunnel has a valid configuration
+            if let Err(error) = tunnels.iter().try_for_each(ClientTunnelConfig::validate) {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    %error,
+                    "invalid client tunnel configuration",
+                );
+                return Err(Error::InvalidData);
+            }
         }
 
         if let Some(tunnels) = &config.server_tunnels {
@@ -864,12 +1139,95 @@ impl Config {
                 );
                 return Err(Error::InvalidData);
             }
+
+            // ensure each server tunnel has a valid configuration
+            if let Err(error) = tunnels.iter().try_for_each(ServerTunnelConfig::validate) {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    %error,
+                    "invalid server tunnel configuration",
+                );
+                return Err(Error::InvalidData);
+            }
+        }
+
+        // ensure blocklist feeds can be verified
+        if let Some(BlocklistConfig {
+            feeds: Some(feeds),
+            certificates,
+            ..
+        }) = &config.blocklist
+        {
+            if !feeds.is_empty()
+                && certificates.as_ref().is_none_or(|certificates| certificates.is_empty())
+            {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    "blocklist feeds require at least one certificate",
+                );
+                return Err(Error::InvalidData);
+            }
+        }
+
+        // ensure news feed can be verified
+        if config.news.as_ref().is_some_and(|config| config.certificates.is_empty()) {
+            tracing::warn!(
+                target: LOG_TARGET,
+                "news feed requires at least one certificate",
+            );
+            return Err(Error::InvalidData);
+        }
+
+        if let Some(users) = config.socks_proxy.as_ref().and_then(|config| config.users.as_ref()) {
+            // ensure each socks user has a unique username
+            if users.iter().map(|user| &user.username).collect::<HashSet<_>>().len() != users.len()
+            {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    "all socks proxy users must have a unique username",
+                );
+                return Err(Error::InvalidData);
+            }
+
+            // ensure usernames and passwords can be sent in a username/password request
+            if users.iter().any(|user| {
+                !(1..=255).contains(&user.username.len())
+                    || !(1..=255).contains(&user.password.len())
+            }) {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    "socks proxy usernames and passwords must be 1-255 bytes long",
+                );
+                return Err(Error::InvalidData);
+            }
+        }
+
+        // ensure the server tunnel of the address book feed doesn't conflict with other tunnels
+        if let (Some(feed), Some(tunnels)) = (
+            config.address_book.as_ref().and_then(|config| config.feed.as_ref()),
+            &config.server_tunnels,
+        ) {
+            if tunnels.iter().any(|config| {
+                config.port == feed.port || config.destination_path == feed.destination_path
+            }) {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    "address book feed must have a unique port and destination path",
+                );
+                return Err(Error::InvalidData);
+            }
         }
 
         Ok(Self {
             address_book: config.address_book,
             allow_local: config.allow_local,
+            bandwidth: config.bandwidth.map(|config| emissary_core::BandwidthConfig {
+                inbound: config.inbound,
+                outbound: config.outbound,
+                share: config.share,
+            }),
             base_path,
+            blocklist: config.blocklist,
             caps: config.caps,
             client_tunnels: config.client_tunnels.unwrap_or(Vec::new()),
             exploratory: config.exploratory.map(|config| emissary_core::ExploratoryConfig {
@@ -884,15 +1242,20 @@ impl Config {
                 port: config.port,
                 host: config.host.unwrap_or(String::from("127.0.0.1")),
             }),
+            i2pcontrol: config.i2pcontrol,
             insecure_tunnels: config.insecure_tunnels,
             log: config.log,
             metrics: config
                 .metrics
                 .map(|config| emissary_core::MetricsConfig { port: config.port }),
             net_id: config.net_id,
+            news: config.news,
             ntcp2_config: config.ntcp2.map(|config| emissary_core::Ntcp2Config {
                 port: config.port,
-                host: config.host,
+                ipv4_host: config.host,
+                ipv6_host: config.ipv6_host,
+                ipv4: config.ipv4.unwrap_or(true),
+                ipv6: config.ipv6.unwrap_or(false),
                 publish: config.publish.unwrap_or(false),
                 key: ntcp2_key,
                 iv: ntcp2_iv,
@@ -913,7 +1276,10 @@ impl Config {
             socks_proxy: config.socks_proxy,
             ssu2_config: config.ssu2.map(|config| emissary_core::Ssu2Config {
                 port: config.port,
-                host: config.host,
+                ipv4_host: config.host,
+                ipv6_host: config.ipv6_host,
+                ipv4: config.ipv4.unwrap_or(true),
+                ipv6: config.ipv6.unwrap_or(false),
                 publish: config.publish.unwrap_or(false),
                 static_key: ssu2_static_key,
                 intro_key: ssu2_intro_key,
@@ -1086,6 +1452,8 @@ impl Config {
                     self.reseed = Some(ReseedConfig {
                         hosts: Some(hosts.clone()),
                         reseed_threshold: 25usize,
+                        proxy: None,
+                        certificates: None,
                     });
                 }
                 Some(config) => {
@@ -1100,6 +1468,8 @@ impl Config {
                     self.reseed = Some(ReseedConfig {
                         hosts: None,
                         reseed_threshold: threshold,
+                        proxy: None,
+                        certificates: None,
                     });
                 }
                 Some(config) => {
@@ -1108,6 +1478,22 @@ impl Config {
             }
         }
 
+        if let Some(proxy) = &arguments.reseed.reseed_proxy {
+            match &mut self.reseed {
+                None => {
+                    self.reseed = Some(ReseedConfig {
+                        hosts: None,
+                        reseed_threshold: 25usize,
+                        proxy: Some(proxy.clone()),
+                        certificates: None,
+                    });
+                }
+                Some(config) => {
+                    config.proxy = Some(proxy.clone());
+                }
+            }
+        }
+
         if let Some(true) = arguments.reseed.disable_reseed {
             self.reseed = None;
         }
@@ -1145,6 +1531,7 @@ impl Config {
                     port: *port,
                     host: host.clone(),
                     outproxy: http_outproxy.clone(),
+                    jump_services: None,
                 });
             }
             _ => {}
@@ -1156,6 +1543,7 @@ impl Config {
                 SocksProxyOptions {
                     socks_proxy_port,
                     socks_proxy_host,
+                    socks_outproxy,
                 },
             ) => {
                 if let Some(port) = socks_proxy_port {
@@ -1165,17 +1553,24 @@ impl Config {
                 if let Some(host) = &socks_proxy_host {
                     config.host = host.clone();
                 }
+
+                if let Some(outproxy) = socks_outproxy {
+                    config.outproxy = Some(outproxy.clone());
+                }
             }
             (
                 None,
                 SocksProxyOptions {
                     socks_proxy_port: Some(port),
                     socks_proxy_host: Some(host),
+                    socks_outproxy,
                 },
             ) => {
                 self.socks_proxy = Some(SocksProxyConfig {
                     port: *port,
                     host: host.clone(),
+                    outproxy: socks_outproxy.clone(),
+                    users: None,
                 });
             }
             _ => {}
@@ -1199,6 +1594,17 @@ impl Config {
             }),
         };
 
+        if arguments.bandwidth.bandwidth_inbound.is_some()
+            || arguments.bandwidth.bandwidth_outbound.is_some()
+            || arguments.bandwidth.bandwidth_share.is_some()
+        {
+            let config = self.bandwidth.get_or_insert_with(Default::default);
+
+            config.inbound = arguments.bandwidth.bandwidth_inbound.or(config.inbound);
+            config.outbound = arguments.bandwidth.bandwidth_outbound.or(config.outbound);
+            config.share = arguments.bandwidth.bandwidth_share.or(config.share);
+        }
+
         if let Some(max_tunnels) = arguments.transit.max_transit_tunnels {
             self.transit = Some(emissary_core::TransitConfig {
                 max_tunnels: Some(max_tunnels),
@@ -1258,7 +1664,8 @@ impl Config {
 #[cfg(test)]
 mod tests {
     use crate::cli::{
-        MetricsOptions, PortForwardingOptions, ReseedOptions, TransitOptions, TunnelOptions,
+        BandwidthOptions, MetricsOptions, PortForwardingOptions, ReseedOptions, TransitOptions,
+        TunnelOptions,
     };
 
     use super::*;
@@ -1298,6 +1705,8 @@ mod tests {
                 force_reseed: None,
                 reseed_threshold: None,
                 disable_force_ipv4: None,
+                reseed_file: None,
+                reseed_proxy: None,
             },
             metrics: MetricsOptions {
                 metrics_server_port: None,
@@ -1311,6 +1720,12 @@ mod tests {
             socks_proxy: SocksProxyOptions {
                 socks_proxy_port: None,
                 socks_proxy_host: None,
+                socks_outproxy: None,
+            },
+            bandwidth: BandwidthOptions {
+                bandwidth_inbound: None,
+                bandwidth_outbound: None,
+                bandwidth_share: None,
             },
             transit: TransitOptions {
                 max_transit_tunnels: None,
@@ -1332,7 +1747,7 @@ mod tests {
         assert!(config.routers.is_empty());
         assert_eq!(config.static_key.len(), 32);
         assert_eq!(config.signing_key.len(), 32);
-        assert_eq!(config.ntcp2_config.as_ref().unwrap().host, None);
+        assert_eq!(config.ntcp2_config.as_ref().unwrap().ipv4_host, None);
 
         // ensure ntcp2 port is within correct range and not any of the reserved ports
         {
@@ -1377,8 +1792,8 @@ mod tests {
             ntcp2_config.as_ref().unwrap().port
         );
         assert_eq!(
-            config.ntcp2_config.as_ref().unwrap().host,
-            ntcp2_config.as_ref().unwrap().host
+            config.ntcp2_config.as_ref().unwrap().ipv4_host,
+            ntcp2_config.as_ref().unwrap().ipv4_host
         );
         assert_eq!(
             config.ntcp2_config.as_ref().unwrap().key,
@@ -1414,6 +1829,9 @@ mod tests {
             ntcp2: Some(Ntcp2Config {
                 port: 1337u16,
                 host: None,
+                ipv6_host: None,
+                ipv4: None,
+                ipv6: None,
                 publish: None,
             }),
             ..Default::default()
@@ -1476,6 +1894,7 @@ mod tests {
                     port: 1337,
                     destination: "hello".to_string(),
                     destination_port: None,
+                    kind: ClientTunnelKind::Tcp,
                 },
                 ClientTunnelConfig {
                     name: "tunnel".to_string(),
@@ -1483,6 +1902,7 @@ mod tests {
                     port: 1338,
                     destination: "hello".to_string(),
                     destination_port: None,
+                    kind: ClientTunnelKind::Tcp,
                 },
             ]),
             ..Default::default()
@@ -1511,6 +1931,7 @@ mod tests {
                     port: 1337,
                     destination: "hello".to_string(),
                     destination_port: None,
+                    kind: ClientTunnelKind::Tcp,
                 },
                 ClientTunnelConfig {
                     name: "tunnel2".to_string(),
@@ -1518,6 +1939,7 @@ mod tests {
                     port: 1337,
                     destination: "hello".to_string(),
                     destination_port: None,
+                    kind: ClientTunnelKind::Tcp,
                 },
             ]),
             ..Default::default()
@@ -1532,4 +1954,241 @@ mod tests {
             _ => panic!("invalid result"),
         }
     }
+
+    #[test]
+    fn socks_proxy_users_with_same_username() {
+        let dir = tempdir().unwrap();
+
+        let config = EmissaryConfig {
+            socks_proxy: Some(SocksProxyConfig {
+                port: 4447,
+                host: "127.0.0.1".to_string(),
+                outproxy: None,
+                users: Some(vec![
+                    SocksUserConfig {
+                        username: "user".to_string(),
+                        password: "password1".to_string(),
+                    },
+                    SocksUserConfig {
+                        username: "user".to_string(),
+                        password: "password2".to_string(),
+                    },
+                ]),
+            }),
+            ..Default::default()
+        };
+
+        let config = toml::to_string(&config).expect("to succeed");
+        let mut file = fs::File::create(dir.path().to_owned().join("router.toml")).unwrap();
+        file.write_all(config.as_bytes()).unwrap();
+
+        match Config::parse(Some(dir.path().to_owned()), &make_arguments()) {
+            Err(Error::InvalidData) => {}
+            _ => panic!("invalid result"),
+        }
+    }
+
+    #[test]
+    fn rate_limit_for_tcp_server_tunnel() {
+        let dir = tempdir().unwrap();
+
+        let config = EmissaryConfig {
+            server_tunnels: Some(vec![ServerTunnelConfig {
+                name: "server".to_string(),
+                port: 8080,
+                destination_path: "server.b64".to_string(),
+                kind: ServerTunnelKind::Tcp,
+                host: None,
+                requests_per_minute: Some(10),
+                meta_lease_set: None,
+            }]),
+            ..Default::default()
+        };
+
+        let config = toml::to_string(&config).expect("to succeed");
+        let mut file = fs::File::create(dir.path().to_owned().join("router.toml")).unwrap();
+        file.write_all(config.as_bytes()).unwrap();
+
+        match Config::parse(Some(dir.path().to_owned()), &make_arguments()) {
+            Err(Error::InvalidData) => {}
+            _ => panic!("invalid result"),
+        }
+    }
+
+    #[test]
+    fn blocklist_feeds_without_certificates() {
+        let dir = tempdir().unwrap();
+        let mut file = fs::File::create(dir.path().to_owned().join("router.toml")).unwrap();
+        file.write_all(
+            "[blocklist]\n\
+            feeds = [\"http://blocklist.i2p/blocklist.su3\"]\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        match Config::parse(Some(dir.path().to_owned()), &make_arguments()) {
+            Err(Error::InvalidData) => {}
+            _ => panic!("invalid result"),
+        }
+    }
+
+    #[test]
+    fn news_without_certificates() {
+        let dir = tempdir().unwrap();
+        let mut file = fs::File::create(dir.path().to_owned().join("router.toml")).unwrap();
+        file.write_all(
+            "[news]\n\
+            url = \"http://news.i2p/news.su3\"\n\
+            certificates = []\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        match Config::parse(Some(dir.path().to_owned()), &make_arguments()) {
+            Err(Error::InvalidData) => {}
+            _ => panic!("invalid result"),
+        }
+    }
+
+    #[test]
+    fn http_server_tunnel() {
+        let dir = tempdir().unwrap();
+        let mut file = fs::File::create(dir.path().to_owned().join("router.toml")).unwrap();
+        file.write_all(
+            "[[server-tunnels]]\n\
+            name = \"server\"\n\
+            port = 8080\n\
+            destination_path = \"server.b64\"\n\
+            type = \"http\"\n\
+            host = \"example.i2p\"\n\
+            requests_per_minute = 30\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        let config = Config::parse(Some(dir.path().to_owned()), &make_arguments()).unwrap();
+        assert_eq!(config.server_tunnels.len(), 1);
+        assert_eq!(config.server_tunnels[0].kind, ServerTunnelKind::Http);
+        assert_eq!(
+            config.server_tunnels[0].host.as_deref(),
+            Some("example.i2p")
+        );
+        assert_eq!(config.server_tunnels[0].requests_per_minute, Some(30));
+    }
+
+    #[test]
+    fn udp_client_tunnel_with_destination_port() {
+        let dir = tempdir().unwrap();
+
+        let config = EmissaryConfig {
+            client_tunnels: Some(vec![ClientTunnelConfig {
+                name: "tunnel".to_string(),
+                address: None,
+                port: 1337,
+                destination: "host.i2p".to_string(),
+                destination_port: Some(1338),
+                kind: ClientTunnelKind::Udp,
+            }]),
+            ..Default::default()
+        };
+
+        let config = toml::to_string(&config).expect("to succeed");
+        let mut file = fs::File::create(dir.path().to_owned().join("router.toml")).unwrap();
+        file.write_all(config.as_bytes()).unwrap();
+
+        match Config::parse(Some(dir.path().to_owned()), &make_arguments()) {
+            Err(Error::InvalidData) => {}
+            _ => panic!("invalid result"),
+        }
+    }
+
+    #[test]
+    fn reseed_proxy_from_cli() {
+        let dir = tempdir().unwrap();
+        let mut arguments = make_arguments();
+        arguments.reseed.reseed_proxy = Some(String::from("socks5h://127.0.0.1:9050"));
+
+        let config = Config::parse(Some(dir.path().to_owned()), &arguments).unwrap();
+        let reseed = config.reseed.unwrap();
+
+        assert_eq!(reseed.proxy.as_deref(), Some("socks5h://127.0.0.1:9050"));
+        assert_eq!(reseed.reseed_threshold, 25usize);
+    }
+
+    #[test]
+    fn settings_saved() {
+        let dir = tempdir().unwrap();
+        let config = Config::parse(Some(dir.path().to_owned()), &make_arguments()).unwrap();
+        assert!(config.bandwidth.is_none());
+        assert!(config.i2pcontrol.is_none());
+
+        let bandwidth = emissary_core::BandwidthConfig {
+            inbound: Some(512),
+            outbound: None,
+            share: Some(50),
+        };
+        let i2pcontrol = I2pControlConfig {
+            host: None,
+            port: 7651,
+            password: Some(String::from("password")),
+            allowed_hosts: None,
+        };
+        Config::save_bandwidth(dir.path(), Some(&bandwidth)).unwrap();
+        Config::save_i2pcontrol(dir.path(), &i2pcontrol).unwrap();
+
+        let config = Config::parse(Some(dir.path().to_owned()), &make_arguments()).unwrap();
+        assert_eq!(config.bandwidth, Some(bandwidth));
+        assert_eq!(config.i2pcontrol, Some(i2pcontrol));
+        assert!(config.http_proxy.is_some());
+    }
+
+    #[test]
+    fn settings_saved_in_place() {
+        let dir = tempdir().unwrap();
+        let contents = "# router configuration\n\
+            log = \"info\"\n\n\
+            [bandwidth]\n\
+            # limit of inbound traffic\n\
+            inbound = 1024 # KBps\n\
+            outbound = 2048\n\n\
+            [ntcp2]\n\
+            port = 8888\n";
+        fs::write(dir.path().join("router.toml"), contents).unwrap();
+
+        Config::save_bandwidth(
+            dir.path(),
+            Some(&emissary_core::BandwidthConfig {
+                inbound: Some(512),
+                outbound: None,
+                share: Some(50),
+            }),
+        )
+        .unwrap();
+
+        // only the changed keys are modified, comments and other settings are preserved
+        let config = fs::read_to_string(dir.path().join("router.toml")).unwrap();
+        assert_eq!(
+            config,
+            "# router configuration\n\
+            log = \"info\"\n\n\
+            [bandwidth]\n\
+            # limit of inbound traffic\n\
+            inbound = 512 # KBps\n\
+            share = 50\n\n\
+            [ntcp2]\n\
+            port = 8888\n"
+        );
+
+        // removing the limits removes the whole table
+        Config::save_bandwidth(dir.path(), None).unwrap();
+
+        let config = fs::read_to_string(dir.path().join("router.toml")).unwrap();
+        assert_eq!(
+            config,
+            "# router configuration\n\
+            log = \"info\"\n\n\
+            [ntcp2]\n\
+            port = 8888\n"
+        );
+    }
 }
@@ -0,0 +1,883 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! I2PControl server.
+//!
+//! Implements the JSON-RPC 2.0 based [I2PControl](https://geti2p.net/en/docs/api/i2pcontrol) API
+//! over plain HTTP, allowing monitoring and orchestration tools to query the status of the router,
+//! change bandwidth limits, add and remove tunnels, reseed the router and shut it down.
+//!
+//! TLS is not supported, so the password and tokens are sent in plaintext. The server is meant to
+//! be exposed only on a loopback address and remote access should go through an encrypted tunnel,
+//! such as SSH port forwarding.
+
+use crate::{
+    config::{I2pControlConfig, ReseedConfig},
+    tunnel::TunnelManagerHandle,
+};
+
+use emissary_core::{router::RouterHandle, BandwidthConfig};
+use emissary_util::runtime::tokio::Runtime;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpListener,
+};
+
+use std::{collections::HashSet, io, net::IpAddr, path::PathBuf, sync::Arc, time::Duration};
+
+pub use rpc::TransportInfo;
+
+mod rpc;
+
+use rpc::RpcHandler;
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::i2pcontrol";
+
+/// Maximum size of a request, including headers.
+const MAX_REQUEST_SIZE: usize = 16384usize;
+
+/// How long is the client given to send the request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often are bandwidth samples taken.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Check if `host`, the value of a `Host` header or an origin, refers to an allowed host.
+///
+/// Requests are accepted only if they're addressed to `localhost`, a loopback address or one of
+/// the `allowed` hosts so that a web page loaded in a browser running on the same host cannot
+/// access the API through DNS rebinding.
+fn is_host_allowed(host: &str, allowed: &HashSet<String>) -> bool {
+    let host = host.split_once("://").map_or(host, |(_, host)| host).to_ascii_lowercase();
+    let host = match host.strip_prefix('[') {
+        Some(host) => match host.split_once(']') {
+            Some((host, _)) => host,
+            None => return false,
+        },
+        None => host.rsplit_once(':').map_or(host.as_str(), |(host, _)| host),
+    };
+
+    host == "localhost"
+        || host.parse::<IpAddr>().is_ok_and(|address| address.is_loopback())
+        || allowed.contains(host)
+}
+
+/// I2PControl server.
+pub struct I2pControlServer {
+    /// Hosts the server can be accessed through, in addition to `localhost` and loopback
+    /// addresses.
+    allowed_hosts: Arc<HashSet<String>>,
+
+    /// JSON-RPC request handler.
+    handler: Arc<RpcHandler>,
+
+    /// TCP listener.
+    listener: TcpListener,
+}
+
+impl I2pControlServer {
+    /// Create new [`I2pControlServer`].
+    pub async fn new(
+        base_path: PathBuf,
+        config: I2pControlConfig,
+        handle: RouterHandle<Runtime>,
+        tunnels: Option<TunnelManagerHandle>,
+        bandwidth: Option<BandwidthConfig>,
+        reseed: Option<ReseedConfig>,
+        ntcp2: TransportInfo,
+        ssu2: TransportInfo,
+    ) -> io::Result<Self> {
+        let listener =
+            TcpListener::bind((config.host.as_deref().unwrap_or("127.0.0.1"), config.port)).await?;
+
+        // the default password is publicly known so the server is only reachable from the local
+        // host unless a password has been configured
+        if config.password.is_none() {
+            if !listener.local_addr()?.ip().is_loopback() {
+                tracing::error!(
+                    target: LOG_TARGET,
+                    host = ?config.host,
+                    "i2pcontrol password must be set when binding to a non-loopback address",
+                );
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "password not set for non-loopback address",
+                ));
+            }
+
+            tracing::warn!(
+                target: LOG_TARGET,
+                "i2pcontrol password not set, using the default password",
+            );
+        } else if !listener.local_addr()?.ip().is_loopback() {
+            tracing::warn!(
+                target: LOG_TARGET,
+                host = ?config.host,
+                "i2pcontrol is served over plain http on a non-loopback address",
+            );
+        }
+
+        // the address the server is bound to is always allowed
+        let allowed_hosts = config
+            .allowed_hosts
+            .iter()
+            .flatten()
+            .chain(config.host.iter())
+            .map(|host| host.to_ascii_lowercase())
+            .collect();
+
+        Ok(Self {
+            allowed_hosts: Arc::new(allowed_hosts),
+            handler: Arc::new(RpcHandler::new(
+                base_path, config, handle, tunnels, bandwidth, reseed, ntcp2, ssu2,
+            )),
+            listener,
+        })
+    }
+
+    /// Read JSON-RPC request from `stream` and respond to it.
+    ///
+    /// Requests that are not addressed to an allowed host or that originate from a web page of
+    /// another host are rejected. If the request resulted in a command, it's executed after the
+    /// response has been sent.
+    async fn on_connection<S: AsyncRead + AsyncWrite + Unpin>(
+        mut stream: S,
+        handler: Arc<RpcHandler>,
+        allowed_hosts: Arc<HashSet<String>>,
+    ) -> io::Result<()> {
+        let mut buffer = vec![0u8; MAX_REQUEST_SIZE];
+        let mut nread = 0usize;
+
+        let (method, path, body_start, content_length, allowed) = loop {
+            if nread == MAX_REQUEST_SIZE {
+                return Err(io::Error::other("request too large"));
+            }
+
+            match stream.read(&mut buffer[nread..]).await? {
+                0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+                read => nread += read,
+            }
+
+            let mut headers = [httparse::EMPTY_HEADER; 64];
+            let mut request = httparse::Request::new(&mut headers);
+
+            match request.parse(&buffer[..nread]) {
+                Ok(httparse::Status::Complete(body_start)) => {
+                    let content_length = request
+                        .headers
+                        .iter()
+                        .find(|header| header.name.eq_ignore_ascii_case("content-length"))
+                        .and_then(|header| std::str::from_utf8(header.value).ok())
+                        .and_then(|value| value.trim().parse::<usize>().ok())
+                        .unwrap_or(0usize);
+
+                    // `Host` is required and `Origin`, if present, must also be an allowed host
+                    let header = |name: &str| {
+                        request
+                            .headers
+                            .iter()
+                            .find(|header| header.name.eq_ignore_ascii_case(name))
+                            .map(|header| std::str::from_utf8(header.value).ok())
+                    };
+                    let allowed = match (header("host"), header("origin")) {
+                        (Some(Some(host)), None) => is_host_allowed(host.trim(), &allowed_hosts),
+                        (Some(Some(host)), Some(Some(origin))) =>
+                            is_host_allowed(host.trim(), &allowed_hosts)
+                                && is_host_allowed(origin.trim(), &allowed_hosts),
+                        _ => false,
+                    };
+
+                    break (
+                        request.method.unwrap_or_default().to_string(),
+                        request.path.unwrap_or_default().to_string(),
+                        body_start,
+                        content_length,
+                        allowed,
+                    );
+                }
+                Ok(httparse::Status::Partial) => {}
+                Err(error) => return Err(io::Error::other(error)),
+            }
+        };
+        let body_end = body_start
+            .checked_add(content_length)
+            .filter(|body_end| *body_end <= MAX_REQUEST_SIZE)
+            .ok_or_else(|| io::Error::other("request too large"))?;
+
+        while nread < body_end {
+            match stream.read(&mut buffer[nread..body_end]).await? {
+                0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+                read => nread += read,
+            }
+        }
+        let path = path.split_once('?').map_or(path.as_str(), |(path, _)| path);
+
+        let (status, body, command) = match (method.as_str(), path) {
+            _ if !allowed => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    "request from disallowed host or origin",
+                );
+                ("403 Forbidden", String::new(), None)
+            }
+            ("POST", "/" | "/jsonrpc" | "/jsonrpc/") => {
+                let (response, command) = handler.on_request(&buffer[body_start..body_end]);
+                ("200 OK", response.to_string(), command)
+            }
+            ("POST", _) => ("404 Not Found", String::new(), None),
+            _ => ("405 Method Not Allowed", String::new(), None),
+        };
+
+        let response = format!(
+            "HTTP/1.1 {status}\r\nConnection: close\r\nContent-Type: application/json\r\n\
+            Content-Length: {}\r\n\r\n{body}",
+            body.len(),
+        );
+
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await?;
+
+        if let Some(command) = command {
+            handler.execute(command);
+        }
+
+        Ok(())
+    }
+
+    /// Run the event loop of [`I2pControlServer`].
+    pub async fn run(self) {
+        tracing::info!(
+            target: LOG_TARGET,
+            address = ?self.listener.local_addr().ok(),
+            "i2pcontrol server started",
+        );
+
+        let mut sample_timer = tokio::time::interval(SAMPLE_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = sample_timer.tick() => self.handler.sample(),
+                connection = self.listener.accept() => {
+                    let (stream, _) = match connection {
+                        Ok(connection) => connection,
+                        Err(error) => {
+                            tracing::warn!(
+                                target: LOG_TARGET,
+                                ?error,
+                                "failed to accept connection",
+                            );
+                            continue;
+                        }
+                    };
+                    let handler = Arc::clone(&self.handler);
+                    let allowed_hosts = Arc::clone(&self.allowed_hosts);
+
+                    tokio::spawn(async move {
+                        match tokio::time::timeout(
+                            REQUEST_TIMEOUT,
+                            Self::on_connection(stream, handler, allowed_hosts),
+                        )
+                        .await
+                        {
+                            Ok(Ok(())) => {}
+                            Ok(Err(error)) => tracing::debug!(
+                                target: LOG_TARGET,
+                                ?error,
+                                "failed to handle request",
+                            ),
+                            Err(_) => tracing::debug!(
+                                target: LOG_TARGET,
+                                "request timed out",
+                            ),
+                        }
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::ServerTunnelKind, tunnel::TunnelCommand};
+    use emissary_core::{router::Router, Ntcp2Config};
+    use serde_json::{json, Value};
+    use std::net::Ipv4Addr;
+    use tempfile::{tempdir, TempDir};
+
+    async fn make_handler(
+        tunnels: Option<TunnelManagerHandle>,
+    ) -> (Arc<RpcHandler>, Router<Runtime>, TempDir) {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("router.toml"), "").unwrap();
+
+        let (router, _events, _) = Router::<Runtime>::new(
+            emissary_core::Config {
+                ntcp2: Some(Ntcp2Config {
+                    port: 0u16,
+                    ipv4_host: Some(Ipv4Addr::LOCALHOST),
+                    ipv6_host: None,
+                    ipv4: true,
+                    ipv6: false,
+                    publish: false,
+                    key: [1u8; 32],
+                    iv: [2u8; 16],
+                }),
+                ..Default::default()
+            },
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let ntcp2 = TransportInfo {
+            ipv4_host: Some(Ipv4Addr::LOCALHOST),
+            ipv4_port: Some(0u16),
+            ipv6_host: None,
+            ipv6_port: None,
+        }
+        .with_port(router.protocol_address_info().ntcp2_port);
+        let handler = RpcHandler::new(
+            dir.path().to_path_buf(),
+            I2pControlConfig {
+                host: None,
+                port: 7650,
+                password: None,
+                allowed_hosts: None,
+            },
+            router.handle(),
+            tunnels,
+            None,
+            None,
+            ntcp2,
+            TransportInfo::default(),
+        );
+
+        (Arc::new(handler), router, dir)
+    }
+
+    fn call(handler: &RpcHandler, method: &str, params: Value) -> Value {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1337,
+            "method": method,
+            "params": params,
+        });
+
+        handler.on_request(request.to_string().as_bytes()).0
+    }
+
+    fn authenticate(handler: &RpcHandler, password: &str) -> String {
+        let response = call(
+            handler,
+            "Authenticate",
+            json!({ "API": 1, "Password": password }),
+        );
+        assert_eq!(response["id"], 1337);
+        assert_eq!(response["result"]["API"], 1);
+
+        response["result"]["Token"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn authentication() {
+        let (handler, _router, _dir) = make_handler(None).await;
+
+        let response = call(&handler, "Authenticate", json!({ "Password": "itoopie" }));
+        assert_eq!(response["error"]["code"], -32005);
+
+        let response = call(
+            &handler,
+            "Authenticate",
+            json!({ "API": 2, "Password": "itoopie" }),
+        );
+        assert_eq!(response["error"]["code"], -32006);
+
+        let response = call(
+            &handler,
+            "Authenticate",
+            json!({ "API": 1, "Password": "hunter2" }),
+        );
+        assert_eq!(response["error"]["code"], -32001);
+
+        let token = authenticate(&handler, "itoopie");
+        let response = call(&handler, "Echo", json!({ "Echo": "hello", "Token": token }));
+        assert_eq!(response["result"]["Result"], "hello");
+
+        let response = call(&handler, "Echo", json!({ "Echo": "hello" }));
+        assert_eq!(response["error"]["code"], -32002);
+
+        let response = call(
+            &handler,
+            "Echo",
+            json!({ "Echo": "hello", "Token": "token" }),
+        );
+        assert_eq!(response["error"]["code"], -32003);
+    }
+
+    #[tokio::test]
+    async fn invalid_requests() {
+        let (handler, _router, _dir) = make_handler(None).await;
+        let token = authenticate(&handler, "itoopie");
+
+        let (response, _) = handler.on_request(b"{\"jsonrpc\":");
+        assert_eq!(response["error"]["code"], -32700);
+
+        let (response, _) = handler.on_request(b"[1, 2, 3]");
+        assert_eq!(response["error"]["code"], -32600);
+
+        let (response, _) = handler.on_request(b"{\"id\":1,\"method\":\"Echo\"}");
+        assert_eq!(response["error"]["code"], -32600);
+
+        let response = call(&handler, "GetRate", json!({ "Token": token }));
+        assert_eq!(response["error"]["code"], -32601);
+
+        let response = call(
+            &handler,
+            "RouterInfo",
+            json!({ "Token": token, "i2p.router.unknown": null }),
+        );
+        assert_eq!(response["error"]["code"], -32602);
+
+        let response = call(
+            &handler,
+            "RouterManager",
+            json!({ "Token": token, "Restart": null }),
+        );
+        assert_eq!(response["error"]["code"], -32602);
+    }
+
+    #[tokio::test]
+    async fn router_info() {
+        let (handler, _router, _dir) = make_handler(None).await;
+        let token = authenticate(&handler, "itoopie");
+
+        handler.sample();
+        handler.sample();
+
+        let response = call(
+            &handler,
+            "RouterInfo",
+            json!({
+                "Token": token,
+                "i2p.router.status": null,
+                "i2p.router.version": null,
+                "i2p.router.net.bw.inbound.15s": null,
+                "i2p.router.net.status": null,
+                "i2p.router.netdb.knownpeers": null,
+                "i2p.router.netdb.isreseeding": null,
+            }),
+        );
+        let result = &response["result"];
+
+        assert_eq!(result["i2p.router.status"], "Running");
+        assert_eq!(result["i2p.router.version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(result["i2p.router.net.bw.inbound.15s"], 0.0);
+        assert_eq!(result["i2p.router.net.status"], 1);
+        assert_eq!(result["i2p.router.netdb.knownpeers"], 0);
+        assert_eq!(result["i2p.router.netdb.isreseeding"], false);
+        assert!(result.get("Token").is_none());
+    }
+
+    #[tokio::test]
+    async fn bandwidth_updated() {
+        let (handler, router, dir) = make_handler(None).await;
+        let token = authenticate(&handler, "itoopie");
+
+        let response = call(
+            &handler,
+            "NetworkSetting",
+            json!({
+                "Token": token,
+                "i2p.router.net.bw.in": "512",
+                "i2p.router.net.bw.share": 50,
+            }),
+        );
+        assert_eq!(response["result"]["SettingsSaved"], true);
+        assert_eq!(response["result"]["RestartNeeded"], false);
+
+        let response = call(
+            &handler,
+            "NetworkSetting",
+            json!({
+                "Token": token,
+                "i2p.router.net.bw.in": null,
+                "i2p.router.net.bw.out": null,
+                "i2p.router.net.bw.share": null,
+                "i2p.router.net.ntcp.port": null,
+                "i2p.router.net.ntcp.hostname": null,
+                "i2p.router.net.ntcp.ipv6.port": null,
+            }),
+        );
+        let result = &response["result"];
+
+        assert_eq!(result["i2p.router.net.bw.in"], "512");
+        assert_eq!(result["i2p.router.net.bw.out"], Value::Null);
+        assert_eq!(result["i2p.router.net.bw.share"], "50");
+        assert_eq!(
+            result["i2p.router.net.ntcp.port"],
+            router.protocol_address_info().ntcp2_port.unwrap().to_string(),
+        );
+        assert_eq!(result["i2p.router.net.ntcp.hostname"], "127.0.0.1");
+        assert_eq!(result["i2p.router.net.ntcp.ipv6.port"], Value::Null);
+        assert_eq!(result["SettingsSaved"], false);
+
+        let config = std::fs::read_to_string(dir.path().join("router.toml")).unwrap();
+        assert!(config.contains("inbound = 512"));
+
+        // invalid share and read-only settings are rejected
+        for params in [
+            json!({ "Token": token, "i2p.router.net.bw.share": "150" }),
+            json!({ "Token": token, "i2p.router.net.ntcp.port": "8888" }),
+            json!({ "Token": token, "i2p.router.net.ssu.ipv6.port": "8888" }),
+        ] {
+            let response = call(&handler, "NetworkSetting", params);
+            assert_eq!(response["error"]["code"], -32602);
+        }
+    }
+
+    #[tokio::test]
+    async fn password_changed() {
+        let (handler, _router, dir) = make_handler(None).await;
+        let token = authenticate(&handler, "itoopie");
+
+        // non-loopback address cannot be used with the default password
+        let response = call(
+            &handler,
+            "I2PControl",
+            json!({ "Token": token, "i2pcontrol.address": "0.0.0.0" }),
+        );
+        assert_eq!(response["error"]["code"], -32602);
+
+        let response = call(
+            &handler,
+            "I2PControl",
+            json!({ "Token": token, "i2pcontrol.password": "hunter2" }),
+        );
+        assert_eq!(response["result"]["SettingsSaved"], true);
+        assert_eq!(response["result"]["RestartNeeded"], false);
+
+        // old token has been invalidated
+        let response = call(&handler, "Echo", json!({ "Echo": "hello", "Token": token }));
+        assert_eq!(response["error"]["code"], -32003);
+
+        let response = call(
+            &handler,
+            "Authenticate",
+            json!({ "API": 1, "Password": "itoopie" }),
+        );
+        assert_eq!(response["error"]["code"], -32001);
+
+        let token = authenticate(&handler, "hunter2");
+        let response = call(
+            &handler,
+            "I2PControl",
+            json!({ "Token": token, "i2pcontrol.port": "7651" }),
+        );
+        assert_eq!(response["result"]["SettingsSaved"], true);
+        assert_eq!(response["result"]["RestartNeeded"], true);
+
+        let config = std::fs::read_to_string(dir.path().join("router.toml")).unwrap();
+        assert!(config.contains("[i2pcontrol]"));
+        assert!(config.contains("port = 7651"));
+    }
+
+    #[tokio::test]
+    async fn tunnels_added_and_removed() {
+        let (tunnels, mut client_rx, mut server_rx) = TunnelManagerHandle::new(&[], &[]);
+        let (handler, _router, _dir) = make_handler(Some(tunnels)).await;
+        let token = authenticate(&handler, "itoopie");
+
+        let response = call(
+            &handler,
+            "emissary.TunnelManager",
+            json!({
+                "Token": token,
+                "AddClientTunnel": {
+                    "name": "irc",
+                    "port": 6668,
+                    "destination": "irc.postman.i2p",
+                },
+                "AddServerTunnel": {
+                    "name": "web",
+                    "port": 8080,
+                    "destination_path": "web.key",
+                    "type": "http",
+                },
+            }),
+        );
+        assert!(response.get("error").is_none());
+
+        match client_rx.try_recv().unwrap() {
+            TunnelCommand::Add(config) => {
+                assert_eq!(config.name, "irc");
+                assert_eq!(config.destination, "irc.postman.i2p");
+            }
+            command => panic!("invalid command: {command:?}"),
+        }
+        match server_rx.try_recv().unwrap() {
+            TunnelCommand::Add(config) => {
+                assert_eq!(config.name, "web");
+                assert_eq!(config.kind, ServerTunnelKind::Http);
+            }
+            command => panic!("invalid command: {command:?}"),
+        }
+
+        let response = call(
+            &handler,
+            "emissary.TunnelManager",
+            json!({ "Token": token, "Tunnels": null }),
+        );
+        assert_eq!(
+            response["result"]["Tunnels"],
+            json!([
+                { "Name": "irc", "Type": "client" },
+                { "Name": "web", "Type": "server" },
+            ]),
+        );
+
+        // duplicate names, paths outside of the base path and invalid configurations are rejected
+        for params in [
+            json!({
+                "Token": token,
+                "AddServerTunnel": { "name": "irc", "port": 8081, "destination_path": "irc.key" },
+            }),
+            json!({
+                "Token": token,
+                "AddServerTunnel": { "name": "ssh", "port": 22, "destination_path": "../ssh.key" },
+            }),
+            json!({
+                "Token": token,
+                "AddClientTunnel": {
+                    "name": "dns",
+                    "port": 5353,
+                    "destination": "dns.i2p",
+                    "destination_port": 53,
+                    "type": "udp",
+                },
+            }),
+            json!({ "Token": token, "AddClientTunnel": { "name": "dns" } }),
+            json!({ "Token": token, "RemoveTunnel": "ssh" }),
+        ] {
+            let response = call(&handler, "emissary.TunnelManager", params);
+            assert_eq!(response["error"]["code"], -32602);
+        }
+        assert!(client_rx.try_recv().is_err());
+        assert!(server_rx.try_recv().is_err());
+
+        let response = call(
+            &handler,
+            "emissary.TunnelManager",
+            json!({ "Token": token, "RemoveTunnel": "irc" }),
+        );
+        assert!(response.get("error").is_none());
+
+        match client_rx.try_recv().unwrap() {
+            TunnelCommand::Remove(name) => assert_eq!(name, "irc"),
+            command => panic!("invalid command: {command:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn tunnels_unavailable_without_sam() {
+        let (handler, _router, _dir) = make_handler(None).await;
+        let token = authenticate(&handler, "itoopie");
+
+        let response = call(
+            &handler,
+            "emissary.TunnelManager",
+            json!({ "Token": token, "Tunnels": null }),
+        );
+        assert_eq!(response["error"]["code"], -32602);
+    }
+
+    #[tokio::test]
+    async fn default_password_requires_loopback() {
+        let (_handler, router, dir) = make_handler(None).await;
+        let server = |host: &str, password: Option<&str>| {
+            I2pControlServer::new(
+                dir.path().to_path_buf(),
+                I2pControlConfig {
+                    host: Some(host.to_string()),
+                    port: 0u16,
+                    password: password.map(ToString::to_string),
+                    allowed_hosts: None,
+                },
+                router.handle(),
+                None,
+                None,
+                None,
+                TransportInfo::default(),
+                TransportInfo::default(),
+            )
+        };
+
+        assert!(server("127.0.0.1", None).await.is_ok());
+        assert_eq!(
+            server("0.0.0.0", None).await.err().unwrap().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert!(server("0.0.0.0", Some("hunter2")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn shutdown_over_http() {
+        let (handler, router, _dir) = make_handler(None).await;
+        let token = authenticate(&handler, "itoopie");
+
+        let (mut client, server) = tokio::io::duplex(4096);
+        let future = tokio::spawn(I2pControlServer::on_connection(
+            server,
+            Arc::clone(&handler),
+            Default::default(),
+        ));
+
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "method": "RouterManager",
+            "params": { "Token": token, "Shutdown": null },
+        })
+        .to_string();
+        let request = format!(
+            "POST /jsonrpc HTTP/1.1\r\nHost: 127.0.0.1\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        client.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        future.await.unwrap().unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        let body = serde_json::from_str::<Value>(body).unwrap();
+        assert_eq!(body["id"], "1");
+        assert_eq!(body["result"]["Shutdown"], Value::Null);
+
+        // the router exits once it has received the command
+        tokio::time::timeout(Duration::from_secs(5), router).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn only_post_allowed() {
+        let (handler, _router, _dir) = make_handler(None).await;
+
+        let (mut client, server) = tokio::io::duplex(4096);
+        let future = tokio::spawn(I2pControlServer::on_connection(
+            server,
+            handler,
+            Default::default(),
+        ));
+
+        client
+            .write_all(b"GET /jsonrpc HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        future.await.unwrap().unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+
+    #[test]
+    fn allowed_hosts() {
+        let allowed = HashSet::from([String::from("router.lan")]);
+
+        for host in [
+            "localhost",
+            "LOCALHOST:7650",
+            "127.0.0.1",
+            "127.0.0.2:7650",
+            "[::1]:7650",
+            "router.lan:7650",
+            "http://localhost:7650",
+            "http://[::1]",
+        ] {
+            assert!(is_host_allowed(host, &allowed), "{host}");
+        }
+
+        for host in [
+            "evil.example",
+            "evil.example:7650",
+            "localhost.evil.example",
+            "192.168.1.1:7650",
+            "[::1",
+            "http://evil.example",
+            "null",
+            "",
+        ] {
+            assert!(!is_host_allowed(host, &allowed), "{host}");
+        }
+    }
+
+    #[tokio::test]
+    async fn foreign_host_rejected() {
+        let (handler, _router, _dir) = make_handler(None).await;
+        let allowed_hosts = Arc::new(HashSet::from([String::from("router.lan")]));
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "Authenticate",
+            "params": { "API": 1, "Password": "itoopie" },
+        })
+        .to_string();
+
+        for (headers, status) in [
+            ("Host: evil.example:7650\r\n", "403 Forbidden"),
+            ("", "403 Forbidden"),
+            (
+                "Host: 127.0.0.1:7650\r\nOrigin: http://evil.example\r\n",
+                "403 Forbidden",
+            ),
+            ("Host: localhost:7650\r\n", "200 OK"),
+            ("Host: router.lan:7650\r\n", "200 OK"),
+            (
+                "Host: 127.0.0.1:7650\r\nOrigin: http://127.0.0.1:7650\r\n",
+                "200 OK",
+            ),
+        ] {
+            let (mut client, server) = tokio::io::duplex(4096);
+            let future = tokio::spawn(I2pControlServer::on_connection(
+                server,
+                Arc::clone(&handler),
+                Arc::clone(&allowed_hosts),
+            ));
+
+            let request = format!(
+                "POST /jsonrpc HTTP/1.1\r\n{headers}Content-Length: {}\r\n\r\n{body}",
+                body.len()
+            );
+            client.write_all(request.as_bytes()).await.unwrap();
+
+            let mut response = String::new();
+            client.read_to_string(&mut response).await.unwrap();
+            future.await.unwrap().unwrap();
+
+            assert!(
+                response.starts_with(&format!("HTTP/1.1 {status}\r\n")),
+                "{headers}"
+            );
+            assert_eq!(response.contains("Token"), status == "200 OK");
+        }
+    }
+}
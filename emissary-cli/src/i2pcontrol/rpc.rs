@@ -0,0 +1,882 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! I2PControl JSON-RPC methods.
+//!
+//! Every method except `Authenticate` requires a token which is acquired by authenticating with
+//! the configured password. Setting keys that are not supported by emissary are rejected with
+//! an invalid parameters error instead of being ignored silently.
+
+use crate::{
+    config::{ClientTunnelConfig, Config, I2pControlConfig, ReseedConfig, ServerTunnelConfig},
+    tunnel::{TunnelKind, TunnelManagerHandle},
+};
+
+use emissary_core::{events::FirewallStatus, router::RouterHandle, BandwidthConfig};
+use emissary_util::{reseeder::Reseeder, runtime::tokio::Runtime, su3::ReseedRouterInfo};
+use parking_lot::Mutex;
+use rand::{distributions::Alphanumeric, Rng};
+use serde_json::{json, Map, Value};
+use subtle::ConstantTimeEq;
+
+use std::{
+    collections::{HashMap, VecDeque},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::i2pcontrol";
+
+/// Supported version of the I2PControl API.
+const API_VERSION: u64 = 1u64;
+
+/// Password used if the user didn't configure one.
+///
+/// The password is publicly known, so it's only accepted if the server binds to a loopback address.
+pub const DEFAULT_PASSWORD: &str = "itoopie";
+
+/// Name of the method for managing tunnels.
+///
+/// The method is an emissary extension and not part of I2PControl, so it's namespaced to avoid
+/// clashing with methods that may be added to the specification.
+const TUNNEL_MANAGER: &str = "emissary.TunnelManager";
+
+/// How long is an authentication token valid for.
+const TOKEN_EXPIRATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Length of an authentication token.
+const TOKEN_LEN: usize = 32usize;
+
+/// How many bandwidth samples are kept.
+///
+/// Samples are taken once per second and the longest averaging window is 15 seconds.
+const NUM_SAMPLES: usize = 16usize;
+
+/// JSON-RPC error.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum RpcError {
+    #[error("JSON parse error")]
+    ParseError,
+
+    #[error("Invalid request")]
+    InvalidRequest,
+
+    #[error("Method not found")]
+    MethodNotFound,
+
+    #[error("Invalid parameters: {0}")]
+    InvalidParams(String),
+
+    #[error("Invalid password provided")]
+    InvalidPassword,
+
+    #[error("No authentication token presented")]
+    NoToken,
+
+    #[error("Authentication token doesn't exist")]
+    NonexistentToken,
+
+    #[error("Provided authentication token was expired and will be removed")]
+    ExpiredToken,
+
+    #[error("API version wasn't specified")]
+    NoApiVersion,
+
+    #[error("Specified API version isn't supported")]
+    UnsupportedApiVersion,
+}
+
+impl RpcError {
+    /// Get JSON-RPC error code of the error.
+    fn code(&self) -> i64 {
+        match self {
+            Self::ParseError => -32700,
+            Self::InvalidRequest => -32600,
+            Self::MethodNotFound => -32601,
+            Self::InvalidParams(_) => -32602,
+            Self::InvalidPassword => -32001,
+            Self::NoToken => -32002,
+            Self::NonexistentToken => -32003,
+            Self::ExpiredToken => -32004,
+            Self::NoApiVersion => -32005,
+            Self::UnsupportedApiVersion => -32006,
+        }
+    }
+}
+
+/// Command executed after the response has been sent to the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Shut down the router immediately.
+    Shutdown,
+
+    /// Shut down the router gracefully.
+    ShutdownGraceful,
+}
+
+/// Addresses and ports of a transport, reported by `NetworkSetting`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TransportInfo {
+    /// IPv4 address the transport was configured with.
+    pub ipv4_host: Option<Ipv4Addr>,
+
+    /// IPv4 port of the transport, `None` if the transport or IPv4 is disabled.
+    pub ipv4_port: Option<u16>,
+
+    /// IPv6 address the transport was configured with.
+    pub ipv6_host: Option<Ipv6Addr>,
+
+    /// IPv6 port of the transport, `None` if the transport or IPv6 is disabled.
+    pub ipv6_port: Option<u16>,
+}
+
+impl TransportInfo {
+    /// Replace the configured ports with `port`, the port the transport is listening on.
+    ///
+    /// If `port` is `None`, the transport is disabled.
+    pub fn with_port(self, port: Option<u16>) -> Self {
+        Self {
+            ipv4_port: self.ipv4_port.and(port),
+            ipv6_port: self.ipv6_port.and(port),
+            ..self
+        }
+    }
+
+    /// Get the address of the transport, preferring the IPv4 address.
+    fn host(&self) -> Option<String> {
+        self.ipv4_host
+            .map(|host| host.to_string())
+            .or_else(|| self.ipv6_host.map(|host| host.to_string()))
+    }
+
+    /// Get the port of the transport, preferring the IPv4 port.
+    fn port(&self) -> Option<u16> {
+        self.ipv4_port.or(self.ipv6_port)
+    }
+}
+
+/// Direction of traffic.
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    /// Inbound traffic.
+    Inbound,
+
+    /// Outbound traffic.
+    Outbound,
+}
+
+/// JSON-RPC request handler.
+pub struct RpcHandler {
+    /// Base path of the router.
+    base_path: PathBuf,
+
+    /// Current bandwidth limits.
+    bandwidth: Mutex<Option<BandwidthConfig>>,
+
+    /// I2PControl configuration.
+    config: Mutex<I2pControlConfig>,
+
+    /// Router handle.
+    handle: RouterHandle<Runtime>,
+
+    /// NTCP2 information.
+    ntcp2: TransportInfo,
+
+    /// Reseed configuration.
+    reseed: Option<ReseedConfig>,
+
+    /// Is the router being reseeded.
+    reseeding: Arc<AtomicBool>,
+
+    /// Samples of the total number of bytes received and sent, oldest first.
+    samples: Mutex<VecDeque<(usize, usize)>>,
+
+    /// SSU2 information.
+    ssu2: TransportInfo,
+
+    /// When was the handler created.
+    started: Instant,
+
+    /// Active authentication tokens and their creation times.
+    tokens: Mutex<HashMap<String, Instant>>,
+
+    /// Tunnel manager handle, `None` if SAMv3 is disabled.
+    tunnels: Option<TunnelManagerHandle>,
+}
+
+impl RpcHandler {
+    /// Create new [`RpcHandler`].
+    pub fn new(
+        base_path: PathBuf,
+        config: I2pControlConfig,
+        handle: RouterHandle<Runtime>,
+        tunnels: Option<TunnelManagerHandle>,
+        bandwidth: Option<BandwidthConfig>,
+        reseed: Option<ReseedConfig>,
+        ntcp2: TransportInfo,
+        ssu2: TransportInfo,
+    ) -> Self {
+        Self {
+            base_path,
+            bandwidth: Mutex::new(bandwidth),
+            config: Mutex::new(config),
+            handle,
+            ntcp2,
+            reseed,
+            reseeding: Arc::new(AtomicBool::new(false)),
+            samples: Mutex::new(VecDeque::with_capacity(NUM_SAMPLES)),
+            ssu2,
+            started: Instant::now(),
+            tokens: Mutex::new(HashMap::new()),
+            tunnels,
+        }
+    }
+
+    /// Record the current number of bytes received and sent.
+    ///
+    /// Must be called once per second for the bandwidth rates to be correct.
+    pub fn sample(&self) {
+        let status = self.handle.status();
+        let mut samples = self.samples.lock();
+
+        if samples.len() == NUM_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back((status.num_bytes_received, status.num_bytes_sent));
+    }
+
+    /// Get average rate of `direction` traffic over the last `window` seconds, in bytes per second.
+    fn rate(&self, window: usize, direction: Direction) -> f64 {
+        let samples = self.samples.lock();
+        let window = window.min(samples.len().saturating_sub(1));
+
+        if window == 0 {
+            return 0f64;
+        }
+        let newest = samples[samples.len() - 1];
+        let oldest = samples[samples.len() - 1 - window];
+
+        let bytes = match direction {
+            Direction::Inbound => newest.0.saturating_sub(oldest.0),
+            Direction::Outbound => newest.1.saturating_sub(oldest.1),
+        };
+
+        bytes as f64 / window as f64
+    }
+
+    /// Execute `command`.
+    pub fn execute(&self, command: Command) {
+        tracing::info!(
+            target: LOG_TARGET,
+            ?command,
+            "shutdown requested",
+        );
+
+        match command {
+            Command::Shutdown => self.handle.shutdown_immediately(),
+            Command::ShutdownGraceful => self.handle.shutdown(),
+        }
+    }
+
+    /// Handle JSON-RPC request.
+    ///
+    /// Returns the response and an optional command which must be executed after the response
+    /// has been sent.
+    pub fn on_request(&self, request: &[u8]) -> (Value, Option<Command>) {
+        let request = match serde_json::from_slice::<Value>(request) {
+            Ok(request) => request,
+            Err(_) => return (Self::response(Value::Null, Err(RpcError::ParseError)), None),
+        };
+        let Some(request) = request.as_object() else {
+            return (
+                Self::response(Value::Null, Err(RpcError::InvalidRequest)),
+                None,
+            );
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+
+        let (Some("2.0"), Some(method)) = (
+            request.get("jsonrpc").and_then(Value::as_str),
+            request.get("method").and_then(Value::as_str),
+        ) else {
+            return (Self::response(id, Err(RpcError::InvalidRequest)), None);
+        };
+        let params = match request.get("params") {
+            None => Map::new(),
+            Some(Value::Object(params)) => params.clone(),
+            Some(_) => {
+                let error = RpcError::InvalidParams(String::from("params must be an object"));
+                return (Self::response(id, Err(error)), None);
+            }
+        };
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            %method,
+            "handle request",
+        );
+
+        match self.dispatch(method, params) {
+            Ok((result, command)) => (Self::response(id, Ok(result)), command),
+            Err(error) => (Self::response(id, Err(error)), None),
+        }
+    }
+
+    /// Create JSON-RPC response.
+    fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+        match result {
+            Ok(result) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": result,
+            }),
+            Err(error) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {
+                    "code": error.code(),
+                    "message": error.to_string(),
+                },
+            }),
+        }
+    }
+
+    /// Dispatch request to the handler of `method`.
+    fn dispatch(
+        &self,
+        method: &str,
+        mut params: Map<String, Value>,
+    ) -> Result<(Value, Option<Command>), RpcError> {
+        if method == "Authenticate" {
+            return self.authenticate(&params).map(|result| (result, None));
+        }
+
+        if ![
+            "Echo",
+            "RouterInfo",
+            "RouterManager",
+            "NetworkSetting",
+            "I2PControl",
+            TUNNEL_MANAGER,
+        ]
+        .contains(&method)
+        {
+            return Err(RpcError::MethodNotFound);
+        }
+        self.validate_token(params.remove("Token"))?;
+
+        match method {
+            "Echo" => Self::echo(params).map(|result| (result, None)),
+            "RouterInfo" => self.router_info(params).map(|result| (result, None)),
+            "RouterManager" => self.router_manager(params),
+            "NetworkSetting" => self.network_setting(params).map(|result| (result, None)),
+            TUNNEL_MANAGER => self.tunnel_manager(params).map(|result| (result, None)),
+            _ => self.i2pcontrol(params).map(|result| (result, None)),
+        }
+    }
+
+    /// Authenticate the client and create a new token.
+    fn authenticate(&self, params: &Map<String, Value>) -> Result<Value, RpcError> {
+        match params.get("API") {
+            None => return Err(RpcError::NoApiVersion),
+            Some(version) if version.as_u64() != Some(API_VERSION) =>
+                return Err(RpcError::UnsupportedApiVersion),
+            Some(_) => {}
+        }
+
+        let password = self.config.lock().password.clone();
+        let password = password.as_deref().unwrap_or(DEFAULT_PASSWORD);
+
+        let valid = params
+            .get("Password")
+            .and_then(Value::as_str)
+            .is_some_and(|provided| bool::from(provided.as_bytes().ct_eq(password.as_bytes())));
+
+        if !valid {
+            tracing::debug!(
+                target: LOG_TARGET,
+                "invalid password",
+            );
+            return Err(RpcError::InvalidPassword);
+        }
+
+        let token = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_LEN)
+            .map(char::from)
+            .collect::<String>();
+
+        let mut tokens = self.tokens.lock();
+        tokens.retain(|_, created| created.elapsed() < TOKEN_EXPIRATION);
+        tokens.insert(token.clone(), Instant::now());
+
+        Ok(json!({
+            "API": API_VERSION,
+            "Token": token,
+        }))
+    }
+
+    /// Ensure `token` exists and hasn't expired.
+    fn validate_token(&self, token: Option<Value>) -> Result<(), RpcError> {
+        let Some(Value::String(token)) = token else {
+            return Err(RpcError::NoToken);
+        };
+        let mut tokens = self.tokens.lock();
+
+        match tokens.get(&token) {
+            None => Err(RpcError::NonexistentToken),
+            Some(created) if created.elapsed() >= TOKEN_EXPIRATION => {
+                tokens.remove(&token);
+                Err(RpcError::ExpiredToken)
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Echo the value of `Echo` back to the client.
+    fn echo(mut params: Map<String, Value>) -> Result<Value, RpcError> {
+        match params.remove("Echo") {
+            Some(value) => Ok(json!({ "Result": value })),
+            None => Err(RpcError::InvalidParams(String::from("`Echo` is missing"))),
+        }
+    }
+
+    /// Get status information of the router.
+    fn router_info(&self, params: Map<String, Value>) -> Result<Value, RpcError> {
+        let status = self.handle.status();
+
+        params
+            .into_iter()
+            .map(|(key, _)| {
+                let value = match key.as_str() {
+                    "i2p.router.status" => json!(match status.shutting_down {
+                        true => "Shutting down",
+                        false => "Running",
+                    }),
+                    "i2p.router.uptime" => json!(self.started.elapsed().as_millis() as u64),
+                    "i2p.router.version" => json!(env!("CARGO_PKG_VERSION")),
+                    "i2p.router.net.bw.inbound.1s" => json!(self.rate(1, Direction::Inbound)),
+                    "i2p.router.net.bw.inbound.15s" => json!(self.rate(15, Direction::Inbound)),
+                    "i2p.router.net.bw.outbound.1s" => json!(self.rate(1, Direction::Outbound)),
+                    "i2p.router.net.bw.outbound.15s" => json!(self.rate(15, Direction::Outbound)),
+                    "i2p.router.net.status" => json!(match status.firewall_status {
+                        FirewallStatus::Ok => 0u64,
+                        FirewallStatus::Unknown => 1u64,
+                        FirewallStatus::Firewalled => 2u64,
+                        FirewallStatus::SymmetricNat => 11u64,
+                    }),
+                    "i2p.router.net.tunnels.participating" => json!(status.num_transit_tunnels),
+                    "i2p.router.netdb.activepeers" => json!(status.num_connected_routers),
+                    "i2p.router.netdb.fastpeers" | "i2p.router.netdb.highcapacitypeers" =>
+                        json!(status.num_fast_routers),
+                    "i2p.router.netdb.isreseeding" => json!(self.reseeding.load(Ordering::Acquire)),
+                    "i2p.router.netdb.knownpeers" => json!(status.num_known_routers),
+                    _ => return Err(RpcError::InvalidParams(format!("unsupported key `{key}`"))),
+                };
+
+                Ok((key, value))
+            })
+            .collect::<Result<Map<_, _>, _>>()
+            .map(Value::Object)
+    }
+
+    /// Manage the router.
+    ///
+    /// Shutdown is returned as a command so that it's executed only after the response has been
+    /// sent.
+    fn router_manager(
+        &self,
+        params: Map<String, Value>,
+    ) -> Result<(Value, Option<Command>), RpcError> {
+        let mut command = None;
+        let mut reseed = false;
+
+        let result = params
+            .into_iter()
+            .map(|(key, _)| {
+                let value = match key.as_str() {
+                    "Shutdown" => {
+                        command = Some(Command::Shutdown);
+                        Value::Null
+                    }
+                    "ShutdownGraceful" => {
+                        command.get_or_insert(Command::ShutdownGraceful);
+                        Value::Null
+                    }
+                    "Reseed" => {
+                        reseed = true;
+                        Value::Null
+                    }
+                    "FindUpdates" => json!(false),
+                    "Update" => json!("No update available"),
+                    "Restart" | "RestartGraceful" =>
+                        return Err(RpcError::InvalidParams(String::from(
+                            "restart is not supported",
+                        ))),
+                    _ => return Err(RpcError::InvalidParams(format!("unsupported key `{key}`"))),
+                };
+
+                Ok((key, value))
+            })
+            .collect::<Result<Map<_, _>, _>>()?;
+
+        if reseed {
+            self.reseed();
+        }
+
+        Ok((Value::Object(result), command))
+    }
+
+    /// List, add and remove client and server tunnels.
+    ///
+    /// This method is not part of I2PControl. New tunnels are configured like the tunnels in the
+    /// router configuration but they're not saved to it, meaning tunnels that are added or
+    /// removed through the API are restored to the configured state after a restart.
+    fn tunnel_manager(&self, params: Map<String, Value>) -> Result<Value, RpcError> {
+        let Some(tunnels) = &self.tunnels else {
+            return Err(RpcError::InvalidParams(String::from(
+                "tunnels are not available, sam is disabled",
+            )));
+        };
+
+        params
+            .into_iter()
+            .map(|(key, value)| {
+                let value = match key.as_str() {
+                    "Tunnels" => Value::Array(
+                        tunnels
+                            .tunnels()
+                            .into_iter()
+                            .map(|(name, kind)| {
+                                json!({
+                                    "Name": name,
+                                    "Type": match kind {
+                                        TunnelKind::Client => "client",
+                                        TunnelKind::Server => "server",
+                                    },
+                                })
+                            })
+                            .collect(),
+                    ),
+                    "AddClientTunnel" => {
+                        let config = parse_config::<ClientTunnelConfig>(&key, value)?;
+
+                        tunnels
+                            .add_client_tunnel(config)
+                            .map_err(|error| RpcError::InvalidParams(error.to_string()))?;
+                        Value::Null
+                    }
+                    "AddServerTunnel" => {
+                        let config = parse_config::<ServerTunnelConfig>(&key, value)?;
+                        let destination_paths = core::iter::once(&config.destination_path).chain(
+                            config.meta_lease_set.as_ref().map(|config| &config.destination_path),
+                        );
+
+                        // destinations must be stored under the base path of the router
+                        for path in destination_paths {
+                            if !Path::new(path)
+                                .components()
+                                .all(|component| core::matches!(component, Component::Normal(_)))
+                            {
+                                return Err(RpcError::InvalidParams(format!(
+                                    "`{path}` must be a relative path without `..`"
+                                )));
+                            }
+                        }
+
+                        tunnels
+                            .add_server_tunnel(config)
+                            .map_err(|error| RpcError::InvalidParams(error.to_string()))?;
+                        Value::Null
+                    }
+                    "RemoveTunnel" => match value {
+                        Value::String(name) => {
+                            tunnels
+                                .remove_tunnel(&name)
+                                .map_err(|error| RpcError::InvalidParams(error.to_string()))?;
+                            Value::Null
+                        }
+                        _ =>
+                            return Err(RpcError::InvalidParams(format!(
+                                "`{key}` must be a tunnel name"
+                            ))),
+                    },
+                    _ => return Err(RpcError::InvalidParams(format!("unsupported key `{key}`"))),
+                };
+
+                Ok((key, value))
+            })
+            .collect::<Result<Map<_, _>, _>>()
+            .map(Value::Object)
+    }
+
+    /// Reseed the router in the background.
+    fn reseed(&self) {
+        if self.reseeding.swap(true, Ordering::AcqRel) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                "reseed already in progress",
+            );
+            return;
+        }
+
+        let (hosts, proxy, certificates) = match self.reseed.clone() {
+            Some(config) => (config.hosts, config.proxy, config.certificates),
+            None => (None, None, None),
+        };
+        let handle = self.handle.clone();
+        let reseeding = Arc::clone(&self.reseeding);
+
+        tokio::spawn(async move {
+            match Reseeder::reseed(hosts, true, proxy, certificates).await {
+                Ok(routers) => {
+                    let num_routers = routers.len();
+                    let num_added = routers
+                        .into_iter()
+                        .filter(|ReseedRouterInfo { router_info, .. }| {
+                            handle.add_router(router_info.clone())
+                        })
+                        .count();
+
+                    tracing::info!(
+                        target: LOG_TARGET,
+                        ?num_routers,
+                        ?num_added,
+                        "router reseeded",
+                    );
+                }
+                Err(error) => tracing::warn!(
+                    target: LOG_TARGET,
+                    ?error,
+                    "failed to reseed router",
+                ),
+            }
+
+            reseeding.store(false, Ordering::Release);
+        });
+    }
+
+    /// Read or write network settings.
+    ///
+    /// Only bandwidth limits can be changed, they're applied immediately and saved to the router
+    /// configuration. A limit of zero removes the limit.
+    fn network_setting(&self, params: Map<String, Value>) -> Result<Value, RpcError> {
+        let current = self.bandwidth.lock().clone();
+        let mut bandwidth = current.clone().unwrap_or_default();
+
+        let mut result = params
+            .into_iter()
+            .map(|(key, value)| {
+                let value = match (key.as_str(), value) {
+                    ("i2p.router.net.ntcp.port", Value::Null) =>
+                        json!(self.ntcp2.port().map(|port| port.to_string())),
+                    ("i2p.router.net.ntcp.hostname", Value::Null) => json!(self.ntcp2.host()),
+                    ("i2p.router.net.ntcp.ipv6.port", Value::Null) =>
+                        json!(self.ntcp2.ipv6_port.map(|port| port.to_string())),
+                    ("i2p.router.net.ntcp.ipv6.hostname", Value::Null) =>
+                        json!(self.ntcp2.ipv6_host.map(|host| host.to_string())),
+                    ("i2p.router.net.ssu.port", Value::Null) =>
+                        json!(self.ssu2.port().map(|port| port.to_string())),
+                    ("i2p.router.net.ssu.hostname", Value::Null) => json!(self.ssu2.host()),
+                    ("i2p.router.net.ssu.ipv6.port", Value::Null) =>
+                        json!(self.ssu2.ipv6_port.map(|port| port.to_string())),
+                    ("i2p.router.net.ssu.ipv6.hostname", Value::Null) =>
+                        json!(self.ssu2.ipv6_host.map(|host| host.to_string())),
+                    ("i2p.router.net.bw.in", Value::Null) =>
+                        json!(bandwidth.inbound.map(|limit| limit.to_string())),
+                    ("i2p.router.net.bw.out", Value::Null) =>
+                        json!(bandwidth.outbound.map(|limit| limit.to_string())),
+                    ("i2p.router.net.bw.share", Value::Null) =>
+                        json!(bandwidth.share.map(|share| share.to_string())),
+                    ("i2p.router.net.bw.in", value) => {
+                        bandwidth.inbound =
+                            Some(parse_number::<usize>(&key, &value)?).filter(|limit| *limit != 0);
+                        Value::Null
+                    }
+                    ("i2p.router.net.bw.out", value) => {
+                        bandwidth.outbound =
+                            Some(parse_number::<usize>(&key, &value)?).filter(|limit| *limit != 0);
+                        Value::Null
+                    }
+                    ("i2p.router.net.bw.share", value) => {
+                        match parse_number::<u8>(&key, &value)? {
+                            share @ 0..=100 => bandwidth.share = Some(share),
+                            _ =>
+                                return Err(RpcError::InvalidParams(format!(
+                                    "`{key}` must be a percentage"
+                                ))),
+                        }
+                        Value::Null
+                    }
+                    (
+                        "i2p.router.net.ntcp.port"
+                        | "i2p.router.net.ntcp.hostname"
+                        | "i2p.router.net.ntcp.ipv6.port"
+                        | "i2p.router.net.ntcp.ipv6.hostname"
+                        | "i2p.router.net.ssu.port"
+                        | "i2p.router.net.ssu.hostname"
+                        | "i2p.router.net.ssu.ipv6.port"
+                        | "i2p.router.net.ssu.ipv6.hostname",
+                        _,
+                    ) => return Err(RpcError::InvalidParams(format!("`{key}` is read-only"))),
+                    _ => return Err(RpcError::InvalidParams(format!("unsupported key `{key}`"))),
+                };
+
+                Ok((key, value))
+            })
+            .collect::<Result<Map<_, _>, _>>()?;
+
+        let bandwidth = (bandwidth != BandwidthConfig::default()).then_some(bandwidth);
+        let saved = if bandwidth != current {
+            self.handle.set_bandwidth(bandwidth.clone());
+            *self.bandwidth.lock() = bandwidth.clone();
+
+            match Config::save_bandwidth(&self.base_path, bandwidth.as_ref()) {
+                Ok(()) => true,
+                Err(error) => {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        ?error,
+                        "failed to save bandwidth limits",
+                    );
+                    false
+                }
+            }
+        } else {
+            false
+        };
+
+        result.insert(String::from("SettingsSaved"), json!(saved));
+        result.insert(String::from("RestartNeeded"), json!(false));
+
+        Ok(Value::Object(result))
+    }
+
+    /// Read or write settings of the I2PControl server.
+    ///
+    /// Changing the password invalidates all tokens, changing the address or port requires a
+    /// restart.
+    fn i2pcontrol(&self, params: Map<String, Value>) -> Result<Value, RpcError> {
+        let current = self.config.lock().clone();
+        let mut config = current.clone();
+
+        let mut result = params
+            .into_iter()
+            .map(|(key, value)| {
+                let value = match (key.as_str(), value) {
+                    ("i2pcontrol.address", Value::Null) =>
+                        json!(config.host.as_deref().unwrap_or("127.0.0.1")),
+                    ("i2pcontrol.port", Value::Null) => json!(config.port.to_string()),
+                    ("i2pcontrol.address", Value::String(address)) => {
+                        if address.parse::<IpAddr>().is_err() {
+                            return Err(RpcError::InvalidParams(format!(
+                                "`{key}` must be an ip address"
+                            )));
+                        }
+                        config.host = Some(address);
+                        Value::Null
+                    }
+                    ("i2pcontrol.port", value) => {
+                        match parse_number::<u16>(&key, &value)? {
+                            0 =>
+                                return Err(RpcError::InvalidParams(format!(
+                                    "`{key}` must be a port"
+                                ))),
+                            port => config.port = port,
+                        }
+                        Value::Null
+                    }
+                    ("i2pcontrol.password", Value::String(password)) if !password.is_empty() => {
+                        config.password = Some(password);
+                        Value::Null
+                    }
+                    ("i2pcontrol.password", _) =>
+                        return Err(RpcError::InvalidParams(format!(
+                            "`{key}` must be a non-empty string"
+                        ))),
+                    _ => return Err(RpcError::InvalidParams(format!("unsupported key `{key}`"))),
+                };
+
+                Ok((key, value))
+            })
+            .collect::<Result<Map<_, _>, _>>()?;
+
+        // the default password must not be used if the server is reachable from other hosts
+        if config.password.is_none()
+            && config.host.as_deref().is_some_and(|host| {
+                host.parse::<IpAddr>().is_ok_and(|address| !address.is_loopback())
+            })
+        {
+            return Err(RpcError::InvalidParams(String::from(
+                "password must be set before using a non-loopback address",
+            )));
+        }
+
+        let restart_needed = config.host != current.host || config.port != current.port;
+        let saved = if config != current {
+            if config.password != current.password {
+                self.tokens.lock().clear();
+            }
+            *self.config.lock() = config.clone();
+
+            match Config::save_i2pcontrol(&self.base_path, &config) {
+                Ok(()) => true,
+                Err(error) => {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        ?error,
+                        "failed to save i2pcontrol settings",
+                    );
+                    false
+                }
+            }
+        } else {
+            false
+        };
+
+        result.insert(String::from("SettingsSaved"), json!(saved));
+        result.insert(String::from("RestartNeeded"), json!(restart_needed));
+
+        Ok(Value::Object(result))
+    }
+}
+
+/// Parse `value` of `key` into a tunnel configuration.
+fn parse_config<T: serde::de::DeserializeOwned>(key: &str, value: Value) -> Result<T, RpcError> {
+    serde_json::from_value::<T>(value)
+        .map_err(|error| RpcError::InvalidParams(format!("invalid `{key}`: {error}")))
+}
+
+/// Parse `value` of `key` into a number.
+///
+/// Clients send numbers as strings but plain JSON numbers are accepted as well.
+fn parse_number<T: FromStr>(key: &str, value: &Value) -> Result<T, RpcError> {
+    match value {
+        Value::String(value) => value.trim().parse::<T>().ok(),
+        Value::Number(value) => value.to_string().parse::<T>().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| RpcError::InvalidParams(format!("`{key}` must be a number")))
+}
@@ -20,15 +20,21 @@
 #![allow(clippy::too_many_arguments)]
 
 use crate::{
-    address_book::AddressBookManager,
+    address_book::{feed::AddressBookFeed, store::AddressBookStore, AddressBookManager},
+    blocklist::BlocklistManager,
     cli::Arguments,
-    config::{Config, ReseedConfig, RouterUiConfig},
+    config::{
+        AddressBookFeedConfig, Config, ReseedConfig, RouterUiConfig, ServerTunnelConfig,
+        ServerTunnelKind,
+    },
     error::Error,
+    i2pcontrol::{I2pControlServer, TransportInfo},
+    news::{NewsManager, NewsStore},
     port_mapper::PortMapper,
     proxy::{http::HttpProxy, socks::SocksProxy},
     storage::RouterStorage,
-    tools::RouterCommand,
-    tunnel::{client::ClientTunnelManager, server::ServerTunnelManager},
+    tools::{AddressBookCommand, RouterCommand},
+    tunnel::{client::ClientTunnelManager, server::ServerTunnelManager, TunnelManagerHandle},
 };
 
 use anyhow::anyhow;
@@ -41,10 +47,13 @@ use tokio::sync::mpsc::{channel, Receiver};
 use std::{fs::File, io::Write, mem, sync::Arc};
 
 mod address_book;
+mod blocklist;
 mod cli;
 mod config;
 mod error;
+mod i2pcontrol;
 mod logger;
+mod news;
 mod port_mapper;
 mod proxy;
 mod storage;
@@ -78,6 +87,18 @@ struct RouterContext {
     /// Router UI config, if enabled.
     #[allow(unused)]
     router_ui_config: Option<RouterUiConfig>,
+
+    /// Address book store, if address book was enabled.
+    ///
+    /// Passed onto a router UI if it has been enabled.
+    #[allow(unused)]
+    address_book: Option<AddressBookStore>,
+
+    /// News store, if news feed was enabled.
+    ///
+    /// Passed onto a router UI if it has been enabled.
+    #[allow(unused)]
+    news: Option<NewsStore>,
 }
 
 /// Parse `Arguments` and if no subcommand has been specified, return `Arguments`, allowing the
@@ -118,6 +139,134 @@ fn parse_arguments() -> Arguments {
                 );
                 std::process::exit(1);
             },
+        RouterCommand::OfflineSign { key, days, output } =>
+            if let Err(error) = tools::offline::sign(key, days, output) {
+                tracing::error!(
+                    target: LOG_TARGET,
+                    ?error,
+                    "failed to create offline signature",
+                );
+                std::process::exit(1);
+            },
+        RouterCommand::AddressBook { command } => {
+            let base_path = match Config::base_path(arguments.base_path) {
+                Ok(base_path) => base_path,
+                Err(error) => {
+                    tracing::error!(
+                        target: LOG_TARGET,
+                        ?error,
+                        "failed to resolve base path",
+                    );
+                    std::process::exit(1);
+                }
+            };
+
+            let result = match command {
+                AddressBookCommand::Add {
+                    host,
+                    destination,
+                    private,
+                } => tools::address_book::add(&base_path, host, destination, private),
+                AddressBookCommand::Remove { host, private } =>
+                    tools::address_book::remove(&base_path, host, private),
+                AddressBookCommand::List { book } => tools::address_book::list(&base_path, book),
+                AddressBookCommand::Export { output } =>
+                    tools::address_book::export(&base_path, output),
+            };
+
+            if let Err(error) = result {
+                tracing::error!(
+                    target: LOG_TARGET,
+                    ?error,
+                    "address book command failed",
+                );
+                std::process::exit(1);
+            }
+        }
+        RouterCommand::CreateReseed {
+            signer_id,
+            signing_key,
+            num_routers,
+            output,
+        } => {
+            let base_path = match Config::base_path(arguments.base_path) {
+                Ok(base_path) => base_path,
+                Err(error) => {
+                    tracing::error!(
+                        target: LOG_TARGET,
+                        ?error,
+                        "failed to resolve base path",
+                    );
+                    std::process::exit(1);
+                }
+            };
+
+            if let Err(error) =
+                tools::reseed::create(&base_path, signer_id, signing_key, num_routers, output)
+            {
+                tracing::error!(
+                    target: LOG_TARGET,
+                    ?error,
+                    "failed to create reseed bundle",
+                );
+                std::process::exit(1);
+            }
+        }
+        RouterCommand::ReseedServer {
+            signer_id,
+            signing_key,
+            tls_certificate,
+            tls_key,
+            host,
+            port,
+            num_routers,
+        } => {
+            init_logger!(arguments.log.clone());
+
+            let base_path = match Config::base_path(arguments.base_path) {
+                Ok(base_path) => base_path,
+                Err(error) => {
+                    tracing::error!(
+                        target: LOG_TARGET,
+                        ?error,
+                        "failed to resolve base path",
+                    );
+                    std::process::exit(1);
+                }
+            };
+            let runtime = match tokio::runtime::Runtime::new() {
+                Ok(runtime) => runtime,
+                Err(error) => {
+                    tracing::error!(
+                        target: LOG_TARGET,
+                        ?error,
+                        "failed to create runtime",
+                    );
+                    std::process::exit(1);
+                }
+            };
+
+            match runtime.block_on(tools::reseed::ReseedServer::new(
+                base_path,
+                signer_id,
+                signing_key,
+                tls_certificate,
+                tls_key,
+                host,
+                port,
+                num_routers,
+            )) {
+                Ok(server) => runtime.block_on(server.run()),
+                Err(error) => {
+                    tracing::error!(
+                        target: LOG_TARGET,
+                        ?error,
+                        "failed to start reseed server",
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
     }
 
     std::process::exit(0);
@@ -156,15 +305,27 @@ async fn setup_router(arguments: Arguments) -> anyhow::Result<RouterContext> {
             num_routers = ?config.routers.len(),
             forced_reseed = ?arguments.reseed.force_reseed.unwrap_or(false),
             force_ipv4 = ?(!arguments.reseed.disable_force_ipv4.unwrap_or(false)),
+            reseed_file = ?arguments.reseed.reseed_file,
             "reseed router"
         );
 
-        match Reseeder::reseed(
-            config.reseed.as_ref().and_then(|config| config.hosts.clone()),
-            !arguments.reseed.disable_force_ipv4.unwrap_or(false),
-        )
-        .await
-        {
+        // reseed from a local file if one was given, without falling back to reseed hosts
+        let result = match &arguments.reseed.reseed_file {
+            Some(path) => Reseeder::reseed_from_file(
+                path,
+                config.reseed.as_ref().and_then(|config| config.certificates.clone()),
+            ),
+            None =>
+                Reseeder::reseed(
+                    config.reseed.as_ref().and_then(|config| config.hosts.clone()),
+                    !arguments.reseed.disable_force_ipv4.unwrap_or(false),
+                    config.reseed.as_ref().and_then(|config| config.proxy.clone()),
+                    config.reseed.as_ref().and_then(|config| config.certificates.clone()),
+                )
+                .await,
+        };
+
+        match result {
             Ok(routers) => {
                 tracing::info!(
                     target: LOG_TARGET,
@@ -217,32 +378,133 @@ async fn setup_router(arguments: Arguments) -> anyhow::Result<RouterContext> {
     let port_forwarding = config.port_forwarding.take();
     let client_tunnels = mem::take(&mut config.client_tunnels);
     let server_tunnels = mem::take(&mut config.server_tunnels);
+    let address_book_feed = config.address_book.as_mut().and_then(|config| config.feed.take());
     let router_ui_config = config.router_ui.clone();
+    let address_book_config = config.address_book.take();
+    let i2pcontrol_config = config.i2pcontrol.take();
+    let bandwidth = config.bandwidth.clone();
+    let reseed_config = config.reseed.clone();
+    let ntcp2_info = config
+        .ntcp2_config
+        .as_ref()
+        .map(|config| TransportInfo {
+            ipv4_host: config.ipv4_host,
+            ipv4_port: config.ipv4.then_some(config.port),
+            ipv6_host: config.ipv6_host,
+            ipv6_port: config.ipv6.then_some(config.port),
+        })
+        .unwrap_or_default();
+    let ssu2_info = config
+        .ssu2_config
+        .as_ref()
+        .map(|config| TransportInfo {
+            ipv4_host: config.ipv4_host,
+            ipv4_port: config.ipv4.then_some(config.port),
+            ipv6_host: config.ipv6_host,
+            ipv6_port: config.ipv6.then_some(config.port),
+        })
+        .unwrap_or_default();
+
+    // load local blocklist and create blocklist manager whose blocklist is shared with the router
+    let blocklist_manager = config
+        .blocklist
+        .take()
+        .map(|blocklist_config| BlocklistManager::new(&config.base_path, blocklist_config))
+        .transpose()?;
+
+    // load stored news and create news manager which periodically downloads the news feed
+    let news_manager = config
+        .news
+        .take()
+        .map(|news_config| NewsManager::new(&config.base_path, news_config))
+        .transpose()?;
+    let news = news_manager.as_ref().map(|manager| manager.store());
+    let mut router_config = emissary_core::Config::from(config);
+    router_config.blocklist = blocklist_manager.as_ref().map(|manager| manager.blocklist());
+
+    let (router, events, local_router_info, address_book_manager) = match address_book_config {
+        None => Router::<Runtime>::new(router_config, None, Some(Arc::new(storage)))
+            .await
+            .map(|(router, event_subscriber, info)| (router, event_subscriber, info, None)),
+
+        Some(address_book_config) => {
+            // create address book, allocate address book handle and pass it to `Router`
+            let address_book_manager = AddressBookManager::new(path.clone(), address_book_config);
+            let address_book_handle = address_book_manager.handle();
+
+            Router::<Runtime>::new(
+                router_config,
+                Some(address_book_handle),
+                Some(Arc::new(storage)),
+            )
+            .await
+            .map(|(router, event_subscriber, info)| {
+                (router, event_subscriber, info, Some(address_book_manager))
+            })
+        }
+    }
+    .map_err(|error| anyhow!(error))?;
+    let address_book = address_book_manager.as_ref().map(|manager| manager.store());
 
-    let (router, events, local_router_info, address_book_manager) =
-        match config.address_book.take() {
-            None => Router::<Runtime>::new(config.into(), None, Some(Arc::new(storage)))
-                .await
-                .map(|(router, event_subscriber, info)| (router, event_subscriber, info, None)),
-
-            Some(address_book_config) => {
-                // create address book, allocate address book handle and pass it to `Router`
-                let address_book_manager =
-                    AddressBookManager::new(config.base_path.clone(), address_book_config);
-                let address_book_handle = address_book_manager.handle();
-
-                Router::<Runtime>::new(
-                    config.into(),
-                    Some(address_book_handle),
-                    Some(Arc::new(storage)),
-                )
-                .await
-                .map(|(router, event_subscriber, info)| {
-                    (router, event_subscriber, info, Some(address_book_manager))
-                })
+    // create handle which allows adding and removing tunnels while the router is running
+    //
+    // tunnels are connected to the router over sam so they're available only if sam is enabled
+    let (tunnel_manager_handle, client_tunnel_rx, server_tunnel_rx) =
+        TunnelManagerHandle::new(&client_tunnels, &server_tunnels);
+
+    // start i2pcontrol server if it was enabled
+    if let Some(i2pcontrol_config) = i2pcontrol_config {
+        // the configured port may be zero, use the ports the transports are listening on
+        let ntcp2 = ntcp2_info.with_port(router.protocol_address_info().ntcp2_port);
+        let ssu2 = ssu2_info.with_port(router.protocol_address_info().ssu2_port);
+
+        match I2pControlServer::new(
+            path.clone(),
+            i2pcontrol_config,
+            router.handle(),
+            router
+                .protocol_address_info()
+                .sam_tcp
+                .is_some()
+                .then(|| tunnel_manager_handle.clone()),
+            bandwidth,
+            reseed_config,
+            ntcp2,
+            ssu2,
+        )
+        .await
+        {
+            Ok(server) => {
+                tokio::spawn(server.run());
             }
+            Err(error) => tracing::warn!(
+                target: LOG_TARGET,
+                ?error,
+                "failed to start i2pcontrol server",
+            ),
         }
-        .map_err(|error| anyhow!(error))?;
+    }
+
+    // start blocklist manager which periodically downloads the blocklist feeds
+    //
+    // feeds hosted inside i2p are downloaded through the http proxy, if it was enabled
+    if let Some(blocklist_manager) = blocklist_manager {
+        let http_proxy = http
+            .as_ref()
+            .filter(|_| router.protocol_address_info().sam_tcp.is_some())
+            .map(|config| (config.host.clone(), config.port));
+
+        tokio::spawn(blocklist_manager.run(http_proxy));
+    }
+
+    if news_manager.is_some()
+        && (http.is_none() || router.protocol_address_info().sam_tcp.is_none())
+    {
+        tracing::warn!(
+            target: LOG_TARGET,
+            "http proxy is disabled, cannot download news feed",
+        );
+    }
 
     // save newest router info to disk
     File::create(path.join("router.info"))?.write_all(&local_router_info)?;
@@ -251,6 +513,13 @@ async fn setup_router(arguments: Arguments) -> anyhow::Result<RouterContext> {
     if let Some(address) = router.protocol_address_info().sam_tcp {
         // start http proxy if it was enabled
         if let Some(config) = http {
+            // start news manager if it was enabled
+            //
+            // news feed is hosted inside i2p and it's downloaded through the http proxy
+            if let Some(news_manager) = news_manager {
+                tokio::spawn(news_manager.run(config.host.clone(), config.port));
+            }
+
             // start event loop of address book manager if address book was enabled
             //
             // address book depends on the http proxy as it downloads hosts.txt from inside i2p
@@ -302,9 +571,13 @@ async fn setup_router(arguments: Arguments) -> anyhow::Result<RouterContext> {
 
         // start socks proxy if it was enabled
         if let Some(config) = socks {
+            let samv3_udp_port =
+                router.protocol_address_info().sam_udp.map_or(0u16, |address| address.port());
+            let address_book = address_book.clone();
+
             // start event loop of socks proxy
             tokio::spawn(async move {
-                match SocksProxy::new(config, address.port()).await {
+                match SocksProxy::new(config, address.port(), samv3_udp_port, address_book).await {
                     Ok(proxy) =>
                         if let Err(error) = proxy.run().await {
                             tracing::debug!(
@@ -322,12 +595,65 @@ async fn setup_router(arguments: Arguments) -> anyhow::Result<RouterContext> {
             });
         }
 
+        // start address book feed if it was enabled and expose it through a server tunnel
+        if let Some(AddressBookFeedConfig {
+            port,
+            destination_path,
+        }) = address_book_feed
+        {
+            match AddressBookFeed::new(path.clone(), port).await {
+                Ok(feed) => {
+                    tokio::spawn(feed.run());
+
+                    if let Err(error) =
+                        tunnel_manager_handle.add_server_tunnel(ServerTunnelConfig {
+                            name: String::from("address-book-feed"),
+                            port,
+                            destination_path,
+                            kind: ServerTunnelKind::Tcp,
+                            host: None,
+                            requests_per_minute: None,
+                            meta_lease_set: None,
+                        })
+                    {
+                        tracing::warn!(
+                            target: LOG_TARGET,
+                            ?error,
+                            "failed to start server tunnel for address book feed",
+                        );
+                    }
+                }
+                Err(error) => tracing::warn!(
+                    target: LOG_TARGET,
+                    ?error,
+                    "failed to start address book feed",
+                ),
+            }
+        }
+
         // start client and server tunnels
-        tokio::spawn(ClientTunnelManager::new(client_tunnels, address.port()).run());
+        let samv3_udp_port =
+            router.protocol_address_info().sam_udp.map_or(0u16, |address| address.port());
+
         tokio::spawn(
-            ServerTunnelManager::new(server_tunnels, address.port(), path.clone())
-                .await
-                .run(),
+            ClientTunnelManager::new(
+                client_tunnels,
+                address.port(),
+                samv3_udp_port,
+                client_tunnel_rx,
+            )
+            .run(),
+        );
+        tokio::spawn(
+            ServerTunnelManager::new(
+                server_tunnels,
+                address.port(),
+                samv3_udp_port,
+                path.clone(),
+                server_tunnel_rx,
+            )
+            .await
+            .run(),
         );
     }
 
@@ -345,6 +671,8 @@ async fn setup_router(arguments: Arguments) -> anyhow::Result<RouterContext> {
         events,
         port_mapper,
         router_ui_config,
+        address_book,
+        news,
     })
 }
 
@@ -411,7 +739,8 @@ fn main() -> anyhow::Result<()> {
         port_mapper,
         router,
         router_ui_config,
-        ..
+        address_book,
+        news,
     } = runtime.block_on(setup_router(arguments))?;
 
     match router_ui_config {
@@ -424,7 +753,16 @@ fn main() -> anyhow::Result<()> {
             ..
         }) => {
             runtime.spawn(async move {
-                ui::web::RouterUi::new(events, port, refresh_interval, shutdown_tx).run().await;
+                ui::web::RouterUi::new(
+                    events,
+                    port,
+                    refresh_interval,
+                    shutdown_tx,
+                    address_book,
+                    news,
+                )
+                .run()
+                .await;
             });
             runtime.block_on(router_event_loop(router, port_mapper, shutdown_rx));
         }
@@ -443,6 +781,8 @@ fn main() -> anyhow::Result<()> {
         port_mapper,
         events,
         router_ui_config,
+        address_book,
+        news,
     } = runtime.block_on(setup_router(arguments))?;
 
     match router_ui_config {
@@ -461,7 +801,14 @@ fn main() -> anyhow::Result<()> {
                 std::process::exit(0);
             });
 
-            ui::native::RouterUi::start(events, theme, refresh_interval, shutdown_tx)
+            ui::native::RouterUi::start(
+                events,
+                theme,
+                refresh_interval,
+                shutdown_tx,
+                address_book,
+                news,
+            )
         }
     }
 }
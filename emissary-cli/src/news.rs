@@ -0,0 +1,459 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! News feed.
+//!
+//! Periodically downloads the signed news feed through the HTTP proxy, verifies it against the
+//! configured certificates and stores it as `news.xml` in the base path. Entries which the router
+//! hasn't seen before are announced to the router UI through [`NewsSubscriber`].
+
+use crate::config::NewsConfig;
+
+use emissary_util::{certificates::load_public_keys, su3::Su3};
+use parking_lot::RwLock;
+use reqwest::{Client, Proxy};
+use rsa::RsaPublicKey;
+use tokio::sync::broadcast::{self, error::TryRecvError, Receiver, Sender};
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::news";
+
+/// Name of the news file in the base path.
+const NEWS_FILE: &str = "news.xml";
+
+/// How often is the news feed downloaded.
+const NEWS_UPDATE_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// How many times is the download of the news feed retried.
+const NEWS_NUM_RETRIES: usize = 5usize;
+
+/// Backoff between download attempts.
+const RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Channel size for news events.
+const EVENT_CHANNEL_SIZE: usize = 64usize;
+
+/// News entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewsEntry {
+    /// Unique ID of the entry.
+    pub id: String,
+
+    /// Title.
+    pub title: String,
+
+    /// When was the entry last updated.
+    pub updated: String,
+
+    /// Link to the full article, if any.
+    pub link: Option<String>,
+
+    /// Summary of the entry.
+    ///
+    /// If the entry has no summary, this is the text of its content.
+    pub summary: String,
+}
+
+/// Parse Atom feed into news entries.
+///
+/// Entries without an ID are ignored.
+fn parse_feed(feed: &str) -> Option<Vec<NewsEntry>> {
+    let document = roxmltree::Document::parse(feed).ok()?;
+    let root = document.root_element();
+
+    if root.tag_name().name() != "feed" {
+        return None;
+    }
+
+    // collapse whitespace of all text inside `node`, including text of nested xhtml elements
+    let text = |node: roxmltree::Node| {
+        node.descendants()
+            .filter(|node| node.is_text())
+            .filter_map(|node| node.text())
+            .flat_map(str::split_whitespace)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    Some(
+        root.children()
+            .filter(|node| node.tag_name().name() == "entry")
+            .filter_map(|entry| {
+                let child =
+                    |name: &str| entry.children().find(|node| node.tag_name().name() == name);
+                let id = child("id").map(text).filter(|id| !id.is_empty())?;
+
+                Some(NewsEntry {
+                    id,
+                    title: child("title").map(text).unwrap_or_default(),
+                    updated: child("updated").map(text).unwrap_or_default(),
+                    link: entry
+                        .children()
+                        .filter(|node| node.tag_name().name() == "link")
+                        .find(|node| node.attribute("rel").is_none_or(|rel| rel == "alternate"))
+                        .and_then(|node| node.attribute("href"))
+                        .map(ToOwned::to_owned),
+                    summary: child("summary")
+                        .or_else(|| child("content"))
+                        .map(text)
+                        .unwrap_or_default(),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Subscriber to new news entries.
+pub struct NewsSubscriber {
+    /// RX channel for receiving new entries.
+    rx: Receiver<NewsEntry>,
+}
+
+impl NewsSubscriber {
+    /// Get next new entry, if any.
+    pub fn next_entry(&mut self) -> Option<NewsEntry> {
+        loop {
+            match self.rx.try_recv() {
+                Ok(entry) => return Some(entry),
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+/// News store.
+///
+/// Shared between [`NewsManager`] and the router UI.
+#[derive(Clone)]
+pub struct NewsStore {
+    /// News entries, in the order of the feed.
+    entries: Arc<RwLock<Vec<NewsEntry>>>,
+
+    /// TX channel for announcing new entries.
+    tx: Sender<NewsEntry>,
+}
+
+impl NewsStore {
+    /// Create new [`NewsStore`].
+    fn new(entries: Vec<NewsEntry>) -> Self {
+        let (tx, _) = broadcast::channel(EVENT_CHANNEL_SIZE);
+
+        Self {
+            entries: Arc::new(RwLock::new(entries)),
+            tx,
+        }
+    }
+
+    /// Get all news entries.
+    pub fn entries(&self) -> Vec<NewsEntry> {
+        self.entries.read().clone()
+    }
+
+    /// Subscribe to new news entries.
+    pub fn subscribe(&self) -> NewsSubscriber {
+        NewsSubscriber {
+            rx: self.tx.subscribe(),
+        }
+    }
+
+    /// Replace stored entries with `entries` and announce the ones not seen before.
+    ///
+    /// Returns the number of new entries.
+    fn update(&self, entries: Vec<NewsEntry>) -> usize {
+        let new_entries = {
+            let mut inner = self.entries.write();
+            let known = inner.iter().map(|entry| &entry.id).collect::<HashSet<_>>();
+            let new_entries = entries
+                .iter()
+                .filter(|entry| !known.contains(&entry.id))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            *inner = entries;
+            new_entries
+        };
+        let num_new = new_entries.len();
+
+        // there may not be any subscribers
+        new_entries.into_iter().for_each(|entry| {
+            let _ = self.tx.send(entry);
+        });
+
+        num_new
+    }
+}
+
+/// News manager.
+pub struct NewsManager {
+    /// Public keys of the news signers, indexed by signer ID.
+    keys: HashMap<String, RsaPublicKey>,
+
+    /// Path to the news file.
+    path: PathBuf,
+
+    /// News store.
+    store: NewsStore,
+
+    /// URL of the news feed.
+    url: String,
+}
+
+impl NewsManager {
+    /// Create new [`NewsManager`].
+    ///
+    /// News entries stored in the base path are loaded but not announced as new.
+    pub fn new(base_path: &Path, config: NewsConfig) -> anyhow::Result<Self> {
+        let path = base_path.join(NEWS_FILE);
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(feed) => parse_feed(&feed).unwrap_or_else(|| {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    ?path,
+                    "stored news feed is invalid, ignoring",
+                );
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+
+        Ok(Self {
+            keys: load_public_keys(&config.certificates)?,
+            path,
+            store: NewsStore::new(entries),
+            url: config.url,
+        })
+    }
+
+    /// Get handle to [`NewsStore`].
+    pub fn store(&self) -> NewsStore {
+        self.store.clone()
+    }
+
+    /// Download news feed and verify it.
+    async fn download(&self, client: &Client) -> Option<String> {
+        let response = match client.get(&self.url).timeout(Duration::from_secs(60)).send().await {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    url = %self.url,
+                    status = ?response.status(),
+                    "failed to download news feed",
+                );
+                return None;
+            }
+            Err(error) => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    url = %self.url,
+                    ?error,
+                    "failed to download news feed",
+                );
+                return None;
+            }
+        };
+        let response = response.bytes().await.ok()?;
+
+        match Su3::parse_news(&response, &self.keys) {
+            Some(feed) => Some(feed),
+            None => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    url = %self.url,
+                    "invalid news feed",
+                );
+                None
+            }
+        }
+    }
+
+    /// Parse verified `feed`, store it on disk and update [`NewsStore`].
+    ///
+    /// Returns `false` if `feed` is not a valid Atom feed.
+    fn update(&self, feed: &str) -> bool {
+        let Some(entries) = parse_feed(feed) else {
+            tracing::warn!(
+                target: LOG_TARGET,
+                url = %self.url,
+                "news feed is not a valid atom feed",
+            );
+            return false;
+        };
+
+        if let Err(error) = std::fs::write(&self.path, feed) {
+            tracing::warn!(
+                target: LOG_TARGET,
+                path = ?self.path,
+                ?error,
+                "failed to store news feed",
+            );
+        }
+
+        let num_entries = entries.len();
+        let num_new = self.store.update(entries);
+
+        tracing::info!(
+            target: LOG_TARGET,
+            ?num_entries,
+            ?num_new,
+            "news feed updated",
+        );
+
+        true
+    }
+
+    /// Start event loop of [`NewsManager`].
+    ///
+    /// The news feed is downloaded through the HTTP proxy listening on `http_host:http_port`.
+    pub async fn run(self, http_host: String, http_port: u16) {
+        let client = Client::builder()
+            .proxy(Proxy::http(format!("http://{http_host}:{http_port}")).expect("to succeed"))
+            .http1_title_case_headers()
+            .build()
+            .expect("to succeed");
+
+        loop {
+            for _ in 0..NEWS_NUM_RETRIES {
+                // the feed is not downloaded again if it's valid but not an atom feed
+                if let Some(feed) = self.download(&client).await {
+                    self.update(&feed);
+                    break;
+                }
+
+                tokio::time::sleep(RETRY_BACKOFF).await;
+            }
+
+            tokio::time::sleep(NEWS_UPDATE_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <id>urn:uuid:60a76c80-d399-11d9-b91C-0003939e0af6</id>
+  <title>I2P News</title>
+  <updated>2025-06-02T12:00:00Z</updated>
+  <entry>
+    <id>urn:uuid:2</id>
+    <title>Release 2.9.0</title>
+    <updated>2025-06-02T12:00:00Z</updated>
+    <link href="http://i2p-projekt.i2p/en/blog/post/2025/06/02/release-2.9.0"/>
+    <summary>New release</summary>
+    <content type="xhtml">
+      <div xmlns="http://www.w3.org/1999/xhtml">
+        <p>Details</p>
+      </div>
+    </content>
+  </entry>
+  <entry>
+    <id>urn:uuid:1</id>
+    <title>Release 2.8.0</title>
+    <updated>2025-03-17T12:00:00Z</updated>
+    <link rel="alternate" href="http://i2p-projekt.i2p/en/blog/post/2025/03/17/release-2.8.0"/>
+    <content type="xhtml">
+      <div xmlns="http://www.w3.org/1999/xhtml">
+        <p>Bug fixes and
+           <b>performance</b> improvements</p>
+      </div>
+    </content>
+  </entry>
+  <entry>
+    <title>No ID</title>
+  </entry>
+</feed>"#;
+
+    #[test]
+    fn parse_atom_feed() {
+        let entries = parse_feed(FEED).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].id, "urn:uuid:2");
+        assert_eq!(entries[0].title, "Release 2.9.0");
+        assert_eq!(entries[0].updated, "2025-06-02T12:00:00Z");
+        assert_eq!(
+            entries[0].link.as_deref(),
+            Some("http://i2p-projekt.i2p/en/blog/post/2025/06/02/release-2.9.0")
+        );
+        assert_eq!(entries[0].summary, "New release");
+        assert_eq!(entries[1].summary, "Bug fixes and performance improvements");
+
+        assert!(parse_feed("<rss></rss>").is_none());
+        assert!(parse_feed("not xml").is_none());
+    }
+
+    #[test]
+    fn only_new_entries_announced() {
+        let dir = tempdir().unwrap();
+        let certificate = dir.path().join("test_at_mail.i2p.crt");
+        std::fs::write(
+            &certificate,
+            include_str!("../../emissary-util/assets/test/test_at_mail.i2p.crt"),
+        )
+        .unwrap();
+
+        // store feed without the newest entry
+        let start = FEED.find("  <entry>").unwrap();
+        let end = start + 1 + FEED[start + 1..].find("  <entry>").unwrap();
+        let old_feed = format!("{}{}", &FEED[..start], &FEED[end..]);
+        std::fs::write(dir.path().join(NEWS_FILE), &old_feed).unwrap();
+
+        let manager = NewsManager::new(
+            dir.path(),
+            NewsConfig {
+                url: String::from("http://news.i2p/news.su3"),
+                certificates: vec![certificate.display().to_string()],
+            },
+        )
+        .unwrap();
+        let store = manager.store();
+        let mut subscriber = store.subscribe();
+
+        assert_eq!(store.entries().len(), 1);
+        assert!(subscriber.next_entry().is_none());
+        assert!(manager.keys.contains_key("test@mail.i2p"));
+
+        // update with the full feed and verify only the newer entry is announced
+        assert!(manager.update(FEED));
+        assert_eq!(store.entries().len(), 2);
+        assert_eq!(subscriber.next_entry().unwrap().id, "urn:uuid:2");
+        assert!(subscriber.next_entry().is_none());
+
+        // feed was stored on disk
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join(NEWS_FILE)).unwrap(),
+            FEED
+        );
+
+        // invalid feed is ignored
+        assert!(!manager.update("<rss></rss>"));
+        assert_eq!(store.entries().len(), 2);
+    }
+}
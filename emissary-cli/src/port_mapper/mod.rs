@@ -22,7 +22,7 @@ use futures::Stream;
 use tokio::sync::{mpsc, oneshot};
 
 use std::{
-    net::Ipv4Addr,
+    net::IpAddr,
     pin::Pin,
     task::{Context, Poll},
 };
@@ -39,7 +39,7 @@ const LOG_TARGET: &str = "emissary::port-mapper";
 /// encounters an error, `PortMapper` stops polling it and keeps returning `Poll::Pending`.
 pub struct PortMapper {
     /// RX channel for receiving external address discoveries.
-    address_rx: Option<mpsc::Receiver<Ipv4Addr>>,
+    address_rx: Option<mpsc::Receiver<IpAddr>>,
 
     /// TX channel for sending shutdown signal to port mapper, whichever is active.
     ///
@@ -136,7 +136,7 @@ impl PortMapper {
 }
 
 impl Stream for PortMapper {
-    type Item = Ipv4Addr;
+    type Item = IpAddr;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         match self.address_rx.as_mut() {
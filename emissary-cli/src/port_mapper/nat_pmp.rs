@@ -25,7 +25,11 @@ use tokio::{
     sync::{mpsc, oneshot},
 };
 
-use std::{future::Future, net::Ipv4Addr, time::Duration};
+use std::{
+    future::Future,
+    net::{IpAddr, Ipv4Addr},
+    time::Duration,
+};
 
 /// Logging target for the file
 const LOG_TARGET: &str = "emissary::port-mapper::nat-pmp";
@@ -52,7 +56,7 @@ const ADDRESS_REFRESH_TIMER: Duration = Duration::from_secs(5 * 60);
 /// and if it's not supported, UPnP is used as a fallback.
 pub struct PortMapper {
     /// TX channel for sending external address discoveries.
-    address_tx: mpsc::Sender<Ipv4Addr>,
+    address_tx: mpsc::Sender<IpAddr>,
 
     /// Port forwarding config.
     config: PortForwardingConfig,
@@ -73,7 +77,7 @@ impl PortMapper {
         config: PortForwardingConfig,
         ntcp2_port: Option<u16>,
         ssu2_port: Option<u16>,
-        address_tx: mpsc::Sender<Ipv4Addr>,
+        address_tx: mpsc::Sender<IpAddr>,
         shutdown_rx: oneshot::Receiver<oneshot::Sender<()>>,
     ) -> Self {
         Self {
@@ -270,7 +274,7 @@ impl PortMapper {
             Err(()) => return self.try_switch_to_upnp(),
             Ok(None) => return self.try_switch_to_upnp(),
             Ok(Some(address)) => {
-                let _ = self.address_tx.send(address).await;
+                let _ = self.address_tx.send(IpAddr::V4(address)).await;
                 address
             }
         };
@@ -306,7 +310,7 @@ impl PortMapper {
                                 "new external address discovered",
                             );
 
-                            let _ = self.address_tx.send(address).await;
+                            let _ = self.address_tx.send(IpAddr::V4(address)).await;
                             external_address = address;
                         }
                     };
@@ -31,7 +31,7 @@ use tokio::sync::{mpsc, oneshot};
 use std::{
     fmt::Debug,
     future::Future,
-    net::{IpAddr, Ipv4Addr, SocketAddr},
+    net::{IpAddr, SocketAddr},
     time::Duration,
 };
 
@@ -55,7 +55,7 @@ const ADDRESS_REFRESH_TIMER: Duration = Duration::from_secs(5 * 60);
 /// UPnP port mapper.
 pub struct PortMapper {
     /// TX channel for sending external address discoveries.
-    address_tx: mpsc::Sender<Ipv4Addr>,
+    address_tx: mpsc::Sender<IpAddr>,
 
     /// Port forwarding config.
     config: PortForwardingConfig,
@@ -76,7 +76,7 @@ impl PortMapper {
         config: PortForwardingConfig,
         ntcp2_port: Option<u16>,
         ssu2_port: Option<u16>,
-        address_tx: mpsc::Sender<Ipv4Addr>,
+        address_tx: mpsc::Sender<IpAddr>,
         shutdown_rx: oneshot::Receiver<oneshot::Sender<()>>,
     ) -> Self {
         Self {
@@ -237,20 +237,10 @@ impl PortMapper {
                     );
                     None
                 }
-                Ok(address) => match address {
-                    IpAddr::V4(address) => {
-                        let _ = self.address_tx.send(address).await;
-                        Some(address)
-                    }
-                    IpAddr::V6(address) => {
-                        tracing::warn!(
-                            target: LOG_TARGET,
-                            ?address,
-                            "ignoring ipv6 external address",
-                        );
-                        None
-                    }
-                },
+                Ok(address) => {
+                    let _ = self.address_tx.send(address).await;
+                    Some(address)
+                }
             };
 
         let mut address_timer = Box::pin(tokio::time::sleep(ADDRESS_REFRESH_TIMER));
@@ -303,18 +293,9 @@ impl PortMapper {
                             target: LOG_TARGET,
                             "failed to fetch external address",
                         ),
-                        Ok(address) => match address {
-                            IpAddr::V4(address) => {
-                                if Some(address) != external_address {
-                                    let _ = self.address_tx.send(address).await;
-                                    external_address = Some(address);
-                                }
-                            }
-                            IpAddr::V6(address) => tracing::warn!(
-                                target: LOG_TARGET,
-                                ?address,
-                                "ignoring external ipv6 address",
-                            ),
+                        Ok(address) => if Some(address) != external_address {
+                            let _ = self.address_tx.send(address).await;
+                            external_address = Some(address);
                         },
                     };
                 }
@@ -47,6 +47,15 @@ pub enum HttpError {
 
     /// Received partial request.
     PartialRequest,
+
+    /// Address helper contained an invalid destination.
+    InvalidAddressHelper,
+
+    /// Address helper was not found or it has expired.
+    UnknownAddressHelper,
+
+    /// Failed to save host into the local address book.
+    AddressBookUpdateFailed,
 }
 
 impl From<std::io::Error> for HttpError {
@@ -78,6 +87,11 @@ impl std::fmt::Display for HttpError {
                 "Cannot connect to clearnet address, outproxy not enabled"
             ),
             HttpError::PartialRequest => write!(f, "Partial request"),
+            HttpError::InvalidAddressHelper =>
+                write!(f, "Address helper contains an invalid destination"),
+            HttpError::UnknownAddressHelper => write!(f, "Address helper not found or expired"),
+            HttpError::AddressBookUpdateFailed =>
+                write!(f, "Failed to save host into local address book"),
         }
     }
 }
@@ -0,0 +1,293 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Address helper support.
+//!
+//! Address helpers are `i2paddresshelper=<destination>` query parameters which tell the proxy
+//! which destination a .i2p host points to. The mapping is not used directly but offered to the
+//! user who can choose to save it into the local address book or to continue to the site without
+//! saving it.
+
+use emissary_core::{
+    crypto::{base32_encode, base64_decode},
+    primitives::Destination,
+};
+use parking_lot::Mutex;
+use rand::{thread_rng, RngCore};
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// How long is a pending address helper kept around.
+const PENDING_HELPER_EXPIRATION: Duration = Duration::from_secs(10 * 60);
+
+/// Maximum number of pending address helpers.
+const MAX_PENDING_HELPERS: usize = 64usize;
+
+/// Validated address helper.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressHelper {
+    /// .i2p host.
+    pub host: String,
+
+    /// Base64-encoded destination.
+    pub destination: String,
+
+    /// .b32.i2p address of the destination, without the `.b32.i2p` suffix.
+    pub b32: String,
+}
+
+impl AddressHelper {
+    /// Create new [`AddressHelper`].
+    ///
+    /// Returns `None` if `destination` is not a valid base64-encoded destination.
+    pub fn new(host: String, destination: String) -> Option<Self> {
+        let b32 = base64_decode(&destination)
+            .and_then(Destination::parse)
+            .map(|destination| base32_encode(destination.id().to_vec()))?;
+
+        Some(Self {
+            host,
+            destination,
+            b32,
+        })
+    }
+}
+
+/// Address helper waiting for the user's confirmation.
+struct PendingHelper {
+    /// Address helper.
+    helper: AddressHelper,
+
+    /// URL the user is redirected to after the helper has been saved.
+    url: String,
+
+    /// When was the helper created.
+    created: Instant,
+}
+
+/// Address helpers waiting for the user's confirmation, indexed by a random nonce.
+///
+/// The nonce is included in the link which saves the address helper into the local address book
+/// which prevents sites from adding arbitrary entries into the address book.
+#[derive(Clone, Default)]
+pub struct PendingHelpers {
+    /// Pending address helpers.
+    helpers: Arc<Mutex<HashMap<String, PendingHelper>>>,
+}
+
+impl PendingHelpers {
+    /// Store `helper` and return the nonce which can be used to fetch it.
+    ///
+    /// `url` is where the user should be redirected after the helper has been saved.
+    ///
+    /// If the same helper is already pending for `url`, its nonce is returned so a site that keeps
+    /// triggering the confirmation page cannot push other pending helpers out.
+    pub fn insert(&self, helper: AddressHelper, url: String) -> String {
+        let mut helpers = self.helpers.lock();
+        helpers.retain(|_, pending| pending.created.elapsed() < PENDING_HELPER_EXPIRATION);
+
+        if let Some((nonce, _)) = helpers
+            .iter()
+            .find(|(_, pending)| pending.helper == helper && pending.url == url)
+        {
+            return nonce.clone();
+        }
+
+        if helpers.len() >= MAX_PENDING_HELPERS {
+            if let Some(oldest) = helpers
+                .iter()
+                .min_by_key(|(_, pending)| pending.created)
+                .map(|(nonce, _)| nonce.clone())
+            {
+                helpers.remove(&oldest);
+            }
+        }
+
+        let nonce = {
+            let mut nonce = [0u8; 16];
+            thread_rng().fill_bytes(&mut nonce);
+
+            nonce.iter().map(|byte| format!("{byte:02x}")).collect::<String>()
+        };
+
+        helpers.insert(
+            nonce.clone(),
+            PendingHelper {
+                helper,
+                url,
+                created: Instant::now(),
+            },
+        );
+
+        nonce
+    }
+
+    /// Take address helper and the URL associated with `nonce`, if it exists and hasn't expired.
+    pub fn take(&self, nonce: &str) -> Option<(AddressHelper, String)> {
+        let mut helpers = self.helpers.lock();
+        helpers.retain(|_, pending| pending.created.elapsed() < PENDING_HELPER_EXPIRATION);
+
+        helpers.remove(nonce).map(|pending| (pending.helper, pending.url))
+    }
+}
+
+/// Create request for jump service `service` to look up `host`.
+///
+/// `service` is an URL prefix, such as `http://stats.i2p/cgi-bin/jump.cgi?a=`, which is
+/// concatenated with `host`.
+///
+/// Returns the host of the jump service and the serialized request.
+pub fn jump_service_request(service: &str, host: &str) -> Option<(String, Vec<u8>)> {
+    let url = url::Url::parse(&format!("{service}{host}")).ok()?;
+    let service_host = url.host_str()?;
+    let service_host = service_host.strip_prefix("www.").unwrap_or(service_host);
+
+    if !service_host.ends_with(".i2p") {
+        return None;
+    }
+
+    let path = match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_string(),
+    };
+
+    Some((
+        service_host.to_string(),
+        format!(
+            "GET {path} HTTP/1.1\r\n\
+            Host: {service_host}\r\n\
+            User-Agent: MYOB/6.66 (AN/ON)\r\n\
+            Connection: close\r\n\r\n"
+        )
+        .into_bytes(),
+    ))
+}
+
+/// Extract destination of the first address helper found in a response of a jump service.
+///
+/// Jump services either redirect the client to a URL containing the address helper or return a
+/// page which links to such URL so both headers and the body are searched.
+pub fn extract_address_helper(response: &[u8]) -> Option<String> {
+    let response = String::from_utf8_lossy(response);
+    let (_, rest) = response.split_once("i2paddresshelper=")?;
+
+    let destination = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '~' | '=' | '%'))
+        .collect::<String>()
+        .replace("%3D", "=")
+        .replace("%3d", "=");
+
+    (!destination.is_empty()).then_some(destination)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESTINATION: &str = "GKapJ8koUcBj~jmQzHsTYxDg2tpfWj0xjQTzd8BhfC9c3OS5fwPBNajgF-eOD6eCjFTqTlorlh7Hnd8kXj1qblUGXT-tDoR9~YV8dmXl51cJn9MVTRrEqRWSJVXbUUz9t5Po6Xa247Vr0sJn27R4KoKP8QVj1GuH6dB3b6wTPbOamC3dkO18vkQkfZWUdRMDXk0d8AdjB0E0864nOT~J9Fpnd2pQE5uoFT6P0DqtQR2jsFvf9ME61aqLvKPPWpkgdn4z6Zkm-NJOcDz2Nv8Si7hli94E9SghMYRsdjU-knObKvxiagn84FIwcOpepxuG~kFXdD5NfsH0v6Uri3usE3XWD7Pw6P8qVYF39jUIq4OiNMwPnNYzy2N4mDMQdsdHO3LUVh~DEppOy9AAmEoHDjjJxt2BFBbGxfdpZCpENkwvmZeYUyNCCzASqTOOlNzdpne8cuesn3NDXIpNnqEE6Oe5Qm5YOJykrX~Vx~cFFT3QzDGkIjjxlFBsjUJyYkFjBQAEAAcAAA==";
+
+    #[test]
+    fn valid_address_helper() {
+        let helper = AddressHelper::new("zzz.i2p".to_string(), DESTINATION.to_string()).unwrap();
+
+        assert_eq!(
+            helper.b32,
+            "lhbd7ojcaiofbfku7ixh47qj537g572zmhdc4oilvugzxdpdghua"
+        );
+        assert!(AddressHelper::new("zzz.i2p".to_string(), "invalid".to_string()).is_none());
+    }
+
+    #[test]
+    fn pending_helper_taken_once() {
+        let pending = PendingHelpers::default();
+        let helper = AddressHelper::new("zzz.i2p".to_string(), DESTINATION.to_string()).unwrap();
+        let nonce = pending.insert(helper.clone(), "http://zzz.i2p/".to_string());
+
+        assert!(pending.take("invalid").is_none());
+        assert_eq!(
+            pending.take(&nonce),
+            Some((helper, "http://zzz.i2p/".to_string()))
+        );
+        assert!(pending.take(&nonce).is_none());
+    }
+
+    #[test]
+    fn pending_helpers_bounded() {
+        let pending = PendingHelpers::default();
+        let helper = AddressHelper::new("zzz.i2p".to_string(), DESTINATION.to_string()).unwrap();
+
+        // same helper for the same url reuses the nonce
+        let nonce = pending.insert(helper.clone(), "http://zzz.i2p/".to_string());
+        assert_eq!(
+            pending.insert(helper.clone(), "http://zzz.i2p/".to_string()),
+            nonce
+        );
+        assert_eq!(pending.helpers.lock().len(), 1);
+
+        // the oldest helper is evicted once the limit is reached
+        let nonces = (0..MAX_PENDING_HELPERS)
+            .map(|i| pending.insert(helper.clone(), format!("http://zzz.i2p/{i}")))
+            .collect::<Vec<_>>();
+
+        assert_eq!(pending.helpers.lock().len(), MAX_PENDING_HELPERS);
+        assert!(pending.take(&nonce).is_none());
+        assert!(nonces.iter().all(|nonce| pending.take(nonce).is_some()));
+    }
+
+    #[test]
+    fn jump_service_request_created() {
+        let (host, request) =
+            jump_service_request("http://stats.i2p/cgi-bin/jump.cgi?a=", "zzz.i2p").unwrap();
+
+        assert_eq!(host, "stats.i2p");
+        assert!(std::str::from_utf8(&request)
+            .unwrap()
+            .starts_with("GET /cgi-bin/jump.cgi?a=zzz.i2p HTTP/1.1\r\nHost: stats.i2p\r\n"));
+
+        // clearnet jump services are not supported
+        assert!(jump_service_request("http://example.com/jump/", "zzz.i2p").is_none());
+    }
+
+    #[test]
+    fn address_helper_extracted_from_response() {
+        let response = format!(
+            "HTTP/1.1 301 Moved Permanently\r\n\
+            Location: http://zzz.i2p/?i2paddresshelper={DESTINATION}\r\n\r\n"
+        );
+        assert_eq!(
+            extract_address_helper(response.as_bytes()),
+            Some(DESTINATION.to_string())
+        );
+
+        let response = format!(
+            "HTTP/1.1 200 OK\r\n\r\n<a href=\"http://zzz.i2p/?i2paddresshelper={}\">zzz.i2p</a>",
+            DESTINATION.replace("=", "%3D"),
+        );
+        assert_eq!(
+            extract_address_helper(response.as_bytes()),
+            Some(DESTINATION.to_string())
+        );
+
+        assert!(extract_address_helper(b"HTTP/1.1 404 Not Found\r\n\r\n").is_none());
+    }
+}
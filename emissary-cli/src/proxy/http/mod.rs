@@ -17,11 +17,15 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
+    address_book::AddressBookHandle,
     config::HttpProxyConfig,
     proxy::http::{
         error::HttpError,
-        request::Request,
-        response::{send_response, Status},
+        helper::{extract_address_helper, jump_service_request, AddressHelper, PendingHelpers},
+        pool::StreamPool,
+        relay::{relay, Outcome, RelayError},
+        request::{HostKind, Request},
+        response::{send_address_helper, send_redirect, send_response, Status},
     },
 };
 
@@ -34,15 +38,35 @@ use tokio::{
 };
 use yosemite::{style, Session, SessionOptions, StreamOptions};
 
-use std::{sync::Arc, time::Duration};
+use std::{future::Future, sync::Arc, time::Duration};
 
 mod error;
+mod helper;
+mod pool;
+mod relay;
 mod request;
 mod response;
 
 /// Logging target for the file.
 const LOG_TARGET: &str = "emissary::proxy::http";
 
+/// Host used to address the proxy itself.
+const PROXY_HOST: &str = "proxy.i2p";
+
+/// Timeout for a jump service query.
+const JUMP_SERVICE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum size of a jump service response.
+const MAX_JUMP_RESPONSE_SIZE: u64 = 64 * 1024;
+
+/// How long is the next request waited for on a persistent client connection.
+const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Methods of requests which can be retried over a new stream if an idle stream was closed.
+///
+/// Other requests may have been processed by the remote, see RFC 7230, section 6.3.1.
+const RETRYABLE_METHODS: [&str; 4] = ["GET", "HEAD", "OPTIONS", "TRACE"];
+
 /// Request context.
 #[derive(Debug)]
 struct RequestContext {
@@ -53,10 +77,43 @@ struct RequestContext {
     request: Request,
 }
 
+/// Context for relaying a request to remote destination.
+struct RelayContext<F> {
+    /// Client's TCP stream.
+    stream: TcpStream,
+
+    /// .b32.i2p host of the remote destination.
+    host: String,
+
+    /// Method of the request.
+    method: String,
+
+    /// Assembled request.
+    request: Vec<u8>,
+
+    /// How many bytes of the request body must still be read from the client.
+    body_remaining: u64,
+
+    /// Can the client connection be used for further requests.
+    keep_alive: bool,
+
+    /// Data received from the client after the request.
+    pipelined: Vec<u8>,
+
+    /// Idle stream to the remote destination, if one was found from the pool.
+    idle: Option<yosemite::Stream>,
+
+    /// Future for opening a new stream to the remote destination.
+    future: F,
+
+    /// Pool of idle streams.
+    pool: StreamPool<yosemite::Stream>,
+}
+
 /// HTTP proxy.
 pub struct HttpProxy {
-    /// Handle to [`AddressBook`], if it was enabled.
-    address_book_handle: Option<Arc<dyn AddressBook>>,
+    /// Handle to the address book, if it was enabled.
+    address_book_handle: Option<Arc<AddressBookHandle>>,
 
     // TCP listener.
     listener: TcpListener,
@@ -69,6 +126,15 @@ pub struct HttpProxy {
 
     /// HTTP outproxy, if enabled.
     outproxy: Option<String>,
+
+    /// Jump services used to look up hosts which are not in the address book.
+    jump_services: Vec<String>,
+
+    /// Address helpers waiting for the user's confirmation.
+    pending_helpers: PendingHelpers,
+
+    /// Idle streams to remote destinations.
+    pool: StreamPool<yosemite::Stream>,
 }
 
 impl HttpProxy {
@@ -80,13 +146,14 @@ impl HttpProxy {
         config: HttpProxyConfig,
         samv3_tcp_port: u16,
         http_proxy_ready_tx: Option<oneshot::Sender<()>>,
-        address_book_handle: Option<Arc<dyn AddressBook>>,
+        address_book_handle: Option<Arc<AddressBookHandle>>,
     ) -> crate::Result<Self> {
         tracing::info!(
             target: LOG_TARGET,
             host = %config.host,
             port = %config.port,
             outproxy = ?config.outproxy,
+            jump_services = ?config.jump_services,
             "starting http proxy",
         );
 
@@ -158,8 +225,11 @@ impl HttpProxy {
 
         Ok(Self {
             address_book_handle,
+            jump_services: config.jump_services.unwrap_or_default(),
             listener,
             outproxy,
+            pending_helpers: PendingHelpers::default(),
+            pool: StreamPool::default(),
             requests: JoinSet::new(),
             session,
         })
@@ -170,24 +240,31 @@ impl HttpProxy {
     /// Parses and validates the received request and returns [`RequestContext`] which contains the
     /// validated request and the TCP stream of the client which is used to send the response or an
     /// error.
-    async fn read_request(mut stream: TcpStream) -> Result<RequestContext, (TcpStream, HttpError)> {
+    ///
+    /// `pipelined` contains data that was received after the previous request on the same
+    /// connection.
+    async fn read_request(
+        mut stream: TcpStream,
+        pipelined: Vec<u8>,
+    ) -> Result<RequestContext, (TcpStream, HttpError)> {
         let mut buffer = vec![0u8; 8192];
-        let mut nread = 0usize;
+        let mut nread = pipelined.len();
+        buffer[..nread].copy_from_slice(&pipelined);
 
         // read from `stream` until complete request has been received
         loop {
-            nread += match stream.read(&mut buffer[nread..]).await {
-                Err(error) => return Err((stream, HttpError::Io(error.kind()))),
-                Ok(0) => return Err((stream, HttpError::Io(std::io::ErrorKind::BrokenPipe))),
-                Ok(nread) => nread,
-            };
-
             let mut headers = [httparse::EMPTY_HEADER; 64];
             match httparse::Request::new(&mut headers).parse(&buffer[..nread]) {
                 Err(_) => return Err((stream, HttpError::Malformed)),
                 Ok(request) if request.is_complete() => break,
                 Ok(_) => {}
             }
+
+            nread += match stream.read(&mut buffer[nread..]).await {
+                Err(error) => return Err((stream, HttpError::Io(error.kind()))),
+                Ok(0) => return Err((stream, HttpError::Io(std::io::ErrorKind::BrokenPipe))),
+                Ok(nread) => nread,
+            };
         }
 
         match Request::parse(buffer[..nread].to_vec()) {
@@ -203,18 +280,50 @@ impl HttpProxy {
     ///
     /// If the outbound request was for an outproxy, ensures that an outproxy has been configured.
     ///
-    /// After the final request has been assembled and the host has been resolved, opens a stream to
-    /// the remote destination and if a connection is successfully established, sends the request
-    /// and reads the response which is relayed to client.
+    /// Requests to [`PROXY_HOST`] and requests containing an address helper are handled by the
+    /// proxy itself. If a .i2p host is not found in the address book, the configured jump services
+    /// are queried for the host.
+    ///
+    /// After the final request has been assembled and the host has been resolved, takes an idle
+    /// stream to the remote destination from the pool or opens a new stream, sends the request and
+    /// relays the response to client. `CONNECT` requests and requests whose body length is unknown
+    /// are tunneled until either side closes the connection.
     async fn on_request(&mut self, request: RequestContext) -> Result<(), (TcpStream, HttpError)> {
         let RequestContext {
             mut stream,
-            request,
+            mut request,
         } = request;
 
+        let i2p_host = match request.host() {
+            HostKind::I2p { host } => Some(host.clone()),
+            _ => None,
+        };
+
+        if let Some(host) = &i2p_host {
+            if host == PROXY_HOST {
+                return self.on_proxy_request(stream, request).await;
+            }
+
+            if let Some(destination) = request.take_address_helper() {
+                return self
+                    .on_address_helper(stream, host.clone(), destination, request.path())
+                    .await;
+            }
+        }
+        let path = request.path().to_string();
+        let method = request.method().to_string();
+        let keep_alive = request.keep_alive();
+        let tunnel = request.tunnel();
+        let body_remaining = request.body_remaining();
+        let pipelined = request.take_pipelined();
+
         let (host, request) =
             match request.assemble(&self.address_book_handle, &self.outproxy).await {
                 Ok((host, request)) => (host, request),
+                Err(HttpError::HostNotFound) if !self.jump_services.is_empty() => match i2p_host {
+                    Some(host) => return self.on_host_not_found(stream, host, path).await,
+                    None => return Err((stream, HttpError::HostNotFound)),
+                },
                 Err(error) => return Err((stream, error)),
             };
 
@@ -226,6 +335,23 @@ impl HttpProxy {
             },
         );
 
+        if !tunnel {
+            self.requests.spawn(Self::relay_request(RelayContext {
+                stream,
+                idle: self.pool.take(&host),
+                host,
+                method,
+                request,
+                body_remaining,
+                keep_alive,
+                pipelined,
+                future,
+                pool: self.pool.clone(),
+            }));
+
+            return Ok(());
+        }
+
         tokio::spawn(async move {
             match future.await {
                 Err(error) => {
@@ -251,6 +377,339 @@ impl HttpProxy {
         Ok(())
     }
 
+    /// Relay request to remote destination and the response back to client.
+    ///
+    /// If an idle stream to the destination was found, it's used for the request and if it turns
+    /// out the remote has closed it, the request is retried over a new stream if the request method
+    /// is idempotent and no part of the request body has been relayed. Otherwise the client is
+    /// sent an error response.
+    ///
+    /// If the stream can be reused after the response has been relayed, it's returned to the pool
+    /// and if the client connection can also be reused, the next request is read from the client
+    /// and returned to the event loop of [`HttpProxy`].
+    async fn relay_request<F>(context: RelayContext<F>) -> Option<RequestContext>
+    where
+        F: Future<Output = yosemite::Result<yosemite::Stream>>,
+    {
+        let RelayContext {
+            mut stream,
+            host,
+            method,
+            request,
+            body_remaining,
+            keep_alive,
+            pipelined,
+            idle,
+            future,
+            pool,
+        } = context;
+
+        let (mut i2p_stream, outcome) = 'outcome: {
+            if let Some(mut i2p_stream) = idle {
+                match relay(
+                    &mut i2p_stream,
+                    &mut stream,
+                    &method,
+                    &request,
+                    body_remaining,
+                )
+                .await
+                {
+                    Ok(outcome) => break 'outcome (i2p_stream, outcome),
+                    Err(RelayError::NoResponse)
+                        if body_remaining == 0 && RETRYABLE_METHODS.contains(&method.as_str()) =>
+                        tracing::trace!(
+                            target: LOG_TARGET,
+                            %host,
+                            "idle stream closed by remote, opening new stream",
+                        ),
+                    Err(RelayError::NoResponse) => {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            %host,
+                            %method,
+                            "idle stream closed by remote, cannot retry request",
+                        );
+                        send_response(stream, Status::BadGateway(host)).await;
+                        return None;
+                    }
+                    Err(error) => {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            %host,
+                            %error,
+                            "failed to relay request",
+                        );
+                        return None;
+                    }
+                }
+            }
+
+            let mut i2p_stream = match future.await {
+                Ok(i2p_stream) => i2p_stream,
+                Err(error) => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?error,
+                        "failed to connect to destination",
+                    );
+                    send_response(stream, Status::GatewayTimeout(host)).await;
+                    return None;
+                }
+            };
+
+            match relay(
+                &mut i2p_stream,
+                &mut stream,
+                &method,
+                &request,
+                body_remaining,
+            )
+            .await
+            {
+                Ok(outcome) => (i2p_stream, outcome),
+                Err(error) => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        %host,
+                        %error,
+                        "failed to relay request",
+                    );
+                    return None;
+                }
+            }
+        };
+
+        match outcome {
+            Outcome::Close => None,
+            Outcome::Upgrade => {
+                let _ = tokio::io::copy_bidirectional(&mut i2p_stream, &mut stream).await;
+                None
+            }
+            Outcome::Reusable => {
+                pool.insert(host, i2p_stream);
+
+                if !keep_alive {
+                    return None;
+                }
+
+                match tokio::time::timeout(
+                    KEEP_ALIVE_TIMEOUT,
+                    Self::read_request(stream, pipelined),
+                )
+                .await
+                {
+                    Err(_) | Ok(Err((_, HttpError::Io(_)))) => None,
+                    Ok(Ok(request)) => Some(request),
+                    Ok(Err((stream, error))) => {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            ?error,
+                            "failed to read request from persistent connection",
+                        );
+                        send_response(stream, Status::BadRequest(error)).await;
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    /// Handle request made to the proxy itself.
+    ///
+    /// The only supported request is `/add?nonce=<nonce>` which saves a pending address helper
+    /// into the local address book and redirects the client to the original URL.
+    async fn on_proxy_request(
+        &mut self,
+        stream: TcpStream,
+        request: Request,
+    ) -> Result<(), (TcpStream, HttpError)> {
+        let Some(nonce) = request.path().strip_prefix("/add?nonce=") else {
+            return Err((stream, HttpError::InvalidPath));
+        };
+
+        let Some((helper, url)) = self.pending_helpers.take(nonce) else {
+            return Err((stream, HttpError::UnknownAddressHelper));
+        };
+
+        let Some(address_book) = &self.address_book_handle else {
+            return Err((stream, HttpError::AddressBookNotEnabled));
+        };
+
+        if !address_book.add_local(helper.host.clone(), helper.destination).await {
+            return Err((stream, HttpError::AddressBookUpdateFailed));
+        }
+
+        tracing::info!(
+            target: LOG_TARGET,
+            host = %helper.host,
+            b32 = %helper.b32,
+            "address helper saved",
+        );
+
+        send_redirect(stream, &url).await;
+        Ok(())
+    }
+
+    /// Handle address helper for `host`.
+    ///
+    /// If the host already exists in the address book with the same destination, the client is
+    /// redirected to the original URL without the address helper. Otherwise the address helper is
+    /// stored as pending and the client is shown a page where it can be saved.
+    async fn on_address_helper(
+        &mut self,
+        stream: TcpStream,
+        host: String,
+        destination: String,
+        path: &str,
+    ) -> Result<(), (TcpStream, HttpError)> {
+        let Some(helper) = AddressHelper::new(host, destination) else {
+            return Err((stream, HttpError::InvalidAddressHelper));
+        };
+        let url = format!("http://{}{path}", helper.host);
+
+        let Some(address_book) = &self.address_book_handle else {
+            send_address_helper(stream, &helper, path, None, false).await;
+            return Ok(());
+        };
+
+        match address_book.resolve_b64(helper.host.clone()).await {
+            Some(existing) if existing == helper.destination => send_redirect(stream, &url).await,
+            existing => {
+                if existing.is_some() {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        host = %helper.host,
+                        "address helper conflicts with address book",
+                    );
+                }
+
+                let nonce = self.pending_helpers.insert(helper.clone(), url);
+                send_address_helper(stream, &helper, path, Some(&nonce), existing.is_some()).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Query the configured jump services for `host` which was not found in the address book.
+    ///
+    /// Jump services are queried in order and the first valid address helper is offered to the
+    /// client for saving.
+    async fn on_host_not_found(
+        &mut self,
+        stream: TcpStream,
+        host: String,
+        path: String,
+    ) -> Result<(), (TcpStream, HttpError)> {
+        let Some(address_book) = self.address_book_handle.clone() else {
+            return Err((stream, HttpError::AddressBookNotEnabled));
+        };
+        let mut lookups = Vec::new();
+
+        for service in self.jump_services.clone() {
+            let Some((service_host, request)) = jump_service_request(&service, &host) else {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    %service,
+                    "invalid jump service",
+                );
+                continue;
+            };
+
+            let b32 = match service_host.ends_with(".b32.i2p") {
+                true => service_host.clone(),
+                false => match address_book.resolve_b32(service_host.clone()) {
+                    Either::Left(b32) => format!("{b32}.b32.i2p"),
+                    Either::Right(future) => match future.await {
+                        Some(b32) => format!("{b32}.b32.i2p"),
+                        None => {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                %service_host,
+                                "jump service not found in address book",
+                            );
+                            continue;
+                        }
+                    },
+                },
+            };
+
+            let future = self.session.connect_detached_with_options(
+                &b32,
+                StreamOptions {
+                    dst_port: 80,
+                    ..Default::default()
+                },
+            );
+            lookups.push((service_host, request, future));
+        }
+
+        if lookups.is_empty() {
+            return Err((stream, HttpError::HostNotFound));
+        }
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            %host,
+            num_services = ?lookups.len(),
+            "querying jump services",
+        );
+
+        let pending_helpers = self.pending_helpers.clone();
+
+        tokio::spawn(async move {
+            for (service_host, request, future) in lookups {
+                let response = tokio::time::timeout(JUMP_SERVICE_TIMEOUT, async move {
+                    let mut i2p_stream = future.await.ok()?;
+                    i2p_stream.write_all(&request).await.ok()?;
+
+                    let mut response = Vec::new();
+                    i2p_stream
+                        .take(MAX_JUMP_RESPONSE_SIZE)
+                        .read_to_end(&mut response)
+                        .await
+                        .ok()?;
+
+                    Some(response)
+                })
+                .await;
+
+                let Some(destination) =
+                    response.ok().flatten().and_then(|response| extract_address_helper(&response))
+                else {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        %host,
+                        %service_host,
+                        "host not found from jump service",
+                    );
+                    continue;
+                };
+
+                match AddressHelper::new(host.clone(), destination) {
+                    None => tracing::debug!(
+                        target: LOG_TARGET,
+                        %host,
+                        %service_host,
+                        "jump service returned an invalid destination",
+                    ),
+                    Some(helper) => {
+                        let nonce =
+                            pending_helpers.insert(helper.clone(), format!("http://{host}{path}"));
+
+                        return send_address_helper(stream, &helper, &path, Some(&nonce), false)
+                            .await;
+                    }
+                }
+            }
+
+            send_response(stream, Status::BadRequest(HttpError::HostNotFound)).await;
+        });
+
+        Ok(())
+    }
+
     /// Run event loop of [`HttpProxy`].
     pub async fn run(mut self) -> anyhow::Result<()> {
         loop {
@@ -258,7 +717,7 @@ impl HttpProxy {
                 connection = self.listener.accept() => match connection {
                     Ok((stream, _)) => {
                         self.requests.spawn(async move {
-                            match tokio::time::timeout(Duration::from_secs(10), Self::read_request(stream)).await {
+                            match tokio::time::timeout(Duration::from_secs(10), Self::read_request(stream, Vec::new())).await {
                                 Err(_) => None,
                                 Ok(Ok(request)) => Some(request),
                                 Ok(Err((stream, error))) => {
@@ -407,6 +866,7 @@ mod tests {
                 port: 0,
                 host: "127.0.0.1".to_string(),
                 outproxy: None,
+                jump_services: None,
             },
             sam_port,
             None,
@@ -441,6 +901,7 @@ mod tests {
                 port: 0,
                 host: "127.0.0.1".to_string(),
                 outproxy: None,
+                jump_services: None,
             },
             sam_port,
             None,
@@ -499,6 +960,7 @@ mod tests {
                 AddressBookConfig {
                     default: None,
                     subscriptions: None,
+                    feed: None,
                 },
             )
             .handle()
@@ -509,6 +971,7 @@ mod tests {
                 port: 0,
                 host: "127.0.0.1".to_string(),
                 outproxy: None,
+                jump_services: None,
             },
             sam_port,
             None,
@@ -542,6 +1005,141 @@ mod tests {
         };
     }
 
+    #[tokio::test]
+    async fn address_helper_saved_to_local_address_book() {
+        let sam_port = {
+            let sam = SamServer::new().await;
+            let port = sam.listener.local_addr().unwrap().port();
+            tokio::spawn(sam.run());
+
+            port
+        };
+
+        // create empty address book
+        let address_book = {
+            let dir = tempdir().unwrap().keep();
+            tokio::fs::create_dir_all(&dir.join("addressbook")).await.unwrap();
+            tokio::fs::File::create(dir.join("addressbook/addresses")).await.unwrap();
+
+            AddressBookManager::new(
+                dir.clone(),
+                AddressBookConfig {
+                    default: None,
+                    subscriptions: None,
+                    feed: None,
+                },
+            )
+            .handle()
+        };
+
+        let proxy = HttpProxy::new(
+            HttpProxyConfig {
+                port: 0,
+                host: "127.0.0.1".to_string(),
+                outproxy: None,
+                jump_services: None,
+            },
+            sam_port,
+            None,
+            Some(Arc::clone(&address_book)),
+        )
+        .await
+        .unwrap();
+        let port = proxy.listener.local_addr().unwrap().port();
+        tokio::spawn(proxy.run());
+
+        let client = Client::builder()
+            .proxy(Proxy::http(format!("http://127.0.0.1:{port}")).expect("to succeed"))
+            .redirect(reqwest::redirect::Policy::none())
+            .http1_title_case_headers()
+            .build()
+            .expect("to succeed");
+
+        let destination = "GKapJ8koUcBj~jmQzHsTYxDg2tpfWj0xjQTzd8BhfC9c3OS5fwPBNajgF-eOD6eCjFTqTlor\
+            lh7Hnd8kXj1qblUGXT-tDoR9~YV8dmXl51cJn9MVTRrEqRWSJVXbUUz9t5Po6Xa247Vr0sJn27R4KoKP8QVj1GuH6\
+            dB3b6wTPbOamC3dkO18vkQkfZWUdRMDXk0d8AdjB0E0864nOT~J9Fpnd2pQE5uoFT6P0DqtQR2jsFvf9ME61aqLvK\
+            PPWpkgdn4z6Zkm-NJOcDz2Nv8Si7hli94E9SghMYRsdjU-knObKvxiagn84FIwcOpepxuG~kFXdD5NfsH0v6Uri3u\
+            sE3XWD7Pw6P8qVYF39jUIq4OiNMwPnNYzy2N4mDMQdsdHO3LUVh~DEppOy9AAmEoHDjjJxt2BFBbGxfdpZCpENkwv\
+            mZeYUyNCCzASqTOOlNzdpne8cuesn3NDXIpNnqEE6Oe5Qm5YOJykrX~Vx~cFFT3QzDGkIjjxlFBsjUJyYkFjBQAEAA\
+            cAAA==";
+
+        // address helper is offered to the user
+        let body = match client
+            .get(format!(
+                "http://host.i2p/page?i2paddresshelper={destination}"
+            ))
+            .headers(HeaderMap::from_iter([(
+                CONNECTION,
+                HeaderValue::from_static("close"),
+            )]))
+            .send()
+            .await
+        {
+            Err(error) => panic!("failure: {error:?}"),
+            Ok(response) => {
+                assert_eq!(response.status(), StatusCode::OK);
+                response.text().await.unwrap()
+            }
+        };
+
+        assert!(body
+            .contains("http://lhbd7ojcaiofbfku7ixh47qj537g572zmhdc4oilvugzxdpdghua.b32.i2p/page"));
+        let nonce = body
+            .split_once("http://proxy.i2p/add?nonce=")
+            .unwrap()
+            .1
+            .split('"')
+            .next()
+            .unwrap()
+            .to_string();
+
+        // save the address helper
+        match client
+            .get(format!("http://proxy.i2p/add?nonce={nonce}"))
+            .headers(HeaderMap::from_iter([(
+                CONNECTION,
+                HeaderValue::from_static("close"),
+            )]))
+            .send()
+            .await
+        {
+            Err(error) => panic!("failure: {error:?}"),
+            Ok(response) => {
+                assert_eq!(response.status(), StatusCode::FOUND);
+                assert_eq!(
+                    response.headers().get("location").unwrap(),
+                    "http://host.i2p/page"
+                );
+            }
+        }
+
+        assert_eq!(
+            address_book.resolve_b64("host.i2p".to_string()).await.as_deref(),
+            Some(destination)
+        );
+
+        // nonce can only be used once
+        match client
+            .get(format!("http://proxy.i2p/add?nonce={nonce}"))
+            .headers(HeaderMap::from_iter([(
+                CONNECTION,
+                HeaderValue::from_static("close"),
+            )]))
+            .send()
+            .await
+        {
+            Err(error) => panic!("failure: {error:?}"),
+            Ok(response) => {
+                assert_eq!(response.status(), StatusCode::from_u16(400).unwrap());
+                assert!(response
+                    .text()
+                    .await
+                    .unwrap()
+                    .contains("Address helper not found or expired"));
+            }
+        }
+    }
+
     #[tokio::test]
     async fn outproxy_not_configured() {
         let sam_port = {
@@ -557,6 +1155,7 @@ mod tests {
                 port: 0,
                 host: "127.0.0.1".to_string(),
                 outproxy: None,
+                jump_services: None,
             },
             sam_port,
             None,
@@ -609,6 +1208,7 @@ mod tests {
                 port: 0,
                 host: "127.0.0.1".to_string(),
                 outproxy: Some("outproxy.i2p".to_string()),
+                jump_services: None,
             },
             sam_port,
             None,
@@ -667,6 +1267,7 @@ mod tests {
                 AddressBookConfig {
                     default: None,
                     subscriptions: None,
+                    feed: None,
                 },
             )
             .handle()
@@ -677,6 +1278,7 @@ mod tests {
                 port: 0,
                 host: "127.0.0.1".to_string(),
                 outproxy: Some("outproxy.i2p".to_string()),
+                jump_services: None,
             },
             sam_port,
             None,
@@ -763,6 +1365,7 @@ mod tests {
                 AddressBookConfig {
                     default: None,
                     subscriptions: None,
+                    feed: None,
                 },
             )
             .handle()
@@ -775,6 +1378,7 @@ mod tests {
                     port: 0,
                     host: "127.0.0.1".to_string(),
                     outproxy: Some("zzz.i2p".to_string()),
+                    jump_services: None,
                 },
                 sam_port,
                 None,
@@ -796,6 +1400,7 @@ mod tests {
                     port: 0,
                     host: "127.0.0.1".to_string(),
                     outproxy: Some("www.zzz.i2p".to_string()),
+                    jump_services: None,
                 },
                 sam_port,
                 None,
@@ -817,6 +1422,7 @@ mod tests {
                     port: 0,
                     host: "127.0.0.1".to_string(),
                     outproxy: Some("http://zzz.i2p".to_string()),
+                    jump_services: None,
                 },
                 sam_port,
                 None,
@@ -838,6 +1444,7 @@ mod tests {
                     port: 0,
                     host: "127.0.0.1".to_string(),
                     outproxy: Some("http://www.zzz.i2p".to_string()),
+                    jump_services: None,
                 },
                 sam_port,
                 None,
@@ -862,6 +1469,7 @@ mod tests {
                         "http://www.lhbd7ojcaiofbfku7ixh47qj537g572zmhdc4oilvugzxdpdghua.b32.i2p"
                             .to_string(),
                     ),
+                    jump_services: None,
                 },
                 sam_port,
                 None,
@@ -886,6 +1494,7 @@ mod tests {
                         "http://lhbd7ojcaiofbfku7ixh47qj537g572zmhdc4oilvugzxdpdghua.b32.i2p"
                             .to_string(),
                     ),
+                    jump_services: None,
                 },
                 sam_port,
                 None,
@@ -910,6 +1519,7 @@ mod tests {
                         "www.lhbd7ojcaiofbfku7ixh47qj537g572zmhdc4oilvugzxdpdghua.b32.i2p"
                             .to_string(),
                     ),
+                    jump_services: None,
                 },
                 sam_port,
                 None,
@@ -933,6 +1543,7 @@ mod tests {
                     outproxy: Some(
                         "lhbd7ojcaiofbfku7ixh47qj537g572zmhdc4oilvugzxdpdghua.b32.i2p".to_string(),
                     ),
+                    jump_services: None,
                 },
                 sam_port,
                 None,
@@ -0,0 +1,172 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Pool of idle I2P streams.
+//!
+//! Once a response has been relayed to the client and the stream can be reused, it's stored in the
+//! pool under the .b32.i2p host of the remote destination. Subsequent requests to the same
+//! destination, either from the same client connection or from a different one, take the most
+//! recently used stream from the pool instead of opening a new stream.
+
+use parking_lot::Mutex;
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// How long can a stream be idle in the pool before it's closed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum number of idle streams per destination.
+const MAX_IDLE_STREAMS_PER_DESTINATION: usize = 4usize;
+
+/// Maximum number of idle streams in total.
+const MAX_IDLE_STREAMS: usize = 64usize;
+
+/// Idle streams of a destination and the times they were returned to the pool.
+type IdleStreams<S> = Vec<(Instant, S)>;
+
+/// Pool of idle streams, indexed by .b32.i2p host.
+pub struct StreamPool<S> {
+    /// Idle streams.
+    streams: Arc<Mutex<HashMap<String, IdleStreams<S>>>>,
+}
+
+impl<S> Clone for StreamPool<S> {
+    fn clone(&self) -> Self {
+        Self {
+            streams: Arc::clone(&self.streams),
+        }
+    }
+}
+
+impl<S> Default for StreamPool<S> {
+    fn default() -> Self {
+        Self {
+            streams: Default::default(),
+        }
+    }
+}
+
+impl<S> StreamPool<S> {
+    /// Take the most recently used idle stream of `host`, if it exists.
+    pub fn take(&self, host: &str) -> Option<S> {
+        let mut streams = self.streams.lock();
+        let idle = streams.get_mut(host)?;
+
+        // streams are stored in the order they were returned to the pool
+        idle.retain(|(since, _)| since.elapsed() < IDLE_TIMEOUT);
+        let stream = idle.pop().map(|(_, stream)| stream);
+
+        if idle.is_empty() {
+            streams.remove(host);
+        }
+
+        stream
+    }
+
+    /// Return idle `stream` of `host` into the pool.
+    ///
+    /// If there are already too many idle streams, the stream that has been idle the longest is
+    /// closed.
+    pub fn insert(&self, host: String, stream: S) {
+        let mut streams = self.streams.lock();
+
+        streams.retain(|_, idle| {
+            idle.retain(|(since, _)| since.elapsed() < IDLE_TIMEOUT);
+            !idle.is_empty()
+        });
+
+        let idle = streams.entry(host).or_default();
+        idle.push((Instant::now(), stream));
+
+        if idle.len() > MAX_IDLE_STREAMS_PER_DESTINATION {
+            idle.remove(0);
+        }
+
+        if streams.values().map(Vec::len).sum::<usize>() > MAX_IDLE_STREAMS {
+            let oldest = streams
+                .iter()
+                .min_by_key(|(_, idle)| idle[0].0)
+                .map(|(host, _)| host.clone())
+                .expect("pool to be non-empty");
+
+            if let Some(idle) = streams.get_mut(&oldest) {
+                idle.remove(0);
+
+                if idle.is_empty() {
+                    streams.remove(&oldest);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_recent_stream_reused() {
+        let pool = StreamPool::<usize>::default();
+
+        pool.insert("host1.b32.i2p".to_string(), 1);
+        pool.insert("host1.b32.i2p".to_string(), 2);
+        pool.insert("host2.b32.i2p".to_string(), 3);
+
+        assert_eq!(pool.take("host1.b32.i2p"), Some(2));
+        assert_eq!(pool.take("host1.b32.i2p"), Some(1));
+        assert_eq!(pool.take("host1.b32.i2p"), None);
+        assert_eq!(pool.take("host2.b32.i2p"), Some(3));
+        assert!(pool.streams.lock().is_empty());
+    }
+
+    #[test]
+    fn idle_streams_limited_per_destination() {
+        let pool = StreamPool::<usize>::default();
+
+        for i in 0..MAX_IDLE_STREAMS_PER_DESTINATION + 2 {
+            pool.insert("host.b32.i2p".to_string(), i);
+        }
+
+        assert_eq!(
+            pool.streams.lock().get("host.b32.i2p").unwrap().len(),
+            MAX_IDLE_STREAMS_PER_DESTINATION
+        );
+        assert_eq!(
+            pool.take("host.b32.i2p"),
+            Some(MAX_IDLE_STREAMS_PER_DESTINATION + 1)
+        );
+    }
+
+    #[test]
+    fn idle_streams_limited_in_total() {
+        let pool = StreamPool::<usize>::default();
+
+        for i in 0..MAX_IDLE_STREAMS + 1 {
+            pool.insert(format!("host{i}.b32.i2p"), i);
+        }
+
+        assert_eq!(
+            pool.streams.lock().values().map(Vec::len).sum::<usize>(),
+            MAX_IDLE_STREAMS
+        );
+    }
+}
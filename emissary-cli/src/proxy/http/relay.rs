@@ -0,0 +1,420 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Relay a request and its response between the client and an I2P stream.
+//!
+//! To allow both the client connection and the I2P stream to be reused for further requests, the
+//! response is parsed just enough to figure out where it ends. Responses without a length are
+//! relayed until the remote closes the stream, after which neither connection can be reused.
+
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+
+/// Maximum size of a response header.
+const MAX_HEADER_SIZE: usize = 64 * 1024;
+
+/// Outcome of a relayed request.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// Response has been relayed and the stream can be used for another request.
+    Reusable,
+
+    /// Response has been relayed and the stream must be closed.
+    Close,
+
+    /// Remote switched protocols and the streams must be connected together.
+    Upgrade,
+}
+
+/// Relay error.
+#[derive(Debug, thiserror::Error)]
+pub enum RelayError {
+    /// Remote closed the stream before sending any part of the response.
+    ///
+    /// The request can be retried over a new stream.
+    #[error("No response")]
+    NoResponse,
+
+    /// Malformed response.
+    #[error("Malformed response")]
+    Malformed,
+
+    /// I/O error.
+    #[error("I/O error: `{0}`")]
+    Io(#[from] std::io::Error),
+}
+
+/// Send `request` over `stream` and relay the response back to `client`.
+///
+/// `request` contains the headers and the part of the body that has already been read and
+/// `body_remaining` bytes of the body are relayed from `client` before reading the response.
+///
+/// `method` is the method of the request, responses to `HEAD` requests never have a body.
+pub async fn relay<S, C>(
+    stream: &mut S,
+    client: &mut C,
+    method: &str,
+    request: &[u8],
+    body_remaining: u64,
+) -> Result<Outcome, RelayError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    C: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(request).await.map_err(|_| RelayError::NoResponse)?;
+
+    if body_remaining > 0 {
+        let copied = tokio::io::copy(&mut client.take(body_remaining), stream).await?;
+
+        if copied != body_remaining {
+            return Err(RelayError::Io(std::io::ErrorKind::UnexpectedEof.into()));
+        }
+    }
+
+    relay_response(stream, client, method == "HEAD").await
+}
+
+/// Read response from `stream` and relay it to `client`.
+///
+/// If `head` is `true`, the response is a response to a `HEAD` request and consists of headers
+/// only, regardless of what `Content-Length` or `Transfer-Encoding` say.
+async fn relay_response<S, C>(
+    stream: &mut S,
+    client: &mut C,
+    head: bool,
+) -> Result<Outcome, RelayError>
+where
+    S: AsyncRead + Unpin,
+    C: AsyncWrite + Unpin,
+{
+    let mut reader = BufReader::new(stream);
+    let mut received = false;
+
+    // skip over informational responses, such as `100 Continue`
+    let (code, framing, close) = loop {
+        let mut header = Vec::new();
+
+        loop {
+            match reader.read_until(b'\n', &mut header).await {
+                Err(_) | Ok(0) if !received => return Err(RelayError::NoResponse),
+                Err(error) => return Err(RelayError::Io(error)),
+                Ok(0) => return Err(RelayError::Io(std::io::ErrorKind::UnexpectedEof.into())),
+                Ok(_) => received = true,
+            }
+
+            if header.ends_with(b"\r\n\r\n") || header.ends_with(b"\n\n") {
+                break;
+            }
+
+            if header.len() > MAX_HEADER_SIZE {
+                return Err(RelayError::Malformed);
+            }
+        }
+
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut response = httparse::Response::new(&mut headers);
+
+        let httparse::Status::Complete(_) =
+            response.parse(&header).map_err(|_| RelayError::Malformed)?
+        else {
+            return Err(RelayError::Malformed);
+        };
+        let code = response.code.ok_or(RelayError::Malformed)?;
+
+        client.write_all(&header).await?;
+
+        if (100..200).contains(&code) && code != 101 {
+            continue;
+        }
+
+        let header_value = |name: &str| {
+            response
+                .headers
+                .iter()
+                .find(|header| header.name.eq_ignore_ascii_case(name))
+                .and_then(|header| std::str::from_utf8(header.value).ok())
+                .map(|value| value.trim().to_ascii_lowercase())
+        };
+
+        // http/1.0 responses close the connection unless explicitly told otherwise
+        let close = match header_value("connection") {
+            Some(value) => value.contains("close"),
+            None => response.version != Some(1),
+        };
+
+        let framing = match (
+            header_value("transfer-encoding"),
+            header_value("content-length"),
+        ) {
+            (Some(encoding), _) if encoding.ends_with("chunked") => Framing::Chunked,
+            (Some(_), _) => Framing::Eof,
+            (None, Some(length)) =>
+                Framing::Length(length.parse::<u64>().map_err(|_| RelayError::Malformed)?),
+            (None, None) => Framing::Eof,
+        };
+
+        break (code, framing, close);
+    };
+
+    if code == 101 {
+        return Ok(Outcome::Upgrade);
+    }
+
+    match code {
+        204 | 304 => {}
+        _ if head => {}
+        _ => match framing {
+            Framing::Length(length) => {
+                let copied = tokio::io::copy(&mut (&mut reader).take(length), client).await?;
+
+                if copied != length {
+                    return Err(RelayError::Io(std::io::ErrorKind::UnexpectedEof.into()));
+                }
+            }
+            Framing::Chunked => relay_chunked(&mut reader, client).await?,
+            Framing::Eof => {
+                tokio::io::copy(&mut reader, client).await?;
+                return Ok(Outcome::Close);
+            }
+        },
+    }
+
+    // if the remote sent more than the response, the stream is in an unknown state
+    match close || !reader.buffer().is_empty() {
+        true => Ok(Outcome::Close),
+        false => Ok(Outcome::Reusable),
+    }
+}
+
+/// How is the end of the response body determined.
+enum Framing {
+    /// `Content-Length`.
+    Length(u64),
+
+    /// `Transfer-Encoding: chunked`.
+    Chunked,
+
+    /// Body ends when the stream is closed.
+    Eof,
+}
+
+/// Relay chunked response body from `reader` to `client`.
+async fn relay_chunked<R, C>(reader: &mut R, client: &mut C) -> Result<(), RelayError>
+where
+    R: AsyncBufRead + Unpin,
+    C: AsyncWrite + Unpin,
+{
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+
+        if reader.read_until(b'\n', &mut line).await? == 0 {
+            return Err(RelayError::Io(std::io::ErrorKind::UnexpectedEof.into()));
+        }
+        client.write_all(&line).await?;
+
+        let size = std::str::from_utf8(&line).map_err(|_| RelayError::Malformed)?;
+        let size = size.split(';').next().unwrap_or_default().trim();
+        let size = u64::from_str_radix(size, 16).map_err(|_| RelayError::Malformed)?;
+
+        // last chunk is followed by optional trailers and an empty line
+        if size == 0 {
+            loop {
+                line.clear();
+
+                if reader.read_until(b'\n', &mut line).await? == 0 {
+                    return Err(RelayError::Io(std::io::ErrorKind::UnexpectedEof.into()));
+                }
+                client.write_all(&line).await?;
+
+                if line == b"\r\n" || line == b"\n" {
+                    return Ok(());
+                }
+            }
+        }
+
+        // chunk data is followed by crlf
+        let copied = tokio::io::copy(&mut (&mut *reader).take(size + 2), client).await?;
+
+        if copied != size + 2 {
+            return Err(RelayError::Io(std::io::ErrorKind::UnexpectedEof.into()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::duplex;
+
+    async fn relay_test(
+        request: &[u8],
+        client_data: &[u8],
+        response: &[u8],
+        body_remaining: u64,
+    ) -> (Result<Outcome, RelayError>, Vec<u8>, Vec<u8>) {
+        let (mut stream, mut remote) = duplex(64 * 1024);
+        let (mut client, mut browser) = duplex(64 * 1024);
+
+        browser.write_all(client_data).await.unwrap();
+        remote.write_all(response).await.unwrap();
+        remote.shutdown().await.unwrap();
+
+        let method = request.split(|byte| *byte == b' ').next().unwrap_or_default();
+        let method = std::str::from_utf8(method).unwrap();
+        let result = relay(&mut stream, &mut client, method, request, body_remaining).await;
+        drop(stream);
+        drop(client);
+
+        let mut sent = Vec::new();
+        remote.read_to_end(&mut sent).await.unwrap();
+
+        let mut received = Vec::new();
+        browser.read_to_end(&mut received).await.unwrap();
+
+        (result, sent, received)
+    }
+
+    #[tokio::test]
+    async fn content_length_response_reusable() {
+        let response = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello";
+        let (result, sent, received) =
+            relay_test(b"GET / HTTP/1.1\r\n\r\n", b"", response, 0).await;
+
+        assert_eq!(result.unwrap(), Outcome::Reusable);
+        assert_eq!(sent, b"GET / HTTP/1.1\r\n\r\n");
+        assert_eq!(received, response);
+    }
+
+    #[tokio::test]
+    async fn chunked_response_reusable() {
+        let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+            5\r\nhello\r\n6;ext=1\r\n world\r\n0\r\nTrailer: 1\r\n\r\n";
+        let (result, _, received) = relay_test(b"GET / HTTP/1.1\r\n\r\n", b"", response, 0).await;
+
+        assert_eq!(result.unwrap(), Outcome::Reusable);
+        assert_eq!(received, response);
+    }
+
+    #[tokio::test]
+    async fn close_delimited_response() {
+        let response = b"HTTP/1.1 200 OK\r\n\r\nhello, world";
+        let (result, _, received) = relay_test(b"GET / HTTP/1.1\r\n\r\n", b"", response, 0).await;
+
+        assert_eq!(result.unwrap(), Outcome::Close);
+        assert_eq!(received, response);
+    }
+
+    #[tokio::test]
+    async fn connection_close_response() {
+        let response = b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 5\r\n\r\nhello";
+        let (result, _, received) = relay_test(b"GET / HTTP/1.1\r\n\r\n", b"", response, 0).await;
+
+        assert_eq!(result.unwrap(), Outcome::Close);
+        assert_eq!(received, response);
+
+        // http/1.0 response without keep-alive
+        let response = b"HTTP/1.0 200 OK\r\nContent-Length: 5\r\n\r\nhello";
+        let (result, _, _) = relay_test(b"GET / HTTP/1.1\r\n\r\n", b"", response, 0).await;
+
+        assert_eq!(result.unwrap(), Outcome::Close);
+    }
+
+    #[tokio::test]
+    async fn informational_response_skipped() {
+        let response = b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 204 No Content\r\n\r\n";
+        let (result, _, received) = relay_test(b"GET / HTTP/1.1\r\n\r\n", b"", response, 0).await;
+
+        assert_eq!(result.unwrap(), Outcome::Reusable);
+        assert_eq!(received, response);
+    }
+
+    #[tokio::test]
+    async fn protocol_upgrade() {
+        let response = b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\r\n";
+        let (result, _, received) = relay_test(b"GET / HTTP/1.1\r\n\r\n", b"", response, 0).await;
+
+        assert_eq!(result.unwrap(), Outcome::Upgrade);
+        assert_eq!(received, response);
+    }
+
+    #[tokio::test]
+    async fn head_response_reusable() {
+        for response in [
+            &b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n"[..],
+            &b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n"[..],
+        ] {
+            let (mut stream, mut remote) = duplex(64 * 1024);
+            let (mut client, mut browser) = duplex(64 * 1024);
+
+            // the stream is kept open as it would be for a pooled stream
+            remote.write_all(response).await.unwrap();
+
+            let result = tokio::time::timeout(
+                std::time::Duration::from_secs(5),
+                relay(
+                    &mut stream,
+                    &mut client,
+                    "HEAD",
+                    b"HEAD / HTTP/1.1\r\n\r\n",
+                    0,
+                ),
+            )
+            .await
+            .expect("no timeout");
+            drop(client);
+
+            let mut received = Vec::new();
+            browser.read_to_end(&mut received).await.unwrap();
+
+            assert_eq!(result.unwrap(), Outcome::Reusable);
+            assert_eq!(received, response);
+        }
+    }
+
+    #[tokio::test]
+    async fn request_body_relayed() {
+        let request = b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nhello";
+        let response = b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
+        let (result, sent, _) = relay_test(request, b"world", response, 5).await;
+
+        assert_eq!(result.unwrap(), Outcome::Reusable);
+        assert_eq!(
+            sent,
+            b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nhelloworld"
+        );
+    }
+
+    #[tokio::test]
+    async fn closed_stream_has_no_response() {
+        let (result, _, received) = relay_test(b"GET / HTTP/1.1\r\n\r\n", b"", b"", 0).await;
+
+        assert!(matches!(result, Err(RelayError::NoResponse)));
+        assert!(received.is_empty());
+    }
+
+    #[tokio::test]
+    async fn truncated_response() {
+        let response = b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nhello";
+        let (result, _, _) = relay_test(b"GET / HTTP/1.1\r\n\r\n", b"", response, 0).await;
+
+        assert!(matches!(result, Err(RelayError::Io(_))));
+    }
+}
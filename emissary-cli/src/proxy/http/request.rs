@@ -16,7 +16,10 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use crate::proxy::http::{HttpError, LOG_TARGET};
+use crate::{
+    address_book::AddressBookHandle,
+    proxy::http::{HttpError, LOG_TARGET},
+};
 
 use emissary_core::runtime::AddressBook;
 use futures::future::Either;
@@ -38,8 +41,11 @@ static ILLEGAL: LazyLock<Vec<&'static str>> = LazyLock::new(|| {
     ])
 });
 
+/// Name of the query parameter used for address helpers.
+const ADDRESS_HELPER: &str = "i2paddresshelper";
+
 /// Host kind.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HostKind {
     /// .i2p host.
     ///
@@ -78,6 +84,23 @@ pub struct Request {
 
     /// Request.
     request: Vec<u8>,
+
+    /// Destination from `i2paddresshelper` query parameter, if it was specified for a .i2p host.
+    address_helper: Option<String>,
+
+    /// Can the client connection be used for further requests.
+    keep_alive: bool,
+
+    /// Should the connection be tunneled, i.e., relayed without parsing the response.
+    ///
+    /// Used for `CONNECT` and for requests where the end of the body cannot be determined.
+    tunnel: bool,
+
+    /// How many bytes of the request body haven't been received yet.
+    body_remaining: u64,
+
+    /// Data received after the request, i.e., the beginning of a pipelined request.
+    pipelined: Vec<u8>,
 }
 
 impl Request {
@@ -90,11 +113,11 @@ impl Request {
     /// If the parsed is [`HostKind::Clearnet`], an outproxy must have been configured and if the
     /// parsed host is [`HostKind::I2p`], address book must have been enabled and the .i2p host must
     /// be found int the address book.
-    pub fn parse(request: Vec<u8>) -> Result<Self, HttpError> {
+    pub fn parse(mut request: Vec<u8>) -> Result<Self, HttpError> {
         // parse request and create a new request with sanitized headers
         let mut headers = [httparse::EMPTY_HEADER; 64];
         let mut req = httparse::Request::new(&mut headers);
-        let httparse::Status::Complete(_) = req.parse(&request)? else {
+        let httparse::Status::Complete(header_len) = req.parse(&request)? else {
             tracing::warn!(
                 target: LOG_TARGET,
                 "received partial response",
@@ -111,7 +134,7 @@ impl Request {
             Some(method) => return Err(HttpError::MethodNotSupported(method.to_string())),
         };
 
-        let path = match url::Url::parse(req.path.ok_or(HttpError::InvalidPath)?) {
+        let mut path = match url::Url::parse(req.path.ok_or(HttpError::InvalidPath)?) {
             Ok(url) => match url.query() {
                 Some(query) => format!("{}?{query}", url.path()),
                 None if method == "CONNECT" => url.to_string(),
@@ -142,14 +165,135 @@ impl Request {
             }
         };
 
+        let header_value = |name: &str| {
+            req.headers
+                .iter()
+                .find(|header| header.name.eq_ignore_ascii_case(name))
+                .and_then(|header| std::str::from_utf8(header.value).ok())
+                .map(|value| value.trim().to_ascii_lowercase())
+        };
+        let content_length = match header_value("content-length") {
+            Some(length) => length.parse::<u64>().map_err(|_| HttpError::Malformed)?,
+            None => 0u64,
+        };
+        let tunnel = method == "CONNECT" || header_value("transfer-encoding").is_some();
+        let keep_alive = !tunnel
+            && match header_value("connection") {
+                Some(value) => !value.contains("close") && !value.contains("upgrade"),
+                None => req.version == Some(1),
+            };
+
+        // if more than the request was received, the rest belongs to the next pipelined request
+        let pipelined = match (tunnel, (header_len as u64).saturating_add(content_length)) {
+            (false, end) if (request.len() as u64) > end => request.split_off(end as usize),
+            _ => Vec::new(),
+        };
+        let body_remaining = match tunnel {
+            true => 0u64,
+            false => content_length.saturating_sub((request.len() - header_len) as u64),
+        };
+
+        // address helpers are only meaningful for .i2p hosts and the parameter is removed from the
+        // path so it's not sent to the remote host
+        let address_helper = match &host {
+            HostKind::I2p { .. } => {
+                let (stripped, address_helper) = Self::strip_address_helper(&path);
+                path = stripped;
+
+                address_helper
+            }
+            _ => None,
+        };
+
         Ok(Self {
             host,
             method,
             path,
             request,
+            address_helper,
+            keep_alive,
+            tunnel,
+            body_remaining,
+            pipelined,
         })
     }
 
+    /// Remove `i2paddresshelper` query parameter from `path`.
+    ///
+    /// Returns the path without the parameter and the decoded value of the parameter, if found.
+    fn strip_address_helper(path: &str) -> (String, Option<String>) {
+        let Some((path, query)) = path.split_once('?') else {
+            return (path.to_string(), None);
+        };
+        let mut address_helper = None;
+
+        let query = query
+            .split('&')
+            .filter(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+
+                if key != ADDRESS_HELPER {
+                    return true;
+                }
+
+                if address_helper.is_none() {
+                    address_helper = url::form_urlencoded::parse(format!("a={value}").as_bytes())
+                        .next()
+                        .map(|(_, value)| value.trim().to_string())
+                        .filter(|value| !value.is_empty());
+                }
+
+                false
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+
+        match query.is_empty() {
+            true => (path.to_string(), address_helper),
+            false => (format!("{path}?{query}"), address_helper),
+        }
+    }
+
+    /// Get reference to [`HostKind`] of the request.
+    pub fn host(&self) -> &HostKind {
+        &self.host
+    }
+
+    /// Get method of the request.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Get path of the request.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Can the client connection be used for further requests after this request.
+    pub fn keep_alive(&self) -> bool {
+        self.keep_alive
+    }
+
+    /// Should the connection be tunneled instead of relaying a single response.
+    pub fn tunnel(&self) -> bool {
+        self.tunnel
+    }
+
+    /// How many bytes of the request body must still be read from the client.
+    pub fn body_remaining(&self) -> u64 {
+        self.body_remaining
+    }
+
+    /// Take the data received after the request, if any.
+    pub fn take_pipelined(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.pipelined)
+    }
+
+    /// Take the destination of the `i2paddresshelper` query parameter, if it was specified.
+    pub fn take_address_helper(&mut self) -> Option<String> {
+        self.address_helper.take()
+    }
+
     /// Attempt to assemble [`Request`] into a serialized request that can be sent to remote host.
     ///
     /// Takes two parameters: `address_book` and `outproxy`. `address_book` is used to resolve .i2p
@@ -167,7 +311,7 @@ impl Request {
     /// serialized HTTP request.
     pub async fn assemble(
         self,
-        address_book: &Option<Arc<dyn AddressBook>>,
+        address_book: &Option<Arc<AddressBookHandle>>,
         outproxy: &Option<String>,
     ) -> Result<(String, Vec<u8>), HttpError> {
         let user_agent = match &self.host {
@@ -260,6 +404,8 @@ impl Request {
                     Ok(value) if value.to_lowercase() == "upgrade" => {
                         sanitized.extend_from_slice("Connection: upgrade\r\n".as_bytes());
                     }
+                    _ if self.keep_alive =>
+                        sanitized.extend_from_slice("Connection: keep-alive\r\n".as_bytes()),
                     _ => sanitized.extend_from_slice("Connection: close\r\n".as_bytes()),
                 }
 
@@ -320,7 +466,7 @@ mod tests {
     use std::path::PathBuf;
     use tempfile::tempdir;
 
-    async fn make_address_book() -> (Arc<dyn AddressBook>, PathBuf) {
+    async fn make_address_book() -> (Arc<AddressBookHandle>, PathBuf) {
         let hosts = "tracker2.postman.i2p=lnQ6yoBTxQuQU8EQ1FlF395ITIQF-HGJxUeFvzETLFnoczNjQvKDbtSB7aHhn853zjVXrJBgwlB9sO\
                 57KakBDaJ50lUZgVPhjlI19TgJ-CxyHhHSCeKx5JzURdEW-ucdONMynr-b2zwhsx8VQCJwCEkARvt21YkOyQDaB9IdV8aTAmP~PUJQxRwcea\
                 TMn96FcVenwdXqleE16fI8CVFOV18jbJKrhTOYpTtcZKV4l1wNYBDwKgwPx5c0kcrRzFyw5~bjuAKO~GJ5dR7BQsL7AwBoQUS4k1lwoYrG1k\
@@ -358,6 +504,7 @@ mod tests {
             AddressBookConfig {
                 default: Some(String::from("url")),
                 subscriptions: None,
+                feed: None,
             },
         );
 
@@ -376,6 +523,8 @@ mod tests {
             request,
             method,
             path,
+            address_helper,
+            ..
         } = Request::parse(request).unwrap();
 
         assert_eq!(
@@ -386,6 +535,7 @@ mod tests {
         );
         assert_eq!(method, "GET".to_string());
         assert_eq!(path, "/".to_string());
+        assert!(address_helper.is_none());
 
         let mut headers = [httparse::EMPTY_HEADER; 64];
         let mut req = httparse::Request::new(&mut headers);
@@ -657,10 +807,9 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn i2p_host_found_in_b32_cache() {
+    async fn i2p_host_resolved_from_index() {
         let (address_book, path) = make_address_book().await;
-
-        // first query which does a lookup to disk
+        // first query which resolves the host from the index
         {
             let request = "GET / HTTP/1.1\r\nHost: zzz.i2p\r\n\r\n".as_bytes().to_vec();
             let request = Request::parse(request).unwrap();
@@ -696,7 +845,7 @@ mod tests {
         // remove address book file
         tokio::fs::remove_file(path).await.unwrap();
 
-        // address book is removed from disk but zzz.i2p has been cached
+        // address book is removed from disk but zzz.i2p is still indexed
         {
             let request = "GET / HTTP/1.1\r\nHost: zzz.i2p\r\n\r\n".as_bytes().to_vec();
             let request = Request::parse(request).unwrap();
@@ -836,4 +985,124 @@ mod tests {
             "Mozilla/5.0 (Windows NT 10.0; rv:109.0) Gecko/20100101 Firefox/115.0".as_bytes(),
         );
     }
+
+    #[tokio::test]
+    async fn address_helper_extracted() {
+        let request = "GET http://host.i2p/topics?id=1&i2paddresshelper=GKapJ8koUcBj~jmQ%3D%3D&page=2 HTTP/1.1\r\n\
+                    Host: host.i2p\r\n\r\n"
+            .as_bytes()
+            .to_vec();
+        let mut request = Request::parse(request).unwrap();
+
+        assert_eq!(
+            request.host(),
+            &HostKind::I2p {
+                host: "host.i2p".to_string()
+            }
+        );
+        assert_eq!(request.path(), "/topics?id=1&page=2");
+        assert_eq!(
+            request.take_address_helper(),
+            Some("GKapJ8koUcBj~jmQ==".to_string())
+        );
+        assert_eq!(request.take_address_helper(), None);
+
+        // only query parameter
+        let request = "GET http://host.i2p/?i2paddresshelper=GKapJ8koUcBj~jmQ== HTTP/1.1\r\n\
+                    Host: host.i2p\r\n\r\n"
+            .as_bytes()
+            .to_vec();
+        let mut request = Request::parse(request).unwrap();
+
+        assert_eq!(request.path(), "/");
+        assert_eq!(
+            request.take_address_helper(),
+            Some("GKapJ8koUcBj~jmQ==".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn address_helper_ignored_for_b32_host() {
+        let request = "GET http://lhbd7ojcaiofbfku7ixh47qj537g572zmhdc4oilvugzxdpdghua.b32.i2p/?i2paddresshelper=abcd HTTP/1.1\r\n\
+                    Host: lhbd7ojcaiofbfku7ixh47qj537g572zmhdc4oilvugzxdpdghua.b32.i2p\r\n\r\n"
+            .as_bytes()
+            .to_vec();
+        let mut request = Request::parse(request).unwrap();
+
+        assert_eq!(request.path(), "/?i2paddresshelper=abcd");
+        assert_eq!(request.take_address_helper(), None);
+    }
+
+    #[tokio::test]
+    async fn keep_alive_detected() {
+        // http/1.1 defaults to keep-alive
+        let request = "GET / HTTP/1.1\r\nHost: host.i2p\r\n\r\n".as_bytes().to_vec();
+        let request = Request::parse(request).unwrap();
+        assert!(request.keep_alive());
+        assert!(!request.tunnel());
+
+        let request = "GET / HTTP/1.1\r\nHost: host.i2p\r\nConnection: close\r\n\r\n"
+            .as_bytes()
+            .to_vec();
+        assert!(!Request::parse(request).unwrap().keep_alive());
+
+        let request = "GET / HTTP/1.0\r\nHost: host.i2p\r\n\r\n".as_bytes().to_vec();
+        assert!(!Request::parse(request).unwrap().keep_alive());
+
+        let request = "POST / HTTP/1.1\r\nHost: host.i2p\r\nTransfer-Encoding: chunked\r\n\r\n"
+            .as_bytes()
+            .to_vec();
+        let request = Request::parse(request).unwrap();
+        assert!(!request.keep_alive());
+        assert!(request.tunnel());
+    }
+
+    #[tokio::test]
+    async fn keep_alive_forwarded() {
+        let request = "GET / HTTP/1.1\r\n\
+            Host: lhbd7ojcaiofbfku7ixh47qj537g572zmhdc4oilvugzxdpdghua.b32.i2p\r\n\
+            Connection: keep-alive\r\n\r\n"
+            .as_bytes()
+            .to_vec();
+        let (_, request) = Request::parse(request).unwrap().assemble(&None, &None).await.unwrap();
+
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut req = httparse::Request::new(&mut headers);
+        let _body_start = req.parse(&request).unwrap().unwrap();
+
+        assert_eq!(
+            req.headers.iter().find(|header| header.name == "Connection").unwrap().value,
+            "keep-alive".as_bytes(),
+        );
+    }
+
+    #[tokio::test]
+    async fn pipelined_request_split() {
+        let request = "POST /upload HTTP/1.1\r\n\
+            Host: host.i2p\r\n\
+            Content-Length: 5\r\n\r\n\
+            helloGET / HTTP/1.1\r\n"
+            .as_bytes()
+            .to_vec();
+        let mut request = Request::parse(request).unwrap();
+
+        assert_eq!(request.body_remaining(), 0);
+        assert_eq!(
+            request.take_pipelined(),
+            "GET / HTTP/1.1\r\n".as_bytes().to_vec()
+        );
+        assert!(request.request.ends_with("hello".as_bytes()));
+
+        // partial body
+        let request = "POST /upload HTTP/1.1\r\n\
+            Host: host.i2p\r\n\
+            Content-Length: 12\r\n\r\n\
+            hello"
+            .as_bytes()
+            .to_vec();
+        let mut request = Request::parse(request).unwrap();
+
+        assert_eq!(request.body_remaining(), 7);
+        assert!(request.take_pipelined().is_empty());
+    }
 }
@@ -16,7 +16,7 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use crate::proxy::http::HttpError;
+use crate::proxy::http::{helper::AddressHelper, HttpError};
 
 use tokio::{io::AsyncWriteExt, net::TcpStream};
 
@@ -25,6 +25,9 @@ pub enum Status {
     /// HTTP 400 Bad Request.
     BadRequest(HttpError),
 
+    /// HTTP 502 Bad Gateway.
+    BadGateway(String),
+
     /// HTTP 500 Gateway Timeout.
     GatewayTimeout(String),
 }
@@ -33,6 +36,7 @@ impl std::fmt::Display for Status {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Status::BadRequest(_) => write!(f, "400 Bad Request"),
+            Self::BadGateway(_) => write!(f, "502 Bad Gateway"),
             Self::GatewayTimeout(_) => write!(f, "504 Gateway Timeout"),
         }
     }
@@ -45,6 +49,8 @@ pub async fn send_response(mut stream: TcpStream, status: Status) {
     let headers = "Connection: close\r\nContent-Type: text/html; charset=UTF-8";
     let error = match status {
         Status::BadRequest(error) => error.to_string(),
+        Status::BadGateway(host) =>
+            format!("Connection to {host} was closed before a response was received"),
         Status::GatewayTimeout(host) => format!("Failed to establish connection to {host}"),
     };
     let body = format!(
@@ -71,3 +77,82 @@ pub async fn send_response(mut stream: TcpStream, status: Status) {
     let _ = stream.write_all(response.as_bytes()).await;
     let _ = stream.shutdown().await;
 }
+
+/// Escape `input` so it can be embedded into an HTML document.
+fn escape(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Send HTTP redirect to `location` to client.
+pub async fn send_redirect(mut stream: TcpStream, location: &str) {
+    let response = format!(
+        "HTTP/1.1 302 Found\r\nLocation: {location}\r\nConnection: close\r\nContent-Length: 0\r\n\r\n"
+    );
+
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Send address helper confirmation page to client.
+///
+/// The page allows the user to either save the host into the local address book, if `nonce` is
+/// provided, or to continue to the site using its .b32.i2p address without saving the host.
+///
+/// `path` is the path of the original request and `conflict` indicates whether the address book
+/// already contains a different destination for the host.
+pub async fn send_address_helper(
+    mut stream: TcpStream,
+    helper: &AddressHelper,
+    path: &str,
+    nonce: Option<&str>,
+    conflict: bool,
+) {
+    let host = escape(&helper.host);
+    let b32 = format!("{}.b32.i2p", helper.b32);
+    let continue_url = escape(&format!("http://{b32}{path}"));
+
+    let warning = match conflict {
+        true => format!(
+            "<p><strong>Warning:</strong> the address book already contains a different \
+            destination for {host}. Saving the host will override the existing entry.</p>"
+        ),
+        false => String::new(),
+    };
+    let save = match nonce {
+        Some(nonce) => format!(
+            "<p><a href=\"http://proxy.i2p/add?nonce={nonce}\">Save {host} to local address book and continue</a></p>"
+        ),
+        None => "<p>Address book is not enabled, the host cannot be saved.</p>".to_string(),
+    };
+    let body = format!(
+        r#"
+            <!DOCTYPE html>
+            <html>
+            <head>
+                <title>Address helper for {host}</title>
+            </head>
+            <body>
+                <h1>Address helper for {host}</h1>
+                <p>The link you followed provided a destination for {host} which resolves to {b32}.</p>
+                <p>Only save the host if you trust the site that provided the link.</p>
+                {warning}
+                {save}
+                <p><a href="{continue_url}">Continue without saving</a></p>
+            </body>
+            </html>
+        "#
+    );
+
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Type: text/html; charset=UTF-8\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    );
+
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
@@ -0,0 +1,1164 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::{
+    address_book::store::AddressBookStore, config::SocksProxyConfig,
+    proxy::socks::udp::UdpAssociation,
+};
+
+use subtle::{Choice, ConstantTimeEq};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinSet,
+};
+use yosemite::{style, Session, SessionOptions, StreamOptions};
+
+use std::{collections::HashMap, sync::Arc};
+
+mod udp;
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::proxy::socks";
+
+/// SOCKSv5 TCP CONNECT
+const SOCKSV5_TCP: u8 = 0x01;
+
+/// SOCKSv5 UDP ASSOCIATE.
+const SOCKSV5_UDP_ASSOCIATE: u8 = 0x03;
+
+/// SOCKSv5 Domain for TCP CONNECT.
+const SOCKSV5_DOMAIN: u8 = 0x03;
+
+/// SOCKSv5 IPv4 address.
+const SOCKSV5_IPV4: u8 = 0x01;
+
+/// SOCKSv5 IPv6 address.
+const SOCKSV5_IPV6: u8 = 0x04;
+
+/// SOCKSv5 username/password authentication method.
+const SOCKSV5_USERNAME_PASSWORD: u8 = 0x02;
+
+/// SOCKSv5 reply when none of the authentication methods offered by the client are acceptable.
+const SOCKSV5_NO_ACCEPTABLE_METHODS: u8 = 0xff;
+
+/// SOCKSv5 host unreachable.
+const SOCKSV5_HOST_UNREACHABLE: u8 = 0x04;
+
+/// Version of the username/password authentication subnegotiation.
+const USERNAME_PASSWORD_VERSION: u8 = 0x01;
+
+/// SOCKSv5 request.
+enum Request {
+    /// `TCP CONNECT` to `host:port`.
+    Connect {
+        /// TCP stream of the client.
+        stream: TcpStream,
+
+        /// Host.
+        host: String,
+
+        /// Port.
+        port: u16,
+
+        /// Authenticated user, if authentication is enabled.
+        user: Option<String>,
+    },
+
+    /// `TCP CONNECT` to a clearnet host through the outproxy.
+    ///
+    /// The client hasn't been sent a reply yet.
+    OutproxyConnect {
+        /// TCP stream of the client.
+        stream: TcpStream,
+
+        /// Address of the target, as it was received from the client.
+        ///
+        /// Contains the address type, the address and the port.
+        address: Vec<u8>,
+
+        /// Authenticated user, if authentication is enabled.
+        user: Option<String>,
+    },
+
+    /// `UDP ASSOCIATE`.
+    ///
+    /// The client hasn't been sent a reply yet.
+    UdpAssociate {
+        /// TCP stream of the client.
+        stream: TcpStream,
+    },
+}
+
+/// SOCKSv5 proxy.
+pub struct SocksProxy {
+    /// Address book, if enabled.
+    address_book: Option<AddressBookStore>,
+
+    /// Pending SOCKSv5 requests.
+    futures: JoinSet<anyhow::Result<Request>>,
+
+    /// TCP listener for the server.
+    listener: TcpListener,
+
+    /// SOCKS outproxy, if enabled.
+    outproxy: Option<String>,
+
+    /// SAMv3 TCP port.
+    samv3_tcp_port: u16,
+
+    /// SAMv3 UDP port.
+    samv3_udp_port: u16,
+
+    /// SAMv3 streaming sessions for the SOCKS proxy, indexed by username.
+    ///
+    /// If authentication is disabled, there's a single session which is indexed by `None`.
+    sessions: HashMap<Option<String>, Session<style::Stream>>,
+
+    /// Passwords of users, indexed by username.
+    ///
+    /// Empty if authentication is disabled.
+    users: Arc<HashMap<String, String>>,
+}
+
+impl SocksProxy {
+    /// Create new [`SocksProxy`].
+    ///
+    /// If users have been configured, clients must authenticate using username/password
+    /// authentication and each user is given a separate streaming session.
+    ///
+    /// `address_book` is used to map replies to `UDP ASSOCIATE` datagrams sent to `.i2p` hosts
+    /// back to the host the client used.
+    pub async fn new(
+        config: SocksProxyConfig,
+        samv3_tcp_port: u16,
+        samv3_udp_port: u16,
+        address_book: Option<AddressBookStore>,
+    ) -> crate::Result<Self> {
+        tracing::info!(
+            target: LOG_TARGET,
+            host = %config.host,
+            port = %config.port,
+            outproxy = ?config.outproxy,
+            num_users = ?config.users.as_ref().map(|users| users.len()),
+            "starting socks proxy",
+        );
+
+        let users = config
+            .users
+            .unwrap_or_default()
+            .into_iter()
+            .map(|user| (user.username, user.password))
+            .collect::<HashMap<_, _>>();
+
+        let mut sessions = HashMap::new();
+
+        if users.is_empty() {
+            let session = Session::<style::Stream>::new(SessionOptions {
+                publish: false,
+                samv3_tcp_port,
+                nickname: "socks-proxy".to_string(),
+                ..Default::default()
+            })
+            .await?;
+
+            sessions.insert(None, session);
+        }
+
+        for (index, username) in users.keys().enumerate() {
+            let session = Session::<style::Stream>::new(SessionOptions {
+                publish: false,
+                samv3_tcp_port,
+                nickname: format!("socks-proxy-{index}"),
+                ..Default::default()
+            })
+            .await?;
+
+            sessions.insert(Some(username.clone()), session);
+        }
+
+        let listener = TcpListener::bind(format!("{}:{}", config.host, config.port)).await?;
+
+        let outproxy = config.outproxy.and_then(|outproxy| {
+            if outproxy.ends_with(".i2p") {
+                return Some(outproxy);
+            }
+
+            tracing::warn!(
+                target: LOG_TARGET,
+                %outproxy,
+                "outproxy must be .b32.i2p or .i2p hostname",
+            );
+            None
+        });
+
+        Ok(Self {
+            address_book,
+            futures: JoinSet::new(),
+            listener,
+            outproxy,
+            samv3_tcp_port,
+            samv3_udp_port,
+            sessions,
+            users: Arc::new(users),
+        })
+    }
+
+    /// Authenticate the client using username/password authentication.
+    ///
+    /// Returns the username of the client if the username and password are valid.
+    async fn authenticate(
+        stream: &mut TcpStream,
+        users: &HashMap<String, String>,
+    ) -> anyhow::Result<String> {
+        let mut buf = [0u8; 255];
+
+        // version, username length
+        stream.read_exact(&mut buf[..2]).await?;
+
+        if buf[0] != USERNAME_PASSWORD_VERSION {
+            anyhow::bail!("Invalid username/password version");
+        }
+
+        let len = buf[1] as usize;
+        stream.read_exact(&mut buf[..len]).await?;
+        let username = String::from_utf8_lossy(&buf[..len]).to_string();
+
+        stream.read_exact(&mut buf[..1]).await?;
+        let len = buf[0] as usize;
+        stream.read_exact(&mut buf[..len]).await?;
+
+        // every user is checked and the credentials are compared in constant time so that the
+        // response time doesn't reveal which part of the credentials was wrong
+        let valid = users.iter().fold(Choice::from(0u8), |valid, (user, password)| {
+            valid
+                | (user.as_bytes().ct_eq(username.as_bytes())
+                    & password.as_bytes().ct_eq(&buf[..len]))
+        });
+
+        if !bool::from(valid) {
+            stream.write_all(&[USERNAME_PASSWORD_VERSION, 0x01]).await?;
+            anyhow::bail!("Invalid username or password");
+        }
+
+        stream.write_all(&[USERNAME_PASSWORD_VERSION, 0x00]).await?;
+
+        Ok(username)
+    }
+
+    /// Attempt to parse SOCKSv5 request from `stream`.
+    ///
+    /// `TCP CONNECT` to an I2P host is replied to immediately. The reply to `UDP ASSOCIATE` is
+    /// sent once the datagram session of the association has been created and the reply to
+    /// `TCP CONNECT` to a clearnet host is received from the outproxy.
+    async fn parse_request(
+        mut stream: TcpStream,
+        users: Arc<HashMap<String, String>>,
+        outproxy: bool,
+    ) -> anyhow::Result<Request> {
+        let mut buf = [0u8; 262];
+        stream.read_exact(&mut buf[..2]).await?;
+
+        if buf[0] != 0x05 {
+            anyhow::bail!("Not SOCKSv5");
+        }
+
+        let nmethods = buf[1] as usize;
+        stream.read_exact(&mut buf[..nmethods]).await?;
+
+        let user = if users.is_empty() {
+            // version 5, no-auth (0x00)
+            stream.write_all(&[0x05, 0x00]).await?;
+            None
+        } else {
+            if !buf[..nmethods].contains(&SOCKSV5_USERNAME_PASSWORD) {
+                stream.write_all(&[0x05, SOCKSV5_NO_ACCEPTABLE_METHODS]).await?;
+                anyhow::bail!("Username/password authentication not offered");
+            }
+
+            stream.write_all(&[0x05, SOCKSV5_USERNAME_PASSWORD]).await?;
+            Some(Self::authenticate(&mut stream, &users).await?)
+        };
+
+        // version, cmd, rsv, atyp
+        stream.read_exact(&mut buf[..4]).await?;
+        let cmd = buf[1];
+        let atyp = buf[3];
+
+        if cmd == SOCKSV5_UDP_ASSOCIATE {
+            // the address is the one client will send datagrams from, often unspecified,
+            // and is ignored as the address is learned from the first datagram
+            let len = match atyp {
+                SOCKSV5_IPV4 => 4,
+                SOCKSV5_IPV6 => 16,
+                SOCKSV5_DOMAIN => {
+                    stream.read_exact(&mut buf[..1]).await?;
+                    buf[0] as usize
+                }
+                _ => anyhow::bail!("Invalid address type"),
+            };
+            stream.read_exact(&mut buf[..len + 2]).await?;
+
+            return Ok(Request::UdpAssociate { stream });
+        }
+
+        if cmd != SOCKSV5_TCP {
+            anyhow::bail!("Only TCP CONNECT and UDP ASSOCIATE supported");
+        }
+
+        // ip addresses can only be reached through the outproxy
+        let len = match atyp {
+            SOCKSV5_DOMAIN => {
+                stream.read_exact(&mut buf[..1]).await?;
+                buf[0] as usize
+            }
+            SOCKSV5_IPV4 if outproxy => 4,
+            SOCKSV5_IPV6 if outproxy => 16,
+            _ => anyhow::bail!("Only Domain supported"),
+        };
+        stream.read_exact(&mut buf[..len]).await?;
+        let target = String::from_utf8_lossy(&buf[..len]).to_string();
+
+        if atyp != SOCKSV5_DOMAIN || !target.ends_with(".i2p") {
+            if !outproxy {
+                anyhow::bail!("Outproxy not enabled");
+            }
+
+            let mut address = vec![atyp];
+            if atyp == SOCKSV5_DOMAIN {
+                address.push(len as u8);
+            }
+            address.extend_from_slice(&buf[..len]);
+
+            stream.read_exact(&mut buf[..2]).await?;
+            address.extend_from_slice(&buf[..2]);
+
+            return Ok(Request::OutproxyConnect {
+                stream,
+                address,
+                user,
+            });
+        }
+
+        // Read port
+        stream.read_exact(&mut buf[..2]).await?;
+        let port = u16::from_be_bytes([buf[0], buf[1]]);
+
+        let reply = [
+            0x05, 0x00, 0x00, 0x01, // version, success, reserved, IPv4
+            0, 0, 0, 0, // BIND addr (0.0.0.0)
+            0, 0, // BIND port (0)
+        ];
+        stream.write_all(&reply).await?;
+
+        Ok(Request::Connect {
+            stream,
+            host: target,
+            port,
+            user,
+        })
+    }
+
+    /// Send `TCP CONNECT` for `address` to the outproxy over `stream`.
+    ///
+    /// Returns the reply of the outproxy which is relayed to client.
+    async fn outproxy_handshake<S: AsyncRead + AsyncWrite + Unpin>(
+        stream: &mut S,
+        address: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        // version 5, one method, no-auth (0x00)
+        stream.write_all(&[0x05, 0x01, 0x00]).await?;
+
+        let mut buf = [0u8; 2];
+        stream.read_exact(&mut buf).await?;
+
+        if buf != [0x05, 0x00] {
+            anyhow::bail!("Outproxy requires authentication");
+        }
+
+        let mut request = vec![0x05, SOCKSV5_TCP, 0x00];
+        request.extend_from_slice(address);
+        stream.write_all(&request).await?;
+
+        // version, reply, rsv, atyp
+        let mut reply = vec![0u8; 4];
+        stream.read_exact(&mut reply).await?;
+
+        let len = match reply[3] {
+            SOCKSV5_IPV4 => 4,
+            SOCKSV5_IPV6 => 16,
+            SOCKSV5_DOMAIN => {
+                stream.read_exact(&mut buf[..1]).await?;
+                reply.push(buf[0]);
+                buf[0] as usize
+            }
+            _ => anyhow::bail!("Invalid address type"),
+        };
+
+        let offset = reply.len();
+        reply.resize(offset + len + 2, 0u8);
+        stream.read_exact(&mut reply[offset..]).await?;
+
+        Ok(reply)
+    }
+
+    /// Run event loop of [`SocksProxy`].
+    pub async fn run(mut self) -> anyhow::Result<()> {
+        loop {
+            tokio::select! {
+                result = self.listener.accept() => {
+                    let (stream, _) = result?;
+                    self.futures.spawn(Self::parse_request(
+                        stream,
+                        Arc::clone(&self.users),
+                        self.outproxy.is_some(),
+                    ));
+                }
+                result = self.futures.join_next(), if !self.futures.is_empty() => match result {
+                    None => {}
+                    Some(Err(error)) => tracing::warn!(
+                        target: LOG_TARGET,
+                        %error,
+                        "failed to read request from client",
+                    ),
+                    Some(Ok(Err(error))) => tracing::warn!(
+                        target: LOG_TARGET,
+                        %error,
+                        "failed to parse request",
+                    ),
+                    Some(Ok(Ok(Request::UdpAssociate { stream }))) => {
+                        tracing::trace!(
+                            target: LOG_TARGET,
+                            "udp associate",
+                        );
+
+                        let future = UdpAssociation::new(
+                            stream,
+                            self.samv3_tcp_port,
+                            self.samv3_udp_port,
+                            self.address_book.clone(),
+                        );
+
+                        tokio::spawn(async move {
+                            let result = match future.await {
+                                Ok(association) => association.run().await,
+                                Err(error) => Err(error),
+                            };
+
+                            if let Err(error) = result {
+                                tracing::debug!(
+                                    target: LOG_TARGET,
+                                    ?error,
+                                    "udp association failed",
+                                );
+                            }
+                        });
+                    }
+                    Some(Ok(Ok(Request::OutproxyConnect { mut stream, address, user }))) => {
+                        let (Some(outproxy), Some(session)) =
+                            (&self.outproxy, self.sessions.get_mut(&user))
+                        else {
+                            continue;
+                        };
+
+                        tracing::trace!(
+                            target: LOG_TARGET,
+                            ?user,
+                            "connect to clearnet host through outproxy"
+                        );
+
+                        let future = session.connect_detached(outproxy);
+
+                        tokio::spawn(async move {
+                            let mut i2p_stream = match future.await {
+                                Ok(i2p_stream) => i2p_stream,
+                                Err(error) => {
+                                    tracing::debug!(
+                                        target: LOG_TARGET,
+                                        ?error,
+                                        "failed to connect to outproxy",
+                                    );
+
+                                    let reply = [0x05, SOCKSV5_HOST_UNREACHABLE, 0x00, 0x01, 0, 0, 0, 0, 0, 0];
+                                    return stream.write_all(&reply).await.map_err(anyhow::Error::from);
+                                }
+                            };
+
+                            let reply = Self::outproxy_handshake(&mut i2p_stream, &address).await?;
+                            stream.write_all(&reply).await?;
+
+                            if reply[1] != 0x00 {
+                                return Ok(());
+                            }
+
+                            tokio::io::copy_bidirectional(&mut i2p_stream, &mut stream)
+                                .await
+                                .map(|_| ())
+                                .map_err(anyhow::Error::from)
+                        });
+                    }
+                    Some(Ok(Ok(Request::Connect { mut stream, host, port, user }))) => {
+                        let Some(session) = self.sessions.get_mut(&user) else {
+                            continue;
+                        };
+
+                        tracing::trace!(
+                            target: LOG_TARGET,
+                            %host,
+                            %port,
+                            ?user,
+                            "connect to remote destination"
+                        );
+
+                        let future = session.connect_detached_with_options(
+                            &host,
+                            StreamOptions {
+                                dst_port: port,
+                                ..Default::default()
+                            },
+                        );
+
+                        tokio::spawn(async move {
+                            match future.await {
+                                Err(error) => {
+                                    tracing::debug!(
+                                        target: LOG_TARGET,
+                                        ?error,
+                                        "failed to connect to destination",
+                                    );
+                                    Err(error)
+                                }
+                                Ok(mut i2p_stream) => {
+                                    tokio::io::copy_bidirectional(&mut i2p_stream, &mut stream)
+                                        .await
+                                        .map_err(From::from)
+                                }
+                            }
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{address_book::book::b32_address, config::SocksUserConfig};
+    use emissary_core::{
+        crypto::{base64_encode, SigningPrivateKey},
+        primitives::Destination,
+    };
+    use emissary_util::runtime::tokio::Runtime;
+    use fast_socks5::{
+        client::{Config, Socks5Stream},
+        socks4::client::Socks4Stream,
+    };
+    use rand::rngs::OsRng;
+    use tokio::{
+        io::{AsyncBufReadExt, BufReader},
+        net::UdpSocket,
+        sync::mpsc::{channel, Sender},
+    };
+
+    /// Fake SAMv3 server.
+    struct SamServer {
+        /// TCP listener for the server.
+        listener: TcpListener,
+
+        /// TX channel for sending the datagram ports of created datagram sessions.
+        datagram_ports: Option<Sender<u16>>,
+
+        /// Should streams act as a SOCKSv5 outproxy which echoes data back.
+        outproxy: bool,
+    }
+
+    impl SamServer {
+        /// Create new [`SamServer`].
+        async fn new() -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+            Self {
+                listener,
+                datagram_ports: None,
+                outproxy: false,
+            }
+        }
+
+        /// Run the event loop of [`SamServer`].
+        async fn run(self) {
+            while let Ok((stream, _)) = self.listener.accept().await {
+                let datagram_ports = self.datagram_ports.clone();
+                let outproxy = self.outproxy;
+
+                tokio::spawn(async move {
+                    let mut lines = BufReader::new(stream).lines();
+
+                    while let Ok(Some(command)) = lines.next_line().await {
+                        if command.starts_with("HELLO VERSION") {
+                            lines
+                                .get_mut()
+                                .write_all("HELLO REPLY RESULT=OK VERSION=3.2\n".as_bytes())
+                                .await
+                                .unwrap();
+                            continue;
+                        }
+
+                        if command.starts_with("SESSION CREATE") {
+                            if let (Some(tx), Some(port)) = (
+                                &datagram_ports,
+                                command
+                                    .split(' ')
+                                    .find_map(|option| option.strip_prefix("PORT="))
+                                    .and_then(|port| port.parse::<u16>().ok()),
+                            ) {
+                                tx.send(port).await.unwrap();
+                            }
+
+                            lines
+                                .get_mut()
+                                .write_all(
+                                    "SESSION STATUS RESULT=OK DESTINATION=Fam-qmfYnngAnwkq3qwhkkoUeWNP\
+                                        ckuYbZhK4xWwTzHa3BN9DY4dozKDPywI22LWfT1ALnVDonnRhCux0Iv3wc74-s2CTJOGLp\
+                                        YvPGviS99dFSqRwgxi1dESbt5Liw4FIDZQMcDjcNziHspnTFfE4B3sZUtoNM0GYkrgksS3\
+                                        BgVo3SvNn57~FkHDJvNxcaEL0uq9OGPfxNXNtyIeBxaUSJjYNbgcHG9Q2kzb~Z39FzylbE\
+                                        iS979HJnc~w9Wo4DO8VCHGM1j6-CeRlf3hZpMaqQQJU0Q~k035~voydSIzDLJzMPvVmKAV\
+                                        4q-0A5ikidKKv1N3kREQF5xDuDT1z3BMVHMIsyUECi8HOm3Ixa7XdcqpvHRl~W4RksOEdM\
+                                        ChLrUZbqVr-8uW0lMRhRszAuU2PnF16bw9XEZoVAsNNHgvFQvnOwfLnPpSxtZaGNHGO8w\
+                                        QaYmT3cImMUUhBbc9dcTYAHy8geZ1KzW4j7lpH4SsNaJPszCevkIVdvlqEAXZqh1YBQAE\
+                                        AAcAADwJfIcEBwdeM2rjFM~cPo4btsSszyKlGZeUPzoTfHZv~4eR5efcr5YlogkmARNw57\
+                                        h4sjmYvTESdTE7353u2uI=\n".as_bytes(),
+                                )
+                                .await
+                                .unwrap();
+                            continue;
+                        }
+
+                        if command.starts_with("STREAM CONNECT") && outproxy {
+                            let mut stream = lines.into_inner();
+                            stream.write_all(b"STREAM STATUS RESULT=OK\n").await.unwrap();
+
+                            // greeting
+                            let mut buf = [0u8; 3];
+                            stream.read_exact(&mut buf).await.unwrap();
+                            assert_eq!(buf, [0x05, 0x01, 0x00]);
+                            stream.write_all(&[0x05, 0x00]).await.unwrap();
+
+                            // connect to example.com:80
+                            let mut buf = [0u8; 18];
+                            stream.read_exact(&mut buf).await.unwrap();
+                            assert_eq!(buf[..5], [0x05, SOCKSV5_TCP, 0x00, SOCKSV5_DOMAIN, 11]);
+                            assert_eq!(&buf[5..16], b"example.com");
+                            assert_eq!(buf[16..], [0x00, 0x50]);
+                            stream
+                                .write_all(&[
+                                    0x05,
+                                    0x00,
+                                    0x00,
+                                    SOCKSV5_IPV4,
+                                    1,
+                                    2,
+                                    3,
+                                    4,
+                                    0x00,
+                                    0x50,
+                                ])
+                                .await
+                                .unwrap();
+
+                            let (mut reader, mut writer) = tokio::io::split(stream);
+                            let _ = tokio::io::copy(&mut reader, &mut writer).await;
+                            return;
+                        }
+
+                        panic!("unhandled command: {command}");
+                    }
+                });
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_request() {
+        let sam_port = {
+            let sam = SamServer::new().await;
+            let port = sam.listener.local_addr().unwrap().port();
+            tokio::spawn(sam.run());
+
+            port
+        };
+
+        let proxy = SocksProxy::new(
+            SocksProxyConfig {
+                port: 0,
+                host: "127.0.0.1".to_string(),
+                outproxy: None,
+                users: None,
+            },
+            sam_port,
+            0,
+            None,
+        )
+        .await
+        .unwrap();
+        let address = proxy.listener.local_addr().unwrap();
+        tokio::spawn(proxy.run());
+
+        // send invalid http request to the proxy
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all("hello, world!\n".as_bytes()).await.unwrap();
+
+        let mut buffer = vec![0u8; 512];
+        assert!(stream.read(&mut buffer).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn socksv4_client() {
+        let sam_port = {
+            let sam = SamServer::new().await;
+            let port = sam.listener.local_addr().unwrap().port();
+            tokio::spawn(sam.run());
+
+            port
+        };
+
+        let proxy = SocksProxy::new(
+            SocksProxyConfig {
+                port: 0,
+                host: "127.0.0.1".to_string(),
+                outproxy: None,
+                users: None,
+            },
+            sam_port,
+            0,
+            None,
+        )
+        .await
+        .unwrap();
+        let address = proxy.listener.local_addr().unwrap();
+        tokio::spawn(proxy.run());
+
+        assert!(
+            Socks4Stream::connect(address, "http://host.i2p".to_string(), 80, false)
+                .await
+                .is_err()
+        )
+    }
+
+    #[tokio::test]
+    async fn socksv5_auth_ignored() {
+        let sam_port = {
+            let sam = SamServer::new().await;
+            let port = sam.listener.local_addr().unwrap().port();
+            tokio::spawn(sam.run());
+
+            port
+        };
+
+        let proxy = SocksProxy::new(
+            SocksProxyConfig {
+                port: 0,
+                host: "127.0.0.1".to_string(),
+                outproxy: None,
+                users: None,
+            },
+            sam_port,
+            0,
+            None,
+        )
+        .await
+        .unwrap();
+        let address = proxy.listener.local_addr().unwrap();
+        tokio::spawn(proxy.run());
+
+        assert!(Socks5Stream::connect_with_password(
+            address,
+            "http://host.i2p".to_string(),
+            80,
+            "username".to_string(),
+            "password".to_string(),
+            Config::default()
+        )
+        .await
+        .is_ok())
+    }
+
+    #[tokio::test]
+    async fn socksv5_ipv4_ignored() {
+        let sam_port = {
+            let sam = SamServer::new().await;
+            let port = sam.listener.local_addr().unwrap().port();
+            tokio::spawn(sam.run());
+
+            port
+        };
+
+        let proxy = SocksProxy::new(
+            SocksProxyConfig {
+                port: 0,
+                host: "127.0.0.1".to_string(),
+                outproxy: None,
+                users: None,
+            },
+            sam_port,
+            0,
+            None,
+        )
+        .await
+        .unwrap();
+        let address = proxy.listener.local_addr().unwrap();
+        tokio::spawn(proxy.run());
+
+        assert!(
+            Socks5Stream::connect(address, "127.0.0.1".to_string(), 80, Config::default())
+                .await
+                .is_err()
+        )
+    }
+
+    #[tokio::test]
+    async fn socksv5_ipv6_ignored() {
+        let sam_port = {
+            let sam = SamServer::new().await;
+            let port = sam.listener.local_addr().unwrap().port();
+            tokio::spawn(sam.run());
+
+            port
+        };
+
+        let proxy = SocksProxy::new(
+            SocksProxyConfig {
+                port: 0,
+                host: "127.0.0.1".to_string(),
+                outproxy: None,
+                users: None,
+            },
+            sam_port,
+            0,
+            None,
+        )
+        .await
+        .unwrap();
+        let address = proxy.listener.local_addr().unwrap();
+        tokio::spawn(proxy.run());
+
+        assert!(
+            Socks5Stream::connect(address, "::".to_string(), 80, Config::default())
+                .await
+                .is_err()
+        )
+    }
+
+    /// Create random destination and return it and its `.b32.i2p` host.
+    fn random_destination() -> (String, String) {
+        let destination = Destination::new::<Runtime>(SigningPrivateKey::random(OsRng).public());
+        let encoded = base64_encode(destination.serialize());
+        let host = format!("{}.b32.i2p", b32_address(&encoded).unwrap());
+
+        (encoded, host)
+    }
+
+    /// Create SOCKSv5 UDP datagram for `host:port`.
+    fn socks_datagram(host: &str, port: u16, payload: &[u8]) -> Vec<u8> {
+        let mut datagram = vec![0x00, 0x00, 0x00, SOCKSV5_DOMAIN, host.len() as u8];
+        datagram.extend_from_slice(host.as_bytes());
+        datagram.extend_from_slice(&port.to_be_bytes());
+        datagram.extend_from_slice(payload);
+        datagram
+    }
+
+    #[tokio::test]
+    async fn udp_associate() {
+        let sam_socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let (tx, mut rx) = channel(1);
+        let sam_port = {
+            let sam = SamServer {
+                datagram_ports: Some(tx),
+                ..SamServer::new().await
+            };
+            let port = sam.listener.local_addr().unwrap().port();
+            tokio::spawn(sam.run());
+
+            port
+        };
+
+        let proxy = SocksProxy::new(
+            SocksProxyConfig {
+                port: 0,
+                host: "127.0.0.1".to_string(),
+                outproxy: None,
+                users: None,
+            },
+            sam_port,
+            sam_socket.local_addr().unwrap().port(),
+            None,
+        )
+        .await
+        .unwrap();
+        let address = proxy.listener.local_addr().unwrap();
+        tokio::spawn(proxy.run());
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let mut reply = [0u8; 10];
+
+        stream.write_all(&[0x05, 0x01, 0x00]).await.unwrap();
+        stream.read_exact(&mut reply[..2]).await.unwrap();
+        assert_eq!(reply[..2], [0x05, 0x00]);
+
+        stream
+            .write_all(&[
+                0x05,
+                SOCKSV5_UDP_ASSOCIATE,
+                0x00,
+                SOCKSV5_IPV4,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+            ])
+            .await
+            .unwrap();
+        stream.read_exact(&mut reply).await.unwrap();
+        assert_eq!(reply[..4], [0x05, 0x00, 0x00, SOCKSV5_IPV4]);
+
+        let relay: std::net::SocketAddr =
+            format!("127.0.0.1:{}", u16::from_be_bytes([reply[8], reply[9]]))
+                .parse()
+                .unwrap();
+        let datagram_port = rx.recv().await.unwrap();
+
+        // send datagram to a .b32.i2p host through the relay
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let (destination, host) = random_destination();
+        client.send_to(&socks_datagram(&host, 1337, b"hello"), relay).await.unwrap();
+
+        let mut buffer = vec![0u8; 1024];
+        let nread = sam_socket.recv(&mut buffer).await.unwrap();
+        assert!(buffer[..nread].ends_with(format!(" {host} TO_PORT=1337\nhello").as_bytes()));
+
+        // reply from the destination is addressed from the host and port client sent to
+        sam_socket
+            .send_to(
+                format!("{destination} FROM_PORT=1337 TO_PORT=0\nworld").as_bytes(),
+                ("127.0.0.1", datagram_port),
+            )
+            .await
+            .unwrap();
+
+        let nread = client.recv(&mut buffer).await.unwrap();
+        assert_eq!(buffer[..nread], socks_datagram(&host, 1337, b"world"));
+
+        // reply from another port of the destination is addressed from that port
+        sam_socket
+            .send_to(
+                format!("{destination} FROM_PORT=7777 TO_PORT=0\nport").as_bytes(),
+                ("127.0.0.1", datagram_port),
+            )
+            .await
+            .unwrap();
+
+        let nread = client.recv(&mut buffer).await.unwrap();
+        assert_eq!(buffer[..nread], socks_datagram(&host, 7777, b"port"));
+
+        // datagram from an unknown destination is addressed from its .b32.i2p address
+        let (destination, host) = random_destination();
+        sam_socket
+            .send_to(
+                format!("{destination} FROM_PORT=0 TO_PORT=0\nunsolicited").as_bytes(),
+                ("127.0.0.1", datagram_port),
+            )
+            .await
+            .unwrap();
+
+        let nread = client.recv(&mut buffer).await.unwrap();
+        assert_eq!(buffer[..nread], socks_datagram(&host, 0, b"unsolicited"));
+    }
+
+    #[tokio::test]
+    async fn socksv5_auth_required() {
+        let sam_port = {
+            let sam = SamServer::new().await;
+            let port = sam.listener.local_addr().unwrap().port();
+            tokio::spawn(sam.run());
+
+            port
+        };
+
+        let proxy = SocksProxy::new(
+            SocksProxyConfig {
+                port: 0,
+                host: "127.0.0.1".to_string(),
+                outproxy: None,
+                users: Some(vec![
+                    SocksUserConfig {
+                        username: "alice".to_string(),
+                        password: "password1".to_string(),
+                    },
+                    SocksUserConfig {
+                        username: "bob".to_string(),
+                        password: "password2".to_string(),
+                    },
+                ]),
+            },
+            sam_port,
+            0,
+            None,
+        )
+        .await
+        .unwrap();
+        let address = proxy.listener.local_addr().unwrap();
+        assert_eq!(proxy.sessions.len(), 2);
+        assert!(proxy.sessions.contains_key(&Some("alice".to_string())));
+        assert!(proxy.sessions.contains_key(&Some("bob".to_string())));
+        tokio::spawn(proxy.run());
+
+        // no authentication
+        assert!(
+            Socks5Stream::connect(address, "host.i2p".to_string(), 80, Config::default())
+                .await
+                .is_err()
+        );
+
+        // invalid password
+        assert!(Socks5Stream::connect_with_password(
+            address,
+            "host.i2p".to_string(),
+            80,
+            "alice".to_string(),
+            "password2".to_string(),
+            Config::default()
+        )
+        .await
+        .is_err());
+
+        // unknown user and a prefix of the password
+        for (username, password) in [("charlie", "password1"), ("alice", "password")] {
+            assert!(Socks5Stream::connect_with_password(
+                address,
+                "host.i2p".to_string(),
+                80,
+                username.to_string(),
+                password.to_string(),
+                Config::default()
+            )
+            .await
+            .is_err());
+        }
+
+        assert!(Socks5Stream::connect_with_password(
+            address,
+            "host.i2p".to_string(),
+            80,
+            "bob".to_string(),
+            "password2".to_string(),
+            Config::default()
+        )
+        .await
+        .is_ok());
+    }
+
+    #[tokio::test]
+    async fn clearnet_host_through_outproxy() {
+        let sam_port = {
+            let sam = SamServer {
+                outproxy: true,
+                ..SamServer::new().await
+            };
+            let port = sam.listener.local_addr().unwrap().port();
+            tokio::spawn(sam.run());
+
+            port
+        };
+
+        let proxy = SocksProxy::new(
+            SocksProxyConfig {
+                port: 0,
+                host: "127.0.0.1".to_string(),
+                outproxy: Some("outproxy.i2p".to_string()),
+                users: None,
+            },
+            sam_port,
+            0,
+            None,
+        )
+        .await
+        .unwrap();
+        let address = proxy.listener.local_addr().unwrap();
+        tokio::spawn(proxy.run());
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let mut reply = [0u8; 10];
+
+        stream.write_all(&[0x05, 0x01, 0x00]).await.unwrap();
+        stream.read_exact(&mut reply[..2]).await.unwrap();
+        assert_eq!(reply[..2], [0x05, 0x00]);
+
+        let mut request = vec![0x05, SOCKSV5_TCP, 0x00, SOCKSV5_DOMAIN, 11];
+        request.extend_from_slice(b"example.com");
+        request.extend_from_slice(&80u16.to_be_bytes());
+        stream.write_all(&request).await.unwrap();
+
+        // reply of the outproxy is relayed to client
+        stream.read_exact(&mut reply).await.unwrap();
+        assert_eq!(
+            reply,
+            [0x05, 0x00, 0x00, SOCKSV5_IPV4, 1, 2, 3, 4, 0x00, 0x50]
+        );
+
+        stream.write_all(b"hello, world").await.unwrap();
+        let mut buf = [0u8; 12];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello, world");
+    }
+
+    #[tokio::test]
+    async fn clearnet_host_rejected_without_outproxy() {
+        let sam_port = {
+            let sam = SamServer::new().await;
+            let port = sam.listener.local_addr().unwrap().port();
+            tokio::spawn(sam.run());
+
+            port
+        };
+
+        let proxy = SocksProxy::new(
+            SocksProxyConfig {
+                port: 0,
+                host: "127.0.0.1".to_string(),
+                outproxy: None,
+                users: None,
+            },
+            sam_port,
+            0,
+            None,
+        )
+        .await
+        .unwrap();
+        let address = proxy.listener.local_addr().unwrap();
+        tokio::spawn(proxy.run());
+
+        assert!(
+            Socks5Stream::connect(address, "example.com".to_string(), 80, Config::default())
+                .await
+                .is_err()
+        )
+    }
+}
@@ -0,0 +1,509 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! SOCKSv5 `UDP ASSOCIATE`.
+//!
+//! Each association is given its own repliable datagram session which is closed when the client
+//! closes the TCP connection of the association. Datagrams the client sends to the relay socket
+//! are sent to the `.i2p` or `.b32.i2p` host and port found in the SOCKSv5 UDP header and datagrams
+//! received by the session are relayed back to the client. Replies are addressed from the host the
+//! client sent datagrams to, or from the `.b32.i2p` address of the sender if the sender is not
+//! known.
+//!
+//! The session is created directly with the SAMv3 bridge, instead of through `yosemite`, so that
+//! the ports of inbound datagrams are available to the association.
+
+use crate::address_book::{book::b32_address, store::AddressBookStore};
+
+use rand::{distributions::Alphanumeric, Rng};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpStream, UdpSocket},
+};
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::proxy::socks::udp";
+
+/// SOCKSv5 domain address type.
+const SOCKSV5_DOMAIN: u8 = 0x03;
+
+/// SOCKSv5 IPv4 address type.
+const SOCKSV5_IPV4: u8 = 0x01;
+
+/// SOCKSv5 IPv6 address type.
+const SOCKSV5_IPV6: u8 = 0x04;
+
+/// SOCKSv5 general failure.
+const SOCKSV5_GENERAL_FAILURE: u8 = 0x01;
+
+/// Maximum size of a datagram.
+const MAX_DATAGRAM_SIZE: usize = 0xffff;
+
+/// Maximum number of remote peers tracked by an association.
+///
+/// If the limit is reached, the peers are forgotten and replies from them are addressed from their
+/// `.b32.i2p` addresses until the client sends them another datagram.
+const MAX_PEERS: usize = 4096usize;
+
+/// Parse SOCKSv5 UDP request header from `datagram`.
+///
+/// Returns the destination host and port, and the payload. Fragmented datagrams and datagrams
+/// addressed to anything other than a domain are rejected.
+fn parse_datagram(datagram: &[u8]) -> Option<(String, u16, &[u8])> {
+    // reserved (2 bytes), fragment number, address type, domain length
+    let (header, rest) = datagram.split_at_checked(5)?;
+
+    if header[2] != 0x00 || header[3] != SOCKSV5_DOMAIN {
+        return None;
+    }
+
+    let (host, rest) = rest.split_at_checked(header[4] as usize)?;
+    let (port, payload) = rest.split_at_checked(2)?;
+
+    Some((
+        std::str::from_utf8(host).ok()?.to_string(),
+        u16::from_be_bytes([port[0], port[1]]),
+        payload,
+    ))
+}
+
+/// Parse datagram forwarded by the SAMv3 bridge.
+///
+/// Returns the destination and the source port of the sender, and the payload.
+fn parse_sam_datagram(datagram: &[u8]) -> Option<(&str, u16, &[u8])> {
+    let end = datagram.iter().position(|byte| byte == &b'\n')?;
+    let mut header = std::str::from_utf8(&datagram[..end]).ok()?.split(' ');
+    let destination = header.next()?;
+    let port = header
+        .find_map(|option| option.strip_prefix("FROM_PORT="))
+        .map_or(Some(0), |port| port.parse::<u16>().ok())?;
+
+    Some((destination, port, &datagram[end + 1..]))
+}
+
+/// Create SAMv3 datagram for `payload`, sent to `port` of `host` using session `session_id`.
+fn make_sam_datagram(session_id: &str, host: &str, port: u16, payload: &[u8]) -> Vec<u8> {
+    let mut datagram = format!("3.0 {session_id} {host} TO_PORT={port}\n").into_bytes();
+
+    datagram.extend_from_slice(payload);
+    datagram
+}
+
+/// Create SOCKSv5 UDP datagram from `host` and `port` for `payload`.
+fn make_datagram(host: &str, port: u16, payload: &[u8]) -> Vec<u8> {
+    let mut datagram = Vec::with_capacity(7 + host.len() + payload.len());
+
+    datagram.extend_from_slice(&[0x00, 0x00, 0x00, SOCKSV5_DOMAIN, host.len() as u8]);
+    datagram.extend_from_slice(host.as_bytes());
+    datagram.extend_from_slice(&port.to_be_bytes());
+    datagram.extend_from_slice(payload);
+    datagram
+}
+
+/// Create SOCKSv5 reply with `status` and bound `address`.
+fn make_reply(status: u8, address: SocketAddr) -> Vec<u8> {
+    let mut reply = vec![0x05, status, 0x00];
+
+    match address.ip() {
+        IpAddr::V4(address) => {
+            reply.push(SOCKSV5_IPV4);
+            reply.extend_from_slice(&address.octets());
+        }
+        IpAddr::V6(address) => {
+            reply.push(SOCKSV5_IPV6);
+            reply.extend_from_slice(&address.octets());
+        }
+    }
+
+    reply.extend_from_slice(&address.port().to_be_bytes());
+    reply
+}
+
+/// SOCKSv5 UDP association.
+pub struct UdpAssociation {
+    /// Address book, used to map replies from `.i2p` hosts to the host the client sent to.
+    address_book: Option<AddressBookStore>,
+
+    /// Address of the client's UDP socket.
+    ///
+    /// Set when the first datagram is received from the client.
+    client: Option<SocketAddr>,
+
+    /// Remote peers, indexed by `.b32.i2p` address and port, and the host the client used for
+    /// them.
+    peers: HashMap<(String, u16), String>,
+
+    /// Control connection of the association's datagram session.
+    ///
+    /// The session is destroyed when the connection is closed.
+    session: TcpStream,
+
+    /// ID of the datagram session.
+    session_id: String,
+
+    /// Socket used to exchange datagrams with the SAMv3 bridge.
+    sam_socket: UdpSocket,
+
+    /// Address of the SAMv3 UDP socket.
+    sam_address: SocketAddr,
+
+    /// Relay socket of the association.
+    socket: UdpSocket,
+
+    /// TCP stream of the association.
+    stream: TcpStream,
+}
+
+impl UdpAssociation {
+    /// Create repliable datagram session whose datagrams are forwarded to `sam_socket`.
+    ///
+    /// Returns the control connection and the ID of the session.
+    async fn create_session(
+        samv3_tcp_port: u16,
+        sam_socket: &UdpSocket,
+    ) -> anyhow::Result<(TcpStream, String)> {
+        let session_id =
+            rand::thread_rng().sample_iter(&Alphanumeric).take(16).map(char::from).collect();
+        let mut stream = BufReader::new(TcpStream::connect(("127.0.0.1", samv3_tcp_port)).await?);
+        let mut line = String::new();
+
+        stream.get_mut().write_all(b"HELLO VERSION MIN=3.1 MAX=3.3\n").await?;
+        stream.read_line(&mut line).await?;
+        anyhow::ensure!(line.contains("RESULT=OK"), "handshake failed: {line:?}");
+
+        stream
+            .get_mut()
+            .write_all(
+                format!(
+                    "SESSION CREATE STYLE=DATAGRAM ID={session_id} DESTINATION=TRANSIENT \
+                    SIGNATURE_TYPE=7 PORT={} HOST=127.0.0.1 i2cp.dontPublishLeaseSet=true\n",
+                    sam_socket.local_addr()?.port(),
+                )
+                .as_bytes(),
+            )
+            .await?;
+
+        line.clear();
+        stream.read_line(&mut line).await?;
+        anyhow::ensure!(
+            line.contains("RESULT=OK"),
+            "failed to create session: {line:?}"
+        );
+
+        Ok((stream.into_inner(), session_id))
+    }
+
+    /// Create new [`UdpAssociation`].
+    ///
+    /// Creates a datagram session and binds a relay socket for the association and sends the
+    /// address of the relay socket to client. If the session cannot be created, the client is sent
+    /// a general failure.
+    pub async fn new(
+        mut stream: TcpStream,
+        samv3_tcp_port: u16,
+        samv3_udp_port: u16,
+        address_book: Option<AddressBookStore>,
+    ) -> anyhow::Result<Self> {
+        let sam_socket = UdpSocket::bind("127.0.0.1:0").await?;
+        let (session, session_id) = match Self::create_session(samv3_tcp_port, &sam_socket).await {
+            Ok(session) => session,
+            Err(error) => {
+                let unspecified = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0);
+                let _ = stream.write_all(&make_reply(SOCKSV5_GENERAL_FAILURE, unspecified)).await;
+
+                return Err(error);
+            }
+        };
+        let socket = UdpSocket::bind(SocketAddr::new(stream.local_addr()?.ip(), 0)).await?;
+
+        stream.write_all(&make_reply(0x00, socket.local_addr()?)).await?;
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            relay = ?socket.local_addr(),
+            "udp association created",
+        );
+
+        Ok(Self {
+            address_book,
+            client: None,
+            peers: HashMap::new(),
+            session,
+            session_id,
+            sam_socket,
+            sam_address: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), samv3_udp_port),
+            socket,
+            stream,
+        })
+    }
+
+    /// Handle `datagram` received from `address` to the relay socket.
+    ///
+    /// Only datagrams from the host of the association's TCP connection are accepted and once the
+    /// first datagram has been received, datagrams from other ports of the host are ignored.
+    async fn on_client_datagram(
+        &mut self,
+        datagram: &[u8],
+        address: SocketAddr,
+    ) -> anyhow::Result<()> {
+        match self.client {
+            Some(client) if client != address => return Ok(()),
+            Some(_) => {}
+            None => {
+                if address.ip() != self.stream.peer_addr()?.ip() {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?address,
+                        "datagram from unknown address",
+                    );
+                    return Ok(());
+                }
+
+                self.client = Some(address);
+            }
+        }
+
+        let Some((host, port, payload)) = parse_datagram(datagram) else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                "invalid or fragmented datagram",
+            );
+            return Ok(());
+        };
+
+        if !host.ends_with(".i2p") {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %host,
+                "datagram not addressed to an i2p host",
+            );
+            return Ok(());
+        }
+
+        let address = match (host.find(".b32.i2p"), &self.address_book) {
+            (Some(end), _) => Some(host[..end].to_ascii_lowercase()),
+            (None, Some(address_book)) => address_book
+                .resolve(host.clone())
+                .await
+                .and_then(|destination| b32_address(&destination)),
+            (None, None) => None,
+        };
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            %host,
+            %port,
+            len = ?payload.len(),
+            "send datagram",
+        );
+
+        let datagram = make_sam_datagram(&self.session_id, &host, port, payload);
+
+        if let Err(error) = self.sam_socket.send_to(&datagram, self.sam_address).await {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %host,
+                ?error,
+                "failed to send datagram",
+            );
+        }
+
+        if let Some(address) = address {
+            let key = (address, port);
+
+            if self.peers.len() >= MAX_PEERS && !self.peers.contains_key(&key) {
+                self.peers.clear();
+            }
+            self.peers.insert(key, host);
+        }
+
+        Ok(())
+    }
+
+    /// Handle `datagram` received from `port` of `destination` over I2P.
+    async fn on_i2p_datagram(
+        &mut self,
+        datagram: &[u8],
+        destination: &str,
+        port: u16,
+    ) -> anyhow::Result<()> {
+        let Some(client) = self.client else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                "datagram received before client sent any datagrams",
+            );
+            return Ok(());
+        };
+
+        let Some(address) = b32_address(destination) else {
+            tracing::warn!(
+                target: LOG_TARGET,
+                "received datagram from invalid destination",
+            );
+            return Ok(());
+        };
+
+        let peer = (address, port);
+        let datagram = match self.peers.get(&peer) {
+            Some(host) => make_datagram(host, port, datagram),
+            None => make_datagram(&format!("{}.b32.i2p", peer.0), port, datagram),
+        };
+
+        if let Err(error) = self.socket.send_to(&datagram, client).await {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?client,
+                ?error,
+                "failed to relay datagram to client",
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Run the event loop of [`UdpAssociation`].
+    ///
+    /// Returns when the client closes the TCP connection of the association or when the SAMv3
+    /// bridge closes the datagram session.
+    pub async fn run(mut self) -> anyhow::Result<()> {
+        let mut client_buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+        let mut i2p_buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+        let mut control = [0u8; 1];
+        let mut session = [0u8; 256];
+
+        loop {
+            tokio::select! {
+                result = self.stream.read(&mut control) => match result {
+                    Ok(0) | Err(_) => {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            "udp association closed",
+                        );
+                        return Ok(());
+                    }
+                    Ok(_) => {}
+                },
+                result = self.socket.recv_from(&mut client_buffer) => {
+                    let (nread, address) = result?;
+                    self.on_client_datagram(&client_buffer[..nread], address).await?;
+                }
+                result = self.session.read(&mut session) => match result {
+                    Ok(0) | Err(_) => {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            "datagram session closed",
+                        );
+                        return Ok(());
+                    }
+                    Ok(_) => {}
+                },
+                result = self.sam_socket.recv(&mut i2p_buffer) => {
+                    let nread = result?;
+                    let Some((destination, port, datagram)) =
+                        parse_sam_datagram(&i2p_buffer[..nread])
+                    else {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            "malformed datagram from sam bridge",
+                        );
+                        continue;
+                    };
+
+                    self.on_i2p_datagram(datagram, destination, port).await?;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_domain_datagram() {
+        let datagram = make_datagram("host.i2p", 8080, b"hello, world");
+        let (host, port, payload) = parse_datagram(&datagram).unwrap();
+
+        assert_eq!(host, "host.i2p");
+        assert_eq!(port, 8080);
+        assert_eq!(payload, b"hello, world");
+    }
+
+    #[test]
+    fn fragmented_and_ip_datagrams_rejected() {
+        let mut datagram = make_datagram("host.i2p", 8080, b"hello, world");
+        datagram[2] = 0x01;
+        assert!(parse_datagram(&datagram).is_none());
+
+        // ipv4 address
+        let datagram = [
+            0x00,
+            0x00,
+            0x00,
+            SOCKSV5_IPV4,
+            127,
+            0,
+            0,
+            1,
+            0x1f,
+            0x90,
+            0xaa,
+        ];
+        assert!(parse_datagram(&datagram).is_none());
+
+        // truncated host
+        let datagram = [0x00, 0x00, 0x00, SOCKSV5_DOMAIN, 0x10, b'h', b'o'];
+        assert!(parse_datagram(&datagram).is_none());
+    }
+
+    #[test]
+    fn sam_datagram_ports() {
+        assert_eq!(
+            make_sam_datagram("session", "host.i2p", 8080, b"hello"),
+            b"3.0 session host.i2p TO_PORT=8080\nhello"
+        );
+
+        let (destination, port, payload) =
+            parse_sam_datagram(b"destination FROM_PORT=1337 TO_PORT=0\nhello").unwrap();
+        assert_eq!(destination, "destination");
+        assert_eq!(port, 1337);
+        assert_eq!(payload, b"hello");
+
+        assert_eq!(parse_sam_datagram(b"destination\nhello").unwrap().1, 0);
+        assert!(parse_sam_datagram(b"destination FROM_PORT=port\nhello").is_none());
+        assert!(parse_sam_datagram(b"destination FROM_PORT=1337").is_none());
+    }
+
+    #[test]
+    fn reply_contains_bound_address() {
+        assert_eq!(
+            make_reply(0x00, "127.0.0.1:1337".parse().unwrap()),
+            vec![0x05, 0x00, 0x00, SOCKSV5_IPV4, 127, 0, 0, 1, 0x05, 0x39]
+        );
+
+        let reply = make_reply(0x00, "[::1]:1337".parse().unwrap());
+        assert_eq!(reply.len(), 22);
+        assert_eq!(reply[3], SOCKSV5_IPV6);
+    }
+}
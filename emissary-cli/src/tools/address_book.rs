@@ -0,0 +1,192 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Manage the local and private address books.
+//!
+//! The router address book is populated from subscriptions and can only be listed.
+
+use crate::address_book::book::{self, Book};
+
+use anyhow::anyhow;
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// Get the user-managed book, based on `private`.
+fn user_book(private: bool) -> Book {
+    match private {
+        true => Book::Private,
+        false => Book::Local,
+    }
+}
+
+/// Add `host` with `destination` into the local address book or into the private address book if
+/// `private` is `true`.
+///
+/// If `host` already exists in the book, its destination is replaced.
+pub fn add(
+    base_path: &Path,
+    host: String,
+    destination: String,
+    private: bool,
+) -> anyhow::Result<()> {
+    if !book::is_valid_host(&host) {
+        return Err(anyhow!("invalid hostname: {host}"));
+    }
+
+    let b32 = book::b32_address(&destination).ok_or_else(|| anyhow!("invalid destination"))?;
+    let book = user_book(private);
+    let mut hosts = book::load(base_path, book)?;
+
+    if let Some(previous) = hosts.insert(host.clone(), destination) {
+        if let Some(previous) = book::b32_address(&previous) {
+            eprintln!("{host} updated: {previous}.b32.i2p -> {b32}.b32.i2p");
+        }
+    }
+
+    book::store(base_path, book, &hosts)?;
+    println!("{host}={b32}.b32.i2p added to {} address book", book.name());
+
+    Ok(())
+}
+
+/// Remove `host` from the local address book or from the private address book if `private` is
+/// `true`.
+pub fn remove(base_path: &Path, host: String, private: bool) -> anyhow::Result<()> {
+    let book = user_book(private);
+    let mut hosts = book::load(base_path, book)?;
+
+    if hosts.remove(&host).is_none() {
+        return Err(anyhow!(
+            "{host} not found from {} address book",
+            book.name()
+        ));
+    }
+
+    book::store(base_path, book, &hosts)?;
+    println!("{host} removed from {} address book", book.name());
+
+    Ok(())
+}
+
+/// Write hosts of `book` into `writer` or hosts of all books if `book` is `None`.
+///
+/// Books are listed in the order of precedence.
+fn write_list(base_path: &Path, book: Option<Book>, writer: &mut impl Write) -> anyhow::Result<()> {
+    let books = match book {
+        Some(book) => vec![book],
+        None => Book::ALL.to_vec(),
+    };
+
+    for book in books {
+        for (host, destination) in book::load(base_path, book)? {
+            match book::b32_address(&destination) {
+                Some(b32) => writeln!(writer, "{host}\t{b32}.b32.i2p\t{}", book.name())?,
+                None => writeln!(writer, "{host}\t<invalid destination>\t{}", book.name())?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// List hosts of `book` or hosts of all books if `book` is `None`.
+pub fn list(base_path: &Path, book: Option<Book>) -> anyhow::Result<()> {
+    write_list(base_path, book, &mut io::stdout().lock())
+}
+
+/// Export the local address book in `hosts.txt` format to `output` (if specified) or to stdout.
+pub fn export(base_path: &Path, output: Option<String>) -> anyhow::Result<()> {
+    let hosts = book::serialize(&book::load(base_path, Book::Local)?);
+
+    if let Some(out) = output {
+        fs::write(out, hosts)?;
+    } else {
+        io::stdout().write_all(hosts.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use emissary_core::{
+        crypto::{base64_encode, SigningPrivateKey},
+        primitives::Destination,
+    };
+    use emissary_util::runtime::tokio::Runtime;
+    use rand::rngs::OsRng;
+    use tempfile::tempdir;
+
+    fn destination() -> (String, String) {
+        let destination = Destination::new::<Runtime>(SigningPrivateKey::random(OsRng).public());
+        let encoded = base64_encode(destination.serialize());
+        let b32 = book::b32_address(&encoded).unwrap();
+
+        (encoded, b32)
+    }
+
+    #[test]
+    fn add_list_export_remove() {
+        let dir = tempdir().unwrap();
+        let (local, local_b32) = destination();
+        let (private, private_b32) = destination();
+
+        assert!(add(dir.path(), "zzz".to_string(), local.clone(), false).is_err());
+        assert!(add(
+            dir.path(),
+            "zzz.i2p".to_string(),
+            "invalid".to_string(),
+            false
+        )
+        .is_err());
+
+        add(dir.path(), "zzz.i2p".to_string(), local.clone(), false).unwrap();
+        add(dir.path(), "secret.i2p".to_string(), private.clone(), true).unwrap();
+
+        let mut out = Vec::new();
+        write_list(dir.path(), None, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "secret.i2p\t{private_b32}.b32.i2p\tprivate\nzzz.i2p\t{local_b32}.b32.i2p\tlocal\n"
+            ),
+        );
+
+        let mut out = Vec::new();
+        write_list(dir.path(), Some(Book::Router), &mut out).unwrap();
+        assert!(out.is_empty());
+
+        // private hosts are not exported
+        let output = dir.path().join("hosts.txt").to_str().unwrap().to_string();
+        export(dir.path(), Some(output.clone())).unwrap();
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            format!("zzz.i2p={local}\n")
+        );
+
+        assert!(remove(dir.path(), "zzz.i2p".to_string(), true).is_err());
+        remove(dir.path(), "zzz.i2p".to_string(), false).unwrap();
+        assert!(book::load(dir.path(), Book::Local).unwrap().is_empty());
+        assert_eq!(book::load(dir.path(), Book::Private).unwrap().len(), 1);
+    }
+}
@@ -16,9 +16,14 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use crate::address_book::book::Book;
+
 use clap::{ArgGroup, Subcommand};
 
+pub mod address_book;
 pub mod base64;
+pub mod offline;
+pub mod reseed;
 
 /// Router commands.
 ///
@@ -74,4 +79,144 @@ pub enum RouterCommand {
         #[arg(short = 'o', long, value_name = "OUTPUT")]
         output: Option<String>,
     },
+
+    /// Create offline signature for a destination.
+    ///
+    /// The long-term signing key of the destination authorizes a new transient signing key which
+    /// is used by the router to sign the lease sets of the destination until the offline signature
+    /// expires, allowing the long-term key to be kept off the server.
+    ///
+    /// Output is written to stdout if `output` is not specified.
+    OfflineSign {
+        /// Path to the Base64-encoded private key of the destination.
+        #[arg(short = 'k', long, value_name = "KEY")]
+        key: String,
+
+        /// How many days is the offline signature valid for.
+        #[arg(short = 'd', long, value_name = "DAYS", default_value_t = 365)]
+        days: u64,
+
+        /// Path to output file where the Base64-encoded transient private key is written to.
+        #[arg(short = 'o', long, value_name = "OUTPUT")]
+        output: Option<String>,
+    },
+
+    /// Manage address books.
+    ///
+    /// Hosts added by the user are stored in the local address book, or in the private address
+    /// book if `--private` is specified. Hosts of the private address book are never published.
+    AddressBook {
+        #[command(subcommand)]
+        command: AddressBookCommand,
+    },
+
+    /// Create reseed bundle from the netDb of the router.
+    ///
+    /// The bundle is signed with `signing_key` of `signer_id` and routers reseeding from it must
+    /// be configured with the certificate of the signing key.
+    CreateReseed {
+        /// Signer ID of the signing key, e.g., `user@mail.i2p`.
+        #[arg(long, value_name = "ID")]
+        signer_id: String,
+
+        /// Path to the PEM-encoded 4096-bit RSA signing key.
+        #[arg(long, value_name = "KEY")]
+        signing_key: String,
+
+        /// Maximum number of router infos in the bundle.
+        #[arg(short = 'n', long, value_name = "NUM", default_value_t = 75)]
+        num_routers: usize,
+
+        /// Path to output file where the bundle is written to.
+        #[arg(
+            short = 'o',
+            long,
+            value_name = "OUTPUT",
+            default_value = "i2pseeds.su3"
+        )]
+        output: String,
+    },
+
+    /// Run HTTPS reseed server which serves reseed bundles created from the netDb of the router.
+    ///
+    /// The bundle is available at `/i2pseeds.su3` and is recreated every 10 minutes.
+    ReseedServer {
+        /// Signer ID of the signing key, e.g., `user@mail.i2p`.
+        #[arg(long, value_name = "ID")]
+        signer_id: String,
+
+        /// Path to the PEM-encoded 4096-bit RSA signing key.
+        #[arg(long, value_name = "KEY")]
+        signing_key: String,
+
+        /// Path to the PEM-encoded TLS certificate of the server.
+        #[arg(long, value_name = "CERTIFICATE")]
+        tls_certificate: String,
+
+        /// Path to the PEM-encoded PKCS #8 TLS private key of the server.
+        #[arg(long, value_name = "KEY")]
+        tls_key: String,
+
+        /// Host the server binds to.
+        #[arg(long, value_name = "HOST", default_value = "0.0.0.0")]
+        host: String,
+
+        /// Port the server binds to.
+        #[arg(short = 'p', long, value_name = "PORT", default_value_t = 8443)]
+        port: u16,
+
+        /// Maximum number of router infos in a bundle.
+        #[arg(short = 'n', long, value_name = "NUM", default_value_t = 75)]
+        num_routers: usize,
+    },
+}
+
+/// Address book commands.
+#[derive(Subcommand)]
+pub enum AddressBookCommand {
+    /// Add host to the local address book.
+    ///
+    /// If the host already exists, its destination is replaced.
+    Add {
+        /// Hostname, e.g., `zzz.i2p`.
+        #[arg(value_name = "HOST")]
+        host: String,
+
+        /// Base64-encoded destination of the host.
+        #[arg(value_name = "DESTINATION")]
+        destination: String,
+
+        /// Add host to the private address book.
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        private: bool,
+    },
+
+    /// Remove host from the local address book.
+    Remove {
+        /// Hostname, e.g., `zzz.i2p`.
+        #[arg(value_name = "HOST")]
+        host: String,
+
+        /// Remove host from the private address book.
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        private: bool,
+    },
+
+    /// List hosts of the address books.
+    ///
+    /// Hosts of all address books are listed if `book` is not specified.
+    List {
+        /// Address book to list.
+        #[arg(long, value_name = "BOOK")]
+        book: Option<Book>,
+    },
+
+    /// Export the local address book in `hosts.txt` format.
+    ///
+    /// Output is written to stdout if `output` is not specified.
+    Export {
+        /// Path to output file where the `hosts.txt` is written to.
+        #[arg(short = 'o', long, value_name = "OUTPUT")]
+        output: Option<String>,
+    },
 }
@@ -0,0 +1,134 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Generate offline signatures for destinations.
+//!
+//! The long-term signing key of the destination is used to authorize a transient signing key
+//! which is then used by the router to sign the lease sets of the destination, allowing the
+//! long-term key to be kept off the server.
+
+use anyhow::anyhow;
+use emissary_core::{
+    crypto::{base64_decode, base64_encode, SigningPrivateKey},
+    primitives::{Destination, OfflineSignature},
+};
+use rand::rngs::OsRng;
+
+use std::{
+    fs,
+    io::{self, Write},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Create offline signature for the destination read from `key` which is valid for `days` and
+/// write the transient private key to `output` (if specified) or to stdout.
+///
+/// `key` must contain a Base64-encoded private key of the destination, as returned by SAM, and
+/// the written private key is in the same format, containing the offline signature and the
+/// transient signing key instead of the long-term signing key.
+pub fn sign(key: String, days: u64, output: Option<String>) -> anyhow::Result<()> {
+    let decoded = base64_decode(fs::read_to_string(key)?.trim())
+        .ok_or_else(|| anyhow!("failed to base64-decode private key"))?;
+
+    let (rest, destination) =
+        Destination::parse_frame(&decoded).map_err(|_| anyhow!("invalid destination"))?;
+
+    if rest.len() < destination.private_key_length() + destination.signing_key_length() {
+        return Err(anyhow!("private key is too short"));
+    }
+    let (private_key, rest) = rest.split_at(destination.private_key_length());
+    let signing_key = &rest[..destination.signing_key_length()];
+
+    if signing_key.iter().all(|byte| byte == &0u8) {
+        return Err(anyhow!("destination already uses an offline signature"));
+    }
+
+    let signing_key = SigningPrivateKey::from_bytes(signing_key)
+        .ok_or_else(|| anyhow!("unsupported signing key"))?;
+
+    if &signing_key.public() != destination.verifying_key() {
+        return Err(anyhow!("signing key doesn't match the destination"));
+    }
+
+    let transient_key = SigningPrivateKey::random(OsRng);
+    let expires =
+        SystemTime::now().duration_since(UNIX_EPOCH)? + Duration::from_secs(days * 24 * 60 * 60);
+    let offline_signature = OfflineSignature::new(expires, transient_key.public(), &signing_key);
+
+    // destination + private key + all-zero signing key + offline signature + transient key
+    let mut out = Vec::with_capacity(
+        destination.serialized_len()
+            + private_key.len()
+            + 2 * 32
+            + offline_signature.serialized_len(),
+    );
+    out.extend_from_slice(&destination.serialize());
+    out.extend_from_slice(private_key);
+    out.extend_from_slice(&[0u8; 32]);
+    out.extend_from_slice(offline_signature.serialize());
+    out.extend_from_slice(transient_key.as_ref());
+
+    let encoded = base64_encode(out);
+
+    if let Some(out) = output {
+        fs::write(out, encoded)?;
+    } else {
+        io::stdout().write_all(encoded.as_ref())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use emissary_util::runtime::tokio::Runtime;
+    use tempfile::tempdir;
+
+    #[test]
+    fn sign_destination() {
+        let dir = tempdir().unwrap();
+        let key = dir.path().join("key.dat").as_path().to_str().unwrap().to_string();
+        let output = dir.path().join("offline.dat").as_path().to_str().unwrap().to_string();
+
+        let signing_key = SigningPrivateKey::random(OsRng);
+        let destination = Destination::new::<Runtime>(signing_key.public());
+        {
+            let mut out = destination.serialize().to_vec();
+            out.extend_from_slice(&[1u8; 32]);
+            out.extend_from_slice(signing_key.as_ref());
+            fs::write(&key, base64_encode(out)).unwrap();
+        }
+
+        sign(key, 30, Some(output.clone())).unwrap();
+
+        let decoded = base64_decode(fs::read_to_string(&output).unwrap()).unwrap();
+        let (rest, parsed) = Destination::parse_frame(&decoded).unwrap();
+        assert_eq!(parsed.id(), destination.id());
+        assert_eq!(&rest[..32], &[1u8; 32]);
+        assert_eq!(&rest[32..64], &[0u8; 32]);
+
+        let (rest, offline_signature) =
+            OfflineSignature::parse_frame(&rest[64..], destination.verifying_key()).unwrap();
+        let transient_key = SigningPrivateKey::from_bytes(rest).unwrap();
+        assert_eq!(offline_signature.verifying_key, transient_key.public());
+
+        // offline signature cannot be created for a destination which already uses one
+        assert!(sign(output.clone(), 30, Some(output)).is_err());
+    }
+}
@@ -0,0 +1,435 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Reseed bundle creation and reseed server.
+//!
+//! Reseed bundles are created from the router infos of the router's netDb and signed with the
+//! RSA signing key of the operator, allowing routers of a private network to bootstrap without the
+//! public reseed servers. The routers must be given the certificate of the signing key in
+//! `certificates` of `[reseed]`.
+
+use emissary_core::{crypto::base64_encode, primitives::RouterInfo};
+use emissary_util::su3::{ReseedRouterInfo, Su3};
+use parking_lot::RwLock;
+use rand::{seq::SliceRandom, thread_rng};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpListener,
+};
+use tokio_native_tls::{native_tls, TlsAcceptor};
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::reseed-server";
+
+/// Maximum size of a request.
+const MAX_REQUEST_SIZE: usize = 8192usize;
+
+/// How long is the client given to send the request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often is the reseed bundle recreated.
+const BUNDLE_REFRESH_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Load reachable router infos from the netDb in `base_path`, including the router's own router
+/// info.
+fn load_router_infos(base_path: &Path) -> Vec<ReseedRouterInfo> {
+    let netdb = fs::read_dir(base_path.join("netDb"))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| fs::read_dir(entry.ok()?.path()).ok())
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path()));
+
+    netdb
+        .chain(std::iter::once(base_path.join("router.info")))
+        .filter_map(|path| {
+            let router_info = fs::read(path).ok()?;
+            let parsed = RouterInfo::parse(&router_info)?;
+
+            parsed.is_reachable().then(|| {
+                (
+                    format!(
+                        "routerInfo-{}.dat",
+                        base64_encode(parsed.identity.id().to_vec())
+                    ),
+                    router_info,
+                )
+            })
+        })
+        .collect::<HashMap<_, _>>()
+        .into_iter()
+        .map(|(name, router_info)| ReseedRouterInfo { name, router_info })
+        .collect()
+}
+
+/// Create reseed bundle of at most `num_routers` random router infos from the netDb in
+/// `base_path`, signed with `signing_key` of `signer_id`.
+fn create_bundle(
+    base_path: &Path,
+    signer_id: &str,
+    signing_key: &str,
+    num_routers: usize,
+) -> anyhow::Result<Vec<u8>> {
+    let mut router_infos = load_router_infos(base_path);
+
+    if router_infos.is_empty() {
+        return Err(anyhow::anyhow!("no reachable router infos found"));
+    }
+
+    router_infos.shuffle(&mut thread_rng());
+    router_infos.truncate(num_routers);
+
+    tracing::debug!(
+        target: LOG_TARGET,
+        num_routers = ?router_infos.len(),
+        "create reseed bundle",
+    );
+
+    Su3::create_reseed(&router_infos, signer_id, signing_key)
+}
+
+/// Create reseed bundle of at most `num_routers` router infos from the netDb in `base_path` and
+/// write it to `output`.
+///
+/// `signing_key` is the path to a PEM-encoded 4096-bit RSA private key of `signer_id`.
+pub fn create(
+    base_path: &Path,
+    signer_id: String,
+    signing_key: String,
+    num_routers: usize,
+    output: String,
+) -> anyhow::Result<()> {
+    let signing_key = fs::read_to_string(signing_key)?;
+    let bundle = create_bundle(base_path, &signer_id, &signing_key, num_routers)?;
+
+    fs::write(output, bundle)?;
+
+    Ok(())
+}
+
+/// HTTPS reseed server.
+///
+/// Serves a reseed bundle created from the netDb of the router, which is recreated every
+/// [`BUNDLE_REFRESH_INTERVAL`].
+pub struct ReseedServer {
+    /// TLS acceptor.
+    acceptor: TlsAcceptor,
+
+    /// Base path of the router.
+    base_path: PathBuf,
+
+    /// Current reseed bundle.
+    bundle: Arc<RwLock<Arc<Vec<u8>>>>,
+
+    /// TCP listener.
+    listener: TcpListener,
+
+    /// Maximum number of router infos in a bundle.
+    num_routers: usize,
+
+    /// Signer ID.
+    signer_id: String,
+
+    /// PEM-encoded signing key.
+    signing_key: String,
+}
+
+impl ReseedServer {
+    /// Create new [`ReseedServer`].
+    ///
+    /// `signing_key` is the path to a PEM-encoded 4096-bit RSA private key of `signer_id` and
+    /// `tls_certificate` and `tls_key` are paths to the PEM-encoded TLS certificate and PKCS #8
+    /// private key of the server.
+    pub async fn new(
+        base_path: PathBuf,
+        signer_id: String,
+        signing_key: String,
+        tls_certificate: String,
+        tls_key: String,
+        host: String,
+        port: u16,
+        num_routers: usize,
+    ) -> anyhow::Result<Self> {
+        let signing_key = fs::read_to_string(signing_key)?;
+        let identity =
+            native_tls::Identity::from_pkcs8(&fs::read(tls_certificate)?, &fs::read(tls_key)?)?;
+        let bundle = create_bundle(&base_path, &signer_id, &signing_key, num_routers)?;
+
+        Ok(Self {
+            acceptor: TlsAcceptor::from(native_tls::TlsAcceptor::new(identity)?),
+            base_path,
+            bundle: Arc::new(RwLock::new(Arc::new(bundle))),
+            listener: TcpListener::bind((host.as_str(), port)).await?,
+            num_routers,
+            signer_id,
+            signing_key,
+        })
+    }
+
+    /// Read request from `stream` and respond with `bundle`.
+    async fn on_connection<S: AsyncRead + AsyncWrite + Unpin>(
+        mut stream: S,
+        bundle: Arc<Vec<u8>>,
+    ) -> io::Result<()> {
+        let mut buffer = vec![0u8; MAX_REQUEST_SIZE];
+        let mut nread = 0usize;
+
+        let (method, path) = loop {
+            if nread == MAX_REQUEST_SIZE {
+                return Err(io::Error::other("request too large"));
+            }
+
+            match stream.read(&mut buffer[nread..]).await? {
+                0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+                read => nread += read,
+            }
+
+            let mut headers = [httparse::EMPTY_HEADER; 64];
+            let mut request = httparse::Request::new(&mut headers);
+
+            match request.parse(&buffer[..nread]) {
+                Ok(httparse::Status::Complete(_)) =>
+                    break (
+                        request.method.unwrap_or_default().to_string(),
+                        request.path.unwrap_or_default().to_string(),
+                    ),
+                Ok(httparse::Status::Partial) => {}
+                Err(error) => return Err(io::Error::other(error)),
+            }
+        };
+        let path = path.split_once('?').map_or(path.as_str(), |(path, _)| path);
+
+        let (status, body) = match (method.as_str(), path) {
+            ("GET" | "HEAD", "/i2pseeds.su3") => ("200 OK", bundle.as_slice()),
+            ("GET" | "HEAD", _) => ("404 Not Found", [].as_slice()),
+            _ => ("405 Method Not Allowed", [].as_slice()),
+        };
+
+        let response = format!(
+            "HTTP/1.1 {status}\r\nConnection: close\r\nContent-Type: application/octet-stream\r\n\
+            Content-Length: {}\r\n\r\n",
+            body.len(),
+        );
+
+        stream.write_all(response.as_bytes()).await?;
+
+        if method != "HEAD" {
+            stream.write_all(body).await?;
+        }
+
+        stream.shutdown().await
+    }
+
+    /// Recreate the reseed bundle every [`BUNDLE_REFRESH_INTERVAL`].
+    async fn refresh_bundle(
+        base_path: PathBuf,
+        signer_id: String,
+        signing_key: String,
+        num_routers: usize,
+        bundle: Arc<RwLock<Arc<Vec<u8>>>>,
+    ) {
+        loop {
+            tokio::time::sleep(BUNDLE_REFRESH_INTERVAL).await;
+
+            let base_path = base_path.clone();
+            let signer_id = signer_id.clone();
+            let signing_key = signing_key.clone();
+
+            match tokio::task::spawn_blocking(move || {
+                create_bundle(&base_path, &signer_id, &signing_key, num_routers)
+            })
+            .await
+            {
+                Ok(Ok(new_bundle)) => *bundle.write() = Arc::new(new_bundle),
+                Ok(Err(error)) => tracing::warn!(
+                    target: LOG_TARGET,
+                    ?error,
+                    "failed to recreate reseed bundle",
+                ),
+                Err(error) => tracing::warn!(
+                    target: LOG_TARGET,
+                    ?error,
+                    "reseed bundle task panicked",
+                ),
+            }
+        }
+    }
+
+    /// Run the event loop of [`ReseedServer`].
+    pub async fn run(self) {
+        tracing::info!(
+            target: LOG_TARGET,
+            address = ?self.listener.local_addr().ok(),
+            signer_id = %self.signer_id,
+            "serving reseed bundles",
+        );
+
+        tokio::spawn(Self::refresh_bundle(
+            self.base_path,
+            self.signer_id,
+            self.signing_key,
+            self.num_routers,
+            Arc::clone(&self.bundle),
+        ));
+
+        loop {
+            let (stream, address) = match self.listener.accept().await {
+                Ok(connection) => connection,
+                Err(error) => {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        ?error,
+                        "failed to accept connection",
+                    );
+                    continue;
+                }
+            };
+            let acceptor = self.acceptor.clone();
+            let bundle = Arc::clone(&self.bundle.read());
+
+            tokio::spawn(async move {
+                let future = async move {
+                    let stream = acceptor.accept(stream).await.map_err(io::Error::other)?;
+                    Self::on_connection(stream, bundle).await
+                };
+
+                match tokio::time::timeout(REQUEST_TIMEOUT, future).await {
+                    Ok(Ok(())) => tracing::debug!(
+                        target: LOG_TARGET,
+                        ?address,
+                        "reseed bundle served",
+                    ),
+                    Ok(Err(error)) => tracing::debug!(
+                        target: LOG_TARGET,
+                        ?address,
+                        ?error,
+                        "failed to serve reseed bundle",
+                    ),
+                    Err(_) => tracing::debug!(
+                        target: LOG_TARGET,
+                        ?address,
+                        "reseed request timed out",
+                    ),
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use tempfile::tempdir;
+
+    const SU3: &[u8] = include_bytes!("../../../emissary-util/assets/i2pseeds.su3");
+    const KEY: &str = include_str!("../../../emissary-util/assets/test/test_at_mail.i2p.pem");
+
+    /// Store the router infos of the test reseed file in the netDb of `base_path`.
+    fn make_netdb(base_path: &Path) -> usize {
+        let routers = Su3::parse_reseed(SU3, true).unwrap();
+
+        for ReseedRouterInfo { name, router_info } in &routers {
+            let dir = base_path.join(format!("netDb/r{}", &name["routerInfo-".len()..][..1]));
+
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join(name), router_info).unwrap();
+        }
+
+        routers
+            .iter()
+            .filter(|router| {
+                RouterInfo::parse(&router.router_info).is_some_and(|info| info.is_reachable())
+            })
+            .count()
+    }
+
+    #[test]
+    fn bundle_created_from_netdb() {
+        let dir = tempdir().unwrap();
+        let num_reachable = make_netdb(dir.path());
+        assert!(num_reachable > 10);
+
+        let bundle = create_bundle(dir.path(), "test@mail.i2p", KEY, 10).unwrap();
+        let routers = Su3::parse_reseed(&bundle, false).unwrap();
+
+        assert_eq!(routers.len(), 10);
+        assert_eq!(
+            routers.iter().map(|router| &router.name).collect::<HashSet<_>>().len(),
+            10
+        );
+        assert!(
+            routers.iter().all(|router| RouterInfo::parse(&router.router_info).is_some_and(
+                |info| format!(
+                    "routerInfo-{}.dat",
+                    base64_encode(info.identity.id().to_vec())
+                ) == router.name
+            ))
+        );
+
+        let bundle = create_bundle(dir.path(), "test@mail.i2p", KEY, usize::MAX).unwrap();
+        assert_eq!(
+            Su3::parse_reseed(&bundle, false).unwrap().len(),
+            num_reachable
+        );
+    }
+
+    #[test]
+    fn empty_netdb() {
+        let dir = tempdir().unwrap();
+        assert!(create_bundle(dir.path(), "test@mail.i2p", KEY, 10).is_err());
+    }
+
+    #[tokio::test]
+    async fn bundle_served() {
+        async fn request(request: &str) -> Vec<u8> {
+            let (mut client, server) = tokio::io::duplex(4096);
+            let handle = tokio::spawn(ReseedServer::on_connection(
+                server,
+                Arc::new(b"hello, world".to_vec()),
+            ));
+
+            client.write_all(request.as_bytes()).await.unwrap();
+
+            let mut response = Vec::new();
+            client.read_to_end(&mut response).await.unwrap();
+            handle.await.unwrap().unwrap();
+
+            response
+        }
+
+        let response =
+            request("GET /i2pseeds.su3?netid=2 HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+        assert!(response.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(b"\r\n\r\nhello, world"));
+
+        let response = request("GET /hosts.txt HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+        assert!(response.starts_with(b"HTTP/1.1 404 Not Found\r\n"));
+
+        let response = request("POST /i2pseeds.su3 HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+        assert!(response.starts_with(b"HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+}
@@ -16,12 +16,19 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use crate::config::ClientTunnelConfig;
+use crate::{
+    config::{ClientTunnelConfig, ClientTunnelKind},
+    tunnel::{udp::UdpClientTunnel, TunnelCommand},
+};
 
-use tokio::{net::TcpListener, task::JoinSet};
+use tokio::{
+    net::TcpListener,
+    sync::mpsc::Receiver,
+    task::{AbortHandle, JoinError, JoinSet},
+};
 use yosemite::{style, Session, SessionOptions, StreamOptions};
 
-use std::{future::Future, sync::Arc, time::Duration};
+use std::{collections::HashMap, future::Future, mem, sync::Arc, time::Duration};
 
 /// Logging target for the file.
 const LOG_TARGET: &str = "emissary::client-tunnel";
@@ -31,22 +38,43 @@ const RETRY_TIMEOUT: Duration = Duration::from_secs(15);
 
 /// Client tunnel manager.
 pub struct ClientTunnelManager {
+    /// RX channel for receiving commands from [`TunnelManagerHandle`](super::TunnelManagerHandle).
+    command_rx: Receiver<TunnelCommand<ClientTunnelConfig>>,
+
     /// Tunnel futures.
     futures: JoinSet<Arc<ClientTunnelConfig>>,
 
+    /// Running tunnels, indexed by name.
+    running: HashMap<String, (Arc<ClientTunnelConfig>, AbortHandle)>,
+
     /// SAMv3 server port of the router.
     sam_tcp_port: u16,
 
+    /// SAMv3 UDP port of the router.
+    sam_udp_port: u16,
+
+    /// Session shared by all TCP client tunnels, created when the first tunnel is started.
+    session: Option<Session<style::Stream>>,
+
     /// Client tunnel configurations.
     tunnels: Vec<Arc<ClientTunnelConfig>>,
 }
 
 impl ClientTunnelManager {
     /// Create new [`ClientTunnelManager`].
-    pub fn new(tunnels: Vec<ClientTunnelConfig>, sam_tcp_port: u16) -> Self {
+    pub fn new(
+        tunnels: Vec<ClientTunnelConfig>,
+        sam_tcp_port: u16,
+        sam_udp_port: u16,
+        command_rx: Receiver<TunnelCommand<ClientTunnelConfig>>,
+    ) -> Self {
         Self {
+            command_rx,
             futures: JoinSet::new(),
+            running: HashMap::new(),
             sam_tcp_port,
+            sam_udp_port,
+            session: None,
             tunnels: tunnels.into_iter().map(Arc::from).collect(),
         }
     }
@@ -71,107 +99,201 @@ impl ClientTunnelManager {
         Ok(())
     }
 
-    /// Run the event loop of [`ClientTunnelManger`].
+    /// Run the event loop of a UDP client tunnel.
     ///
-    /// If there are no client tunnels congigured, [`ClientTunnelManager`] exits immediately.
-    pub async fn run(mut self) {
-        if self.tunnels.is_empty() {
-            return;
+    /// If the tunnel exits with an error, it's restarted after [`RETRY_TIMEOUT`].
+    async fn udp_tunnel_event_loop(
+        tunnel: Arc<ClientTunnelConfig>,
+        sam_tcp_port: u16,
+        sam_udp_port: u16,
+    ) {
+        loop {
+            let result =
+                match UdpClientTunnel::new(Arc::clone(&tunnel), sam_tcp_port, sam_udp_port).await {
+                    Ok(udp_tunnel) => udp_tunnel.run().await,
+                    Err(error) => Err(error),
+                };
+
+            if let Err(error) = result {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    name = %tunnel.name,
+                    ?error,
+                    "udp client tunnel exited with error",
+                );
+            }
+
+            tokio::time::sleep(RETRY_TIMEOUT).await;
         }
+    }
 
+    /// Start client tunnel.
+    ///
+    /// TCP client tunnels share a session which is created when the first TCP client tunnel is
+    /// started. If the session cannot be created, the tunnel is not started.
+    async fn start_tunnel(&mut self, tunnel: Arc<ClientTunnelConfig>) {
         tracing::info!(
             target: LOG_TARGET,
-            num_tunnels = ?self.tunnels.len(),
-            "starting client tunnel manager",
+            name = %tunnel.name,
+            port = %tunnel.port,
+            kind = ?tunnel.kind,
+            "starting client tunnel",
         );
 
-        let mut session = match Session::<style::Stream>::new(SessionOptions {
-            publish: false,
-            samv3_tcp_port: self.sam_tcp_port,
-            nickname: "i2p-tunnel".to_string(),
-            inbound_quantity: 4,
-            outbound_quantity: 4,
-            ..Default::default()
-        })
-        .await
-        {
-            Ok(session) => session,
-            Err(error) => {
-                tracing::error!(
-                    target: LOG_TARGET,
-                    ?error,
-                    "failed to start client tunnel manager",
-                );
-                return;
-            }
-        };
+        if tunnel.kind == ClientTunnelKind::Udp {
+            let handle = tokio::spawn(Self::udp_tunnel_event_loop(
+                Arc::clone(&tunnel),
+                self.sam_tcp_port,
+                self.sam_udp_port,
+            ))
+            .abort_handle();
 
-        for tunnel in self.tunnels.iter().cloned() {
-            let future = session.connect_detached_with_options(
-                &tunnel.destination,
-                StreamOptions {
-                    dst_port: tunnel.destination_port.unwrap_or(0),
-                    ..Default::default()
-                },
-            );
+            self.running.insert(tunnel.name.clone(), (tunnel, handle));
+            return;
+        }
 
-            self.futures.spawn(async move {
-                match Self::tunnel_event_loop(future, &tunnel).await {
-                    Ok(()) => tunnel,
-                    Err(error) => {
-                        tracing::debug!(
-                            target: LOG_TARGET,
-                            name = %tunnel.name,
-                            ?error,
-                            "client tunnel exited with error",
-                        );
-
-                        tokio::time::sleep(RETRY_TIMEOUT).await;
-                        tunnel
-                    }
+        if self.session.is_none() {
+            match Session::<style::Stream>::new(SessionOptions {
+                publish: false,
+                samv3_tcp_port: self.sam_tcp_port,
+                nickname: "i2p-tunnel".to_string(),
+                inbound_quantity: 4,
+                outbound_quantity: 4,
+                ..Default::default()
+            })
+            .await
+            {
+                Ok(session) => self.session = Some(session),
+                Err(error) => {
+                    tracing::error!(
+                        target: LOG_TARGET,
+                        name = %tunnel.name,
+                        ?error,
+                        "failed to create session for client tunnels",
+                    );
+                    return;
                 }
-            });
+            }
         }
 
-        while let Some(result) = self.futures.join_next().await {
-            match result {
+        self.spawn_tunnel(tunnel);
+    }
+
+    /// Spawn the event loop of a TCP client tunnel.
+    fn spawn_tunnel(&mut self, tunnel: Arc<ClientTunnelConfig>) {
+        let Some(session) = self.session.as_mut() else {
+            return;
+        };
+        let future = session.connect_detached_with_options(
+            &tunnel.destination,
+            StreamOptions {
+                dst_port: tunnel.destination_port.unwrap_or(0),
+                ..Default::default()
+            },
+        );
+        let config = Arc::clone(&tunnel);
+
+        let handle = self.futures.spawn(async move {
+            match Self::tunnel_event_loop(future, &config).await {
+                Ok(()) => config,
                 Err(error) => {
-                    tracing::warn!(
+                    tracing::debug!(
                         target: LOG_TARGET,
+                        name = %config.name,
                         ?error,
-                        "client tunnel panicked, unable to restart",
+                        "client tunnel exited with error",
                     );
-                    debug_assert!(false);
+
+                    tokio::time::sleep(RETRY_TIMEOUT).await;
+                    config
                 }
-                Ok(tunnel) => {
-                    tracing::error!(target: LOG_TARGET, "tunnel returned, restart event loop");
-
-                    let future = session.connect_detached_with_options(
-                        &tunnel.destination,
-                        StreamOptions {
-                            dst_port: tunnel.destination_port.unwrap_or(0),
-                            ..Default::default()
-                        },
-                    );
+            }
+        });
+
+        self.running.insert(tunnel.name.clone(), (tunnel, handle));
+    }
+
+    /// Stop client tunnel `name`.
+    fn stop_tunnel(&mut self, name: &str) {
+        match self.running.remove(name) {
+            None => tracing::debug!(
+                target: LOG_TARGET,
+                %name,
+                "client tunnel is not running",
+            ),
+            Some((_, handle)) => {
+                tracing::info!(
+                    target: LOG_TARGET,
+                    %name,
+                    "stopping client tunnel",
+                );
+                handle.abort();
+            }
+        }
+    }
 
-                    self.futures.spawn(async move {
-                        match Self::tunnel_event_loop(future, &tunnel).await {
-                            Ok(()) => tunnel,
-                            Err(error) => {
-                                tracing::debug!(
-                                    target: LOG_TARGET,
-                                    name = %tunnel.name,
-                                    ?error,
-                                    "client tunnel exited with error",
-                                );
-
-                                tokio::time::sleep(RETRY_TIMEOUT).await;
-                                tunnel
-                            }
-                        }
-                    });
+    /// Handle exited TCP client tunnel.
+    ///
+    /// The tunnel is restarted unless it was stopped.
+    fn on_tunnel_exited(&mut self, result: Result<Arc<ClientTunnelConfig>, JoinError>) {
+        match result {
+            Err(error) if error.is_cancelled() => {}
+            Err(error) => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    ?error,
+                    "client tunnel panicked, unable to restart",
+                );
+                debug_assert!(false);
+            }
+            Ok(tunnel) => {
+                // the tunnel may have exited right before it was stopped or replaced
+                if !self
+                    .running
+                    .get(&tunnel.name)
+                    .is_some_and(|(running, _)| Arc::ptr_eq(running, &tunnel))
+                {
+                    return;
                 }
+
+                tracing::error!(target: LOG_TARGET, "tunnel returned, restart event loop");
+                self.spawn_tunnel(tunnel);
             }
         }
     }
+
+    /// Run the event loop of [`ClientTunnelManger`].
+    ///
+    /// Client tunnels of the router configuration are started first, after which tunnels are
+    /// added and removed as commands are received.
+    pub async fn run(mut self) {
+        if !self.tunnels.is_empty() {
+            tracing::info!(
+                target: LOG_TARGET,
+                num_tunnels = ?self.tunnels.len(),
+                "starting client tunnel manager",
+            );
+        }
+
+        for tunnel in mem::take(&mut self.tunnels) {
+            self.start_tunnel(tunnel).await;
+        }
+
+        loop {
+            tokio::select! {
+                command = self.command_rx.recv() => match command {
+                    None => break,
+                    Some(TunnelCommand::Add(config)) => self.start_tunnel(Arc::new(config)).await,
+                    Some(TunnelCommand::Remove(name)) => self.stop_tunnel(&name),
+                },
+                Some(result) = self.futures.join_next(), if !self.futures.is_empty() =>
+                    self.on_tunnel_exited(result),
+            }
+        }
+
+        // tunnels can no longer be added or removed but the existing tunnels are kept running
+        while let Some(result) = self.futures.join_next().await {
+            self.on_tunnel_exited(result);
+        }
+    }
 }
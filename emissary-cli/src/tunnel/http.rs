@@ -0,0 +1,947 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! HTTP server tunnel.
+//!
+//! Inbound streams are not forwarded as-is to the server but the request headers are rewritten to
+//! include the identity of the client destination, `Host` is replaced with a configured value and
+//! spoofed identity headers sent by the client are removed.
+
+use emissary_core::{
+    crypto::{base32_encode, base64_decode, base64_encode},
+    primitives::Destination,
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::http-server-tunnel";
+
+/// Maximum size of the request head.
+const MAX_REQUEST_HEAD_SIZE: usize = 8192usize;
+
+/// Maximum size of the response head.
+const MAX_RESPONSE_HEAD_SIZE: usize = 16384usize;
+
+/// Maximum size of a chunk size or trailer line.
+const MAX_LINE_SIZE: usize = 1024usize;
+
+/// Rate limit window.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Prefix of the identity headers.
+const IDENTITY_HEADER_PREFIX: &str = "x-i2p-dest";
+
+/// Response sent when the request couldn't be parsed.
+const BAD_REQUEST: &[u8] =
+    b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+/// Response sent when the client has exceeded its rate limit.
+const TOO_MANY_REQUESTS: &[u8] =
+    b"HTTP/1.1 429 Too Many Requests\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+/// Identity of the client destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientIdentity {
+    /// Base64-encoded hash of the destination.
+    pub hash: String,
+
+    /// Base64-encoded destination.
+    pub destination: String,
+
+    /// `.b32.i2p` address of the destination.
+    pub address: String,
+}
+
+impl ClientIdentity {
+    /// Create new [`ClientIdentity`] from a base64-encoded `destination`.
+    ///
+    /// Returns `None` if `destination` is invalid.
+    pub fn new(destination: &str) -> Option<Self> {
+        let id = base64_decode(destination).and_then(Destination::parse)?.id().to_vec();
+
+        Some(Self {
+            hash: base64_encode(&id),
+            destination: destination.to_string(),
+            address: format!("{}.b32.i2p", base32_encode(&id)),
+        })
+    }
+}
+
+/// Per-destination rate limiter.
+///
+/// Requests are counted in fixed one-minute windows and the counters of all destinations are
+/// reset when a new window starts.
+pub struct RateLimiter {
+    /// Maximum number of requests per destination per window.
+    limit: usize,
+
+    /// Number of requests of each destination in the current window.
+    requests: HashMap<String, usize>,
+
+    /// When did the current window start.
+    window_start: Instant,
+}
+
+impl RateLimiter {
+    /// Create new [`RateLimiter`].
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            requests: HashMap::new(),
+            window_start: Instant::now(),
+        }
+    }
+
+    /// Register request from destination identified by `hash`.
+    ///
+    /// Returns `false` if the destination has exceeded its rate limit.
+    pub fn register(&mut self, hash: &str) -> bool {
+        self.register_at(hash, Instant::now())
+    }
+
+    fn register_at(&mut self, hash: &str, now: Instant) -> bool {
+        if now.saturating_duration_since(self.window_start) >= RATE_LIMIT_WINDOW {
+            self.requests.clear();
+            self.window_start = now;
+        }
+
+        let requests = self.requests.entry(hash.to_string()).or_default();
+
+        if *requests >= self.limit {
+            return false;
+        }
+
+        *requests += 1;
+        true
+    }
+}
+
+/// Framing of the request body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Body {
+    /// Request doesn't have a body.
+    None,
+
+    /// Body of `Content-Length` bytes.
+    Length(usize),
+
+    /// Body with chunked transfer encoding.
+    Chunked,
+}
+
+/// Rewritten request.
+#[derive(Debug)]
+pub struct Request {
+    /// Rewritten request head.
+    pub head: Vec<u8>,
+
+    /// Framing of the request body.
+    pub body: Body,
+
+    /// Whether the client asked for a protocol upgrade.
+    pub upgrade: bool,
+}
+
+/// Rewrite the head of `request` received from `client`.
+///
+/// `Host` is set to `host`, identity headers of `client` are added and identity headers sent by
+/// the client are removed. Only the first request of a connection is relayed to the server and
+/// the connection is closed after the response, unless the protocol is switched.
+///
+/// Any data received after the request head is ignored. Returns `None` if `request` is malformed
+/// or its body can't be framed unambiguously.
+pub fn rewrite_request(request: &[u8], client: &ClientIdentity, host: &str) -> Option<Request> {
+    let mut headers = [httparse::EMPTY_HEADER; 64];
+    let mut req = httparse::Request::new(&mut headers);
+    let httparse::Status::Complete(_) = req.parse(request).ok()? else {
+        return None;
+    };
+
+    let mut rewritten = Vec::with_capacity(request.len() + client.destination.len() + 256);
+
+    rewritten.extend_from_slice(format!("{} ", req.method?).as_bytes());
+    rewritten.extend_from_slice(format!("{} ", req.path?).as_bytes());
+    rewritten.extend_from_slice(format!("HTTP/1.{}\r\n", req.version?).as_bytes());
+    rewritten.extend_from_slice(format!("Host: {host}\r\n").as_bytes());
+
+    let mut connection_upgrade = false;
+    let mut upgrade_header = false;
+    let mut content_length = None::<usize>;
+    let mut chunked = None::<bool>;
+
+    for header in req.headers.iter() {
+        let name = header.name.to_lowercase();
+
+        if name == "host" || name == "keep-alive" || name.starts_with(IDENTITY_HEADER_PREFIX) {
+            continue;
+        }
+
+        if name == "connection" {
+            connection_upgrade |= std::str::from_utf8(header.value).is_ok_and(|value| {
+                value.split(',').any(|token| token.trim().eq_ignore_ascii_case("upgrade"))
+            });
+            continue;
+        }
+
+        match name.as_str() {
+            "upgrade" => upgrade_header = true,
+            "content-length" => {
+                let length = std::str::from_utf8(header.value).ok()?.trim();
+
+                if !length.bytes().all(|byte| byte.is_ascii_digit()) {
+                    return None;
+                }
+                let length = length.parse::<usize>().ok()?;
+
+                match content_length {
+                    Some(previous) if previous != length => return None,
+                    _ => content_length = Some(length),
+                }
+            }
+            "transfer-encoding" => {
+                let encoding = std::str::from_utf8(header.value).ok()?;
+
+                chunked = Some(
+                    encoding
+                        .rsplit(',')
+                        .next()
+                        .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked")),
+                );
+            }
+            _ => {}
+        }
+
+        rewritten.extend_from_slice(header.name.as_bytes());
+        rewritten.extend_from_slice(b": ");
+        rewritten.extend_from_slice(header.value);
+        rewritten.extend_from_slice(b"\r\n");
+    }
+
+    let body = match (chunked, content_length) {
+        (None, None) => Body::None,
+        (None, Some(length)) => Body::Length(length),
+        (Some(true), None) => Body::Chunked,
+        _ => return None,
+    };
+    let upgrade = connection_upgrade && upgrade_header;
+
+    rewritten.extend_from_slice(format!("X-I2P-DestHash: {}\r\n", client.hash).as_bytes());
+    rewritten.extend_from_slice(format!("X-I2P-DestB64: {}\r\n", client.destination).as_bytes());
+    rewritten.extend_from_slice(format!("X-I2P-DestB32: {}\r\n", client.address).as_bytes());
+
+    match upgrade {
+        true => rewritten.extend_from_slice(b"Connection: upgrade\r\n"),
+        false => rewritten.extend_from_slice(b"Connection: close\r\n"),
+    }
+    rewritten.extend_from_slice(b"\r\n");
+
+    Some(Request {
+        head: rewritten,
+        body,
+        upgrade,
+    })
+}
+
+/// Buffered reader for the request sent by the client.
+struct RequestReader<R> {
+    /// Read half of the client stream.
+    stream: R,
+
+    /// Data that has been read from the client but not yet consumed.
+    buffer: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> RequestReader<R> {
+    /// Create new [`RequestReader`].
+    fn new(stream: R) -> Self {
+        Self {
+            stream,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Read more data from the client.
+    async fn fill(&mut self) -> crate::Result<()> {
+        let mut chunk = [0u8; 4096];
+
+        match self.stream.read(&mut chunk).await? {
+            0 => Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe).into()),
+            nread => {
+                self.buffer.extend_from_slice(&chunk[..nread]);
+                Ok(())
+            }
+        }
+    }
+
+    /// Read the request head.
+    async fn read_head(&mut self) -> crate::Result<Vec<u8>> {
+        loop {
+            let mut headers = [httparse::EMPTY_HEADER; 64];
+
+            match httparse::Request::new(&mut headers).parse(&self.buffer) {
+                Ok(httparse::Status::Complete(body_start)) => {
+                    let rest = self.buffer.split_off(body_start);
+                    return Ok(std::mem::replace(&mut self.buffer, rest));
+                }
+                Ok(httparse::Status::Partial) if self.buffer.len() < MAX_REQUEST_HEAD_SIZE =>
+                    self.fill().await?,
+                _ => return Err(crate::error::Error::InvalidData),
+            }
+        }
+    }
+
+    /// Read a line, including the line terminator.
+    async fn read_line(&mut self) -> crate::Result<Vec<u8>> {
+        loop {
+            if let Some(position) = self.buffer.windows(2).position(|window| window == b"\r\n") {
+                let rest = self.buffer.split_off(position + 2);
+                return Ok(std::mem::replace(&mut self.buffer, rest));
+            }
+
+            if self.buffer.len() >= MAX_LINE_SIZE {
+                return Err(crate::error::Error::InvalidData);
+            }
+
+            self.fill().await?;
+        }
+    }
+
+    /// Forward `len` bytes to `writer`.
+    async fn forward(
+        &mut self,
+        mut len: usize,
+        writer: &mut (impl AsyncWrite + Unpin),
+    ) -> crate::Result<()> {
+        while len > 0 {
+            if self.buffer.is_empty() {
+                self.fill().await?;
+            }
+
+            let nforward = std::cmp::min(len, self.buffer.len());
+            writer.write_all(&self.buffer[..nforward]).await?;
+            self.buffer.drain(..nforward);
+            len -= nforward;
+        }
+
+        Ok(())
+    }
+
+    /// Forward request body to `writer`.
+    ///
+    /// Only the body is forwarded and any data the client sent after it is left in the buffer.
+    async fn forward_body(
+        &mut self,
+        body: Body,
+        writer: &mut (impl AsyncWrite + Unpin),
+    ) -> crate::Result<()> {
+        match body {
+            Body::None => Ok(()),
+            Body::Length(len) => self.forward(len, writer).await,
+            Body::Chunked => loop {
+                let line = self.read_line().await?;
+                let size = std::str::from_utf8(&line[..line.len() - 2])
+                    .ok()
+                    .and_then(|line| line.split(';').next())
+                    .map(str::trim)
+                    .filter(|size| !size.is_empty() && size.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .and_then(|size| usize::from_str_radix(size, 16).ok())
+                    .ok_or(crate::error::Error::InvalidData)?;
+
+                writer.write_all(&line).await?;
+
+                if size == 0 {
+                    // forward trailers, except spoofed identity headers
+                    loop {
+                        let line = self.read_line().await?;
+
+                        if line.to_ascii_lowercase().starts_with(IDENTITY_HEADER_PREFIX.as_bytes())
+                        {
+                            continue;
+                        }
+                        writer.write_all(&line).await?;
+
+                        if line == b"\r\n" {
+                            return Ok(());
+                        }
+                    }
+                }
+
+                self.forward(size, writer).await?;
+
+                if self.read_line().await? != b"\r\n" {
+                    return Err(crate::error::Error::InvalidData);
+                }
+                writer.write_all(b"\r\n").await?;
+            },
+        }
+    }
+}
+
+/// Relay response head from `server` to `client`.
+///
+/// Returns the status code of the response.
+async fn relay_response_head(
+    server: &mut (impl AsyncRead + Unpin),
+    client: &mut (impl AsyncWrite + Unpin),
+) -> crate::Result<u16> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    loop {
+        let nread = match server.read(&mut chunk).await? {
+            0 => return Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe).into()),
+            nread => nread,
+        };
+        client.write_all(&chunk[..nread]).await?;
+        buffer.extend_from_slice(&chunk[..nread]);
+
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut response = httparse::Response::new(&mut headers);
+
+        match response.parse(&buffer) {
+            Ok(httparse::Status::Complete(_)) =>
+                return response.code.ok_or(crate::error::Error::InvalidData),
+            Ok(httparse::Status::Partial) if buffer.len() < MAX_RESPONSE_HEAD_SIZE => {}
+            _ => return Err(crate::error::Error::InvalidData),
+        }
+    }
+}
+
+/// Reject inbound `stream` because the client has exceeded its rate limit.
+pub async fn reject(mut stream: impl AsyncWrite + Unpin) {
+    let _ = stream.write_all(TOO_MANY_REQUESTS).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Handle inbound `stream` from `client`.
+///
+/// Reads the request head from `stream`, rewrites it and relays the request and its body to the
+/// server listening on `port`. The response is relayed back to the client and the connection is
+/// closed once the server closes its side, which means that pipelined requests are discarded.
+///
+/// If the client asked for a protocol upgrade and the server responded with `101`, data is
+/// relayed between the client and the server until either side closes the connection.
+pub async fn handle_stream(
+    stream: impl AsyncRead + AsyncWrite + Unpin,
+    client: ClientIdentity,
+    port: u16,
+    host: &str,
+) -> crate::Result<()> {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = RequestReader::new(reader);
+
+    let request = match reader.read_head().await {
+        Ok(head) => rewrite_request(&head, &client, host),
+        Err(error) => {
+            tracing::debug!(
+                target: LOG_TARGET,
+                client = %client.address,
+                ?error,
+                "failed to read request",
+            );
+            None
+        }
+    };
+
+    let Some(request) = request else {
+        tracing::debug!(
+            target: LOG_TARGET,
+            client = %client.address,
+            "malformed request",
+        );
+
+        let _ = writer.write_all(BAD_REQUEST).await;
+        return Err(crate::error::Error::InvalidData);
+    };
+
+    let server = TcpStream::connect(("127.0.0.1", port)).await?;
+    let (mut server_read, mut server_write) = server.into_split();
+
+    server_write.write_all(&request.head).await?;
+
+    if !request.upgrade {
+        {
+            let body = reader.forward_body(request.body, &mut server_write);
+            let response = tokio::io::copy(&mut server_read, &mut writer);
+            tokio::pin!(body, response);
+
+            // the response may be received before the body has been forwarded in full
+            tokio::select! {
+                result = &mut response => {
+                    result?;
+                }
+                result = &mut body => {
+                    result?;
+                    response.await?;
+                }
+            }
+        }
+
+        return writer.shutdown().await.map_err(From::from);
+    }
+
+    reader.forward_body(request.body, &mut server_write).await?;
+
+    if relay_response_head(&mut server_read, &mut writer).await? != 101 {
+        tokio::io::copy(&mut server_read, &mut writer).await?;
+        return writer.shutdown().await.map_err(From::from);
+    }
+
+    // protocol has been switched, relay any data the client sent after the request head
+    server_write.write_all(&reader.buffer).await?;
+
+    let mut stream = reader.stream.unsplit(writer);
+    let mut server = server_read
+        .reunite(server_write)
+        .map_err(|_| crate::error::Error::InvalidData)?;
+    tokio::io::copy_bidirectional(&mut stream, &mut server).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use emissary_core::crypto::SigningPrivateKey;
+    use emissary_util::runtime::tokio::Runtime;
+    use rand::rngs::OsRng;
+    use tokio::net::TcpListener;
+
+    fn make_client() -> ClientIdentity {
+        let destination = Destination::new::<Runtime>(SigningPrivateKey::random(OsRng).public());
+
+        ClientIdentity::new(&base64_encode(destination.serialize())).unwrap()
+    }
+
+    #[test]
+    fn invalid_destination() {
+        assert!(ClientIdentity::new("hello, world").is_none());
+        assert!(ClientIdentity::new(&base64_encode([0u8; 32])).is_none());
+    }
+
+    #[test]
+    fn identity_headers_added() {
+        let client = make_client();
+        let request = rewrite_request(
+            b"GET /index.html HTTP/1.1\r\nHost: example.i2p\r\nUser-Agent: test\r\n\r\n",
+            &client,
+            "127.0.0.1:8080",
+        )
+        .unwrap();
+        assert_eq!(request.body, Body::None);
+        assert!(!request.upgrade);
+        let request = request.head;
+
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut req = httparse::Request::new(&mut headers);
+        let body_start = req.parse(&request).unwrap().unwrap();
+
+        assert_eq!(body_start, request.len());
+        assert_eq!(req.method, Some("GET"));
+        assert_eq!(req.path, Some("/index.html"));
+
+        let find = |name: &str| {
+            req.headers
+                .iter()
+                .find(|header| header.name.eq_ignore_ascii_case(name))
+                .map(|header| std::str::from_utf8(header.value).unwrap().to_string())
+        };
+
+        assert_eq!(find("host").as_deref(), Some("127.0.0.1:8080"));
+        assert_eq!(find("user-agent").as_deref(), Some("test"));
+        assert_eq!(find("connection").as_deref(), Some("close"));
+        assert_eq!(find("x-i2p-desthash"), Some(client.hash.clone()));
+        assert_eq!(find("x-i2p-destb64"), Some(client.destination.clone()));
+        assert_eq!(find("x-i2p-destb32"), Some(client.address.clone()));
+        assert!(client.address.ends_with(".b32.i2p"));
+    }
+
+    #[test]
+    fn spoofed_headers_removed() {
+        let client = make_client();
+        let request = rewrite_request(
+            b"POST /upload HTTP/1.1\r\n\
+            Host: example.i2p\r\n\
+            X-I2P-DestHash: spoofed\r\n\
+            x-i2p-destb32: spoofed.b32.i2p\r\n\
+            X-I2P-DestB64: spoofed\r\n\
+            Connection: keep-alive\r\n\
+            Keep-Alive: timeout=5\r\n\
+            Content-Length: 5\r\n\r\nhello",
+            &client,
+            "example.i2p",
+        )
+        .unwrap();
+        assert_eq!(request.body, Body::Length(5));
+        let request = request.head;
+
+        let mut headers = [httparse::EMPTY_HEADER; 64];
+        let mut req = httparse::Request::new(&mut headers);
+        let body_start = req.parse(&request).unwrap().unwrap();
+
+        assert_eq!(body_start, request.len());
+        assert_eq!(
+            req.headers
+                .iter()
+                .filter(|header| header.name.eq_ignore_ascii_case("host"))
+                .count(),
+            1
+        );
+        assert!(req.headers.iter().all(|header| header.value != b"spoofed"));
+        assert!(req.headers.iter().all(|header| header.value != b"spoofed.b32.i2p"));
+        assert!(req.headers.iter().all(|header| !header.name.eq_ignore_ascii_case("keep-alive")));
+        assert_eq!(
+            req.headers
+                .iter()
+                .filter(|header| header.name.to_lowercase().starts_with(IDENTITY_HEADER_PREFIX))
+                .count(),
+            3
+        );
+        assert_eq!(
+            req.headers
+                .iter()
+                .find(|header| header.name == "Connection")
+                .map(|header| header.value),
+            Some(b"close".as_slice())
+        );
+    }
+
+    #[test]
+    fn upgrade_preserved() {
+        let client = make_client();
+        let request = rewrite_request(
+            b"GET /ws HTTP/1.1\r\nHost: example.i2p\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\r\n",
+            &client,
+            "example.i2p",
+        )
+        .unwrap();
+
+        assert!(request.upgrade);
+        assert!(std::str::from_utf8(&request.head).unwrap().contains("Connection: upgrade\r\n"));
+    }
+
+    #[test]
+    fn upgrade_without_upgrade_header() {
+        let request = rewrite_request(
+            b"GET / HTTP/1.1\r\nHost: example.i2p\r\nConnection: upgrade\r\n\r\n",
+            &make_client(),
+            "example.i2p",
+        )
+        .unwrap();
+
+        assert!(!request.upgrade);
+        assert!(std::str::from_utf8(&request.head).unwrap().contains("Connection: close\r\n"));
+    }
+
+    #[test]
+    fn ambiguous_body_rejected() {
+        let client = make_client();
+
+        // both `Content-Length` and `Transfer-Encoding`
+        assert!(rewrite_request(
+            b"POST / HTTP/1.1\r\nContent-Length: 5\r\nTransfer-Encoding: chunked\r\n\r\n",
+            &client,
+            "example.i2p",
+        )
+        .is_none());
+
+        // conflicting `Content-Length` values
+        assert!(rewrite_request(
+            b"POST / HTTP/1.1\r\nContent-Length: 5\r\nContent-Length: 6\r\n\r\n",
+            &client,
+            "example.i2p",
+        )
+        .is_none());
+
+        // body that isn't chunked and has no length
+        assert!(rewrite_request(
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n",
+            &client,
+            "example.i2p",
+        )
+        .is_none());
+
+        assert_eq!(
+            rewrite_request(
+                b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n",
+                &client,
+                "example.i2p",
+            )
+            .unwrap()
+            .body,
+            Body::Chunked
+        );
+    }
+
+    #[test]
+    fn malformed_request() {
+        assert!(rewrite_request(b"hello, world\r\n\r\n", &make_client(), "example.i2p").is_none());
+        assert!(
+            rewrite_request(b"GET / HTTP/1.1\r\nHost: ", &make_client(), "example.i2p").is_none()
+        );
+    }
+
+    #[test]
+    fn rate_limit() {
+        let mut limiter = RateLimiter::new(2);
+        let now = Instant::now();
+
+        assert!(limiter.register_at("client1", now));
+        assert!(limiter.register_at("client1", now));
+        assert!(!limiter.register_at("client1", now));
+        assert!(limiter.register_at("client2", now));
+
+        // counters are reset when a new window starts
+        assert!(limiter.register_at("client1", now + RATE_LIMIT_WINDOW));
+        assert_eq!(limiter.requests.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn request_relayed_to_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let client = make_client();
+        let (mut stream, remote) = tokio::io::duplex(4096);
+
+        let handle = tokio::spawn({
+            let client = client.clone();
+            async move { handle_stream(remote, client, port, "example.i2p").await }
+        });
+
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: foo.i2p\r\nX-I2P-DestB32: spoofed\r\n\r\n")
+            .await
+            .unwrap();
+
+        let (mut server, _) = listener.accept().await.unwrap();
+        let mut buffer = vec![0u8; 4096];
+        let mut nread = 0usize;
+
+        while !buffer[..nread].ends_with(b"\r\n\r\n") {
+            nread += server.read(&mut buffer[nread..]).await.unwrap();
+        }
+
+        let request = std::str::from_utf8(&buffer[..nread]).unwrap();
+        assert!(request.contains("Host: example.i2p\r\n"));
+        assert!(request.contains(&format!("X-I2P-DestB32: {}\r\n", client.address)));
+        assert!(!request.contains("spoofed"));
+
+        server.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").await.unwrap();
+        drop(server);
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        assert_eq!(response, b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+
+        drop(stream);
+        let _ = handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn malformed_request_rejected() {
+        let client = make_client();
+        let (mut stream, remote) = tokio::io::duplex(4096);
+
+        let handle =
+            tokio::spawn(async move { handle_stream(remote, client, 1, "example.i2p").await });
+
+        stream.write_all(b"hello, world\r\n\r\n").await.unwrap();
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        assert_eq!(response, BAD_REQUEST);
+        assert!(handle.await.unwrap().is_err());
+    }
+
+    /// Read everything the server receives until the client side of the tunnel is closed.
+    async fn read_server(listener: &TcpListener) -> String {
+        let (mut server, _) = listener.accept().await.unwrap();
+        let mut received = Vec::new();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            let mut buffer = vec![0u8; 4096];
+
+            loop {
+                match server.read(&mut buffer).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(nread) => received.extend_from_slice(&buffer[..nread]),
+                }
+
+                // respond once the request head has been received so the tunnel closes
+                if received.windows(4).any(|window| window == b"\r\n\r\n") {
+                    let _ = server.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").await;
+                    let _ = server.shutdown().await;
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        String::from_utf8(received).unwrap()
+    }
+
+    #[tokio::test]
+    async fn pipelined_requests_not_relayed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let client = make_client();
+        let (mut stream, remote) = tokio::io::duplex(4096);
+
+        let handle = tokio::spawn({
+            let client = client.clone();
+            async move { handle_stream(remote, client, port, "example.i2p").await }
+        });
+
+        stream
+            .write_all(
+                b"POST / HTTP/1.1\r\nHost: foo.i2p\r\nContent-Length: 5\r\n\r\nhello\
+                GET /second HTTP/1.1\r\nHost: foo.i2p\r\nX-I2P-DestB32: spoofed\r\n\r\n",
+            )
+            .await
+            .unwrap();
+
+        let received = read_server(&listener).await;
+        assert!(received.ends_with("\r\n\r\nhello"));
+        assert!(!received.contains("/second"));
+        assert!(!received.contains("spoofed"));
+        assert_eq!(received.matches("X-I2P-DestB32").count(), 1);
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        assert_eq!(response, b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+        let _ = handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn chunked_body_relayed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (mut stream, remote) = tokio::io::duplex(4096);
+
+        let handle = tokio::spawn({
+            let client = make_client();
+            async move { handle_stream(remote, client, port, "example.i2p").await }
+        });
+
+        stream
+            .write_all(
+                b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+                5\r\nhello\r\n0\r\nX-I2P-DestHash: spoofed\r\n\r\n\
+                GET / HTTP/1.1\r\nX-I2P-DestHash: spoofed\r\n\r\n",
+            )
+            .await
+            .unwrap();
+
+        let received = read_server(&listener).await;
+        assert!(received.ends_with("\r\n\r\n5\r\nhello\r\n0\r\n\r\n"));
+        assert!(!received.contains("spoofed"));
+
+        drop(stream);
+        let _ = handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn fake_upgrade_not_relayed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (mut stream, remote) = tokio::io::duplex(4096);
+
+        let handle = tokio::spawn({
+            let client = make_client();
+            async move { handle_stream(remote, client, port, "example.i2p").await }
+        });
+
+        stream
+            .write_all(
+                b"GET / HTTP/1.1\r\nHost: foo.i2p\r\nConnection: upgrade\r\n\r\n\
+                GET / HTTP/1.1\r\nHost: foo.i2p\r\nX-I2P-DestHash: spoofed\r\n\r\n",
+            )
+            .await
+            .unwrap();
+
+        let received = read_server(&listener).await;
+        assert!(received.contains("Connection: close\r\n"));
+        assert!(!received.contains("spoofed"));
+        assert_eq!(received.matches("GET").count(), 1);
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        let _ = handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn upgraded_connection_relayed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (mut stream, remote) = tokio::io::duplex(4096);
+
+        let handle = tokio::spawn({
+            let client = make_client();
+            async move { handle_stream(remote, client, port, "example.i2p").await }
+        });
+
+        stream
+            .write_all(b"GET /ws HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\r\nping")
+            .await
+            .unwrap();
+
+        let (mut server, _) = listener.accept().await.unwrap();
+        let mut buffer = vec![0u8; 4096];
+        let mut nread = 0usize;
+
+        while !buffer[..nread].ends_with(b"\r\n\r\n") {
+            nread += server.read(&mut buffer[nread..]).await.unwrap();
+        }
+        assert!(std::str::from_utf8(&buffer[..nread])
+            .unwrap()
+            .contains("Connection: upgrade\r\n"));
+
+        server
+            .write_all(b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\r\n")
+            .await
+            .unwrap();
+
+        // data sent by the client after the request head is relayed once the protocol switched
+        let mut ping = [0u8; 4];
+        server.read_exact(&mut ping).await.unwrap();
+        assert_eq!(&ping, b"ping");
+
+        let mut response = vec![0u8; 4096];
+        let mut nread = 0usize;
+        while !response[..nread].ends_with(b"\r\n\r\n") {
+            nread += stream.read(&mut response[nread..]).await.unwrap();
+        }
+
+        server.write_all(b"pong").await.unwrap();
+        let mut pong = [0u8; 4];
+        stream.read_exact(&mut pong).await.unwrap();
+        assert_eq!(&pong, b"pong");
+
+        drop(server);
+        drop(stream);
+        let _ = handle.await.unwrap();
+    }
+}
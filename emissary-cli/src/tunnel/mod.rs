@@ -16,5 +16,233 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+//! Client and server tunnels.
+//!
+//! Tunnels are started from the router configuration and can be added and removed while the
+//! router is running through [`TunnelManagerHandle`].
+
+use crate::config::{ClientTunnelConfig, ServerTunnelConfig};
+
+use parking_lot::Mutex;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+use std::{collections::BTreeMap, sync::Arc};
+
 pub mod client;
+pub mod http;
 pub mod server;
+pub mod udp;
+
+/// Size of the command channels of the tunnel managers.
+const COMMAND_CHANNEL_SIZE: usize = 64usize;
+
+/// Command sent to a tunnel manager.
+#[derive(Debug)]
+pub enum TunnelCommand<T> {
+    /// Start new tunnel.
+    Add(T),
+
+    /// Stop tunnel.
+    Remove(String),
+}
+
+/// Kind of a tunnel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TunnelKind {
+    /// Client tunnel.
+    Client,
+
+    /// Server tunnel.
+    Server,
+}
+
+/// Registered tunnel.
+#[derive(Debug)]
+enum TunnelEntry {
+    /// Client tunnel listening on `port`.
+    Client { port: u16 },
+
+    /// Server tunnel using the destination stored in `destination_path`.
+    Server { destination_path: String },
+}
+
+/// Tunnel error.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum TunnelError {
+    #[error("tunnel `{0}` already exists")]
+    AlreadyExists(String),
+
+    #[error("tunnel `{0}` doesn't exist")]
+    DoesntExist(String),
+
+    #[error("{0}")]
+    InvalidConfig(String),
+
+    #[error("tunnel manager is not available")]
+    NotAvailable,
+}
+
+/// Tunnel manager handle.
+///
+/// Allows adding and removing client and server tunnels while the router is running. Tunnel
+/// names are unique across client and server tunnels.
+#[derive(Clone)]
+pub struct TunnelManagerHandle {
+    /// TX channel for sending commands to [`ClientTunnelManager`](client::ClientTunnelManager).
+    client_tx: Sender<TunnelCommand<ClientTunnelConfig>>,
+
+    /// TX channel for sending commands to [`ServerTunnelManager`](server::ServerTunnelManager).
+    server_tx: Sender<TunnelCommand<ServerTunnelConfig>>,
+
+    /// Registered tunnels.
+    tunnels: Arc<Mutex<BTreeMap<String, TunnelEntry>>>,
+}
+
+impl TunnelManagerHandle {
+    /// Create new [`TunnelManagerHandle`] and register `client_tunnels` and `server_tunnels`.
+    ///
+    /// Returns the handle and RX channels for the client and server tunnel managers.
+    #[allow(clippy::type_complexity)]
+    pub fn new(
+        client_tunnels: &[ClientTunnelConfig],
+        server_tunnels: &[ServerTunnelConfig],
+    ) -> (
+        Self,
+        Receiver<TunnelCommand<ClientTunnelConfig>>,
+        Receiver<TunnelCommand<ServerTunnelConfig>>,
+    ) {
+        let (client_tx, client_rx) = channel(COMMAND_CHANNEL_SIZE);
+        let (server_tx, server_rx) = channel(COMMAND_CHANNEL_SIZE);
+        let tunnels = client_tunnels
+            .iter()
+            .map(|config| {
+                (
+                    config.name.clone(),
+                    TunnelEntry::Client { port: config.port },
+                )
+            })
+            .chain(server_tunnels.iter().map(|config| {
+                (
+                    config.name.clone(),
+                    TunnelEntry::Server {
+                        destination_path: config.destination_path.clone(),
+                    },
+                )
+            }))
+            .collect();
+
+        (
+            Self {
+                client_tx,
+                server_tx,
+                tunnels: Arc::new(Mutex::new(tunnels)),
+            },
+            client_rx,
+            server_rx,
+        )
+    }
+
+    /// Get names and kinds of all registered tunnels, sorted by name.
+    pub fn tunnels(&self) -> Vec<(String, TunnelKind)> {
+        self.tunnels
+            .lock()
+            .iter()
+            .map(|(name, entry)| {
+                let kind = match entry {
+                    TunnelEntry::Client { .. } => TunnelKind::Client,
+                    TunnelEntry::Server { .. } => TunnelKind::Server,
+                };
+
+                (name.clone(), kind)
+            })
+            .collect()
+    }
+
+    /// Start new client tunnel.
+    ///
+    /// The tunnel must have a unique name and port.
+    pub fn add_client_tunnel(&self, config: ClientTunnelConfig) -> Result<(), TunnelError> {
+        config.validate().map_err(TunnelError::InvalidConfig)?;
+
+        let mut tunnels = self.tunnels.lock();
+
+        if tunnels.contains_key(&config.name) {
+            return Err(TunnelError::AlreadyExists(config.name));
+        }
+
+        if tunnels.values().any(
+            |entry| core::matches!(entry, TunnelEntry::Client { port } if *port == config.port),
+        ) {
+            return Err(TunnelError::InvalidConfig(format!(
+                "port {} is used by another client tunnel",
+                config.port
+            )));
+        }
+
+        let name = config.name.clone();
+        let port = config.port;
+
+        Self::send(&self.client_tx, TunnelCommand::Add(config))?;
+        tunnels.insert(name, TunnelEntry::Client { port });
+
+        Ok(())
+    }
+
+    /// Start new server tunnel.
+    ///
+    /// The tunnel must have a unique name and destination path.
+    pub fn add_server_tunnel(&self, config: ServerTunnelConfig) -> Result<(), TunnelError> {
+        config.validate().map_err(TunnelError::InvalidConfig)?;
+
+        let mut tunnels = self.tunnels.lock();
+
+        if tunnels.contains_key(&config.name) {
+            return Err(TunnelError::AlreadyExists(config.name));
+        }
+
+        if tunnels.values().any(|entry| {
+            core::matches!(
+                entry,
+                TunnelEntry::Server { destination_path }
+                    if destination_path == &config.destination_path
+            )
+        }) {
+            return Err(TunnelError::InvalidConfig(format!(
+                "destination path `{}` is used by another server tunnel",
+                config.destination_path
+            )));
+        }
+
+        let name = config.name.clone();
+        let destination_path = config.destination_path.clone();
+
+        Self::send(&self.server_tx, TunnelCommand::Add(config))?;
+        tunnels.insert(name, TunnelEntry::Server { destination_path });
+
+        Ok(())
+    }
+
+    /// Stop tunnel `name`.
+    pub fn remove_tunnel(&self, name: &str) -> Result<(), TunnelError> {
+        let mut tunnels = self.tunnels.lock();
+
+        match tunnels.get(name) {
+            None => return Err(TunnelError::DoesntExist(name.to_string())),
+            Some(TunnelEntry::Client { .. }) =>
+                Self::send(&self.client_tx, TunnelCommand::Remove(name.to_string()))?,
+            Some(TunnelEntry::Server { .. }) =>
+                Self::send(&self.server_tx, TunnelCommand::Remove(name.to_string()))?,
+        }
+        tunnels.remove(name);
+
+        Ok(())
+    }
+
+    /// Send `command` to a tunnel manager.
+    fn send<T>(
+        tx: &Sender<TunnelCommand<T>>,
+        command: TunnelCommand<T>,
+    ) -> Result<(), TunnelError> {
+        tx.try_send(command).map_err(|_| TunnelError::NotAvailable)
+    }
+}
@@ -16,11 +16,24 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use crate::config::ServerTunnelConfig;
+use crate::{
+    config::{MetaLeaseSetConfig, ServerTunnelConfig, ServerTunnelKind},
+    tunnel::{
+        http::{self, ClientIdentity, RateLimiter},
+        udp::UdpServerTunnel,
+        TunnelCommand,
+    },
+};
 
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    sync::mpsc::Receiver,
+    task::AbortHandle,
+};
 use yosemite::{style, DestinationKind, RouterApi, Session, SessionOptions};
 
-use std::{path::PathBuf, sync::Arc, time::Duration};
+use std::{collections::HashMap, mem, path::PathBuf, sync::Arc, time::Duration};
 
 /// Logging target for the file.
 const LOG_TARGET: &str = "emissary::server-tunnel";
@@ -34,23 +47,73 @@ const DESTINATION_CREATION_BACKOFF: Duration = Duration::from_secs(10);
 /// Backoff for `STREAM FORWARD` failure.
 const STREAM_FORWARD_BACKOFF: Duration = Duration::from_secs(10);
 
+/// Backoff for `STREAM ACCEPT` failure.
+const STREAM_ACCEPT_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Backoff for meta lease set session failure.
+const META_SESSION_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Meta lease set configuration of a server tunnel.
+pub struct MetaLeaseSet {
+    /// Base64 destination of the meta lease set.
+    destination: String,
+
+    /// Member destinations, as `<base32 address>[:<cost>]`.
+    members: Vec<String>,
+}
+
 /// Server tunnel configuration
 pub struct TunnelConfig {
     /// Base64 destination.
     destination: String,
 
+    /// Value of the `Host` header of HTTP server tunnels.
+    host: String,
+
+    /// Tunnel kind.
+    kind: ServerTunnelKind,
+
+    /// Meta lease set configuration, if any.
+    meta_lease_set: Option<MetaLeaseSet>,
+
     /// Name of the tunnel.
     name: String,
 
     /// Server port.
     port: u16,
 
+    /// Maximum number of requests per minute per client destination, if any.
+    requests_per_minute: Option<usize>,
+
     /// SAMv3 TCP port.
     sam_tcp_port: u16,
+
+    /// SAMv3 UDP port.
+    sam_udp_port: u16,
 }
 
 /// Server tunnel manager.
 pub struct ServerTunnelManager {
+    /// Base path of the router.
+    base_path: PathBuf,
+
+    /// RX channel for receiving commands from [`TunnelManagerHandle`](super::TunnelManagerHandle).
+    command_rx: Receiver<TunnelCommand<ServerTunnelConfig>>,
+
+    /// Router API used to generate destinations.
+    router_api: RouterApi,
+
+    /// Running tunnels, indexed by name.
+    ///
+    /// Each tunnel has an event loop and, if it has a meta lease set, a meta lease set session.
+    running: HashMap<String, Vec<AbortHandle>>,
+
+    /// SAMv3 TCP port.
+    sam_tcp_port: u16,
+
+    /// SAMv3 UDP port.
+    sam_udp_port: u16,
+
     /// Server tunnels.
     tunnels: Vec<Arc<TunnelConfig>>,
 }
@@ -60,21 +123,52 @@ impl ServerTunnelManager {
     pub async fn new(
         configs: Vec<ServerTunnelConfig>,
         sam_tcp_port: u16,
+        sam_udp_port: u16,
         base_path: PathBuf,
+        command_rx: Receiver<TunnelCommand<ServerTunnelConfig>>,
     ) -> Self {
-        let mut tunnels = Vec::<Arc<TunnelConfig>>::new();
-        let mut router_api = RouterApi::new(sam_tcp_port);
+        let mut manager = Self {
+            base_path,
+            command_rx,
+            router_api: RouterApi::new(sam_tcp_port),
+            running: HashMap::new(),
+            sam_tcp_port,
+            sam_udp_port,
+            tunnels: Vec::new(),
+        };
+
+        for config in configs {
+            if let Some(tunnel) = manager.load_tunnel(config).await {
+                manager.tunnels.push(Arc::new(tunnel));
+            }
+        }
 
-        for ServerTunnelConfig {
+        manager
+    }
+
+    /// Load destinations of the server tunnel, creating them if they don't exist.
+    ///
+    /// Returns `None` if a destination couldn't be loaded or created.
+    async fn load_tunnel(
+        &mut self,
+        ServerTunnelConfig {
             name,
             port,
             destination_path,
-            ..
-        } in configs
-        {
-            match Self::load_or_create_destination(
-                &mut router_api,
-                base_path.join(&destination_path),
+            kind,
+            host,
+            requests_per_minute,
+            meta_lease_set,
+        }: ServerTunnelConfig,
+    ) -> Option<TunnelConfig> {
+        let meta_lease_set = match meta_lease_set {
+            None => None,
+            Some(MetaLeaseSetConfig {
+                destination_path,
+                members,
+            }) => match Self::load_or_create_destination(
+                &mut self.router_api,
+                self.base_path.join(&destination_path),
             )
             .await
             {
@@ -83,22 +177,44 @@ impl ServerTunnelManager {
                         target: LOG_TARGET,
                         %name,
                         %destination_path,
-                        "failed to load or create destination for server tunnel",
+                        "failed to load or create destination for meta lease set",
                     );
-                    continue;
-                }
-                Some(destination) => {
-                    tunnels.push(Arc::from(TunnelConfig {
-                        destination,
-                        name,
-                        port,
-                        sam_tcp_port,
-                    }));
+                    return None;
                 }
+                Some(destination) => Some(MetaLeaseSet {
+                    destination,
+                    members,
+                }),
+            },
+        };
+
+        match Self::load_or_create_destination(
+            &mut self.router_api,
+            self.base_path.join(&destination_path),
+        )
+        .await
+        {
+            None => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    %name,
+                    %destination_path,
+                    "failed to load or create destination for server tunnel",
+                );
+                None
             }
+            Some(destination) => Some(TunnelConfig {
+                destination,
+                host: host.unwrap_or_else(|| format!("127.0.0.1:{port}")),
+                kind,
+                meta_lease_set,
+                name,
+                port,
+                requests_per_minute,
+                sam_tcp_port: self.sam_tcp_port,
+                sam_udp_port: self.sam_udp_port,
+            }),
         }
-
-        Self { tunnels }
     }
 
     /// Attempt to load destination from `path` and if it does't exist, call router over SAMv3 to
@@ -158,14 +274,23 @@ impl ServerTunnelManager {
     }
 
     /// Run the event loop of server tunnel.
+    ///
+    /// TCP server tunnels forward inbound streams directly to the server whereas HTTP server
+    /// tunnels accept the streams and rewrite the request headers before relaying the request to
+    /// the server. UDP server tunnels receive repliable datagrams and forward them to the server.
     async fn server_event_loop(config: Arc<TunnelConfig>) {
         tracing::info!(
             target: LOG_TARGET,
             name = %config.name,
             port = %config.port,
+            kind = ?config.kind,
             "starting server tunnel",
         );
 
+        if config.kind == ServerTunnelKind::Udp {
+            return Self::udp_event_loop(config).await;
+        }
+
         let mut session = match Session::<style::Stream>::new(SessionOptions {
             samv3_tcp_port: config.sam_tcp_port,
             nickname: config.name.clone(),
@@ -189,6 +314,10 @@ impl ServerTunnelManager {
             }
         };
 
+        if config.kind == ServerTunnelKind::Http {
+            return Self::http_event_loop(session, config).await;
+        }
+
         // send `STREAM FORWARD` command to session and if it fails, sleep and try again later
         loop {
             let Err(error) = session.forward(config.port).await else {
@@ -210,14 +339,230 @@ impl ServerTunnelManager {
         }
     }
 
-    /// Run the event loop of [`ServerTunnelManager`].
-    pub async fn run(self) {
-        if self.tunnels.is_empty() {
+    /// Run the event loop of a UDP server tunnel.
+    async fn udp_event_loop(config: Arc<TunnelConfig>) {
+        let session = match Session::<style::Repliable>::new(SessionOptions {
+            samv3_tcp_port: config.sam_tcp_port,
+            samv3_udp_port: config.sam_udp_port,
+            nickname: config.name.clone(),
+            destination: DestinationKind::Persistent {
+                private_key: config.destination.clone(),
+            },
+            ..Default::default()
+        })
+        .await
+        {
+            Ok(session) => session,
+            Err(error) => {
+                tracing::error!(
+                    target: LOG_TARGET,
+                    name = %config.name,
+                    ?error,
+                    "failed to start datagram session for server tunnel",
+                );
+                return;
+            }
+        };
+
+        if let Err(error) =
+            UdpServerTunnel::new(config.name.clone(), config.port, session).run().await
+        {
+            tracing::warn!(
+                target: LOG_TARGET,
+                name = %config.name,
+                ?error,
+                "udp server tunnel exited with error",
+            );
+        }
+    }
+
+    /// Run the event loop of an HTTP server tunnel.
+    ///
+    /// Inbound streams are accepted one by one and if the client destination hasn't exceeded its
+    /// rate limit, the stream is handled in a separate task.
+    async fn http_event_loop(mut session: Session<style::Stream>, config: Arc<TunnelConfig>) {
+        let mut rate_limiter = config.requests_per_minute.map(RateLimiter::new);
+
+        loop {
+            let stream = match session.accept().await {
+                Ok(stream) => stream,
+                Err(error) => {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        name = %config.name,
+                        ?error,
+                        "failed to accept stream",
+                    );
+
+                    tokio::time::sleep(STREAM_ACCEPT_BACKOFF).await;
+                    continue;
+                }
+            };
+
+            // the destination may be followed by the ports of the stream
+            let Some(client) = stream
+                .remote_destination()
+                .split_whitespace()
+                .next()
+                .and_then(ClientIdentity::new)
+            else {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    name = %config.name,
+                    "invalid remote destination for inbound stream",
+                );
+                continue;
+            };
+
+            if let Some(rate_limiter) = &mut rate_limiter {
+                if !rate_limiter.register(&client.hash) {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        name = %config.name,
+                        client = %client.address,
+                        "client exceeded rate limit",
+                    );
+
+                    tokio::spawn(http::reject(stream));
+                    continue;
+                }
+            }
+
+            let config = Arc::clone(&config);
+
+            tokio::spawn(async move {
+                let client_address = client.address.clone();
+
+                if let Err(error) =
+                    http::handle_stream(stream, client, config.port, &config.host).await
+                {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        name = %config.name,
+                        client = %client_address,
+                        ?error,
+                        "failed to handle inbound stream",
+                    );
+                }
+            });
+        }
+    }
+
+    /// Create a session for the meta lease set of the server tunnel.
+    ///
+    /// The session only publishes a meta lease set listing the member destinations and doesn't
+    /// accept any connections. The session is kept alive for as long as the control connection is
+    /// open.
+    async fn create_meta_session(config: &TunnelConfig, meta: &MetaLeaseSet) -> anyhow::Result<()> {
+        let stream = TcpStream::connect(("127.0.0.1", config.sam_tcp_port)).await?;
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        writer.write_all(b"HELLO VERSION MIN=3.1 MAX=3.3\n").await?;
+        match lines.next_line().await? {
+            Some(line) if line.contains("RESULT=OK") => {}
+            line => anyhow::bail!("handshake failed: {line:?}"),
+        }
+
+        writer
+            .write_all(
+                format!(
+                    "SESSION CREATE STYLE=STREAM ID={}-meta DESTINATION={} SIGNATURE_TYPE=7 \
+                    i2cp.leaseSetEncType=4 i2cp.leaseSetType=7 i2cp.metaLeaseSetEntries={}\n",
+                    config.name,
+                    meta.destination,
+                    meta.members.join(","),
+                )
+                .as_bytes(),
+            )
+            .await?;
+        match lines.next_line().await? {
+            Some(line) if line.contains("RESULT=OK") => {}
+            line => anyhow::bail!("failed to create session: {line:?}"),
+        }
+
+        tracing::info!(
+            target: LOG_TARGET,
+            name = %config.name,
+            num_members = ?meta.members.len(),
+            "meta lease set session started",
+        );
+
+        while lines.next_line().await?.is_some() {}
+
+        Ok(())
+    }
+
+    /// Run the event loop of the meta lease set session of a server tunnel.
+    async fn meta_event_loop(config: Arc<TunnelConfig>) {
+        let Some(meta) = &config.meta_lease_set else {
             return;
+        };
+
+        loop {
+            if let Err(error) = Self::create_meta_session(&config, meta).await {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    name = %config.name,
+                    ?error,
+                    "meta lease set session failed",
+                );
+            }
+
+            tokio::time::sleep(META_SESSION_BACKOFF).await;
         }
+    }
+
+    /// Start the event loops of `tunnel`.
+    fn start_tunnel(&mut self, tunnel: Arc<TunnelConfig>) {
+        let mut handles =
+            vec![tokio::spawn(Self::server_event_loop(Arc::clone(&tunnel))).abort_handle()];
+
+        if tunnel.meta_lease_set.is_some() {
+            handles.push(tokio::spawn(Self::meta_event_loop(Arc::clone(&tunnel))).abort_handle());
+        }
+
+        self.running.insert(tunnel.name.clone(), handles);
+    }
 
-        for tunnel in self.tunnels {
-            tokio::spawn(Self::server_event_loop(Arc::clone(&tunnel)));
+    /// Stop server tunnel `name`.
+    ///
+    /// Streams that have already been accepted by an HTTP server tunnel are handled to completion.
+    fn stop_tunnel(&mut self, name: &str) {
+        match self.running.remove(name) {
+            None => tracing::debug!(
+                target: LOG_TARGET,
+                %name,
+                "server tunnel is not running",
+            ),
+            Some(handles) => {
+                tracing::info!(
+                    target: LOG_TARGET,
+                    %name,
+                    "stopping server tunnel",
+                );
+                handles.into_iter().for_each(|handle| handle.abort());
+            }
+        }
+    }
+
+    /// Run the event loop of [`ServerTunnelManager`].
+    ///
+    /// Server tunnels of the router configuration are started first, after which tunnels are
+    /// added and removed as commands are received.
+    pub async fn run(mut self) {
+        for tunnel in mem::take(&mut self.tunnels) {
+            self.start_tunnel(tunnel);
+        }
+
+        while let Some(command) = self.command_rx.recv().await {
+            match command {
+                TunnelCommand::Add(config) =>
+                    if let Some(tunnel) = self.load_tunnel(config).await {
+                        self.start_tunnel(Arc::new(tunnel));
+                    },
+                TunnelCommand::Remove(name) => self.stop_tunnel(&name),
+            }
         }
     }
 }
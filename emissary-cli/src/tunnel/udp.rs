@@ -0,0 +1,778 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! UDP client and server tunnels.
+//!
+//! UDP client tunnels bind a local UDP socket and send the datagrams received on it to a remote
+//! destination using a repliable datagram session. Datagrams received by the session are sent back
+//! to the local address that sent the most recent datagram.
+//!
+//! UDP server tunnels receive repliable datagrams on a persistent destination and forward them to
+//! a local UDP service. Each remote destination is given its own local socket so that responses of
+//! the service can be mapped back to the destination they're meant for.
+
+use crate::config::ClientTunnelConfig;
+
+use tokio::{
+    net::UdpSocket,
+    sync::mpsc::{channel, Receiver, Sender},
+    task::{AbortHandle, Id},
+};
+use yosemite::{style, Session, SessionOptions};
+
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::udp-tunnel";
+
+/// Maximum size of a datagram.
+const MAX_DATAGRAM_SIZE: usize = 0xffff;
+
+/// Maximum number of remote destinations tracked by a server tunnel.
+const MAX_PEERS: usize = 1024usize;
+
+/// How long is a remote destination of a server tunnel kept if no datagrams are exchanged.
+const PEER_IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// How often are idle remote destinations of a server tunnel removed.
+const PEER_CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Channel size for responses received from the local service.
+const RESPONSE_CHANNEL_SIZE: usize = 256usize;
+
+/// UDP client tunnel.
+pub struct UdpClientTunnel {
+    /// Local address that sent the most recent datagram.
+    client: Option<SocketAddr>,
+
+    /// Client tunnel configuration.
+    config: Arc<ClientTunnelConfig>,
+
+    /// Repliable datagram session of the tunnel.
+    session: Session<style::Repliable>,
+
+    /// Local UDP socket of the tunnel.
+    socket: UdpSocket,
+}
+
+impl UdpClientTunnel {
+    /// Create new [`UdpClientTunnel`].
+    ///
+    /// Binds the local UDP socket and creates a datagram session for the tunnel.
+    pub async fn new(
+        config: Arc<ClientTunnelConfig>,
+        samv3_tcp_port: u16,
+        samv3_udp_port: u16,
+    ) -> crate::Result<Self> {
+        let socket = UdpSocket::bind(format!(
+            "{}:{}",
+            config.address.clone().unwrap_or(String::from("127.0.0.1")),
+            config.port
+        ))
+        .await?;
+
+        let session = Session::<style::Repliable>::new(SessionOptions {
+            publish: false,
+            samv3_tcp_port,
+            samv3_udp_port,
+            nickname: config.name.clone(),
+            ..Default::default()
+        })
+        .await?;
+
+        Ok(Self {
+            client: None,
+            config,
+            session,
+            socket,
+        })
+    }
+
+    /// Run the event loop of [`UdpClientTunnel`].
+    pub async fn run(mut self) -> crate::Result<()> {
+        let mut local_buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+        let mut i2p_buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+
+        tracing::info!(
+            target: LOG_TARGET,
+            name = %self.config.name,
+            port = %self.config.port,
+            "udp client tunnel started",
+        );
+
+        loop {
+            tokio::select! {
+                result = self.socket.recv_from(&mut local_buffer) => {
+                    let (nread, address) = result?;
+
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        name = %self.config.name,
+                        ?address,
+                        len = ?nread,
+                        "send datagram",
+                    );
+
+                    self.client = Some(address);
+
+                    if let Err(error) =
+                        self.session.send_to(&local_buffer[..nread], &self.config.destination).await
+                    {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            name = %self.config.name,
+                            ?error,
+                            "failed to send datagram",
+                        );
+                    }
+                }
+                result = self.session.recv_from(&mut i2p_buffer) => {
+                    let (nread, _) = result?;
+
+                    match self.client {
+                        Some(client) => {
+                            if let Err(error) = self.socket.send_to(&i2p_buffer[..nread], client).await
+                            {
+                                tracing::debug!(
+                                    target: LOG_TARGET,
+                                    name = %self.config.name,
+                                    ?client,
+                                    ?error,
+                                    "failed to send datagram to local client",
+                                );
+                            }
+                        }
+                        None => tracing::debug!(
+                            target: LOG_TARGET,
+                            name = %self.config.name,
+                            "datagram received before any local datagrams were sent",
+                        ),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Event sent by a task reading responses of the local service.
+enum ServiceEvent {
+    /// Response of the local service to `destination`.
+    Response {
+        /// Base64 destination the response is meant for.
+        destination: Arc<str>,
+
+        /// Response datagram.
+        datagram: Vec<u8>,
+    },
+
+    /// Reading responses for `destination` failed and the task exited.
+    Closed {
+        /// Base64 destination of the peer.
+        destination: Arc<str>,
+
+        /// ID of the exited task.
+        id: Id,
+    },
+}
+
+/// Remote destination of a UDP server tunnel.
+struct Peer {
+    /// Abort handle of the task reading responses from `socket`.
+    handle: AbortHandle,
+
+    /// When was the last datagram exchanged with the destination.
+    last_activity: Instant,
+
+    /// Local socket connected to the service.
+    socket: Arc<UdpSocket>,
+}
+
+/// UDP server tunnel.
+pub struct UdpServerTunnel {
+    /// Name of the tunnel.
+    name: String,
+
+    /// Remote destinations, indexed by their base64 destinations.
+    peers: HashMap<Arc<str>, Peer>,
+
+    /// Port of the local service.
+    port: u16,
+
+    /// RX channel for receiving events from the tasks reading responses of the local service.
+    rx: Receiver<ServiceEvent>,
+
+    /// Repliable datagram session of the tunnel.
+    session: Session<style::Repliable>,
+
+    /// TX channel given to the tasks reading responses of the local service.
+    tx: Sender<ServiceEvent>,
+}
+
+impl UdpServerTunnel {
+    /// Create new [`UdpServerTunnel`].
+    pub fn new(name: String, port: u16, session: Session<style::Repliable>) -> Self {
+        let (tx, rx) = channel(RESPONSE_CHANNEL_SIZE);
+
+        Self {
+            name,
+            peers: HashMap::new(),
+            port,
+            rx,
+            session,
+            tx,
+        }
+    }
+
+    /// Create local socket for `destination` and start reading responses of the service from it.
+    ///
+    /// Errors caused by the service being unreachable, e.g., while it's restarting, are ignored. If
+    /// reading fails for any other reason, the task exits and the event loop is notified so that
+    /// the next datagram from `destination` creates a new socket.
+    async fn create_peer(&mut self, destination: Arc<str>) -> crate::Result<Arc<UdpSocket>> {
+        let socket = UdpSocket::bind("127.0.0.1:0").await?;
+        socket.connect(("127.0.0.1", self.port)).await?;
+
+        let socket = Arc::new(socket);
+        let handle = tokio::spawn({
+            let socket = Arc::clone(&socket);
+            let destination = Arc::clone(&destination);
+            let tx = self.tx.clone();
+            let name = self.name.clone();
+
+            async move {
+                let mut buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+
+                loop {
+                    match socket.recv(&mut buffer).await {
+                        Ok(nread) => {
+                            let event = ServiceEvent::Response {
+                                destination: Arc::clone(&destination),
+                                datagram: buffer[..nread].to_vec(),
+                            };
+
+                            if tx.send(event).await.is_err() {
+                                return;
+                            }
+                        }
+                        Err(error)
+                            if matches!(
+                                error.kind(),
+                                ErrorKind::ConnectionRefused | ErrorKind::ConnectionReset
+                            ) =>
+                        {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                %name,
+                                ?error,
+                                "local service unreachable",
+
+                            );
+                        }
+                        Err(error) => {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                %name,
+                                ?error,
+                                "failed to read response of local service",
+                            );
+
+                            let _ = tx
+                                .send(ServiceEvent::Closed {
+                                    destination,
+                                    id: tokio::task::id(),
+                                })
+                                .await;
+                            return;
+                        }
+                    }
+                }
+            }
+        })
+        .abort_handle();
+
+        self.peers.insert(
+            destination,
+            Peer {
+                handle,
+                last_activity: Instant::now(),
+                socket: Arc::clone(&socket),
+            },
+        );
+
+        Ok(socket)
+    }
+
+    /// Handle `datagram` received from `destination` over I2P.
+    async fn on_i2p_datagram(&mut self, datagram: &[u8], destination: String) {
+        let socket = match self.peers.get_mut(destination.as_str()) {
+            Some(peer) => {
+                peer.last_activity = Instant::now();
+                Arc::clone(&peer.socket)
+            }
+            None => {
+                if self.peers.len() >= MAX_PEERS {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        name = %self.name,
+                        "too many remote destinations, ignoring datagram",
+                    );
+                    return;
+                }
+
+                match self.create_peer(Arc::from(destination)).await {
+                    Ok(socket) => socket,
+                    Err(error) => {
+                        tracing::warn!(
+                            target: LOG_TARGET,
+                            name = %self.name,
+                            ?error,
+                            "failed to create local socket for remote destination",
+                        );
+                        return;
+                    }
+                }
+            }
+        };
+
+        // error of a datagram sent while the service was down may be reported by the next send,
+        // in which case the datagram is sent again
+        let result = match socket.send(datagram).await {
+            Err(error) if error.kind() == ErrorKind::ConnectionRefused =>
+                socket.send(datagram).await,
+            result => result,
+        };
+
+        if let Err(error) = result {
+            tracing::debug!(
+                target: LOG_TARGET,
+                name = %self.name,
+                ?error,
+                "failed to send datagram to local service",
+
+            );
+        }
+    }
+
+    /// Handle `datagram` received from the local service for `destination`.
+    async fn on_response(&mut self, destination: Arc<str>, datagram: Vec<u8>) {
+        if let Some(peer) = self.peers.get_mut(&destination) {
+            peer.last_activity = Instant::now();
+        }
+
+        if let Err(error) = self.session.send_to(&datagram, &destination).await {
+            tracing::debug!(
+                target: LOG_TARGET,
+                name = %self.name,
+                ?error,
+                "failed to send response of local service",
+            );
+        }
+    }
+
+    /// Handle exit of the task, identified by `id`, which read responses for `destination`.
+    ///
+    /// The peer is removed unless it has already been replaced by a new one.
+    fn on_reader_closed(&mut self, destination: Arc<str>, id: Id) {
+        if self.peers.get(&destination).is_some_and(|peer| peer.handle.id() == id) {
+            self.peers.remove(&destination);
+        }
+    }
+
+    /// Remove remote destinations which haven't exchanged datagrams within [`PEER_IDLE_TIMEOUT`].
+    fn remove_idle_peers(&mut self) {
+        self.peers.retain(|_, peer| {
+            if peer.last_activity.elapsed() < PEER_IDLE_TIMEOUT {
+                return true;
+            }
+
+            peer.handle.abort();
+            false
+        });
+    }
+
+    /// Run the event loop of [`UdpServerTunnel`].
+    pub async fn run(mut self) -> crate::Result<()> {
+        let mut buffer = vec![0u8; MAX_DATAGRAM_SIZE];
+        let mut cleanup = tokio::time::interval(PEER_CLEANUP_INTERVAL);
+
+        loop {
+            tokio::select! {
+                result = self.session.recv_from(&mut buffer) => {
+                    let (nread, destination) = result?;
+                    self.on_i2p_datagram(&buffer[..nread], destination).await;
+                }
+                event = self.rx.recv() => match event {
+                    None => return Ok(()),
+                    Some(ServiceEvent::Response { destination, datagram }) =>
+                        self.on_response(destination, datagram).await,
+                    Some(ServiceEvent::Closed { destination, id }) =>
+                        self.on_reader_closed(destination, id),
+                },
+                _ = cleanup.tick() => self.remove_idle_peers(),
+            }
+        }
+    }
+}
+
+impl Drop for UdpServerTunnel {
+    fn drop(&mut self) {
+        self.peers.values().for_each(|peer| peer.handle.abort());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ClientTunnelKind;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    /// Start SAMv3 server which accepts datagram sessions.
+    ///
+    /// Returns the TCP port of the server, the UDP socket of the server and an RX channel for the
+    /// datagram ports of the created sessions.
+    async fn sam_server() -> (u16, UdpSocket, Receiver<u16>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = channel(16);
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let tx = tx.clone();
+
+                tokio::spawn(async move {
+                    let mut lines = BufReader::new(stream).lines();
+
+                    while let Ok(Some(command)) = lines.next_line().await {
+                        let response = if command.starts_with("HELLO VERSION") {
+                            "HELLO REPLY RESULT=OK VERSION=3.2\n"
+                        } else if command.starts_with("SESSION CREATE") {
+                            let port = command
+                                .split(' ')
+                                .find_map(|option| option.strip_prefix("PORT="))
+                                .and_then(|port| port.parse::<u16>().ok())
+                                .unwrap();
+                            tx.send(port).await.unwrap();
+
+                            "SESSION STATUS RESULT=OK DESTINATION=I2P_DESTINATION\n"
+                        } else {
+                            continue;
+                        };
+
+                        lines.get_mut().write_all(response.as_bytes()).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        (port, UdpSocket::bind("127.0.0.1:0").await.unwrap(), rx)
+    }
+
+    /// Create [`UdpServerTunnel`] for the local service listening on `port`.
+    ///
+    /// Returns the tunnel, the UDP socket of the SAMv3 server and the datagram port of the session.
+    async fn server_tunnel(port: u16) -> (UdpServerTunnel, UdpSocket, u16) {
+        let (sam_tcp_port, sam_socket, mut rx) = sam_server().await;
+        let session = Session::<style::Repliable>::new(SessionOptions {
+            samv3_tcp_port: sam_tcp_port,
+            samv3_udp_port: sam_socket.local_addr().unwrap().port(),
+            nickname: String::from("server"),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        (
+            UdpServerTunnel::new(String::from("server"), port, session),
+            sam_socket,
+            rx.recv().await.unwrap(),
+        )
+    }
+
+    /// Receive datagram sent by a session from `sam_socket`.
+    ///
+    /// Returns the destination and the payload of the datagram.
+    async fn recv_sam_datagram(sam_socket: &UdpSocket) -> (String, Vec<u8>) {
+        let mut buffer = vec![0u8; 1024];
+        let nread = tokio::time::timeout(Duration::from_secs(5), sam_socket.recv(&mut buffer))
+            .await
+            .unwrap()
+            .unwrap();
+        let end = buffer[..nread].iter().position(|byte| byte == &b'\n').unwrap();
+        let header = std::str::from_utf8(&buffer[..end]).unwrap();
+
+        (
+            header.split(' ').nth(2).unwrap().to_string(),
+            buffer[end + 1..nread].to_vec(),
+        )
+    }
+
+    #[tokio::test]
+    async fn server_replies_mapped_to_source() {
+        // local service which echoes datagrams back with the address of the sender
+        let service = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = service.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buffer = vec![0u8; 1024];
+
+            while let Ok((nread, address)) = service.recv_from(&mut buffer).await {
+                let mut response = buffer[..nread].to_vec();
+                response.extend_from_slice(format!(" {}", address.port()).as_bytes());
+                service.send_to(&response, address).await.unwrap();
+            }
+        });
+
+        let (tunnel, sam_socket, datagram_port) = server_tunnel(port).await;
+        tokio::spawn(tunnel.run());
+
+        let mut ports = HashMap::new();
+
+        for (destination, payload) in [("alice", "hello"), ("bob", "world"), ("alice", "again")] {
+            sam_socket
+                .send_to(
+                    format!("{destination} FROM_PORT=0 TO_PORT=0\n{payload}").as_bytes(),
+                    ("127.0.0.1", datagram_port),
+                )
+                .await
+                .unwrap();
+
+            let (receiver, response) = recv_sam_datagram(&sam_socket).await;
+            let response = String::from_utf8(response).unwrap();
+            let (echoed, port) = response.split_once(' ').unwrap();
+
+            assert_eq!(receiver, destination);
+            assert_eq!(echoed, payload);
+
+            // each destination is given its own local socket
+            assert_eq!(
+                ports.entry(destination).or_insert(port.to_string()).as_str(),
+                port
+            );
+        }
+
+        assert_ne!(ports["alice"], ports["bob"]);
+    }
+
+    #[tokio::test]
+    async fn server_peers_capped() {
+        let service = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let (mut tunnel, _sam_socket, _) =
+            server_tunnel(service.local_addr().unwrap().port()).await;
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+
+        for i in 0..MAX_PEERS {
+            tunnel.peers.insert(
+                Arc::from(format!("peer{i}")),
+                Peer {
+                    handle: tokio::spawn(async {}).abort_handle(),
+                    last_activity: Instant::now(),
+                    socket: Arc::clone(&socket),
+                },
+            );
+        }
+
+        // datagram from a new destination is ignored
+        tunnel.on_i2p_datagram(b"hello", String::from("new")).await;
+        assert_eq!(tunnel.peers.len(), MAX_PEERS);
+        assert!(!tunnel.peers.contains_key("new"));
+
+        let mut buffer = [0u8; 16];
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), service.recv(&mut buffer))
+                .await
+                .is_err()
+        );
+
+        // new destination is accepted once there is room for it
+        tunnel.peers.remove("peer0");
+        tunnel.on_i2p_datagram(b"hello", String::from("new")).await;
+        assert!(tunnel.peers.contains_key("new"));
+
+        let nread = service.recv(&mut buffer).await.unwrap();
+        assert_eq!(&buffer[..nread], b"hello");
+    }
+
+    #[tokio::test]
+    async fn idle_peers_removed() {
+        let service = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let (mut tunnel, _sam_socket, _) =
+            server_tunnel(service.local_addr().unwrap().port()).await;
+
+        tunnel.on_i2p_datagram(b"hello", String::from("idle")).await;
+        tunnel.on_i2p_datagram(b"hello", String::from("active")).await;
+        assert_eq!(tunnel.peers.len(), 2);
+
+        let handle = {
+            let peer = tunnel.peers.get_mut("idle").unwrap();
+            peer.last_activity = Instant::now() - PEER_IDLE_TIMEOUT - Duration::from_secs(1);
+            peer.handle.clone()
+        };
+
+        tunnel.remove_idle_peers();
+        assert_eq!(tunnel.peers.len(), 1);
+        assert!(tunnel.peers.contains_key("active"));
+
+        // reader task of the removed destination is stopped
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !handle.is_finished() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    /// Start local service which echoes datagrams received on `service` back to their senders.
+    fn echo_service(service: UdpSocket) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut buffer = vec![0u8; 1024];
+
+            while let Ok((nread, address)) = service.recv_from(&mut buffer).await {
+                let _ = service.send_to(&buffer[..nread], address).await;
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn replies_resume_after_service_restart() {
+        let service = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = service.local_addr().unwrap();
+        let handle = echo_service(service);
+
+        let (tunnel, sam_socket, datagram_port) = server_tunnel(address.port()).await;
+        tokio::spawn(tunnel.run());
+
+        let send = |payload: &'static str| {
+            let sam_socket = &sam_socket;
+
+            async move {
+                sam_socket
+                    .send_to(
+                        format!("alice FROM_PORT=0 TO_PORT=0\n{payload}").as_bytes(),
+                        ("127.0.0.1", datagram_port),
+                    )
+                    .await
+                    .unwrap();
+            }
+        };
+
+        send("hello").await;
+        assert_eq!(
+            recv_sam_datagram(&sam_socket).await,
+            (String::from("alice"), b"hello".to_vec())
+        );
+
+        // stop the service and send a datagram while it's down
+        handle.abort();
+        let _ = handle.await;
+
+        send("lost").await;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        // restart the service and verify that replies are received again
+        echo_service(UdpSocket::bind(address).await.unwrap());
+
+        send("world").await;
+        assert_eq!(
+            recv_sam_datagram(&sam_socket).await,
+            (String::from("alice"), b"world".to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn closed_reader_removes_peer() {
+        let service = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let (mut tunnel, _sam_socket, _) =
+            server_tunnel(service.local_addr().unwrap().port()).await;
+
+        tunnel.on_i2p_datagram(b"hello", String::from("alice")).await;
+        let id = tunnel.peers["alice"].handle.id();
+
+        // exit of a task of a replaced peer is ignored
+        tunnel.on_reader_closed(Arc::from("alice"), tokio::spawn(async {}).id());
+        assert!(tunnel.peers.contains_key("alice"));
+
+        tunnel.on_reader_closed(Arc::from("alice"), id);
+        assert!(tunnel.peers.is_empty());
+
+        // next datagram creates a new socket
+        tunnel.on_i2p_datagram(b"hello", String::from("alice")).await;
+        assert_ne!(tunnel.peers["alice"].handle.id(), id);
+    }
+
+    #[tokio::test]
+    async fn client_replies_sent_to_last_sender() {
+        let (sam_tcp_port, sam_socket, mut rx) = sam_server().await;
+        let tunnel = UdpClientTunnel::new(
+            Arc::new(ClientTunnelConfig {
+                name: String::from("client"),
+                address: None,
+                port: 0,
+                destination: String::from("host.i2p"),
+                destination_port: None,
+                kind: ClientTunnelKind::Udp,
+            }),
+            sam_tcp_port,
+            sam_socket.local_addr().unwrap().port(),
+        )
+        .await
+        .unwrap();
+        let address = tunnel.socket.local_addr().unwrap();
+        let datagram_port = rx.recv().await.unwrap();
+        tokio::spawn(tunnel.run());
+
+        let first = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let second = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        for (socket, payload) in [(&first, b"first"), (&second, b"other")] {
+            socket.send_to(payload, address).await.unwrap();
+
+            let (destination, datagram) = recv_sam_datagram(&sam_socket).await;
+            assert_eq!(destination, "host.i2p");
+            assert_eq!(datagram, payload);
+        }
+
+        sam_socket
+            .send_to(
+                b"destination FROM_PORT=0 TO_PORT=0\nreply",
+                ("127.0.0.1", datagram_port),
+            )
+            .await
+            .unwrap();
+
+        let mut buffer = [0u8; 16];
+        let nread = tokio::time::timeout(Duration::from_secs(5), second.recv(&mut buffer))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&buffer[..nread], b"reply");
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), first.recv(&mut buffer))
+                .await
+                .is_err()
+        );
+    }
+}
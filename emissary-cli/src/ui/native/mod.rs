@@ -17,11 +17,13 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
+    address_book::store::AddressBookStore,
     config::Theme as RouterTheme,
+    news::{NewsStore, NewsSubscriber},
     ui::{calculate_bandwidth, Status},
 };
 
-use emissary_core::events::{Event, EventSubscriber};
+use emissary_core::events::{Event, EventSubscriber, FirewallStatus};
 use iced::{
     time,
     widget::{button, column, container, row, toggler, Column, Text},
@@ -29,12 +31,16 @@ use iced::{
 };
 use tokio::sync::mpsc::Sender;
 
-use std::time::{Duration, Instant};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
 #[derive(Debug, Clone)]
 enum View {
     Overview,
     Destinations,
+    News,
     Settings,
 }
 
@@ -50,6 +56,9 @@ enum Message {
 
 /// Router UI.
 pub struct RouterUi {
+    /// Address book store, if the address book is enabled.
+    address_book: Option<AddressBookStore>,
+
     /// Cumulative bandwidth of all transports.
     bandwidth: usize,
 
@@ -59,15 +68,27 @@ pub struct RouterUi {
     /// Subscriber to events emitted by `emissary-core`.
     events: EventSubscriber,
 
+    /// Firewall status.
+    firewall_status: FirewallStatus,
+
     /// Has light mode been enabled.
     light_mode: bool,
 
+    /// News store, if the news feed is enabled.
+    news: Option<NewsStore>,
+
+    /// Subscriber to new news entries, if the news feed is enabled.
+    news_events: Option<NewsSubscriber>,
+
     /// Total number of routers.
     num_routers: usize,
 
     /// Total number of transit tunnels.
     num_transit_tunnels: usize,
 
+    /// Number of news entries received since the news were last viewed.
+    num_unread_news: usize,
+
     /// How many tunnel builds have failed.
     num_tunnel_build_failures: usize,
 
@@ -80,6 +101,9 @@ pub struct RouterUi {
     /// Active server destinations.
     server_destinations: Vec<(String, String)>,
 
+    /// Host names of server destinations, indexed by `.b32.i2p` address.
+    server_hosts: HashMap<String, String>,
+
     /// TX channel for sending a graceful shutdown signal to router.
     shutdown_tx: Sender<()>,
 
@@ -102,15 +126,22 @@ impl RouterUi {
         light_mode: bool,
         refresh_interval: usize,
         shutdown_tx: Sender<()>,
+        address_book: Option<AddressBookStore>,
+        news: Option<NewsStore>,
     ) -> (Self, Task<Message>) {
         (
             RouterUi {
+                address_book,
                 bandwidth: 0usize,
                 client_destinations: Vec::new(),
                 events,
+                firewall_status: FirewallStatus::Unknown,
                 light_mode,
+                news_events: news.as_ref().map(|store| store.subscribe()),
+                news,
                 num_routers: 0usize,
                 num_transit_tunnels: 0usize,
+                num_unread_news: 0usize,
                 num_tunnel_build_failures: 0usize,
                 num_tunnels_built: 0usize,
                 refresh_interval: if refresh_interval == 0 {
@@ -119,6 +150,7 @@ impl RouterUi {
                     Duration::from_secs(refresh_interval as u64)
                 },
                 server_destinations: Vec::new(),
+                server_hosts: HashMap::new(),
                 shutdown_tx,
                 status: Status::Active,
                 transit_bandwidth: 0usize,
@@ -134,6 +166,8 @@ impl RouterUi {
         theme: RouterTheme,
         refresh_interval: usize,
         shutdown_tx: Sender<()>,
+        address_book: Option<AddressBookStore>,
+        news: Option<NewsStore>,
     ) -> anyhow::Result<()> {
         iced::application("emissary", RouterUi::update, RouterUi::view)
             .subscription(RouterUi::subscription)
@@ -144,6 +178,8 @@ impl RouterUi {
                     std::matches!(theme, RouterTheme::Light),
                     refresh_interval,
                     shutdown_tx,
+                    address_book,
+                    news,
                 )
             })
             .map_err(From::from)
@@ -165,6 +201,7 @@ impl RouterUi {
                             self.num_transit_tunnels = transit.num_tunnels;
                             self.bandwidth = transport.bandwidth;
                             self.num_routers = transport.num_connected_routers;
+                            self.firewall_status = transport.firewall_status;
                             self.server_destinations.extend(server_destinations);
                             self.client_destinations.extend(client_destinations);
                             self.num_tunnels_built = tunnel.num_tunnels_built;
@@ -178,9 +215,30 @@ impl RouterUi {
                     }
                 }
 
+                if let Some(news_events) = &mut self.news_events {
+                    while news_events.next_entry().is_some() {
+                        self.num_unread_news += 1;
+                    }
+                }
+
+                // host names are resolved here rather than when the view is drawn as the router
+                // address book is read from disk
+                if let Some(store) = &self.address_book {
+                    self.server_hosts = self
+                        .server_destinations
+                        .iter()
+                        .filter_map(|(_, address)| {
+                            store.resolve_name_blocking(address).map(|host| (address.clone(), host))
+                        })
+                        .collect();
+                }
+
                 Task::none()
             }
             Message::ButtonPressed(view) => {
+                if let View::News = view {
+                    self.num_unread_news = 0;
+                }
                 self.view = view;
 
                 Task::none()
@@ -205,6 +263,11 @@ impl RouterUi {
         let sidebar = column![
             button("Overview").on_press(Message::ButtonPressed(View::Overview)),
             button("Destinations").on_press(Message::ButtonPressed(View::Destinations)),
+            button(Text::new(match self.num_unread_news {
+                0 => "News".to_string(),
+                num_unread => format!("News ({num_unread})"),
+            }))
+            .on_press(Message::ButtonPressed(View::News)),
             button("Settings").on_press(Message::ButtonPressed(View::Settings)),
         ]
         .spacing(10)
@@ -237,6 +300,8 @@ impl RouterUi {
                 };
                 let num_connected_text =
                     Text::new(format!("Number of connected routers: {}", self.num_routers));
+                let firewall_status_text =
+                    Text::new(format!("Firewall status: {}", self.firewall_status));
                 let tunnel_build_success_rate_text = {
                     if self.num_tunnels_built == 0 && self.num_tunnel_build_failures == 0 {
                         Text::new("Tunnel build success rate: 0%".to_string())
@@ -262,12 +327,20 @@ impl RouterUi {
                     ))
                 };
 
+                let news_text = match self.num_unread_news {
+                    0 => Text::new(""),
+                    1 => Text::new("1 new news entry"),
+                    num_unread => Text::new(format!("{num_unread} new news entries")),
+                };
+
                 column![
                     Text::new("Overview").size(36),
+                    news_text,
                     uptime_text,
                     status_text,
                     total_bandwidth_text,
                     num_connected_text,
+                    firewall_status_text,
                     tunnel_build_success_rate_text,
                     num_transit_tunnels_text,
                     transit_bandwidth_text,
@@ -296,9 +369,15 @@ impl RouterUi {
                 test.push(Text::new("Server destinations").size(36).into());
 
                 for (name, address) in &self.server_destinations {
+                    let host = self.server_hosts.get(address);
+
                     test.push(
                         row![
-                            Text::new(format!("{name}: http://{address}.b32.i2p")),
+                            Text::new(match host {
+                                Some(host) =>
+                                    format!("{name}: http://{host} (http://{address}.b32.i2p)"),
+                                None => format!("{name}: http://{address}.b32.i2p"),
+                            }),
                             button("Copy to clipboard").on_press(Message::CopyToClipboard(
                                 format!("http://{address}.b32.i2p")
                             ))
@@ -316,6 +395,46 @@ impl RouterUi {
 
                 Column::from_vec(test).spacing(20).padding(30).align_x(Alignment::Start)
             }
+            View::News => {
+                let mut entries = Vec::new();
+
+                entries.push(Text::new("News").size(36).into());
+
+                match &self.news {
+                    None => entries.push(Text::new("News feed is disabled").into()),
+                    Some(store) => {
+                        let news = store.entries();
+
+                        if news.is_empty() {
+                            entries.push(Text::new("No news").into());
+                        }
+
+                        for entry in news {
+                            let mut item = column![
+                                Text::new(entry.title).size(24),
+                                Text::new(entry.updated),
+                                Text::new(entry.summary),
+                            ]
+                            .spacing(5);
+
+                            if let Some(link) = entry.link {
+                                item = item.push(
+                                    row![
+                                        Text::new(link.clone()),
+                                        button("Copy to clipboard")
+                                            .on_press(Message::CopyToClipboard(link)),
+                                    ]
+                                    .spacing(10),
+                                );
+                            }
+
+                            entries.push(item.into());
+                        }
+                    }
+                }
+
+                Column::from_vec(entries).spacing(20).padding(30).align_x(Alignment::Start)
+            }
             View::Settings => column![
                 Text::new("Settings").size(36),
                 toggler(self.light_mode)
@@ -17,6 +17,8 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
+    address_book::store::AddressBookStore,
+    news::{NewsStore, NewsSubscriber},
     ui::{calculate_bandwidth, Status},
     LOG_TARGET,
 };
@@ -30,7 +32,7 @@ use axum::{
     routing::get,
     Router,
 };
-use emissary_core::events::{Event, EventSubscriber};
+use emissary_core::events::{Event, EventSubscriber, FirewallStatus};
 use futures::StreamExt;
 use tokio::{
     net::TcpListener,
@@ -39,6 +41,7 @@ use tokio::{
 };
 
 use std::{
+    collections::HashMap,
     sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
@@ -52,18 +55,30 @@ const LISTEN_PORT: u16 = 7657;
 
 /// Router state.
 struct InnerRouterState {
+    /// Address book store, if the address book is enabled.
+    address_book: Option<AddressBookStore>,
+
     /// Cumulative bandwidth of all transports.
     bandwidth: usize,
 
     /// Active client destinations.
     client_destinations: Vec<String>,
 
+    /// Firewall status.
+    firewall_status: FirewallStatus,
+
+    /// News store, if the news feed is enabled.
+    news: Option<NewsStore>,
+
     /// Total number of routers.
     num_routers: usize,
 
     /// Total number of transit tunnels.
     num_transit_tunnels: usize,
 
+    /// Number of news entries received since the news were last viewed.
+    num_unread_news: usize,
+
     /// How many tunnel builds have failed.
     num_tunnel_build_failures: usize,
 
@@ -101,6 +116,9 @@ pub struct RouterUi {
     /// Subscriber to events emitted by `emissary-core`.
     events: EventSubscriber,
 
+    /// Subscriber to new news entries, if the news feed is enabled.
+    news_events: Option<NewsSubscriber>,
+
     /// Listen port for the web UI.
     port: u16,
 
@@ -121,6 +139,8 @@ impl RouterUi {
         port: Option<u16>,
         refresh_interval: usize,
         shutdown_tx: Sender<()>,
+        address_book: Option<AddressBookStore>,
+        news: Option<NewsStore>,
     ) -> Self {
         let update_interval = if refresh_interval == 0 {
             Duration::from_secs(10)
@@ -130,14 +150,19 @@ impl RouterUi {
 
         RouterUi {
             events,
+            news_events: news.as_ref().map(|store| store.subscribe()),
             port: port.unwrap_or(LISTEN_PORT),
             _shutdown_tx: shutdown_tx.clone(),
             state: RouterState {
                 state: Arc::new(Mutex::new(InnerRouterState {
+                    address_book,
                     bandwidth: 0usize,
                     client_destinations: Vec::new(),
+                    firewall_status: FirewallStatus::Unknown,
+                    news,
                     num_routers: 0usize,
                     num_transit_tunnels: 0usize,
+                    num_unread_news: 0usize,
                     num_tunnel_build_failures: 0usize,
                     num_tunnels_built: 0usize,
                     server_destinations: Vec::new(),
@@ -202,6 +227,7 @@ impl RouterUi {
                                 inner.num_transit_tunnels = transit.num_tunnels;
                                 inner.bandwidth = transport.bandwidth;
                                 inner.num_routers = transport.num_connected_routers;
+                                inner.firewall_status = transport.firewall_status;
                                 inner.server_destinations.extend(server_destinations);
                                 inner.client_destinations.extend(client_destinations);
                                 inner.num_tunnels_built = tunnel.num_tunnels_built;
@@ -216,6 +242,16 @@ impl RouterUi {
                             Event::ShutDown => {}
                         }
                     }
+
+                    if let Some(news_events) = &mut self.news_events {
+                        let Ok(mut inner) = self.state.state.lock() else {
+                            return;
+                        };
+
+                        while news_events.next_entry().is_some() {
+                            inner.num_unread_news += 1;
+                        }
+                    }
                 }
             }
         }
@@ -244,6 +280,32 @@ async fn handle_socket(mut socket: WebSocket, state: RouterState) {
     loop {
         tokio::select! {
             _ = interval.tick() => {
+                // resolve host names of server destinations before locking the state
+                let hosts = {
+                    let (address_book, addresses) = match state.state.lock() {
+                        Ok(inner) => (
+                            inner.address_book.clone(),
+                            inner
+                                .server_destinations
+                                .iter()
+                                .map(|(_, address)| address.clone())
+                                .collect::<Vec<_>>(),
+                        ),
+                        Err(_) => return,
+                    };
+                    let mut hosts = HashMap::new();
+
+                    if let Some(store) = address_book {
+                        for address in addresses {
+                            if let Some(host) = store.resolve_name(address.clone()).await {
+                                hosts.insert(address, host);
+                            }
+                        }
+                    }
+
+                    hosts
+                };
+
                 let update = {
                     let Ok(inner) = state.state.lock() else {
                         return;
@@ -272,6 +334,7 @@ async fn handle_socket(mut socket: WebSocket, state: RouterState) {
                         )
                     };
                     let num_connected_text = format!("Number of connected routers: {}", inner.num_routers);
+                    let firewall_status_text = format!("Firewall status: {}", inner.firewall_status);
                     let tunnel_build_success_rate_text = {
                         if inner.num_tunnels_built == 0 && inner.num_tunnel_build_failures == 0 {
                             format!("Tunnel build success rate: 0%")
@@ -297,13 +360,42 @@ async fn handle_socket(mut socket: WebSocket, state: RouterState) {
                         )
                     };
 
+                    let server_destinations = inner
+                        .server_destinations
+                        .iter()
+                        .map(|(name, address)| {
+                            serde_json::json!([name, address, hosts.get(address)])
+                        })
+                        .collect::<Vec<_>>();
+
+                    let news = inner
+                        .news
+                        .as_ref()
+                        .map(|store| {
+                            store
+                                .entries()
+                                .into_iter()
+                                .map(|entry| {
+                                    serde_json::json!({
+                                        "title": entry.title,
+                                        "updated": entry.updated,
+                                        "summary": entry.summary,
+                                        "link": entry.link,
+                                    })
+                                })
+                                .collect::<Vec<_>>()
+                        });
+
                     serde_json::json!({
                         "bandwidth": total_bandwidth_text,
                         "client_destinations": inner.client_destinations.clone(),
+                        "firewall_status": firewall_status_text,
+                        "news": news,
                         "num_routers": num_connected_text,
                         "num_transit_tunnels": num_transit_tunnels_text,
+                        "num_unread_news": inner.num_unread_news,
                         "tunnel_build_ratio": tunnel_build_success_rate_text,
-                        "server_destinations": inner.server_destinations.clone(),
+                        "server_destinations": server_destinations,
                         "status": status_text,
                         "transit_bandwidth": transit_bandwidth_text,
                         "uptime": uptime_text,
@@ -333,6 +425,11 @@ async fn handle_socket(mut socket: WebSocket, state: RouterState) {
                                     };
                                 }
                                 Some("forceful_shutdown") => std::process::exit(0),
+                                Some("news_read") => {
+                                    if let Ok(mut inner) = state.state.lock() {
+                                        inner.num_unread_news = 0;
+                                    };
+                                }
                                 command => tracing::warn!(
                                     target: LOG_TARGET,
                                     ?command,
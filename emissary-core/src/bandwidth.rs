@@ -0,0 +1,534 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Global bandwidth limiter.
+//!
+//! Inbound and outbound traffic of all transports is limited by token buckets that are shared
+//! between all active sessions. A session that finds a bucket empty pauses reading from its
+//! socket or writing to it until the bucket has been refilled.
+//!
+//! Transit tunnels have a bucket of their own, the size of which is the share percentage of the
+//! configured limits. Transit traffic that exceeds the share is dropped.
+//!
+//! The limits can be changed while the router is running, see [`BandwidthLimiter::update()`].
+
+use crate::{
+    config::BandwidthConfig,
+    primitives::Bandwidth,
+    runtime::{Instant, Runtime},
+};
+
+#[cfg(feature = "std")]
+use parking_lot::RwLock;
+#[cfg(feature = "no_std")]
+use spin::rwlock::RwLock;
+
+use alloc::sync::Arc;
+use core::{
+    cmp::min,
+    sync::atomic::{AtomicIsize, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::bandwidth";
+
+/// Default share percentage for transit traffic.
+const DEFAULT_SHARE: u8 = 80u8;
+
+/// Size of a KB, in bytes.
+const KB: usize = 1024usize;
+
+/// Minimum delay returned by [`TokenBucket::delay()`].
+///
+/// Prevents sessions from waking up too often when the bucket is almost refilled.
+const MIN_DELAY: Duration = Duration::from_millis(5);
+
+/// Refill state of a [`TokenBucket`].
+struct RefillState<R: Runtime> {
+    /// When was the bucket last refilled.
+    last_refill: R::Instant,
+
+    /// Time elapsed before `last_refill` which wasn't yet converted into tokens.
+    carry: Duration,
+}
+
+impl<R: Runtime> RefillState<R> {
+    fn new() -> Self {
+        Self {
+            last_refill: R::now(),
+            carry: Duration::ZERO,
+        }
+    }
+}
+
+/// Token bucket.
+///
+/// The bucket holds at most one second worth of tokens. Consuming tokens is allowed as long as
+/// the bucket isn't empty, meaning the bucket can go into debt by at most one message.
+///
+/// Tokens are consumed without locking. The bucket is refilled only once it has run out of
+/// tokens and the refill state is locked only for the duration of the refill. If another
+/// session is already refilling the bucket, the refill is skipped.
+struct TokenBucket<R: Runtime> {
+    /// Refill rate, in bytes per second, zero if traffic is not limited.
+    rate: AtomicUsize,
+
+    /// Available tokens, negative if the bucket is in debt.
+    tokens: AtomicIsize,
+
+    /// Refill state.
+    refill: RwLock<RefillState<R>>,
+}
+
+impl<R: Runtime> TokenBucket<R> {
+    /// Create new [`TokenBucket`] which is refilled `kbps` KB per second.
+    ///
+    /// If `kbps` is `None`, traffic is not limited.
+    fn new(kbps: Option<usize>) -> Self {
+        let rate = Self::rate(kbps);
+
+        Self {
+            rate: AtomicUsize::new(rate),
+            tokens: AtomicIsize::new(rate as isize),
+            refill: RwLock::new(RefillState::new()),
+        }
+    }
+
+    /// Convert `kbps` into refill rate.
+    fn rate(kbps: Option<usize>) -> usize {
+        kbps.map_or(0, |kbps| kbps.saturating_mul(KB).max(1))
+    }
+
+    /// Replace the refill rate with `kbps` and fill the bucket.
+    fn set_rate(&self, kbps: Option<usize>) {
+        let rate = Self::rate(kbps);
+
+        *self.refill.write() = RefillState::new();
+        self.tokens.store(rate as isize, Ordering::Release);
+        self.rate.store(rate, Ordering::Release);
+    }
+
+    /// Refill the bucket based on how much time has passed since the last refill.
+    ///
+    /// Only the time that the added tokens account for is consumed, the remainder is carried
+    /// over to the next refill so slow rates don't lose tokens to rounding.
+    fn refill(&self, rate: usize) {
+        let Some(mut state) = self.refill.try_write() else {
+            return;
+        };
+
+        let elapsed = state.last_refill.elapsed() + state.carry;
+        let tokens = elapsed.as_micros() * rate as u128 / 1_000_000;
+
+        if tokens == 0 {
+            return;
+        }
+
+        let accounted = Duration::from_micros((tokens * 1_000_000 / rate as u128) as u64);
+        state.last_refill = R::now();
+        state.carry = elapsed.saturating_sub(accounted);
+
+        self.tokens.fetch_add(min(tokens, rate as u128) as isize, Ordering::AcqRel);
+        self.tokens.fetch_min(rate as isize, Ordering::AcqRel);
+    }
+
+    /// Consume `bytes` tokens from the bucket, regardless of how many tokens are available.
+    fn consume(&self, bytes: usize) {
+        if self.rate.load(Ordering::Acquire) == 0 {
+            return;
+        }
+
+        self.tokens.fetch_sub(bytes as isize, Ordering::AcqRel);
+    }
+
+    /// Attempt to consume `bytes` tokens from the bucket.
+    ///
+    /// Returns `false` if the bucket is empty.
+    fn try_consume(&self, bytes: usize) -> bool {
+        let rate = self.rate.load(Ordering::Acquire);
+
+        if rate == 0 {
+            return true;
+        }
+
+        if self.tokens.load(Ordering::Acquire) <= 0 {
+            self.refill(rate);
+
+            if self.tokens.load(Ordering::Acquire) <= 0 {
+                return false;
+            }
+        }
+
+        self.tokens.fetch_sub(bytes as isize, Ordering::AcqRel);
+        true
+    }
+
+    /// Get how long the caller must wait until the bucket has tokens available.
+    ///
+    /// Returns `None` if there are tokens available.
+    fn delay(&self) -> Option<Duration> {
+        let rate = self.rate.load(Ordering::Acquire);
+
+        if rate == 0 || self.tokens.load(Ordering::Acquire) > 0 {
+            return None;
+        }
+
+        self.refill(rate);
+
+        let tokens = self.tokens.load(Ordering::Acquire);
+
+        (tokens <= 0).then(|| {
+            let missing = tokens.unsigned_abs() as u64 + 1;
+
+            Duration::from_micros(missing * 1_000_000 / rate as u64).max(MIN_DELAY)
+        })
+    }
+}
+
+/// Inner bandwidth limiter.
+struct InnerBandwidthLimiter<R: Runtime> {
+    /// Token bucket for inbound traffic.
+    inbound: TokenBucket<R>,
+
+    /// Token bucket for outbound traffic.
+    outbound: TokenBucket<R>,
+
+    /// Token bucket for transit traffic.
+    transit: TokenBucket<R>,
+}
+
+impl<R: Runtime> InnerBandwidthLimiter<R> {
+    /// Create new [`InnerBandwidthLimiter`] from `config`.
+    fn new(config: Option<&BandwidthConfig>) -> Self {
+        Self::log_limits(config);
+
+        Self {
+            inbound: TokenBucket::new(config.and_then(|config| config.inbound)),
+            outbound: TokenBucket::new(config.and_then(|config| config.outbound)),
+            transit: TokenBucket::new(config.and_then(BandwidthConfig::shared_bandwidth)),
+        }
+    }
+
+    /// Replace the current limits with `config`.
+    fn update(&self, config: Option<&BandwidthConfig>) {
+        Self::log_limits(config);
+
+        self.inbound.set_rate(config.and_then(|config| config.inbound));
+        self.outbound.set_rate(config.and_then(|config| config.outbound));
+        self.transit.set_rate(config.and_then(BandwidthConfig::shared_bandwidth));
+    }
+
+    fn log_limits(config: Option<&BandwidthConfig>) {
+        if let Some(config) = config {
+            tracing::info!(
+                target: LOG_TARGET,
+                inbound = ?config.inbound,
+                outbound = ?config.outbound,
+                share = ?config.share(),
+                shared = ?config.shared_bandwidth(),
+                "bandwidth limits",
+            );
+        }
+    }
+}
+
+/// Bandwidth limiter.
+///
+/// Cheap to clone, all clones share the same token buckets.
+#[derive(Clone)]
+pub struct BandwidthLimiter<R: Runtime> {
+    /// Inner bandwidth limiter.
+    inner: Arc<InnerBandwidthLimiter<R>>,
+
+    /// Total number of bytes received.
+    num_received: Arc<AtomicUsize>,
+
+    /// Total number of bytes sent.
+    num_sent: Arc<AtomicUsize>,
+}
+
+impl<R: Runtime> Default for BandwidthLimiter<R> {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl<R: Runtime> BandwidthLimiter<R> {
+    /// Create new [`BandwidthLimiter`].
+    ///
+    /// If `config` is `None`, the bandwidth is not limited.
+    pub fn new(config: Option<&BandwidthConfig>) -> Self {
+        Self {
+            inner: Arc::new(InnerBandwidthLimiter::new(config)),
+            num_received: Default::default(),
+            num_sent: Default::default(),
+        }
+    }
+
+    /// Replace the current limits with `config`.
+    ///
+    /// If `config` is `None`, the bandwidth is no longer limited.
+    pub fn update(&self, config: Option<&BandwidthConfig>) {
+        self.inner.update(config);
+    }
+
+    /// Register `bytes` of received traffic.
+    pub fn inbound(&self, bytes: usize) {
+        self.num_received.fetch_add(bytes, Ordering::Relaxed);
+        self.inner.inbound.consume(bytes);
+    }
+
+    /// Register `bytes` of sent traffic.
+    pub fn outbound(&self, bytes: usize) {
+        self.num_sent.fetch_add(bytes, Ordering::Relaxed);
+        self.inner.outbound.consume(bytes);
+    }
+
+    /// Get how long reading from the network must be paused, if at all.
+    pub fn inbound_delay(&self) -> Option<Duration> {
+        self.inner.inbound.delay()
+    }
+
+    /// Get how long writing to the network must be paused, if at all.
+    pub fn outbound_delay(&self) -> Option<Duration> {
+        self.inner.outbound.delay()
+    }
+
+    /// Get the total number of bytes received.
+    pub fn num_received(&self) -> usize {
+        self.num_received.load(Ordering::Relaxed)
+    }
+
+    /// Get the total number of bytes sent.
+    pub fn num_sent(&self) -> usize {
+        self.num_sent.load(Ordering::Relaxed)
+    }
+
+    /// Get bandwidth class derived from the current limits.
+    ///
+    /// Returns `None` if bandwidth is not limited.
+    pub fn bandwidth_class(&self) -> Option<Bandwidth> {
+        match self.inner.transit.rate.load(Ordering::Acquire) {
+            0 => None,
+            rate => Some(Bandwidth::from_kbps(rate / KB)),
+        }
+    }
+
+    /// Attempt to forward `bytes` of transit traffic.
+    ///
+    /// Returns `false` if the transit share has been exceeded and the message must be dropped.
+    pub fn transit(&self, bytes: usize) -> bool {
+        self.inner.transit.try_consume(bytes)
+    }
+}
+
+impl BandwidthConfig {
+    /// Get share percentage for transit traffic.
+    pub fn share(&self) -> u8 {
+        min(self.share.unwrap_or(DEFAULT_SHARE), 100u8)
+    }
+
+    /// Get shared bandwidth, in KBps.
+    ///
+    /// Shared bandwidth is the share percentage of the lower of the two limits. Returns `None`
+    /// if neither inbound nor outbound bandwidth is limited.
+    pub fn shared_bandwidth(&self) -> Option<usize> {
+        let limit = match (self.inbound, self.outbound) {
+            (None, None) => return None,
+            (Some(inbound), None) => inbound,
+            (None, Some(outbound)) => outbound,
+            (Some(inbound), Some(outbound)) => min(inbound, outbound),
+        };
+
+        Some(limit * self.share() as usize / 100)
+    }
+
+    /// Get bandwidth class derived from the shared bandwidth.
+    ///
+    /// Returns `None` if bandwidth is not limited.
+    pub fn bandwidth_class(&self) -> Option<Bandwidth> {
+        self.shared_bandwidth().map(Bandwidth::from_kbps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::mock::MockRuntime;
+
+    #[test]
+    fn bandwidth_class() {
+        let class = |inbound, outbound, share| {
+            BandwidthConfig {
+                inbound,
+                outbound,
+                share,
+            }
+            .bandwidth_class()
+        };
+
+        assert!(class(None, None, None).is_none());
+        assert!(core::matches!(
+            class(Some(10), None, Some(100)),
+            Some(Bandwidth::K)
+        ));
+        assert!(core::matches!(
+            class(Some(50), Some(40), None),
+            Some(Bandwidth::L)
+        ));
+        assert!(core::matches!(
+            class(None, Some(60), Some(100)),
+            Some(Bandwidth::M)
+        ));
+        assert!(core::matches!(
+            class(Some(100), Some(150), None),
+            Some(Bandwidth::N)
+        ));
+        assert!(core::matches!(
+            class(Some(300), Some(300), None),
+            Some(Bandwidth::O)
+        ));
+        assert!(core::matches!(
+            class(Some(1000), None, None),
+            Some(Bandwidth::P)
+        ));
+        assert!(core::matches!(
+            class(Some(4000), Some(5000), Some(90)),
+            Some(Bandwidth::X)
+        ));
+    }
+
+    #[test]
+    fn unlimited() {
+        let limiter = BandwidthLimiter::<MockRuntime>::new(None);
+
+        limiter.inbound(usize::MAX / 2);
+        limiter.outbound(usize::MAX / 2);
+
+        assert!(limiter.inbound_delay().is_none());
+        assert!(limiter.outbound_delay().is_none());
+        assert!(limiter.transit(usize::MAX / 2));
+    }
+
+    #[tokio::test]
+    async fn outbound_limited() {
+        let limiter = BandwidthLimiter::<MockRuntime>::new(Some(&BandwidthConfig {
+            inbound: None,
+            outbound: Some(10),
+            share: None,
+        }));
+
+        // full bucket
+        assert!(limiter.outbound_delay().is_none());
+
+        // consume all tokens and verify that writing must be paused
+        limiter.outbound(10 * KB + 512);
+        let delay = limiter.outbound_delay().expect("delay");
+        assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+
+        // inbound traffic is not limited
+        limiter.inbound(100 * KB);
+        assert!(limiter.inbound_delay().is_none());
+
+        // wait until the bucket has been refilled
+        tokio::time::sleep(delay).await;
+        assert!(limiter.outbound_delay().is_none());
+    }
+
+    #[test]
+    fn transit_share_exceeded() {
+        let limiter = BandwidthLimiter::<MockRuntime>::new(Some(&BandwidthConfig {
+            inbound: Some(100),
+            outbound: Some(100),
+            share: Some(50),
+        }));
+
+        // 50 KB of transit traffic is allowed and the bucket is refilled slowly enough
+        // that the share is exceeded after a few more messages
+        let num_forwarded = (0..100).take_while(|_| limiter.transit(KB)).count();
+        assert!((50..55).contains(&num_forwarded));
+
+        // outbound traffic is not affected by the transit share
+        assert!(limiter.outbound_delay().is_none());
+    }
+
+    #[test]
+    fn limits_updated() {
+        let limiter = BandwidthLimiter::<MockRuntime>::new(None);
+
+        limiter.outbound(100 * KB);
+        limiter.inbound(50 * KB);
+        assert!(limiter.outbound_delay().is_none());
+
+        // limit outbound bandwidth and verify that writing must be paused after the bucket is empty
+        limiter.update(Some(&BandwidthConfig {
+            inbound: None,
+            outbound: Some(10),
+            share: None,
+        }));
+        limiter.outbound(20 * KB);
+        assert!(limiter.outbound_delay().is_some());
+        assert!(limiter.inbound_delay().is_none());
+        assert!(core::matches!(
+            limiter.bandwidth_class(),
+            Some(Bandwidth::K)
+        ));
+
+        // raise the limit and verify that the bandwidth class follows it
+        limiter.update(Some(&BandwidthConfig {
+            inbound: None,
+            outbound: Some(1000),
+            share: Some(50),
+        }));
+        assert!(core::matches!(
+            limiter.bandwidth_class(),
+            Some(Bandwidth::P)
+        ));
+
+        // remove limits
+        limiter.update(None);
+        assert!(limiter.outbound_delay().is_none());
+        assert!(limiter.bandwidth_class().is_none());
+
+        // traffic is counted regardless of the limits
+        assert_eq!(limiter.num_sent(), 120 * KB);
+        assert_eq!(limiter.num_received(), 50 * KB);
+    }
+
+    #[test]
+    fn slow_refill_keeps_fractional_time() {
+        // one token every ~977 microseconds
+        let bucket = TokenBucket::<MockRuntime>::new(Some(1));
+        bucket.tokens.store(0, Ordering::Release);
+
+        let started = std::time::Instant::now();
+
+        // refill more often than a single token is produced
+        while started.elapsed() < Duration::from_millis(200) {
+            std::thread::sleep(Duration::from_micros(600));
+            bucket.refill(KB);
+        }
+
+        let expected = started.elapsed().as_micros() as usize * KB / 1_000_000;
+        let tokens = bucket.tokens.load(Ordering::Acquire) as usize;
+
+        // without carrying the fractional time over, only about 100 tokens would be produced
+        assert!(tokens + 2 >= expected && tokens <= expected + 2);
+    }
+}
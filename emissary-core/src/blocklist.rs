@@ -0,0 +1,454 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Blocklist of IP addresses, IP ranges and routers.
+//!
+//! Entries are grouped by their source, e.g., a local blocklist file or a blocklist feed, and the
+//! entries of a source are replaced as a whole when the source is updated.
+//!
+//! Blocklist format is one entry per line where an entry is either an IPv4/IPv6 address, an IP
+//! range in CIDR notation or a Base64-encoded router hash. Everything after `#` is a comment.
+
+use crate::{
+    crypto::base64_decode,
+    primitives::{RouterId, RouterInfo},
+};
+
+use hashbrown::{HashMap, HashSet};
+
+#[cfg(feature = "std")]
+use parking_lot::RwLock;
+#[cfg(feature = "no_std")]
+use spin::rwlock::RwLock;
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::net::IpAddr;
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::blocklist";
+
+/// Length of a router hash.
+const ROUTER_HASH_LEN: usize = 32usize;
+
+/// Blocklist entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlocklistEntry {
+    /// IP address.
+    Address(IpAddr),
+
+    /// IP range.
+    Range {
+        /// Network address of the range.
+        address: IpAddr,
+
+        /// Prefix length.
+        prefix: u8,
+    },
+
+    /// Router.
+    Router(RouterId),
+}
+
+impl BlocklistEntry {
+    /// Attempt to parse [`BlocklistEntry`] from `input`.
+    pub fn parse(input: &str) -> Option<Self> {
+        if let Some((address, prefix)) = input.split_once('/') {
+            let address = address.parse::<IpAddr>().ok()?.to_canonical();
+            let prefix = prefix.parse::<u8>().ok()?;
+
+            return match address {
+                IpAddr::V4(address) if prefix <= 32 => Some(Self::Range {
+                    address: IpAddr::V4(
+                        (u32::from(address)
+                            & u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0))
+                        .into(),
+                    ),
+                    prefix,
+                }),
+                IpAddr::V6(address) if prefix <= 128 => Some(Self::Range {
+                    address: IpAddr::V6(
+                        (u128::from(address)
+                            & u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0))
+                        .into(),
+                    ),
+                    prefix,
+                }),
+                _ => None,
+            };
+        }
+
+        if let Ok(address) = input.parse::<IpAddr>() {
+            return Some(Self::Address(address.to_canonical()));
+        }
+
+        let hash = base64_decode(input)?;
+
+        (hash.len() == ROUTER_HASH_LEN).then(|| Self::Router(RouterId::from(hash)))
+    }
+
+    /// Parse blocklist entries from `input`, ignoring comments and invalid entries.
+    pub fn parse_list(input: &str) -> Vec<Self> {
+        input
+            .lines()
+            .filter_map(|line| {
+                let line = line.split_once('#').map_or(line, |(line, _)| line).trim();
+
+                if line.is_empty() {
+                    return None;
+                }
+
+                let entry = Self::parse(line);
+
+                if entry.is_none() {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        %line,
+                        "invalid blocklist entry, ignoring",
+                    );
+                }
+
+                entry
+            })
+            .collect()
+    }
+}
+
+/// Sorted, non-overlapping ranges of addresses.
+///
+/// Blocklist feeds may contain thousands of ranges so instead of checking each range, the range
+/// which may contain an address is found with a binary search.
+struct Ranges<T> {
+    /// First and last address of each range, sorted by the first address.
+    ranges: Vec<(T, T)>,
+}
+
+impl<T> Default for Ranges<T> {
+    fn default() -> Self {
+        Self { ranges: Vec::new() }
+    }
+}
+
+impl<T: Ord + Copy> Ranges<T> {
+    /// Create new [`Ranges`] from `ranges`, merging overlapping ranges.
+    fn new(mut ranges: Vec<(T, T)>) -> Self {
+        ranges.sort_unstable();
+
+        let mut merged: Vec<(T, T)> = Vec::with_capacity(ranges.len());
+
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some((_, last_end)) if start <= *last_end =>
+                    *last_end = core::cmp::max(*last_end, end),
+                _ => merged.push((start, end)),
+            }
+        }
+
+        Self { ranges: merged }
+    }
+
+    /// Check if any of the ranges contains `address`.
+    fn contains(&self, address: T) -> bool {
+        match self.ranges.partition_point(|(start, _)| *start <= address) {
+            0 => false,
+            index => address <= self.ranges[index - 1].1,
+        }
+    }
+}
+
+/// Inner blocklist.
+#[derive(Default)]
+struct InnerBlocklist {
+    /// Blocked addresses.
+    addresses: HashSet<IpAddr>,
+
+    /// Blocked IPv4 ranges.
+    ipv4_ranges: Ranges<u32>,
+
+    /// Blocked IPv6 ranges.
+    ipv6_ranges: Ranges<u128>,
+
+    /// Number of blocked ranges.
+    num_ranges: usize,
+
+    /// Blocked routers.
+    routers: HashSet<RouterId>,
+
+    /// Entries of each source.
+    sources: HashMap<String, Vec<BlocklistEntry>>,
+}
+
+/// Blocklist.
+///
+/// Cheaply cloneable handle which allows updating the blocklist while the router is running.
+#[derive(Default, Clone)]
+pub struct Blocklist {
+    /// Inner blocklist.
+    inner: Arc<RwLock<InnerBlocklist>>,
+}
+
+impl Blocklist {
+    /// Create new [`Blocklist`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace entries of `source` with `entries`.
+    pub fn update(&self, source: impl Into<String>, entries: Vec<BlocklistEntry>) {
+        let mut inner = self.inner.write();
+        let source = source.into();
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            %source,
+            num_entries = ?entries.len(),
+            "update blocklist",
+        );
+
+        inner.sources.insert(source, entries);
+
+        let mut addresses = HashSet::new();
+        let mut ipv4_ranges = Vec::new();
+        let mut ipv6_ranges = Vec::new();
+        let mut routers = HashSet::new();
+
+        inner.sources.values().flatten().for_each(|entry| match entry {
+            BlocklistEntry::Address(address) => {
+                addresses.insert(*address);
+            }
+            BlocklistEntry::Range {
+                address: IpAddr::V4(network),
+                prefix,
+            } => {
+                let mask = u32::MAX.checked_shl(32 - *prefix as u32).unwrap_or(0);
+                ipv4_ranges.push((u32::from(*network), u32::from(*network) | !mask));
+            }
+            BlocklistEntry::Range {
+                address: IpAddr::V6(network),
+                prefix,
+            } => {
+                let mask = u128::MAX.checked_shl(128 - *prefix as u32).unwrap_or(0);
+                ipv6_ranges.push((u128::from(*network), u128::from(*network) | !mask));
+            }
+            BlocklistEntry::Router(router_id) => {
+                routers.insert(router_id.clone());
+            }
+        });
+
+        inner.addresses = addresses;
+        inner.num_ranges = ipv4_ranges.len() + ipv6_ranges.len();
+        inner.ipv4_ranges = Ranges::new(ipv4_ranges);
+        inner.ipv6_ranges = Ranges::new(ipv6_ranges);
+        inner.routers = routers;
+    }
+
+    /// Get the total number of blocked addresses, ranges and routers.
+    pub fn num_entries(&self) -> usize {
+        let inner = self.inner.read();
+
+        inner.addresses.len() + inner.num_ranges + inner.routers.len()
+    }
+
+    /// Check if `address` is blocked.
+    pub fn is_address_blocked(&self, address: &IpAddr) -> bool {
+        let address = address.to_canonical();
+        let inner = self.inner.read();
+
+        inner.addresses.contains(&address)
+            || match address {
+                IpAddr::V4(address) => inner.ipv4_ranges.contains(u32::from(address)),
+                IpAddr::V6(address) => inner.ipv6_ranges.contains(u128::from(address)),
+            }
+    }
+
+    /// Check if `router_id` is blocked.
+    pub fn is_router_blocked(&self, router_id: &RouterId) -> bool {
+        self.inner.read().routers.contains(router_id)
+    }
+
+    /// Check if the router of `router_info` is blocked, either by its router hash or by any of its
+    /// published addresses.
+    pub fn is_blocked(&self, router_info: &RouterInfo) -> bool {
+        self.is_router_blocked(&router_info.identity.id())
+            || router_info.addresses.values().any(|address| {
+                address
+                    .socket_address
+                    .is_some_and(|address| self.is_address_blocked(&address.ip()))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{crypto::base64_encode, primitives::RouterInfoBuilder, Ntcp2Config};
+    use core::net::{Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn parse_entries() {
+        let router_id = RouterId::random();
+        let entries = BlocklistEntry::parse_list(&format!(
+            "# comment\n\
+            1.2.3.4\n\
+            \n\
+            10.0.0.1/8 # private\n\
+            2001:db8::1\n\
+            2001:db8:1::/48\n\
+            {}\n\
+            1.2.3.4/33\n\
+            invalid\n",
+            base64_encode(router_id.to_vec()),
+        ));
+
+        assert_eq!(
+            entries,
+            vec![
+                BlocklistEntry::Address(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4))),
+                BlocklistEntry::Range {
+                    address: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)),
+                    prefix: 8,
+                },
+                BlocklistEntry::Address(IpAddr::V6("2001:db8::1".parse().unwrap())),
+                BlocklistEntry::Range {
+                    address: IpAddr::V6("2001:db8:1::".parse().unwrap()),
+                    prefix: 48,
+                },
+                BlocklistEntry::Router(router_id),
+            ]
+        );
+    }
+
+    #[test]
+    fn addresses_and_ranges_blocked() {
+        let blocklist = Blocklist::new();
+        blocklist.update(
+            "local",
+            BlocklistEntry::parse_list("1.2.3.4\n10.0.0.0/8\n2001:db8::/32\n0.0.0.0/0"),
+        );
+
+        assert_eq!(blocklist.num_entries(), 4);
+        assert!(blocklist.is_address_blocked(&IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4))));
+        assert!(blocklist.is_address_blocked(&IpAddr::V4(Ipv4Addr::new(10, 20, 30, 40))));
+        assert!(blocklist.is_address_blocked(&IpAddr::V6("2001:db8:ffff::1".parse().unwrap())));
+        assert!(!blocklist.is_address_blocked(&IpAddr::V6("2001:db9::1".parse().unwrap())));
+
+        // `0.0.0.0/0` blocks all ipv4 addresses, including ipv4-mapped ipv6 addresses
+        assert!(blocklist.is_address_blocked(&IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8))));
+        assert!(
+            blocklist.is_address_blocked(&IpAddr::V6(Ipv4Addr::new(8, 8, 8, 8).to_ipv6_mapped()))
+        );
+        assert!(!blocklist.is_address_blocked(&IpAddr::V6(Ipv6Addr::LOCALHOST)));
+    }
+
+    #[test]
+    fn overlapping_ranges_merged() {
+        let blocklist = Blocklist::new();
+        blocklist.update(
+            "local",
+            BlocklistEntry::parse_list(
+                "10.0.0.0/16\n10.0.128.0/17\n10.0.0.0/8\n192.168.1.0/24\n192.168.3.0/24",
+            ),
+        );
+
+        assert_eq!(blocklist.num_entries(), 5);
+        assert_eq!(blocklist.inner.read().ipv4_ranges.ranges.len(), 3);
+
+        for (address, blocked) in [
+            (Ipv4Addr::new(9, 255, 255, 255), false),
+            (Ipv4Addr::new(10, 0, 0, 0), true),
+            (Ipv4Addr::new(10, 200, 0, 1), true),
+            (Ipv4Addr::new(10, 255, 255, 255), true),
+            (Ipv4Addr::new(11, 0, 0, 0), false),
+            (Ipv4Addr::new(192, 168, 1, 255), true),
+            (Ipv4Addr::new(192, 168, 2, 1), false),
+            (Ipv4Addr::new(192, 168, 3, 0), true),
+            (Ipv4Addr::new(192, 168, 4, 0), false),
+        ] {
+            assert_eq!(
+                blocklist.is_address_blocked(&IpAddr::V4(address)),
+                blocked,
+                "{address}"
+            );
+        }
+    }
+
+    #[test]
+    fn many_ranges() {
+        let blocklist = Blocklist::new();
+        let entries = (0..=255u8)
+            .step_by(2)
+            .map(|i| format!("{i}.0.0.0/8"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        blocklist.update("feed", BlocklistEntry::parse_list(&entries));
+
+        assert_eq!(blocklist.num_entries(), 128);
+        assert!((0..=255u8).all(|i| {
+            blocklist.is_address_blocked(&IpAddr::V4(Ipv4Addr::new(i, 1, 2, 3))) == (i % 2 == 0)
+        }));
+    }
+
+    #[test]
+    fn source_entries_replaced() {
+        let blocklist = Blocklist::new();
+        let address = IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4));
+
+        blocklist.update("local", BlocklistEntry::parse_list("1.2.3.4"));
+        blocklist.update("feed", BlocklistEntry::parse_list("1.2.3.4\n5.6.7.8"));
+        assert_eq!(blocklist.num_entries(), 2);
+
+        // address is still blocked by the local blocklist
+        blocklist.update("feed", Vec::new());
+        assert!(blocklist.is_address_blocked(&address));
+        assert_eq!(blocklist.num_entries(), 1);
+
+        blocklist.update("local", Vec::new());
+        assert!(!blocklist.is_address_blocked(&address));
+        assert_eq!(blocklist.num_entries(), 0);
+    }
+
+    #[test]
+    fn router_blocked() {
+        let (router_info, _, _) = RouterInfoBuilder::default()
+            .with_ntcp2(Ntcp2Config {
+                port: 8888,
+                ipv4_host: Some(Ipv4Addr::new(1, 2, 3, 4)),
+                ipv6_host: None,
+                ipv4: true,
+                ipv6: false,
+                publish: true,
+                key: [0u8; 32],
+                iv: [0u8; 16],
+            })
+            .build();
+        let blocklist = Blocklist::new();
+        assert!(!blocklist.is_blocked(&router_info));
+
+        // blocked by address
+        blocklist.update("local", BlocklistEntry::parse_list("1.2.3.0/24"));
+        assert!(blocklist.is_blocked(&router_info));
+
+        // blocked by router hash
+        blocklist.update(
+            "local",
+            vec![BlocklistEntry::Router(router_info.identity.id())],
+        );
+        assert!(blocklist.is_blocked(&router_info));
+        assert!(blocklist.is_router_blocked(&router_info.identity.id()));
+        assert!(!blocklist.is_router_blocked(&RouterId::random()));
+    }
+}
@@ -16,9 +16,9 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use core::net::Ipv4Addr;
+use core::net::{Ipv4Addr, Ipv6Addr};
 
-use crate::{primitives::Str, profile::Profile, tunnel::TunnelPoolConfig};
+use crate::{blocklist::Blocklist, primitives::Str, profile::Profile, tunnel::TunnelPoolConfig};
 
 use alloc::{string::String, vec::Vec};
 
@@ -61,8 +61,17 @@ pub struct Ntcp2Config {
     /// NTCP2 port.
     pub port: u16,
 
-    /// NTCP2 listen address.
-    pub host: Option<Ipv4Addr>,
+    /// Publicly reachable IPv4 address of the router.
+    pub ipv4_host: Option<Ipv4Addr>,
+
+    /// Publicly reachable IPv6 address of the router.
+    pub ipv6_host: Option<Ipv6Addr>,
+
+    /// Should NTCP2 accept and dial connections over IPv4.
+    pub ipv4: bool,
+
+    /// Should NTCP2 accept and dial connections over IPv6.
+    pub ipv6: bool,
 
     /// Should NTCP2 be published in router info.
     pub publish: bool,
@@ -80,8 +89,17 @@ pub struct Ssu2Config {
     /// SSU2 port.
     pub port: u16,
 
-    /// SSU2 listen address.
-    pub host: Option<Ipv4Addr>,
+    /// Publicly reachable IPv4 address of the router.
+    pub ipv4_host: Option<Ipv4Addr>,
+
+    /// Publicly reachable IPv6 address of the router.
+    pub ipv6_host: Option<Ipv6Addr>,
+
+    /// Should SSU2 accept and dial connections over IPv4.
+    pub ipv4: bool,
+
+    /// Should SSU2 accept and dial connections over IPv6.
+    pub ipv6: bool,
 
     /// Should SSU2 be published in router info.
     pub publish: bool,
@@ -132,12 +150,41 @@ pub struct TransitConfig {
     pub max_tunnels: Option<usize>,
 }
 
+/// Bandwidth configuration.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct BandwidthConfig {
+    /// Maximum inbound bandwidth, in KBps.
+    ///
+    /// If `None`, inbound bandwidth is not limited.
+    pub inbound: Option<usize>,
+
+    /// Maximum outbound bandwidth, in KBps.
+    ///
+    /// If `None`, outbound bandwidth is not limited.
+    pub outbound: Option<usize>,
+
+    /// Percentage of bandwidth shared with transit tunnels.
+    ///
+    /// If `None`, 80% of bandwidth is shared.
+    pub share: Option<u8>,
+}
+
 /// Router configuration.
 #[derive(Default)]
 pub struct Config {
     /// Allow local addresses.
     pub allow_local: bool,
 
+    /// Bandwidth configuration.
+    ///
+    /// `None` if bandwidth is not limited.
+    pub bandwidth: Option<BandwidthConfig>,
+
+    /// Blocklist.
+    ///
+    /// `None` if no addresses or routers are blocked.
+    pub blocklist: Option<Blocklist>,
+
     /// Router capabilities.
     pub caps: Option<String>,
 
@@ -0,0 +1,346 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Key blinding for encrypted lease sets.
+//!
+//! https://geti2p.net/spec/encryptedleaseset#key-blinding
+
+use crate::crypto::{hmac::Hmac, sha256::Sha256, SigningPrivateKey, SigningPublicKey};
+
+use curve25519_elligator2::{edwards::CompressedEdwardsY, EdwardsPoint, Scalar};
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+use zeroize::Zeroize;
+
+use alloc::vec::Vec;
+
+/// Signature type of an unblinded `EdDSA_SHA512_Ed25519` signing key.
+pub const SIG_TYPE_EDDSA_SHA512_ED25519: u16 = 0x0007;
+
+/// Signature type of a blinded `RedDSA_SHA512_Ed25519` signing key.
+pub const SIG_TYPE_REDDSA_SHA512_ED25519: u16 = 0x000b;
+
+/// HKDF-SHA256, as specified in RFC 5869.
+///
+/// `N` must not exceed 255 * 32 bytes.
+pub fn hkdf<const N: usize>(salt: &[u8], ikm: &[u8], info: &[u8]) -> [u8; N] {
+    let mut prk = Hmac::new(salt).update(ikm).finalize_new();
+    let mut okm = [0u8; N];
+    let mut block = Vec::<u8>::new();
+
+    for (i, chunk) in okm.chunks_mut(32).enumerate() {
+        block = Hmac::new(&prk).update(&block).update(info).update([i as u8 + 1]).finalize();
+
+        chunk.copy_from_slice(&block[..chunk.len()]);
+    }
+
+    prk.zeroize();
+    block.zeroize();
+
+    okm
+}
+
+/// Create credential for the unblinded `public_key`.
+pub fn credential(public_key: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .update(b"credential")
+        .update(public_key)
+        .update(SIG_TYPE_EDDSA_SHA512_ED25519.to_be_bytes())
+        .update(SIG_TYPE_REDDSA_SHA512_ED25519.to_be_bytes())
+        .finalize_new()
+}
+
+/// Create subcredential for the unblinded `public_key` and its blinded counterpart.
+pub fn subcredential(public_key: &[u8; 32], blinded_public_key: &BlindedPublicKey) -> [u8; 32] {
+    Sha256::new()
+        .update(b"subcredential")
+        .update(credential(public_key))
+        .update(blinded_public_key.as_bytes())
+        .finalize_new()
+}
+
+/// Generate blinding factor for `public_key` for `date`.
+///
+/// `date` is the current UTC date in `yyyyMMdd` format and `secret` is the optional lookup
+/// password of the encrypted lease set.
+fn generate_alpha(public_key: &[u8; 32], date: &str, secret: Option<&str>) -> Scalar {
+    let salt = Sha256::new()
+        .update(b"I2PGenerateAlpha")
+        .update(public_key)
+        .update(SIG_TYPE_EDDSA_SHA512_ED25519.to_be_bytes())
+        .update(SIG_TYPE_REDDSA_SHA512_ED25519.to_be_bytes())
+        .finalize_new();
+
+    let mut ikm = Vec::with_capacity(date.len() + secret.map_or(0usize, |secret| secret.len()));
+    ikm.extend_from_slice(date.as_bytes());
+    ikm.extend_from_slice(secret.unwrap_or_default().as_bytes());
+
+    let mut seed = hkdf::<64>(&salt, &ikm, b"i2pblinding1");
+    let alpha = Scalar::from_bytes_mod_order_wide(&seed);
+    seed.zeroize();
+
+    alpha
+}
+
+/// Blinded public key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlindedPublicKey([u8; 32]);
+
+impl BlindedPublicKey {
+    /// Blind `public_key` for `date`, using an optional `secret`.
+    ///
+    /// Returns `None` if `public_key` is not a valid Ed25519 point.
+    pub fn new(public_key: &[u8; 32], date: &str, secret: Option<&str>) -> Option<Self> {
+        let point = CompressedEdwardsY(*public_key).decompress()?;
+        let alpha = generate_alpha(public_key, date, secret);
+
+        Some(Self(
+            (point + EdwardsPoint::mul_base(&alpha)).compress().to_bytes(),
+        ))
+    }
+
+    /// Get reference to the serialized blinded public key.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Get key under which an encrypted lease set signed by the blinded key is stored in `NetDb`.
+    pub fn store_key(&self) -> [u8; 32] {
+        Sha256::new()
+            .update(SIG_TYPE_REDDSA_SHA512_ED25519.to_be_bytes())
+            .update(self.0)
+            .finalize_new()
+    }
+
+    /// Get verifying key for signatures created with the blinded signing key.
+    pub fn verifying_key(&self) -> Option<SigningPublicKey> {
+        SigningPublicKey::from_bytes(&self.0)
+    }
+}
+
+impl From<[u8; 32]> for BlindedPublicKey {
+    fn from(value: [u8; 32]) -> Self {
+        Self(value)
+    }
+}
+
+/// Blinded signing key.
+///
+/// Signatures are created using `RedDSA_SHA512_Ed25519` and they can be verified with
+/// the regular Ed25519 verification algorithm.
+pub struct BlindedSigningKey {
+    /// Blinded scalar.
+    scalar: Scalar,
+
+    /// Blinded public key.
+    public: BlindedPublicKey,
+}
+
+impl BlindedSigningKey {
+    /// Blind `signing_key` for `date`, using an optional `secret`.
+    pub fn new(signing_key: &SigningPrivateKey, date: &str, secret: Option<&str>) -> Self {
+        let SigningPrivateKey::Ed25519(signing_key) = signing_key;
+
+        let mut scalar_bytes = signing_key.to_scalar_bytes();
+        scalar_bytes[0] &= 248;
+        scalar_bytes[31] &= 127;
+        scalar_bytes[31] |= 64;
+
+        let alpha = generate_alpha(signing_key.verifying_key().as_bytes(), date, secret);
+        let scalar = Scalar::from_bytes_mod_order(scalar_bytes) + alpha;
+        scalar_bytes.zeroize();
+
+        Self {
+            public: BlindedPublicKey(EdwardsPoint::mul_base(&scalar).compress().to_bytes()),
+            scalar,
+        }
+    }
+
+    /// Get reference to the blinded public key.
+    pub fn public(&self) -> &BlindedPublicKey {
+        &self.public
+    }
+
+    /// Sign `message` with the blinded signing key.
+    pub fn sign(&self, message: &[u8], mut csprng: impl RngCore + CryptoRng) -> [u8; 64] {
+        let mut nonce = [0u8; 80];
+        csprng.fill_bytes(&mut nonce);
+
+        let r = Scalar::from_bytes_mod_order_wide(
+            &Sha512::new()
+                .chain_update(nonce)
+                .chain_update(self.public.0)
+                .chain_update(message)
+                .finalize()
+                .into(),
+        );
+        let big_r = EdwardsPoint::mul_base(&r).compress();
+        let hram = Scalar::from_bytes_mod_order_wide(
+            &Sha512::new()
+                .chain_update(big_r.as_bytes())
+                .chain_update(self.public.0)
+                .chain_update(message)
+                .finalize()
+                .into(),
+        );
+        let s = r + hram * self.scalar;
+        nonce.zeroize();
+
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice(big_r.as_bytes());
+        signature[32..].copy_from_slice(s.as_bytes());
+
+        signature
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Seed of the signing key used for the known-answer vectors.
+    ///
+    /// The vectors were generated with an independent implementation of the specification.
+    const SEED: [u8; 32] = [
+        0xf3, 0x00, 0xe4, 0xd8, 0x1d, 0x4c, 0xf1, 0x8a, 0x93, 0x6b, 0x84, 0x8f, 0x03, 0xb4, 0xb0,
+        0x9f, 0x9b, 0xec, 0xb4, 0xd1, 0xab, 0x9d, 0x41, 0xd7, 0xb6, 0x8d, 0x47, 0x41, 0xc6, 0x29,
+        0x1c, 0x61,
+    ];
+
+    /// Unblinded public key of [`SEED`].
+    const PUBLIC_KEY: [u8; 32] = [
+        0x3c, 0x0c, 0xcf, 0x60, 0x40, 0x62, 0xde, 0xdd, 0x13, 0xdf, 0xec, 0x00, 0x85, 0x6b, 0xd3,
+        0x49, 0xd8, 0xf9, 0xf5, 0x1c, 0xc2, 0x2d, 0x2d, 0xbf, 0xf0, 0xbd, 0x59, 0x78, 0xb9, 0xea,
+        0x07, 0x43,
+    ];
+
+    #[test]
+    fn hkdf_rfc5869() {
+        let okm = hkdf::<42>(
+            &[
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c,
+            ],
+            &[0x0b; 22],
+            &[0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9],
+        );
+
+        assert_eq!(
+            okm,
+            [
+                0x3c, 0xb2, 0x5f, 0x25, 0xfa, 0xac, 0xd5, 0x7a, 0x90, 0x43, 0x4f, 0x64, 0xd0, 0x36,
+                0x2f, 0x2a, 0x2d, 0x2d, 0x0a, 0x90, 0xcf, 0x1a, 0x5a, 0x4c, 0x5d, 0xb0, 0x2d, 0x56,
+                0xec, 0xc4, 0xc5, 0xbf, 0x34, 0x00, 0x72, 0x08, 0xd5, 0xb8, 0x87, 0x18, 0x58, 0x65,
+            ]
+        );
+    }
+
+    #[test]
+    fn blinded_keys_match() {
+        let signing_key = SigningPrivateKey::random(rand::thread_rng());
+        let public_key: [u8; 32] = signing_key.public().as_ref().try_into().unwrap();
+
+        for secret in [None, Some("hunter2")] {
+            let blinded_signing_key = BlindedSigningKey::new(&signing_key, "20250105", secret);
+            let blinded_public_key =
+                BlindedPublicKey::new(&public_key, "20250105", secret).unwrap();
+
+            assert_eq!(blinded_signing_key.public(), &blinded_public_key);
+            assert_ne!(blinded_public_key.as_bytes(), &public_key);
+        }
+
+        // blinded key rotates daily
+        assert_ne!(
+            BlindedPublicKey::new(&public_key, "20250105", None),
+            BlindedPublicKey::new(&public_key, "20250106", None),
+        );
+
+        // secret changes the blinded key
+        assert_ne!(
+            BlindedPublicKey::new(&public_key, "20250105", None),
+            BlindedPublicKey::new(&public_key, "20250105", Some("hunter2")),
+        );
+    }
+
+    #[test]
+    fn blinded_signature_verifies() {
+        let signing_key = SigningPrivateKey::random(rand::thread_rng());
+        let blinded_signing_key = BlindedSigningKey::new(&signing_key, "20250105", None);
+        let verifying_key = blinded_signing_key.public().verifying_key().unwrap();
+
+        let signature = blinded_signing_key.sign(b"hello, world", rand::thread_rng());
+        assert!(verifying_key.verify(b"hello, world", &signature).is_ok());
+        assert!(verifying_key.verify(b"goodbye, world", &signature).is_err());
+
+        // signature doesn't verify with the unblinded key
+        assert!(signing_key.public().verify(b"hello, world", &signature).is_err());
+    }
+
+    #[test]
+    fn blinding_known_answers() {
+        let signing_key = SigningPrivateKey::from_bytes(&SEED).unwrap();
+        assert_eq!(signing_key.public().as_ref(), &PUBLIC_KEY);
+
+        for (date, secret, expected) in [
+            (
+                "20250105",
+                None,
+                [
+                    0x86, 0x20, 0xa2, 0xc3, 0xb9, 0x5b, 0xeb, 0xe8, 0x9a, 0x29, 0x0a, 0x60, 0x30,
+                    0x7a, 0x88, 0x45, 0x7e, 0xfe, 0x37, 0xaa, 0x24, 0x81, 0x2b, 0x44, 0xeb, 0xda,
+                    0x24, 0x65, 0x69, 0x81, 0x85, 0xc4,
+                ],
+            ),
+            (
+                "20250106",
+                None,
+                [
+                    0xe8, 0x62, 0x74, 0x0d, 0x3b, 0x90, 0xf6, 0xf4, 0xa0, 0xf8, 0x1f, 0x2d, 0xa7,
+                    0x3e, 0xac, 0xd7, 0x11, 0x06, 0x63, 0xb9, 0xcd, 0x26, 0xc6, 0x89, 0xf4, 0xe0,
+                    0xf8, 0xb1, 0x2f, 0x4e, 0xd2, 0xc1,
+                ],
+            ),
+            (
+                "20250105",
+                Some("hunter2"),
+                [
+                    0xdd, 0xc0, 0x89, 0x2e, 0xac, 0x56, 0xcf, 0xd1, 0xa9, 0x84, 0x0c, 0x4f, 0xe1,
+                    0xdd, 0x14, 0xfe, 0xf9, 0x05, 0x67, 0xc5, 0x1a, 0x64, 0x95, 0x57, 0x21, 0xe1,
+                    0x86, 0xb2, 0x0b, 0xe5, 0x22, 0x18,
+                ],
+            ),
+        ] {
+            assert_eq!(
+                BlindedPublicKey::new(&PUBLIC_KEY, date, secret).unwrap().as_bytes(),
+                &expected
+            );
+            assert_eq!(
+                BlindedSigningKey::new(&signing_key, date, secret).public().as_bytes(),
+                &expected
+            );
+        }
+
+        assert_eq!(
+            BlindedPublicKey::new(&PUBLIC_KEY, "20250105", None).unwrap().store_key(),
+            [
+                0x75, 0x0a, 0x11, 0x05, 0x65, 0x44, 0xa8, 0x9d, 0x72, 0x0c, 0x03, 0x2a, 0x5e, 0x57,
+                0xda, 0x31, 0x90, 0x0b, 0x0f, 0xa2, 0x07, 0xbd, 0x24, 0x2a, 0xb3, 0x6e, 0x09, 0xf5,
+                0x87, 0x67, 0x15, 0xc1
+            ]
+        );
+    }
+}
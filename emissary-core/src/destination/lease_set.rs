@@ -17,7 +17,10 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    crypto::{chachapoly::ChaChaPoly, EphemeralPrivateKey, StaticPublicKey},
+    crypto::{
+        base32_decode, base64_decode, blinding::BlindedSigningKey, chachapoly::ChaChaPoly,
+        EphemeralPrivateKey, SigningPrivateKey, StaticPrivateKey, StaticPublicKey,
+    },
     error::QueryError,
     i2np::{
         database::{
@@ -28,7 +31,10 @@ use crate::{
         MessageBuilder, MessageType, I2NP_MESSAGE_EXPIRATION,
     },
     netdb::{Dht, NetDbHandle},
-    primitives::{DestinationId, Lease, MessageId, RouterId, TunnelId},
+    primitives::{
+        ClientAuth, ClientKey, DestinationId, EncryptedLeaseSetBuilder, Lease, LeaseSet2,
+        LeaseSet2Header, MessageId, MetaLease, MetaLeaseKind, MetaLeaseSet, RouterId, TunnelId,
+    },
     profile::ProfileStorage,
     runtime::{Instant, JoinSet, Runtime},
     tunnel::{NoiseContext, TunnelMessageSender},
@@ -40,7 +46,7 @@ use futures_channel::oneshot;
 use hashbrown::{HashMap, HashSet};
 use rand_core::RngCore;
 
-use alloc::vec::Vec;
+use alloc::{format, string::String, vec::Vec};
 use core::{
     fmt,
     future::Future,
@@ -77,6 +83,12 @@ const STORAGE_VERIFICATION_TIMEOUT: Duration = Duration::from_secs(2);
 /// Once deemed as failed, local lease set is republished to `NetDb`.
 const STORAGE_VERIFICATION_TOTAL_TIMEOUT: Duration = Duration::from_secs(15);
 
+/// How long before the offline signature expires should the user be alerted.
+const OFFLINE_SIGNATURE_EXPIRATION_WARNING: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// How often is the user alerted about an expiring offline signature.
+const OFFLINE_SIGNATURE_ALERT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 enum RetryKind<R: Runtime> {
     /// Get floodfills closet to [`Destination`].
     GetClosestFloodfills,
@@ -168,11 +180,174 @@ impl<R: Runtime> fmt::Debug for PublishState<R> {
     }
 }
 
+/// Encryption configuration for the local lease set.
+///
+/// If specified, the local lease set is published as an encrypted lease set, stored under a key
+/// derived from the blinded public key of the destination.
+pub struct LeaseSetEncryption {
+    /// Signing key of the destination.
+    pub signing_key: SigningPrivateKey,
+
+    /// Lookup password, if any.
+    pub secret: Option<String>,
+
+    /// Per-client authentication, if any.
+    pub client_auth: Option<ClientAuth>,
+}
+
+impl LeaseSetEncryption {
+    /// Attempt to create [`LeaseSetEncryption`] from I2CP session options.
+    ///
+    /// Returns `None` if `i2cp.leaseSetType` is not 5 or if the client authentication options are
+    /// invalid.
+    pub fn from_options(
+        options: &HashMap<String, String>,
+        signing_key: &SigningPrivateKey,
+    ) -> Option<Self> {
+        if options.get("i2cp.leaseSetType").is_none_or(|kind| kind != "5") {
+            return None;
+        }
+
+        // client keys are of format `i2cp.leaseSetClient.<dh|psk>.<index>=<name>:<base64 key>`
+        let client_keys = |kind: &str| -> Option<Vec<[u8; 32]>> {
+            let prefix = format!("i2cp.leaseSetClient.{kind}.");
+
+            options
+                .iter()
+                .filter(|(key, _)| key.starts_with(&prefix))
+                .map(|(_, value)| {
+                    let key = value.rsplit_once(':').map_or(value.as_str(), |(_, key)| key);
+
+                    base64_decode(key)?.try_into().ok()
+                })
+                .collect()
+        };
+
+        let client_auth = match options.get("i2cp.leaseSetAuthType").map(|kind| kind.as_str()) {
+            None | Some("0") => None,
+            Some("1") => Some(ClientAuth::Dh {
+                clients: client_keys("dh")?
+                    .into_iter()
+                    .map(|key| StaticPublicKey::from_bytes(&key))
+                    .collect::<Option<Vec<_>>>()?,
+            }),
+            Some("2") => Some(ClientAuth::Psk {
+                clients: client_keys("psk")?,
+            }),
+            Some(kind) => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    ?kind,
+                    "unsupported client authentication type",
+                );
+                return None;
+            }
+        };
+
+        Some(Self {
+            signing_key: signing_key.clone(),
+            secret: options.get("i2cp.leaseSetSecret").cloned(),
+            client_auth,
+        })
+    }
+
+    /// Attempt to parse the key used to decrypt encrypted lease sets from I2CP session options.
+    ///
+    /// The key is specified in `i2cp.leaseSetPrivKey` and `i2cp.leaseSetAuthType` specifies
+    /// whether it's a DH or a PSK key.
+    pub fn client_key_from_options(options: &HashMap<String, String>) -> Option<ClientKey> {
+        let key = base64_decode(options.get("i2cp.leaseSetPrivKey")?)?;
+
+        match options.get("i2cp.leaseSetAuthType").map(|kind| kind.as_str()) {
+            Some("1") => Some(ClientKey::Dh(StaticPrivateKey::from_bytes(&key)?)),
+            Some("2") => Some(ClientKey::Psk(key.try_into().ok()?)),
+            _ => None,
+        }
+    }
+}
+
+/// Kind of the published lease set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaseSetKind {
+    /// Lease set, type 2.
+    LeaseSet2,
+
+    /// Encrypted lease set.
+    EncryptedLeaseSet,
+
+    /// Meta lease set.
+    MetaLeaseSet,
+}
+
+/// Meta lease set configuration for the local lease set.
+///
+/// If specified, instead of its own lease set, the destination publishes a meta lease set which
+/// lists the destinations hosting the service.
+pub struct MetaLeaseSetConfig {
+    /// Signing key of the destination.
+    pub signing_key: SigningPrivateKey,
+
+    /// Member destinations and their costs.
+    pub entries: Vec<(DestinationId, u8)>,
+}
+
+impl MetaLeaseSetConfig {
+    /// Attempt to create [`MetaLeaseSetConfig`] from I2CP session options.
+    ///
+    /// Member destinations are specified in `i2cp.metaLeaseSetEntries` as a comma-separated list
+    /// of `<base32 address>[:<cost>]`.
+    ///
+    /// Returns `None` if `i2cp.leaseSetType` is not 7 or if the entries are invalid.
+    pub fn from_options(
+        options: &HashMap<String, String>,
+        signing_key: &SigningPrivateKey,
+    ) -> Option<Self> {
+        if options.get("i2cp.leaseSetType").is_none_or(|kind| kind != "7") {
+            return None;
+        }
+
+        let entries = options
+            .get("i2cp.metaLeaseSetEntries")?
+            .split(',')
+            .map(|entry| {
+                let (address, cost) = match entry.trim().split_once(':') {
+                    None => (entry.trim(), 0u8),
+                    Some((address, cost)) => (address, cost.parse::<u8>().ok()?),
+                };
+                let address = address.strip_suffix(".b32.i2p").unwrap_or(address);
+                let destination_id = base32_decode(address)?;
+
+                (destination_id.len() == 32).then(|| (DestinationId::from(destination_id), cost))
+            })
+            .collect::<Option<Vec<_>>>();
+
+        match entries {
+            Some(entries) if !entries.is_empty() && entries.len() <= 16 => Some(Self {
+                signing_key: signing_key.clone(),
+                entries,
+            }),
+            _ => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    "invalid meta lease set entries",
+                );
+                None
+            }
+        }
+    }
+}
+
 /// Local lease set manager.
 pub struct LeaseSetManager<R: Runtime> {
     /// ID of the local destination.
     destination_id: DestinationId,
 
+    /// Kind of the local lease set.
+    kind: LeaseSetKind,
+
+    /// Encryption configuration, if the lease set is encrypted by [`LeaseSetManager`].
+    encryption: Option<LeaseSetEncryption>,
+
     /// Expiring inbound tunnels.
     expiring_tunnels: HashSet<TunnelId>,
 
@@ -185,12 +360,19 @@ pub struct LeaseSetManager<R: Runtime> {
     /// Local lease set.
     lease_set: Bytes,
 
+    /// Meta lease set configuration, if the lease set is a meta lease set created by
+    /// [`LeaseSetManager`].
+    meta: Option<MetaLeaseSetConfig>,
+
     /// Handle to [`NetDb`].
     netdb_handle: NetDbHandle,
 
     /// Noise context.
     noise_ctx: NoiseContext,
 
+    /// When was the user last alerted about an expiring offline signature.
+    offline_signature_alert: Option<R::Instant>,
+
     /// How many inbound tunnels is the [`Destination`] configured to have.
     ///
     /// Used to gauge when to publish new lease set to `NetDb`.
@@ -261,15 +443,19 @@ impl<R: Runtime> LeaseSetManager<R> {
             }
         };
 
-        Self {
+        let mut manager = Self {
             destination_id: destination_id.clone(),
+            encryption: None,
             expiring_tunnels: HashSet::new(),
             floodfills: HashMap::new(),
             key,
+            kind: LeaseSetKind::LeaseSet2,
             lease_set,
+            meta: None,
             netdb_handle,
             noise_ctx,
             num_inbound,
+            offline_signature_alert: None,
             pending_floodfills: HashSet::new(),
             profile_storage,
             queried_floodfills: HashSet::new(),
@@ -280,7 +466,195 @@ impl<R: Runtime> LeaseSetManager<R> {
             tunnels: HashMap::from_iter(tunnels.into_iter().map(|lease| (lease.tunnel_id, lease))),
             unpublished,
             waker: None,
+        };
+        manager.check_offline_signature(&manager.lease_set.clone());
+
+        manager
+    }
+
+    /// Publish the local lease set as an encrypted lease set.
+    pub fn with_encryption(mut self, encryption: LeaseSetEncryption) -> Self {
+        tracing::debug!(
+            target: LOG_TARGET,
+            local = %self.destination_id,
+            secret = ?encryption.secret.is_some(),
+            client_auth = ?encryption.client_auth.is_some(),
+            "publish encrypted lease set",
+        );
+
+        self.encryption = Some(encryption);
+        self.kind = LeaseSetKind::EncryptedLeaseSet;
+
+        if let Some(lease_set) = self.encrypt_lease_set(&self.lease_set.clone()) {
+            self.lease_set = lease_set;
+        }
+
+        if !self.unpublished {
+            self.get_closest_floodfills();
+        }
+
+        self
+    }
+
+    /// Publish a meta lease set instead of the local lease set.
+    pub fn with_meta_lease_set(mut self, meta: MetaLeaseSetConfig) -> Self {
+        tracing::debug!(
+            target: LOG_TARGET,
+            local = %self.destination_id,
+            num_entries = ?meta.entries.len(),
+            "publish meta lease set",
+        );
+
+        self.meta = Some(meta);
+        self.kind = LeaseSetKind::MetaLeaseSet;
+
+        if let Some(lease_set) = self.create_meta_lease_set(&self.lease_set.clone()) {
+            self.lease_set = lease_set;
+        }
+
+        if !self.unpublished {
+            self.get_closest_floodfills();
+        }
+
+        self
+    }
+
+    /// Update the key under which the local lease set is stored.
+    ///
+    /// The key of an encrypted lease set rotates daily and when that happens, the floodfills
+    /// closest to the previous key are no longer valid.
+    fn update_key(&mut self, key: Bytes) {
+        if self.key != key {
+            tracing::debug!(
+                target: LOG_TARGET,
+                local = %self.destination_id,
+                "lease set store key changed",
+            );
+
+            self.key = key;
+            self.floodfills.clear();
+        }
+    }
+
+    /// Encrypt `lease_set` and update the store key.
+    ///
+    /// Returns `None` if encryption hasn't been configured or if `lease_set` is malformed.
+    fn encrypt_lease_set(&mut self, lease_set: &[u8]) -> Option<Bytes> {
+        let LeaseSetEncryption {
+            signing_key,
+            secret,
+            client_auth,
+        } = self.encryption.as_ref()?;
+
+        let date = Dht::<R>::utc_date(R::time_since_epoch().as_secs());
+        let blinded_key = BlindedSigningKey::new(signing_key, &date, secret.as_deref());
+        let public_key = TryInto::<[u8; 32]>::try_into(signing_key.public().as_ref()).ok()?;
+
+        let builder = match client_auth {
+            None => EncryptedLeaseSetBuilder::new(lease_set),
+            Some(client_auth) =>
+                EncryptedLeaseSetBuilder::new(lease_set).with_client_auth(client_auth),
+        };
+
+        let Some(encrypted) = builder.build(&public_key, &blinded_key, R::rng()) else {
+            tracing::warn!(
+                target: LOG_TARGET,
+                local = %self.destination_id,
+                "failed to create encrypted lease set",
+            );
+            return None;
+        };
+
+        self.update_key(Bytes::from(blinded_key.public().store_key().to_vec()));
+
+        Some(Bytes::from(encrypted))
+    }
+
+    /// Create meta lease set from the header of `lease_set`.
+    ///
+    /// The entries of the meta lease set expire at the same time as `lease_set` so a new meta
+    /// lease set is published each time the local lease set is recreated.
+    ///
+    /// Returns `None` if meta lease set hasn't been configured or if `lease_set` is malformed.
+    fn create_meta_lease_set(&self, lease_set: &[u8]) -> Option<Bytes> {
+        let MetaLeaseSetConfig {
+            signing_key,
+            entries,
+        } = self.meta.as_ref()?;
+
+        let Some(lease_set) = LeaseSet2::parse(lease_set) else {
+            tracing::warn!(
+                target: LOG_TARGET,
+                local = %self.destination_id,
+                "failed to parse local lease set, cannot create meta lease set",
+            );
+            return None;
+        };
+
+        let published = R::time_since_epoch();
+        let expires = lease_set.expires().max(published);
+
+        Some(Bytes::from(
+            MetaLeaseSet {
+                header: LeaseSet2Header {
+                    destination: lease_set.header.destination,
+                    expires: (expires - published).as_secs() as u32,
+                    is_unpublished: false,
+                    offline_signature: lease_set.header.offline_signature,
+                    published: published.as_secs() as u32,
+                },
+                leases: entries
+                    .iter()
+                    .map(|(destination_id, cost)| MetaLease {
+                        key: Bytes::from(destination_id.to_vec()),
+                        kind: MetaLeaseKind::LeaseSet2,
+                        cost: *cost,
+                        expires,
+                    })
+                    .collect(),
+            }
+            .serialize(signing_key),
+        ))
+    }
+
+    /// Check if the offline signature of `lease_set` is about to expire and alert the user.
+    ///
+    /// Once the offline signature has expired, the lease set signed with the transient key is
+    /// rejected by remote routers and a new offline signature must be generated with the
+    /// long-term signing key of the destination.
+    fn check_offline_signature(&mut self, lease_set: &[u8]) {
+        let Ok((_, header)) = LeaseSet2Header::parse_frame(lease_set) else {
+            return;
+        };
+        let Some(offline_signature) = header.offline_signature else {
+            return;
+        };
+
+        if self
+            .offline_signature_alert
+            .as_ref()
+            .is_some_and(|alert| alert.elapsed() < OFFLINE_SIGNATURE_ALERT_INTERVAL)
+        {
+            return;
+        }
+
+        match offline_signature.valid_for::<R>() {
+            None => tracing::error!(
+                target: LOG_TARGET,
+                local = %self.destination_id,
+                expires = ?offline_signature.expires,
+                "offline signature has expired, destination is unreachable",
+            ),
+            Some(valid_for) if valid_for < OFFLINE_SIGNATURE_EXPIRATION_WARNING => tracing::warn!(
+                target: LOG_TARGET,
+                local = %self.destination_id,
+                ?valid_for,
+                "offline signature is about to expire",
+            ),
+            Some(_) => return,
         }
+
+        self.offline_signature_alert = Some(R::now());
     }
 
     fn get_closest_floodfills(&mut self) {
@@ -298,9 +672,58 @@ impl<R: Runtime> LeaseSetManager<R> {
     }
 
     /// Register new lease set for the [`Destination`].
+    ///
+    /// If encryption has been configured, the lease set is encrypted before it's published and if
+    /// meta lease set has been configured, the meta lease set is published instead.
     pub fn register_lease_set(&mut self, lease_set: Bytes) {
+        self.check_offline_signature(&lease_set);
+
+        let lease_set = match self.kind {
+            LeaseSetKind::EncryptedLeaseSet if self.encryption.is_some() =>
+                self.encrypt_lease_set(&lease_set),
+            LeaseSetKind::MetaLeaseSet if self.meta.is_some() =>
+                self.create_meta_lease_set(&lease_set),
+            _ => {
+                // lease set created by the client replaces any previous encrypted or meta lease
+                // set that was also created by the client
+                self.kind = LeaseSetKind::LeaseSet2;
+                self.update_key(Bytes::from(self.destination_id.to_vec()));
+
+                Some(lease_set)
+            }
+        };
+
+        let Some(lease_set) = lease_set else {
+            return;
+        };
         self.lease_set = lease_set;
 
+        self.publish_lease_set();
+    }
+
+    /// Register new encrypted lease set for the [`Destination`].
+    ///
+    /// `lease_set` has been encrypted and signed by the client and it's stored under `key`.
+    pub fn register_encrypted_lease_set(&mut self, key: Bytes, lease_set: Bytes) {
+        self.lease_set = lease_set;
+        self.kind = LeaseSetKind::EncryptedLeaseSet;
+        self.update_key(key);
+
+        self.publish_lease_set();
+    }
+
+    /// Register new meta lease set for the [`Destination`].
+    ///
+    /// `lease_set` has been created and signed by the client.
+    pub fn register_meta_lease_set(&mut self, lease_set: Bytes) {
+        self.lease_set = lease_set;
+        self.kind = LeaseSetKind::MetaLeaseSet;
+
+        self.publish_lease_set();
+    }
+
+    /// Start publishing the local lease set if [`LeaseSetManager`] was waiting for it.
+    fn publish_lease_set(&mut self) {
         if self.unpublished {
             return;
         }
@@ -548,8 +971,16 @@ impl<R: Runtime> LeaseSetManager<R> {
 
         let message = DatabaseStoreBuilder::new(
             self.key.clone(),
-            DatabaseStoreKind::LeaseSet2 {
-                lease_set: self.lease_set.clone(),
+            match self.kind {
+                LeaseSetKind::LeaseSet2 => DatabaseStoreKind::LeaseSet2 {
+                    lease_set: self.lease_set.clone(),
+                },
+                LeaseSetKind::EncryptedLeaseSet => DatabaseStoreKind::EncryptedLeaseSet {
+                    lease_set: self.lease_set.clone(),
+                },
+                LeaseSetKind::MetaLeaseSet => DatabaseStoreKind::MetaLeaseSet {
+                    lease_set: self.lease_set.clone(),
+                },
             },
         )
         .with_reply_type(ReplyType::Tunnel {
@@ -987,13 +1418,16 @@ impl<R: Runtime> Future for LeaseSetManager<R> {
 mod tests {
     use super::*;
     use crate::{
-        crypto::StaticPrivateKey,
+        crypto::{base32_encode, StaticPrivateKey},
         i2np::{
-            database::{lookup::DatabaseLookup, store::DatabaseStore},
+            database::{
+                lookup::DatabaseLookup,
+                store::{DatabaseStore, DatabaseStorePayload},
+            },
             Message,
         },
         netdb::NetDbAction,
-        primitives::{LeaseSet2, RouterInfo, RouterInfoBuilder},
+        primitives::{LeaseSet2, OfflineSignature, RouterInfo, RouterInfoBuilder},
         runtime::mock::MockRuntime,
         tunnel::{
             DeliveryInstructions as GarlicDeliveryInstructions, GarlicHandler, TunnelMessage,
@@ -2872,6 +3306,290 @@ mod tests {
         assert_eq!(manager.floodfills.len(), NUM_CLOSEST_FLOODFILLS);
     }
 
+    #[tokio::test]
+    async fn encrypted_lease_set_published() {
+        let (tp_handle, tm_rx, _tp_tx, _srx) = TunnelPoolHandle::create();
+        let sender = tp_handle.sender();
+        let (netdb_handle, netdb_rx) = NetDbHandle::create();
+        let noise_ctx = NoiseContext::new(
+            StaticPrivateKey::random(MockRuntime::rng()),
+            Bytes::from(RouterId::random().to_vec()),
+        );
+        let (lease_set, signing_key) = LeaseSet2::random();
+        let tunnels = lease_set.leases.clone();
+        let destination_id = lease_set.header.destination.id();
+        let serialized = lease_set.serialize(&signing_key);
+        let public_key =
+            TryInto::<[u8; 32]>::try_into(signing_key.public().as_ref()).expect("to succeed");
+        let mut manager = LeaseSetManager::<MockRuntime>::new(
+            tunnels,
+            destination_id.clone(),
+            sender,
+            3usize,
+            netdb_handle,
+            noise_ctx,
+            ProfileStorage::new(&[], &[]),
+            false,
+            Bytes::from(serialized),
+        )
+        .with_encryption(LeaseSetEncryption {
+            signing_key: signing_key.clone(),
+            secret: Some(String::from("secret")),
+            client_auth: None,
+        });
+
+        // encrypted lease set is stored under the blinded public key
+        let date = Dht::<MockRuntime>::utc_date(MockRuntime::time_since_epoch().as_secs());
+        let blinded_key = BlindedSigningKey::new(&signing_key, &date, Some("secret"));
+        let store_key = Bytes::from(blinded_key.public().store_key().to_vec());
+        assert_eq!(manager.key, store_key);
+
+        let floodfills = (0..3)
+            .map(|_| {
+                (
+                    RouterId::random(),
+                    StaticPrivateKey::random(MockRuntime::rng()),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        loop {
+            tokio::select! {
+                _ = &mut manager => {}
+                event = netdb_rx.recv() => match event.unwrap() {
+                    NetDbAction::GetClosestFloodfills { tx, .. } => {
+                        // the first query was started with the unblinded key and is stale
+                        let _ = tx.send(
+                            floodfills
+                                .iter()
+                                .map(|(router_id, key)| (router_id.clone(), key.public()))
+                                .collect(),
+                        );
+                    }
+                    _ => panic!("invalid action received"),
+                },
+                event = tm_rx.recv() => match event.unwrap() {
+                    TunnelMessage::RouterDeliveryViaRoute {
+                        outbound_tunnel: None,
+                        router_id,
+                        message,
+                    } => {
+                        let message = Message::parse_standard(&message).unwrap();
+                        let static_key = floodfills.get(&router_id).unwrap();
+
+                        let mut garlic = GarlicHandler::<MockRuntime>::new(
+                            NoiseContext::new(static_key.clone(), Bytes::from(router_id.to_vec())),
+                            MockRuntime::register_metrics(vec![], None),
+                        );
+                        let GarlicDeliveryInstructions::Local { message } = garlic
+                            .handle_message(message)
+                            .unwrap()
+                            .filter(|message| {
+                                std::matches!(message, GarlicDeliveryInstructions::Local { .. })
+                            })
+                            .collect::<VecDeque<_>>()
+                            .pop_front()
+                            .expect("to exist")
+                        else {
+                            panic!("invalid type");
+                        };
+                        assert_eq!(message.message_type, MessageType::DatabaseStore);
+
+                        let DatabaseStore { key, payload, .. } =
+                            DatabaseStore::<MockRuntime>::parse(&message.payload).unwrap();
+                        assert_eq!(key, store_key);
+
+                        let DatabaseStorePayload::EncryptedLeaseSet { lease_set } = payload else {
+                            panic!("invalid payload");
+                        };
+                        assert_eq!(lease_set.blinded_public_key(), blinded_key.public());
+
+                        let lease_set = lease_set.decrypt(&public_key, None).unwrap();
+                        assert_eq!(lease_set.header.destination.id(), destination_id);
+                        break;
+                    }
+                    _ => panic!("unexpected tunnel message"),
+                },
+                _ = tokio::time::sleep(Duration::from_secs(30)) => panic!("timeout"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn expiring_offline_signature_alerted() {
+        let (tp_handle, _tm_rx, _tp_tx, _srx) = TunnelPoolHandle::create();
+        let (netdb_handle, _netdb_rx) = NetDbHandle::create();
+        let noise_ctx = NoiseContext::new(
+            StaticPrivateKey::random(MockRuntime::rng()),
+            Bytes::from(RouterId::random().to_vec()),
+        );
+        let (mut lease_set, signing_key) = LeaseSet2::random();
+        let transient_key = SigningPrivateKey::random(MockRuntime::rng());
+        let destination_id = lease_set.header.destination.id();
+
+        // offline signature that is valid for a long time doesn't cause an alert
+        lease_set.header.offline_signature = Some(OfflineSignature::new(
+            MockRuntime::time_since_epoch() + Duration::from_secs(30 * 24 * 60 * 60),
+            transient_key.public(),
+            &signing_key,
+        ));
+        let mut manager = LeaseSetManager::<MockRuntime>::new(
+            lease_set.leases.clone(),
+            destination_id,
+            tp_handle.sender(),
+            3usize,
+            netdb_handle,
+            noise_ctx,
+            ProfileStorage::new(&[], &[]),
+            true,
+            Bytes::from(lease_set.clone().serialize(&transient_key)),
+        );
+        assert!(manager.offline_signature_alert.is_none());
+
+        // offline signature which expires in a day causes an alert
+        lease_set.header.offline_signature = Some(OfflineSignature::new(
+            MockRuntime::time_since_epoch() + Duration::from_secs(24 * 60 * 60),
+            transient_key.public(),
+            &signing_key,
+        ));
+        manager.register_lease_set(Bytes::from(lease_set.clone().serialize(&transient_key)));
+        assert!(manager.offline_signature_alert.is_some());
+
+        // the alert is rate-limited
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        manager.register_lease_set(Bytes::from(lease_set.serialize(&transient_key)));
+        assert!(manager.offline_signature_alert.unwrap().elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn meta_lease_set_published() {
+        let (tp_handle, tm_rx, _tp_tx, _srx) = TunnelPoolHandle::create();
+        let sender = tp_handle.sender();
+        let (netdb_handle, netdb_rx) = NetDbHandle::create();
+        let noise_ctx = NoiseContext::new(
+            StaticPrivateKey::random(MockRuntime::rng()),
+            Bytes::from(RouterId::random().to_vec()),
+        );
+        let (lease_set, signing_key) = LeaseSet2::random();
+        let tunnels = lease_set.leases.clone();
+        let destination_id = lease_set.header.destination.id();
+        let serialized = lease_set.serialize(&signing_key);
+
+        // create meta lease set configuration which lists two member destinations
+        let members = (0..2).map(|_| DestinationId::random()).collect::<Vec<_>>();
+        let meta = MetaLeaseSetConfig::from_options(
+            &HashMap::from_iter([
+                (String::from("i2cp.leaseSetType"), String::from("7")),
+                (
+                    String::from("i2cp.metaLeaseSetEntries"),
+                    format!(
+                        "{}.b32.i2p:5,{}",
+                        base32_encode(members[0].to_vec()),
+                        base32_encode(members[1].to_vec())
+                    ),
+                ),
+            ]),
+            &signing_key,
+        )
+        .unwrap();
+        assert_eq!(
+            meta.entries,
+            vec![(members[0].clone(), 5), (members[1].clone(), 0)]
+        );
+
+        let mut manager = LeaseSetManager::<MockRuntime>::new(
+            tunnels,
+            destination_id.clone(),
+            sender,
+            3usize,
+            netdb_handle,
+            noise_ctx,
+            ProfileStorage::new(&[], &[]),
+            false,
+            Bytes::from(serialized),
+        )
+        .with_meta_lease_set(meta);
+
+        // meta lease set is stored under the key of the destination
+        let store_key = Bytes::from(destination_id.to_vec());
+        assert_eq!(manager.key, store_key);
+
+        let floodfills = (0..3)
+            .map(|_| {
+                (
+                    RouterId::random(),
+                    StaticPrivateKey::random(MockRuntime::rng()),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        loop {
+            tokio::select! {
+                _ = &mut manager => {}
+                event = netdb_rx.recv() => match event.unwrap() {
+                    NetDbAction::GetClosestFloodfills { tx, .. } => {
+                        let _ = tx.send(
+                            floodfills
+                                .iter()
+                                .map(|(router_id, key)| (router_id.clone(), key.public()))
+                                .collect(),
+                        );
+                    }
+                    _ => panic!("invalid action received"),
+                },
+                event = tm_rx.recv() => match event.unwrap() {
+                    TunnelMessage::RouterDeliveryViaRoute {
+                        outbound_tunnel: None,
+                        router_id,
+                        message,
+                    } => {
+                        let message = Message::parse_standard(&message).unwrap();
+                        let static_key = floodfills.get(&router_id).unwrap();
+
+                        let mut garlic = GarlicHandler::<MockRuntime>::new(
+                            NoiseContext::new(static_key.clone(), Bytes::from(router_id.to_vec())),
+                            MockRuntime::register_metrics(vec![], None),
+                        );
+                        let GarlicDeliveryInstructions::Local { message } = garlic
+                            .handle_message(message)
+                            .unwrap()
+                            .filter(|message| {
+                                std::matches!(message, GarlicDeliveryInstructions::Local { .. })
+                            })
+                            .collect::<VecDeque<_>>()
+                            .pop_front()
+                            .expect("to exist")
+                        else {
+                            panic!("invalid type");
+                        };
+                        assert_eq!(message.message_type, MessageType::DatabaseStore);
+
+                        let DatabaseStore { key, payload, .. } =
+                            DatabaseStore::<MockRuntime>::parse(&message.payload).unwrap();
+                        assert_eq!(key, store_key);
+
+                        let DatabaseStorePayload::MetaLeaseSet { lease_set } = payload else {
+                            panic!("invalid payload");
+                        };
+                        assert_eq!(lease_set.header.destination.id(), destination_id);
+                        assert_eq!(
+                            lease_set
+                                .leases
+                                .iter()
+                                .map(|lease| (DestinationId::from(lease.key.clone()), lease.cost))
+                                .collect::<Vec<_>>(),
+                            vec![(members[0].clone(), 5), (members[1].clone(), 0)],
+                        );
+                        assert!(!lease_set.is_expired::<MockRuntime>());
+                        break;
+                    }
+                    _ => panic!("unexpected tunnel message"),
+                },
+                _ = tokio::time::sleep(Duration::from_secs(30)) => panic!("timeout"),
+            }
+        }
+    }
+
     #[tokio::test]
     async fn new_lease_set_published() {
         let (tp_handle, tm_rx, _tp_tx, _srx) = TunnelPoolHandle::create();
@@ -34,8 +34,8 @@ use crate::{
         delivery_status::DeliveryStatus,
         Message, MessageBuilder, MessageType, I2NP_MESSAGE_EXPIRATION,
     },
-    netdb::NetDbHandle,
-    primitives::{DestinationId, Lease, LeaseSet2, TunnelId},
+    netdb::{Dht, NetDbHandle},
+    primitives::{BlindedAddress, ClientKey, DestinationId, Lease, LeaseSet2, TunnelId},
     profile::ProfileStorage,
     runtime::{JoinSet, Runtime},
     tunnel::{NoiseContext, TunnelPoolEvent, TunnelPoolHandle},
@@ -46,7 +46,7 @@ use futures::{FutureExt, Stream, StreamExt};
 use hashbrown::{HashMap, HashSet};
 use rand_core::RngCore;
 
-use alloc::{collections::VecDeque, vec::Vec};
+use alloc::{collections::VecDeque, string::String, vec::Vec};
 use core::{
     mem,
     pin::Pin,
@@ -59,6 +59,8 @@ mod lease_set;
 pub mod routing_path;
 pub mod session;
 
+pub use lease_set::{LeaseSetEncryption, LeaseSetKind, MetaLeaseSetConfig};
+
 /// Logging target for the file.
 const LOG_TARGET: &str = "emissary::destination";
 
@@ -186,8 +188,29 @@ pub struct DestinationContext {
     expiring_leases: HashMap<TunnelId, Lease>,
 }
 
+/// Remote destination identified by a blinded address.
+struct BlindedDestination {
+    /// Blinded address of the destination.
+    address: BlindedAddress,
+
+    /// Lookup password, if required.
+    secret: Option<String>,
+
+    /// Client key, if per-client authentication is required.
+    client_key: Option<ClientKey>,
+
+    /// ID of the destination, known after its encrypted lease set has been decrypted.
+    destination_id: Option<DestinationId>,
+}
+
 /// Client destination.
 pub struct Destination<R: Runtime> {
+    /// Blinded remote destinations, indexed by the IDs of their blinded addresses.
+    blinded_destinations: HashMap<DestinationId, BlindedDestination>,
+
+    /// Client key used for blinded destinations that have no blinding info of their own.
+    client_key: Option<ClientKey>,
+
     /// Destination ID of the client.
     destination_id: DestinationId,
 
@@ -218,6 +241,9 @@ pub struct Destination<R: Runtime> {
     /// Routing path manager.
     routing_path_manager: RoutingPathManager<R>,
 
+    /// IDs of blinded addresses, indexed by the IDs of the destinations they resolved to.
+    unblinded_destinations: HashMap<DestinationId, DestinationId>,
+
     /// Session manager.
     session_manager: SessionManager<R>,
 
@@ -245,6 +271,8 @@ impl<R: Runtime> Destination<R> {
         profile_storage: ProfileStorage<R>,
     ) -> Self {
         Self {
+            blinded_destinations: HashMap::new(),
+            client_key: None,
             destination_id: destination_id.clone(),
             lease_set: lease_set.clone(),
             lease_set_manager: LeaseSetManager::new(
@@ -266,10 +294,96 @@ impl<R: Runtime> Destination<R> {
             routing_path_manager: RoutingPathManager::new(destination_id.clone(), outbound_tunnels),
             session_manager: SessionManager::new(destination_id, private_key, lease_set),
             tunnel_pool_handle,
+            unblinded_destinations: HashMap::new(),
             waker: None,
         }
     }
 
+    /// Publish the lease set of the [`Destination`] as an encrypted lease set.
+    pub fn with_encrypted_lease_set(mut self, encryption: LeaseSetEncryption) -> Self {
+        self.lease_set_manager = self.lease_set_manager.with_encryption(encryption);
+        self
+    }
+
+    /// Publish a meta lease set, listing other destinations, instead of the lease set of
+    /// [`Destination`].
+    pub fn with_meta_lease_set(mut self, meta: MetaLeaseSetConfig) -> Self {
+        self.lease_set_manager = self.lease_set_manager.with_meta_lease_set(meta);
+        self
+    }
+
+    /// Specify client key used to decrypt encrypted lease sets with per-client authentication.
+    pub fn with_client_key(mut self, client_key: ClientKey) -> Self {
+        self.client_key = Some(client_key);
+        self
+    }
+
+    /// Register blinding info for `address`.
+    ///
+    /// `secret` is the lookup password of the encrypted lease set and `client_key` the key used
+    /// for per-client authentication. If `client_key` is `None`, the client key of [`Destination`]
+    /// is used, if it was specified.
+    pub fn register_blinding_info(
+        &mut self,
+        address: BlindedAddress,
+        secret: Option<String>,
+        client_key: Option<ClientKey>,
+    ) {
+        let alias = address.id();
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            local = %self.destination_id,
+            %alias,
+            secret = ?secret.is_some(),
+            client_key = ?client_key.is_some(),
+            "register blinding info",
+        );
+
+        match self.blinded_destinations.get_mut(&alias) {
+            Some(destination) => {
+                destination.secret = secret;
+                destination.client_key = client_key;
+            }
+            None => {
+                self.blinded_destinations.insert(
+                    alias,
+                    BlindedDestination {
+                        address,
+                        secret,
+                        client_key,
+                        destination_id: None,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Look up lease set status of a remote destination identified by a blinded `address`.
+    ///
+    /// Blinded destinations are identified by [`BlindedAddress::id()`] until their encrypted lease
+    /// set has been found and decrypted. The result of the query is reported using that ID and
+    /// once the lease set has been found, the actual ID of the remote destination can be resolved
+    /// with [`Destination::unblind()`].
+    ///
+    /// See [`Destination::query_lease_set()`] for more details.
+    pub fn query_blinded_lease_set(&mut self, address: &BlindedAddress) -> LeaseSetStatus {
+        let alias = address.id();
+
+        if !self.blinded_destinations.contains_key(&alias) {
+            self.register_blinding_info(address.clone(), None, None);
+        }
+
+        self.query_lease_set(&alias)
+    }
+
+    /// Get the ID of the destination `alias`, the ID of a blinded address, resolved to.
+    ///
+    /// Returns `None` if `alias` is not a blinded address or if its lease set hasn't been found.
+    pub fn unblind(&self, alias: &DestinationId) -> Option<DestinationId> {
+        self.blinded_destinations.get(alias)?.destination_id.clone()
+    }
+
     /// Look up lease set status of remote destination.
     ///
     /// Before sending a message to remote, the caller must ensure [`Destination`] holds a valid
@@ -288,7 +402,9 @@ impl<R: Runtime> Destination<R> {
             return LeaseSetStatus::Pending;
         }
 
-        if let Some(context) = self.remote_destinations.get(destination_id) {
+        let remote_id = self.unblind(destination_id).unwrap_or(destination_id.clone());
+
+        if let Some(context) = self.remote_destinations.get(&remote_id) {
             if !context.lease_set.is_expired::<R>() {
                 return LeaseSetStatus::Found;
             }
@@ -310,6 +426,88 @@ impl<R: Runtime> Destination<R> {
         let destination_id = destination_id.clone();
 
         self.pending_queries.insert(destination_id.clone());
+
+        // lease sets of blinded destinations are always queried using their blinded addresses,
+        // even after the actual ID of the destination has been resolved
+        let blinded = self.blinded_destinations.get(&destination_id).or_else(|| {
+            self.unblinded_destinations
+                .get(&destination_id)
+                .and_then(|alias| self.blinded_destinations.get(alias))
+        });
+
+        if let Some(BlindedDestination {
+            address,
+            secret,
+            client_key,
+            ..
+        }) = blinded
+        {
+            let address = address.clone();
+            let secret = secret.clone();
+            let client_key = client_key.clone().or_else(|| self.client_key.clone());
+
+            self.query_futures.push(async move {
+                for _ in 0..NUM_QUERY_RETRIES {
+                    let date = Dht::<R>::utc_date(R::time_since_epoch().as_secs());
+                    let Some(blinded_public_key) = address.blind(&date, secret.as_deref()) else {
+                        return (destination_id, Err(QueryError::Malformed));
+                    };
+
+                    let Ok(rx) = handle.query_encrypted_lease_set(Bytes::from(
+                        blinded_public_key.store_key().to_vec(),
+                    )) else {
+                        R::delay(NETDB_BACKOFF_TIMEOUT).await;
+                        continue;
+                    };
+
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        %destination_id,
+                        "encrypted lease set query started",
+                    );
+
+                    let lease_set = match rx.await {
+                        Err(_) => return (destination_id, Err(QueryError::Timeout)),
+                        Ok(Err(error)) => return (destination_id, Err(error)),
+                        Ok(Ok(lease_set)) => lease_set,
+                    };
+
+                    if lease_set.blinded_public_key() != &blinded_public_key {
+                        tracing::warn!(
+                            target: LOG_TARGET,
+                            %destination_id,
+                            "encrypted lease set signed with unexpected blinded key",
+                        );
+                        return (destination_id, Err(QueryError::Malformed));
+                    }
+
+                    return match lease_set.decrypt(address.public_key(), client_key.as_ref()) {
+                        Some(lease_set) => (destination_id, Ok(lease_set)),
+                        None => {
+                            tracing::warn!(
+                                target: LOG_TARGET,
+                                %destination_id,
+                                secret_required = ?address.secret_required(),
+                                client_auth_required = ?address.client_auth_required(),
+                                "failed to decrypt encrypted lease set",
+                            );
+                            (destination_id, Err(QueryError::Malformed))
+                        }
+                    };
+                }
+
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    %destination_id,
+                    "failed to start encrypted lease set query after {NUM_QUERY_RETRIES} retries",
+                );
+
+                (destination_id, Err(QueryError::RetryFailure))
+            });
+
+            return LeaseSetStatus::NotFound;
+        }
+
         self.query_futures.push(async move {
             for _ in 0..NUM_QUERY_RETRIES {
                 let Ok(rx) = handle.query_lease_set(Bytes::from(destination_id.to_vec())) else {
@@ -347,7 +545,9 @@ impl<R: Runtime> Destination<R> {
     /// Caller must calle [`Destination::query_lease_set()`] and get a return value of
     /// [`LeaseSetStatus::Found`] before calling this function.
     pub fn lease_set(&self, destination_id: &DestinationId) -> &LeaseSet2 {
-        &self.remote_destinations.get(destination_id).expect("to exist").lease_set
+        let destination_id = self.unblind(destination_id).unwrap_or(destination_id.clone());
+
+        &self.remote_destinations.get(&destination_id).expect("to exist").lease_set
     }
 
     /// Send encrypted `message` to remote `destination`.
@@ -552,7 +752,9 @@ impl<R: Runtime> Destination<R> {
                     })?;
 
                 match payload {
-                    DatabaseStorePayload::LeaseSet2 { .. } => {
+                    DatabaseStorePayload::LeaseSet2 { .. }
+                    | DatabaseStorePayload::EncryptedLeaseSet { .. }
+                    | DatabaseStorePayload::MetaLeaseSet { .. } => {
                         // self.lease_set_manager.register_database_store(
                         //     key.clone(),
                         //     DatabaseStore::<R>::extract_raw_lease_set(&message.payload),
@@ -731,6 +933,20 @@ impl<R: Runtime> Destination<R> {
         self.lease_set_manager.register_lease_set(lease_set.clone());
     }
 
+    /// Attempt to publish new encrypted lease set to `NetDb`.
+    ///
+    /// `lease_set` has been encrypted and signed by the client and it's stored under `key`.
+    pub fn publish_encrypted_lease_set(&mut self, key: Bytes, lease_set: Bytes) {
+        self.lease_set_manager.register_encrypted_lease_set(key, lease_set);
+    }
+
+    /// Attempt to publish new meta lease set to `NetDb`.
+    ///
+    /// `lease_set` has been created and signed by the client.
+    pub fn publish_meta_lease_set(&mut self, lease_set: Bytes) {
+        self.lease_set_manager.register_meta_lease_set(lease_set);
+    }
+
     /// Shutdown session by shutting down the tunnel pool.
     pub fn shutdown(&mut self) {
         self.tunnel_pool_handle.shutdown();
@@ -860,6 +1076,23 @@ impl<R: Runtime> Stream for Destination<R> {
                     }
                     Ok(lease_set) => {
                         self.pending_queries.remove(&destination_id);
+
+                        // if the query was made using a blinded address, the actual ID of the
+                        // remote destination is known only after the lease set was decrypted
+                        let query_id = destination_id;
+                        let destination_id = match self.blinded_destinations.get_mut(&query_id) {
+                            None => query_id.clone(),
+                            Some(blinded) => {
+                                let destination_id = lease_set.header.destination.id();
+
+                                blinded.destination_id = Some(destination_id.clone());
+                                self.unblinded_destinations
+                                    .insert(destination_id.clone(), query_id.clone());
+
+                                destination_id
+                            }
+                        };
+
                         self.session_manager.add_remote_destination(
                             destination_id.clone(),
                             lease_set.public_keys[0].clone(),
@@ -906,7 +1139,7 @@ impl<R: Runtime> Stream for Destination<R> {
                         }
 
                         return Poll::Ready(Some(DestinationEvent::LeaseSetFound {
-                            destination_id,
+                            destination_id: query_id,
                         }));
                     }
                 }
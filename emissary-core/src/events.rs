@@ -23,6 +23,7 @@ use thingbuf::mpsc::{channel, Receiver, Sender};
 
 use alloc::{string::String, sync::Arc, vec::Vec};
 use core::{
+    fmt,
     future::Future,
     mem,
     pin::Pin,
@@ -84,6 +85,9 @@ pub(crate) struct EventHandle<R: Runtime> {
     /// Cumulative bandwidth used by all transit tunnels.
     transit_bandwidth: Arc<AtomicUsize>,
 
+    /// Firewall status of the router.
+    firewall_status: Arc<AtomicUsize>,
+
     /// Update interval.
     update_interval: Duration,
 
@@ -101,6 +105,7 @@ impl<R: Runtime> Clone for EventHandle<R> {
             num_tunnel_build_failures: Arc::clone(&self.num_tunnel_build_failures),
             num_tunnels_built: Arc::clone(&self.num_tunnels_built),
             transit_bandwidth: Arc::clone(&self.transit_bandwidth),
+            firewall_status: Arc::clone(&self.firewall_status),
             update_interval: self.update_interval,
             timer: Some(R::timer(self.update_interval)),
         }
@@ -140,6 +145,14 @@ impl<R: Runtime> EventHandle<R> {
         self.num_connected_routers.store(num_connected_routers, Ordering::Release);
     }
 
+    /// Update firewall status.
+    ///
+    /// [`AtomicUsize::store()`] is used because the status is updated only by
+    /// `TransportManager`.
+    pub(crate) fn firewall_status(&self, status: FirewallStatus) {
+        self.firewall_status.store(status.as_usize(), Ordering::Release);
+    }
+
     /// Update tunnel build success/failure status.
     ///
     /// [`AtomicUsize::fetch_add()`] is used because each tunnel pool keeps track of its own
@@ -150,6 +163,27 @@ impl<R: Runtime> EventHandle<R> {
             .fetch_add(num_tunnel_build_failures, Ordering::Release);
     }
 
+    /// Get current status of the transit tunnel, transport and tunnel subsystems.
+    pub(crate) fn snapshot(&self) -> (TransitTunnelStatus, TransportStatus, TunnelStatus) {
+        (
+            TransitTunnelStatus {
+                num_tunnels: self.num_transit_tunnels.load(Ordering::Acquire),
+                bandwidth: self.transit_bandwidth.load(Ordering::Acquire),
+            },
+            TransportStatus {
+                num_connected_routers: self.num_connected_routers.load(Ordering::Acquire),
+                bandwidth: self.bandwidth.load(Ordering::Acquire),
+                firewall_status: FirewallStatus::from_usize(
+                    self.firewall_status.load(Ordering::Acquire),
+                ),
+            },
+            TunnelStatus {
+                num_tunnels_built: self.num_tunnels_built.load(Ordering::Acquire),
+                num_tunnel_build_failures: self.num_tunnel_build_failures.load(Ordering::Acquire),
+            },
+        )
+    }
+
     // TODO:
     pub(crate) fn server_destination_started(&self, name: String, address: String) {
         let _ = self
@@ -210,6 +244,56 @@ pub struct TransitTunnelStatus {
     pub bandwidth: usize,
 }
 
+/// Firewall status of the router, as determined by SSU2 peer tests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FirewallStatus {
+    /// Status is not known.
+    #[default]
+    Unknown,
+
+    /// Router is reachable.
+    Ok,
+
+    /// Router is behind a firewall and not reachable.
+    Firewalled,
+
+    /// Router is behind a symmetric NAT and not reachable.
+    SymmetricNat,
+}
+
+impl FirewallStatus {
+    /// Convert [`FirewallStatus`] into `usize`.
+    fn as_usize(&self) -> usize {
+        match self {
+            Self::Unknown => 0usize,
+            Self::Ok => 1usize,
+            Self::Firewalled => 2usize,
+            Self::SymmetricNat => 3usize,
+        }
+    }
+
+    /// Convert `usize` into [`FirewallStatus`].
+    fn from_usize(value: usize) -> Self {
+        match value {
+            1usize => Self::Ok,
+            2usize => Self::Firewalled,
+            3usize => Self::SymmetricNat,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for FirewallStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown => write!(f, "Unknown"),
+            Self::Ok => write!(f, "Ok"),
+            Self::Firewalled => write!(f, "Firewalled"),
+            Self::SymmetricNat => write!(f, "Symmetric NAT"),
+        }
+    }
+}
+
 /// Transport status.
 #[derive(Debug, Clone, Default)]
 pub struct TransportStatus {
@@ -218,6 +302,9 @@ pub struct TransportStatus {
 
     /// Cumulative bandwith consumed by all transports.
     pub bandwidth: usize,
+
+    /// Firewall status of the router.
+    pub firewall_status: FirewallStatus,
 }
 
 /// Tunnel status.
@@ -310,6 +397,7 @@ impl<R: Runtime> EventManager<R> {
             num_tunnel_build_failures: Default::default(),
             num_tunnels_built: Default::default(),
             transit_bandwidth: Default::default(),
+            firewall_status: Default::default(),
             update_interval,
             timer: None,
         };
@@ -326,6 +414,7 @@ impl<R: Runtime> EventManager<R> {
                     num_tunnel_build_failures: Arc::clone(&handle.num_tunnel_build_failures),
                     num_tunnels_built: Arc::clone(&handle.num_tunnels_built),
                     transit_bandwidth: Arc::clone(&handle.transit_bandwidth),
+                    firewall_status: Arc::clone(&handle.firewall_status),
                     update_interval,
                     timer: None,
                 },
@@ -378,25 +467,12 @@ impl<R: Runtime> Future for EventManager<R> {
             let server_destinations = mem::take(&mut self.pending_server_updates);
             let client_destinations = mem::take(&mut self.pending_client_updates);
 
+            let (transit, transport, tunnel) = self.handle.snapshot();
+
             let _ = self.status_tx.try_send(Event::RouterStatus {
-                transit: TransitTunnelStatus {
-                    num_tunnels: self.handle.num_transit_tunnels.load(Ordering::Acquire),
-                    bandwidth: self.handle.transit_bandwidth.load(Ordering::Acquire),
-                },
-                transport: TransportStatus {
-                    num_connected_routers: self
-                        .handle
-                        .num_connected_routers
-                        .load(Ordering::Acquire),
-                    bandwidth: self.handle.bandwidth.load(Ordering::Acquire),
-                },
-                tunnel: TunnelStatus {
-                    num_tunnels_built: self.handle.num_tunnels_built.load(Ordering::Acquire),
-                    num_tunnel_build_failures: self
-                        .handle
-                        .num_tunnel_build_failures
-                        .load(Ordering::Acquire),
-                },
+                transit,
+                transport,
+                tunnel,
                 server_destinations,
                 client_destinations,
             });
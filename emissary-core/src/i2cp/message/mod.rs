@@ -17,9 +17,13 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    crypto::StaticPrivateKey,
+    crypto::{SigningPublicKey, StaticPrivateKey},
+    destination::LeaseSetKind,
     i2cp::payload::I2cpParameters,
-    primitives::{Date, Destination, LeaseSet2, Mapping, Str},
+    primitives::{
+        BlindedAddress, ClientKey, Date, Destination, EncryptedLeaseSet, LeaseSet2, Mapping,
+        MetaLeaseSet, Str,
+    },
 };
 
 use bytes::Bytes;
@@ -56,6 +60,15 @@ pub const I2CP_HEADER_SIZE: usize = 5;
 /// Signature length.
 const SIGNATURE_LEN: usize = 64usize;
 
+/// Signature type of EdDSA-SHA512-Ed25519.
+const SIG_TYPE_EDDSA_SHA512_ED25519: u16 = 7u16;
+
+/// Per-client authentication is required to decrypt the lease set.
+const BLINDING_INFO_PER_CLIENT: u8 = 0x01;
+
+/// Lookup password is required to find the lease set.
+const BLINDING_INFO_SECRET: u8 = 0x10;
+
 /// Session ID.
 #[derive(Debug)]
 pub enum SessionId {
@@ -267,8 +280,20 @@ pub enum Message {
     /// Bandwidth limit
     BandwidthLimits,
 
-    /// Binding info.
-    BlindingInfo,
+    /// Blinding info.
+    BlindingInfo {
+        /// Session ID.
+        session_id: SessionId,
+
+        /// Blinded address of the remote destination.
+        address: BlindedAddress,
+
+        /// Client key used to decrypt the encrypted lease set, if any.
+        client_key: Option<ClientKey>,
+
+        /// Lookup password, if any.
+        secret: Option<Str>,
+    },
 
     /// Create `LeaseSet`.
     CreateLeaseSet,
@@ -278,14 +303,17 @@ pub enum Message {
         /// Session ID.
         session_id: SessionId,
 
-        /// SHA256 of the `Destination`.
+        /// SHA256 of the `Destination` or of the blinded public key if the lease set is encrypted.
         ///
         /// `leaseset` needs to be stored in `key` in `NetDb`.
         key: Bytes,
 
-        /// Serialized `LeaseSet2`.
+        /// Serialized `LeaseSet2`, `EncryptedLeaseSet` or `MetaLeaseSet`.
         leaseset: Bytes,
 
+        /// Kind of `leaseset`.
+        kind: LeaseSetKind,
+
         /// Encryption private keys.
         private_keys: Vec<StaticPrivateKey>,
     },
@@ -528,7 +556,7 @@ impl Message {
         let (rest, session_id) = be_u16::<_, ()>(input.as_ref()).ok()?;
         let (rest, kind) = be_u8::<_, ()>(rest).ok()?;
 
-        let (rest, key, leaseset) = match kind {
+        let (rest, key, leaseset, kind) = match kind {
             3 => {
                 // parse `LeaseSet2` from input to verify it's valid and supports correct crypto
                 //
@@ -546,6 +574,19 @@ impl Message {
                     rest,
                     Bytes::from(parsed.header.destination.id().to_vec()),
                     Bytes::from(input.as_ref()[3..(input.as_ref().len() - rest.len())].to_vec()),
+                    LeaseSetKind::LeaseSet2,
+                )
+            }
+            5 => {
+                // encrypted lease set has been created and signed by the client so, like
+                // `LeaseSet2`, it's published unmodified
+                let (rest, parsed) = EncryptedLeaseSet::parse_frame(rest).ok()?;
+
+                (
+                    rest,
+                    Bytes::from(parsed.blinded_public_key().store_key().to_vec()),
+                    Bytes::from(input.as_ref()[3..(input.as_ref().len() - rest.len())].to_vec()),
+                    LeaseSetKind::EncryptedLeaseSet,
                 )
             }
             1 => {
@@ -555,19 +596,17 @@ impl Message {
                 );
                 return None;
             }
-            5 => {
-                tracing::warn!(
-                    target: LOG_TARGET,
-                    "encrypted leasesets not supported",
-                );
-                return None;
-            }
             7 => {
-                tracing::warn!(
-                    target: LOG_TARGET,
-                    "meta leasesets not supported",
-                );
-                return None;
+                // meta lease set has been created and signed by the client and it's published
+                // unmodified under the key of the destination
+                let (rest, parsed) = MetaLeaseSet::parse_frame(rest).ok()?;
+
+                (
+                    rest,
+                    Bytes::from(parsed.header.destination.id().to_vec()),
+                    Bytes::from(input.as_ref()[3..(input.as_ref().len() - rest.len())].to_vec()),
+                    LeaseSetKind::MetaLeaseSet,
+                )
             }
             _ => {
                 tracing::warn!(
@@ -616,10 +655,107 @@ impl Message {
             session_id: SessionId::from(session_id),
             key,
             leaseset,
+            kind,
             private_keys,
         })
     }
 
+    /// Attempt to parse [`Message::BlindingInfo`] from `input`.
+    ///
+    /// Only endpoints which specify the signing public key of the destination, either directly or
+    /// through a blinded address, are supported.
+    ///
+    /// https://geti2p.net/spec/i2cp#blindinginfomessage
+    fn parse_blinding_info(input: impl AsRef<[u8]>) -> Option<Self> {
+        let (rest, session_id) = be_u16::<_, ()>(input.as_ref()).ok()?;
+        let (rest, flags) = be_u8::<_, ()>(rest).ok()?;
+        let (rest, endpoint_kind) = be_u8::<_, ()>(rest).ok()?;
+        let (rest, _blinded_sig_type) = be_u16::<_, ()>(rest).ok()?;
+        let (rest, _expiration) = be_u32::<_, ()>(rest).ok()?;
+
+        let per_client = flags & BLINDING_INFO_PER_CLIENT != 0;
+        let secret_required = flags & BLINDING_INFO_SECRET != 0;
+
+        let (rest, address) = match endpoint_kind {
+            1 => {
+                let (rest, host_name) = Str::parse_frame(rest).ok()?;
+
+                (rest, BlindedAddress::parse(&host_name)?)
+            }
+            2 => {
+                let (rest, destination) = Destination::parse_frame(rest).ok()?;
+
+                (
+                    rest,
+                    BlindedAddress::new(destination.verifying_key(), secret_required, per_client)?,
+                )
+            }
+            3 => {
+                let (rest, sig_type) = be_u16::<_, ()>(rest).ok()?;
+
+                if sig_type != SIG_TYPE_EDDSA_SHA512_ED25519 {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        ?sig_type,
+                        "unsupported signature type for blinding info",
+                    );
+                    return None;
+                }
+
+                let (rest, key) = take::<_, _, ()>(32usize)(rest).ok()?;
+                let public_key = SigningPublicKey::from_bytes(key.try_into().ok()?)?;
+
+                (
+                    rest,
+                    BlindedAddress::new(&public_key, secret_required, per_client)?,
+                )
+            }
+            endpoint_kind => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    ?endpoint_kind,
+                    "unsupported blinding info endpoint",
+                );
+                return None;
+            }
+        };
+
+        let (rest, client_key) = match per_client {
+            false => (rest, None),
+            true => {
+                let (rest, key) = take::<_, _, ()>(32usize)(rest).ok()?;
+
+                match (flags >> 1) & 0x07 {
+                    0 => (
+                        rest,
+                        Some(ClientKey::Dh(StaticPrivateKey::from_bytes(key)?)),
+                    ),
+                    1 => (rest, Some(ClientKey::Psk(key.try_into().ok()?))),
+                    scheme => {
+                        tracing::warn!(
+                            target: LOG_TARGET,
+                            ?scheme,
+                            "unsupported client authentication scheme",
+                        );
+                        return None;
+                    }
+                }
+            }
+        };
+
+        let secret = match secret_required {
+            false => None,
+            true => Some(Str::parse_frame(rest).ok()?.1),
+        };
+
+        Some(Message::BlindingInfo {
+            session_id: SessionId::from(session_id),
+            address,
+            client_key,
+            secret,
+        })
+    }
+
     /// Attempt to parse [`Message::SendMessageExpires`] from `input`.
     ///
     /// https://geti2p.net/spec/i2cp#sendmessageexpiresmessage
@@ -668,6 +804,7 @@ impl Message {
             MessageType::CreateSession => Self::parse_create_session(input),
             MessageType::HostLookup => Self::parse_host_lookup(input),
             MessageType::CreateLeaseSet2 => Self::parse_create_leaseset2(input),
+            MessageType::BlindingInfo => Self::parse_blinding_info(input),
             MessageType::SendMessageExpires => Self::parse_send_message_expires(input),
             msg_type => {
                 tracing::warn!(
@@ -725,4 +862,76 @@ mod tests {
 
         assert!(Message::parse(MessageType::CreateLeaseSet2, &message).is_some());
     }
+
+    #[test]
+    fn parse_blinding_info() {
+        use crate::{
+            crypto::SigningPrivateKey,
+            runtime::{mock::MockRuntime, Runtime},
+        };
+        use bytes::{BufMut, BytesMut};
+
+        let signing_key = SigningPrivateKey::random(MockRuntime::rng());
+        let public_key = signing_key.public();
+
+        // per-client psk authentication with lookup password
+        let mut out = BytesMut::new();
+        out.put_u16(1337);
+        out.put_u8(BLINDING_INFO_PER_CLIENT | (1 << 1) | BLINDING_INFO_SECRET);
+        out.put_u8(3);
+        out.put_u16(11);
+        out.put_u32(0);
+        out.put_u16(SIG_TYPE_EDDSA_SHA512_ED25519);
+        out.put_slice(public_key.as_ref());
+        out.put_slice(&[0xaa; 32]);
+        out.put_slice(&Str::from("secret").serialize());
+
+        match Message::parse(MessageType::BlindingInfo, &out) {
+            Some(Message::BlindingInfo {
+                session_id: SessionId::Session(1337),
+                address,
+                client_key: Some(ClientKey::Psk(key)),
+                secret: Some(secret),
+            }) => {
+                assert_eq!(
+                    address,
+                    BlindedAddress::new(&public_key, true, true).unwrap()
+                );
+                assert_eq!(key, [0xaa; 32]);
+                assert_eq!(secret, Str::from("secret"));
+            }
+            _ => panic!("invalid message"),
+        }
+
+        // blinded address as host name
+        let address = BlindedAddress::new(&public_key, false, false).unwrap();
+        let mut out = BytesMut::new();
+        out.put_u16(1337);
+        out.put_u8(0);
+        out.put_u8(1);
+        out.put_u16(11);
+        out.put_u32(0);
+        out.put_slice(&Str::from(address.to_string()).serialize());
+
+        match Message::parse(MessageType::BlindingInfo, &out) {
+            Some(Message::BlindingInfo {
+                address: parsed,
+                client_key: None,
+                secret: None,
+                ..
+            }) => assert_eq!(parsed, address),
+            _ => panic!("invalid message"),
+        }
+
+        // hash endpoint is not supported
+        let mut out = BytesMut::new();
+        out.put_u16(1337);
+        out.put_u8(0);
+        out.put_u8(0);
+        out.put_u16(11);
+        out.put_u32(0);
+        out.put_slice(&[0u8; 32]);
+
+        assert!(Message::parse(MessageType::BlindingInfo, &out).is_none());
+    }
 }
@@ -26,6 +26,7 @@
 
 use crate::{
     crypto::StaticPrivateKey,
+    destination::LeaseSetKind,
     i2cp::{
         message::{
             BandwidthLimits, Message, RequestVariableLeaseSet, SessionId, SessionStatus,
@@ -67,6 +68,12 @@ pub struct I2cpSessionContext<R: Runtime> {
     /// Active inbound tunnels and their leases.
     pub inbound: HashMap<TunnelId, Lease>,
 
+    /// Store key of the lease set.
+    pub lease_set_key: Bytes,
+
+    /// Kind of the lease set created by the client.
+    pub lease_set_kind: LeaseSetKind,
+
     /// Serialized [`LeaseSet2`].
     pub leaseset: Bytes,
 
@@ -109,6 +116,9 @@ enum PendingSessionState<R: Runtime> {
         /// Session ID.
         session_id: u16,
 
+        /// ID of the client destination.
+        destination_id: DestinationId,
+
         /// I2CP socket.
         socket: I2cpSocket<R>,
 
@@ -130,6 +140,9 @@ enum PendingSessionState<R: Runtime> {
         /// Session ID.
         session_id: u16,
 
+        /// ID of the client destination.
+        destination_id: DestinationId,
+
         /// I2CP socket.
         socket: I2cpSocket<R>,
 
@@ -154,6 +167,9 @@ enum PendingSessionState<R: Runtime> {
         /// Session ID.
         session_id: u16,
 
+        /// ID of the client destination.
+        destination_id: DestinationId,
+
         /// I2CP socket.
         socket: I2cpSocket<R>,
 
@@ -361,6 +377,7 @@ impl<R: Runtime> PendingI2cpSession<R> {
                                 SessionStatusKind::Created,
                             ));
                             self.state = PendingSessionState::BuildingPool {
+                                destination_id: destination.id(),
                                 session_id,
                                 socket,
                                 options,
@@ -393,9 +410,11 @@ impl<R: Runtime> PendingI2cpSession<R> {
                 key,
                 leaseset,
                 private_keys,
+                kind,
                 ..
             } => match mem::replace(&mut self.state, PendingSessionState::Poisoned) {
                 PendingSessionState::AwaitingLeaseSet {
+                    destination_id,
                     session_id,
                     socket,
                     options,
@@ -407,8 +426,10 @@ impl<R: Runtime> PendingI2cpSession<R> {
                     // it to netdb
                     return Some(I2cpSessionContext {
                         address_book: self.address_book.clone(),
-                        destination_id: DestinationId::from(key),
+                        destination_id,
                         inbound,
+                        lease_set_key: key,
+                        lease_set_kind: kind,
                         leaseset,
                         options,
                         outbound,
@@ -459,6 +480,7 @@ impl<R: Runtime> Future for PendingI2cpSession<R> {
                     break;
                 }
                 PendingSessionState::BuildingPool {
+                    destination_id,
                     session_id,
                     socket,
                     options,
@@ -472,6 +494,7 @@ impl<R: Runtime> Future for PendingI2cpSession<R> {
                         );
 
                         self.state = PendingSessionState::BuildingTunnels {
+                            destination_id,
                             session_id,
                             socket,
                             options,
@@ -482,6 +505,7 @@ impl<R: Runtime> Future for PendingI2cpSession<R> {
                     }
                     Poll::Pending => {
                         self.state = PendingSessionState::BuildingPool {
+                            destination_id,
                             session_id,
                             socket,
                             options,
@@ -491,6 +515,7 @@ impl<R: Runtime> Future for PendingI2cpSession<R> {
                     }
                 },
                 PendingSessionState::BuildingTunnels {
+                    destination_id,
                     session_id,
                     mut socket,
                     options,
@@ -500,6 +525,7 @@ impl<R: Runtime> Future for PendingI2cpSession<R> {
                 } => match handle.poll_next_unpin(cx) {
                     Poll::Pending => {
                         self.state = PendingSessionState::BuildingTunnels {
+                            destination_id,
                             session_id,
                             socket,
                             options,
@@ -524,6 +550,7 @@ impl<R: Runtime> Future for PendingI2cpSession<R> {
                             || outbound.len() != handle.config().num_outbound
                         {
                             self.state = PendingSessionState::BuildingTunnels {
+                                destination_id,
                                 session_id,
                                 socket,
                                 options,
@@ -546,6 +573,7 @@ impl<R: Runtime> Future for PendingI2cpSession<R> {
                         ));
 
                         self.state = PendingSessionState::AwaitingLeaseSet {
+                            destination_id,
                             inbound,
                             options,
                             outbound,
@@ -568,6 +596,7 @@ impl<R: Runtime> Future for PendingI2cpSession<R> {
                             || outbound.len() != handle.config().num_outbound
                         {
                             self.state = PendingSessionState::BuildingTunnels {
+                                destination_id,
                                 session_id,
                                 socket,
                                 options,
@@ -590,6 +619,7 @@ impl<R: Runtime> Future for PendingI2cpSession<R> {
                         ));
 
                         self.state = PendingSessionState::AwaitingLeaseSet {
+                            destination_id,
                             inbound,
                             options,
                             outbound,
@@ -608,6 +638,7 @@ impl<R: Runtime> Future for PendingI2cpSession<R> {
                         inbound.remove(&tunnel_id);
 
                         self.state = PendingSessionState::BuildingTunnels {
+                            destination_id,
                             session_id,
                             socket,
                             options,
@@ -626,6 +657,7 @@ impl<R: Runtime> Future for PendingI2cpSession<R> {
                         outbound.remove(&tunnel_id);
 
                         self.state = PendingSessionState::BuildingTunnels {
+                            destination_id,
                             session_id,
                             socket,
                             options,
@@ -643,6 +675,7 @@ impl<R: Runtime> Future for PendingI2cpSession<R> {
                         );
 
                         self.state = PendingSessionState::BuildingTunnels {
+                            destination_id,
                             session_id,
                             socket,
                             options,
@@ -18,7 +18,7 @@
 
 use crate::{
     crypto::base64_decode,
-    destination::{DeliveryStyle, Destination, DestinationEvent, LeaseSetStatus},
+    destination::{DeliveryStyle, Destination, DestinationEvent, LeaseSetKind, LeaseSetStatus},
     i2cp::{
         message::{
             BandwidthLimits, HostReply, HostReplyKind, Message, MessagePayload, RequestKind,
@@ -29,7 +29,7 @@ use crate::{
         socket::I2cpSocket,
     },
     netdb::NetDbHandle,
-    primitives::{Date, DestinationId, Mapping, Str},
+    primitives::{BlindedAddress, Date, DestinationId, Mapping, Str},
     runtime::{AddressBook, JoinSet, Runtime},
 };
 
@@ -102,6 +102,8 @@ impl<R: Runtime> I2cpSession<R> {
             address_book,
             destination_id,
             inbound,
+            lease_set_key,
+            lease_set_kind,
             leaseset,
             options,
             outbound,
@@ -139,7 +141,13 @@ impl<R: Runtime> I2cpSession<R> {
                 .unwrap_or(true),
             profile_storage,
         );
-        destination.publish_lease_set(leaseset);
+
+        match lease_set_kind {
+            LeaseSetKind::LeaseSet2 => destination.publish_lease_set(leaseset),
+            LeaseSetKind::EncryptedLeaseSet =>
+                destination.publish_encrypted_lease_set(lease_set_key, leaseset),
+            LeaseSetKind::MetaLeaseSet => destination.publish_meta_lease_set(leaseset),
+        }
 
         Self {
             address_book,
@@ -167,6 +175,49 @@ impl<R: Runtime> I2cpSession<R> {
             .send_message(MessagePayload::new(self.session_id, message_id, payload));
     }
 
+    /// Handle lease set lookup result for a host lookup.
+    ///
+    /// If the lease set was found, the client is sent the destination of the host. Otherwise the
+    /// host lookup is marked as pending and the client is sent a reply once the query finishes.
+    fn on_lease_set_lookup(
+        &mut self,
+        session_id: SessionId,
+        request_id: u32,
+        destination_id: DestinationId,
+        status: LeaseSetStatus,
+    ) {
+        match status {
+            LeaseSetStatus::Found => {
+                let destination = self
+                    .destination
+                    .lease_set(&destination_id)
+                    .header
+                    .destination
+                    .serialized()
+                    .clone();
+
+                self.socket.send_message(HostReply::new(
+                    session_id.as_u16(),
+                    request_id,
+                    HostReplyKind::Success { destination },
+                ));
+            }
+            LeaseSetStatus::NotFound => {
+                tracing::trace!(
+                    target: LOG_TARGET,
+                    %destination_id,
+                    "lease set lookup started for host lookup",
+                );
+                self.pending_lookups.insert(destination_id, (session_id, request_id));
+            }
+            LeaseSetStatus::Pending => tracing::warn!(
+                target: LOG_TARGET,
+                %destination_id,
+                "host lookup is already pending",
+            ),
+        }
+    }
+
     /// Handle I2CP message received from the client.
     fn on_message(&mut self, message: Message) {
         match message {
@@ -235,6 +286,33 @@ impl<R: Runtime> I2cpSession<R> {
                 );
 
                 match (self.address_book.clone(), kind) {
+                    (_, RequestKind::HostName { host_name })
+                        if BlindedAddress::is_blinded(&host_name) =>
+                        match BlindedAddress::parse(&host_name) {
+                            None => {
+                                tracing::warn!(
+                                    target: LOG_TARGET,
+                                    %host_name,
+                                    "invalid blinded address",
+                                );
+
+                                self.socket.send_message(HostReply::new(
+                                    session_id.as_u16(),
+                                    request_id,
+                                    HostReplyKind::Failure,
+                                ));
+                            }
+                            Some(address) => {
+                                let status = self.destination.query_blinded_lease_set(&address);
+
+                                self.on_lease_set_lookup(
+                                    session_id,
+                                    request_id,
+                                    address.id(),
+                                    status,
+                                );
+                            }
+                        },
                     (Some(address_book), RequestKind::HostName { host_name }) => {
                         self.host_lookups.push(async move {
                             let destination = address_book
@@ -265,55 +343,54 @@ impl<R: Runtime> I2cpSession<R> {
                     }
                     (Some(_), RequestKind::Hash { hash }) => {
                         let destination_id = DestinationId::from(hash);
+                        let status = self.destination.query_lease_set(&destination_id);
 
-                        match self.destination.query_lease_set(&destination_id) {
-                            LeaseSetStatus::Found => {
-                                let destination = self
-                                    .destination
-                                    .lease_set(&destination_id)
-                                    .header
-                                    .destination
-                                    .serialized()
-                                    .clone();
-
-                                self.socket.send_message(HostReply::new(
-                                    session_id.as_u16(),
-                                    request_id,
-                                    HostReplyKind::Success { destination },
-                                ));
-                            }
-                            LeaseSetStatus::NotFound => {
-                                tracing::trace!(
-                                    target: LOG_TARGET,
-                                    %destination_id,
-                                    "lease set lookup started for hash-based host lookup",
-                                );
-                                self.pending_lookups
-                                    .insert(destination_id, (session_id, request_id));
-                            }
-                            LeaseSetStatus::Pending => tracing::warn!(
-                                target: LOG_TARGET,
-                                %destination_id,
-                                "hash-based host lookup is already pending",
-                            ),
-                        }
+                        self.on_lease_set_lookup(session_id, request_id, destination_id, status);
                     }
                 }
             }
             Message::CreateLeaseSet2 {
                 session_id,
+                key,
                 leaseset,
                 private_keys,
-                ..
+                kind,
             } => {
                 tracing::debug!(
                     target: LOG_TARGET,
                     ?session_id,
                     num_private_keys = ?private_keys.len(),
+                    ?kind,
                     "store lease set",
                 );
 
-                self.destination.publish_lease_set(leaseset);
+                match kind {
+                    LeaseSetKind::LeaseSet2 => self.destination.publish_lease_set(leaseset),
+                    LeaseSetKind::EncryptedLeaseSet =>
+                        self.destination.publish_encrypted_lease_set(key, leaseset),
+                    LeaseSetKind::MetaLeaseSet => self.destination.publish_meta_lease_set(leaseset),
+                }
+            }
+            Message::BlindingInfo {
+                session_id,
+                address,
+                client_key,
+                secret,
+            } => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?session_id,
+                    %address,
+                    client_key = ?client_key.is_some(),
+                    secret = ?secret.is_some(),
+                    "register blinding info",
+                );
+
+                self.destination.register_blinding_info(
+                    address,
+                    secret.map(|secret| secret.to_string()),
+                    client_key,
+                );
             }
             Message::SendMessageExpires {
                 session_id,
@@ -18,7 +18,7 @@
 
 use crate::{
     i2np::{database::DATABASE_KEY_SIZE, LOG_TARGET, ROUTER_HASH_LEN},
-    primitives::{LeaseSet2, RouterId, RouterInfo, TunnelId},
+    primitives::{EncryptedLeaseSet, LeaseSet2, MetaLeaseSet, RouterId, RouterInfo, TunnelId},
     runtime::Runtime,
 };
 
@@ -158,6 +158,18 @@ pub enum DatabaseStorePayload {
         /// Lease set.
         lease_set: LeaseSet2,
     },
+
+    /// Encrypted lease set.
+    EncryptedLeaseSet {
+        /// Encrypted lease set.
+        lease_set: EncryptedLeaseSet,
+    },
+
+    /// Meta lease set.
+    MetaLeaseSet {
+        /// Meta lease set.
+        lease_set: MetaLeaseSet,
+    },
 }
 
 impl fmt::Display for DatabaseStorePayload {
@@ -173,6 +185,12 @@ impl fmt::Display for DatabaseStorePayload {
                 "DatabaseStorePayload::LeaseSet2 ({})",
                 lease_set.header.destination.id()
             ),
+            Self::EncryptedLeaseSet { .. } => write!(f, "DatabaseStorePayload::EncryptedLeaseSet"),
+            Self::MetaLeaseSet { lease_set } => write!(
+                f,
+                "DatabaseStorePayload::MetaLeaseSet ({})",
+                lease_set.header.destination.id()
+            ),
         }
     }
 }
@@ -184,6 +202,8 @@ impl DatabaseStorePayload {
             // TODO: calculate actual size
             Self::RouterInfo { .. } => 2048usize,
             Self::LeaseSet2 { lease_set } => lease_set.serialized_len(),
+            Self::EncryptedLeaseSet { lease_set } => lease_set.serialized_len(),
+            Self::MetaLeaseSet { lease_set } => lease_set.serialized_len(),
         }
     }
 }
@@ -295,6 +315,32 @@ impl<R: Runtime> DatabaseStore<R> {
                     },
                 ))
             }
+            StoreType::EncryptedLeaseSet => {
+                let (rest, lease_set) = EncryptedLeaseSet::parse_frame(rest)?;
+
+                Ok((
+                    rest,
+                    Self {
+                        key: Bytes::from(key.to_vec()),
+                        payload: DatabaseStorePayload::EncryptedLeaseSet { lease_set },
+                        reply,
+                        _runtime: Default::default(),
+                    },
+                ))
+            }
+            StoreType::MetaLeaseSet => {
+                let (rest, lease_set) = MetaLeaseSet::parse_frame(rest)?;
+
+                Ok((
+                    rest,
+                    Self {
+                        key: Bytes::from(key.to_vec()),
+                        payload: DatabaseStorePayload::MetaLeaseSet { lease_set },
+                        reply,
+                        _runtime: Default::default(),
+                    },
+                ))
+            }
             kind => {
                 tracing::warn!(
                     target: LOG_TARGET,
@@ -367,6 +413,18 @@ pub enum DatabaseStoreKind {
         /// Serialized [`LeaseSet2`].
         lease_set: Bytes,
     },
+
+    /// [`EncryptedLeaseSet`].
+    EncryptedLeaseSet {
+        /// Serialized [`EncryptedLeaseSet`].
+        lease_set: Bytes,
+    },
+
+    /// [`MetaLeaseSet`].
+    MetaLeaseSet {
+        /// Serialized [`MetaLeaseSet`].
+        lease_set: Bytes,
+    },
 }
 
 impl DatabaseStoreKind {
@@ -375,6 +433,8 @@ impl DatabaseStoreKind {
         match self {
             Self::RouterInfo { router_info } => router_info.len(),
             Self::LeaseSet2 { lease_set } => lease_set.len(),
+            Self::EncryptedLeaseSet { lease_set } => lease_set.len(),
+            Self::MetaLeaseSet { lease_set } => lease_set.len(),
         }
     }
 }
@@ -422,6 +482,9 @@ impl DatabaseStoreBuilder {
         match &self.kind {
             DatabaseStoreKind::RouterInfo { .. } => out.put_u8(StoreType::RouterInfo.as_u8()),
             DatabaseStoreKind::LeaseSet2 { .. } => out.put_u8(StoreType::LeaseSet2.as_u8()),
+            DatabaseStoreKind::EncryptedLeaseSet { .. } =>
+                out.put_u8(StoreType::EncryptedLeaseSet.as_u8()),
+            DatabaseStoreKind::MetaLeaseSet { .. } => out.put_u8(StoreType::MetaLeaseSet.as_u8()),
         }
 
         match reply {
@@ -453,6 +516,8 @@ impl DatabaseStoreBuilder {
                 out.put_slice(&router_info);
             }
             DatabaseStoreKind::LeaseSet2 { lease_set } => out.put_slice(&lease_set),
+            DatabaseStoreKind::EncryptedLeaseSet { lease_set } => out.put_slice(&lease_set),
+            DatabaseStoreKind::MetaLeaseSet { lease_set } => out.put_slice(&lease_set),
         }
 
         out
@@ -655,4 +720,104 @@ mod tests {
             _ => panic!("invalid payload"),
         }
     }
+
+    #[test]
+    fn serialize_and_parse_encrypted_lease_set_store() {
+        use crate::{crypto::blinding::BlindedSigningKey, primitives::EncryptedLeaseSetBuilder};
+
+        let (leaseset, signing_key) = LeaseSet2::random();
+        let public_key = signing_key.public().as_ref().try_into().unwrap();
+        let blinded_key = BlindedSigningKey::new(&signing_key, "20250105", None);
+        let lease_set = EncryptedLeaseSetBuilder::new(&leaseset.clone().serialize(&signing_key))
+            .build(&public_key, &blinded_key, rand::thread_rng())
+            .unwrap();
+        let key = Bytes::from(blinded_key.public().store_key().to_vec());
+
+        let serialized = DatabaseStoreBuilder::new(
+            key.clone(),
+            DatabaseStoreKind::EncryptedLeaseSet {
+                lease_set: Bytes::from(lease_set.clone()),
+            },
+        )
+        .build();
+
+        let store = DatabaseStore::<MockRuntime>::parse(&serialized).unwrap();
+
+        assert_eq!(store.key, key);
+        assert_eq!(
+            DatabaseStore::<MockRuntime>::extract_raw_lease_set(&serialized),
+            lease_set
+        );
+
+        match store.payload {
+            DatabaseStorePayload::EncryptedLeaseSet { lease_set: parsed } => {
+                assert_eq!(parsed.blinded_public_key(), blinded_key.public());
+                assert_eq!(
+                    parsed.decrypt(&public_key, None).unwrap().header.destination.id(),
+                    leaseset.header.destination.id(),
+                );
+            }
+            _ => panic!("invalid payload"),
+        }
+    }
+
+    #[test]
+    fn serialize_and_parse_meta_lease_set_store() {
+        use crate::{
+            crypto::SigningPrivateKey,
+            primitives::{Destination, LeaseSet2Header, MetaLease, MetaLeaseKind},
+        };
+        use core::time::Duration;
+
+        let signing_key = SigningPrivateKey::random(MockRuntime::rng());
+        let destination = Destination::new::<MockRuntime>(signing_key.public());
+        let key = Bytes::from(destination.id().to_vec());
+        let (member, _) = LeaseSet2::random();
+
+        let lease_set = Bytes::from(
+            MetaLeaseSet {
+                header: LeaseSet2Header {
+                    destination,
+                    expires: 600,
+                    is_unpublished: false,
+                    offline_signature: None,
+                    published: MockRuntime::time_since_epoch().as_secs() as u32,
+                },
+                leases: vec![MetaLease {
+                    key: Bytes::from(member.header.destination.id().to_vec()),
+                    kind: MetaLeaseKind::LeaseSet2,
+                    cost: 0u8,
+                    expires: MockRuntime::time_since_epoch() + Duration::from_secs(600),
+                }],
+            }
+            .serialize(&signing_key),
+        );
+
+        let serialized = DatabaseStoreBuilder::new(
+            key.clone(),
+            DatabaseStoreKind::MetaLeaseSet {
+                lease_set: lease_set.clone(),
+            },
+        )
+        .build();
+
+        let store = DatabaseStore::<MockRuntime>::parse(&serialized).unwrap();
+
+        assert_eq!(store.key, key);
+        assert_eq!(
+            DatabaseStore::<MockRuntime>::extract_raw_lease_set(&serialized),
+            lease_set
+        );
+
+        match store.payload {
+            DatabaseStorePayload::MetaLeaseSet { lease_set } => {
+                assert_eq!(lease_set.leases.len(), 1);
+                assert_eq!(
+                    lease_set.leases[0].key.as_ref(),
+                    member.header.destination.id().to_vec().as_slice()
+                );
+            }
+            _ => panic!("invalid payload"),
+        }
+    }
 }
@@ -34,13 +34,16 @@ extern crate alloc;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
+pub use blocklist::{Blocklist, BlocklistEntry};
 pub use config::{
-    Config, ExploratoryConfig, I2cpConfig, MetricsConfig, Ntcp2Config, SamConfig, Ssu2Config,
-    TransitConfig,
+    BandwidthConfig, Config, ExploratoryConfig, I2cpConfig, MetricsConfig, Ntcp2Config, SamConfig,
+    Ssu2Config, TransitConfig,
 };
 pub use error::Error;
 pub use profile::Profile;
 
+mod bandwidth;
+mod blocklist;
 mod bloom;
 mod config;
 mod destination;
@@ -101,7 +101,7 @@ impl<R: Runtime> Dht<R> {
     }
 
     /// Get UTC date from the unix timestamp.
-    fn utc_date(unix_timestamp: u64) -> String {
+    pub(crate) fn utc_date(unix_timestamp: u64) -> String {
         const DAYS_PER_YEAR: u64 = 365;
         const DAYS_PER_4_YEARS: u64 = 4 * DAYS_PER_YEAR + 1;
         const DAYS_PER_100_YEARS: u64 = 25 * DAYS_PER_4_YEARS - 1;
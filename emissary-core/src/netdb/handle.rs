@@ -20,7 +20,7 @@ use crate::{
     crypto::StaticPublicKey,
     error::{ChannelError, QueryError},
     netdb::LOG_TARGET,
-    primitives::{LeaseSet2, RouterId},
+    primitives::{EncryptedLeaseSet, LeaseSet2, RouterId},
 };
 
 use bytes::Bytes;
@@ -54,6 +54,15 @@ pub enum NetDbAction {
         tx: oneshot::Sender<Result<LeaseSet2, QueryError>>,
     },
 
+    /// [`EncryptedLeaseSet`] query.
+    QueryEncryptedLeaseSet {
+        /// Key,
+        key: Bytes,
+
+        /// Oneshot sender used to send the result to caller.
+        tx: oneshot::Sender<Result<EncryptedLeaseSet, QueryError>>,
+    },
+
     /// [`RouterInfo`] query.
     QueryRouterInfo {
         /// Router ID.
@@ -139,6 +148,25 @@ impl NetDbHandle {
             .map_err(From::from)
     }
 
+    /// Send `DatabaseLookup` for an `EncryptedLeaseSet` identified by `key`.
+    ///
+    /// `key` is the store key derived from the blinded public key of the destination and the
+    /// encrypted lease set must be decrypted by the caller.
+    ///
+    /// If the channel towards `NetDb` is full, `ChannelError::Full` is returned and the caller must
+    /// retry later.
+    pub fn query_encrypted_lease_set(
+        &self,
+        key: Bytes,
+    ) -> Result<oneshot::Receiver<Result<EncryptedLeaseSet, QueryError>>, ChannelError> {
+        let (tx, rx) = oneshot::channel();
+
+        self.tx
+            .try_send(NetDbAction::QueryEncryptedLeaseSet { key, tx })
+            .map(|_| rx)
+            .map_err(From::from)
+    }
+
     /// Send `DatabaseLookup` for a `RouterInfo` identified by `router_id`.
     ///
     /// On success returns a `oneshot::Receiver` the caller must poll for a reply poll for a reply.
@@ -33,7 +33,7 @@ use crate::{
         Message, MessageBuilder, MessageType, I2NP_MESSAGE_EXPIRATION,
     },
     netdb::{metrics::*, query::*},
-    primitives::{LeaseSet2, RouterId, RouterInfo},
+    primitives::{EncryptedLeaseSet, LeaseSet2, MetaLeaseSet, RouterId, RouterInfo},
     profile::Bucket,
     router::context::RouterContext,
     runtime::{Counter, Gauge, JoinSet, MetricType, MetricsHandle, Runtime},
@@ -96,6 +96,9 @@ const EXPLORATION_INTERVAL_LOW_ROUTER_COUNT: usize = 55usize;
 /// How often should router exploration be performed if the known peer count is high
 const EXPLORATION_INTERVAL_HIGH_ROUTER_COUNT: usize = 170usize;
 
+/// How many meta lease sets are resolved recursively before the lease set query is failed.
+const MAX_META_LEASE_SET_DEPTH: usize = 4usize;
+
 /// Message kind.
 #[derive(Clone)]
 enum MessageKind {
@@ -176,12 +179,27 @@ pub struct NetDb<R: Runtime> {
     /// This contains entries only if `floodfill` is true.
     lease_sets: HashMap<Bytes, (Bytes, Duration)>,
 
+    /// Serialized [`EncryptedLeaseSet`]s received via `DatabaseStore` messages.
+    ///
+    /// This contains entries only if `floodfill` is true.
+    encrypted_lease_sets: HashMap<Bytes, (Bytes, Duration)>,
+
     /// `NetDb` maintenance timer.
     maintenance_timer: R::Timer,
 
     /// Message builder
     message_builder: NetDbMessageBuilder<R>,
 
+    /// Serialized [`MetaLeaseSet`]s received via `DatabaseStore` messages.
+    ///
+    /// This contains entries only if `floodfill` is true.
+    meta_lease_sets: HashMap<Bytes, (Bytes, Duration)>,
+
+    /// Lease set queries started for the entries of [`MetaLeaseSet`]s.
+    ///
+    /// Maps the key of the member lease set to the number of meta lease sets resolved so far.
+    meta_queries: HashMap<Bytes, usize>,
+
     /// RX channel for receiving NetDb-related messages from [`TunnelManager`].
     netdb_msg_rx: mpsc::Receiver<Message>,
 
@@ -286,8 +304,11 @@ impl<R: Runtime> NetDb<R> {
                 ),
                 handle_rx,
                 lease_sets: HashMap::new(),
+                encrypted_lease_sets: HashMap::new(),
                 maintenance_timer: R::timer(Duration::from_secs(5)),
                 message_builder: NetDbMessageBuilder::new(router_ctx.clone()),
+                meta_lease_sets: HashMap::new(),
+                meta_queries: HashMap::new(),
                 netdb_msg_rx,
                 pending_ready_awaits: Vec::new(),
                 query_timers: R::join_set(),
@@ -483,6 +504,15 @@ impl<R: Runtime> NetDb<R> {
             return;
         }
 
+        if self.router_ctx.blocklist().is_blocked(&router_info) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %router_id,
+                "router is blocked, ignoring router info store",
+            );
+            return;
+        }
+
         tracing::trace!(
             target: LOG_TARGET,
             %router_id,
@@ -659,8 +689,110 @@ impl<R: Runtime> NetDb<R> {
         let raw_lease_set = DatabaseStore::<R>::extract_raw_lease_set(message);
         let expires = lease_set.expires();
 
+        self.meta_lease_sets.remove(&key);
         self.lease_sets.insert(key.clone(), (raw_lease_set.clone(), expires));
+        self.flood_lease_set(
+            key,
+            reply,
+            DatabaseStoreKind::LeaseSet2 {
+                lease_set: raw_lease_set,
+            },
+            expires,
+        );
+    }
 
+    /// Handle [`DatabaseStore`] for [`EncryptedLeaseSet`] if the local router is run as a
+    /// floodfill.
+    fn on_encrypted_lease_set_store(
+        &mut self,
+        key: Bytes,
+        reply: StoreReplyType,
+        message: &[u8],
+        lease_set: EncryptedLeaseSet,
+    ) {
+        tracing::trace!(
+            target: LOG_TARGET,
+            key = ?base32_encode(&key),
+            "encrypted lease set store",
+        );
+
+        if lease_set.is_expired::<R>() {
+            tracing::warn!(
+                target: LOG_TARGET,
+                key = ?base32_encode(&key),
+                expires = ?lease_set.expires(),
+                "received an expired encrypted lease set, ignoring",
+            );
+            return;
+        }
+
+        let raw_lease_set = DatabaseStore::<R>::extract_raw_lease_set(message);
+        let expires = lease_set.expires();
+
+        self.encrypted_lease_sets.insert(key.clone(), (raw_lease_set.clone(), expires));
+        self.flood_lease_set(
+            key,
+            reply,
+            DatabaseStoreKind::EncryptedLeaseSet {
+                lease_set: raw_lease_set,
+            },
+            expires,
+        );
+    }
+
+    /// Handle [`DatabaseStore`] for [`MetaLeaseSet`] if the local router is run as a floodfill.
+    ///
+    /// Meta lease set replaces any [`LeaseSet2`] stored under the same key.
+    fn on_meta_lease_set_store(
+        &mut self,
+        key: Bytes,
+        reply: StoreReplyType,
+        message: &[u8],
+        lease_set: MetaLeaseSet,
+    ) {
+        let destination_id = lease_set.header.destination.id();
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            %destination_id,
+            num_leases = ?lease_set.leases.len(),
+            "meta lease set store",
+        );
+
+        if lease_set.is_expired::<R>() {
+            tracing::warn!(
+                target: LOG_TARGET,
+                %destination_id,
+                expired = ?lease_set.header.expires,
+                "received an expired meta lease set, ignoring",
+            );
+            return;
+        }
+
+        let raw_lease_set = DatabaseStore::<R>::extract_raw_lease_set(message);
+        let expires = lease_set.expires();
+
+        self.lease_sets.remove(&key);
+        self.meta_lease_sets.insert(key.clone(), (raw_lease_set.clone(), expires));
+        self.flood_lease_set(
+            key,
+            reply,
+            DatabaseStoreKind::MetaLeaseSet {
+                lease_set: raw_lease_set,
+            },
+            expires,
+        );
+    }
+
+    /// Send reply for a lease set `DatabaseStore`, if requested, and flood the lease set to three
+    /// floodfills closest to `key`.
+    fn flood_lease_set(
+        &mut self,
+        key: Bytes,
+        reply: StoreReplyType,
+        lease_set: DatabaseStoreKind,
+        expires: Duration,
+    ) {
         match reply {
             StoreReplyType::None => {
                 tracing::trace!(
@@ -731,19 +863,13 @@ impl<R: Runtime> NetDb<R> {
         if floodfills.is_empty() {
             tracing::debug!(
                 target: LOG_TARGET,
-                %destination_id,
+                key = ?base32_encode(&key),
                 "cannot flood lease set, no floodfills",
             );
             return;
         }
 
-        let message = DatabaseStoreBuilder::new(
-            key,
-            DatabaseStoreKind::LeaseSet2 {
-                lease_set: raw_lease_set,
-            },
-        )
-        .build();
+        let message = DatabaseStoreBuilder::new(key, lease_set).build();
 
         let message_id = R::rng().next_u32();
         let message = MessageBuilder::short()
@@ -767,7 +893,23 @@ impl<R: Runtime> NetDb<R> {
         reply_type: ReplyType,
         ignore: HashSet<RouterId>,
     ) {
-        let (message_type, message) = match self.lease_sets.get(&key) {
+        let lease_set = match self.lease_sets.get(&key) {
+            Some((lease_set, _)) => Some(DatabaseStoreKind::LeaseSet2 {
+                lease_set: lease_set.clone(),
+            }),
+            None => match self.encrypted_lease_sets.get(&key) {
+                Some((lease_set, _)) => Some(DatabaseStoreKind::EncryptedLeaseSet {
+                    lease_set: lease_set.clone(),
+                }),
+                None => self.meta_lease_sets.get(&key).map(|(lease_set, _)| {
+                    DatabaseStoreKind::MetaLeaseSet {
+                        lease_set: lease_set.clone(),
+                    }
+                }),
+            },
+        };
+
+        let (message_type, message) = match lease_set {
             None => {
                 tracing::trace!(
                     target: LOG_TARGET,
@@ -793,7 +935,7 @@ impl<R: Runtime> NetDb<R> {
                     .serialize(),
                 )
             }
-            Some((lease_set, _)) => {
+            Some(lease_set) => {
                 tracing::trace!(
                     target: LOG_TARGET,
                     key = ?key[..4],
@@ -802,13 +944,7 @@ impl<R: Runtime> NetDb<R> {
 
                 (
                     MessageType::DatabaseStore,
-                    DatabaseStoreBuilder::new(
-                        key,
-                        DatabaseStoreKind::LeaseSet2 {
-                            lease_set: lease_set.clone(),
-                        },
-                    )
-                    .build(),
+                    DatabaseStoreBuilder::new(key, lease_set).build(),
                 )
             }
         };
@@ -1059,6 +1195,22 @@ impl<R: Runtime> NetDb<R> {
                     destination_id = %lease_set.header.destination.id(),
                     "ignoring lease set database store",
                 ),
+                DatabaseStorePayload::EncryptedLeaseSet { lease_set } if self.floodfill => {
+                    self.on_encrypted_lease_set_store(key, reply, &message.payload, lease_set);
+                }
+                DatabaseStorePayload::EncryptedLeaseSet { .. } => tracing::trace!(
+                    target: LOG_TARGET,
+                    key = ?base32_encode(&key),
+                    "ignoring encrypted lease set database store",
+                ),
+                DatabaseStorePayload::MetaLeaseSet { lease_set } if self.floodfill => {
+                    self.on_meta_lease_set_store(key, reply, &message.payload, lease_set);
+                }
+                DatabaseStorePayload::MetaLeaseSet { lease_set } => tracing::trace!(
+                    target: LOG_TARGET,
+                    destination_id = %lease_set.header.destination.id(),
+                    "ignoring meta lease set database store",
+                ),
             },
             Some(kind) => match (payload, kind) {
                 (DatabaseStorePayload::LeaseSet2 { lease_set }, QueryKind::LeaseSet { query }) => {
@@ -1067,6 +1219,30 @@ impl<R: Runtime> NetDb<R> {
                         destination_id = %lease_set.header.destination.id(),
                         "lease set query reply received",
                     );
+                    self.meta_queries.remove(&key);
+                    query.complete(Ok(lease_set));
+                }
+                (
+                    DatabaseStorePayload::MetaLeaseSet { lease_set },
+                    QueryKind::LeaseSet { query },
+                ) => {
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        destination_id = %lease_set.header.destination.id(),
+                        num_leases = ?lease_set.leases.len(),
+                        "meta lease set query reply received",
+                    );
+                    self.on_meta_lease_set_reply(key, lease_set, query);
+                }
+                (
+                    DatabaseStorePayload::EncryptedLeaseSet { lease_set },
+                    QueryKind::EncryptedLeaseSet { query },
+                ) => {
+                    tracing::trace!(
+                        target: LOG_TARGET,
+                        key = ?base32_encode(&key),
+                        "encrypted lease set query reply received",
+                    );
                     query.complete(Ok(lease_set));
                 }
                 (DatabaseStorePayload::RouterInfo { router_info }, QueryKind::Router) => {
@@ -1096,6 +1272,15 @@ impl<R: Runtime> NetDb<R> {
                         return Ok(());
                     }
 
+                    if self.router_ctx.blocklist().is_blocked(&router_info) {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            %router_id,
+                            "router is blocked, ignoring router info query reply",
+                        );
+                        return Ok(());
+                    }
+
                     if router_info.is_floodfill() {
                         self.floodfill_dht.add_router(router_id.clone());
                     }
@@ -1170,6 +1355,109 @@ impl<R: Runtime> NetDb<R> {
         Ok(())
     }
 
+    /// Handle [`MetaLeaseSet`] received as a reply to a lease set query.
+    ///
+    /// The query is resolved recursively by querying the cheapest entry of the meta lease set
+    /// and sending the member lease set to the subscribers of `query` once it's been found.
+    fn on_meta_lease_set_reply(
+        &mut self,
+        key: Bytes,
+        lease_set: MetaLeaseSet,
+        query: Query<R, LeaseSet2>,
+    ) {
+        let depth = self.meta_queries.remove(&key).unwrap_or(0usize) + 1;
+
+        if depth > MAX_META_LEASE_SET_DEPTH {
+            tracing::debug!(
+                target: LOG_TARGET,
+                key = ?base32_encode(&key),
+                ?depth,
+                "too many nested meta lease sets",
+            );
+            return query.complete(Err(QueryError::ValueNotFound));
+        }
+
+        let Some(lease) = lease_set.resolvable_leases::<R>().next() else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                key = ?base32_encode(&key),
+                "meta lease set doesn't contain any usable entries",
+            );
+            return query.complete(Err(QueryError::ValueNotFound));
+        };
+        let member = lease.key.clone();
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            key = ?base32_encode(&key),
+            member = ?base32_encode(&member),
+            cost = ?lease.cost,
+            "resolve meta lease set entry",
+        );
+
+        self.meta_queries.insert(member.clone(), depth);
+        query.subscribers.into_iter().for_each(|tx| {
+            self.query_lease_set(member.clone(), tx);
+        });
+    }
+
+    /// Handle `DatabaseSearchReply` for an active lease set query.
+    ///
+    /// `kind` is used to convert `query` back into [`QueryKind`] once the reply has been handled.
+    fn on_lease_set_search_reply<T: Clone>(
+        &mut self,
+        key: Bytes,
+        router_id: RouterId,
+        routers: Vec<RouterId>,
+        mut query: Query<R, T>,
+        kind: impl FnOnce(Query<R, T>) -> QueryKind<R>,
+    ) {
+        let unknown = query.handle_search_reply(&routers, self.router_ctx.profile_storage());
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            key = base32_encode(&key),
+            num_queried = ?query.queried.len(),
+            ?unknown,
+            "received `DatabaseSearchReply` for lease set query",
+        );
+
+        // send lookup messages for the found routers
+        unknown.iter().for_each(|lookup_router_id| {
+            let key = Bytes::from(lookup_router_id.to_vec());
+
+            match self.message_builder.create_router_info_query(key.clone()) {
+                Ok((message, outbound_tunnel)) => match self
+                    .exploratory_pool_handle
+                    .send_message(message)
+                    .router_delivery(router_id.clone())
+                    .via_outbound_tunnel(outbound_tunnel)
+                    .try_send()
+                {
+                    Ok(()) => {
+                        self.active.insert(key.clone(), QueryKind::Router);
+                        self.query_timers.push(async move {
+                            R::delay(QUERY_TIMEOUT).await;
+                            key
+                        });
+                    }
+                    Err(error) => tracing::debug!(
+                        target: LOG_TARGET,
+                        ?error,
+                        "failed to send database lookup message for router info",
+                    ),
+                },
+                Err(error) => tracing::debug!(
+                    target: LOG_TARGET,
+                    ?error,
+                    "failed to database lookup message for router info",
+                ),
+            }
+        });
+
+        self.active.insert(key.clone(), kind(query));
+    }
+
     /// Handle `DatabaseSearchReply` message.
     fn on_database_search_reply(
         &mut self,
@@ -1241,53 +1529,14 @@ impl<R: Runtime> NetDb<R> {
                     }
                 });
             }
-            Some(QueryKind::LeaseSet { mut query }) => {
-                let unknown =
-                    query.handle_search_reply(&routers, self.router_ctx.profile_storage());
-
-                tracing::trace!(
-                    target: LOG_TARGET,
-                    key = base32_encode(&key),
-                    num_queried = ?query.queried.len(),
-                    ?unknown,
-                    "received `DatabaseSearchReply` for lease set query",
-                );
-
-                // send lookup messages for the found routers
-                unknown.iter().for_each(|lookup_router_id| {
-                    let key = Bytes::from(lookup_router_id.to_vec());
-
-                    match self.message_builder.create_router_info_query(key.clone()) {
-                        Ok((message, outbound_tunnel)) => match self
-                            .exploratory_pool_handle
-                            .send_message(message)
-                            .router_delivery(router_id.clone())
-                            .via_outbound_tunnel(outbound_tunnel)
-                            .try_send()
-                        {
-                            Ok(()) => {
-                                self.active.insert(key.clone(), QueryKind::Router);
-                                self.query_timers.push(async move {
-                                    R::delay(QUERY_TIMEOUT).await;
-                                    key
-                                });
-                            }
-                            Err(error) => tracing::debug!(
-                                target: LOG_TARGET,
-                                ?error,
-                                "failed to send database lookup message for router info",
-                            ),
-                        },
-                        Err(error) => tracing::debug!(
-                            target: LOG_TARGET,
-                            ?error,
-                            "failed to database lookup message for router info",
-                        ),
-                    }
-                });
-
-                self.active.insert(key.clone(), QueryKind::LeaseSet { query });
-            }
+            Some(QueryKind::LeaseSet { query }) =>
+                self.on_lease_set_search_reply(key, router_id, routers, query, |query| {
+                    QueryKind::LeaseSet { query }
+                }),
+            Some(QueryKind::EncryptedLeaseSet { query }) =>
+                self.on_lease_set_search_reply(key, router_id, routers, query, |query| {
+                    QueryKind::EncryptedLeaseSet { query }
+                }),
             Some(QueryKind::RouterInfo { mut query }) => {
                 let unknown =
                     query.handle_search_reply(&routers, self.router_ctx.profile_storage());
@@ -1427,6 +1676,52 @@ impl<R: Runtime> NetDb<R> {
             None => {}
         }
 
+        self.start_lease_set_query(key, tx, |query| QueryKind::LeaseSet { query });
+    }
+
+    /// Query `EncryptedLeaseSet` under `key` from `NetDb` and return result to caller via `tx`.
+    ///
+    /// `key` is the store key derived from the blinded public key of the destination.
+    fn query_encrypted_lease_set(
+        &mut self,
+        key: Bytes,
+        tx: oneshot::Sender<Result<EncryptedLeaseSet, QueryError>>,
+    ) {
+        match self.active.get_mut(&key) {
+            Some(QueryKind::EncryptedLeaseSet { query }) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    key = base32_encode(&key),
+                    "encrypted lease set query already in progress, adding subscriber",
+                );
+
+                query.add_subscriber(tx);
+                return;
+            }
+            Some(kind) => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    ?kind,
+                    key = ?key.to_vec(),
+                    "unable to handle encrypted lease set query, different kind of query already in progress",
+                );
+                return;
+            }
+            None => {}
+        }
+
+        self.start_lease_set_query(key, tx, |query| QueryKind::EncryptedLeaseSet { query });
+    }
+
+    /// Start lease set query for `key`.
+    ///
+    /// `kind` is used to convert the created [`Query`] into [`QueryKind`].
+    fn start_lease_set_query<T: Clone>(
+        &mut self,
+        key: Bytes,
+        tx: oneshot::Sender<Result<T, QueryError>>,
+        kind: impl FnOnce(Query<R, T>) -> QueryKind<R>,
+    ) {
         let mut ignored = HashSet::<RouterId>::new();
 
         let (floodfill, floodfill_public_key) = loop {
@@ -1476,12 +1771,7 @@ impl<R: Runtime> NetDb<R> {
             {
                 Ok(()) => {
                     // store leaseset query into active queries and start timer for the query
-                    self.active.insert(
-                        key.clone(),
-                        QueryKind::LeaseSet {
-                            query: Query::new(key.clone(), tx, floodfill),
-                        },
-                    );
+                    self.active.insert(key.clone(), kind(Query::new(key.clone(), tx, floodfill)));
                     self.query_timers.push(async move {
                         R::delay(QUERY_TIMEOUT).await;
                         key
@@ -1667,16 +1957,18 @@ impl<R: Runtime> NetDb<R> {
         // prune expired lease sets
         {
             let now = R::time_since_epoch();
-            let num_pruned = self
-                .lease_sets
-                .iter()
-                .filter_map(|(key, (_, expires))| (expires < &now).then_some(key.clone()))
-                .collect::<Vec<_>>()
-                .into_iter()
-                .fold(0usize, |count, key| {
-                    self.lease_sets.remove(&key);
-                    count + 1
-                });
+            let num_pruned = [
+                &mut self.lease_sets,
+                &mut self.encrypted_lease_sets,
+                &mut self.meta_lease_sets,
+            ]
+            .into_iter()
+            .fold(0usize, |count, lease_sets| {
+                let num_lease_sets = lease_sets.len();
+                lease_sets.retain(|_, (_, expires)| *expires >= now);
+
+                count + num_lease_sets - lease_sets.len()
+            });
 
             if num_pruned > 0 {
                 tracing::trace!(
@@ -1686,6 +1978,9 @@ impl<R: Runtime> NetDb<R> {
                 );
             }
         }
+
+        // remove depths of meta lease set entries whose queries have finished
+        self.meta_queries.retain(|key, _| self.active.contains_key(key));
     }
 
     /// Perform router exploration.
@@ -1743,84 +2038,98 @@ impl<R: Runtime> NetDb<R> {
         }
     }
 
-    /// Handle timeout for `query`.
-    fn handle_timeout(&mut self, key: Bytes, query: QueryKind<R>) {
-        match query {
-            QueryKind::LeaseSet { mut query } => {
-                if let Some(floodfill) = query.selected.take() {
-                    self.floodfill_dht.register_lookup_timeout(&floodfill);
-                }
+    /// Handle timeout for lease set `query`.
+    ///
+    /// `kind` is used to convert `query` back into [`QueryKind`] if the query is continued.
+    fn handle_lease_set_timeout<T: Clone>(
+        &mut self,
+        key: Bytes,
+        mut query: Query<R, T>,
+        kind: impl FnOnce(Query<R, T>) -> QueryKind<R>,
+    ) {
+        if let Some(floodfill) = query.selected.take() {
+            self.floodfill_dht.register_lookup_timeout(&floodfill);
+        }
 
-                let (floodfill, public_key) = loop {
-                    // attempt to select next floodfill if none is found or the query has expired,
-                    // send failure to caller
-                    let floodfill = match query
-                        .handle_timeout(&self.floodfill_dht, self.router_ctx.profile_storage())
-                    {
-                        Err(error) => {
-                            tracing::debug!(
-                                target: LOG_TARGET,
-                                key = %base32_encode(&key),
-                                ?error,
-                                "lease set query timed out",
-                            );
-                            query.complete(Err(error));
-                            return;
-                        }
-                        Ok(floodfill) => floodfill,
-                    };
+        let (floodfill, public_key) = loop {
+            // attempt to select next floodfill if none is found or the query has expired,
+            // send failure to caller
+            let floodfill = match query
+                .handle_timeout(&self.floodfill_dht, self.router_ctx.profile_storage())
+            {
+                Err(error) => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        key = %base32_encode(&key),
+                        ?error,
+                        "lease set query timed out",
+                    );
+                    query.complete(Err(error));
+                    return;
+                }
+                Ok(floodfill) => floodfill,
+            };
 
-                    let reader = self.router_ctx.profile_storage().reader();
+            let reader = self.router_ctx.profile_storage().reader();
 
-                    match reader.router_info(&floodfill) {
-                        Some(router_info) =>
-                            break (floodfill, router_info.identity.static_key().clone()),
-                        None => {
-                            tracing::debug!(
-                                target: LOG_TARGET,
-                                key = ?base32_encode(&key),
-                                %floodfill,
-                                "cannot send lease set query, floodfill router info doesn't exist",
-                            );
-                            query.queried.insert(floodfill);
-                        }
-                    }
-                };
+            match reader.router_info(&floodfill) {
+                Some(router_info) => break (floodfill, router_info.identity.static_key().clone()),
+                None => {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        key = ?base32_encode(&key),
+                        %floodfill,
+                        "cannot send lease set query, floodfill router info doesn't exist",
+                    );
+                    query.queried.insert(floodfill);
+                }
+            }
+        };
 
-                tracing::debug!(
-                    target: LOG_TARGET,
-                    key = ?base32_encode(&key),
-                    %floodfill,
-                    "send lease set query",
-                );
+        tracing::debug!(
+            target: LOG_TARGET,
+            key = ?base32_encode(&key),
+            %floodfill,
+            "send lease set query",
+        );
 
-                match self.message_builder.create_lease_set_query(key.clone(), public_key) {
-                    Ok((message, outbound_tunnel)) => match self
-                        .exploratory_pool_handle
-                        .send_message(message)
-                        .router_delivery(floodfill.clone())
-                        .via_outbound_tunnel(outbound_tunnel)
-                        .try_send()
-                    {
-                        Ok(()) => {
-                            query.queried.insert(floodfill.clone());
-                            query.selected = Some(floodfill);
+        match self.message_builder.create_lease_set_query(key.clone(), public_key) {
+            Ok((message, outbound_tunnel)) => match self
+                .exploratory_pool_handle
+                .send_message(message)
+                .router_delivery(floodfill.clone())
+                .via_outbound_tunnel(outbound_tunnel)
+                .try_send()
+            {
+                Ok(()) => {
+                    query.queried.insert(floodfill.clone());
+                    query.selected = Some(floodfill);
 
-                            self.active.insert(key.clone(), QueryKind::LeaseSet { query });
-                            self.query_timers.push(async move {
-                                R::delay(QUERY_TIMEOUT).await;
-                                key
-                            });
-                        }
-                        Err(_) => {
-                            query.complete(Err(QueryError::RetryFailure));
-                        }
-                    },
-                    Err(error) => {
-                        query.complete(Err(error));
-                    }
+                    self.active.insert(key.clone(), kind(query));
+                    self.query_timers.push(async move {
+                        R::delay(QUERY_TIMEOUT).await;
+                        key
+                    });
+                }
+                Err(_) => {
+                    query.complete(Err(QueryError::RetryFailure));
                 }
+            },
+            Err(error) => {
+                query.complete(Err(error));
             }
+        }
+    }
+
+    /// Handle timeout for `query`.
+    fn handle_timeout(&mut self, key: Bytes, query: QueryKind<R>) {
+        match query {
+            QueryKind::LeaseSet { query } =>
+                self.handle_lease_set_timeout(key, query, |query| QueryKind::LeaseSet { query }),
+            QueryKind::EncryptedLeaseSet { query } =>
+                self.handle_lease_set_timeout(key, query, |query| QueryKind::EncryptedLeaseSet {
+                    query,
+                }),
             QueryKind::RouterInfo { mut query } => {
                 if let Some(floodfill) = query.selected.take() {
                     self.floodfill_dht.register_lookup_timeout(&floodfill);
@@ -1985,6 +2294,8 @@ impl<R: Runtime> Future for NetDb<R> {
                 Poll::Ready(None) => return Poll::Ready(()),
                 Poll::Ready(Some(NetDbAction::QueryLeaseSet2 { key, tx })) =>
                     self.query_lease_set(key, tx),
+                Poll::Ready(Some(NetDbAction::QueryEncryptedLeaseSet { key, tx })) =>
+                    self.query_encrypted_lease_set(key, tx),
                 Poll::Ready(Some(NetDbAction::GetClosestFloodfills { key, tx })) =>
                     self.get_closest_floodfills(key, tx),
                 Poll::Ready(Some(NetDbAction::QueryRouterInfo { router_id, tx })) =>
@@ -2768,7 +3079,7 @@ mod tests {
                                 .as_millis() as u64,
                             ),
                             addresses: HashMap::from_iter([(
-                                TransportKind::Ntcp2,
+                                TransportKind::Ntcp2V4,
                                 RouterAddress::new_unpublished_ntcp2([1u8; 32], 8888),
                             )]),
                             options: Mapping::from_iter([
@@ -2882,7 +3193,7 @@ mod tests {
                                 .as_millis() as u64,
                             ),
                             addresses: HashMap::from_iter([(
-                                TransportKind::Ntcp2,
+                                TransportKind::Ntcp2V4,
                                 RouterAddress::new_unpublished_ntcp2([1u8; 32], 8888),
                             )]),
                             options: Mapping::from_iter([
@@ -3197,7 +3508,7 @@ mod tests {
                                 .as_millis() as u64,
                             ),
                             addresses: HashMap::from_iter([(
-                                TransportKind::Ntcp2,
+                                TransportKind::Ntcp2V4,
                                 RouterAddress::new_unpublished_ntcp2([1u8; 32], 8888),
                             )]),
                             options: Mapping::from_iter([
@@ -3746,7 +4057,7 @@ mod tests {
                                     .as_millis() as u64,
                             ),
                             addresses: HashMap::from_iter([(
-                                TransportKind::Ntcp2,
+                                TransportKind::Ntcp2V4,
                                 RouterAddress::new_unpublished_ntcp2([1u8; 32], 8888),
                             )]),
                             options: Mapping::from_iter([
@@ -3779,7 +4090,7 @@ mod tests {
                                 .as_millis() as u64,
                             ),
                             addresses: HashMap::from_iter([(
-                                TransportKind::Ntcp2,
+                                TransportKind::Ntcp2V4,
                                 RouterAddress::new_unpublished_ntcp2([1u8; 32], 8888),
                             )]),
                             options: Mapping::from_iter([
@@ -4000,7 +4311,7 @@ mod tests {
                                     .as_millis() as u64,
                             ),
                             addresses: HashMap::from_iter([(
-                                TransportKind::Ntcp2,
+                                TransportKind::Ntcp2V4,
                                 RouterAddress::new_unpublished_ntcp2([1u8; 32], 8888),
                             )]),
                             options: Mapping::from_iter([
@@ -4189,7 +4500,7 @@ mod tests {
                                 .as_millis() as u64,
                             ),
                             addresses: HashMap::from_iter([(
-                                TransportKind::Ntcp2,
+                                TransportKind::Ntcp2V4,
                                 RouterAddress::new_unpublished_ntcp2([1u8; 32], 8888),
                             )]),
                             options: Mapping::from_iter([
@@ -4493,7 +4804,9 @@ mod tests {
 
                             true
                         }
-                        DatabaseStorePayload::LeaseSet2 { .. } => false,
+                        DatabaseStorePayload::LeaseSet2 { .. }
+                        | DatabaseStorePayload::EncryptedLeaseSet { .. }
+                        | DatabaseStorePayload::MetaLeaseSet { .. } => false,
                     }
                 }
                 _ => false,
@@ -4859,4 +5172,145 @@ mod tests {
 
         assert_eq!(message.message_type, MessageType::DeliveryStatus);
     }
+
+    #[tokio::test]
+    async fn meta_lease_set_resolved_recursively() {
+        use crate::primitives::{MetaLease, MetaLeaseKind, MetaLeaseSet};
+
+        let (service, _rx, _tx, storage) = TransportService::new();
+        let (tp_handle, _tm_rx, _tp_tx, _srx) = TunnelPoolHandle::create();
+        let (router_info, static_key, signing_key) = RouterInfoBuilder::default().build();
+        let (_msg_tx, msg_rx) = channel(64);
+        let (_event_mgr, _event_subscriber, event_handle) = EventManager::new(None);
+        let (tm_mgr_tx, _tm_mgr_rx) = with_recycle(64, RoutingKindRecycle::default());
+        let (transit_tx, _transit_rx) = channel(64);
+        let rtbl = RoutingTable::new(router_info.identity.id(), tm_mgr_tx, transit_tx);
+
+        let (mut netdb, _handle) = NetDb::<MockRuntime>::new(
+            RouterContext::new(
+                MockRuntime::register_metrics(vec![], None),
+                storage,
+                router_info.identity.id(),
+                Bytes::from(router_info.serialize(&signing_key)),
+                static_key,
+                signing_key,
+                2u8,
+                event_handle.clone(),
+            ),
+            false,
+            service,
+            tp_handle,
+            rtbl,
+            msg_rx,
+        );
+
+        // create member lease set and a meta lease set which lists it as the cheapest entry
+        let (member, member_signing_key) = LeaseSet2::random();
+        let member_key = Bytes::from(member.header.destination.id().to_vec());
+
+        let meta_signing_key = SigningPrivateKey::random(MockRuntime::rng());
+        let meta_destination = Destination::new::<MockRuntime>(meta_signing_key.public());
+        let meta_key = Bytes::from(meta_destination.id().to_vec());
+        let expires = MockRuntime::time_since_epoch() + Duration::from_secs(600);
+
+        let meta_lease_set = MetaLeaseSet {
+            header: LeaseSet2Header {
+                destination: meta_destination,
+                expires: 600,
+                is_unpublished: false,
+                offline_signature: None,
+                published: MockRuntime::time_since_epoch().as_secs() as u32,
+            },
+            leases: vec![
+                MetaLease {
+                    key: Bytes::from(DestinationId::random().to_vec()),
+                    kind: MetaLeaseKind::LeaseSet2,
+                    cost: 10u8,
+                    expires,
+                },
+                MetaLease {
+                    key: member_key.clone(),
+                    kind: MetaLeaseKind::LeaseSet2,
+                    cost: 1u8,
+                    expires,
+                },
+            ],
+        }
+        .serialize(&meta_signing_key);
+
+        // start queries for both the meta lease set and the member lease set
+        let (tx, rx) = oneshot::channel();
+        let (member_tx, member_rx) = oneshot::channel();
+
+        netdb.active.insert(
+            meta_key.clone(),
+            QueryKind::LeaseSet {
+                query: Query::new(meta_key.clone(), tx, RouterId::random()),
+            },
+        );
+        netdb.active.insert(
+            member_key.clone(),
+            QueryKind::LeaseSet {
+                query: Query::new(member_key.clone(), member_tx, RouterId::random()),
+            },
+        );
+
+        // receive meta lease set and verify the original query is moved to the member query
+        let message = DatabaseStoreBuilder::new(
+            meta_key.clone(),
+            DatabaseStoreKind::MetaLeaseSet {
+                lease_set: Bytes::from(meta_lease_set),
+            },
+        )
+        .build();
+
+        assert!(netdb
+            .on_message(
+                Message {
+                    payload: message.to_vec(),
+                    message_type: MessageType::DatabaseStore,
+                    ..Default::default()
+                },
+                None
+            )
+            .is_ok());
+
+        assert!(!netdb.active.contains_key(&meta_key));
+        assert_eq!(netdb.meta_queries.get(&member_key), Some(&1usize));
+
+        match netdb.active.get(&member_key) {
+            Some(QueryKind::LeaseSet { query }) => assert_eq!(query.subscribers.len(), 2),
+            _ => panic!("invalid query"),
+        }
+
+        // receive member lease set and verify both subscribers get it
+        let message = DatabaseStoreBuilder::new(
+            member_key.clone(),
+            DatabaseStoreKind::LeaseSet2 {
+                lease_set: Bytes::from(member.serialize(&member_signing_key)),
+            },
+        )
+        .build();
+
+        assert!(netdb
+            .on_message(
+                Message {
+                    payload: message.to_vec(),
+                    message_type: MessageType::DatabaseStore,
+                    ..Default::default()
+                },
+                None
+            )
+            .is_ok());
+
+        assert!(netdb.meta_queries.is_empty());
+
+        for rx in [rx, member_rx] {
+            let lease_set = rx.await.unwrap().unwrap();
+            assert_eq!(
+                lease_set.header.destination.id().to_vec(),
+                member_key.to_vec()
+            );
+        }
+    }
 }
@@ -25,7 +25,7 @@ use crate::{
         MessageBuilder, MessageType, I2NP_MESSAGE_EXPIRATION,
     },
     netdb::Dht,
-    primitives::{Lease, LeaseSet2, MessageId, RouterId, TunnelId},
+    primitives::{EncryptedLeaseSet, Lease, LeaseSet2, MessageId, RouterId, TunnelId},
     profile::ProfileStorage,
     router::context::RouterContext,
     runtime::{Instant, Runtime},
@@ -234,6 +234,12 @@ pub enum QueryKind<R: Runtime> {
         query: Query<R, LeaseSet2>,
     },
 
+    /// Encrypted lease set query.
+    EncryptedLeaseSet {
+        /// Active query.
+        query: Query<R, EncryptedLeaseSet>,
+    },
+
     /// Router info.
     RouterInfo {
         /// Active query.
@@ -251,6 +257,8 @@ impl<R: Runtime> fmt::Debug for QueryKind<R> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::LeaseSet { .. } => f.debug_struct("QueryKind::LeaseSet").finish_non_exhaustive(),
+            Self::EncryptedLeaseSet { .. } =>
+                f.debug_struct("QueryKind::EncryptedLeaseSet").finish_non_exhaustive(),
             Self::RouterInfo { .. } =>
                 f.debug_struct("QueryKind::RouterInfo").finish_non_exhaustive(),
             Self::Exploration => f.debug_struct("QueryKind::Exploration").finish(),
@@ -0,0 +1,266 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::{
+    crypto::{
+        base32_decode, base32_encode,
+        blinding::{
+            BlindedPublicKey, SIG_TYPE_EDDSA_SHA512_ED25519, SIG_TYPE_REDDSA_SHA512_ED25519,
+        },
+        sha256::Sha256,
+        SigningPublicKey,
+    },
+    primitives::DestinationId,
+};
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+/// Size of a serialized [`BlindedAddress`].
+const BLINDED_ADDRESS_LEN: usize = 35usize;
+
+/// Signature types are two bytes long.
+///
+/// Not supported since both `EdDSA_SHA512_Ed25519` and `RedDSA_SHA512_Ed25519` fit in one byte.
+const FLAG_TWO_BYTE_SIG_TYPES: u8 = 0x01;
+
+/// Lookup password is required.
+const FLAG_SECRET_REQUIRED: u8 = 0x02;
+
+/// Per-client authentication is required.
+const FLAG_CLIENT_AUTH_REQUIRED: u8 = 0x04;
+
+/// Blinded address, also known as a b33 address.
+///
+/// Blinded address contains the unblinded signing public key of the destination which is used to
+/// derive the daily blinded key under which the encrypted lease set of the destination is stored.
+///
+/// https://geti2p.net/spec/b32encrypted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlindedAddress {
+    /// Unblinded signing public key of the destination.
+    public_key: [u8; 32],
+
+    /// Is a lookup password required.
+    secret_required: bool,
+
+    /// Is per-client authentication required.
+    client_auth_required: bool,
+}
+
+impl BlindedAddress {
+    /// Create new [`BlindedAddress`] for `verifying_key`.
+    ///
+    /// Returns `None` if `verifying_key` is not an Ed25519 key as blinding is not supported for
+    /// other key types.
+    pub fn new(
+        verifying_key: &SigningPublicKey,
+        secret_required: bool,
+        client_auth_required: bool,
+    ) -> Option<Self> {
+        let SigningPublicKey::Ed25519(key) = verifying_key else {
+            return None;
+        };
+
+        Some(Self {
+            public_key: key.to_bytes(),
+            secret_required,
+            client_auth_required,
+        })
+    }
+
+    /// Attempt to parse [`BlindedAddress`] from `host`.
+    ///
+    /// `host` may or may not contain the `.b32.i2p` suffix.
+    pub fn parse(host: &str) -> Option<Self> {
+        let host = host.strip_suffix(".b32.i2p").unwrap_or(host);
+        let data = base32_decode(host)?;
+
+        if data.len() != BLINDED_ADDRESS_LEN {
+            return None;
+        }
+
+        let checksum = crc32fast::hash(&data[3..]);
+        let flags = data[0] ^ checksum as u8;
+        let sig_type = data[1] ^ (checksum >> 8) as u8;
+        let blinded_sig_type = data[2] ^ (checksum >> 16) as u8;
+
+        if flags & FLAG_TWO_BYTE_SIG_TYPES != 0
+            || sig_type as u16 != SIG_TYPE_EDDSA_SHA512_ED25519
+            || blinded_sig_type as u16 != SIG_TYPE_REDDSA_SHA512_ED25519
+        {
+            return None;
+        }
+
+        Some(Self {
+            // conversion must succeed since `data` is of correct size
+            public_key: TryInto::<[u8; 32]>::try_into(&data[3..]).expect("to succeed"),
+            secret_required: flags & FLAG_SECRET_REQUIRED != 0,
+            client_auth_required: flags & FLAG_CLIENT_AUTH_REQUIRED != 0,
+        })
+    }
+
+    /// Returns `true` if `host` looks like a blinded address.
+    ///
+    /// Regular `.b32.i2p` addresses encode 32 bytes into 52 characters, blinded addresses are
+    /// 56 characters or longer.
+    pub fn is_blinded(host: &str) -> bool {
+        host.strip_suffix(".b32.i2p").is_some_and(|host| host.len() >= 56)
+    }
+
+    /// Get reference to the unblinded signing public key of the destination.
+    pub fn public_key(&self) -> &[u8; 32] {
+        &self.public_key
+    }
+
+    /// Is a lookup password required to find the lease set.
+    pub fn secret_required(&self) -> bool {
+        self.secret_required
+    }
+
+    /// Is per-client authentication required to decrypt the lease set.
+    pub fn client_auth_required(&self) -> bool {
+        self.client_auth_required
+    }
+
+    /// Get ID of the blinded destination.
+    ///
+    /// The actual ID of the destination is not known until its lease set has been decrypted so
+    /// before that, the destination is identified by the hash of its signing public key.
+    pub fn id(&self) -> DestinationId {
+        DestinationId::from(Sha256::new().update(self.public_key).finalize())
+    }
+
+    /// Get base32-encoded address, without the `.b32.i2p` suffix.
+    pub fn to_base32(&self) -> String {
+        let mut data = Vec::with_capacity(BLINDED_ADDRESS_LEN);
+        let flags = if self.secret_required {
+            FLAG_SECRET_REQUIRED
+        } else {
+            0u8
+        } | if self.client_auth_required {
+            FLAG_CLIENT_AUTH_REQUIRED
+        } else {
+            0u8
+        };
+
+        let checksum = crc32fast::hash(&self.public_key);
+        data.push(flags ^ checksum as u8);
+        data.push(SIG_TYPE_EDDSA_SHA512_ED25519 as u8 ^ (checksum >> 8) as u8);
+        data.push(SIG_TYPE_REDDSA_SHA512_ED25519 as u8 ^ (checksum >> 16) as u8);
+        data.extend_from_slice(&self.public_key);
+
+        base32_encode(data)
+    }
+
+    /// Blind the public key of the destination for `date`.
+    pub fn blind(&self, date: &str, secret: Option<&str>) -> Option<BlindedPublicKey> {
+        BlindedPublicKey::new(&self.public_key, date, secret)
+    }
+}
+
+impl fmt::Display for BlindedAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.b32.i2p", self.to_base32())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::SigningPrivateKey;
+
+    #[test]
+    fn serialize_and_parse() {
+        let signing_key = SigningPrivateKey::random(rand::thread_rng());
+
+        for (secret, auth) in [(false, false), (true, false), (false, true), (true, true)] {
+            let address = BlindedAddress::new(&signing_key.public(), secret, auth).unwrap();
+            let host = address.to_string();
+
+            assert_eq!(host.len(), 56 + ".b32.i2p".len());
+            assert!(BlindedAddress::is_blinded(&host));
+
+            let parsed = BlindedAddress::parse(&host).unwrap();
+            assert_eq!(parsed, address);
+            assert_eq!(parsed.secret_required(), secret);
+            assert_eq!(parsed.client_auth_required(), auth);
+        }
+    }
+
+    #[test]
+    fn regular_b32_address_is_not_blinded() {
+        let host = "udhdrtrcetjm5sxzskjyr5ztpeszydbh4dpl3pl4utgqqw2v4jna.b32.i2p";
+
+        assert!(!BlindedAddress::is_blinded(host));
+        assert!(BlindedAddress::parse(host).is_none());
+    }
+
+    #[test]
+    fn invalid_checksum() {
+        let signing_key = SigningPrivateKey::random(rand::thread_rng());
+        let address = BlindedAddress::new(&signing_key.public(), false, false).unwrap();
+        let mut data =
+            base32_decode(address.to_string().strip_suffix(".b32.i2p").unwrap()).unwrap();
+        data[10] ^= 0xff;
+
+        assert!(BlindedAddress::parse(&base32_encode(data)).is_none());
+    }
+
+    #[test]
+    fn known_answers() {
+        // generated with an independent implementation of the specification
+        let public_key = SigningPublicKey::from_bytes(&[
+            0x3c, 0x0c, 0xcf, 0x60, 0x40, 0x62, 0xde, 0xdd, 0x13, 0xdf, 0xec, 0x00, 0x85, 0x6b,
+            0xd3, 0x49, 0xd8, 0xf9, 0xf5, 0x1c, 0xc2, 0x2d, 0x2d, 0xbf, 0xf0, 0xbd, 0x59, 0x78,
+            0xb9, 0xea, 0x07, 0x43,
+        ])
+        .unwrap();
+
+        for (secret, auth, expected) in [
+            (
+                false,
+                false,
+                "mtmbspamz5qeayw63uj573aaqvv5gsoy7h2rzqrnfw77bpkzpc46ub2d",
+            ),
+            (
+                true,
+                false,
+                "m3mbspamz5qeayw63uj573aaqvv5gsoy7h2rzqrnfw77bpkzpc46ub2d",
+            ),
+            (
+                false,
+                true,
+                "mdmbspamz5qeayw63uj573aaqvv5gsoy7h2rzqrnfw77bpkzpc46ub2d",
+            ),
+            (
+                true,
+                true,
+                "mlmbspamz5qeayw63uj573aaqvv5gsoy7h2rzqrnfw77bpkzpc46ub2d",
+            ),
+        ] {
+            let address = BlindedAddress::new(&public_key, secret, auth).unwrap();
+            assert_eq!(address.to_base32(), expected);
+
+            let parsed = BlindedAddress::parse(&format!("{expected}.b32.i2p")).unwrap();
+            assert_eq!(parsed.public_key(), public_key.as_ref());
+            assert_eq!(parsed.secret_required(), secret);
+            assert_eq!(parsed.client_auth_required(), auth);
+        }
+    }
+}
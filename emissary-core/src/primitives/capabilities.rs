@@ -18,6 +18,7 @@
 
 use crate::primitives::Str;
 
+use alloc::string::String;
 use core::fmt;
 
 /// Specified bandwidth of the router.
@@ -45,7 +46,50 @@ pub enum Bandwidth {
     X,
 }
 
+/// Characters of all bandwidth classes.
+const BANDWIDTH_CLASSES: [char; 7] = ['K', 'L', 'M', 'N', 'O', 'P', 'X'];
+
 impl Bandwidth {
+    /// Get [`Bandwidth`] class for `kbps` KBps of shared bandwidth.
+    pub fn from_kbps(kbps: usize) -> Self {
+        match kbps {
+            0..12 => Self::K,
+            12..48 => Self::L,
+            48..64 => Self::M,
+            64..128 => Self::N,
+            128..256 => Self::O,
+            256..=2000 => Self::P,
+            _ => Self::X,
+        }
+    }
+
+    /// Get the character of the bandwidth class, as published in capabilities.
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::K => 'K',
+            Self::L => 'L',
+            Self::M => 'M',
+            Self::N => 'N',
+            Self::O => 'O',
+            Self::P => 'P',
+            Self::X => 'X',
+        }
+    }
+
+    /// Replace bandwidth class of `caps` with `self`.
+    ///
+    /// If `caps` doesn't specify a bandwidth class, the class is added to the front.
+    pub fn replace(&self, caps: &str) -> String {
+        core::iter::once(self.as_char())
+            .chain(caps.chars().filter(|c| !BANDWIDTH_CLASSES.contains(c)))
+            .collect()
+    }
+
+    /// Remove bandwidth class from `caps`.
+    pub fn remove(caps: &str) -> String {
+        caps.chars().filter(|c| !BANDWIDTH_CLASSES.contains(c)).collect()
+    }
+
     /// Attempt to parse [`Bandwidth`] from `caps`.
     pub fn parse(caps: &Str) -> Option<Self> {
         if caps.contains("K") {
@@ -203,4 +247,12 @@ mod tests {
         assert!(!Capabilities::parse(&Str::from("HX")).unwrap().is_reachable());
         assert!(!Capabilities::parse(&Str::from("UL")).unwrap().is_reachable());
     }
+
+    #[test]
+    fn replace_bandwidth() {
+        assert_eq!(Bandwidth::O.replace("XfR"), "OfR");
+        assert_eq!(Bandwidth::K.replace("LU"), "KU");
+        assert_eq!(Bandwidth::P.replace("R"), "PR");
+        assert_eq!(Bandwidth::M.replace(""), "M");
+    }
 }
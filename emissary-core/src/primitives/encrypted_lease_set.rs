@@ -0,0 +1,745 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::{
+    crypto::{
+        blinding::{hkdf, subcredential, BlindedPublicKey, BlindedSigningKey},
+        chachapoly::ChaCha,
+        StaticPrivateKey, StaticPublicKey,
+    },
+    primitives::{LeaseSet2, LeaseSet2Header, OfflineSignature, LOG_TARGET},
+    runtime::Runtime,
+};
+
+use bytes::{BufMut, BytesMut};
+use nom::{
+    bytes::complete::take,
+    error::{make_error, ErrorKind},
+    number::complete::{be_u16, be_u32, be_u8},
+    Err, IResult,
+};
+use rand_core::{CryptoRng, RngCore};
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+/// Signature type of the blinded key, `RedDSA_SHA512_Ed25519`.
+const BLINDED_SIG_TYPE: u16 = 0x000b;
+
+/// Offline signature flag.
+const OFFLINE_SIGNATURE: u16 = 1u16;
+
+/// [`EncryptedLeaseSet`] is unpublished.
+const UNPUBLISHED: u16 = 1u16 << 1;
+
+/// Per-client authentication is enabled.
+const PER_CLIENT_AUTH: u8 = 0x01;
+
+/// DH client authentication scheme.
+const AUTH_SCHEME_DH: u8 = 0x00;
+
+/// PSK client authentication scheme.
+const AUTH_SCHEME_PSK: u8 = 0x01;
+
+/// Length of a client authentication entry, client ID + client cookie.
+const CLIENT_ENTRY_LEN: usize = 8usize + 32usize;
+
+/// Size of the salt prepended to both ciphertext layers.
+const SALT_LEN: usize = 32usize;
+
+/// Store type of `LeaseSet2`, used as the inner type byte.
+const INNER_TYPE_LEASE_SET2: u8 = 3u8;
+
+/// Store type of an encrypted lease set, prepended to the signed data.
+const ENCRYPTED_LEASE_SET_TYPE: u8 = 5u8;
+
+/// Client authentication used when publishing an [`EncryptedLeaseSet`].
+#[derive(Clone)]
+pub enum ClientAuth {
+    /// DH client authentication.
+    Dh {
+        /// X25519 public keys of the authorized clients.
+        clients: Vec<StaticPublicKey>,
+    },
+
+    /// PSK client authentication.
+    Psk {
+        /// Pre-shared keys of the authorized clients.
+        clients: Vec<[u8; 32]>,
+    },
+}
+
+/// Client key used for decrypting an [`EncryptedLeaseSet`] with per-client authentication.
+#[derive(Clone)]
+pub enum ClientKey {
+    /// X25519 private key of the client.
+    Dh(StaticPrivateKey),
+
+    /// Pre-shared key.
+    Psk([u8; 32]),
+}
+
+/// Encrypted lease set.
+///
+/// The outer layer of the encrypted lease set is parsed and its signature is verified but the
+/// lease set itself can only be decrypted by clients who know the unblinded public key of the
+/// destination, the optional lookup password and, if per-client authentication is used, have
+/// a valid client key.
+///
+/// https://geti2p.net/spec/encryptedleaseset
+#[derive(Debug, Clone)]
+pub struct EncryptedLeaseSet {
+    /// Blinded public key.
+    blinded_public_key: BlindedPublicKey,
+
+    /// When was the lease set published, in seconds since UNIX epoch.
+    published: u32,
+
+    /// When does the lease set expire, in seconds since UNIX epoch.
+    expires: u32,
+
+    /// Is the lease set unpublished.
+    is_unpublished: bool,
+
+    /// Outer ciphertext.
+    ciphertext: Vec<u8>,
+}
+
+impl EncryptedLeaseSet {
+    /// Attempt to parse [`EncryptedLeaseSet`] from `input` and verify its signature.
+    ///
+    /// Returns the parsed message and rest of `input` on success.
+    pub fn parse_frame(input: &[u8]) -> IResult<&[u8], Self> {
+        let (rest, sig_type) = be_u16(input)?;
+
+        if sig_type != BLINDED_SIG_TYPE {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?sig_type,
+                "unsupported signature type for encrypted lease set",
+            );
+            return Err(Err::Error(make_error(input, ErrorKind::Fail)));
+        }
+
+        let (rest, blinded_public_key) = take(32usize)(rest)?;
+        let (rest, published) = be_u32(rest)?;
+        let (rest, expires) = be_u16(rest)?;
+        let (rest, flags) = be_u16(rest)?;
+
+        // must succeed since `blinded_public_key` is 32 bytes long
+        let blinded_public_key = BlindedPublicKey::from(
+            TryInto::<[u8; 32]>::try_into(blinded_public_key).expect("to succeed"),
+        );
+        let blinded_verifying_key = blinded_public_key
+            .verifying_key()
+            .ok_or_else(|| Err::Error(make_error(input, ErrorKind::Fail)))?;
+
+        let (rest, verifying_key) = match flags & OFFLINE_SIGNATURE {
+            0 => (rest, blinded_verifying_key),
+            _ => OfflineSignature::parse_frame(rest, &blinded_verifying_key)
+                .map(|(rest, offline_signature)| (rest, offline_signature.verifying_key))?,
+        };
+
+        let (rest, ciphertext_len) = be_u16(rest)?;
+        let (rest, ciphertext) = take(ciphertext_len)(rest)?;
+
+        if ciphertext.len() <= SALT_LEN {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ciphertext_len = ?ciphertext.len(),
+                "encrypted lease set ciphertext is too short",
+            );
+            return Err(Err::Error(make_error(input, ErrorKind::Fail)));
+        }
+
+        let (rest, signature) = take(verifying_key.signature_len())(rest)?;
+
+        let mut bytes = BytesMut::with_capacity(input.len() + 1);
+        bytes.put_u8(ENCRYPTED_LEASE_SET_TYPE);
+        bytes.put_slice(&input[..input.len() - rest.len() - signature.len()]);
+
+        verifying_key.verify(&bytes, signature).map_err(|error| {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?error,
+                "invalid signature for encrypted lease set",
+            );
+
+            Err::Error(make_error(input, ErrorKind::Fail))
+        })?;
+
+        Ok((
+            rest,
+            Self {
+                blinded_public_key,
+                published,
+                expires: published.saturating_add(expires as u32),
+                is_unpublished: flags & UNPUBLISHED != 0,
+                ciphertext: ciphertext.to_vec(),
+            },
+        ))
+    }
+
+    /// Attempt to parse `input` into [`EncryptedLeaseSet`].
+    pub fn parse(input: &[u8]) -> Option<Self> {
+        Some(Self::parse_frame(input).ok()?.1)
+    }
+
+    /// Get reference to the blinded public key of the lease set.
+    pub fn blinded_public_key(&self) -> &BlindedPublicKey {
+        &self.blinded_public_key
+    }
+
+    /// Is the [`EncryptedLeaseSet`] unpublished.
+    pub fn is_unpublished(&self) -> bool {
+        self.is_unpublished
+    }
+
+    /// Get serialized length of [`EncryptedLeaseSet`], excluding the offline signature.
+    pub fn serialized_len(&self) -> usize {
+        // signature type + blinded key + published + expires + flags + ciphertext + signature
+        2usize + 32usize + 4usize + 2usize + 2usize + 2usize + self.ciphertext.len() + 64usize
+    }
+
+    /// Has the [`EncryptedLeaseSet`] expired.
+    pub fn is_expired<R: Runtime>(&self) -> bool {
+        self.expires < R::time_since_epoch().as_secs() as u32
+    }
+
+    /// When does the [`EncryptedLeaseSet`] expire, from seconds since epoch.
+    pub fn expires(&self) -> Duration {
+        Duration::from_secs(self.expires as u64)
+    }
+
+    /// Attempt to decrypt the [`EncryptedLeaseSet`].
+    ///
+    /// `public_key` is the unblinded signing public key of the destination and `client_key` must
+    /// be specified if the lease set uses per-client authentication.
+    ///
+    /// Returns `None` if the lease set couldn't be decrypted or if the decrypted lease set doesn't
+    /// belong to the destination identified by `public_key`.
+    pub fn decrypt(
+        &self,
+        public_key: &[u8; 32],
+        client_key: Option<&ClientKey>,
+    ) -> Option<LeaseSet2> {
+        let subcredential = subcredential(public_key, &self.blinded_public_key);
+        let published = self.published.to_be_bytes();
+
+        // decrypt outer layer
+        let mut layer1 = self.ciphertext[SALT_LEN..].to_vec();
+        {
+            let ikm = [subcredential.as_slice(), &published].concat();
+            let keys = hkdf::<44>(&self.ciphertext[..SALT_LEN], &ikm, b"ELS2_L1K");
+
+            ChaCha::with_iv(
+                TryInto::<[u8; 32]>::try_into(&keys[..32]).expect("to succeed"),
+                TryInto::<[u8; 12]>::try_into(&keys[32..]).expect("to succeed"),
+            )
+            .decrypt_ref(&mut layer1);
+        }
+
+        let (inner, auth_cookie) =
+            Self::parse_auth_data(&layer1, &subcredential, &published, client_key)?;
+
+        if inner.len() <= SALT_LEN {
+            tracing::debug!(
+                target: LOG_TARGET,
+                "encrypted lease set inner ciphertext is too short",
+            );
+            return None;
+        }
+
+        // decrypt inner layer
+        let mut layer2 = inner[SALT_LEN..].to_vec();
+        {
+            let ikm = [
+                auth_cookie.as_ref().map_or(&[][..], |cookie| cookie.as_slice()),
+                subcredential.as_slice(),
+                &published,
+            ]
+            .concat();
+            let keys = hkdf::<44>(&inner[..SALT_LEN], &ikm, b"ELS2_L2K");
+
+            ChaCha::with_iv(
+                TryInto::<[u8; 32]>::try_into(&keys[..32]).expect("to succeed"),
+                TryInto::<[u8; 12]>::try_into(&keys[32..]).expect("to succeed"),
+            )
+            .decrypt_ref(&mut layer2);
+        }
+
+        match layer2.first() {
+            Some(&INNER_TYPE_LEASE_SET2) => {}
+            kind => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?kind,
+                    "unsupported inner lease set type",
+                );
+                return None;
+            }
+        }
+
+        let lease_set = LeaseSet2::parse(&layer2[1..])?;
+
+        if lease_set.header.destination.verifying_key().as_ref() != public_key {
+            tracing::warn!(
+                target: LOG_TARGET,
+                destination_id = %lease_set.header.destination.id(),
+                "encrypted lease set contained a lease set of another destination",
+            );
+            return None;
+        }
+
+        Some(lease_set)
+    }
+
+    /// Parse authorization data from the decrypted outer layer and if per-client authentication
+    /// is used, attempt to find and decrypt the authentication cookie of the client.
+    ///
+    /// Returns the inner ciphertext and the authentication cookie, if per-client authentication
+    /// is used.
+    fn parse_auth_data<'a>(
+        layer1: &'a [u8],
+        subcredential: &[u8; 32],
+        published: &[u8; 4],
+        client_key: Option<&ClientKey>,
+    ) -> Option<(&'a [u8], Option<[u8; 32]>)> {
+        let (rest, flag) = be_u8::<_, ()>(layer1).ok()?;
+
+        if flag & PER_CLIENT_AUTH == 0 {
+            return Some((rest, None));
+        }
+
+        let (rest, key_material) = take::<_, _, ()>(32usize)(rest).ok()?;
+        let (rest, num_clients) = be_u16::<_, ()>(rest).ok()?;
+        let (rest, clients) =
+            take::<_, _, ()>(num_clients as usize * CLIENT_ENTRY_LEN)(rest).ok()?;
+
+        let okm = match (((flag >> 1) & 0x07), client_key) {
+            (AUTH_SCHEME_DH, Some(ClientKey::Dh(private_key))) => {
+                let ephemeral_public_key = StaticPublicKey::from_bytes(key_material)?;
+                let shared_secret = private_key.diffie_hellman(&ephemeral_public_key);
+                let ikm = [
+                    shared_secret.as_slice(),
+                    private_key.public().as_ref(),
+                    subcredential,
+                    published,
+                ]
+                .concat();
+
+                hkdf::<52>(key_material, &ikm, b"ELS2_XCA")
+            }
+            (AUTH_SCHEME_PSK, Some(ClientKey::Psk(psk))) => {
+                let ikm = [psk.as_slice(), subcredential, published].concat();
+
+                hkdf::<52>(key_material, &ikm, b"ELS2PSKA")
+            }
+            (scheme, _) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?scheme,
+                    has_client_key = ?client_key.is_some(),
+                    "cannot decrypt encrypted lease set, no suitable client key",
+                );
+                return None;
+            }
+        };
+
+        let Some(entry) = clients.chunks(CLIENT_ENTRY_LEN).find(|entry| entry[..8] == okm[44..])
+        else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                "client not authorized to decrypt encrypted lease set",
+            );
+            return None;
+        };
+
+        let auth_cookie = ChaCha::with_iv(
+            TryInto::<[u8; 32]>::try_into(&okm[..32]).expect("to succeed"),
+            TryInto::<[u8; 12]>::try_into(&okm[32..44]).expect("to succeed"),
+        )
+        .decrypt(TryInto::<[u8; 32]>::try_into(&entry[8..]).expect("to succeed"));
+
+        Some((rest, Some(auth_cookie)))
+    }
+}
+
+/// [`EncryptedLeaseSet`] builder.
+pub struct EncryptedLeaseSetBuilder<'a> {
+    /// Serialized [`LeaseSet2`].
+    lease_set: &'a [u8],
+
+    /// Client authentication, if enabled.
+    client_auth: Option<&'a ClientAuth>,
+}
+
+impl<'a> EncryptedLeaseSetBuilder<'a> {
+    /// Create new [`EncryptedLeaseSetBuilder`].
+    ///
+    /// `lease_set` must be a serialized and signed [`LeaseSet2`].
+    pub fn new(lease_set: &'a [u8]) -> Self {
+        Self {
+            lease_set,
+            client_auth: None,
+        }
+    }
+
+    /// Specify client authentication.
+    pub fn with_client_auth(mut self, client_auth: &'a ClientAuth) -> Self {
+        self.client_auth = Some(client_auth);
+        self
+    }
+
+    /// Encrypt and sign the lease set and serialize it into a byte vector.
+    ///
+    /// `public_key` is the unblinded signing public key of the destination and `signing_key`
+    /// its blinded signing key for the current day.
+    ///
+    /// Returns `None` if the lease set is malformed.
+    pub fn build(
+        self,
+        public_key: &[u8; 32],
+        signing_key: &BlindedSigningKey,
+        mut csprng: impl RngCore + CryptoRng,
+    ) -> Option<Vec<u8>> {
+        let (_, header) = LeaseSet2Header::parse_frame(self.lease_set).ok()?;
+        let subcredential = subcredential(public_key, signing_key.public());
+        let published = header.published.to_be_bytes();
+        let mut auth_cookie = [0u8; 32];
+
+        // create authorization data for the outer layer
+        let auth_data = match self.client_auth {
+            None => alloc::vec![0u8],
+            Some(client_auth) => {
+                csprng.fill_bytes(&mut auth_cookie);
+
+                let (flag, key_material, entries) = match client_auth {
+                    ClientAuth::Dh { clients } => {
+                        let ephemeral_private_key = StaticPrivateKey::random(&mut csprng);
+                        let ephemeral_public_key = ephemeral_private_key.public();
+
+                        let entries = clients
+                            .iter()
+                            .map(|client| {
+                                let shared_secret = ephemeral_private_key.diffie_hellman(client);
+                                let ikm = [
+                                    shared_secret.as_slice(),
+                                    client.as_ref(),
+                                    &subcredential,
+                                    &published,
+                                ]
+                                .concat();
+
+                                hkdf::<52>(ephemeral_public_key.as_ref(), &ikm, b"ELS2_XCA")
+                            })
+                            .collect::<Vec<_>>();
+
+                        (
+                            PER_CLIENT_AUTH | (AUTH_SCHEME_DH << 1),
+                            ephemeral_public_key.to_vec(),
+                            entries,
+                        )
+                    }
+                    ClientAuth::Psk { clients } => {
+                        let mut auth_salt = [0u8; 32];
+                        csprng.fill_bytes(&mut auth_salt);
+
+                        let entries = clients
+                            .iter()
+                            .map(|psk| {
+                                let ikm = [psk.as_slice(), &subcredential, &published].concat();
+
+                                hkdf::<52>(&auth_salt, &ikm, b"ELS2PSKA")
+                            })
+                            .collect::<Vec<_>>();
+
+                        (
+                            PER_CLIENT_AUTH | (AUTH_SCHEME_PSK << 1),
+                            auth_salt.to_vec(),
+                            entries,
+                        )
+                    }
+                };
+
+                let mut out =
+                    BytesMut::with_capacity(1 + 32 + 2 + entries.len() * CLIENT_ENTRY_LEN);
+                out.put_u8(flag);
+                out.put_slice(&key_material);
+                out.put_u16(entries.len() as u16);
+
+                entries.into_iter().for_each(|okm| {
+                    let mut cookie = auth_cookie;
+
+                    ChaCha::with_iv(
+                        TryInto::<[u8; 32]>::try_into(&okm[..32]).expect("to succeed"),
+                        TryInto::<[u8; 12]>::try_into(&okm[32..44]).expect("to succeed"),
+                    )
+                    .encrypt_ref(&mut cookie);
+
+                    out.put_slice(&okm[44..]);
+                    out.put_slice(&cookie);
+                });
+
+                out.to_vec()
+            }
+        };
+
+        // create inner layer
+        let inner = {
+            let mut salt = [0u8; SALT_LEN];
+            csprng.fill_bytes(&mut salt);
+
+            let ikm = [
+                self.client_auth.map_or(&[][..], |_| auth_cookie.as_slice()),
+                subcredential.as_slice(),
+                &published,
+            ]
+            .concat();
+            let keys = hkdf::<44>(&salt, &ikm, b"ELS2_L2K");
+
+            let mut plaintext = Vec::with_capacity(1 + self.lease_set.len());
+            plaintext.push(INNER_TYPE_LEASE_SET2);
+            plaintext.extend_from_slice(self.lease_set);
+
+            ChaCha::with_iv(
+                TryInto::<[u8; 32]>::try_into(&keys[..32]).expect("to succeed"),
+                TryInto::<[u8; 12]>::try_into(&keys[32..]).expect("to succeed"),
+            )
+            .encrypt_ref(&mut plaintext);
+
+            [salt.as_slice(), &plaintext].concat()
+        };
+
+        // create outer layer
+        let outer = {
+            let mut salt = [0u8; SALT_LEN];
+            csprng.fill_bytes(&mut salt);
+
+            let ikm = [subcredential.as_slice(), &published].concat();
+            let keys = hkdf::<44>(&salt, &ikm, b"ELS2_L1K");
+
+            let mut plaintext = [auth_data.as_slice(), &inner].concat();
+
+            ChaCha::with_iv(
+                TryInto::<[u8; 32]>::try_into(&keys[..32]).expect("to succeed"),
+                TryInto::<[u8; 12]>::try_into(&keys[32..]).expect("to succeed"),
+            )
+            .encrypt_ref(&mut plaintext);
+
+            [salt.as_slice(), &plaintext].concat()
+        };
+
+        if outer.len() > u16::MAX as usize {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ciphertext_len = ?outer.len(),
+                "encrypted lease set is too large",
+            );
+            return None;
+        }
+
+        let mut out = BytesMut::with_capacity(1 + 2 + 32 + 4 + 2 + 2 + 2 + outer.len() + 64);
+        out.put_u8(ENCRYPTED_LEASE_SET_TYPE);
+        out.put_u16(BLINDED_SIG_TYPE);
+        out.put_slice(signing_key.public().as_bytes());
+        out.put_u32(header.published);
+        out.put_u16(header.expires.saturating_sub(header.published).min(u16::MAX as u32) as u16);
+        out.put_u16(if header.is_unpublished {
+            UNPUBLISHED
+        } else {
+            0u16
+        });
+        out.put_u16(outer.len() as u16);
+        out.put_slice(&outer);
+
+        let signature = signing_key.sign(&out, csprng);
+        out.put_slice(&signature);
+
+        Some(out[1..].to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{crypto::SigningPrivateKey, primitives::DestinationId, runtime::mock::MockRuntime};
+
+    fn create_lease_set() -> ([u8; 32], SigningPrivateKey, Vec<u8>) {
+        let (lease_set, signing_key) = LeaseSet2::random();
+        let public_key = signing_key.public().as_ref().try_into().unwrap();
+
+        (
+            public_key,
+            signing_key.clone(),
+            lease_set.serialize(&signing_key),
+        )
+    }
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        let (public_key, signing_key, lease_set) = create_lease_set();
+        let blinded_key = BlindedSigningKey::new(&signing_key, "20250105", None);
+
+        let serialized = EncryptedLeaseSetBuilder::new(&lease_set)
+            .build(&public_key, &blinded_key, rand::thread_rng())
+            .unwrap();
+        let encrypted = EncryptedLeaseSet::parse(&serialized).unwrap();
+
+        assert_eq!(encrypted.blinded_public_key(), blinded_key.public());
+        assert!(!encrypted.is_expired::<MockRuntime>());
+
+        let decrypted = encrypted.decrypt(&public_key, None).unwrap();
+        assert_eq!(
+            decrypted.header.destination.id(),
+            LeaseSet2::parse(&lease_set).unwrap().header.destination.id()
+        );
+
+        // wrong destination
+        let (other_key, _, _) = create_lease_set();
+        assert!(encrypted.decrypt(&other_key, None).is_none());
+    }
+
+    #[test]
+    fn invalid_signature() {
+        let (public_key, signing_key, lease_set) = create_lease_set();
+        let blinded_key = BlindedSigningKey::new(&signing_key, "20250105", Some("secret"));
+
+        let mut serialized = EncryptedLeaseSetBuilder::new(&lease_set)
+            .build(&public_key, &blinded_key, rand::thread_rng())
+            .unwrap();
+        let len = serialized.len();
+        serialized[len - 80] ^= 0xff;
+
+        assert!(EncryptedLeaseSet::parse(&serialized).is_none());
+    }
+
+    #[test]
+    fn dh_client_auth() {
+        let (public_key, signing_key, lease_set) = create_lease_set();
+        let blinded_key = BlindedSigningKey::new(&signing_key, "20250105", None);
+        let clients =
+            (0..3).map(|_| StaticPrivateKey::random(rand::thread_rng())).collect::<Vec<_>>();
+        let client_auth = ClientAuth::Dh {
+            clients: clients[..2].iter().map(|key| key.public()).collect(),
+        };
+
+        let serialized = EncryptedLeaseSetBuilder::new(&lease_set)
+            .with_client_auth(&client_auth)
+            .build(&public_key, &blinded_key, rand::thread_rng())
+            .unwrap();
+        let encrypted = EncryptedLeaseSet::parse(&serialized).unwrap();
+
+        for client in &clients[..2] {
+            assert!(encrypted.decrypt(&public_key, Some(&ClientKey::Dh(client.clone()))).is_some());
+        }
+
+        // unauthorized client and no client key
+        assert!(encrypted
+            .decrypt(&public_key, Some(&ClientKey::Dh(clients[2].clone())))
+            .is_none());
+        assert!(encrypted.decrypt(&public_key, None).is_none());
+    }
+
+    #[test]
+    fn psk_client_auth() {
+        let (public_key, signing_key, lease_set) = create_lease_set();
+        let blinded_key = BlindedSigningKey::new(&signing_key, "20250105", None);
+        let client_auth = ClientAuth::Psk {
+            clients: vec![[1u8; 32], [2u8; 32]],
+        };
+
+        let serialized = EncryptedLeaseSetBuilder::new(&lease_set)
+            .with_client_auth(&client_auth)
+            .build(&public_key, &blinded_key, rand::thread_rng())
+            .unwrap();
+        let encrypted = EncryptedLeaseSet::parse(&serialized).unwrap();
+
+        assert!(encrypted.decrypt(&public_key, Some(&ClientKey::Psk([2u8; 32]))).is_some());
+        assert!(encrypted.decrypt(&public_key, Some(&ClientKey::Psk([3u8; 32]))).is_none());
+        assert!(encrypted
+            .decrypt(
+                &public_key,
+                Some(&ClientKey::Dh(StaticPrivateKey::random(rand::thread_rng())))
+            )
+            .is_none());
+    }
+
+    /// Unblinded public key of the destination of the known-answer vectors.
+    ///
+    /// The vectors were generated with an independent implementation of the specification.
+    const PUBLIC_KEY: [u8; 32] = [
+        0x3c, 0x0c, 0xcf, 0x60, 0x40, 0x62, 0xde, 0xdd, 0x13, 0xdf, 0xec, 0x00, 0x85, 0x6b, 0xd3,
+        0x49, 0xd8, 0xf9, 0xf5, 0x1c, 0xc2, 0x2d, 0x2d, 0xbf, 0xf0, 0xbd, 0x59, 0x78, 0xb9, 0xea,
+        0x07, 0x43,
+    ];
+
+    /// Blinded public key of [`PUBLIC_KEY`] for 2025-01-05.
+    const BLINDED_PUBLIC_KEY: [u8; 32] = [
+        0x86, 0x20, 0xa2, 0xc3, 0xb9, 0x5b, 0xeb, 0xe8, 0x9a, 0x29, 0x0a, 0x60, 0x30, 0x7a, 0x88,
+        0x45, 0x7e, 0xfe, 0x37, 0xaa, 0x24, 0x81, 0x2b, 0x44, 0xeb, 0xda, 0x24, 0x65, 0x69, 0x81,
+        0x85, 0xc4,
+    ];
+
+    /// ID of the destination of the lease set.
+    const DESTINATION_ID: [u8; 32] = [
+        0x41, 0x63, 0x21, 0xbf, 0x48, 0x4a, 0xad, 0xe2, 0x26, 0xfa, 0x62, 0x06, 0xa2, 0xf3, 0x31,
+        0xd0, 0x12, 0xd4, 0xde, 0x47, 0x55, 0xbc, 0xa7, 0x49, 0x60, 0x95, 0xff, 0xf3, 0x28, 0xd3,
+        0x01, 0x5f,
+    ];
+
+    #[test]
+    fn decrypt_known_answer() {
+        let encrypted =
+            EncryptedLeaseSet::parse(include_bytes!("../../test-vectors/els2.dat")).unwrap();
+
+        assert_eq!(
+            encrypted.blinded_public_key().as_bytes(),
+            &BLINDED_PUBLIC_KEY
+        );
+        assert!(!encrypted.is_unpublished());
+
+        let decrypted = encrypted.decrypt(&PUBLIC_KEY, None).unwrap();
+        assert_eq!(
+            decrypted.header.destination.id(),
+            DestinationId::from(DESTINATION_ID)
+        );
+    }
+
+    #[test]
+    fn decrypt_psk_known_answer() {
+        let encrypted =
+            EncryptedLeaseSet::parse(include_bytes!("../../test-vectors/els2-psk.dat")).unwrap();
+
+        assert_eq!(
+            encrypted.blinded_public_key().as_bytes(),
+            &BLINDED_PUBLIC_KEY
+        );
+
+        for psk in [[1u8; 32], [2u8; 32]] {
+            let decrypted = encrypted.decrypt(&PUBLIC_KEY, Some(&ClientKey::Psk(psk))).unwrap();
+            assert_eq!(
+                decrypted.header.destination.id(),
+                DestinationId::from(DESTINATION_ID)
+            );
+        }
+
+        assert!(encrypted.decrypt(&PUBLIC_KEY, Some(&ClientKey::Psk([3u8; 32]))).is_none());
+        assert!(encrypted.decrypt(&PUBLIC_KEY, None).is_none());
+    }
+}
@@ -17,7 +17,7 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    crypto::{SigningPrivateKey, SigningPublicKey, StaticPublicKey},
+    crypto::{SigningPrivateKey, StaticPublicKey},
     primitives::{Destination, Mapping, OfflineSignature, RouterId, TunnelId, LOG_TARGET},
     runtime::Runtime,
 };
@@ -38,6 +38,11 @@ use core::{fmt, iter, time::Duration};
 /// <https://geti2p.net/spec/common-structures#leaseset2header>
 const UNPUBLISHED: u16 = 1u16 << 1;
 
+/// [`LeaseSet2Header`] contains an offline signature.
+///
+/// <https://geti2p.net/spec/common-structures#leaseset2header>
+const OFFLINE_SIGNATURE: u16 = 1u16;
+
 /// Header for [`LeaseSet2`].
 ///
 /// https://geti2p.net/spec/common-structures#leaseset2header
@@ -49,8 +54,8 @@ pub struct LeaseSet2Header {
     /// When [`LeaseSet2`] expires.
     pub expires: u32,
 
-    /// Offline key, if specified.
-    pub offline_signature: Option<SigningPublicKey>,
+    /// Offline signature, if specified.
+    pub offline_signature: Option<OfflineSignature>,
 
     /// When [`LeaseSet2`] was published.
     pub published: u32,
@@ -84,7 +89,7 @@ impl LeaseSet2Header {
         }
 
         // parse and verify offline signature and get key for verifying the lease set's signature
-        let (rest, verifying_key) =
+        let (rest, offline_signature) =
             OfflineSignature::parse_frame(rest, destination.verifying_key())?;
 
         Ok((
@@ -93,7 +98,7 @@ impl LeaseSet2Header {
                 destination,
                 expires: published.saturating_add(expires as u32),
                 is_unpublished: (flags >> 1) & 1 == 1,
-                offline_signature: Some(verifying_key),
+                offline_signature: Some(offline_signature),
                 published,
             },
         ))
@@ -101,8 +106,15 @@ impl LeaseSet2Header {
 
     /// Get serialized length of [`LeaseSet2Header`].
     pub fn serialized_len(&self) -> usize {
-        // destination + published + expires + flags
-        self.destination.serialized_len() + 4usize + 2usize + 2usize
+        // destination + published + expires + flags + offline signature
+        self.destination.serialized_len()
+            + 4usize
+            + 2usize
+            + 2usize
+            + self
+                .offline_signature
+                .as_ref()
+                .map_or(0usize, |signature| signature.serialized_len())
     }
 
     /// Serialize [`LeaseSet2Header`] into a byte vector.
@@ -112,11 +124,21 @@ impl LeaseSet2Header {
         out.put_slice(&self.destination.serialize());
         out.put_u32(self.published);
         out.put_u16(self.expires as u16);
-        out.put_u16(if self.is_unpublished {
-            UNPUBLISHED
-        } else {
-            0u16
-        });
+        out.put_u16(
+            if self.is_unpublished {
+                UNPUBLISHED
+            } else {
+                0u16
+            } | if self.offline_signature.is_some() {
+                OFFLINE_SIGNATURE
+            } else {
+                0u16
+            },
+        );
+
+        if let Some(offline_signature) = &self.offline_signature {
+            out.put_slice(offline_signature.serialize());
+        }
 
         out
     }
@@ -373,8 +395,8 @@ impl LeaseSet2 {
                     Err::Error(make_error(input, ErrorKind::Fail))
                 })?;
             }
-            Some(verifying_key) => {
-                verifying_key.verify(&bytes, signature).map_err(|error| {
+            Some(offline_signature) => {
+                offline_signature.verifying_key.verify(&bytes, signature).map_err(|error| {
                     tracing::warn!(
                         target: LOG_TARGET,
                         ?error,
@@ -1128,6 +1150,39 @@ mod tests {
         assert!(leaseset.header.is_unpublished);
     }
 
+    #[test]
+    fn serialize_and_parse_offline_signature() {
+        let sk = StaticPrivateKey::random(MockRuntime::rng());
+        let sgk = SigningPrivateKey::random(MockRuntime::rng());
+        let transient = SigningPrivateKey::random(MockRuntime::rng());
+        let destination = Destination::new::<MockRuntime>(sgk.public());
+        let offline_signature = OfflineSignature::new(
+            MockRuntime::time_since_epoch() + Duration::from_secs(60 * 60),
+            transient.public(),
+            &sgk,
+        );
+
+        let lease_set = LeaseSet2 {
+            header: LeaseSet2Header {
+                destination: destination.clone(),
+                expires: 1337,
+                is_unpublished: false,
+                offline_signature: Some(offline_signature.clone()),
+                published: 1337,
+            },
+            public_keys: vec![sk.public()],
+            leases: vec![Lease::random()],
+        };
+
+        // lease set signed with the transient key is accepted
+        let parsed = LeaseSet2::parse(&lease_set.clone().serialize(&transient)).unwrap();
+        assert_eq!(parsed.header.destination.id(), destination.id());
+        assert_eq!(parsed.header.offline_signature, Some(offline_signature));
+
+        // lease set signed with the long-term key is rejected
+        assert!(LeaseSet2::parse(&lease_set.serialize(&sgk)).is_none());
+    }
+
     #[test]
     fn lease_set_dsa_sha1() {
         let input = vec![
@@ -107,6 +107,11 @@ impl Mapping {
         self.0.insert(key, value)
     }
 
+    /// Equivalent to `HashMap::remove`
+    pub fn remove(&mut self, key: &Str) -> Option<Str> {
+        self.0.remove(key)
+    }
+
     /// Equivalent to `HashMap::get`
     pub fn get(&self, key: &Str) -> Option<&Str> {
         self.0.get(key)
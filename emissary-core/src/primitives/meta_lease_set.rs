@@ -0,0 +1,379 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Meta lease set.
+//!
+//! Meta lease set doesn't contain leases of its own but instead lists the lease sets of other
+//! destinations, allowing the same service to be hosted by multiple destinations.
+//!
+//! https://geti2p.net/spec/common-structures#struct-metaleaseset
+
+use crate::{
+    crypto::SigningPrivateKey,
+    primitives::{LeaseSet2Header, Mapping, LOG_TARGET},
+    runtime::Runtime,
+};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use nom::{
+    bytes::complete::take,
+    error::{make_error, ErrorKind},
+    number::complete::{be_u32, be_u8},
+    Err, IResult,
+};
+
+use alloc::{collections::BTreeSet, vec::Vec};
+use core::{iter, time::Duration};
+
+/// Store type of [`MetaLeaseSet`], used for signing.
+const META_LEASE_SET_TYPE: u8 = 7u8;
+
+/// Maximum number of entries in a [`MetaLeaseSet`].
+const MAX_ENTRIES: usize = 16usize;
+
+/// Serialized length of [`MetaLease`].
+const META_LEASE_LEN: usize = 40usize;
+
+/// Kind of the lease set [`MetaLease`] points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetaLeaseKind {
+    /// Unknown.
+    Unknown,
+
+    /// Lease set.
+    LeaseSet,
+
+    /// Lease set, type 2.
+    LeaseSet2,
+
+    /// Encrypted lease set.
+    EncryptedLeaseSet,
+
+    /// Meta lease set.
+    MetaLeaseSet,
+}
+
+impl MetaLeaseKind {
+    /// Get [`MetaLeaseKind`] from the flags of a [`MetaLease`].
+    fn from_flags(flags: u8) -> Self {
+        match flags & 0x0f {
+            1 => Self::LeaseSet,
+            3 => Self::LeaseSet2,
+            5 => Self::EncryptedLeaseSet,
+            7 => Self::MetaLeaseSet,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Serialize [`MetaLeaseKind`] into flags.
+    fn as_flags(&self) -> u8 {
+        match self {
+            Self::Unknown => 0u8,
+            Self::LeaseSet => 1u8,
+            Self::LeaseSet2 => 3u8,
+            Self::EncryptedLeaseSet => 5u8,
+            Self::MetaLeaseSet => 7u8,
+        }
+    }
+}
+
+/// Meta lease.
+///
+/// https://geti2p.net/spec/common-structures#struct-metalease
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetaLease {
+    /// Key under which the lease set is stored.
+    pub key: Bytes,
+
+    /// Kind of the lease set.
+    pub kind: MetaLeaseKind,
+
+    /// Cost of the lease set, lower is better.
+    pub cost: u8,
+
+    /// When does the lease expire, as duration since UNIX epoch.
+    pub expires: Duration,
+}
+
+impl MetaLease {
+    /// Attempt to parse [`MetaLease`] from `input`.
+    ///
+    /// Returns the parsed lease and rest of `input` on success.
+    pub fn parse_frame(input: &[u8]) -> IResult<&[u8], Self> {
+        let (rest, key) = take(32usize)(input)?;
+        let (rest, flags) = take(3usize)(rest)?;
+        let (rest, cost) = be_u8(rest)?;
+        let (rest, expires) = be_u32(rest)?;
+
+        Ok((
+            rest,
+            Self {
+                key: Bytes::from(key.to_vec()),
+                kind: MetaLeaseKind::from_flags(flags[2]),
+                cost,
+                expires: Duration::from_secs(expires as u64),
+            },
+        ))
+    }
+
+    /// Serialize [`MetaLease`] into a byte vector.
+    pub fn serialize(&self) -> [u8; META_LEASE_LEN] {
+        let mut out = [0u8; META_LEASE_LEN];
+
+        out[..32].copy_from_slice(&self.key);
+        out[34] = self.kind.as_flags();
+        out[35] = self.cost;
+        out[36..].copy_from_slice(&(self.expires.as_secs() as u32).to_be_bytes());
+
+        out
+    }
+}
+
+/// Meta lease set.
+///
+/// Parsed meta lease set is guaranteed to contain at least one entry. Revocations are not
+/// supported and are ignored.
+#[derive(Debug, Clone)]
+pub struct MetaLeaseSet {
+    /// Header.
+    pub header: LeaseSet2Header,
+
+    /// Entries.
+    pub leases: Vec<MetaLease>,
+}
+
+impl MetaLeaseSet {
+    /// Attempt to parse [`MetaLeaseSet`] from `input`.
+    ///
+    /// Returns the parsed meta lease set and rest of `input` on success.
+    pub fn parse_frame(input: &[u8]) -> IResult<&[u8], Self> {
+        let (rest, header) = LeaseSet2Header::parse_frame(input)?;
+        let (rest, _) = Mapping::parse_frame(rest)?;
+        let (rest, num_leases) = be_u8(rest)?;
+
+        if num_leases as usize > MAX_ENTRIES || num_leases == 0 {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?num_leases,
+                "invalid number of meta leases",
+            );
+
+            return Err(Err::Error(make_error(input, ErrorKind::Fail)));
+        }
+
+        let (rest, leases) = (0..num_leases)
+            .try_fold((rest, Vec::<MetaLease>::new()), |(rest, mut leases), _| {
+                let (rest, lease) = MetaLease::parse_frame(rest).ok()?;
+                leases.push(lease);
+
+                Some((rest, leases))
+            })
+            .ok_or_else(|| {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    "failed to parse meta lease list",
+                );
+
+                Err::Error(make_error(input, ErrorKind::Fail))
+            })?;
+
+        let (rest, num_revocations) = be_u8(rest)?;
+        let (rest, _revocations) = take(num_revocations as usize * 32)(rest)?;
+
+        // verify signature
+        let signature_len = header.destination.verifying_key().signature_len();
+        let (rest, signature) = take(signature_len)(rest)?;
+
+        let mut bytes = BytesMut::with_capacity(input.len() + 1);
+        bytes.put_u8(META_LEASE_SET_TYPE);
+        bytes.put_slice(&input[..input.len() - rest.len() - signature_len]);
+
+        let verifying_key = header
+            .offline_signature
+            .as_ref()
+            .map(|offline_signature| &offline_signature.verifying_key)
+            .unwrap_or_else(|| header.destination.verifying_key());
+
+        verifying_key.verify(&bytes, signature).map_err(|error| {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?error,
+                "invalid signature for meta lease set",
+            );
+
+            Err::Error(make_error(input, ErrorKind::Fail))
+        })?;
+
+        Ok((rest, Self { header, leases }))
+    }
+
+    /// Attempt to parse `input` into [`MetaLeaseSet`].
+    pub fn parse(input: &[u8]) -> Option<Self> {
+        Some(Self::parse_frame(input).ok()?.1)
+    }
+
+    /// Get serialized length of [`MetaLeaseSet`].
+    pub fn serialized_len(&self) -> usize {
+        // header + no options + entries + no revocations + signature
+        self.header.serialized_len()
+            + 2usize
+            + 1usize
+            + self.leases.len() * META_LEASE_LEN
+            + 1usize
+            + 64usize
+    }
+
+    /// Serialize [`MetaLeaseSet`] into a byte vector.
+    ///
+    /// The returned byte vector doesn't contain the store type.
+    pub fn serialize(self, signing_key: &SigningPrivateKey) -> Vec<u8> {
+        let mut out = BytesMut::with_capacity(self.serialized_len() + 1);
+
+        out.put_u8(META_LEASE_SET_TYPE);
+        out.put_slice(&self.header.serialize());
+        out.put_u16(0u16); // no options
+        out.put_u8(self.leases.len() as u8);
+
+        self.leases.iter().for_each(|lease| {
+            out.put_slice(&lease.serialize());
+        });
+
+        out.put_u8(0u8); // no revocations
+
+        let signature = signing_key.sign(&out[..out.len()]);
+        out.put_slice(&signature);
+
+        out[1..].to_vec()
+    }
+
+    /// Has the [`MetaLeaseSet`] expired.
+    pub fn is_expired<R: Runtime>(&self) -> bool {
+        let now = R::time_since_epoch();
+
+        self.header.expires < now.as_secs() as u32
+            || self.leases.iter().all(|lease| lease.expires < now)
+    }
+
+    /// When does the [`MetaLeaseSet`] expire, from seconds since epoch.
+    pub fn expires(&self) -> Duration {
+        // expiration must exist since the header contains an expiration
+        Duration::from_secs(
+            *BTreeSet::from_iter(
+                iter::once(self.header.expires)
+                    .chain(self.leases.iter().map(|lease| lease.expires.as_secs() as u32)),
+            )
+            .first()
+            .expect("expiration to exist") as u64,
+        )
+    }
+
+    /// Get the unexpired entries of [`MetaLeaseSet`] which point to lease sets `emissary` can
+    /// resolve, ordered by cost.
+    pub fn resolvable_leases<R: Runtime>(&self) -> impl Iterator<Item = &MetaLease> {
+        let now = R::time_since_epoch();
+        let mut leases = self
+            .leases
+            .iter()
+            .filter(|lease| {
+                lease.expires >= now
+                    && core::matches!(
+                        lease.kind,
+                        MetaLeaseKind::LeaseSet2 | MetaLeaseKind::MetaLeaseSet
+                    )
+            })
+            .collect::<Vec<_>>();
+
+        leases.sort_by_key(|lease| lease.cost);
+        leases.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        primitives::{Destination, DestinationId},
+        runtime::mock::MockRuntime,
+    };
+
+    fn meta_lease_set(leases: Vec<MetaLease>) -> (MetaLeaseSet, SigningPrivateKey) {
+        let signing_key = SigningPrivateKey::random(MockRuntime::rng());
+
+        (
+            MetaLeaseSet {
+                header: LeaseSet2Header {
+                    destination: Destination::new::<MockRuntime>(signing_key.public()),
+                    expires: 600,
+                    is_unpublished: false,
+                    offline_signature: None,
+                    published: MockRuntime::time_since_epoch().as_secs() as u32,
+                },
+                leases,
+            },
+            signing_key,
+        )
+    }
+
+    fn meta_lease(kind: MetaLeaseKind, cost: u8) -> MetaLease {
+        MetaLease {
+            key: Bytes::from(DestinationId::random().to_vec()),
+            kind,
+            cost,
+            expires: Duration::from_secs(MockRuntime::time_since_epoch().as_secs() + 600),
+        }
+    }
+
+    #[test]
+    fn serialize_and_parse() {
+        let leases = vec![
+            meta_lease(MetaLeaseKind::LeaseSet2, 10),
+            meta_lease(MetaLeaseKind::MetaLeaseSet, 5),
+            meta_lease(MetaLeaseKind::EncryptedLeaseSet, 1),
+        ];
+        let (lease_set, signing_key) = meta_lease_set(leases.clone());
+        let destination_id = lease_set.header.destination.id();
+        let serialized = lease_set.serialize(&signing_key);
+
+        let parsed = MetaLeaseSet::parse(&serialized).unwrap();
+        assert_eq!(parsed.header.destination.id(), destination_id);
+        assert_eq!(parsed.leases, leases);
+        assert!(!parsed.is_expired::<MockRuntime>());
+
+        // encrypted lease sets are not resolvable and leases are ordered by cost
+        assert_eq!(
+            parsed.resolvable_leases::<MockRuntime>().cloned().collect::<Vec<_>>(),
+            vec![leases[1].clone(), leases[0].clone()],
+        );
+    }
+
+    #[test]
+    fn invalid_signature() {
+        let (lease_set, _) = meta_lease_set(vec![meta_lease(MetaLeaseKind::LeaseSet2, 0)]);
+        let serialized = lease_set.serialize(&SigningPrivateKey::random(MockRuntime::rng()));
+
+        assert!(MetaLeaseSet::parse(&serialized).is_none());
+    }
+
+    #[test]
+    fn no_entries() {
+        let (lease_set, signing_key) = meta_lease_set(vec![]);
+        let serialized = lease_set.serialize(&signing_key);
+
+        assert!(MetaLeaseSet::parse(&serialized).is_none());
+    }
+}
@@ -18,13 +18,16 @@
 
 use core::{fmt, ops::Deref};
 
-pub use capabilities::Capabilities;
+pub use blinded_address::BlindedAddress;
+pub use capabilities::{Bandwidth, Capabilities};
 pub use date::Date;
 pub use destination::{Destination, DestinationId};
+pub use encrypted_lease_set::{ClientAuth, ClientKey, EncryptedLeaseSet, EncryptedLeaseSetBuilder};
 pub use lease_set::{Lease, LeaseSet2, LeaseSet2Header};
 pub use mapping::Mapping;
+pub use meta_lease_set::{MetaLease, MetaLeaseKind, MetaLeaseSet};
 pub use offline_signature::OfflineSignature;
-pub use router_address::{RouterAddress, TransportKind};
+pub use router_address::{Introducer, RouterAddress, TransportKind};
 pub use router_identity::{RouterId, RouterIdentity};
 pub use router_info::RouterInfo;
 pub use string::Str;
@@ -32,11 +35,14 @@ pub use string::Str;
 #[cfg(test)]
 pub use router_info::RouterInfoBuilder;
 
+mod blinded_address;
 mod capabilities;
 mod date;
 mod destination;
+mod encrypted_lease_set;
 mod lease_set;
 mod mapping;
+mod meta_lease_set;
 mod offline_signature;
 mod router_address;
 mod router_identity;
@@ -16,8 +16,13 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use crate::{crypto::SigningPublicKey, primitives::LOG_TARGET};
+use crate::{
+    crypto::{SigningPrivateKey, SigningPublicKey},
+    primitives::LOG_TARGET,
+    runtime::Runtime,
+};
 
+use bytes::{BufMut, Bytes, BytesMut};
 use nom::{
     bytes::complete::take,
     error::{make_error, ErrorKind},
@@ -25,6 +30,8 @@ use nom::{
     Err, IResult,
 };
 
+use core::time::Duration;
+
 /// Signature kind for `EdDSA_SHA512_Ed25519`.
 ///
 /// https://geti2p.net/spec/common-structures#key-certificates
@@ -36,18 +43,55 @@ const SIGNATURE_KIND_EDDSA_SHA512_ED25519: u16 = 0x0007;
 const SIGNATURE_KIND_ECDSA_SHA256_P256: u16 = 0x0001;
 
 /// Offline signature.
-pub struct OfflineSignature;
+///
+/// Authorizes a transient signing key to sign on behalf of the destination until `expires`,
+/// allowing the long-term signing key of the destination to be kept offline.
+///
+/// https://geti2p.net/spec/common-structures#offline-signatures
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OfflineSignature {
+    /// When does the offline signature expire, in seconds since UNIX epoch.
+    pub expires: u32,
+
+    /// Transient verifying key.
+    pub verifying_key: SigningPublicKey,
+
+    /// Serialized offline signature.
+    serialized: Bytes,
+}
 
 impl OfflineSignature {
+    /// Create new [`OfflineSignature`] which authorizes `verifying_key` to sign on behalf of the
+    /// destination of `signing_key` until `expires`.
+    ///
+    /// Only Ed25519 transient keys are supported.
+    pub fn new(
+        expires: Duration,
+        verifying_key: SigningPublicKey,
+        signing_key: &SigningPrivateKey,
+    ) -> Self {
+        let mut out = BytesMut::with_capacity(6 + 32 + 64);
+
+        out.put_u32(expires.as_secs() as u32);
+        out.put_u16(SIGNATURE_KIND_EDDSA_SHA512_ED25519);
+        out.put_slice(verifying_key.as_ref());
+
+        let signature = signing_key.sign(&out);
+        out.put_slice(&signature);
+
+        Self {
+            expires: expires.as_secs() as u32,
+            verifying_key,
+            serialized: out.freeze(),
+        }
+    }
+
     /// Attempt to parse [`OfflineSignature`] from `input` and verify the signature using `key`
-    pub fn parse_frame<'a>(
-        input: &'a [u8],
-        key: &SigningPublicKey,
-    ) -> IResult<&'a [u8], SigningPublicKey> {
+    pub fn parse_frame<'a>(input: &'a [u8], key: &SigningPublicKey) -> IResult<&'a [u8], Self> {
         // save start of the signed segment so the offline signature can be verified
         let signed_segment = input;
 
-        let (rest, _expires) = be_u32(input)?;
+        let (rest, expires) = be_u32(input)?;
         let (rest, signature_kind) = be_u16(rest)?;
 
         // extract verifying key from the offline signature
@@ -85,7 +129,7 @@ impl OfflineSignature {
         // extract offline signature and verify it with the destination's verifying key
         //
         // the signed portion covers expiration + signature kind + verifying key
-        let (rest, signature) = take(verifying_key.signature_len())(rest)?;
+        let (rest, signature) = take(key.signature_len())(rest)?;
 
         key.verify(&signed_segment[..(6 + verifying_key_len)], signature)
             .map_err(|error| {
@@ -98,6 +142,89 @@ impl OfflineSignature {
                 Err::Error(make_error(input, ErrorKind::Fail))
             })?;
 
-        Ok((rest, verifying_key))
+        Ok((
+            rest,
+            Self {
+                expires,
+                verifying_key,
+                serialized: Bytes::from(input[..input.len() - rest.len()].to_vec()),
+            },
+        ))
+    }
+
+    /// Get serialized length of [`OfflineSignature`].
+    pub fn serialized_len(&self) -> usize {
+        self.serialized.len()
+    }
+
+    /// Serialize [`OfflineSignature`].
+    pub fn serialize(&self) -> &[u8] {
+        &self.serialized
+    }
+
+    /// Has the [`OfflineSignature`] expired.
+    pub fn is_expired<R: Runtime>(&self) -> bool {
+        (self.expires as u64) < R::time_since_epoch().as_secs()
+    }
+
+    /// How long is the [`OfflineSignature`] still valid.
+    ///
+    /// Returns `None` if the offline signature has expired.
+    pub fn valid_for<R: Runtime>(&self) -> Option<Duration> {
+        Duration::from_secs(self.expires as u64).checked_sub(R::time_since_epoch())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::mock::MockRuntime;
+
+    #[test]
+    fn create_and_parse() {
+        let signing_key = SigningPrivateKey::random(MockRuntime::rng());
+        let transient_key = SigningPrivateKey::random(MockRuntime::rng());
+        let expires = MockRuntime::time_since_epoch() + Duration::from_secs(60 * 60);
+
+        let offline = OfflineSignature::new(expires, transient_key.public(), &signing_key);
+        assert_eq!(offline.serialized_len(), 6 + 32 + 64);
+        assert!(!offline.is_expired::<MockRuntime>());
+        assert!(offline.valid_for::<MockRuntime>().unwrap() <= Duration::from_secs(60 * 60));
+
+        let (rest, parsed) =
+            OfflineSignature::parse_frame(offline.serialize(), &signing_key.public()).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parsed, offline);
+        assert_eq!(parsed.verifying_key, transient_key.public());
+    }
+
+    #[test]
+    fn invalid_signature() {
+        let signing_key = SigningPrivateKey::random(MockRuntime::rng());
+        let transient_key = SigningPrivateKey::random(MockRuntime::rng());
+        let offline = OfflineSignature::new(
+            MockRuntime::time_since_epoch(),
+            transient_key.public(),
+            &signing_key,
+        );
+
+        assert!(OfflineSignature::parse_frame(
+            offline.serialize(),
+            &SigningPrivateKey::random(MockRuntime::rng()).public()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn expired() {
+        let signing_key = SigningPrivateKey::random(MockRuntime::rng());
+        let offline = OfflineSignature::new(
+            MockRuntime::time_since_epoch() - Duration::from_secs(10),
+            SigningPrivateKey::random(MockRuntime::rng()).public(),
+            &signing_key,
+        );
+
+        assert!(offline.is_expired::<MockRuntime>());
+        assert!(offline.valid_for::<MockRuntime>().is_none());
     }
 }
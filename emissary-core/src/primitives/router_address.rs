@@ -17,8 +17,8 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    crypto::{base64_encode, StaticPrivateKey},
-    primitives::{Date, Mapping, Str},
+    crypto::{base64_decode, base64_encode, StaticPrivateKey},
+    primitives::{Date, Mapping, RouterId, Str},
 };
 
 use bytes::{BufMut, BytesMut};
@@ -28,48 +28,90 @@ use nom::{
     Err, IResult,
 };
 
-use alloc::{string::ToString, vec::Vec};
+use alloc::{format, string::ToString, vec::Vec};
 use core::{
-    net::{IpAddr, Ipv4Addr, SocketAddr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     str::FromStr,
 };
 
 /// Transport kind.
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
 pub enum TransportKind {
-    /// NTCP2.
-    Ntcp2,
+    /// NTCP2 over IPv4.
+    Ntcp2V4,
 
-    /// SSU2.
-    Ssu2,
+    /// NTCP2 over IPv6.
+    Ntcp2V6,
+
+    /// SSU2 over IPv4.
+    Ssu2V4,
+
+    /// SSU2 over IPv6.
+    Ssu2V6,
 }
 
-impl TryFrom<Str> for TransportKind {
-    type Error = ();
+impl TransportKind {
+    /// Resolve [`TransportKind`] from transport's name and the address options.
+    ///
+    /// The address family is determined by the published host and if the address doesn't have a
+    /// host, by the `caps` option of the address. Addresses which don't specify the address family
+    /// at all are considered IPv4 addresses.
+    fn resolve(name: &Str, options: &Mapping, socket_address: Option<SocketAddr>) -> Option<Self> {
+        let ipv6 = match socket_address {
+            Some(address) => address.is_ipv6(),
+            None => options
+                .get(&Str::from("caps"))
+                .is_some_and(|caps| caps.contains('6') && !caps.contains('4')),
+        };
 
-    fn try_from(value: Str) -> Result<Self, Self::Error> {
-        if value.starts_with("SSU") {
-            return Ok(TransportKind::Ssu2);
+        if name.starts_with("SSU") {
+            return Some(if ipv6 { Self::Ssu2V6 } else { Self::Ssu2V4 });
         }
 
-        if value.starts_with("NTCP2") {
-            return Ok(TransportKind::Ntcp2);
+        if name.starts_with("NTCP2") {
+            return Some(if ipv6 { Self::Ntcp2V6 } else { Self::Ntcp2V4 });
         }
 
-        Err(())
+        None
+    }
+
+    /// Is the transport NTCP2.
+    pub fn is_ntcp2(&self) -> bool {
+        core::matches!(self, Self::Ntcp2V4 | Self::Ntcp2V6)
+    }
+
+    /// Is the transport used over IPv6.
+    pub fn is_ipv6(&self) -> bool {
+        core::matches!(self, Self::Ntcp2V6 | Self::Ssu2V6)
     }
-}
 
-impl TransportKind {
     /// Serialize [`TransportKind`].
     fn serialize(&self) -> Vec<u8> {
         match self {
-            Self::Ntcp2 => Str::from("NTCP2").serialize(),
-            Self::Ssu2 => Str::from("SSU2").serialize(),
+            Self::Ntcp2V4 | Self::Ntcp2V6 => Str::from("NTCP2").serialize(),
+            Self::Ssu2V4 | Self::Ssu2V6 => Str::from("SSU2").serialize(),
         }
     }
 }
 
+/// Maximum number of introducers published for an SSU2 address.
+const MAX_INTRODUCERS: usize = 3usize;
+
+/// SSU2 introducer.
+///
+/// Introducer acts as Bob for relayed connections to a router that is not directly reachable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Introducer {
+    /// ID of the introducer.
+    pub router_id: RouterId,
+
+    /// Relay tag issued by the introducer.
+    pub tag: u32,
+
+    /// When the introducer expires, seconds since UNIX epoch.
+    pub expires: u32,
+}
+
 /// Router address.
 #[derive(Debug, Clone)]
 pub struct RouterAddress {
@@ -102,14 +144,14 @@ impl RouterAddress {
         Self {
             cost: 14,
             expires: Date::new(0),
-            transport: TransportKind::Ntcp2,
+            transport: TransportKind::Ntcp2V4,
             socket_address: Some(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)),
             options,
         }
     }
 
-    /// Create new unpublished NTCP2 [`RouterAddress`].
-    pub fn new_published_ntcp2(key: [u8; 32], iv: [u8; 16], port: u16, host: Ipv4Addr) -> Self {
+    /// Create new published NTCP2 [`RouterAddress`].
+    pub fn new_published_ntcp2(key: [u8; 32], iv: [u8; 16], port: u16, host: IpAddr) -> Self {
         let static_key = StaticPrivateKey::from(key).public();
 
         let mut options = Mapping::default();
@@ -122,9 +164,12 @@ impl RouterAddress {
         Self {
             cost: 3,
             expires: Date::new(0),
-            transport: TransportKind::Ntcp2,
+            transport: match host {
+                IpAddr::V4(_) => TransportKind::Ntcp2V4,
+                IpAddr::V6(_) => TransportKind::Ntcp2V6,
+            },
             options,
-            socket_address: Some(SocketAddr::new(IpAddr::V4(host), port)),
+            socket_address: Some(SocketAddr::new(host, port)),
         }
     }
 
@@ -150,18 +195,18 @@ impl RouterAddress {
         Self {
             cost: 14,
             expires: Date::new(0),
-            transport: TransportKind::Ssu2,
+            transport: TransportKind::Ssu2V4,
             socket_address: Some(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)),
             options,
         }
     }
 
-    /// Create new unpublished SSU2 [`RouterAddress`].
+    /// Create new published SSU2 [`RouterAddress`].
     pub fn new_published_ssu2(
         static_key: [u8; 32],
         intro_key: [u8; 32],
         port: u16,
-        host: Ipv4Addr,
+        host: IpAddr,
     ) -> Self {
         let static_key = {
             let static_key = StaticPrivateKey::from(static_key).public();
@@ -182,12 +227,101 @@ impl RouterAddress {
         options.insert(Str::from("host"), Str::from(host.to_string()));
         options.insert(Str::from("port"), Str::from(port.to_string()));
 
+        // published addresses can act as bob/charlie in peer tests and as introducers
+        options.insert(Str::from("caps"), Str::from("BC"));
+
         Self {
             cost: 8,
             expires: Date::new(0),
-            transport: TransportKind::Ssu2,
+            transport: match host {
+                IpAddr::V4(_) => TransportKind::Ssu2V4,
+                IpAddr::V6(_) => TransportKind::Ssu2V6,
+            },
             options,
-            socket_address: Some(SocketAddr::new(IpAddr::V4(host), port)),
+            socket_address: Some(SocketAddr::new(host, port)),
+        }
+    }
+
+    /// Specify which address families the router supports for outbound connections.
+    ///
+    /// Only relevant for unpublished addresses as the address family of a published address is
+    /// determined by its host. If IPv6 is not supported, the `caps` option is left out and the
+    /// address is interpreted as an IPv4 address.
+    pub fn with_address_families(mut self, ipv4: bool, ipv6: bool) -> Self {
+        let caps = match (ipv4, ipv6) {
+            (true, true) => "46",
+            (false, true) => "6",
+            (_, false) => return self,
+        };
+        self.options.insert(Str::from("caps"), Str::from(caps));
+
+        if !ipv4 {
+            self.transport = match self.transport {
+                TransportKind::Ntcp2V4 | TransportKind::Ntcp2V6 => TransportKind::Ntcp2V6,
+                TransportKind::Ssu2V4 | TransportKind::Ssu2V6 => TransportKind::Ssu2V6,
+            };
+            self.socket_address = self
+                .socket_address
+                .map(|address| SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), address.port()));
+        }
+
+        self
+    }
+
+    /// Returns `true` if the router supports acting as Bob or Charlie in SSU2 peer tests.
+    pub fn supports_peer_test(&self) -> bool {
+        self.options.get(&Str::from("caps")).is_some_and(|caps| caps.contains('B'))
+    }
+
+    /// Returns `true` if the router supports acting as an introducer for SSU2 relays.
+    pub fn supports_relay(&self) -> bool {
+        self.options.get(&Str::from("caps")).is_some_and(|caps| caps.contains('C'))
+    }
+
+    /// Get introducers published for the address.
+    ///
+    /// Introducers with missing or malformed options are ignored.
+    pub fn introducers(&self) -> Vec<Introducer> {
+        (0..MAX_INTRODUCERS)
+            .filter_map(|i| {
+                let router_id = self.options.get(&Str::from(format!("ih{i}")))?;
+                let router_id = base64_decode(router_id.as_bytes())?;
+                let tag = self.options.get(&Str::from(format!("itag{i}")))?.parse::<u32>().ok()?;
+                let expires =
+                    self.options.get(&Str::from(format!("iexp{i}")))?.parse::<u32>().ok()?;
+
+                (router_id.len() == 32).then(|| Introducer {
+                    router_id: RouterId::from(router_id),
+                    tag,
+                    expires,
+                })
+            })
+            .collect()
+    }
+
+    /// Replace the introducers of the address with `introducers`.
+    ///
+    /// At most three introducers are published.
+    pub fn set_introducers(&mut self, introducers: &[Introducer]) {
+        for i in 0..MAX_INTRODUCERS {
+            self.options.remove(&Str::from(format!("ih{i}")));
+            self.options.remove(&Str::from(format!("itag{i}")));
+            self.options.remove(&Str::from(format!("iexp{i}")));
+        }
+
+        for (i, introducer) in introducers.iter().take(MAX_INTRODUCERS).enumerate() {
+            self.options.insert(
+                Str::from(format!("ih{i}")),
+                Str::from(base64_encode(introducer.router_id.to_vec())),
+            );
+            self.options.insert(
+                Str::from(format!("itag{i}")),
+                Str::from(introducer.tag.to_string()),
+            );
+            self.options.insert(
+                Str::from(format!("iexp{i}")),
+                Str::from(introducer.expires.to_string()),
+            );
         }
     }
 
@@ -220,8 +354,8 @@ impl RouterAddress {
             RouterAddress {
                 cost,
                 expires,
-                transport: TransportKind::try_from(transport)
-                    .map_err(|_| Err::Error(make_error(input, ErrorKind::Fail)))?,
+                transport: TransportKind::resolve(&transport, &options, socket_address)
+                    .ok_or_else(|| Err::Error(make_error(input, ErrorKind::Fail)))?,
                 options,
                 socket_address,
             },
@@ -355,4 +489,110 @@ mod tests {
             Some(&Str::from("8888"))
         );
     }
+
+    #[test]
+    fn serialize_deserialize_published_ipv6() {
+        let serialized = RouterAddress::new_published_ntcp2(
+            [1u8; 32],
+            [0xaa; 16],
+            8888,
+            "2001:db8::1".parse().unwrap(),
+        )
+        .serialize();
+
+        let address = RouterAddress::parse(&serialized).unwrap();
+        assert_eq!(address.transport, TransportKind::Ntcp2V6);
+        assert_eq!(
+            address.socket_address,
+            Some("[2001:db8::1]:8888".parse().unwrap())
+        );
+        assert_eq!(
+            address.options.get(&Str::from("host")),
+            Some(&Str::from("2001:db8::1"))
+        );
+
+        let serialized = RouterAddress::new_published_ssu2(
+            [1u8; 32],
+            [2u8; 32],
+            8888,
+            "2001:db8::1".parse().unwrap(),
+        )
+        .serialize();
+
+        let address = RouterAddress::parse(&serialized).unwrap();
+        assert_eq!(address.transport, TransportKind::Ssu2V6);
+        assert_eq!(
+            address.socket_address,
+            Some("[2001:db8::1]:8888".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn unpublished_address_families() {
+        // no caps, interpreted as ipv4
+        let serialized = RouterAddress::new_unpublished_ntcp2([1u8; 32], 8888)
+            .with_address_families(true, false)
+            .serialize();
+        let address = RouterAddress::parse(&serialized).unwrap();
+        assert_eq!(address.transport, TransportKind::Ntcp2V4);
+        assert!(address.options.get(&Str::from("caps")).is_none());
+
+        // ipv4 and ipv6
+        let serialized = RouterAddress::new_unpublished_ssu2([1u8; 32], [2u8; 32], 8888)
+            .with_address_families(true, true)
+            .serialize();
+        let address = RouterAddress::parse(&serialized).unwrap();
+        assert_eq!(address.transport, TransportKind::Ssu2V4);
+        assert_eq!(
+            address.options.get(&Str::from("caps")),
+            Some(&Str::from("46"))
+        );
+
+        // ipv6 only
+        let serialized = RouterAddress::new_unpublished_ntcp2([1u8; 32], 8888)
+            .with_address_families(false, true)
+            .serialize();
+        let address = RouterAddress::parse(&serialized).unwrap();
+        assert_eq!(address.transport, TransportKind::Ntcp2V6);
+        assert_eq!(
+            address.options.get(&Str::from("caps")),
+            Some(&Str::from("6"))
+        );
+    }
+
+    #[test]
+    fn serialize_deserialize_introducers() {
+        let introducers = (0..4)
+            .map(|i| Introducer {
+                router_id: RouterId::random(),
+                tag: 1337 + i,
+                expires: 1_000_000 + i,
+            })
+            .collect::<Vec<_>>();
+
+        let mut address = RouterAddress::new_unpublished_ssu2([1u8; 32], [2u8; 32], 8888);
+        assert!(address.introducers().is_empty());
+        assert!(!address.supports_relay());
+
+        // only three introducers are published
+        address.set_introducers(&introducers);
+        let address = RouterAddress::parse(address.serialize()).unwrap();
+        assert_eq!(address.introducers(), introducers[..3]);
+
+        // old introducers are removed
+        let mut address = address;
+        address.set_introducers(&introducers[3..]);
+        let address = RouterAddress::parse(address.serialize()).unwrap();
+        assert_eq!(address.introducers(), introducers[3..]);
+        assert!(address.options.get(&Str::from("ih1")).is_none());
+
+        let address = RouterAddress::new_published_ssu2(
+            [1u8; 32],
+            [2u8; 32],
+            8888,
+            "127.0.0.1".parse().unwrap(),
+        );
+        assert!(address.supports_relay());
+        assert!(address.supports_peer_test());
+    }
 }
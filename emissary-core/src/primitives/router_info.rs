@@ -34,7 +34,10 @@ use nom::{
     Err, IResult,
 };
 
-use alloc::{string::ToString, vec::Vec};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
 
 /// Signature length.
 const SIGNATURE_LEN: usize = 64usize;
@@ -64,18 +67,15 @@ pub struct RouterInfo {
 impl RouterInfo {
     /// Create new [`RouterInfo`].
     ///
-    /// `ntcp2` is `Some` if NTCP has been enabled.
+    /// `addresses` contains the addresses of all enabled transports.
     pub fn new<R: Runtime>(
         config: &Config,
-        ntcp2: Option<RouterAddress>,
-        ssu2: Option<RouterAddress>,
+        addresses: Vec<RouterAddress>,
         static_key: &StaticPrivateKey,
         signing_key: &SigningPrivateKey,
         transit_tunnels_disabled: bool,
     ) -> Self {
-        let Config {
-            caps, router_info, ..
-        } = config;
+        let Config { router_info, .. } = config;
 
         let identity = match router_info {
             None => {
@@ -99,32 +99,22 @@ impl RouterInfo {
 
         let caps = match transit_tunnels_disabled {
             true => Str::from("G"),
-            false => match caps {
-                Some(caps) => Str::from(caps.clone()),
-                None => match config.floodfill {
-                    true => Str::from("Xf"),
-                    false => Str::from("L"),
-                },
-            },
+            false => {
+                let caps = Self::configured_caps(config);
+
+                // if bandwidth has been limited, the bandwidth class is derived from the limits
+                match config.bandwidth.as_ref().and_then(|config| config.bandwidth_class()) {
+                    Some(bandwidth) => Str::from(bandwidth.replace(&caps)),
+                    None => Str::from(caps),
+                }
+            }
         };
 
         options.insert(Str::from("router.version"), Str::from("0.9.62"));
         options.insert(Str::from("caps"), caps.clone());
 
         RouterInfo {
-            addresses: {
-                let mut addresses = HashMap::<TransportKind, RouterAddress>::new();
-
-                if let Some(ntcp2) = ntcp2 {
-                    addresses.insert(TransportKind::Ntcp2, ntcp2);
-                }
-
-                if let Some(ssu2) = ssu2 {
-                    addresses.insert(TransportKind::Ssu2, ssu2);
-                }
-
-                addresses
-            },
+            addresses: addresses.into_iter().map(|address| (address.transport, address)).collect(),
             capabilities: Capabilities::parse(&caps).expect("to succeed"),
             identity,
             net_id: config.net_id.unwrap_or(2),
@@ -133,6 +123,18 @@ impl RouterInfo {
         }
     }
 
+    /// Get capabilities configured for the router, before the bandwidth class is derived from
+    /// the bandwidth limits.
+    pub(crate) fn configured_caps(config: &Config) -> String {
+        match &config.caps {
+            Some(caps) => caps.clone(),
+            None => match config.floodfill {
+                true => String::from("Xf"),
+                false => String::from("L"),
+            },
+        }
+    }
+
     fn parse_frame(input: &[u8]) -> IResult<&[u8], RouterInfo> {
         let (rest, identity) = RouterIdentity::parse_frame(input)?;
         let (rest, published) = Date::parse_frame(rest)?;
@@ -241,18 +243,26 @@ impl RouterInfo {
     pub fn serialize(&self, signing_key: &SigningPrivateKey) -> Vec<u8> {
         let identity = self.identity.serialize();
         let published = self.published.serialize();
-        let maybe_ntcp2 =
-            self.addresses.get(&TransportKind::Ntcp2).map(|address| address.serialize());
-        let maybe_ssu2 =
-            self.addresses.get(&TransportKind::Ssu2).map(|address| address.serialize());
+        let addresses = [
+            TransportKind::Ntcp2V4,
+            TransportKind::Ntcp2V6,
+            TransportKind::Ssu2V4,
+            TransportKind::Ssu2V6,
+        ]
+        .into_iter()
+        .filter_map(|kind| self.addresses.get(&kind).map(|address| address.serialize()))
+        .collect::<Vec<_>>();
         let options = self.options.serialize();
 
+        if addresses.is_empty() {
+            panic!("tried to publish router info with no addresses");
+        }
+
         let size = identity
             .len()
             .saturating_add(published.len())
             .saturating_add(1usize) // field for router address count
-            .saturating_add(maybe_ntcp2.as_ref().map_or(0usize, |address| address.len()))
-            .saturating_add(maybe_ssu2.as_ref().map_or(0usize, |address| address.len()))
+            .saturating_add(addresses.iter().map(|address| address.len()).sum::<usize>())
             .saturating_add(options.len())
             .saturating_add(1usize) // psize
             .saturating_add(64usize); // signature
@@ -262,18 +272,8 @@ impl RouterInfo {
         out.put_slice(&identity);
         out.put_slice(&published);
 
-        match (maybe_ntcp2, maybe_ssu2) {
-            (Some(ntcp2), Some(ssu2)) => {
-                out.put_u8(2u8);
-                out.put_slice(&ntcp2);
-                out.put_slice(&ssu2);
-            }
-            (Some(info), None) | (None, Some(info)) => {
-                out.put_u8(1u8);
-                out.put_slice(&info);
-            }
-            (None, None) => panic!("tried to publish router info with no addresses"),
-        }
+        out.put_u8(addresses.len() as u8);
+        addresses.iter().for_each(|address| out.put_slice(address));
 
         out.put_u8(0u8); // psize
         out.put_slice(&options);
@@ -303,23 +303,10 @@ impl RouterInfo {
             return false;
         }
 
-        if let Some(ntcp2) = self.addresses.get(&TransportKind::Ntcp2) {
-            if ntcp2.options.get(&Str::from("host")).is_some()
-                && ntcp2.options.get(&Str::from("port")).is_some()
-            {
-                return true;
-            }
-        }
-
-        if let Some(ssu2) = self.addresses.get(&TransportKind::Ssu2) {
-            if ssu2.options.get(&Str::from("host")).is_some()
-                && ssu2.options.get(&Str::from("port")).is_some()
-            {
-                return true;
-            }
-        }
-
-        false
+        self.addresses.values().any(|address| {
+            address.options.get(&Str::from("host")).is_some()
+                && address.options.get(&Str::from("port")).is_some()
+        })
     }
 
     /// Is the router usable.
@@ -334,20 +321,78 @@ impl RouterInfo {
         self.net_id
     }
 
-    /// Check if the router is reachable via NTCP2.
-    pub fn is_reachable_ntcp2(&self) -> bool {
-        let Some(ntcp2) = self.addresses.get(&TransportKind::Ntcp2) else {
-            return false;
+    /// Get NTCP2 address of the router which can be dialed over the enabled address families.
+    ///
+    /// IPv4 address is preferred if the router is reachable over both IPv4 and IPv6.
+    pub fn ntcp2_address(&self, ipv4: bool, ipv6: bool) -> Option<&RouterAddress> {
+        self.dialable_address(
+            (ipv4, TransportKind::Ntcp2V4),
+            (ipv6, TransportKind::Ntcp2V6),
+        )
+    }
+
+    /// Get SSU2 address of the router which can be dialed over the enabled address families.
+    ///
+    /// IPv4 address is preferred if the router is reachable over both IPv4 and IPv6.
+    pub fn ssu2_address(&self, ipv4: bool, ipv6: bool) -> Option<&RouterAddress> {
+        self.dialable_address((ipv4, TransportKind::Ssu2V4), (ipv6, TransportKind::Ssu2V6))
+    }
+
+    /// Get SSU2 address of the router which can be dialed through an introducer over the enabled
+    /// address families.
+    ///
+    /// Returns `None` if the router has an SSU2 address which can be dialed directly.
+    pub fn ssu2_introduced_address(&self, ipv4: bool, ipv6: bool) -> Option<&RouterAddress> {
+        if self.ssu2_address(ipv4, ipv6).is_some() {
+            return None;
+        }
+
+        [(ipv4, TransportKind::Ssu2V4), (ipv6, TransportKind::Ssu2V6)]
+            .into_iter()
+            .filter_map(|(enabled, kind)| enabled.then_some(kind))
+            .filter_map(|kind| self.addresses.get(&kind))
+            .find(|address| {
+                !address.introducers().is_empty()
+                    && address.options.get(&Str::from("i")).is_some()
+                    && address.options.get(&Str::from("s")).is_some()
+            })
+    }
+
+    /// Get the first enabled address which has a socket address and the keys needed to dial it.
+    fn dialable_address(
+        &self,
+        ipv4: (bool, TransportKind),
+        ipv6: (bool, TransportKind),
+    ) -> Option<&RouterAddress> {
+        [ipv4, ipv6]
+            .into_iter()
+            .filter_map(|(enabled, kind)| enabled.then_some(kind))
+            .filter_map(|kind| self.addresses.get(&kind))
+            .find(|address| {
+                address.socket_address.is_some()
+                    && address.options.get(&Str::from("i")).is_some()
+                    && address.options.get(&Str::from("s")).is_some()
+            })
+    }
+
+    /// Get value of `key` from the first address of the transport which specifies it.
+    ///
+    /// All addresses of a transport share the same keys regardless of their address family.
+    fn transport_option(&self, ntcp2: bool, key: &'static str) -> Option<&Str> {
+        let kinds = match ntcp2 {
+            true => [TransportKind::Ntcp2V4, TransportKind::Ntcp2V6],
+            false => [TransportKind::Ssu2V4, TransportKind::Ssu2V6],
         };
 
-        ntcp2.socket_address.is_some()
-            && ntcp2.options.get(&Str::from("i")).is_some()
-            && ntcp2.options.get(&Str::from("s")).is_some()
+        kinds
+            .iter()
+            .filter_map(|kind| self.addresses.get(kind))
+            .find_map(|address| address.options.get(&Str::from(key)))
     }
 
     /// Attempt to get SSU2 intro key from [`RouterInfo`]
     pub fn ssu2_intro_key(&self) -> Option<[u8; 32]> {
-        let intro_key = self.addresses.get(&TransportKind::Ssu2)?.options.get(&Str::from("i"))?;
+        let intro_key = self.transport_option(false, "i")?;
         let intro_key = base64_decode(intro_key.as_bytes())?;
 
         TryInto::<[u8; 32]>::try_into(intro_key).ok()
@@ -355,7 +400,7 @@ impl RouterInfo {
 
     /// Attempt to get SSU2 static key from [`RouterInfo`].
     pub fn ssu2_static_key(&self) -> Option<StaticPublicKey> {
-        let static_key = self.addresses.get(&TransportKind::Ssu2)?.options.get(&Str::from("s"))?;
+        let static_key = self.transport_option(false, "s")?;
         let static_key = base64_decode(static_key.as_bytes())?;
 
         StaticPublicKey::from_bytes(&static_key)
@@ -363,7 +408,7 @@ impl RouterInfo {
 
     /// Attempt to get NTCP2 static key from [`RouterInfo`].
     pub fn ntcp2_static_key(&self) -> Option<StaticPublicKey> {
-        let static_key = self.addresses.get(&TransportKind::Ntcp2)?.options.get(&Str::from("s"))?;
+        let static_key = self.transport_option(true, "s")?;
         let static_key = base64_decode(static_key.as_bytes())?;
 
         StaticPublicKey::from_bytes(&static_key)
@@ -371,7 +416,7 @@ impl RouterInfo {
 
     /// Attempt to get NTCP2 IV from [`RouterInfo`].
     pub fn ntcp2_iv(&self) -> Option<[u8; 16]> {
-        let iv = self.addresses.get(&TransportKind::Ntcp2)?.options.get(&Str::from("i"))?;
+        let iv = self.transport_option(true, "i")?;
         let iv = base64_decode(iv.as_bytes())?;
 
         TryInto::<[u8; 16]>::try_into(iv).ok()
@@ -423,6 +468,7 @@ impl RouterInfoBuilder {
     /// Build [`RouterInfoBuilder`] into a [`RouterInfo].
     pub fn build(&mut self) -> (RouterInfo, StaticPrivateKey, SigningPrivateKey) {
         use crate::{runtime::mock::MockRuntime, Ntcp2Config, Ssu2Config};
+        use core::net::IpAddr;
         use rand_core::RngCore;
 
         let static_key = match self.static_key.take() {
@@ -436,39 +482,70 @@ impl RouterInfoBuilder {
         let identity = RouterIdentity::from_keys::<MockRuntime>(&static_key, &signing_key)
             .expect("to succeed");
 
-        let mut ntcp2 = match self.ntcp2.take() {
-            None => None,
-            Some(Ntcp2Config {
-                port,
-                host,
-                publish,
-                key,
-                iv,
-            }) => match (publish, host) {
-                (true, Some(host)) => Some(RouterAddress::new_published_ntcp2(key, iv, port, host)),
-                (_, _) => Some(RouterAddress::new_unpublished_ntcp2(key, port)),
-            },
-        };
-        let mut ssu2 = match self.ssu2.take() {
-            None => None,
-            Some(Ssu2Config {
-                port,
-                host,
-                publish,
-                static_key,
-                intro_key,
-            }) => match (publish, host) {
-                (true, Some(host)) => Some(RouterAddress::new_published_ssu2(
-                    static_key, intro_key, port, host,
-                )),
-                (_, _) => Some(RouterAddress::new_unpublished_ssu2(
-                    static_key, intro_key, port,
-                )),
-            },
-        };
+        let mut addresses = Vec::<RouterAddress>::new();
+
+        if let Some(Ntcp2Config {
+            port,
+            ipv4_host,
+            ipv6_host,
+            ipv4,
+            ipv6,
+            publish,
+            key,
+            iv,
+        }) = self.ntcp2.take()
+        {
+            let published = [
+                ipv4_host.filter(|_| ipv4).map(IpAddr::V4),
+                ipv6_host.filter(|_| ipv6).map(IpAddr::V6),
+            ]
+            .into_iter()
+            .flatten()
+            .filter(|_| publish)
+            .map(|host| RouterAddress::new_published_ntcp2(key, iv, port, host))
+            .collect::<Vec<_>>();
+
+            match published.is_empty() {
+                true => addresses.push(
+                    RouterAddress::new_unpublished_ntcp2(key, port)
+                        .with_address_families(ipv4, ipv6),
+                ),
+                false => addresses.extend(published),
+            }
+        }
+
+        if let Some(Ssu2Config {
+            port,
+            ipv4_host,
+            ipv6_host,
+            ipv4,
+            ipv6,
+            publish,
+            static_key,
+            intro_key,
+        }) = self.ssu2.take()
+        {
+            let published = [
+                ipv4_host.filter(|_| ipv4).map(IpAddr::V4),
+                ipv6_host.filter(|_| ipv6).map(IpAddr::V6),
+            ]
+            .into_iter()
+            .flatten()
+            .filter(|_| publish)
+            .map(|host| RouterAddress::new_published_ssu2(static_key, intro_key, port, host))
+            .collect::<Vec<_>>();
+
+            match published.is_empty() {
+                true => addresses.push(
+                    RouterAddress::new_unpublished_ssu2(static_key, intro_key, port)
+                        .with_address_families(ipv4, ipv6),
+                ),
+                false => addresses.extend(published),
+            }
+        }
 
         // create default ntcp2 transport if neither transport was explicitly enabled
-        if ntcp2.is_none() && ssu2.is_none() {
+        if addresses.is_empty() {
             let ntcp2_port = MockRuntime::rng().next_u32() as u16;
             let ntcp2_host = format!(
                 "{}.{}.{}.{}",
@@ -498,7 +575,7 @@ impl RouterInfoBuilder {
                 iv_bytes
             };
 
-            ntcp2 = Some(RouterAddress::new_published_ntcp2(
+            addresses.push(RouterAddress::new_published_ntcp2(
                 ntcp2_key,
                 ntcp2_iv,
                 ntcp2_port,
@@ -518,19 +595,12 @@ impl RouterInfoBuilder {
             Capabilities::parse(&Str::from("L")).expect("to succeed")
         };
 
-        let mut addresses = HashMap::<TransportKind, RouterAddress>::new();
-
-        if let Some(ntcp2) = ntcp2.take() {
-            addresses.insert(TransportKind::Ntcp2, ntcp2);
-        }
-
-        if let Some(ssu2) = ssu2.take() {
-            addresses.insert(TransportKind::Ssu2, ssu2);
-        }
-
         (
             RouterInfo {
-                addresses,
+                addresses: addresses
+                    .into_iter()
+                    .map(|address| (address.transport, address))
+                    .collect(),
                 capabilities,
                 identity,
                 net_id: 2,
@@ -558,13 +628,13 @@ mod tests {
 
         // ssu
         assert_eq!(
-            router_info.addresses.get(&TransportKind::Ssu2).unwrap().cost,
+            router_info.addresses.get(&TransportKind::Ssu2V4).unwrap().cost,
             5
         );
         assert_eq!(
             router_info
                 .addresses
-                .get(&TransportKind::Ssu2)
+                .get(&TransportKind::Ssu2V4)
                 .unwrap()
                 .options
                 .get(&Str::from_str("host").unwrap()),
@@ -573,7 +643,7 @@ mod tests {
         assert_eq!(
             router_info
                 .addresses
-                .get(&TransportKind::Ssu2)
+                .get(&TransportKind::Ssu2V4)
                 .unwrap()
                 .options
                 .get(&Str::from_str("port").unwrap()),
@@ -582,14 +652,14 @@ mod tests {
 
         // ntcp2
         assert_eq!(
-            router_info.addresses.get(&TransportKind::Ntcp2).unwrap().cost,
+            router_info.addresses.get(&TransportKind::Ntcp2V4).unwrap().cost,
             11
         );
 
         assert_eq!(
             router_info
                 .addresses
-                .get(&TransportKind::Ntcp2)
+                .get(&TransportKind::Ntcp2V4)
                 .unwrap()
                 .options
                 .get(&Str::from("host")),
@@ -598,7 +668,7 @@ mod tests {
         assert_eq!(
             router_info
                 .addresses
-                .get(&TransportKind::Ntcp2)
+                .get(&TransportKind::Ntcp2V4)
                 .unwrap()
                 .options
                 .get(&Str::from_str("port").unwrap()),
@@ -625,22 +695,36 @@ mod tests {
         let router_info_bytes = include_bytes!("../../test-vectors/router2.dat");
         let router_info = RouterInfo::parse(router_info_bytes).unwrap();
 
-        assert_eq!(router_info.addresses.len(), 2);
+        assert_eq!(router_info.addresses.len(), 4);
+
+        // unpublished ipv6 addresses
+        assert!(router_info
+            .addresses
+            .get(&TransportKind::Ntcp2V6)
+            .unwrap()
+            .socket_address
+            .is_none());
+        assert!(router_info
+            .addresses
+            .get(&TransportKind::Ssu2V6)
+            .unwrap()
+            .socket_address
+            .is_none());
 
         // ssu
         assert_eq!(
-            router_info.addresses.get(&TransportKind::Ssu2).unwrap().cost,
+            router_info.addresses.get(&TransportKind::Ssu2V4).unwrap().cost,
             8,
         );
         // ntcp2
         assert_eq!(
-            router_info.addresses.get(&TransportKind::Ntcp2).unwrap().cost,
+            router_info.addresses.get(&TransportKind::Ntcp2V4).unwrap().cost,
             3
         );
         assert_eq!(
             router_info
                 .addresses
-                .get(&TransportKind::Ntcp2)
+                .get(&TransportKind::Ntcp2V4)
                 .unwrap()
                 .options
                 .get(&Str::from_str("host").unwrap()),
@@ -649,7 +733,7 @@ mod tests {
         assert_eq!(
             router_info
                 .addresses
-                .get(&TransportKind::Ntcp2)
+                .get(&TransportKind::Ntcp2V4)
                 .unwrap()
                 .options
                 .get(&Str::from_str("port").unwrap()),
@@ -701,7 +785,7 @@ mod tests {
                 (MockRuntime::time_since_epoch() - Duration::from_secs(60)).as_millis() as u64,
             ),
             addresses: HashMap::from_iter([(
-                TransportKind::Ntcp2,
+                TransportKind::Ntcp2V4,
                 RouterAddress::new_published_ntcp2(
                     [1u8; 32],
                     [2u8; 16],
@@ -728,7 +812,7 @@ mod tests {
                 (MockRuntime::time_since_epoch() - Duration::from_secs(60)).as_millis() as u64,
             ),
             addresses: HashMap::from_iter([(
-                TransportKind::Ntcp2,
+                TransportKind::Ntcp2V4,
                 RouterAddress::new_published_ntcp2(
                     [1u8; 32],
                     [2u8; 16],
@@ -755,7 +839,7 @@ mod tests {
                 (MockRuntime::time_since_epoch() - Duration::from_secs(60)).as_millis() as u64,
             ),
             addresses: HashMap::from_iter([(
-                TransportKind::Ntcp2,
+                TransportKind::Ntcp2V4,
                 RouterAddress::new_published_ntcp2(
                     [1u8; 32],
                     [2u8; 16],
@@ -785,7 +869,7 @@ mod tests {
                 (MockRuntime::time_since_epoch() - Duration::from_secs(60)).as_millis() as u64,
             ),
             addresses: HashMap::from_iter([(
-                TransportKind::Ntcp2,
+                TransportKind::Ntcp2V4,
                 RouterAddress::new_unpublished_ntcp2([1u8; 32], 8888),
             )]),
             options: Mapping::from_iter([
@@ -810,7 +894,7 @@ mod tests {
                 (MockRuntime::time_since_epoch() - Duration::from_secs(60)).as_millis() as u64,
             ),
             addresses: HashMap::from_iter([(
-                TransportKind::Ntcp2,
+                TransportKind::Ntcp2V4,
                 RouterAddress::new_unpublished_ntcp2([1u8; 32], 8888),
             )]),
             options: Mapping::from_iter([
@@ -835,7 +919,7 @@ mod tests {
                 (MockRuntime::time_since_epoch() - Duration::from_secs(60)).as_millis() as u64,
             ),
             addresses: HashMap::from_iter([(
-                TransportKind::Ntcp2,
+                TransportKind::Ntcp2V4,
                 RouterAddress::new_published_ntcp2(
                     [1u8; 32],
                     [2u8; 16],
@@ -866,7 +950,7 @@ mod tests {
                 (MockRuntime::time_since_epoch() - Duration::from_secs(60)).as_millis() as u64,
             ),
             addresses: HashMap::from_iter([(
-                TransportKind::Ntcp2,
+                TransportKind::Ntcp2V4,
                 RouterAddress::new_published_ntcp2(
                     [1u8; 32],
                     [2u8; 16],
@@ -897,11 +981,11 @@ mod tests {
             ),
             addresses: HashMap::from_iter([
                 (
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_unpublished_ntcp2([1u8; 32], 8888),
                 ),
                 (
-                    TransportKind::Ssu2,
+                    TransportKind::Ssu2V4,
                     RouterAddress::new_published_ssu2(
                         [1u8; 32],
                         [2u8; 32],
@@ -933,7 +1017,7 @@ mod tests {
             ),
             addresses: HashMap::from_iter([
                 (
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_published_ntcp2(
                         [1u8; 32],
                         [2u8; 16],
@@ -942,7 +1026,7 @@ mod tests {
                     ),
                 ),
                 (
-                    TransportKind::Ssu2,
+                    TransportKind::Ssu2V4,
                     RouterAddress::new_published_ssu2(
                         [1u8; 32],
                         [2u8; 32],
@@ -974,11 +1058,11 @@ mod tests {
             ),
             addresses: HashMap::from_iter([
                 (
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_unpublished_ntcp2([1u8; 32], 8888),
                 ),
                 (
-                    TransportKind::Ssu2,
+                    TransportKind::Ssu2V4,
                     RouterAddress::new_unpublished_ssu2([1u8; 32], [2u8; 32], 8888),
                 ),
             ]),
@@ -993,4 +1077,43 @@ mod tests {
 
         assert!(!RouterInfo::parse(&serialized).unwrap().is_reachable());
     }
+
+    #[test]
+    fn bandwidth_class_derived_from_limits() {
+        let static_key = StaticPrivateKey::random(rand::thread_rng());
+        let signing_key = SigningPrivateKey::random(rand::thread_rng());
+        let caps = |bandwidth: Option<crate::BandwidthConfig>| {
+            let router_info = RouterInfo::new::<MockRuntime>(
+                &Config {
+                    bandwidth,
+                    caps: Some(String::from("XR")),
+                    ..Default::default()
+                },
+                Vec::new(),
+                &static_key,
+                &signing_key,
+                false,
+            );
+
+            router_info.options.get(&Str::from("caps")).unwrap().to_string()
+        };
+
+        assert_eq!(caps(None), "XR");
+        assert_eq!(
+            caps(Some(crate::BandwidthConfig {
+                inbound: Some(256),
+                outbound: Some(200),
+                share: None,
+            })),
+            "OR"
+        );
+        assert_eq!(
+            caps(Some(crate::BandwidthConfig {
+                inbound: None,
+                outbound: None,
+                share: Some(50),
+            })),
+            "XR"
+        );
+    }
 }
@@ -17,6 +17,7 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
+    blocklist::Blocklist,
     crypto::{base64_decode, base64_encode},
     primitives::{RouterId, RouterInfo},
     runtime::Runtime,
@@ -227,6 +228,9 @@ impl Reader<'_> {
 /// Profile storage.
 #[derive(Clone)]
 pub struct ProfileStorage<R: Runtime> {
+    /// Blocklist.
+    blocklist: Blocklist,
+
     /// Discovered routers.
     discovered_routers: Arc<RwLock<HashMap<RouterId, Vec<u8>>>>,
 
@@ -252,6 +256,18 @@ pub struct ProfileStorage<R: Runtime> {
 impl<R: Runtime> ProfileStorage<R> {
     /// Create new [`ProfileStorage`].
     pub fn new(routers: &[Vec<u8>], profiles: &[(String, Profile)]) -> Self {
+        Self::with_blocklist(routers, profiles, Blocklist::default())
+    }
+
+    /// Create new [`ProfileStorage`] which doesn't accept routers blocked by `blocklist`.
+    ///
+    /// Routers blocked after they've been added to [`ProfileStorage`] are not removed but they're
+    /// not returned by [`ProfileStorage::get_router_ids()`].
+    pub fn with_blocklist(
+        routers: &[Vec<u8>],
+        profiles: &[(String, Profile)],
+        blocklist: Blocklist,
+    ) -> Self {
         tracing::info!(
             target: LOG_TARGET,
             num_routers = ?routers.len(),
@@ -264,6 +280,7 @@ impl<R: Runtime> ProfileStorage<R> {
             .filter_map(|router| {
                 RouterInfo::parse(router).map(|router| (router.identity.id(), router))
             })
+            .filter(|(_, router_info)| !blocklist.is_blocked(router_info))
             .collect::<HashMap<_, _>>();
 
         let mut profiles = profiles
@@ -403,6 +420,7 @@ impl<R: Runtime> ProfileStorage<R> {
         };
 
         let storage = Self {
+            blocklist,
             discovered_routers: Default::default(),
             fast: Arc::new(RwLock::new(fast)),
             profiles: Arc::new(RwLock::new(profiles)),
@@ -418,9 +436,20 @@ impl<R: Runtime> ProfileStorage<R> {
     }
 
     /// Insert `router` into [`ProfileStorage`].
+    ///
+    /// Returns `false` if the router is blocked.
     pub fn add_router(&self, router_info: RouterInfo) -> bool {
         let router_id = router_info.identity.id();
 
+        if self.blocklist.is_blocked(&router_info) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %router_id,
+                "router is blocked, ignoring",
+            );
+            return false;
+        }
+
         {
             let mut fast = self.fast.write();
             let mut standard = self.standard.write();
@@ -460,6 +489,11 @@ impl<R: Runtime> ProfileStorage<R> {
         self.routers.read().len()
     }
 
+    /// Get the number of fast routers currently stored in [`ProfileStorage`].
+    pub fn num_fast_routers(&self) -> usize {
+        self.fast.read().len()
+    }
+
     // TODO: remove
     // TODO: why?
     pub fn get(&self, router: &RouterId) -> Option<RouterInfo> {
@@ -494,7 +528,9 @@ impl<R: Runtime> ProfileStorage<R> {
                         let profile = profiles.get(router_id).expect("to exist");
                         let router_info = routers.get(router_id).expect("to exist");
 
-                        filter(router_id, router_info, profile).then_some(router_id.clone())
+                        (!self.blocklist.is_blocked(router_info)
+                            && filter(router_id, router_info, profile))
+                        .then_some(router_id.clone())
                     })
                     .collect()
             }
@@ -508,7 +544,9 @@ impl<R: Runtime> ProfileStorage<R> {
                         let profile = profiles.get(router_id).expect("to exist");
                         let router_info = routers.get(router_id).expect("to exist");
 
-                        filter(router_id, router_info, profile).then_some(router_id.clone())
+                        (!self.blocklist.is_blocked(router_info)
+                            && filter(router_id, router_info, profile))
+                        .then_some(router_id.clone())
                     })
                     .collect()
             }
@@ -521,7 +559,9 @@ impl<R: Runtime> ProfileStorage<R> {
                         let profile = profiles.get(router_id).expect("to exist");
                         let router_info = routers.get(router_id).expect("to exist");
 
-                        filter(router_id, router_info, profile).then_some(router_id.clone())
+                        (!self.blocklist.is_blocked(router_info)
+                            && filter(router_id, router_info, profile))
+                        .then_some(router_id.clone())
                     })
                     .collect()
             }
@@ -535,7 +575,9 @@ impl<R: Runtime> ProfileStorage<R> {
                         let profile = profiles.get(router_id).expect("to exist");
                         let router_info = routers.get(router_id).expect("to exist");
 
-                        filter(router_id, router_info, profile).then_some(router_id.clone())
+                        (!self.blocklist.is_blocked(router_info)
+                            && filter(router_id, router_info, profile))
+                        .then_some(router_id.clone())
                     })
                     .collect()
             }
@@ -758,6 +800,7 @@ impl<R: Runtime> ProfileStorage<R> {
             .unzip();
 
         Self {
+            blocklist: Blocklist::default(),
             discovered_routers: Default::default(),
             fast: Arc::new(RwLock::new(fast.into_iter().flatten().collect())),
             profiles: Arc::new(RwLock::new(profiles)),
@@ -875,7 +918,10 @@ impl<R: Runtime> ProfileManager<R> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{crypto::base64_encode, primitives::RouterInfoBuilder, runtime::mock::MockRuntime};
+    use crate::{
+        blocklist::BlocklistEntry, crypto::base64_encode, primitives::RouterInfoBuilder,
+        runtime::mock::MockRuntime,
+    };
 
     #[tokio::test]
     async fn initialize_with_infos_without_profiles() {
@@ -1012,4 +1058,47 @@ mod tests {
             1usize
         );
     }
+
+    #[tokio::test]
+    async fn blocked_routers_ignored() {
+        let blocklist = Blocklist::new();
+        let (infos, serialized): (Vec<_>, Vec<_>) = (0..3)
+            .map(|_| {
+                let (info, _, sgn_key) = RouterInfoBuilder::default().build();
+                let serialized = info.serialize(&sgn_key);
+
+                (info, serialized)
+            })
+            .unzip();
+        blocklist.update(
+            "local",
+            vec![BlocklistEntry::Router(infos[0].identity.id())],
+        );
+
+        // blocked router is not loaded
+        let profiles = ProfileStorage::<MockRuntime>::with_blocklist(
+            &serialized,
+            &Vec::new(),
+            blocklist.clone(),
+        );
+        assert!(!profiles.contains(&infos[0].identity.id()));
+        assert!(profiles.contains(&infos[1].identity.id()));
+
+        // router blocked after it was added is not selected
+        blocklist.update(
+            "local",
+            vec![
+                BlocklistEntry::Router(infos[0].identity.id()),
+                BlocklistEntry::Router(infos[1].identity.id()),
+            ],
+        );
+        let router_ids = profiles.get_router_ids(Bucket::Any, |_, _, _| true);
+        assert_eq!(router_ids, vec![infos[2].identity.id()]);
+
+        // blocked router is not added
+        let (info, _, _) = RouterInfoBuilder::default().build();
+        blocklist.update("feed", vec![BlocklistEntry::Router(info.identity.id())]);
+        assert!(!profiles.add_router(info.clone()));
+        assert!(!profiles.contains(&info.identity.id()));
+    }
 }
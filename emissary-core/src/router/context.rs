@@ -17,6 +17,8 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
+    bandwidth::BandwidthLimiter,
+    blocklist::Blocklist,
     crypto::{SigningPrivateKey, StaticPrivateKey},
     events::EventHandle,
     primitives::RouterId,
@@ -68,6 +70,12 @@ struct InnerRouterContext<R: Runtime> {
 /// Passed onto different subsystems of emissary.
 #[derive(Clone)]
 pub struct RouterContext<R: Runtime> {
+    /// Bandwidth limiter.
+    bandwidth_limiter: BandwidthLimiter<R>,
+
+    /// Blocklist.
+    blocklist: Blocklist,
+
     /// Router context.
     inner: Arc<InnerRouterContext<R>>,
 
@@ -91,6 +99,8 @@ impl<R: Runtime> RouterContext<R> {
         event_handle: EventHandle<R>,
     ) -> Self {
         Self {
+            bandwidth_limiter: BandwidthLimiter::default(),
+            blocklist: Blocklist::default(),
             event_handle,
             inner: Arc::new(InnerRouterContext {
                 metrics_handle,
@@ -105,6 +115,22 @@ impl<R: Runtime> RouterContext<R> {
         }
     }
 
+    /// Specify [`BandwidthLimiter`].
+    ///
+    /// If not specified, bandwidth is not limited.
+    pub(crate) fn with_bandwidth_limiter(mut self, bandwidth_limiter: BandwidthLimiter<R>) -> Self {
+        self.bandwidth_limiter = bandwidth_limiter;
+        self
+    }
+
+    /// Specify [`Blocklist`].
+    ///
+    /// If not specified, no addresses or routers are blocked.
+    pub(crate) fn with_blocklist(mut self, blocklist: Blocklist) -> Self {
+        self.blocklist = blocklist;
+        self
+    }
+
     /// Get copy of serialized local [`RouterInfo`].
     ///
     /// Note that the returned [`RouterInfo`] is uncompressed.
@@ -152,4 +178,14 @@ impl<R: Runtime> RouterContext<R> {
     pub(crate) fn event_handle(&self) -> &EventHandle<R> {
         &self.event_handle
     }
+
+    /// Get reference to [`BandwidthLimiter`].
+    pub(crate) fn bandwidth_limiter(&self) -> &BandwidthLimiter<R> {
+        &self.bandwidth_limiter
+    }
+
+    /// Get reference to [`Blocklist`].
+    pub(crate) fn blocklist(&self) -> &Blocklist {
+        &self.blocklist
+    }
 }
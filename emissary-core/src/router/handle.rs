@@ -0,0 +1,188 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Router handle.
+//!
+//! [`RouterHandle`] allows querying and controlling a running [`Router`](super::Router) from
+//! outside of the future that drives it, e.g., from a control server.
+
+use crate::{
+    bandwidth::BandwidthLimiter,
+    config::BandwidthConfig,
+    events::{EventHandle, FirewallStatus},
+    primitives::RouterInfo,
+    profile::ProfileStorage,
+    router::LOG_TARGET,
+    runtime::Runtime,
+};
+
+use bytes::Bytes;
+use thingbuf::mpsc::Sender;
+
+use alloc::{sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Command sent from [`RouterHandle`] to [`Router`](super::Router).
+///
+/// The default command only re-derives the bandwidth class which is idempotent, so it's safe to
+/// use as the value of empty channel slots.
+#[derive(Debug, Default, Clone)]
+pub(super) enum RouterCommand {
+    /// Start graceful shutdown.
+    Shutdown,
+
+    /// Shut down the router immediately.
+    ShutdownImmediately,
+
+    /// Bandwidth limits have been updated.
+    ///
+    /// The bandwidth class is re-derived from the new limits and if it changed, the local router
+    /// info is republished.
+    #[default]
+    BandwidthUpdated,
+}
+
+/// Status of the router.
+#[derive(Debug, Clone, Default)]
+pub struct RouterStatus {
+    /// Firewall status of the router.
+    pub firewall_status: FirewallStatus,
+
+    /// Total number of bytes received by all transports.
+    pub num_bytes_received: usize,
+
+    /// Total number of bytes sent by all transports.
+    pub num_bytes_sent: usize,
+
+    /// Number of connected routers.
+    pub num_connected_routers: usize,
+
+    /// Number of fast routers in profile storage.
+    pub num_fast_routers: usize,
+
+    /// Number of routers in profile storage.
+    pub num_known_routers: usize,
+
+    /// Number of transit tunnels.
+    pub num_transit_tunnels: usize,
+
+    /// Has shutdown been requested.
+    pub shutting_down: bool,
+}
+
+/// Router handle.
+#[derive(Clone)]
+pub struct RouterHandle<R: Runtime> {
+    /// Bandwidth limiter.
+    bandwidth_limiter: BandwidthLimiter<R>,
+
+    /// TX channel for sending commands to [`Router`](super::Router).
+    command_tx: Sender<RouterCommand>,
+
+    /// Event handle.
+    event_handle: EventHandle<R>,
+
+    /// Profile storage.
+    profile_storage: ProfileStorage<R>,
+
+    /// Has shutdown been requested.
+    shutting_down: Arc<AtomicBool>,
+}
+
+impl<R: Runtime> RouterHandle<R> {
+    /// Create new [`RouterHandle`].
+    pub(super) fn new(
+        bandwidth_limiter: BandwidthLimiter<R>,
+        command_tx: Sender<RouterCommand>,
+        event_handle: EventHandle<R>,
+        profile_storage: ProfileStorage<R>,
+        shutting_down: Arc<AtomicBool>,
+    ) -> Self {
+        Self {
+            bandwidth_limiter,
+            command_tx,
+            event_handle,
+            profile_storage,
+            shutting_down,
+        }
+    }
+
+    /// Get current status of the router.
+    pub fn status(&self) -> RouterStatus {
+        let (transit, transport, _) = self.event_handle.snapshot();
+
+        RouterStatus {
+            firewall_status: transport.firewall_status,
+            num_bytes_received: self.bandwidth_limiter.num_received(),
+            num_bytes_sent: self.bandwidth_limiter.num_sent(),
+            num_connected_routers: transport.num_connected_routers,
+            num_fast_routers: self.profile_storage.num_fast_routers(),
+            num_known_routers: self.profile_storage.num_routers(),
+            num_transit_tunnels: transit.num_tunnels,
+            shutting_down: self.shutting_down.load(Ordering::Acquire),
+        }
+    }
+
+    /// Start graceful shutdown of the router.
+    pub fn shutdown(&self) {
+        if let Err(error) = self.command_tx.try_send(RouterCommand::Shutdown) {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?error,
+                "failed to send shutdown command",
+            );
+        }
+    }
+
+    /// Shut down the router immediately.
+    pub fn shutdown_immediately(&self) {
+        if let Err(error) = self.command_tx.try_send(RouterCommand::ShutdownImmediately) {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?error,
+                "failed to send immediate shutdown command",
+            );
+        }
+    }
+
+    /// Update bandwidth limits of the router.
+    ///
+    /// The new limits take effect immediately and the bandwidth class derived from them is
+    /// published in a new router info.
+    pub fn set_bandwidth(&self, config: Option<BandwidthConfig>) {
+        self.bandwidth_limiter.update(config.as_ref());
+
+        if let Err(error) = self.command_tx.try_send(RouterCommand::BandwidthUpdated) {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?error,
+                "failed to send bandwidth update command",
+            );
+        }
+    }
+
+    /// Add serialized router info to profile storage, e.g., after reseeding.
+    ///
+    /// Returns `false` if `router_info` is invalid or was rejected by profile storage.
+    pub fn add_router(&self, router_info: Vec<u8>) -> bool {
+        match RouterInfo::parse(&router_info) {
+            Some(parsed) => self.profile_storage.discover_router(parsed, Bytes::from(router_info)),
+            None => false,
+        }
+    }
+}
@@ -17,15 +17,16 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
+    bandwidth::BandwidthLimiter,
     config::{Config, I2cpConfig, MetricsConfig, SamConfig},
     crypto::{SigningPrivateKey, StaticPrivateKey},
     error::Error,
     events::{EventManager, EventSubscriber},
     i2cp::I2cpServer,
     netdb::NetDb,
-    primitives::RouterInfo,
+    primitives::{Bandwidth, RouterInfo, Str},
     profile::ProfileStorage,
-    router::context::RouterContext,
+    router::{context::RouterContext, handle::RouterCommand},
     runtime::{AddressBook, Runtime, Storage},
     sam::SamServer,
     shutdown::ShutdownContext,
@@ -37,18 +38,23 @@ use crate::{
 use bytes::Bytes;
 use futures::FutureExt;
 use rand_core::RngCore;
+use thingbuf::mpsc::{channel, Receiver};
 
 use alloc::{string::ToString, sync::Arc, vec::Vec};
 use core::{
     future::Future,
     marker::PhantomData,
-    net::{Ipv4Addr, SocketAddr},
+    net::{IpAddr, SocketAddr},
     pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
     task::{Context, Poll},
     time::Duration,
 };
 
 pub mod context;
+mod handle;
+
+pub use handle::{RouterHandle, RouterStatus};
 
 /// Logging target for the file.
 const LOG_TARGET: &str = "emissary::router";
@@ -60,6 +66,9 @@ const NET_ID: u8 = 2u8;
 /// immediately, cancelling graceful shutdown.
 const IMMEDIATE_SHUTDOWN_COUNT: usize = 2usize;
 
+/// Size of the command channel between [`RouterHandle`] and [`Router`].
+const COMMAND_CHANNEL_SIZE: usize = 16usize;
+
 /// Profile storage backup interval.
 ///
 /// How often is backup (stored to disk) taken of [`ProfileStorage`].
@@ -132,9 +141,18 @@ pub struct Router<R: Runtime> {
     /// Protocol address information.
     address_info: ProtocolAddressInfo,
 
+    /// RX channel for receiving commands from [`RouterHandle`].
+    command_rx: Receiver<RouterCommand>,
+
     /// Event manager
     event_manager: EventManager<R>,
 
+    /// Router handle.
+    handle: RouterHandle<R>,
+
+    /// Has shutdown been requested.
+    shutting_down: Arc<AtomicBool>,
+
     /// Shutdown context.
     shutdown_context: ShutdownContext<R>,
 
@@ -163,11 +181,11 @@ impl<R: Runtime> Router<R> {
         // this is done prior to constructing local router info in case ntcp2 config contained an
         // unspecified port, meaning the actual socket address of the transport is available only
         // after the listener has been created
-        let (ntcp2_context, ntcp2_address) =
+        let (ntcp2_context, ntcp2_addresses) =
             Ntcp2Transport::<R>::initialize(config.ntcp2.take()).await?;
 
         // attempt to initialize the ssu2 transport from provided config
-        let (ssu2_context, ssu2_address) =
+        let (ssu2_context, ssu2_addresses) =
             Ssu2Transport::<R>::initialize(config.ssu2.take()).await?;
 
         if ntcp2_context.is_none() && ssu2_context.is_none() {
@@ -192,10 +210,11 @@ impl<R: Runtime> Router<R> {
             key
         }));
 
+        // bandwidth class that is published if bandwidth limits are removed while running
+        let default_bandwidth = Bandwidth::parse(&Str::from(RouterInfo::configured_caps(&config)));
         let local_router_info = RouterInfo::new::<R>(
             &config,
-            ntcp2_address,
-            ssu2_address,
+            ntcp2_addresses.into_iter().chain(ssu2_addresses).collect(),
             &local_static_key,
             &local_signing_key,
             config.transit.is_none(),
@@ -213,10 +232,14 @@ impl<R: Runtime> Router<R> {
             metrics,
             transit,
             refresh_interval,
+            bandwidth,
+            blocklist,
             ..
         } = config;
 
-        let profile_storage = ProfileStorage::<R>::new(&routers, &profiles);
+        let blocklist = blocklist.unwrap_or_default();
+        let profile_storage =
+            ProfileStorage::<R>::with_blocklist(&routers, &profiles, blocklist.clone());
         let serialized_router_info = local_router_info.serialize(&local_signing_key);
         let local_router_id = local_router_info.identity.id();
         let mut address_info = ProtocolAddressInfo::default();
@@ -274,9 +297,22 @@ impl<R: Runtime> Router<R> {
             local_signing_key.clone(),
             net_id.unwrap_or(NET_ID),
             event_handle,
-        );
+        )
+        .with_bandwidth_limiter(BandwidthLimiter::new(bandwidth.as_ref()))
+        .with_blocklist(blocklist);
         let sam_event_handle = router_ctx.event_handle().clone();
 
+        // create handle which allows controlling the router while it's running
+        let (command_tx, command_rx) = channel(COMMAND_CHANNEL_SIZE);
+        let shutting_down = Arc::new(AtomicBool::new(false));
+        let handle = RouterHandle::new(
+            router_ctx.bandwidth_limiter().clone(),
+            command_tx,
+            router_ctx.event_handle().clone(),
+            router_ctx.profile_storage().clone(),
+            Arc::clone(&shutting_down),
+        );
+
         // create transport manager builder and initialize & start enabled transports
         //
         // note: order of initialization is important
@@ -287,6 +323,7 @@ impl<R: Runtime> Router<R> {
         //
         // if they are, the router will always publish an RI with `G` flag
         transport_manager_builder.with_transit_tunnels_disabled(transit.is_none());
+        transport_manager_builder.with_default_bandwidth(default_bandwidth);
 
         // initialize and start tunnel manager
         //
@@ -419,9 +456,12 @@ impl<R: Runtime> Router<R> {
         Ok((
             Self {
                 address_info,
+                command_rx,
                 event_manager,
+                handle,
                 shutdown_context,
                 shutdown_count: 0usize,
+                shutting_down,
                 transport_manager: transport_manager_builder.build(),
                 _tunnel_manager_handle: tunnel_manager_handle,
             },
@@ -435,6 +475,7 @@ impl<R: Runtime> Router<R> {
     /// The first request to shutdown the router starts a graceful shutdown and TOOD
     pub fn shutdown(&mut self) {
         self.shutdown_count += 1;
+        self.shutting_down.store(true, Ordering::Release);
 
         if self.shutdown_count == 1 {
             tracing::info!(
@@ -453,6 +494,14 @@ impl<R: Runtime> Router<R> {
         }
     }
 
+    /// Get handle to [`Router`].
+    ///
+    /// The handle can be used to query the status of the router and to control it while the
+    /// router is running.
+    pub fn handle(&self) -> RouterHandle<R> {
+        self.handle.clone()
+    }
+
     /// Get reference to [`ProtocolAddressInfo`].
     pub fn protocol_address_info(&self) -> &ProtocolAddressInfo {
         &self.address_info
@@ -465,7 +514,7 @@ impl<R: Runtime> Router<R> {
     ///
     /// If `address` differs from the address that was specified the router configuration,
     /// a warning is logged.
-    pub fn add_external_address(&mut self, address: Ipv4Addr) {
+    pub fn add_external_address(&mut self, address: IpAddr) {
         self.transport_manager.add_external_address(address);
     }
 }
@@ -474,6 +523,22 @@ impl<R: Runtime> Future for Router<R> {
     type Output = ();
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            match self.command_rx.poll_recv(cx) {
+                Poll::Pending | Poll::Ready(None) => break,
+                Poll::Ready(Some(RouterCommand::Shutdown)) =>
+                    if self.shutdown_count == 0 {
+                        self.shutdown();
+                    },
+                Poll::Ready(Some(RouterCommand::ShutdownImmediately)) =>
+                    while self.shutdown_count < IMMEDIATE_SHUTDOWN_COUNT {
+                        self.shutdown();
+                    },
+                Poll::Ready(Some(RouterCommand::BandwidthUpdated)) =>
+                    self.transport_manager.update_bandwidth_class(),
+            }
+        }
+
         if self.shutdown_count >= IMMEDIATE_SHUTDOWN_COUNT {
             return Poll::Ready(());
         }
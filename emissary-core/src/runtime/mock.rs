@@ -32,13 +32,14 @@ use futures::Stream;
 use futures_io::{AsyncRead as _, AsyncWrite as _};
 use parking_lot::RwLock;
 use rand_core::{CryptoRng, RngCore};
+use socket2::{Domain, Protocol, Socket, Type};
 use tokio::{net, task, time::Sleep};
 use tokio_util::compat::{Compat, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
 
 use std::{
     collections::HashMap,
     future::Future,
-    io::Write,
+    io::{self, Write},
     net::SocketAddr,
     pin::{pin, Pin},
     sync::{Arc, LazyLock},
@@ -122,7 +123,24 @@ pub struct MockTcpListener(net::TcpListener);
 
 impl TcpListener<MockTcpStream> for MockTcpListener {
     async fn bind(address: SocketAddr) -> Option<Self> {
-        net::TcpListener::bind(&address).await.ok().map(MockTcpListener)
+        let listener = || -> io::Result<net::TcpListener> {
+            let socket = Socket::new(
+                Domain::for_address(address),
+                Type::STREAM,
+                Some(Protocol::TCP),
+            )?;
+
+            if address.is_ipv6() {
+                socket.set_only_v6(true)?;
+            }
+            socket.set_nonblocking(true)?;
+            socket.bind(&address.into())?;
+            socket.listen(1024)?;
+
+            net::TcpListener::from_std(socket.into())
+        };
+
+        listener().ok().map(MockTcpListener)
     }
 
     fn poll_accept(&mut self, cx: &mut Context<'_>) -> Poll<Option<(MockTcpStream, SocketAddr)>> {
@@ -142,7 +160,25 @@ pub struct MockUdpSocket(Arc<net::UdpSocket>);
 
 impl UdpSocket for MockUdpSocket {
     fn bind(address: SocketAddr) -> impl Future<Output = Option<Self>> {
-        async move { net::UdpSocket::bind(address).await.ok().map(|socket| Self(Arc::new(socket))) }
+        async move {
+            let socket = || -> io::Result<net::UdpSocket> {
+                let socket = Socket::new(
+                    Domain::for_address(address),
+                    Type::DGRAM,
+                    Some(Protocol::UDP),
+                )?;
+
+                if address.is_ipv6() {
+                    socket.set_only_v6(true)?;
+                }
+                socket.set_nonblocking(true)?;
+                socket.bind(&address.into())?;
+
+                net::UdpSocket::from_std(socket.into())
+            };
+
+            socket().ok().map(|socket| Self(Arc::new(socket)))
+        }
     }
 
     fn send_to(&mut self, buf: &[u8], target: SocketAddr) -> impl Future<Output = Option<usize>> {
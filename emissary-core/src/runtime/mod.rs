@@ -213,6 +213,18 @@ pub trait AddressBook: Unpin + Send + Sync + 'static {
         &self,
         host: String,
     ) -> Either<String, Pin<Box<dyn Future<Output = Option<String>> + Send>>>;
+
+    /// Attempt to resolve base32-encoded destination hash `address` into a host name.
+    ///
+    /// `address` must not contain the `.b32.i2p` suffix.
+    ///
+    /// By default, reverse lookups are not supported.
+    fn resolve_name(
+        &self,
+        _address: String,
+    ) -> Pin<Box<dyn Future<Output = Option<String>> + Send>> {
+        Box::pin(async { None })
+    }
 }
 
 pub trait Storage: Unpin + Send + Sync + 'static {
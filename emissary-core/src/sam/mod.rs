@@ -229,6 +229,9 @@ pub struct SamServer<R: Runtime> {
     /// TX channel given to active sessions they can use to send datagrams to clients.
     datagram_tx: Sender<(u16, Vec<u8>)>,
 
+    /// Pending host lookups for outbound datagrams.
+    datagram_lookups: R::JoinSet<(Arc<str>, Option<DestinationId>, Vec<u8>, (u16, u16))>,
+
     /// Datagra writer state.
     datagram_writer_state: DatagramWriterState,
 
@@ -332,6 +335,7 @@ impl<R: Runtime> SamServer<R> {
             address_book,
             datagram_rx,
             datagram_tx,
+            datagram_lookups: R::join_set(),
             datagram_writer_state: DatagramWriterState::GetMessage,
             event_handle,
             host_lookups: R::join_set(),
@@ -358,6 +362,35 @@ impl<R: Runtime> SamServer<R> {
     pub fn udp_local_address(&self) -> Option<SocketAddr> {
         self.socket_handle.local_address()
     }
+
+    /// Send `datagram` to `destination_id` using the active session `session_id`.
+    ///
+    /// `ports` contains the source and destination ports of the datagram.
+    fn send_datagram(
+        &mut self,
+        session_id: Arc<str>,
+        destination_id: DestinationId,
+        datagram: Vec<u8>,
+        (src_port, dst_port): (u16, u16),
+    ) {
+        if let Err(error) = self.active_sessions.send_command(
+            &Arc::clone(&session_id),
+            SamSessionCommand::SendDatagram {
+                destination_id,
+                datagram,
+                session_id: Arc::clone(&session_id),
+                src_port,
+                dst_port,
+            },
+        ) {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?session_id,
+                ?error,
+                "failed to send datagram to active session",
+            );
+        }
+    }
 }
 
 impl<R: Runtime> Future for SamServer<R> {
@@ -385,7 +418,7 @@ impl<R: Runtime> Future for SamServer<R> {
                         session_id,
                         destination,
                         datagram,
-                        ..
+                        options,
                     }) = Datagram::parse(&datagram)
                     else {
                         tracing::warn!(
@@ -394,26 +427,84 @@ impl<R: Runtime> Future for SamServer<R> {
                         );
                         continue;
                     };
+                    let ports = (
+                        options
+                            .get("FROM_PORT")
+                            .and_then(|port| port.parse::<u16>().ok())
+                            .unwrap_or(0),
+                        options
+                            .get("TO_PORT")
+                            .and_then(|port| port.parse::<u16>().ok())
+                            .unwrap_or(0),
+                    );
 
-                    if let Err(error) = this.active_sessions.send_command(
-                        &Arc::clone(&session_id),
-                        SamSessionCommand::SendDatagram {
-                            destination,
-                            datagram,
-                            session_id: Arc::clone(&session_id),
+                    let destination_id = match destination {
+                        HostKind::Destination { destination } => destination.id(),
+                        HostKind::B32Host { destination_id } => destination_id,
+                        HostKind::B33Host { .. } => {
+                            tracing::warn!(
+                                target: LOG_TARGET,
+                                ?session_id,
+                                "datagrams to blinded destinations are not supported",
+                            );
+                            continue;
+                        }
+                        HostKind::Host { host } => match &this.address_book {
+                            None => {
+                                tracing::warn!(
+                                    target: LOG_TARGET,
+                                    ?session_id,
+                                    %host,
+                                    "host lookup requested but address book not specified",
+                                );
+                                continue;
+                            }
+                            Some(address_book) => match address_book.resolve_b32(host) {
+                                Either::Left(destination) => match base32_decode(&destination) {
+                                    Some(destination) => DestinationId::from(destination),
+                                    None => {
+                                        tracing::error!(
+                                            target: LOG_TARGET,
+                                            "failed to base32-decode destination id from a host lookup",
+                                        );
+                                        debug_assert!(false);
+                                        continue;
+                                    }
+                                },
+                                Either::Right(future) => {
+                                    this.datagram_lookups.push(async move {
+                                        let destination_id = future
+                                            .await
+                                            .and_then(base32_decode)
+                                            .map(DestinationId::from);
+
+                                        (session_id, destination_id, datagram, ports)
+                                    });
+                                    continue;
+                                }
+                            },
                         },
-                    ) {
-                        tracing::warn!(
-                            target: LOG_TARGET,
-                            ?session_id,
-                            ?error,
-                            "failed to send datagram to active session",
-                        );
-                    }
+                    };
+
+                    this.send_datagram(session_id, destination_id, datagram, ports);
                 }
             }
         }
 
+        loop {
+            match this.datagram_lookups.poll_next_unpin(cx) {
+                Poll::Pending => break,
+                Poll::Ready(None) => return Poll::Ready(()),
+                Poll::Ready(Some((session_id, None, _, _))) => tracing::debug!(
+                    target: LOG_TARGET,
+                    ?session_id,
+                    "failed to resolve host, dropping datagram",
+                ),
+                Poll::Ready(Some((session_id, Some(destination_id), datagram, ports))) =>
+                    this.send_datagram(session_id, destination_id, datagram, ports),
+            }
+        }
+
         loop {
             match mem::replace(
                 &mut this.datagram_writer_state,
@@ -628,6 +719,24 @@ impl<R: Runtime> Future for SamServer<R> {
                                 )
                             }
                         }
+                        HostKind::B33Host { address } => {
+                            if let Err(error) = this.active_sessions.send_command(
+                                &Arc::clone(&session_id),
+                                SamSessionCommand::ConnectBlinded {
+                                    socket,
+                                    address,
+                                    options,
+                                    session_id: Arc::clone(&session_id),
+                                },
+                            ) {
+                                tracing::warn!(
+                                    target: LOG_TARGET,
+                                    %session_id,
+                                    ?error,
+                                    "failed to send `STREAM CONNECT` to active session",
+                                )
+                            }
+                        }
                         HostKind::Host { host } => match &this.address_book {
                             None => {
                                 tracing::warn!(
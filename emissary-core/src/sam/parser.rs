@@ -18,7 +18,7 @@
 
 use crate::{
     crypto::{base32_decode, base64_decode, SigningPrivateKey, StaticPrivateKey},
-    primitives::{Destination, DestinationId},
+    primitives::{BlindedAddress, Destination, DestinationId, OfflineSignature},
     runtime::Runtime,
 };
 
@@ -130,7 +130,12 @@ pub struct DestinationContext {
     pub private_key: Box<StaticPrivateKey>,
 
     /// Signing key of the destination.
+    ///
+    /// If the destination uses an offline signature, this is the transient signing key.
     pub signing_key: Box<SigningPrivateKey>,
+
+    /// Offline signature authorizing the transient signing key, if specified.
+    pub offline_signature: Option<OfflineSignature>,
 }
 
 impl fmt::Debug for DestinationContext {
@@ -144,6 +149,7 @@ impl PartialEq for DestinationContext {
         self.destination == other.destination
             && (*self.private_key).as_ref() == (*other.private_key).as_ref()
             && (*self.signing_key).as_ref() == (*other.signing_key).as_ref()
+            && self.offline_signature == other.offline_signature
     }
 }
 
@@ -164,6 +170,15 @@ pub enum HostKind {
         destination_id: DestinationId,
     },
 
+    /// Blinded host, a `.b32.i2p` address of 56 or more characters.
+    ///
+    /// The lease set of the host is an encrypted lease set which is looked up using the blinded
+    /// public key derived from the address.
+    B33Host {
+        /// Blinded address.
+        address: BlindedAddress,
+    },
+
     /// Regular host, such as host.i2p.
     Host {
         /// Host.
@@ -191,6 +206,8 @@ impl PartialEq for HostKind {
                     destination: destination2,
                 },
             ) => destination1 == destination2,
+            (Self::B33Host { address: address1 }, Self::B33Host { address: address2 }) =>
+                address1 == address2,
             _ => false,
         }
     }
@@ -276,6 +293,14 @@ pub enum SamCommand {
         name: String,
     },
 
+    /// `NAMING LOOKUP` message with `REVERSE=true`.
+    ///
+    /// Resolves a `.b32.i2p` address into a host name using the address book.
+    ReverseLookup {
+        /// `.b32.i2p` address to lookup.
+        name: String,
+    },
+
     /// Generate destination.
     GenerateDestination,
 
@@ -302,6 +327,7 @@ impl fmt::Display for SamCommand {
             Self::Accept { session_id, .. } => write!(f, "SamCommand::StreamAccept({session_id})"),
             Self::Forward { session_id, .. } => write!(f, "SamCommand::Forward({session_id})"),
             Self::NamingLookup { name } => write!(f, "SamCommand::NamingLookup({name})"),
+            Self::ReverseLookup { name } => write!(f, "SamCommand::ReverseLookup({name})"),
             Self::GenerateDestination => write!(f, "SamCommand::GenerateDestination"),
             Self::Dummy => unreachable!(),
         }
@@ -414,6 +440,7 @@ impl<'a, R: Runtime> TryFrom<ParsedCommand<'a, R>> for SamCommand {
                             destination,
                             private_key: Box::new(encryption_key),
                             signing_key: Box::new(signing_key),
+                            offline_signature: None,
                         }
                     }
                     Some(destination) => {
@@ -425,11 +452,42 @@ impl<'a, R: Runtime> TryFrom<ParsedCommand<'a, R>> for SamCommand {
                             take::<_, _, ()>(destination.private_key_length())(rest)
                                 .map_err(|_| ())
                                 .unwrap();
-                        let (_, signing_key) =
+                        let (rest, signing_key) =
                             take::<_, _, ()>(destination.signing_key_length())(rest)
                                 .map_err(|_| ())
                                 .unwrap();
 
+                        // all-zero signing key indicates that the long-term signing key is kept
+                        // offline and the private key is followed by an offline signature block
+                        // and the transient signing key
+                        //
+                        // https://geti2p.net/en/docs/api/samv3#offline-signatures
+                        let (signing_key, offline_signature) =
+                            match signing_key.iter().all(|byte| byte == &0u8) {
+                                false => (signing_key, None),
+                                true => {
+                                    let (rest, offline_signature) = OfflineSignature::parse_frame(
+                                        rest,
+                                        destination.verifying_key(),
+                                    )
+                                    .map_err(|_| {
+                                        tracing::warn!(
+                                            target: LOG_TARGET,
+                                            "invalid offline signature",
+                                        );
+                                    })?;
+                                    let (_, signing_key) = take::<_, _, ()>(32usize)(rest)
+                                        .map_err(|_| {
+                                            tracing::warn!(
+                                                target: LOG_TARGET,
+                                                "transient signing key missing",
+                                            );
+                                        })?;
+
+                                    (signing_key, Some(offline_signature))
+                                }
+                            };
+
                         // conversions are expected to succeed since the client is interacting with
                         // a local router and would only crash their onw router if they provided
                         // invalid keying material
@@ -439,6 +497,7 @@ impl<'a, R: Runtime> TryFrom<ParsedCommand<'a, R>> for SamCommand {
                             signing_key: Box::new(
                                 SigningPrivateKey::from_bytes(signing_key).expect("to succeed"),
                             ),
+                            offline_signature,
                         }
                     }
                     None => {
@@ -554,16 +613,29 @@ impl<'a, R: Runtime> TryFrom<ParsedCommand<'a, R>> for SamCommand {
                         0usize
                     };
 
-                    let decoded = base32_decode(&destination[start..end]).ok_or_else(|| {
-                        tracing::warn!(
-                            target: LOG_TARGET,
-                            ?destination,
-                            "invalid .b32.i2p address",
-                        );
-                    })?;
+                    if BlindedAddress::is_blinded(&destination[start..end + 8]) {
+                        let address =
+                            BlindedAddress::parse(&destination[start..end]).ok_or_else(|| {
+                                tracing::warn!(
+                                    target: LOG_TARGET,
+                                    ?destination,
+                                    "invalid blinded .b32.i2p address",
+                                );
+                            })?;
+
+                        HostKind::B33Host { address }
+                    } else {
+                        let decoded = base32_decode(&destination[start..end]).ok_or_else(|| {
+                            tracing::warn!(
+                                target: LOG_TARGET,
+                                ?destination,
+                                "invalid .b32.i2p address",
+                            );
+                        })?;
 
-                    HostKind::B32Host {
-                        destination_id: DestinationId::from(&decoded),
+                        HostKind::B32Host {
+                            destination_id: DestinationId::from(&decoded),
+                        }
                     }
                 } else if destination.ends_with(".i2p") {
                     tracing::trace!(
@@ -647,9 +719,14 @@ impl<'a, R: Runtime> TryFrom<ParsedCommand<'a, R>> for SamCommand {
                         .collect(),
                 })
             }
-            ("NAMING", Some("LOOKUP")) => Ok(SamCommand::NamingLookup {
-                name: parsed_cmd.key_value_pairs.get("NAME").ok_or(())?.to_string(),
-            }),
+            ("NAMING", Some("LOOKUP")) => {
+                let name = parsed_cmd.key_value_pairs.get("NAME").ok_or(())?.to_string();
+
+                match parsed_cmd.key_value_pairs.get("REVERSE") {
+                    Some(reverse) if *reverse == "true" => Ok(SamCommand::ReverseLookup { name }),
+                    _ => Ok(SamCommand::NamingLookup { name }),
+                }
+            }
             ("DEST", Some("GENERATE")) => match parsed_cmd.key_value_pairs.get("SIGNATURE_TYPE") {
                 Some(signature_type) if *signature_type == "7" =>
                     Ok(SamCommand::GenerateDestination),
@@ -735,13 +812,14 @@ pub struct Datagram {
     pub session_id: Arc<str>,
 
     /// Destination of the remote peer where the datagram should be sent.
-    pub destination: Destination,
+    ///
+    /// May be a base64-encoded destination, a `.b32.i2p` address or a host name.
+    pub destination: HostKind,
 
     /// Datagram.
     pub datagram: Vec<u8>,
 
     /// Options.
-    #[allow(unused)]
     pub options: HashMap<String, String>,
 }
 
@@ -759,14 +837,45 @@ impl Datagram {
         Ok((input, Arc::from(id)))
     }
 
-    fn parse_destination(input: &str) -> IResult<&str, Destination> {
-        let (input, dest_b64) = take_while1(|c| c != ' ')(input)?;
-        let decoded = base64_decode(dest_b64)
-            .ok_or_else(|| nom::Err::Error(nom::error::Error::new(input, ErrorKind::Char)))?;
-        let destination = Destination::parse(&decoded)
-            .ok_or_else(|| nom::Err::Error(nom::error::Error::new(input, ErrorKind::Char)))?;
+    fn parse_destination(input: &str) -> IResult<&str, HostKind> {
+        let (input, destination) = take_while1(|c| c != ' ')(input)?;
+        let error = || nom::Err::Error(nom::error::Error::new(input, ErrorKind::Char));
 
-        Ok((input, destination))
+        if let Some(end) = destination.find(".b32.i2p") {
+            if BlindedAddress::is_blinded(&destination[..end + 8]) {
+                let address = BlindedAddress::parse(&destination[..end]).ok_or_else(error)?;
+
+                return Ok((input, HostKind::B33Host { address }));
+            }
+
+            let decoded = base32_decode(&destination[..end]).ok_or_else(error)?;
+
+            return Ok((
+                input,
+                HostKind::B32Host {
+                    destination_id: DestinationId::from(&decoded),
+                },
+            ));
+        }
+
+        if destination.ends_with(".i2p") {
+            return Ok((
+                input,
+                HostKind::Host {
+                    host: destination.to_string(),
+                },
+            ));
+        }
+
+        let decoded = base64_decode(destination).ok_or_else(error)?;
+        let destination = Destination::parse(&decoded).ok_or_else(error)?;
+
+        Ok((
+            input,
+            HostKind::Destination {
+                destination: Box::new(destination),
+            },
+        ))
     }
 
     /// Attempt to parse `input` into `Datagram`.
@@ -936,6 +1045,57 @@ mod tests {
         .is_none());
     }
 
+    #[test]
+    fn parse_session_create_offline_signature() {
+        let signing_key = SigningPrivateKey::random(MockRuntime::rng());
+        let transient_key = SigningPrivateKey::random(MockRuntime::rng());
+        let encryption_key = StaticPrivateKey::random(MockRuntime::rng());
+        let destination = Destination::new::<MockRuntime>(signing_key.public());
+        let offline_signature = OfflineSignature::new(
+            MockRuntime::time_since_epoch() + core::time::Duration::from_secs(60 * 60),
+            transient_key.public(),
+            &signing_key,
+        );
+
+        let privkey = |offline_signature: &OfflineSignature| {
+            let mut out = BytesMut::with_capacity(destination.serialized_len() + 4 * 32 + 6 + 64);
+            out.put_slice(&destination.serialize());
+            out.put_slice(encryption_key.as_ref());
+            out.put_slice(&[0u8; 32]);
+            out.put_slice(offline_signature.serialize());
+            out.put_slice(transient_key.as_ref());
+
+            base64_encode(out)
+        };
+
+        match SamCommand::parse::<MockRuntime>(&format!(
+            "SESSION CREATE STYLE=STREAM ID=test DESTINATION={}",
+            privkey(&offline_signature)
+        )) {
+            Some(SamCommand::CreateSession { destination, .. }) => {
+                assert_eq!(
+                    destination.signing_key.as_ref().as_ref(),
+                    transient_key.as_ref()
+                );
+                assert_eq!(destination.offline_signature, Some(offline_signature));
+            }
+            response => panic!("invalid response: {response:?}"),
+        }
+
+        // offline signature not signed by the destination's signing key
+        let offline_signature = OfflineSignature::new(
+            MockRuntime::time_since_epoch() + core::time::Duration::from_secs(60 * 60),
+            transient_key.public(),
+            &SigningPrivateKey::random(MockRuntime::rng()),
+        );
+
+        assert!(SamCommand::parse::<MockRuntime>(&format!(
+            "SESSION CREATE STYLE=STREAM ID=test DESTINATION={}",
+            privkey(&offline_signature)
+        ))
+        .is_none());
+    }
+
     #[test]
     fn reject_invalid_outbound_tunnel_quantity() {
         let test_cases = ["0", "17", "abc", "-1", "1.1"];
@@ -1182,6 +1342,36 @@ mod tests {
         .is_none());
     }
 
+    #[test]
+    fn parse_stream_connect_blinded() {
+        let signing_key = SigningPrivateKey::random(MockRuntime::rng());
+        let address = BlindedAddress::new(&signing_key.public(), false, false).unwrap();
+
+        match SamCommand::parse::<MockRuntime>(&format!(
+            "STREAM CONNECT ID=MM9z52ZwnTTPwfeD DESTINATION={address} SILENT=false"
+        )) {
+            Some(SamCommand::Connect {
+                session_id,
+                host: HostKind::B33Host { address: parsed },
+                ..
+            }) => {
+                assert_eq!(session_id.as_str(), "MM9z52ZwnTTPwfeD");
+                assert_eq!(parsed, address);
+            }
+            response => panic!("invalid response: {response:?}"),
+        }
+
+        // invalid checksum
+        let mut host = address.to_string();
+        let corrupted = if &host[20..21] == "a" { "b" } else { "a" };
+        host.replace_range(20..21, corrupted);
+
+        assert!(SamCommand::parse::<MockRuntime>(&format!(
+            "STREAM CONNECT ID=MM9z52ZwnTTPwfeD DESTINATION={host}"
+        ))
+        .is_none());
+    }
+
     #[test]
     fn parse_stream_accept() {
         match SamCommand::parse::<MockRuntime>("STREAM ACCEPT ID=MM9z52ZwnTTPwfeD SILENT=false") {
@@ -1247,6 +1437,28 @@ mod tests {
         assert!(SamCommand::parse::<MockRuntime>("NAMING LOOKUP").is_none());
     }
 
+    #[test]
+    fn parse_reverse_lookup() {
+        match SamCommand::parse::<MockRuntime>(
+            "NAMING LOOKUP NAME=lhbd7ojcaiofbfku7ixh47qj537g572zmhdc4oilvugzxdpdghua.b32.i2p REVERSE=true",
+        ) {
+            Some(SamCommand::ReverseLookup { name }) => {
+                assert_eq!(
+                    name.as_str(),
+                    "lhbd7ojcaiofbfku7ixh47qj537g572zmhdc4oilvugzxdpdghua.b32.i2p"
+                );
+            }
+            response => panic!("invalid response: {response:?}"),
+        }
+
+        match SamCommand::parse::<MockRuntime>("NAMING LOOKUP NAME=host.i2p REVERSE=false") {
+            Some(SamCommand::NamingLookup { name }) => {
+                assert_eq!(name.as_str(), "host.i2p");
+            }
+            response => panic!("invalid response: {response:?}"),
+        }
+    }
+
     #[test]
     fn parse_dest_generate() {
         match SamCommand::parse::<MockRuntime>("DEST GENERATE SIGNATURE_TYPE=7") {
@@ -1631,6 +1843,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_datagram_b32_destination() {
+        let datagram =
+            "3.0 test udhdrtrcetjm5sxzskjyr5ztpeszydbh4dpl3pl4utgqqw2v4jna.b32.i2p\nhello"
+                .as_bytes()
+                .to_vec();
+
+        match Datagram::parse(&datagram) {
+            Some(Datagram {
+                session_id,
+                destination: HostKind::B32Host { destination_id },
+                datagram,
+                ..
+            }) => {
+                assert_eq!(*session_id, *"test");
+                assert_eq!(
+                    destination_id,
+                    DestinationId::from(
+                        &base32_decode("udhdrtrcetjm5sxzskjyr5ztpeszydbh4dpl3pl4utgqqw2v4jna")
+                            .unwrap()
+                    )
+                );
+                assert_eq!(datagram, b"hello");
+            }
+            _ => panic!("invalid datagram"),
+        }
+    }
+
+    #[test]
+    fn parse_datagram_host_destination() {
+        let datagram = "3.0 test host.i2p FROM_PORT=1234\nhello".as_bytes().to_vec();
+
+        match Datagram::parse(&datagram) {
+            Some(Datagram {
+                destination: HostKind::Host { host },
+                datagram,
+                options,
+                ..
+            }) => {
+                assert_eq!(host, "host.i2p");
+                assert_eq!(datagram, b"hello");
+                assert_eq!(options.get("FROM_PORT"), Some(&String::from("1234")));
+            }
+            _ => panic!("invalid datagram"),
+        }
+
+        assert!(Datagram::parse(b"3.0 test 1nva1id.b32.i2p\nhello").is_none());
+    }
+
     #[test]
     fn parse_primary_session() {
         // transient
@@ -384,7 +384,9 @@ impl<R: Runtime> Future for PendingSamConnection<R> {
                             options,
                         }));
                     }
-                    Poll::Ready(Some(SamCommand::NamingLookup { name })) => {
+                    Poll::Ready(Some(
+                        SamCommand::NamingLookup { name } | SamCommand::ReverseLookup { name },
+                    )) => {
                         tracing::debug!(
                             target: LOG_TARGET,
                             ?version,
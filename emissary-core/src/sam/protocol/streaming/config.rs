@@ -16,13 +16,13 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-#![allow(unused)]
+use hashbrown::HashMap;
 
 use alloc::string::String;
 use core::{num::NonZeroUsize, time::Duration};
 
 /// Inactivity action.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InactivityAction {
     /// Do nothing,
     DoNothing,
@@ -35,7 +35,7 @@ pub enum InactivityAction {
 }
 
 /// Limit action.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum LimitAction {
     /// Reset connection.
     Reset,
@@ -45,6 +45,9 @@ pub enum LimitAction {
 
     /// Send HTTP 429 status code.
     Http,
+
+    /// Send custom response.
+    Custom(String),
 }
 
 /// Profile for the streaming application.
@@ -52,17 +55,18 @@ pub enum LimitAction {
 /// See section `i2p.streaming.profile Notes` in the docs [1]
 ///
 /// [1]: https://geti2p.net/en/docs/api/streaming
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Profile {
     /// Bulk.
     Bulk,
 
     /// Interactive.
+    #[allow(unused)]
     Interactive,
 }
 
 /// Streaming protocol configuration.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct StreamConfig {
     /// Whether to respond to incoming pings
     pub answer_pings: bool,
@@ -75,21 +79,25 @@ pub struct StreamConfig {
     pub blacklist: String,
 
     /// How much transmit data (in bytes) will be accepted that hasn't been written out yet.
+    #[allow(unused)]
     pub buffer_size: usize,
 
     /// When we're in congestion avoidance, we grow the window size at the rate of
     /// 1/(windowSize*factor). In standard TCP, window sizes are in bytes, while in I2P, window
     /// sizes are in messages. A higher number means slower growth.
+    #[allow(unused)]
     pub congestion_avoidance_growth_rate_factor: usize,
 
     /// How long to wait after instantiating a new con before actually attempting to connect. If
     /// this is <= 0, connect immediately with no initial data. If greater than 0, wait until the
     /// output stream is flushed, the buffer fills, or that many milliseconds pass, and include any
     /// initial data with the SYN.
+    #[allow(unused)]
     pub connect_delay: Option<Duration>,
 
     /// How long to block on connect, in milliseconds. Negative means indefinitely. Default is 5
     /// minutes.
+    #[allow(unused)]
     pub connect_timeout: Option<Duration>,
 
     /// Comma- or space-separated list of Base64 peer Hashes or host names to be contacted using an
@@ -98,12 +106,14 @@ pub struct StreamConfig {
     /// properties, NOT in the createManager() options argument. Note that setting this in the
     /// router context will not affect clients outside the router in a separate JVM and context. As
     /// of release 0.9.21.
+    #[allow(unused)]
     pub dsa_list: String,
 
     /// Whether to listen only for the streaming protocol. Setting to true will prohibit
     /// communication with Destinations earlier than release 0.7.1 (released March 2009). Set to
     /// true if running multiple protocols on this Destination. As of release 0.9.1. Default true
     /// as of release 0.9.36.
+    #[allow(unused)]
     pub enforce_protocol: bool,
 
     /// (send)  (0=noop, 1=disconnect) What to do on an inactivity timeout - do nothing,
@@ -114,10 +124,12 @@ pub struct StreamConfig {
     pub inactivity_timeout: Duration,
 
     /// Delay before sending an ack
+    #[allow(unused)]
     pub initial_ack_delay: Duration,
 
     /// The initial value of the resend delay field in the packet header, times 1000. Not fully
     /// implemented; see below.
+    #[allow(unused)]
     pub initial_resend_delay: Duration,
 
     /// Initial timeout (if no sharing data available). As of release 0.9.8.
@@ -151,9 +163,11 @@ pub struct StreamConfig {
     pub max_conns_per_day: Option<NonZeroUsize>,
 
     /// The max_Imum size of the payload, i.e. the MTU in bytes.
+    #[allow(unused)]
     pub max_message_size: usize,
 
     /// Max_Imum number of retransmissions before failure.
+    #[allow(unused)]
     pub max_resends: usize,
 
     /// Incoming connection limit (all peers; 0 means disabled) As of release 0.7.14.
@@ -171,32 +185,116 @@ pub struct StreamConfig {
     pub max_window_size: usize,
 
     /// Streaming application profile.
+    #[allow(unused)]
     pub profile: Profile,
 
     /// How long to block on read, in milliseconds. Negative means indefinitely.
+    #[allow(unused)]
     pub read_timeout: Option<NonZeroUsize>,
 
     /// When we're in slow start, we grow the window size at the rate of 1/(factor). In standard
     /// TCP, window sizes are in bytes, while in I2P, window sizes are in messages. A higher number
     /// means slower growth.
+    #[allow(unused)]
     pub slow_start_growth_rate_factor: usize,
 
-    /// Ref: RFC 2140. Floating point value. May be set only via context properties, not connection
-    /// options. As of release 0.9.8.
+    /// Ref: RFC 2140. Floating point value. As of release 0.9.8.
+    ///
+    /// Unlike in Java I2P, this is a session option (`i2p.streaming.tcbcache.rttDampening`) since
+    /// the control block cache is kept per session and not shared by all sessions of the router.
     pub rtt_dampening: f64,
 
-    /// Ref: RFC 2140. Floating point value. May be set only via context properties, not connection
-    /// options. As of release 0.9.8.
+    /// Ref: RFC 2140. Floating point value. As of release 0.9.8.
+    ///
+    /// Session option `i2p.streaming.tcbcache.rttdevDampening`, see `rtt_dampening`.
     pub rttdev_dampening: f64,
 
-    /// Ref: RFC 2140. Floating point value. May be set only via context properties, not connection
-    /// options. As of release 0.9.8.
+    /// Ref: RFC 2140. Floating point value. As of release 0.9.8.
+    ///
+    /// Session option `i2p.streaming.tcbcache.wdwDampening`, see `rtt_dampening`.
     pub wdw_dampening: f64,
 
     /// How long to block on write/flush, in milliseconds. Negative means indefinitely.
+    #[allow(unused)]
     pub write_timeout: Option<NonZeroUsize>,
 }
 
+impl StreamConfig {
+    /// Create [`StreamConfig`] from `i2p.streaming.*` session options.
+    ///
+    /// Options which are not specified or which are invalid use their default values.
+    pub fn from_options(options: &HashMap<String, String>) -> Self {
+        // zero or a negative value means that the limit is disabled
+        let limit = |key: &str| -> Option<NonZeroUsize> {
+            options
+                .get(key)
+                .and_then(|value| value.parse::<i64>().ok())
+                .and_then(|value| NonZeroUsize::new(value.max(0) as usize))
+        };
+        let millis = |key: &str| -> Option<Duration> {
+            options
+                .get(key)
+                .and_then(|value| value.parse::<u64>().ok())
+                .filter(|value| *value > 0)
+                .map(Duration::from_millis)
+        };
+        let dampening = |key: &str| -> Option<f64> {
+            options
+                .get(key)
+                .and_then(|value| value.parse::<f64>().ok())
+                .filter(|value| (0f64..=1f64).contains(value))
+        };
+        let defaults = Self::default();
+        let max_window_size = limit("i2p.streaming.maxWindowSize")
+            .map_or(defaults.max_window_size, |value| value.get());
+
+        Self {
+            answer_pings: options
+                .get("i2p.streaming.answerPings")
+                .is_none_or(|value| value.as_str() != "false"),
+            blacklist: options.get("i2p.streaming.blacklist").cloned().unwrap_or_default(),
+            inactivity_action: match options
+                .get("i2p.streaming.inactivityAction")
+                .map(|value| value.as_str())
+            {
+                Some("0") => InactivityAction::DoNothing,
+                Some("1") => InactivityAction::Disconnect,
+                _ => InactivityAction::Send,
+            },
+            inactivity_timeout: millis("i2p.streaming.inactivityTimeout")
+                .unwrap_or(defaults.inactivity_timeout),
+            initial_rto: millis("i2p.streaming.initialRTO").unwrap_or(defaults.initial_rto),
+            initial_rtt: millis("i2p.streaming.initialRTT").unwrap_or(defaults.initial_rtt),
+            initial_window_size: limit("i2p.streaming.initialWindowSize")
+                .map_or(defaults.initial_window_size, |value| value.get())
+                .min(max_window_size),
+            limit_action: match options.get("i2p.streaming.limitAction").map(|value| value.as_str())
+            {
+                None | Some("reset") => LimitAction::Reset,
+                Some("drop") => LimitAction::Drop,
+                Some("http") => LimitAction::Http,
+                Some(response) =>
+                    LimitAction::Custom(response.replace("\\r", "\r").replace("\\n", "\n")),
+            },
+            max_concurrent_streams: limit("i2p.streaming.maxConcurrentStreams"),
+            max_conns_per_minute: limit("i2p.streaming.maxConnsPerMinute"),
+            max_conns_per_hour: limit("i2p.streaming.maxConnsPerHour"),
+            max_conns_per_day: limit("i2p.streaming.maxConnsPerDay"),
+            max_total_conns_per_minute: limit("i2p.streaming.maxTotalConnsPerMinute"),
+            max_total_conns_per_hour: limit("i2p.streaming.maxTotalConnsPerHour"),
+            max_total_conns_per_day: limit("i2p.streaming.maxTotalConnsPerDay"),
+            max_window_size,
+            rtt_dampening: dampening("i2p.streaming.tcbcache.rttDampening")
+                .unwrap_or(defaults.rtt_dampening),
+            rttdev_dampening: dampening("i2p.streaming.tcbcache.rttdevDampening")
+                .unwrap_or(defaults.rttdev_dampening),
+            wdw_dampening: dampening("i2p.streaming.tcbcache.wdwDampening")
+                .unwrap_or(defaults.wdw_dampening),
+            ..defaults
+        }
+    }
+}
+
 impl Default for StreamConfig {
     fn default() -> Self {
         Self {
@@ -214,7 +312,7 @@ impl Default for StreamConfig {
             initial_resend_delay: Duration::from_secs(1),
             initial_rto: Duration::from_secs(9),
             initial_rtt: Duration::from_secs(8),
-            initial_window_size: 6,
+            initial_window_size: 1,
             limit_action: LimitAction::Reset,
             max_concurrent_streams: None,
             max_conns_per_minute: None,
@@ -225,7 +323,7 @@ impl Default for StreamConfig {
             max_total_conns_per_minute: None,
             max_total_conns_per_hour: None,
             max_total_conns_per_day: None,
-            max_window_size: 12,
+            max_window_size: 128,
             profile: Profile::Bulk,
             read_timeout: None,
             slow_start_growth_rate_factor: 1,
@@ -236,3 +334,129 @@ impl Default for StreamConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_from_options() {
+        let config = StreamConfig::from_options(&HashMap::from_iter([
+            (
+                String::from("i2p.streaming.maxConnsPerMinute"),
+                String::from("5"),
+            ),
+            (
+                String::from("i2p.streaming.maxTotalConnsPerDay"),
+                String::from("1000"),
+            ),
+            (
+                String::from("i2p.streaming.maxConcurrentStreams"),
+                String::from("-1"),
+            ),
+            (
+                String::from("i2p.streaming.maxConnsPerHour"),
+                String::from("invalid"),
+            ),
+            (
+                String::from("i2p.streaming.limitAction"),
+                String::from("http"),
+            ),
+        ]));
+
+        assert_eq!(config.max_conns_per_minute, NonZeroUsize::new(5));
+        assert_eq!(config.max_total_conns_per_day, NonZeroUsize::new(1000));
+        assert_eq!(config.max_concurrent_streams, None);
+        assert_eq!(config.max_conns_per_hour, None);
+        assert_eq!(config.limit_action, LimitAction::Http);
+
+        let config = StreamConfig::from_options(&HashMap::from_iter([(
+            String::from("i2p.streaming.limitAction"),
+            String::from("HTTP/1.1 503 Busy\\r\\n\\r\\n"),
+        )]));
+        assert_eq!(
+            config.limit_action,
+            LimitAction::Custom(String::from("HTTP/1.1 503 Busy\r\n\r\n"))
+        );
+        assert_eq!(
+            StreamConfig::from_options(&HashMap::new()).limit_action,
+            LimitAction::Reset
+        );
+    }
+
+    #[test]
+    fn inactivity_config_from_options() {
+        let config = StreamConfig::from_options(&HashMap::from_iter([
+            (
+                String::from("i2p.streaming.answerPings"),
+                String::from("false"),
+            ),
+            (
+                String::from("i2p.streaming.inactivityTimeout"),
+                String::from("30000"),
+            ),
+            (
+                String::from("i2p.streaming.inactivityAction"),
+                String::from("1"),
+            ),
+        ]));
+
+        assert!(!config.answer_pings);
+        assert_eq!(config.inactivity_timeout, Duration::from_secs(30));
+        assert_eq!(config.inactivity_action, InactivityAction::Disconnect);
+
+        let config = StreamConfig::from_options(&HashMap::new());
+
+        assert!(config.answer_pings);
+        assert_eq!(config.inactivity_timeout, Duration::from_secs(90));
+        assert_eq!(config.inactivity_action, InactivityAction::Send);
+    }
+
+    #[test]
+    fn congestion_control_config_from_options() {
+        let config = StreamConfig::from_options(&HashMap::from_iter([
+            (
+                String::from("i2p.streaming.initialWindowSize"),
+                String::from("6"),
+            ),
+            (
+                String::from("i2p.streaming.maxWindowSize"),
+                String::from("64"),
+            ),
+            (
+                String::from("i2p.streaming.initialRTO"),
+                String::from("3000"),
+            ),
+            (
+                String::from("i2p.streaming.tcbcache.rttDampening"),
+                String::from("0.5"),
+            ),
+            (
+                String::from("i2p.streaming.tcbcache.wdwDampening"),
+                String::from("1.5"),
+            ),
+        ]));
+
+        assert_eq!(config.initial_window_size, 6);
+        assert_eq!(config.max_window_size, 64);
+        assert_eq!(config.initial_rto, Duration::from_secs(3));
+        assert_eq!(config.initial_rtt, Duration::from_secs(8));
+        assert_eq!(config.rtt_dampening, 0.5f64);
+        assert_eq!(config.wdw_dampening, 0.75f64);
+
+        // initial window size is clamped to maximum window size
+        let config = StreamConfig::from_options(&HashMap::from_iter([
+            (
+                String::from("i2p.streaming.initialWindowSize"),
+                String::from("32"),
+            ),
+            (
+                String::from("i2p.streaming.maxWindowSize"),
+                String::from("16"),
+            ),
+        ]));
+
+        assert_eq!(config.initial_window_size, 16);
+        assert_eq!(config.max_window_size, 16);
+    }
+}
@@ -0,0 +1,418 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Connection limiter for inbound streams.
+//!
+//! Enforces the per-peer and total inbound connection limits, the concurrent stream limit and the
+//! blacklist configured in [`StreamConfig`].
+
+use crate::{
+    crypto::base64_decode,
+    primitives::DestinationId,
+    runtime::{Instant, Runtime},
+    sam::protocol::streaming::config::StreamConfig,
+};
+
+use hashbrown::{HashMap, HashSet};
+
+use alloc::vec::Vec;
+
+use core::{cmp::Reverse, num::NonZeroUsize, time::Duration};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::streaming::limiter";
+
+/// Length of the minute window.
+const MINUTE: Duration = Duration::from_secs(60);
+
+/// Length of the hour window.
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+/// Length of the day window.
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Maximum number of peers whose connections are tracked.
+const MAX_TRACKED_PEERS: usize = 8192usize;
+
+/// How many of the least recently seen peers are evicted once [`MAX_TRACKED_PEERS`] is reached.
+const EVICT_BATCH: usize = MAX_TRACKED_PEERS / 8;
+
+/// Reason why an inbound connection was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitReason {
+    /// Remote destination is blacklisted.
+    Blacklisted,
+
+    /// Per-peer connection limit exceeded.
+    PeerLimit,
+
+    /// Total connection limit exceeded.
+    TotalLimit,
+
+    /// Too many concurrent streams.
+    ConcurrentStreams,
+}
+
+/// Connection counter for a fixed window.
+struct Counter<R: Runtime> {
+    /// When was the window started.
+    started: R::Instant,
+
+    /// Number of connections seen in the window.
+    count: usize,
+}
+
+impl<R: Runtime> Counter<R> {
+    /// Create new [`Counter`].
+    fn new() -> Self {
+        Self {
+            started: R::now(),
+            count: 0usize,
+        }
+    }
+
+    /// Register new connection and return the number of connections seen in the current window.
+    fn increment(&mut self, window: Duration) -> usize {
+        if self.started.elapsed() >= window {
+            self.started = R::now();
+            self.count = 0usize;
+        }
+
+        self.count += 1;
+        self.count
+    }
+}
+
+/// Connection counters for minute, hour and day windows.
+struct Counters<R: Runtime> {
+    /// Minute counter.
+    minute: Counter<R>,
+
+    /// Hour counter.
+    hour: Counter<R>,
+
+    /// Day counter.
+    day: Counter<R>,
+
+    /// When was the last connection registered.
+    last_seen: R::Instant,
+}
+
+impl<R: Runtime> Counters<R> {
+    /// Create new [`Counters`].
+    fn new() -> Self {
+        Self {
+            minute: Counter::new(),
+            hour: Counter::new(),
+            day: Counter::new(),
+            last_seen: R::now(),
+        }
+    }
+
+    /// Register new connection and check if any of the limits was exceeded.
+    fn exceeds(
+        &mut self,
+        minute: Option<NonZeroUsize>,
+        hour: Option<NonZeroUsize>,
+        day: Option<NonZeroUsize>,
+    ) -> bool {
+        let minute_count = self.minute.increment(MINUTE);
+        let hour_count = self.hour.increment(HOUR);
+        let day_count = self.day.increment(DAY);
+        self.last_seen = R::now();
+
+        minute.is_some_and(|limit| minute_count > limit.get())
+            || hour.is_some_and(|limit| hour_count > limit.get())
+            || day.is_some_and(|limit| day_count > limit.get())
+    }
+
+    /// Have all windows of length `window` or shorter expired.
+    fn is_expired(&self, window: Duration) -> bool {
+        self.last_seen.elapsed() >= window
+    }
+}
+
+/// Connection limiter.
+pub struct ConnectionLimiter<R: Runtime> {
+    /// Blacklisted destinations.
+    blacklist: HashSet<DestinationId>,
+
+    /// Maximum number of concurrent streams.
+    max_concurrent_streams: Option<NonZeroUsize>,
+
+    /// Per-peer limits for minute, hour and day windows.
+    peer_limits: (
+        Option<NonZeroUsize>,
+        Option<NonZeroUsize>,
+        Option<NonZeroUsize>,
+    ),
+
+    /// Per-peer connection counters.
+    peers: HashMap<DestinationId, Counters<R>>,
+
+    /// When were the per-peer counters last pruned.
+    pruned: R::Instant,
+
+    /// Total limits for minute, hour and day windows.
+    total_limits: (
+        Option<NonZeroUsize>,
+        Option<NonZeroUsize>,
+        Option<NonZeroUsize>,
+    ),
+
+    /// Total connection counters.
+    total: Counters<R>,
+}
+
+impl<R: Runtime> ConnectionLimiter<R> {
+    /// Create new [`ConnectionLimiter`] from `config`.
+    pub fn new(config: &StreamConfig) -> Self {
+        let blacklist = config
+            .blacklist
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|hash| !hash.is_empty())
+            .filter_map(|hash| match base64_decode(hash) {
+                Some(hash) if hash.len() == 32 => Some(DestinationId::from(hash)),
+                _ => {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        %hash,
+                        "invalid destination hash in blacklist",
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            blacklist,
+            max_concurrent_streams: config.max_concurrent_streams,
+            peer_limits: (
+                config.max_conns_per_minute,
+                config.max_conns_per_hour,
+                config.max_conns_per_day,
+            ),
+            peers: HashMap::new(),
+            pruned: R::now(),
+            total_limits: (
+                config.max_total_conns_per_minute,
+                config.max_total_conns_per_hour,
+                config.max_total_conns_per_day,
+            ),
+            total: Counters::new(),
+        }
+    }
+
+    /// Check if an inbound connection from `destination_id` should be accepted.
+    ///
+    /// `num_streams` is the number of currently open streams, both inbound and outbound.
+    ///
+    /// Returns `Err(LimitReason)` if the connection should be rejected.
+    pub fn on_inbound_connection(
+        &mut self,
+        destination_id: &DestinationId,
+        num_streams: usize,
+    ) -> Result<(), LimitReason> {
+        if self.blacklist.contains(destination_id) {
+            return Err(LimitReason::Blacklisted);
+        }
+
+        if self.max_concurrent_streams.is_some_and(|limit| num_streams >= limit.get()) {
+            return Err(LimitReason::ConcurrentStreams);
+        }
+
+        // connections which exceed the limits are counted as well so a peer which keeps
+        // connecting stays throttled until its connection rate drops below the limit
+        let (minute, hour, day) = self.peer_limits;
+        let peer_exceeded = match self.peer_windows() {
+            None => false,
+            Some((shortest, longest)) => {
+                if self.pruned.elapsed() >= shortest {
+                    self.peers.retain(|_, counters| !counters.is_expired(longest));
+                    self.pruned = R::now();
+                }
+
+                if !self.peers.contains_key(destination_id) && self.peers.len() >= MAX_TRACKED_PEERS
+                {
+                    self.evict_peers();
+                }
+
+                self.peers
+                    .entry(destination_id.clone())
+                    .or_insert_with(Counters::new)
+                    .exceeds(minute, hour, day)
+            }
+        };
+
+        let (minute, hour, day) = self.total_limits;
+        let total_exceeded = self.total.exceeds(minute, hour, day);
+
+        match (peer_exceeded, total_exceeded) {
+            (true, _) => Err(LimitReason::PeerLimit),
+            (_, true) => Err(LimitReason::TotalLimit),
+            (false, false) => Ok(()),
+        }
+    }
+
+    /// Get the shortest and the longest window of the configured per-peer limits.
+    ///
+    /// Returns `None` if there are no per-peer limits.
+    fn peer_windows(&self) -> Option<(Duration, Duration)> {
+        let (minute, hour, day) = self.peer_limits;
+        let windows = [(minute, MINUTE), (hour, HOUR), (day, DAY)];
+        let mut configured =
+            windows.iter().filter(|(limit, _)| limit.is_some()).map(|(_, window)| *window);
+
+        let shortest = configured.next()?;
+        let longest = configured.next_back().unwrap_or(shortest);
+
+        Some((shortest, longest))
+    }
+
+    /// Evict the least recently seen peers.
+    fn evict_peers(&mut self) {
+        let mut peers = self
+            .peers
+            .iter()
+            .map(|(destination_id, counters)| {
+                (counters.last_seen.elapsed(), destination_id.clone())
+            })
+            .collect::<Vec<_>>();
+        peers.sort_unstable_by_key(|(elapsed, _)| Reverse(*elapsed));
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            num_peers = ?peers.len(),
+            "too many tracked peers, evicting least recently seen",
+        );
+
+        peers.into_iter().take(EVICT_BATCH).for_each(|(_, destination_id)| {
+            self.peers.remove(&destination_id);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{crypto::base64_encode, runtime::mock::MockRuntime};
+
+    #[test]
+    fn per_peer_limit() {
+        let mut limiter = ConnectionLimiter::<MockRuntime>::new(&StreamConfig {
+            max_conns_per_minute: NonZeroUsize::new(2),
+            ..Default::default()
+        });
+        let peer1 = DestinationId::random();
+        let peer2 = DestinationId::random();
+
+        assert!(limiter.on_inbound_connection(&peer1, 0).is_ok());
+        assert!(limiter.on_inbound_connection(&peer1, 0).is_ok());
+        assert_eq!(
+            limiter.on_inbound_connection(&peer1, 0),
+            Err(LimitReason::PeerLimit)
+        );
+
+        // limit is per peer
+        assert!(limiter.on_inbound_connection(&peer2, 0).is_ok());
+    }
+
+    #[test]
+    fn total_limit() {
+        let mut limiter = ConnectionLimiter::<MockRuntime>::new(&StreamConfig {
+            max_total_conns_per_hour: NonZeroUsize::new(3),
+            ..Default::default()
+        });
+
+        for _ in 0..3 {
+            assert!(limiter.on_inbound_connection(&DestinationId::random(), 0).is_ok());
+        }
+
+        assert_eq!(
+            limiter.on_inbound_connection(&DestinationId::random(), 0),
+            Err(LimitReason::TotalLimit)
+        );
+    }
+
+    #[test]
+    fn concurrent_streams() {
+        let mut limiter = ConnectionLimiter::<MockRuntime>::new(&StreamConfig {
+            max_concurrent_streams: NonZeroUsize::new(5),
+            ..Default::default()
+        });
+
+        assert!(limiter.on_inbound_connection(&DestinationId::random(), 4).is_ok());
+        assert_eq!(
+            limiter.on_inbound_connection(&DestinationId::random(), 5),
+            Err(LimitReason::ConcurrentStreams)
+        );
+    }
+
+    #[test]
+    fn blacklist() {
+        let peer1 = DestinationId::random();
+        let peer2 = DestinationId::random();
+        let mut limiter = ConnectionLimiter::<MockRuntime>::new(&StreamConfig {
+            blacklist: format!(
+                "{}, {} invalid",
+                base64_encode(peer1.to_vec()),
+                base64_encode(peer2.to_vec())
+            ),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            limiter.on_inbound_connection(&peer1, 0),
+            Err(LimitReason::Blacklisted)
+        );
+        assert_eq!(
+            limiter.on_inbound_connection(&peer2, 0),
+            Err(LimitReason::Blacklisted)
+        );
+        assert!(limiter.on_inbound_connection(&DestinationId::random(), 0).is_ok());
+    }
+
+    #[test]
+    fn no_limits() {
+        let mut limiter = ConnectionLimiter::<MockRuntime>::new(&StreamConfig::default());
+        let peer = DestinationId::random();
+
+        for _ in 0..100 {
+            assert!(limiter.on_inbound_connection(&peer, 100).is_ok());
+        }
+        assert!(limiter.peers.is_empty());
+    }
+
+    #[test]
+    fn tracked_peers_bounded() {
+        let mut limiter = ConnectionLimiter::<MockRuntime>::new(&StreamConfig {
+            max_conns_per_day: NonZeroUsize::new(1),
+            ..Default::default()
+        });
+        let peer = DestinationId::random();
+        assert!(limiter.on_inbound_connection(&peer, 0).is_ok());
+
+        for _ in 0..MAX_TRACKED_PEERS {
+            assert!(limiter.on_inbound_connection(&DestinationId::random(), 0).is_ok());
+        }
+
+        // the least recently seen peers were evicted
+        assert_eq!(limiter.peers.len(), MAX_TRACKED_PEERS - EVICT_BATCH + 1);
+        assert!(!limiter.peers.contains_key(&peer));
+    }
+}
@@ -25,19 +25,21 @@ use crate::{
     runtime::{Instant, JoinSet, Runtime},
     sam::{
         protocol::streaming::{
-            config::StreamConfig,
+            config::LimitAction,
+            limiter::ConnectionLimiter,
             listener::{SocketKind, StreamListener, StreamListenerEvent},
             packet::{Packet, PacketBuilder},
             stream::{
                 active::{Stream, StreamContext, StreamEvent, StreamKind},
                 pending::{PendingStream, PendingStreamResult},
             },
+            tcb::TcbCache,
         },
         socket::SamSocket,
     },
 };
 
-use bytes::{BufMut, BytesMut};
+use bytes::{BufMut, Bytes, BytesMut};
 use futures::{FutureExt, StreamExt};
 use hashbrown::{HashMap, HashSet};
 use rand_core::RngCore;
@@ -52,10 +54,13 @@ use core::{
 };
 
 mod config;
+mod limiter;
 mod listener;
 mod packet;
 mod stream;
+mod tcb;
 
+pub use config::StreamConfig;
 pub use listener::ListenerKind;
 
 /// Logging target for the file.
@@ -83,6 +88,16 @@ const GRACEFUL_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(60);
 /// Maximum `SYN` retries before the remote destination is considered unreachable.
 const MAX_SYN_RETRIES: usize = 3usize;
 
+/// Response sent to rejected inbound streams if limit action is [`LimitAction::Http`].
+const HTTP_429_RESPONSE: &str = "HTTP/1.1 429 Too Many Requests\r\n\
+    Content-Type: text/html; charset=utf-8\r\n\
+    Cache-Control: no-cache\r\n\
+    Connection: close\r\n\
+    \r\n\
+    <html><head><title>429 Too Many Requests</title></head>\
+    <body><h2>429 Too Many Requests</h2>\
+    <p>Denied due to excessive requests. Please try again later.</p></body></html>";
+
 /// Direction of stream.
 pub enum Direction {
     /// Inbound stream.
@@ -240,6 +255,9 @@ pub struct StreamManager<R: Runtime> {
     /// Indexed with receive stream ID.
     active: HashMap<u32, (DestinationId, Sender<StreamEvent>)>,
 
+    /// Stream configuration.
+    config: StreamConfig,
+
     /// Destination of the session the stream manager is bound to.
     destination: Destination,
 
@@ -272,17 +290,26 @@ pub struct StreamManager<R: Runtime> {
     /// Pending outbound streams.
     pending_outbound: HashMap<u32, PendingOutboundStream<R>>,
 
+    /// Connection limiter for inbound streams.
+    limiter: ConnectionLimiter<R>,
+
     /// Timer for pruning stale pending streams.
     prune_timer: R::Timer,
 
     /// Shutdown handler.
     shutdown_handler: ShutdownHandler<R>,
 
+    /// Serialized offline signature, if the local destination uses one.
+    offline_signature: Option<Bytes>,
+
     /// Signing key.
     signing_key: SigningPrivateKey,
 
     /// Active streams.
     streams: R::JoinSet<u32>,
+
+    /// Shared TCP control block cache.
+    tcb: TcbCache<R>,
 }
 
 impl<R: Runtime> StreamManager<R> {
@@ -293,9 +320,11 @@ impl<R: Runtime> StreamManager<R> {
 
         Self {
             active: HashMap::new(),
+            config: StreamConfig::default(),
             destination,
             destination_id: destination_id.clone(),
             destination_streams: HashMap::new(),
+            limiter: ConnectionLimiter::new(&StreamConfig::default()),
             listener: StreamListener::new(destination_id),
             outbound_rx,
             outbound_timers: R::join_set(),
@@ -305,44 +334,58 @@ impl<R: Runtime> StreamManager<R> {
             pending_outbound: HashMap::new(),
             prune_timer: R::timer(PENDING_STREAM_PRUNE_THRESHOLD),
             shutdown_handler: ShutdownHandler::new(),
+            offline_signature: None,
             signing_key,
             streams: R::join_set(),
+            tcb: TcbCache::new(&StreamConfig::default()),
         }
     }
 
-    /// Handle message with `SYN`.
+    /// Use `config` for the streams of the destination.
     ///
-    /// If this a response to an outbound stream sent by us, convert the pending stream to an active
-    /// stream by allocating it a new channel and spawning it in a background task.
+    /// Connection limits of `config` are enforced for inbound streams and the ping, inactivity and
+    /// congestion control settings are applied to all streams.
+    pub fn with_config(mut self, config: StreamConfig) -> Self {
+        self.limiter = ConnectionLimiter::new(&config);
+        self.tcb = TcbCache::new(&config);
+        self.config = config;
+        self
+    }
+
+    /// Include `offline_signature` in the signed packets sent by the local destination.
     ///
-    /// If this a new inbound stream ensure that signature and destination are in the message and
-    /// verify their validity. Additionally ensure that the NACK field contains local destination's
-    /// ID. If validity checks pass, send the message to a listener if it exists. If there are no
-    /// active listeners, mark the stream as pending and start a timer for waiting for a new
-    /// listener to be registered. If no listener is registered within the time window, the stream
-    /// is closed.
-    fn on_synchronize(
-        &mut self,
-        packet: Vec<u8>,
-        src_port: u16,
-        dst_port: u16,
-    ) -> Result<(), StreamingError> {
+    /// `signing_key` given to [`StreamManager::new()`] must be the transient signing key
+    /// authorized by `offline_signature`.
+    pub fn with_offline_signature(mut self, offline_signature: Bytes) -> Self {
+        self.offline_signature = Some(offline_signature);
+        self
+    }
+
+    /// Verify the signature of `packet` which was received from a remote destination.
+    ///
+    /// Both the signature and the remote destination must be included in the packet. If the
+    /// packet also included an offline signature, the packet is verified using the transient key.
+    ///
+    /// On success, returns the ID of the remote destination.
+    fn verify_signature(
+        &self,
+        original: &[u8],
+        packet: &Packet<'_>,
+    ) -> Result<DestinationId, StreamingError> {
         let Packet {
             send_stream_id,
             recv_stream_id,
-            nacks,
             flags,
             payload,
             ..
-        } = Packet::parse(&packet).ok_or(StreamingError::Malformed)?;
+        } = packet;
 
-        // verify signature
         let signature = flags.signature().ok_or_else(|| {
             tracing::warn!(
                 target: LOG_TARGET,
                 ?recv_stream_id,
                 ?send_stream_id,
-                "signature missing from syn packet",
+                "signature missing from packet",
             );
 
             StreamingError::SignatureMissing
@@ -352,60 +395,147 @@ impl<R: Runtime> StreamManager<R> {
                 target: LOG_TARGET,
                 ?recv_stream_id,
                 ?send_stream_id,
-                "destination missing from syn packet",
+                "destination missing from packet",
             );
             StreamingError::DestinationMissing
         })?;
         let destination_id = destination.id();
 
-        {
-            // if the packet included an offline signature, use the verifying key specified in the
-            // offline signature to verify the packet's signature
-            //
-            // otherwise use the verifying key specified in the destination
-            let verifying_key = match flags.offline_signature() {
-                None => destination.verifying_key(),
-                Some(key) => key,
-            };
+        // if the packet included an offline signature, use the verifying key specified in the
+        // offline signature to verify the packet's signature
+        //
+        // otherwise use the verifying key specified in the destination
+        let verifying_key = match flags.offline_signature() {
+            None => destination.verifying_key(),
+            Some(key) => key,
+        };
 
-            // signature field is the last field of options, meaning it starts at
-            // `original.len() - payload.len() - verifying_key.signature_len()`
-            //
-            // in order to verify the signature, the calculated signature must be filled
-            // with zeros
-            let mut original = packet.to_vec();
+        // signature field is the last field of options, meaning it starts at
+        // `original.len() - payload.len() - verifying_key.signature_len()`
+        //
+        // in order to verify the signature, the calculated signature must be filled
+        // with zeros
+        let mut original = original.to_vec();
 
-            if original.len() < payload.len() + verifying_key.signature_len() {
-                tracing::warn!(
-                    target: LOG_TARGET,
-                    local = %self.destination_id,
-                    remote = %destination_id,
-                    ?recv_stream_id,
-                    ?send_stream_id,
-                    "cannot verify signature, packet is too short",
-                );
-                return Err(StreamingError::Malformed);
-            }
+        if original.len() < payload.len() + verifying_key.signature_len() {
+            tracing::warn!(
+                target: LOG_TARGET,
+                local = %self.destination_id,
+                remote = %destination_id,
+                ?recv_stream_id,
+                ?send_stream_id,
+                "cannot verify signature, packet is too short",
+            );
+            return Err(StreamingError::Malformed);
+        }
 
-            let signature_start = original.len() - payload.len() - verifying_key.signature_len();
-            original[signature_start..signature_start + verifying_key.signature_len()]
-                .copy_from_slice(&vec![0u8; verifying_key.signature_len()]);
+        let signature_start = original.len() - payload.len() - verifying_key.signature_len();
+        original[signature_start..signature_start + verifying_key.signature_len()]
+            .copy_from_slice(&vec![0u8; verifying_key.signature_len()]);
 
-            verifying_key.verify(&original, signature).map_err(|error| {
-                tracing::warn!(
-                    target: LOG_TARGET,
-                    local = %self.destination_id,
-                    remote = %destination_id,
-                    ?recv_stream_id,
-                    ?send_stream_id,
-                    ?error,
-                    "failed to verify packet signature"
-                );
+        verifying_key.verify(&original, signature).map_err(|error| {
+            tracing::warn!(
+                target: LOG_TARGET,
+                local = %self.destination_id,
+                remote = %destination_id,
+                ?recv_stream_id,
+                ?send_stream_id,
+                ?error,
+                "failed to verify packet signature"
+            );
+
+            StreamingError::InvalidSignature
+        })?;
+
+        Ok(destination_id)
+    }
+
+    /// Handle `ECHO` packet with a non-zero send stream ID, i.e., a ping.
+    ///
+    /// Pings must be signed and include the destination of the sender. If the signature is valid
+    /// and pings are answered, send a pong back to the remote destination which echoes the payload
+    /// of the ping.
+    fn on_ping(
+        &mut self,
+        packet: Vec<u8>,
+        src_port: u16,
+        dst_port: u16,
+    ) -> Result<(), StreamingError> {
+        if !self.config.answer_pings {
+            tracing::trace!(
+                target: LOG_TARGET,
+                local = %self.destination_id,
+                "ignoring ping, pings are not answered",
+            );
+            return Ok(());
+        }
+
+        let parsed = Packet::parse(&packet).ok_or(StreamingError::Malformed)?;
+        let destination_id = self.verify_signature(&packet, &parsed)?;
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            local = %self.destination_id,
+            remote = %destination_id,
+            send_stream_id = ?parsed.send_stream_id,
+            payload_len = ?parsed.payload.len(),
+            "answer ping",
+        );
+
+        // pong has a zero send stream id and the send stream id of the ping as its receive stream
+        // id
+        let packet = PacketBuilder::new(parsed.send_stream_id)
+            .with_send_stream_id(0u32)
+            .with_echo()
+            .with_no_ack()
+            .with_payload(parsed.payload)
+            .build()
+            .to_vec();
 
-                StreamingError::InvalidSignature
-            })?;
+        if let Err(error) = self.outbound_tx.try_send((
+            DeliveryStyle::Unspecified { destination_id },
+            packet,
+            dst_port,
+            src_port,
+        )) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                local = %self.destination_id,
+                ?error,
+                "failed to send pong",
+            );
         }
 
+        Ok(())
+    }
+
+    /// Handle message with `SYN`.
+    ///
+    /// If this a response to an outbound stream sent by us, convert the pending stream to an active
+    /// stream by allocating it a new channel and spawning it in a background task.
+    ///
+    /// If this a new inbound stream ensure that signature and destination are in the message and
+    /// verify their validity. Additionally ensure that the NACK field contains local destination's
+    /// ID. If validity checks pass, send the message to a listener if it exists. If there are no
+    /// active listeners, mark the stream as pending and start a timer for waiting for a new
+    /// listener to be registered. If no listener is registered within the time window, the stream
+    /// is closed.
+    fn on_synchronize(
+        &mut self,
+        packet: Vec<u8>,
+        src_port: u16,
+        dst_port: u16,
+    ) -> Result<(), StreamingError> {
+        let parsed = Packet::parse(&packet).ok_or(StreamingError::Malformed)?;
+        let destination_id = self.verify_signature(&packet, &parsed)?;
+        let Packet {
+            send_stream_id,
+            recv_stream_id,
+            nacks,
+            payload,
+            ..
+        } = parsed;
+
         // if this is a syn-ack for an outbound stream, initialize state
         // for a new stream future and spawn it in the background
         if let Some(PendingOutboundStream {
@@ -472,6 +602,27 @@ impl<R: Runtime> StreamManager<R> {
             return Err(StreamingError::ReplayProtectionCheckFailed);
         }
 
+        // check that the inbound stream doesn't exceed connection limits and if it does, reject
+        // the stream using the configured limit action
+        let num_streams =
+            self.active.len() + self.pending_inbound.len() + self.pending_outbound.len();
+
+        if let Err(reason) = self.limiter.on_inbound_connection(&destination_id, num_streams) {
+            tracing::info!(
+                target: LOG_TARGET,
+                local = %self.destination_id,
+                remote = %destination_id,
+                ?recv_stream_id,
+                ?send_stream_id,
+                ?reason,
+                action = ?self.config.limit_action,
+                "inbound stream rejected",
+            );
+
+            self.reject_stream(destination_id, recv_stream_id, src_port, dst_port);
+            return Ok(());
+        }
+
         tracing::info!(
             target: LOG_TARGET,
             local = %self.destination_id,
@@ -489,7 +640,7 @@ impl<R: Runtime> StreamManager<R> {
             Some(socket) => self.spawn_stream(
                 socket,
                 recv_stream_id,
-                destination.id(),
+                destination_id,
                 StreamKind::Inbound {
                     payload: payload.to_vec(),
                 },
@@ -505,14 +656,13 @@ impl<R: Runtime> StreamManager<R> {
                 );
 
                 // create new pending stream and send syn-ack for it
-                let destination_id = destination.id();
-
                 let (pending, packet) = PendingStream::new(
                     self.destination.clone(),
                     destination_id.clone(),
                     recv_stream_id,
                     payload.to_vec(),
                     &self.signing_key,
+                    self.offline_signature.as_deref(),
                 );
                 let _ = self.outbound_tx.try_send((
                     DeliveryStyle::Unspecified {
@@ -534,6 +684,44 @@ impl<R: Runtime> StreamManager<R> {
         Ok(())
     }
 
+    /// Reject inbound stream which exceeded connection limits.
+    ///
+    /// Depending on the configured limit action, the stream is either reset, dropped silently or
+    /// accepted and immediately closed with an HTTP 429 or a custom response.
+    fn reject_stream(
+        &mut self,
+        destination_id: DestinationId,
+        recv_stream_id: u32,
+        src_port: u16,
+        dst_port: u16,
+    ) {
+        let response = match &self.config.limit_action {
+            LimitAction::Drop => return,
+            LimitAction::Reset => None,
+            LimitAction::Http => Some(HTTP_429_RESPONSE.as_bytes()),
+            LimitAction::Custom(response) => Some(response.as_bytes()),
+        };
+
+        let builder = PacketBuilder::new(R::rng().next_u32())
+            .with_send_stream_id(recv_stream_id)
+            .with_from_included(self.destination.clone())
+            .with_offline_signature(self.offline_signature.as_deref())
+            .with_signature();
+
+        let packet = match response {
+            None => builder.with_reset(),
+            Some(response) => builder.with_synchronize().with_close().with_payload(response),
+        }
+        .build_and_sign(&self.signing_key);
+
+        let _ = self.outbound_tx.try_send((
+            DeliveryStyle::Unspecified { destination_id },
+            packet.to_vec(),
+            dst_port,
+            src_port,
+        ));
+    }
+
     /// Spawn new [`Stream`] in the background.
     ///
     /// This function can spawn streams of two different kinds:
@@ -562,6 +750,7 @@ impl<R: Runtime> StreamManager<R> {
             recv_stream_id,
             remote: destination_id.clone(),
             signing_key: self.signing_key.clone(),
+            offline_signature: self.offline_signature.clone(),
         };
 
         // if the socket wasn't configured to be silent, send the remote's destination
@@ -609,6 +798,9 @@ impl<R: Runtime> StreamManager<R> {
         // if the listener was created with `STREAM FORWARD`, a new tcp connection must be opened to
         // the forwarded listener before the stream can be started and if the listener is not
         // active, the stream is closed immediately
+        let config = self.config.clone();
+        let tcb = self.tcb.clone();
+
         match socket {
             SocketKind::Connect {
                 socket,
@@ -618,7 +810,8 @@ impl<R: Runtime> StreamManager<R> {
                 socket,
                 initial_message,
                 context,
-                StreamConfig::default(),
+                config,
+                tcb,
                 stream_kind,
                 routing_path_handle,
             )),
@@ -642,7 +835,8 @@ impl<R: Runtime> StreamManager<R> {
                         socket,
                         initial_message,
                         context,
-                        StreamConfig::default(),
+                        config,
+                        tcb,
                         stream_kind,
                         routing_path_handle,
                     )
@@ -676,7 +870,8 @@ impl<R: Runtime> StreamManager<R> {
                     stream,
                     initial_message,
                     context,
-                    StreamConfig::default(),
+                    config,
+                    tcb,
                     stream_kind,
                     routing_path_handle,
                 )
@@ -782,6 +977,13 @@ impl<R: Runtime> StreamManager<R> {
             "inbound message",
         );
 
+        // pings are answered regardless of whether the stream they were sent to exists
+        //
+        // pongs have a zero send stream id and are forwarded to the stream that sent the ping
+        if packet.echo() && packet.send_stream_id() != 0 {
+            return self.on_ping(payload, src_port, dst_port);
+        }
+
         // forward received packet to an active handler if it exists
         if let Some((_, tx)) = self.active.get(&packet.recv_stream_id()) {
             if let Err(error) = tx.try_send(StreamEvent::Packet { packet: payload }) {
@@ -939,6 +1141,7 @@ impl<R: Runtime> StreamManager<R> {
             .with_synchronize()
             .with_signature()
             .with_from_included(self.destination.clone())
+            .with_offline_signature(self.offline_signature.as_deref())
             .build_and_sign(&self.signing_key);
 
         tracing::debug!(
@@ -1267,7 +1470,7 @@ mod tests {
     use crate::{
         destination::routing_path::{PendingRoutingPathHandle, RoutingPathManager},
         error::QueryError,
-        primitives::{Destination, Lease, RouterId, TunnelId},
+        primitives::{Destination, Lease, OfflineSignature, RouterId, TunnelId},
         protocol::Protocol,
         runtime::{
             mock::{MockRuntime, MockTcpStream},
@@ -1424,6 +1627,328 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn inbound_stream_with_offline_signature() {
+        let signing_key = SigningPrivateKey::from_bytes(&[0u8; 32]).unwrap();
+        let destination = Destination::new::<MockRuntime>(signing_key.public());
+        let destination_id = destination.id();
+        let mut manager = StreamManager::<MockRuntime>::new(destination, signing_key);
+
+        // remote destination signs the packet with a transient key authorized by its offline key
+        let signing_key = SigningPrivateKey::from_bytes(&[1u8; 32]).unwrap();
+        let transient_key = SigningPrivateKey::from_bytes(&[2u8; 32]).unwrap();
+        let destination = Destination::new::<MockRuntime>(signing_key.public());
+        let offline_signature = OfflineSignature::new(
+            MockRuntime::time_since_epoch() + Duration::from_secs(60 * 60),
+            transient_key.public(),
+            &signing_key,
+        );
+
+        // offline signature missing, signature verification fails
+        let packet = PacketBuilder::new(1337u32)
+            .with_synchronize()
+            .with_send_stream_id(0u32)
+            .with_replay_protection(&destination_id)
+            .with_from_included(destination.clone())
+            .with_signature()
+            .build_and_sign(&transient_key)
+            .to_vec();
+
+        assert!(manager
+            .on_packet(I2cpPayload {
+                src_port: 13u16,
+                dst_port: 37u16,
+                protocol: Protocol::Streaming,
+                payload: packet,
+            })
+            .is_err());
+        assert!(manager.pending_inbound.is_empty());
+
+        // offline signature included, stream is accepted
+        let packet = PacketBuilder::new(1338u32)
+            .with_synchronize()
+            .with_send_stream_id(0u32)
+            .with_replay_protection(&destination_id)
+            .with_from_included(destination)
+            .with_offline_signature(Some(offline_signature.serialize()))
+            .with_signature()
+            .build_and_sign(&transient_key)
+            .to_vec();
+
+        assert!(manager
+            .on_packet(I2cpPayload {
+                src_port: 13u16,
+                dst_port: 37u16,
+                protocol: Protocol::Streaming,
+                payload: packet,
+            })
+            .is_ok());
+        assert_eq!(manager.pending_inbound.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn inbound_stream_rejected_with_http_429() {
+        let signing_key = SigningPrivateKey::from_bytes(&[0u8; 32]).unwrap();
+        let destination = Destination::new::<MockRuntime>(signing_key.public());
+        let destination_id = destination.id();
+        let mut manager = StreamManager::<MockRuntime>::new(destination, signing_key).with_config(
+            StreamConfig::from_options(&HashMap::from_iter([
+                (
+                    String::from("i2p.streaming.maxConnsPerMinute"),
+                    String::from("1"),
+                ),
+                (
+                    String::from("i2p.streaming.limitAction"),
+                    String::from("http"),
+                ),
+            ])),
+        );
+
+        let signing_key = SigningPrivateKey::from_bytes(&[1u8; 32]).unwrap();
+        let destination = Destination::new::<MockRuntime>(signing_key.public());
+        let remote_destination_id = destination.id();
+
+        for recv_stream_id in [1337u32, 1338u32] {
+            let packet = PacketBuilder::new(recv_stream_id)
+                .with_synchronize()
+                .with_send_stream_id(0u32)
+                .with_replay_protection(&destination_id)
+                .with_from_included(destination.clone())
+                .with_signature()
+                .build_and_sign(&signing_key)
+                .to_vec();
+
+            assert!(manager
+                .on_packet(I2cpPayload {
+                    src_port: 13u16,
+                    dst_port: 37u16,
+                    protocol: Protocol::Streaming,
+                    payload: packet,
+                })
+                .is_ok());
+        }
+
+        // first stream is accepted and second stream is rejected
+        assert_eq!(manager.pending_inbound.len(), 1);
+        assert!(manager.pending_inbound.contains_key(&1337u32));
+
+        // syn-ack for the first stream
+        match tokio::time::timeout(Duration::from_secs(5), manager.next())
+            .await
+            .unwrap()
+            .unwrap()
+        {
+            StreamManagerEvent::SendPacket { packet, .. } => {
+                let Packet {
+                    send_stream_id,
+                    flags,
+                    ..
+                } = Packet::parse(&packet).unwrap();
+
+                assert_eq!(send_stream_id, 1337u32);
+                assert!(flags.synchronize());
+                assert!(!flags.close());
+            }
+            _ => panic!("invalid event"),
+        }
+
+        // http 429 response for the second stream
+        match tokio::time::timeout(Duration::from_secs(5), manager.next())
+            .await
+            .unwrap()
+            .unwrap()
+        {
+            StreamManagerEvent::SendPacket {
+                delivery_style,
+                packet,
+                ..
+            } => {
+                let Packet {
+                    send_stream_id,
+                    flags,
+                    payload,
+                    ..
+                } = Packet::parse(&packet).unwrap();
+
+                assert_eq!(delivery_style.destination_id(), &remote_destination_id);
+                assert_eq!(send_stream_id, 1338u32);
+                assert!(flags.synchronize());
+                assert!(flags.close());
+                assert!(core::str::from_utf8(payload).unwrap().starts_with("HTTP/1.1 429"));
+            }
+            _ => panic!("invalid event"),
+        }
+    }
+
+    #[tokio::test]
+    async fn inbound_stream_rejected_with_reset() {
+        let signing_key = SigningPrivateKey::from_bytes(&[0u8; 32]).unwrap();
+        let destination = Destination::new::<MockRuntime>(signing_key.public());
+        let destination_id = destination.id();
+
+        let signing_key_remote = SigningPrivateKey::from_bytes(&[1u8; 32]).unwrap();
+        let remote = Destination::new::<MockRuntime>(signing_key_remote.public());
+
+        let mut manager = StreamManager::<MockRuntime>::new(destination, signing_key).with_config(
+            StreamConfig::from_options(&HashMap::from_iter([(
+                String::from("i2p.streaming.blacklist"),
+                base64_encode(remote.id().to_vec()),
+            )])),
+        );
+
+        let packet = PacketBuilder::new(1337u32)
+            .with_synchronize()
+            .with_send_stream_id(0u32)
+            .with_replay_protection(&destination_id)
+            .with_from_included(remote)
+            .with_signature()
+            .build_and_sign(&signing_key_remote)
+            .to_vec();
+
+        assert!(manager
+            .on_packet(I2cpPayload {
+                src_port: 13u16,
+                dst_port: 37u16,
+                protocol: Protocol::Streaming,
+                payload: packet,
+            })
+            .is_ok());
+        assert!(manager.pending_inbound.is_empty());
+
+        match tokio::time::timeout(Duration::from_secs(5), manager.next())
+            .await
+            .unwrap()
+            .unwrap()
+        {
+            StreamManagerEvent::SendPacket { packet, .. } => {
+                let Packet {
+                    send_stream_id,
+                    flags,
+                    ..
+                } = Packet::parse(&packet).unwrap();
+
+                assert_eq!(send_stream_id, 1337u32);
+                assert!(flags.reset());
+                assert!(flags.signature().is_some());
+            }
+            _ => panic!("invalid event"),
+        }
+    }
+
+    #[tokio::test]
+    async fn ping_answered_with_pong() {
+        let signing_key = SigningPrivateKey::from_bytes(&[0u8; 32]).unwrap();
+        let destination = Destination::new::<MockRuntime>(signing_key.public());
+        let mut manager = StreamManager::<MockRuntime>::new(destination, signing_key);
+
+        let signing_key_remote = SigningPrivateKey::from_bytes(&[1u8; 32]).unwrap();
+        let remote = Destination::new::<MockRuntime>(signing_key_remote.public());
+        let remote_id = remote.id();
+
+        let packet = PacketBuilder::new(0u32)
+            .with_send_stream_id(1337u32)
+            .with_echo()
+            .with_from_included(remote)
+            .with_signature()
+            .with_payload(b"ping")
+            .build_and_sign(&signing_key_remote)
+            .to_vec();
+
+        assert!(manager
+            .on_packet(I2cpPayload {
+                src_port: 13u16,
+                dst_port: 37u16,
+                protocol: Protocol::Streaming,
+                payload: packet,
+            })
+            .is_ok());
+
+        match tokio::time::timeout(Duration::from_secs(5), manager.next())
+            .await
+            .unwrap()
+            .unwrap()
+        {
+            StreamManagerEvent::SendPacket {
+                delivery_style: DeliveryStyle::Unspecified { destination_id },
+                packet,
+                src_port,
+                dst_port,
+            } => {
+                let Packet {
+                    send_stream_id,
+                    recv_stream_id,
+                    flags,
+                    payload,
+                    ..
+                } = Packet::parse(&packet).unwrap();
+
+                assert_eq!(destination_id, remote_id);
+                assert_eq!((src_port, dst_port), (37u16, 13u16));
+                assert_eq!(send_stream_id, 0u32);
+                assert_eq!(recv_stream_id, 1337u32);
+                assert!(flags.echo());
+                assert_eq!(payload, b"ping");
+            }
+            _ => panic!("invalid event"),
+        }
+    }
+
+    #[tokio::test]
+    async fn ping_ignored() {
+        let signing_key = SigningPrivateKey::from_bytes(&[0u8; 32]).unwrap();
+        let destination = Destination::new::<MockRuntime>(signing_key.public());
+        let mut manager = StreamManager::<MockRuntime>::new(destination, signing_key).with_config(
+            StreamConfig::from_options(&HashMap::from_iter([(
+                String::from("i2p.streaming.answerPings"),
+                String::from("false"),
+            )])),
+        );
+
+        let signing_key_remote = SigningPrivateKey::from_bytes(&[1u8; 32]).unwrap();
+        let remote = Destination::new::<MockRuntime>(signing_key_remote.public());
+
+        let packet = PacketBuilder::new(0u32)
+            .with_send_stream_id(1337u32)
+            .with_echo()
+            .with_from_included(remote)
+            .with_signature()
+            .build_and_sign(&signing_key_remote)
+            .to_vec();
+
+        assert!(manager
+            .on_packet(I2cpPayload {
+                src_port: 13u16,
+                dst_port: 37u16,
+                protocol: Protocol::Streaming,
+                payload: packet,
+            })
+            .is_ok());
+        assert!(tokio::time::timeout(Duration::from_secs(2), manager.next()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn unsigned_ping_rejected() {
+        let signing_key = SigningPrivateKey::from_bytes(&[0u8; 32]).unwrap();
+        let destination = Destination::new::<MockRuntime>(signing_key.public());
+        let mut manager = StreamManager::<MockRuntime>::new(destination, signing_key);
+
+        let packet = PacketBuilder::new(0u32)
+            .with_send_stream_id(1337u32)
+            .with_echo()
+            .build()
+            .to_vec();
+
+        assert_eq!(
+            manager.on_packet(I2cpPayload {
+                src_port: 13u16,
+                dst_port: 37u16,
+                protocol: Protocol::Streaming,
+                payload: packet,
+            }),
+            Err(StreamingError::SignatureMissing)
+        );
+    }
+
     #[tokio::test]
     async fn pending_stream_initialized_with_silent_listener() {
         let signing_key = SigningPrivateKey::from_bytes(&[0u8; 32]).unwrap();
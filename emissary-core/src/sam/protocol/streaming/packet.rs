@@ -168,10 +168,10 @@ impl<'a> Flags<'a> {
                     return Err(Err::Error(make_error(options, ErrorKind::Fail)));
                 }
                 Some(destination) => {
-                    let (rest, verifying_key) =
+                    let (rest, offline_signature) =
                         OfflineSignature::parse_frame(rest, destination.verifying_key())?;
 
-                    (rest, Some(verifying_key))
+                    (rest, Some(offline_signature.verifying_key))
                 }
             },
             false => (rest, None),
@@ -465,7 +465,7 @@ pub struct FlagsBuilder<'a> {
     signature: Option<&'a [u8]>,
 }
 
-impl FlagsBuilder<'_> {
+impl<'a> FlagsBuilder<'a> {
     /// Specify `SYNCHRONIZE` .
     pub fn with_synchronize(mut self) -> Self {
         self.flags |= 1;
@@ -527,6 +527,14 @@ impl FlagsBuilder<'_> {
         self
     }
 
+    /// Specify offline signature.
+    pub fn with_offline_signature(mut self, offline_signature: &'a [u8]) -> Self {
+        self.offline_signature = Some(offline_signature);
+        self.options_len += offline_signature.len();
+        self.flags |= (1 << 11);
+        self
+    }
+
     /// Build [`FlagsBuilder`] and return `(flags, options)` tuple.
     fn build(self) -> (u16, Option<BytesMut>) {
         // no options
@@ -548,6 +556,10 @@ impl FlagsBuilder<'_> {
             out.put_u16(max_packet_size);
         }
 
+        if let Some(offline_signature) = self.offline_signature {
+            out.put_slice(offline_signature);
+        }
+
         // the field needs to be all zeros when the signature is calculated
         if (self.flags >> 3) & 1 == 1 {
             out.put_slice(&[0u8; 64]);
@@ -689,6 +701,14 @@ impl<'a> PacketBuilder<'a> {
         self
     }
 
+    /// Specify offline signature, if the local destination uses one.
+    pub fn with_offline_signature(mut self, offline_signature: Option<&'a [u8]>) -> Self {
+        if let Some(offline_signature) = offline_signature {
+            self.flags_builder = self.flags_builder.with_offline_signature(offline_signature);
+        }
+        self
+    }
+
     /// Specify `ECHO`.
     pub fn with_echo(mut self) -> Self {
         self.flags_builder = self.flags_builder.with_echo();
@@ -965,6 +985,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn build_syn_with_offline_signature() {
+        let signing_key = SigningPrivateKey::random(MockRuntime::rng());
+        let transient_key = SigningPrivateKey::random(MockRuntime::rng());
+        let destination = Destination::new::<MockRuntime>(signing_key.public());
+        let offline_signature = OfflineSignature::new(
+            MockRuntime::time_since_epoch() + core::time::Duration::from_secs(60 * 60),
+            transient_key.public(),
+            &signing_key,
+        );
+
+        let serialized = PacketBuilder::new(MockRuntime::rng().next_u32())
+            .with_send_stream_id(0)
+            .with_synchronize()
+            .with_signature()
+            .with_max_packet_size(1337)
+            .with_from_included(destination.clone())
+            .with_offline_signature(Some(offline_signature.serialize()))
+            .with_payload(b"hello, world")
+            .build_and_sign(&transient_key);
+
+        let packet = Packet::parse(&serialized).unwrap();
+
+        assert!(packet.flags.synchronize());
+        assert!(packet.flags.signature().is_some());
+        assert_eq!(packet.flags.max_packet_size(), Some(1337));
+        assert_eq!(
+            packet.flags.offline_signature(),
+            Some(&transient_key.public())
+        );
+        assert_eq!(packet.payload, b"hello, world");
+    }
+
     #[test]
     fn build_ack_packet() {
         let serialized = PacketBuilder::new(1337)
@@ -23,11 +23,13 @@ use crate::{
     primitives::{Destination, DestinationId},
     runtime::{AsyncRead, AsyncWrite, Instant, Runtime},
     sam::protocol::streaming::{
-        config::StreamConfig,
+        config::{InactivityAction, StreamConfig},
         packet::{Packet, PacketBuilder},
+        tcb::{TcbCache, TcbState},
     },
 };
 
+use bytes::Bytes;
 use futures::FutureExt;
 use rand_core::RngCore;
 use thingbuf::mpsc::{Receiver, Sender};
@@ -59,14 +61,8 @@ const INITIAL_ACK_DELAY: Duration = Duration::from_millis(200);
 /// Sequence number for a plain ACK message.
 const PLAIN_ACK: u32 = 0u32;
 
-/// Initial window size.
-const INITIAL_WINDOW_SIZE: usize = 1usize;
-
-/// Maximum window size in packets.
-const MAX_WINDOW_SIZE: usize = 128usize;
-
 /// How far ahead of the current highest received sequence number is a packet accepted.
-const MAX_WINDOW_LOOKAHEAD: usize = 4 * MAX_WINDOW_SIZE;
+const MAX_WINDOW_LOOKAHEAD: usize = 512usize;
 
 /// Delay request which indicates choking.
 const CHOKING_REQUEST: u16 = 60_001u16;
@@ -74,12 +70,6 @@ const CHOKING_REQUEST: u16 = 60_001u16;
 /// Maximum number of NACKs sent.
 const MAX_NACKS: usize = 255usize;
 
-/// Initial RTO.
-const INITIAL_RTO: Duration = Duration::from_millis(9000);
-
-/// Initial RTT.
-const INITIAL_RTT: Duration = Duration::from_millis(8000);
-
 /// RTT dampening factor (alpha).
 const RTT_DAMPENING_FACTOR: f64 = 0.125f64;
 
@@ -118,8 +108,8 @@ enum Rtt {
 
 impl Rtt {
     /// Create new [`Rtt`].
-    fn new() -> Self {
-        Self::Unsampled(INITIAL_RTT)
+    fn new(initial_rtt: Duration) -> Self {
+        Self::Unsampled(initial_rtt)
     }
 
     /// Calculate new [`Rtt`] from `sample` and previous RTT.
@@ -160,8 +150,21 @@ enum Rto {
 
 impl Rto {
     /// Create new [`Rto`].
-    fn new() -> Self {
-        Self::Unsampled(INITIAL_RTO)
+    fn new(initial_rto: Duration) -> Self {
+        Self::Unsampled(initial_rto)
+    }
+
+    /// Create new [`Rto`] from RTT and RTT deviation of a shared control block.
+    fn from_shared(rtt: Duration, rtt_dev: Duration) -> Self {
+        Self::Sampled((rtt + rtt_dev * 4, rtt_dev, 1))
+    }
+
+    /// Get RTT deviation, if RTO has been sampled.
+    fn rtt_dev(&self) -> Option<Duration> {
+        match self {
+            Self::Unsampled(_) => None,
+            Self::Sampled((_, rtt_dev, _)) => Some(*rtt_dev),
+        }
     }
 
     fn calculate_rto(&mut self, rtt: &Rtt, sample: Duration) {
@@ -273,6 +276,9 @@ pub struct StreamContext {
 
     /// Signing key.
     pub signing_key: SigningPrivateKey,
+
+    /// Serialized offline signature, if the local destination uses one.
+    pub offline_signature: Option<Bytes>,
 }
 
 /// Pending outbound packet.
@@ -504,12 +510,33 @@ pub struct Stream<R: Runtime> {
     /// TX channel for sending [`Packet`]s to the network.
     event_tx: Sender<(DeliveryStyle, Vec<u8>, u16, u16)>,
 
+    /// What to do when the stream has been inactive for `inactivity_timeout`.
+    inactivity_action: InactivityAction,
+
+    /// How long the stream can be inactive before `inactivity_action` is taken.
+    inactivity_timeout: Duration,
+
+    /// Inactivity timer.
+    inactivity_timer: R::Timer,
+
     /// Inbound context for packets received from the network.
     inbound_context: InboundContext<R>,
 
+    /// Initial RTO.
+    initial_rto: Duration,
+
+    /// Initial RTT.
+    initial_rtt: Duration,
+
+    /// When was the last packet received from the remote destination.
+    last_activity: R::Instant,
+
     /// ID of the local destination.
     local: DestinationId,
 
+    /// Maximum window size.
+    max_window_size: usize,
+
     /// Next sequence number.
     next_seq_nro: u32,
 
@@ -544,6 +571,9 @@ pub struct Stream<R: Runtime> {
     /// Send stream ID (selected by us).
     send_stream_id: u32,
 
+    /// Serialized offline signature, if the local destination uses one.
+    offline_signature: Option<Bytes>,
+
     /// Signing key.
     signing_key: SigningPrivateKey,
 
@@ -553,6 +583,9 @@ pub struct Stream<R: Runtime> {
     /// Underlying TCP stream used to communicate with the client.
     stream: R::TcpStream,
 
+    /// Shared TCP control block cache.
+    tcb: TcbCache<R>,
+
     /// Pending (unACKed) outbound packets.
     unacked: BTreeMap<u32, PendingPacket<R>>,
 
@@ -569,7 +602,8 @@ impl<R: Runtime> Stream<R> {
         stream: R::TcpStream,
         initial_message: Option<Vec<u8>>,
         context: StreamContext,
-        _: StreamConfig,
+        config: StreamConfig,
+        tcb: TcbCache<R>,
         state: StreamKind,
         mut routing_path_handle: RoutingPathHandle<R>,
     ) -> Self {
@@ -580,6 +614,7 @@ impl<R: Runtime> Stream<R> {
             event_tx,
             recv_stream_id,
             signing_key,
+            offline_signature,
             destination,
         } = context;
 
@@ -589,6 +624,7 @@ impl<R: Runtime> Stream<R> {
                 let packet = PacketBuilder::new(send_stream_id)
                     .with_send_stream_id(recv_stream_id)
                     .with_from_included(destination.clone())
+                    .with_offline_signature(offline_signature.as_deref())
                     .with_seq_nro(0)
                     .with_synchronize()
                     .with_signature()
@@ -683,14 +719,40 @@ impl<R: Runtime> Stream<R> {
             ),
         };
 
+        // initialize rtt, rto and window size from the shared control block of the remote
+        // destination if it exists and otherwise use the configured initial values
+        let (rtt, rto, window_size) = match tcb.get(&remote) {
+            Some(TcbState {
+                rtt,
+                rtt_dev,
+                window_size,
+            }) => (
+                Rtt::Sampled(rtt),
+                Rto::from_shared(rtt, rtt_dev),
+                cmp::min(window_size, config.max_window_size),
+            ),
+            None => (
+                Rtt::new(config.initial_rtt),
+                Rto::new(config.initial_rto),
+                config.initial_window_size,
+            ),
+        };
+
         Self {
             close_requested: false,
             cmd_rx,
             destination,
             dst_port,
             event_tx,
+            inactivity_action: config.inactivity_action,
+            inactivity_timeout: config.inactivity_timeout,
+            inactivity_timer: R::timer(config.inactivity_timeout),
             inbound_context: InboundContext::new(highest_ack),
+            initial_rto: config.initial_rto,
+            initial_rtt: config.initial_rtt,
+            last_activity: R::now(),
             local,
+            max_window_size: config.max_window_size,
             next_seq_nro: 1u32,
             pending: BTreeMap::new(),
             read_buffer: vec![0u8; READ_BUFFER_SIZE],
@@ -698,15 +760,17 @@ impl<R: Runtime> Stream<R> {
             recv_stream_id,
             remote,
             routing_path_handle,
-            rto: Rto::new(),
+            rto,
             rto_timer: None,
-            rtt: Rtt::new(),
+            rtt,
             send_stream_id,
+            offline_signature,
             signing_key,
             src_port,
             stream,
+            tcb,
             unacked: BTreeMap::new(),
-            window_size: INITIAL_WINDOW_SIZE,
+            window_size,
             write_state: match initial_message {
                 None => WriteState::GetMessage,
                 Some(message) => WriteState::WriteMessage {
@@ -768,7 +832,7 @@ impl<R: Runtime> Stream<R> {
 
             if self.window_size < EXP_GROWTH_STOP_THRESHOLD {
                 self.window_size *= 2;
-            } else if self.window_size < MAX_WINDOW_SIZE {
+            } else if self.window_size < self.max_window_size {
                 self.window_size += 1;
             }
         }
@@ -785,6 +849,23 @@ impl<R: Runtime> Stream<R> {
             ..
         } = Packet::parse(&packet).ok_or(StreamingError::Malformed)?;
 
+        self.last_activity = R::now();
+
+        // pings are answered by `StreamManager` so this must be a pong which needs no handling
+        // other than marking the stream as active
+        if flags.echo() {
+            tracing::trace!(
+                target: LOG_TARGET,
+                local = %self.local,
+                remote = %self.remote,
+                recv_id = ?self.recv_stream_id,
+                send_id = ?self.send_stream_id,
+                "received pong",
+            );
+
+            return Ok(());
+        }
+
         if flags.synchronize() {
             tracing::warn!(
                 target: LOG_TARGET,
@@ -800,6 +881,7 @@ impl<R: Runtime> Stream<R> {
                 .with_seq_nro(0)
                 .with_synchronize()
                 .with_from_included(self.destination.clone())
+                .with_offline_signature(self.offline_signature.as_deref())
                 .with_signature()
                 .build_and_sign(&self.signing_key);
 
@@ -1000,8 +1082,8 @@ impl<R: Runtime> Stream<R> {
                     outbound = ?routing_path.outbound,
                     "routing path recreated"
                 );
-                self.rto = Rto::new();
-                self.rtt = Rtt::new();
+                self.rto = Rto::new(self.initial_rto);
+                self.rtt = Rtt::new(self.initial_rtt);
 
                 routing_path
             }
@@ -1090,6 +1172,7 @@ impl<R: Runtime> Stream<R> {
             .with_seq_nro(seq_nro)
             .with_close()
             .with_from_included(self.destination.clone())
+            .with_offline_signature(self.offline_signature.as_deref())
             .with_signature()
             .build_and_sign(&self.signing_key)
             .to_vec();
@@ -1152,6 +1235,89 @@ impl<R: Runtime> Stream<R> {
             }
         }
     }
+
+    /// Handle inactivity timeout.
+    ///
+    /// Returns `true` if the stream should be closed.
+    fn on_inactivity(&mut self) -> bool {
+        tracing::debug!(
+            target: LOG_TARGET,
+            local = %self.local,
+            remote = %self.remote,
+            recv_id = ?self.recv_stream_id,
+            send_id = ?self.send_stream_id,
+            action = ?self.inactivity_action,
+            "stream inactive",
+        );
+
+        let (packet, close) = match self.inactivity_action {
+            InactivityAction::DoNothing => return false,
+            // send duplicate ack to keep the tunnels to remote destination alive
+            //
+            // if the stream is closing, the stream is disconnected instead
+            InactivityAction::Send if !self.close_requested => (
+                PacketBuilder::new(self.send_stream_id)
+                    .with_send_stream_id(self.recv_stream_id)
+                    .with_ack_through(self.inbound_context.seq_nro)
+                    .with_seq_nro(PLAIN_ACK)
+                    .build()
+                    .to_vec(),
+                false,
+            ),
+            InactivityAction::Send | InactivityAction::Disconnect => (
+                PacketBuilder::new(self.send_stream_id)
+                    .with_send_stream_id(self.recv_stream_id)
+                    .with_ack_through(self.inbound_context.seq_nro)
+                    .with_reset()
+                    .with_from_included(self.destination.clone())
+                    .with_offline_signature(self.offline_signature.as_deref())
+                    .with_signature()
+                    .build_and_sign(&self.signing_key)
+                    .to_vec(),
+                true,
+            ),
+        };
+
+        if let Err(error) = self.event_tx.try_send((
+            match self.routing_path_handle.routing_path() {
+                None => DeliveryStyle::Unspecified {
+                    destination_id: self.remote.clone(),
+                },
+                Some(routing_path) => DeliveryStyle::ViaRoute { routing_path },
+            },
+            packet,
+            self.src_port,
+            self.dst_port,
+        )) {
+            tracing::trace!(
+                target: LOG_TARGET,
+                local = %self.local,
+                remote = %self.remote,
+                recv_id = ?self.recv_stream_id,
+                send_id = ?self.send_stream_id,
+                ?error,
+                "failed to send packet",
+            );
+        }
+
+        close
+    }
+}
+
+impl<R: Runtime> Drop for Stream<R> {
+    fn drop(&mut self) {
+        // share measured rtt and window size with future streams to the remote destination
+        if let (Rtt::Sampled(rtt), Some(rtt_dev)) = (self.rtt, self.rto.rtt_dev()) {
+            self.tcb.update(
+                &self.remote,
+                TcbState {
+                    rtt,
+                    rtt_dev,
+                    window_size: self.window_size,
+                },
+            );
+        }
+    }
 }
 
 impl<R: Runtime> Future for Stream<R> {
@@ -1426,6 +1592,7 @@ impl<R: Runtime> Future for Stream<R> {
                 builder
                     .with_close()
                     .with_from_included(this.destination.clone())
+                    .with_offline_signature(this.offline_signature.as_deref())
                     .with_signature()
                     .build_and_sign(&this.signing_key)
             } else {
@@ -1460,6 +1627,24 @@ impl<R: Runtime> Future for Stream<R> {
             }
         }
 
+        // the inactivity timer is reset lazily: if a packet has been received since the timer was
+        // started, the timer is restarted for the remaining time of the inactivity timeout
+        while this.inactivity_timer.poll_unpin(cx).is_ready() {
+            let elapsed = this.last_activity.elapsed();
+
+            if elapsed < this.inactivity_timeout {
+                this.inactivity_timer = R::timer(this.inactivity_timeout - elapsed);
+                continue;
+            }
+
+            if this.on_inactivity() {
+                return Poll::Ready(this.recv_stream_id);
+            }
+
+            this.last_activity = R::now();
+            this.inactivity_timer = R::timer(this.inactivity_timeout);
+        }
+
         Poll::Pending
     }
 }
@@ -1485,6 +1670,18 @@ mod tests {
     use thingbuf::mpsc::channel;
     use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 
+    /// Default initial window size.
+    const INITIAL_WINDOW_SIZE: usize = 1usize;
+
+    /// Default maximum window size.
+    const MAX_WINDOW_SIZE: usize = 128usize;
+
+    /// Default initial RTO.
+    const INITIAL_RTO: Duration = Duration::from_millis(9000);
+
+    /// Default initial RTT.
+    const INITIAL_RTT: Duration = Duration::from_millis(8000);
+
     struct StreamBuilder {
         cmd_tx: Sender<StreamEvent>,
         event_rx: Receiver<(DeliveryStyle, Vec<u8>, u16, u16)>,
@@ -1495,6 +1692,20 @@ mod tests {
 
     impl StreamBuilder {
         async fn build_stream() -> (Stream<MockRuntime>, Self) {
+            Self::build_stream_with_config(Default::default()).await
+        }
+
+        async fn build_stream_with_config(config: StreamConfig) -> (Stream<MockRuntime>, Self) {
+            let tcb = TcbCache::new(&config);
+
+            Self::build_stream_with_tcb(config, tcb, DestinationId::random()).await
+        }
+
+        async fn build_stream_with_tcb(
+            config: StreamConfig,
+            tcb: TcbCache<MockRuntime>,
+            remote: DestinationId,
+        ) -> (Stream<MockRuntime>, Self) {
             let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
             let signing_key = SigningPrivateKey::random(MockRuntime::rng());
             let destination = Destination::new::<MockRuntime>(signing_key.public());
@@ -1508,7 +1719,6 @@ mod tests {
             let (event_tx, event_rx) = channel(64);
             let (cmd_tx, cmd_rx) = channel(64);
 
-            let remote = DestinationId::random();
             let outbound = TunnelId::random();
             let inbound = Lease::random();
             let mut path_manager =
@@ -1528,10 +1738,12 @@ mod tests {
                         event_tx,
                         local: destination_id,
                         recv_stream_id: 1337u32,
-                        remote: DestinationId::random(),
+                        remote,
                         signing_key,
+                        offline_signature: None,
                     },
-                    Default::default(),
+                    config,
+                    tcb,
                     StreamKind::Inbound { payload: vec![] },
                     handle,
                 ),
@@ -1627,8 +1839,10 @@ mod tests {
                             recv_stream_id: 1337u32,
                             remote: inbound_destination_id.clone(),
                             signing_key: outbound_signing_key,
+                            offline_signature: None,
                         },
                         Default::default(),
+                        TcbCache::new(&Default::default()),
                         StreamKind::Outbound {
                             dst_port: 0,
                             payload: Vec::new(),
@@ -1657,8 +1871,10 @@ mod tests {
                             recv_stream_id: 1338u32,
                             remote: outbound_destination_id,
                             signing_key: inbound_signing_key,
+                            offline_signature: None,
                         },
                         Default::default(),
+                        TcbCache::new(&Default::default()),
                         StreamKind::Inbound { payload: vec![] },
                         inbound_path_handle,
                     ),
@@ -2135,6 +2351,151 @@ mod tests {
             .unwrap();
     }
 
+    #[tokio::test]
+    async fn inactivity_sends_duplicate_ack() {
+        let (
+            stream,
+            StreamBuilder {
+                cmd_tx,
+                event_rx,
+                stream: _stream,
+                ..
+            },
+        ) = StreamBuilder::build_stream_with_config(StreamConfig {
+            inactivity_timeout: Duration::from_secs(1),
+            inactivity_action: InactivityAction::Send,
+            ..Default::default()
+        })
+        .await;
+
+        let handle = tokio::spawn(stream);
+
+        // syn-ack sent for the inbound stream
+        let (_, packet, _, _) = event_rx.recv().await.unwrap();
+        assert!(Packet::parse(&packet).unwrap().flags.synchronize());
+
+        cmd_tx
+            .send(StreamEvent::Packet {
+                packet: PacketBuilder::new(1338u32)
+                    .with_send_stream_id(1337u32)
+                    .with_seq_nro(1u32)
+                    .with_payload(b"hello, world\n")
+                    .build()
+                    .to_vec(),
+            })
+            .await
+            .unwrap();
+
+        // ack for the received data
+        let (_, packet, _, _) = event_rx.recv().await.unwrap();
+        assert_eq!(Packet::parse(&packet).unwrap().ack_through, 1u32);
+
+        // verify that duplicate acks are sent while the stream is inactive
+        for _ in 0..2 {
+            let (_, packet, _, _) = tokio::time::timeout(Duration::from_secs(3), event_rx.recv())
+                .await
+                .expect("no timeout")
+                .unwrap();
+            let packet = Packet::parse(&packet).unwrap();
+
+            assert_eq!(packet.seq_nro, PLAIN_ACK);
+            assert_eq!(packet.ack_through, 1u32);
+            assert!(!packet.flags.reset());
+        }
+
+        assert!(!handle.is_finished());
+    }
+
+    #[tokio::test]
+    async fn inactivity_disconnects_stream() {
+        let (
+            stream,
+            StreamBuilder {
+                cmd_tx: _cmd_tx,
+                event_rx,
+                stream: _stream,
+                ..
+            },
+        ) = StreamBuilder::build_stream_with_config(StreamConfig {
+            inactivity_timeout: Duration::from_secs(1),
+            inactivity_action: InactivityAction::Disconnect,
+            ..Default::default()
+        })
+        .await;
+
+        let handle = tokio::spawn(stream);
+
+        // syn-ack sent for the inbound stream
+        let (_, packet, _, _) = event_rx.recv().await.unwrap();
+        assert!(Packet::parse(&packet).unwrap().flags.synchronize());
+
+        // verify that the stream is reset after the inactivity timeout
+        let (_, packet, _, _) = tokio::time::timeout(Duration::from_secs(3), event_rx.recv())
+            .await
+            .expect("no timeout")
+            .unwrap();
+        assert!(Packet::parse(&packet).unwrap().flags.reset());
+
+        assert_eq!(
+            tokio::time::timeout(Duration::from_secs(5), handle)
+                .await
+                .expect("no timeout")
+                .unwrap(),
+            1337u32
+        );
+    }
+
+    #[tokio::test]
+    async fn pong_resets_inactivity_timer() {
+        let (
+            stream,
+            StreamBuilder {
+                cmd_tx,
+                event_rx,
+                stream: _stream,
+                ..
+            },
+        ) = StreamBuilder::build_stream_with_config(StreamConfig {
+            inactivity_timeout: Duration::from_secs(2),
+            inactivity_action: InactivityAction::Disconnect,
+            ..Default::default()
+        })
+        .await;
+
+        let handle = tokio::spawn(stream);
+
+        // syn-ack sent for the inbound stream
+        let (_, packet, _, _) = event_rx.recv().await.unwrap();
+        assert!(Packet::parse(&packet).unwrap().flags.synchronize());
+
+        // keep the stream active with pongs
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+
+            cmd_tx
+                .send(StreamEvent::Packet {
+                    packet: PacketBuilder::new(1337u32)
+                        .with_send_stream_id(0u32)
+                        .with_echo()
+                        .with_no_ack()
+                        .build()
+                        .to_vec(),
+                })
+                .await
+                .unwrap();
+        }
+
+        assert!(event_rx.try_recv().is_err());
+        assert!(!handle.is_finished());
+
+        // verify that the stream is reset once pongs are no longer received
+        let (_, packet, _, _) = tokio::time::timeout(Duration::from_secs(4), event_rx.recv())
+            .await
+            .expect("no timeout")
+            .unwrap();
+        assert!(Packet::parse(&packet).unwrap().flags.reset());
+    }
+
     #[tokio::test]
     async fn duplicate_packets() {
         let (
@@ -2949,6 +3310,69 @@ mod tests {
         assert_eq!(stream.window_size, 66);
     }
 
+    #[tokio::test]
+    async fn initial_values_from_config() {
+        let (stream, _builder) = StreamBuilder::build_stream_with_config(StreamConfig {
+            initial_window_size: 4,
+            max_window_size: 8,
+            initial_rto: Duration::from_secs(3),
+            initial_rtt: Duration::from_secs(2),
+            ..Default::default()
+        })
+        .await;
+
+        assert_eq!(stream.window_size, 4);
+        assert_eq!(stream.max_window_size, 8);
+        assert_eq!(*stream.rto, Duration::from_secs(3));
+        assert_eq!(*stream.rtt, Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn control_block_shared_between_streams() {
+        let config = StreamConfig::default();
+        let tcb = TcbCache::<MockRuntime>::new(&config);
+        let remote = DestinationId::random();
+
+        tcb.update(
+            &remote,
+            TcbState {
+                rtt: Duration::from_millis(500),
+                rtt_dev: Duration::from_millis(100),
+                window_size: 16,
+            },
+        );
+
+        // new stream to the remote destination is initialized from the shared control block
+        let (mut stream, _builder) =
+            StreamBuilder::build_stream_with_tcb(config.clone(), tcb.clone(), remote.clone()).await;
+
+        assert_eq!(stream.window_size, 16);
+        assert_eq!(*stream.rtt, Duration::from_millis(500));
+        assert_eq!(*stream.rto, Duration::from_millis(900));
+
+        // measurements of the stream are merged into the shared control block when it's dropped
+        stream.window_size = 32;
+        stream.rtt = Rtt::Sampled(Duration::from_millis(1500));
+        drop(stream);
+
+        assert_eq!(
+            tcb.get(&remote),
+            Some(TcbState {
+                rtt: Duration::from_millis(750),
+                rtt_dev: Duration::from_millis(100),
+                window_size: 20,
+            })
+        );
+
+        // stream to another destination uses default values
+        let (stream, _builder) =
+            StreamBuilder::build_stream_with_tcb(config, tcb, DestinationId::random()).await;
+
+        assert_eq!(stream.window_size, INITIAL_WINDOW_SIZE);
+        assert_eq!(*stream.rtt, INITIAL_RTT);
+        assert_eq!(*stream.rto, INITIAL_RTO);
+    }
+
     #[tokio::test]
     async fn rtt_rto_calculated_correctly() {
         let (
@@ -3181,8 +3605,8 @@ mod tests {
 
     #[test]
     fn exponential_backoff_rto() {
-        let mut rto = Rto::new();
-        let mut rtt = Rtt::new();
+        let mut rto = Rto::new(INITIAL_RTO);
+        let mut rtt = Rtt::new(INITIAL_RTT);
 
         for _ in 0..10 {
             let sample = Duration::from_millis(100 + 1);
@@ -96,12 +96,14 @@ impl<R: Runtime> PendingStream<R> {
         recv_stream_id: u32,
         syn_payload: Vec<u8>,
         signing_key: &SigningPrivateKey,
+        offline_signature: Option<&[u8]>,
     ) -> (Self, Vec<u8>) {
         let send_stream_id = R::rng().next_u32();
         let packet = PacketBuilder::new(send_stream_id)
             .with_send_stream_id(recv_stream_id)
             .with_seq_nro(0)
             .with_from_included(destination)
+            .with_offline_signature(offline_signature)
             .with_synchronize()
             .with_signature()
             .build_and_sign(signing_key)
@@ -216,6 +218,7 @@ mod tests {
             1337u32,
             vec![],
             &SigningPrivateKey::random(NoopRuntime::rng()),
+            None,
         );
 
         let packet = PacketBuilder::new(stream.send_stream_id)
@@ -239,6 +242,7 @@ mod tests {
             1337u32,
             vec![],
             &SigningPrivateKey::random(NoopRuntime::rng()),
+            None,
         );
 
         let packet = PacketBuilder::new(stream.send_stream_id)
@@ -263,6 +267,7 @@ mod tests {
             1337u32,
             vec![],
             &SigningPrivateKey::random(NoopRuntime::rng()),
+            None,
         );
 
         let packet = PacketBuilder::new(stream.send_stream_id)
@@ -287,6 +292,7 @@ mod tests {
             1337u32,
             vec![],
             &SigningPrivateKey::random(NoopRuntime::rng()),
+            None,
         );
 
         for i in 1..=3 {
@@ -333,6 +339,7 @@ mod tests {
             1337u32,
             vec![],
             &SigningPrivateKey::random(NoopRuntime::rng()),
+            None,
         );
 
         match stream.on_packet(vec![1, 2, 3, 4]) {
@@ -350,6 +357,7 @@ mod tests {
             1337u32,
             vec![],
             &SigningPrivateKey::random(NoopRuntime::rng()),
+            None,
         );
 
         for i in 1..=INITIAL_WINDOW_SIZE {
@@ -398,6 +406,7 @@ mod tests {
             1337u32,
             vec![1, 2, 3, 4],
             &SigningPrivateKey::random(NoopRuntime::rng()),
+            None,
         );
 
         match stream.packets.pop_front() {
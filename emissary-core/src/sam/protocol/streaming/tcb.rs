@@ -0,0 +1,212 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Shared TCP control block.
+//!
+//! Implements temporal sharing of RTT and window size between streams to the same remote
+//! destination as described in RFC 2140. When a stream is closed, its RTT, RTT deviation and
+//! window size are merged into the cached values of the remote destination and new streams to the
+//! destination are initialized from the cached values instead of the conservative defaults.
+
+use crate::{
+    primitives::DestinationId,
+    runtime::{Instant, Runtime},
+    sam::protocol::streaming::config::StreamConfig,
+};
+
+use hashbrown::HashMap;
+
+#[cfg(feature = "std")]
+use parking_lot::RwLock;
+#[cfg(feature = "no_std")]
+use spin::rwlock::RwLock;
+
+use alloc::sync::Arc;
+use core::time::Duration;
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::streaming::tcb";
+
+/// How long is a cached control block valid after it was last updated.
+const TCB_EXPIRATION: Duration = Duration::from_secs(15 * 60);
+
+/// Shared control block state of a remote destination.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TcbState {
+    /// Smoothed RTT.
+    pub rtt: Duration,
+
+    /// RTT deviation.
+    pub rtt_dev: Duration,
+
+    /// Window size.
+    pub window_size: usize,
+}
+
+/// Cached control block.
+struct TcbEntry<R: Runtime> {
+    /// Cached state.
+    state: TcbState,
+
+    /// When was the entry last updated.
+    updated: R::Instant,
+}
+
+/// Shared TCP control block cache.
+///
+/// Cloned to every stream of the session.
+pub struct TcbCache<R: Runtime> {
+    /// Cached control blocks, indexed by remote destination ID.
+    entries: Arc<RwLock<HashMap<DestinationId, TcbEntry<R>>>>,
+
+    /// Dampening factor for RTT.
+    rtt_dampening: f64,
+
+    /// Dampening factor for RTT deviation.
+    rttdev_dampening: f64,
+
+    /// Dampening factor for window size.
+    wdw_dampening: f64,
+}
+
+impl<R: Runtime> Clone for TcbCache<R> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: Arc::clone(&self.entries),
+            rtt_dampening: self.rtt_dampening,
+            rttdev_dampening: self.rttdev_dampening,
+            wdw_dampening: self.wdw_dampening,
+        }
+    }
+}
+
+impl<R: Runtime> TcbCache<R> {
+    /// Create new [`TcbCache`].
+    pub fn new(config: &StreamConfig) -> Self {
+        Self {
+            entries: Default::default(),
+            rtt_dampening: config.rtt_dampening,
+            rttdev_dampening: config.rttdev_dampening,
+            wdw_dampening: config.wdw_dampening,
+        }
+    }
+
+    /// Get cached state of `destination_id`, if it exists and hasn't expired.
+    pub fn get(&self, destination_id: &DestinationId) -> Option<TcbState> {
+        self.entries
+            .read()
+            .get(destination_id)
+            .and_then(|entry| (entry.updated.elapsed() < TCB_EXPIRATION).then_some(entry.state))
+    }
+
+    /// Merge `state` of a closed stream into the cached state of `destination_id`.
+    ///
+    /// Expired entries are pruned from the cache.
+    pub fn update(&self, destination_id: &DestinationId, state: TcbState) {
+        let mut entries = self.entries.write();
+
+        entries.retain(|_, entry| entry.updated.elapsed() < TCB_EXPIRATION);
+
+        let state = match entries.get(destination_id) {
+            None => state,
+            Some(TcbEntry { state: cached, .. }) => TcbState {
+                rtt: dampen(cached.rtt, state.rtt, self.rtt_dampening),
+                rtt_dev: dampen(cached.rtt_dev, state.rtt_dev, self.rttdev_dampening),
+                window_size: (self.wdw_dampening * cached.window_size as f64
+                    + (1f64 - self.wdw_dampening) * state.window_size as f64)
+                    .round()
+                    .max(1f64) as usize,
+            },
+        };
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            remote = %destination_id,
+            rtt = ?state.rtt,
+            rtt_dev = ?state.rtt_dev,
+            window_size = ?state.window_size,
+            "update shared control block",
+        );
+
+        entries.insert(
+            destination_id.clone(),
+            TcbEntry {
+                state,
+                updated: R::now(),
+            },
+        );
+    }
+}
+
+/// Dampen `sample` using `cached` value and `factor`.
+fn dampen(cached: Duration, sample: Duration, factor: f64) -> Duration {
+    Duration::from_millis(
+        (factor * cached.as_millis() as f64 + (1f64 - factor) * sample.as_millis() as f64) as u64,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::mock::MockRuntime;
+
+    #[test]
+    fn values_are_dampened() {
+        let cache = TcbCache::<MockRuntime>::new(&StreamConfig::default());
+        let remote = DestinationId::random();
+
+        assert!(cache.get(&remote).is_none());
+
+        cache.update(
+            &remote,
+            TcbState {
+                rtt: Duration::from_millis(1000),
+                rtt_dev: Duration::from_millis(200),
+                window_size: 8,
+            },
+        );
+        assert_eq!(
+            cache.get(&remote),
+            Some(TcbState {
+                rtt: Duration::from_millis(1000),
+                rtt_dev: Duration::from_millis(200),
+                window_size: 8,
+            })
+        );
+
+        // default dampening factors are 0.75
+        cache.clone().update(
+            &remote,
+            TcbState {
+                rtt: Duration::from_millis(2000),
+                rtt_dev: Duration::from_millis(600),
+                window_size: 16,
+            },
+        );
+        assert_eq!(
+            cache.get(&remote),
+            Some(TcbState {
+                rtt: Duration::from_millis(1250),
+                rtt_dev: Duration::from_millis(300),
+                window_size: 10,
+            })
+        );
+
+        assert!(cache.get(&DestinationId::random()).is_none());
+    }
+}
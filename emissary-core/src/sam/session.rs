@@ -18,11 +18,17 @@
 
 use crate::{
     crypto::{base32_decode, base32_encode, base64_encode, SigningPrivateKey, StaticPrivateKey},
-    destination::{DeliveryStyle, Destination, DestinationEvent, LeaseSetStatus},
+    destination::{
+        DeliveryStyle, Destination, DestinationEvent, LeaseSetEncryption, LeaseSetStatus,
+        MetaLeaseSetConfig,
+    },
     error::QueryError,
     events::EventHandle,
     i2cp::{I2cpPayload, I2cpPayloadBuilder},
-    primitives::{Destination as Dest, DestinationId, LeaseSet2, LeaseSet2Header},
+    primitives::{
+        BlindedAddress, Destination as Dest, DestinationId, LeaseSet2, LeaseSet2Header,
+        OfflineSignature,
+    },
     protocol::Protocol,
     runtime::{AddressBook, JoinSet, Runtime},
     sam::{
@@ -30,7 +36,7 @@ use crate::{
         pending::session::SamSessionContext,
         protocol::{
             datagram::DatagramManager,
-            streaming::{Direction, ListenerKind, StreamManager, StreamManagerEvent},
+            streaming::{Direction, ListenerKind, StreamConfig, StreamManager, StreamManagerEvent},
         },
         socket::SamSocket,
         SubSessionCommand,
@@ -91,6 +97,21 @@ pub enum SamSessionCommand<R: Runtime> {
         session_id: Arc<str>,
     },
 
+    /// Open virtual stream to a blinded destination over this connection.
+    ConnectBlinded {
+        /// SAMv3 socket associated with the outbound stream.
+        socket: SamSocket<R>,
+
+        /// Blinded address of the destination.
+        address: BlindedAddress,
+
+        /// Options.
+        options: HashMap<String, String>,
+
+        /// Session ID.
+        session_id: Arc<str>,
+    },
+
     /// Accept inbond virtual stream over this connection.
     Accept {
         /// SAMv3 socket associated with the inbound stream.
@@ -120,14 +141,20 @@ pub enum SamSessionCommand<R: Runtime> {
 
     /// Send repliable datagram to remote destination.
     SendDatagram {
-        /// Destination of the receiver.
-        destination: Dest,
+        /// ID of the receiver's destination.
+        destination_id: DestinationId,
 
         /// Datagram.
         datagram: Vec<u8>,
 
         /// Session ID.
         session_id: Arc<str>,
+
+        /// Source port.
+        src_port: u16,
+
+        /// Destination port.
+        dst_port: u16,
     },
 
     /// Dummy event, never constructed.
@@ -171,6 +198,21 @@ impl<R: Runtime> fmt::Debug for PendingSessionState<R> {
     }
 }
 
+/// Outbound datagram waiting for the lease set of the remote destination.
+pub struct PendingDatagram {
+    /// Protocol of the datagram.
+    protocol: Protocol,
+
+    /// Datagram.
+    datagram: Vec<u8>,
+
+    /// Source port.
+    src_port: u16,
+
+    /// Destination port.
+    dst_port: u16,
+}
+
 /// Pending sessions.
 ///
 /// Session is considered pending if it's lease set is being queried.
@@ -187,7 +229,7 @@ pub struct PendingSession<R: Runtime> {
     /// Pending datagrams.
     ///
     /// Only set if there are pending datagrams for the remote destination.
-    datagrams: Option<(Dest, Vec<(Protocol, Vec<u8>)>)>,
+    datagrams: Option<Vec<PendingDatagram>>,
 }
 
 impl<R: Runtime> PendingSession<R> {
@@ -341,6 +383,12 @@ pub struct SamSession<R: Runtime> {
     /// Session kind.
     session_kind: SamSessionKind,
 
+    /// Offline signature, if the destination uses one.
+    ///
+    /// If specified, `signing_key` is the transient signing key authorized by the offline
+    /// signature.
+    offline_signature: Option<OfflineSignature>,
+
     /// Signing key.
     signing_key: SigningPrivateKey,
 
@@ -382,11 +430,12 @@ impl<R: Runtime> SamSession<R> {
             tunnel_pool_handle,
         } = context;
 
-        let (session_destination, dest, privkey, encryption_key, signing_key) = {
+        let (session_destination, dest, privkey, encryption_key, signing_key, offline_signature) = {
             let DestinationContext {
                 destination,
                 private_key,
                 signing_key,
+                offline_signature,
             } = destination;
             let destination_id = destination.id();
 
@@ -396,11 +445,28 @@ impl<R: Runtime> SamSession<R> {
             // Private Key followed by the Signing Private Key, optionally followed by the Offline
             // Signature, which is 663 or more bytes in binary and 884 or more bytes in base 64,
             // depending on signature type. The binary format is specified in Private Key File."
+            //
+            // if the destination uses an offline signature, the signing private key is all zeros
+            // and it's followed by the offline signature and the transient signing private key
             let privkey = {
-                let mut out = BytesMut::with_capacity(destination.serialized_len() + 2 * 32);
+                let mut out = BytesMut::with_capacity(
+                    destination.serialized_len()
+                        + 2 * 32
+                        + offline_signature
+                            .as_ref()
+                            .map_or(0usize, |signature| signature.serialized_len() + 32),
+                );
                 out.put_slice(&destination.serialize());
                 out.put_slice((*private_key).as_ref());
-                out.put_slice((*signing_key).as_ref());
+
+                match &offline_signature {
+                    None => out.put_slice((*signing_key).as_ref()),
+                    Some(offline_signature) => {
+                        out.put_slice(&[0u8; 32]);
+                        out.put_slice(offline_signature.serialize());
+                        out.put_slice((*signing_key).as_ref());
+                    }
+                }
 
                 base64_encode(out)
             };
@@ -418,7 +484,7 @@ impl<R: Runtime> SamSession<R> {
                         destination: destination.clone(),
                         expires: Duration::from_secs(10 * 60).as_secs() as u32,
                         is_unpublished,
-                        offline_signature: None,
+                        offline_signature: offline_signature.clone(),
                         published: R::time_since_epoch().as_secs() as u32,
                     },
                     public_keys: vec![public_key],
@@ -427,14 +493,41 @@ impl<R: Runtime> SamSession<R> {
                 .serialize(&signing_key),
             );
 
+            // if the lease set is to be published as an encrypted lease set, the destination is
+            // reachable only through its blinded address
+            //
+            // blinding requires the long-term signing key so encrypted lease sets are not
+            // supported for destinations which use an offline signature
+            let encryption =
+                LeaseSetEncryption::from_options(&options, &signing_key).filter(|_| {
+                    if offline_signature.is_some() {
+                        tracing::warn!(
+                            target: LOG_TARGET,
+                            %session_id,
+                            "encrypted lease set not supported with offline signature",
+                        );
+                    }
+
+                    offline_signature.is_none()
+                });
+            let address = match &encryption {
+                None => base32_encode(destination_id.to_vec()),
+                Some(encryption) => BlindedAddress::new(
+                    &signing_key.public(),
+                    encryption.secret.is_some(),
+                    encryption.client_auth.is_some(),
+                )
+                .map_or_else(
+                    || base32_encode(destination_id.to_vec()),
+                    |address| address.to_base32(),
+                ),
+            };
+
             // publish the new destination to the event system
             if is_unpublished {
                 event_handle.client_destination_started(session_id.to_string());
             } else {
-                event_handle.server_destination_started(
-                    session_id.to_string(),
-                    base32_encode(destination_id.to_vec()),
-                );
+                event_handle.server_destination_started(session_id.to_string(), address.clone());
             }
 
             let mut session_destination = Destination::new(
@@ -448,6 +541,21 @@ impl<R: Runtime> SamSession<R> {
                 is_unpublished,
                 profile_storage,
             );
+
+            if let Some(encryption) = encryption {
+                session_destination = session_destination.with_encrypted_lease_set(encryption);
+            }
+
+            // if the destination publishes a meta lease set, clients connecting to it are
+            // directed to the member destinations listed in the meta lease set
+            if let Some(meta) = MetaLeaseSetConfig::from_options(&options, &signing_key) {
+                session_destination = session_destination.with_meta_lease_set(meta);
+            }
+
+            if let Some(client_key) = LeaseSetEncryption::client_key_from_options(&options) {
+                session_destination = session_destination.with_client_key(client_key);
+            }
+
             // // TODO: not needed anymore?
             session_destination.publish_lease_set(local_leaseset.clone());
 
@@ -455,6 +563,7 @@ impl<R: Runtime> SamSession<R> {
                 target: LOG_TARGET,
                 %session_id,
                 %destination_id,
+                %address,
                 "start active session",
             );
 
@@ -464,9 +573,22 @@ impl<R: Runtime> SamSession<R> {
                 privkey,
                 private_key,
                 signing_key,
+                offline_signature,
             )
         };
 
+        // stream manager enforces the connection limits specified in `i2p.streaming.*` options
+        let stream_manager = {
+            let stream_manager = StreamManager::new(dest.clone(), *signing_key.clone())
+                .with_config(StreamConfig::from_options(&options));
+
+            match &offline_signature {
+                None => stream_manager,
+                Some(offline_signature) => stream_manager
+                    .with_offline_signature(Bytes::copy_from_slice(offline_signature.serialize())),
+            }
+        };
+
         socket.send_message(
             format!("SESSION STATUS RESULT=OK DESTINATION={privkey}\n").as_bytes().to_vec(),
         );
@@ -484,6 +606,7 @@ impl<R: Runtime> SamSession<R> {
             encryption_key: *encryption_key,
             event_handle,
             lookup_futures: R::join_set(),
+            offline_signature: offline_signature.clone(),
             options,
             pending_host_lookups: HashMap::new(),
             pending_outbound: HashMap::new(),
@@ -503,7 +626,7 @@ impl<R: Runtime> SamSession<R> {
             },
             signing_key: *signing_key.clone(),
             socket: Some(socket),
-            stream_manager: StreamManager::new(dest, *signing_key),
+            stream_manager,
             sub_session_tx,
             waker: None,
         }
@@ -618,6 +741,8 @@ impl<R: Runtime> SamSession<R> {
                     "lease set found, create outbound stream",
                 );
 
+                let destination_id =
+                    self.destination.unblind(&destination_id).unwrap_or(destination_id);
                 self.create_outbound_stream(destination_id, socket, options);
             }
             status @ (LeaseSetStatus::NotFound | LeaseSetStatus::Pending) => {
@@ -638,6 +763,37 @@ impl<R: Runtime> SamSession<R> {
         }
     }
 
+    /// Handle `STREAM CONNECT` for a blinded destination.
+    ///
+    /// Lookup password for the encrypted lease set of the destination may be specified in
+    /// `i2cp.leaseSetSecret` of either the stream or the session options. The stream is
+    /// identified by the ID of the blinded address until the lease set has been found.
+    fn on_stream_connect_blinded(
+        &mut self,
+        socket: SamSocket<R>,
+        address: BlindedAddress,
+        options: HashMap<String, String>,
+        session_id: Arc<str>,
+    ) {
+        let secret = options
+            .get("i2cp.leaseSetSecret")
+            .or_else(|| self.options.get("i2cp.leaseSetSecret"))
+            .cloned();
+
+        if address.secret_required() && secret.is_none() {
+            tracing::warn!(
+                target: LOG_TARGET,
+                session_id = %self.session_id,
+                %address,
+                "lookup password required for blinded address",
+            );
+        }
+
+        let destination_id = address.id();
+        self.destination.register_blinding_info(address, secret, None);
+        self.on_stream_connect(socket, destination_id, options, session_id);
+    }
+
     /// Handle `STREAM ACCEPT` command.
     ///
     /// Register the socket as an active listener to [`StreamManager`].
@@ -716,10 +872,49 @@ impl<R: Runtime> SamSession<R> {
         }
     }
 
+    /// Send `datagram` to `destination_id` whose lease set is known.
+    fn send_datagram_message(&mut self, destination_id: &DestinationId, datagram: PendingDatagram) {
+        let PendingDatagram {
+            protocol,
+            datagram,
+            src_port,
+            dst_port,
+        } = datagram;
+        let datagram = self.datagram_manager.make_datagram(protocol, datagram);
+
+        if let Some(message) = I2cpPayloadBuilder::<R>::new(&datagram)
+            .with_protocol(protocol)
+            .with_source_port(src_port)
+            .with_destination_port(dst_port)
+            .build()
+        {
+            if let Err(error) = self.destination.send_message(
+                DeliveryStyle::Unspecified {
+                    destination_id: destination_id.clone(),
+                },
+                message,
+            ) {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    session_id = %self.session_id,
+                    %destination_id,
+                    ?error,
+                    "failed to send repliable datagram",
+                )
+            }
+        };
+    }
+
     /// Send datagram to destination.
     ///
     /// If the session wasn't configured to use streams, the datagram is dropped.
-    fn on_send_datagram(&mut self, destination: Dest, datagram: Vec<u8>, session_id: Arc<str>) {
+    fn on_send_datagram(
+        &mut self,
+        destination_id: DestinationId,
+        datagram: Vec<u8>,
+        session_id: Arc<str>,
+        (src_port, dst_port): (u16, u16),
+    ) {
         if !self.session_kind.supports_datagrams(&session_id) {
             tracing::warn!(
                 target: LOG_TARGET,
@@ -733,34 +928,19 @@ impl<R: Runtime> SamSession<R> {
         tracing::info!(
             target: LOG_TARGET,
             session_id = %self.session_id,
-            destination_id = %destination.id(),
+            %destination_id,
             style = ?self.session_kind,
             "send datagram",
         );
-        let destination_id = destination.id();
-        let protocol = self.session_kind.as_protocol(&session_id);
+        let datagram = PendingDatagram {
+            protocol: self.session_kind.as_protocol(&session_id),
+            datagram,
+            src_port,
+            dst_port,
+        };
 
         match self.destination.query_lease_set(&destination_id) {
-            LeaseSetStatus::Found => {
-                let datagram = self.datagram_manager.make_datagram(protocol, datagram);
-
-                if let Some(message) =
-                    I2cpPayloadBuilder::<R>::new(&datagram).with_protocol(protocol).build()
-                {
-                    if let Err(error) = self
-                        .destination
-                        .send_message(DeliveryStyle::Unspecified { destination_id }, message)
-                    {
-                        tracing::warn!(
-                            target: LOG_TARGET,
-                            session_id = %self.session_id,
-                            destination_id = %destination.id(),
-                            ?error,
-                            "failed to send repliable datagram",
-                        )
-                    }
-                };
-            }
+            LeaseSetStatus::Found => self.send_datagram_message(&destination_id, datagram),
             LeaseSetStatus::NotFound => {
                 tracing::trace!(
                     target: LOG_TARGET,
@@ -772,16 +952,16 @@ impl<R: Runtime> SamSession<R> {
                 match self.pending_outbound.get_mut(&destination_id) {
                     Some(PendingSession { datagrams, .. }) => match datagrams {
                         None => {
-                            *datagrams = Some((destination, vec![(protocol, datagram)]));
+                            *datagrams = Some(vec![datagram]);
                         }
-                        Some((_, datagrams)) => datagrams.push((protocol, datagram)),
+                        Some(datagrams) => datagrams.push(datagram),
                     },
                     None => {
                         self.pending_outbound.insert(
                             destination_id,
                             PendingSession {
                                 streams: Vec::new(),
-                                datagrams: Some((destination, vec![(protocol, datagram)])),
+                                datagrams: Some(vec![datagram]),
                             },
                         );
                     }
@@ -798,16 +978,16 @@ impl<R: Runtime> SamSession<R> {
                 match self.pending_outbound.get_mut(&destination_id) {
                     Some(PendingSession { datagrams, .. }) => match datagrams {
                         None => {
-                            *datagrams = Some((destination, vec![(protocol, datagram)]));
+                            *datagrams = Some(vec![datagram]);
                         }
-                        Some((_, datagrams)) => datagrams.push((protocol, datagram)),
+                        Some(datagrams) => datagrams.push(datagram),
                     },
                     None => {
                         self.pending_outbound.insert(
                             destination_id,
                             PendingSession {
                                 streams: Vec::new(),
-                                datagrams: Some((destination, vec![(protocol, datagram)])),
+                                datagrams: Some(vec![datagram]),
                             },
                         );
                     }
@@ -837,9 +1017,16 @@ impl<R: Runtime> SamSession<R> {
         if let Some(PendingSession { streams, datagrams }) =
             self.pending_outbound.remove(&destination_id)
         {
+            // streams to blinded destinations are pending under the ID of the blinded address
+            let remote_id = self.destination.unblind(&destination_id);
+
             streams.into_iter().for_each(|state| match state {
                 PendingSessionState::AwaitingLeaseSet { socket, options } => {
-                    self.create_outbound_stream(destination_id.clone(), socket, options);
+                    self.create_outbound_stream(
+                        remote_id.clone().unwrap_or(destination_id.clone()),
+                        socket,
+                        options,
+                    );
                 }
                 PendingSessionState::AwaitingSession { .. } => {
                     // new stream was opened but by the the time the initial `SYN` packet was sent,
@@ -853,28 +1040,9 @@ impl<R: Runtime> SamSession<R> {
                 }
             });
 
-            if let Some((destination, datagrams)) = datagrams {
-                datagrams.into_iter().for_each(|(protocol, datagram)| {
-                    let datagram = self.datagram_manager.make_datagram(protocol, datagram);
-
-                    if let Some(message) =
-                        I2cpPayloadBuilder::<R>::new(&datagram).with_protocol(protocol).build()
-                    {
-                        if let Err(error) = self.destination.send_message(
-                            DeliveryStyle::Unspecified {
-                                destination_id: destination_id.clone(),
-                            },
-                            message,
-                        ) {
-                            tracing::warn!(
-                                target: LOG_TARGET,
-                                session_id = %self.session_id,
-                                destination_id = %destination.id(),
-                                ?error,
-                                "failed to send repliable datagram",
-                            )
-                        }
-                    };
+            if let Some(datagrams) = datagrams {
+                datagrams.into_iter().for_each(|datagram| {
+                    self.send_datagram_message(&destination_id, datagram);
                 });
             }
         } else {
@@ -939,7 +1107,7 @@ impl<R: Runtime> SamSession<R> {
         if let Some(PendingSession { streams, datagrams }) =
             self.pending_outbound.remove(&destination_id)
         {
-            if let Some((_, datagrams)) = datagrams {
+            if let Some(datagrams) = datagrams {
                 tracing::debug!(
                     target: LOG_TARGET,
                     %destination_id,
@@ -1100,6 +1268,13 @@ impl<R: Runtime> SamSession<R> {
             return;
         }
 
+        // if the host name is a blinded address, start a query for its encrypted lease set
+        //
+        // once the query finishes, the naming reply is sent to client
+        if BlindedAddress::is_blinded(&name) {
+            return self.on_blinded_naming_lookup(name);
+        }
+
         // if the host name ends in `.b32.i2p`, validate the hostname and check if [`Destination`]
         // already holds the host's lease set and if not, start a query
         //
@@ -1232,6 +1407,128 @@ impl<R: Runtime> SamSession<R> {
         }
     }
 
+    /// Handle `NAMING LOOKUP` with `REVERSE=true`.
+    ///
+    /// The `.b32.i2p` address is resolved into a host name using the address book and the host
+    /// name is returned in `VALUE`.
+    fn on_reverse_lookup(&mut self, name: String) {
+        let start = if name.starts_with("http://") {
+            7usize
+        } else if name.starts_with("https://") {
+            8usize
+        } else {
+            0usize
+        };
+
+        let message = match (name.find(".b32.i2p"), &self.address_book) {
+            (None, _) => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    session_id = %self.session_id,
+                    ?name,
+                    "reverse lookup for a host that is not a .b32.i2p address",
+                );
+
+                Some(format!("NAMING REPLY RESULT=INVALID_KEY NAME={name}\n").as_bytes().to_vec())
+            }
+            (Some(_), None) => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    session_id = %self.session_id,
+                    ?name,
+                    "address book doesn't exist",
+                );
+
+                Some(format!("NAMING REPLY RESULT=KEY_NOT_FOUND NAME={name}\n").as_bytes().to_vec())
+            }
+            (Some(end), Some(address_book)) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    ?name,
+                    "reverse lookup from address book",
+                );
+
+                let future = address_book.resolve_name(name[start..end].to_string());
+                self.lookup_futures.push(async move { (name, future.await) });
+
+                None
+            }
+        };
+
+        if let (Some(socket), Some(message)) = (&mut self.socket, message) {
+            socket.send_message(message);
+        }
+    }
+
+    /// Handle `NAMING LOOKUP` for a blinded address.
+    ///
+    /// If the lease set is not found, the naming reply is deferred until the query is finished.
+    fn on_blinded_naming_lookup(&mut self, name: String) {
+        tracing::debug!(
+            target: LOG_TARGET,
+            session_id = %self.session_id,
+            "naming lookup for blinded .b32.i2p address",
+        );
+
+        let start = if name.starts_with("http://") {
+            7usize
+        } else if name.starts_with("https://") {
+            8usize
+        } else {
+            0usize
+        };
+
+        let message = match BlindedAddress::parse(&name[start..]) {
+            None => {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    session_id = %self.session_id,
+                    ?name,
+                    "invalid blinded .b32.i2p address",
+                );
+
+                Some(format!("NAMING REPLY RESULT=INVALID_KEY NAME={name}\n").as_bytes().to_vec())
+            }
+            Some(address) => {
+                let destination_id = address.id();
+
+                match self.destination.query_blinded_lease_set(&address) {
+                    LeaseSetStatus::Found => Some(
+                        format!(
+                            "NAMING REPLY RESULT=OK NAME={name} VALUE={}\n",
+                            base64_encode(
+                                self.destination
+                                    .lease_set(&destination_id)
+                                    .header
+                                    .destination
+                                    .serialized()
+                            )
+                        )
+                        .as_bytes()
+                        .to_vec(),
+                    ),
+                    status => {
+                        tracing::trace!(
+                            target: LOG_TARGET,
+                            session_id = %self.session_id,
+                            %destination_id,
+                            ?name,
+                            ?status,
+                            "encrypted lease set not found for host, query started",
+                        );
+                        self.pending_host_lookups.insert(destination_id, name);
+
+                        None
+                    }
+                }
+            }
+        };
+
+        if let (Some(socket), Some(message)) = (&mut self.socket, message) {
+            socket.send_message(message);
+        }
+    }
+
     /// Attempt to create new sub-session.
     ///
     /// The sub-session is rejected if [`SamSessionKind`] is not `Primary`, if there already exists
@@ -1342,6 +1639,7 @@ impl<R: Runtime> Future for SamSession<R> {
 
             match command {
                 SamCommand::NamingLookup { name } => self.on_naming_lookup(name),
+                SamCommand::ReverseLookup { name } => self.on_reverse_lookup(name),
                 SamCommand::CreateSubSession {
                     session_id,
                     session_kind,
@@ -1376,6 +1674,12 @@ impl<R: Runtime> Future for SamSession<R> {
                     options,
                     session_id,
                 })) => self.on_stream_connect(socket, destination_id, options, session_id),
+                Poll::Ready(Some(SamSessionCommand::ConnectBlinded {
+                    socket,
+                    address,
+                    options,
+                    session_id,
+                })) => self.on_stream_connect_blinded(socket, address, options, session_id),
                 Poll::Ready(Some(SamSessionCommand::Accept {
                     socket,
                     options,
@@ -1388,10 +1692,17 @@ impl<R: Runtime> Future for SamSession<R> {
                     session_id,
                 })) => self.on_stream_forward(socket, port, options, session_id),
                 Poll::Ready(Some(SamSessionCommand::SendDatagram {
-                    destination,
+                    destination_id,
                     datagram,
                     session_id,
-                })) => self.on_send_datagram(destination, datagram, session_id),
+                    src_port,
+                    dst_port,
+                })) => self.on_send_datagram(
+                    destination_id,
+                    datagram,
+                    session_id,
+                    (src_port, dst_port),
+                ),
                 Poll::Ready(Some(SamSessionCommand::Dummy)) => unreachable!(),
             }
         }
@@ -1500,7 +1811,7 @@ impl<R: Runtime> Future for SamSession<R> {
                                     .map(|value| value.parse::<bool>().unwrap_or(false))
                                     .unwrap_or(false),
                                 expires: Duration::from_secs(10 * 60).as_secs() as u32,
-                                offline_signature: None,
+                                offline_signature: self.offline_signature.clone(),
                                 published: R::time_since_epoch().as_secs() as u32,
                             },
                             public_keys: vec![self.encryption_key.public()],
@@ -1634,6 +1945,7 @@ mod tests {
                     destination,
                     private_key: Box::new(encryption_key),
                     signing_key: Box::new(signing_key),
+                    offline_signature: None,
                 },
                 event_handle,
                 inbound: Default::default(),
@@ -18,9 +18,12 @@
 
 use crate::{
     error::{ChannelError, QueryError},
-    events::EventHandle,
+    events::{EventHandle, FirewallStatus},
     netdb::NetDbHandle,
-    primitives::{Date, RouterAddress, RouterId, RouterInfo, Str, TransportKind},
+    primitives::{
+        Bandwidth, Capabilities, Date, Introducer, RouterAddress, RouterId, RouterInfo, Str,
+        TransportKind,
+    },
     router::context::RouterContext,
     runtime::{Counter, Gauge, JoinSet, MetricType, MetricsHandle, Runtime},
     subsystem::{
@@ -35,11 +38,11 @@ use futures::{FutureExt, Stream, StreamExt};
 use hashbrown::{HashMap, HashSet};
 use thingbuf::mpsc::{channel, errors::TrySendError, Receiver, Sender};
 
-use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
+use alloc::{boxed::Box, collections::VecDeque, string::String, vec::Vec};
 use core::{
     future::Future,
     marker::PhantomData,
-    net::Ipv4Addr,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
     pin::Pin,
     task::{Context, Poll},
     time::Duration,
@@ -233,6 +236,18 @@ pub enum TransportEvent {
         /// ID of the remote router.
         router_id: RouterId,
     },
+
+    /// Firewall status of the router has changed.
+    FirewallStatusChanged {
+        /// New firewall status.
+        status: FirewallStatus,
+    },
+
+    /// Introducers of the router have changed.
+    IntroducersChanged {
+        /// New introducers.
+        introducers: Vec<Introducer>,
+    },
 }
 
 /// Transport interface.
@@ -413,20 +428,23 @@ pub struct TransportManagerBuilder<R: Runtime> {
     /// TX channel passed onto other subsystems.
     cmd_tx: Sender<ProtocolCommand>,
 
+    /// Bandwidth class published if bandwidth is not limited.
+    default_bandwidth: Option<Bandwidth>,
+
     /// Local router info.
     local_router_info: RouterInfo,
 
     /// Handle to [`NetDb`].
     netdb_handle: Option<NetDbHandle>,
 
-    /// NTCP2 config.
-    ntcp2_config: Option<Ntcp2Config>,
+    /// NTCP2 config and index of the transport in `transports`.
+    ntcp2_config: Option<(usize, Ntcp2Config)>,
 
     /// Router context.
     router_ctx: RouterContext<R>,
 
-    /// SSU2 config.
-    ssu2_config: Option<Ssu2Config>,
+    /// SSU2 config and index of the transport in `transports`.
+    ssu2_config: Option<(usize, Ssu2Config)>,
 
     /// Subsystem handle passed onto enabled transports.
     subsystem_handle: SubsystemHandle,
@@ -451,6 +469,7 @@ impl<R: Runtime> TransportManagerBuilder<R> {
             allow_local,
             cmd_rx,
             cmd_tx,
+            default_bandwidth: None,
             local_router_info,
             netdb_handle: None,
             ntcp2_config: None,
@@ -485,7 +504,7 @@ impl<R: Runtime> TransportManagerBuilder<R> {
 
     /// Register NTCP2 as an active transport.
     pub fn register_ntcp2(&mut self, context: Ntcp2Context<R>) {
-        self.ntcp2_config = Some(context.config());
+        self.ntcp2_config = Some((self.transports.len(), context.config()));
         self.transports.push(Box::new(Ntcp2Transport::new(
             context,
             self.allow_local,
@@ -496,7 +515,7 @@ impl<R: Runtime> TransportManagerBuilder<R> {
 
     /// Register SSU2 as an active transport.
     pub fn register_ssu2(&mut self, context: Ssu2Context<R>) {
-        self.ssu2_config = Some(context.config());
+        self.ssu2_config = Some((self.transports.len(), context.config()));
         self.transports.push(Box::new(Ssu2Transport::new(
             context,
             self.allow_local,
@@ -516,12 +535,20 @@ impl<R: Runtime> TransportManagerBuilder<R> {
         self
     }
 
+    /// Specify the bandwidth class that is published if bandwidth is not limited.
+    pub fn with_default_bandwidth(&mut self, default_bandwidth: Option<Bandwidth>) -> &mut Self {
+        self.default_bandwidth = default_bandwidth;
+        self
+    }
+
     /// Build into [`TransportManager`].
     pub fn build(self) -> TransportManager<R> {
         TransportManager {
             cmd_rx: self.cmd_rx,
+            default_bandwidth: self.default_bandwidth,
             event_handle: self.router_ctx.event_handle().clone(),
-            external_address: None,
+            external_ipv4_address: None,
+            external_ipv6_address: None,
             local_router_info: self.local_router_info,
             netdb_handle: self.netdb_handle.expect("to exist"),
             ntcp2_config: self.ntcp2_config,
@@ -552,11 +579,17 @@ pub struct TransportManager<R: Runtime> {
     /// RX channel for receiving commands from other subsystems.
     cmd_rx: Receiver<ProtocolCommand>,
 
+    /// Bandwidth class published if bandwidth is not limited.
+    default_bandwidth: Option<Bandwidth>,
+
     /// Event handle.
     event_handle: EventHandle<R>,
 
-    /// External address, if any.
-    external_address: Option<Ipv4Addr>,
+    /// External IPv4 address, if any.
+    external_ipv4_address: Option<Ipv4Addr>,
+
+    /// External IPv6 address, if any.
+    external_ipv6_address: Option<Ipv6Addr>,
 
     /// Local router info.
     local_router_info: RouterInfo,
@@ -564,8 +597,8 @@ pub struct TransportManager<R: Runtime> {
     /// Handle to [`NetDb`].
     netdb_handle: NetDbHandle,
 
-    /// NTCP2 config.
-    ntcp2_config: Option<Ntcp2Config>,
+    /// NTCP2 config and index of the transport in `transports`.
+    ntcp2_config: Option<(usize, Ntcp2Config)>,
 
     /// Pending outbound connections.
     pending_connections: HashSet<RouterId>,
@@ -591,8 +624,8 @@ pub struct TransportManager<R: Runtime> {
     /// Is the router shutting down.
     shutting_down: bool,
 
-    /// SSU2 config.
-    ssu2_config: Option<Ssu2Config>,
+    /// SSU2 config and index of the transport in `transports`.
+    ssu2_config: Option<(usize, Ssu2Config)>,
 
     /// Subsystem handle.
     subsystem_handle: SubsystemHandle,
@@ -620,59 +653,112 @@ impl<R: Runtime> TransportManager<R> {
         self.shutting_down = true;
     }
 
+    /// Update the bandwidth class of the local router info to match the current bandwidth limits.
+    ///
+    /// If the bandwidth class changed, the local router info is republished immediately.
+    pub fn update_bandwidth_class(&mut self) {
+        // router info with `G` doesn't have a bandwidth class
+        if self.shutting_down || self.transit_tunnels_disabled {
+            return;
+        }
+
+        let bandwidth =
+            self.router_ctx.bandwidth_limiter().bandwidth_class().or(self.default_bandwidth);
+        let old_caps =
+            self.local_router_info.options.get(&Str::from("caps")).map_or("", |caps| caps);
+        let caps = match bandwidth {
+            Some(bandwidth) => bandwidth.replace(old_caps),
+            None => Bandwidth::remove(old_caps),
+        };
+
+        if caps == old_caps {
+            return;
+        }
+
+        tracing::info!(
+            target: LOG_TARGET,
+            %old_caps,
+            %caps,
+            "bandwidth class changed, republishing router info",
+        );
+
+        let caps = Str::from(caps);
+        self.local_router_info.capabilities = Capabilities::parse(&caps).expect("to succeed");
+        self.local_router_info.options.insert(Str::from("caps"), caps);
+        self.router_info_republish_timer = R::timer(Duration::ZERO);
+    }
+
     /// Add external address for the router.
-    pub fn add_external_address(&mut self, address: Ipv4Addr) {
+    ///
+    /// The address is published for each enabled transport which doesn't have a host configured
+    /// for the address family of `address`.
+    pub fn add_external_address(&mut self, address: IpAddr) {
         tracing::info!(
             target: LOG_TARGET,
             ?address,
             "external address discovered",
         );
 
-        match (self.external_address, address) {
-            (None, address) => {
-                tracing::info!(
-                    target: LOG_TARGET,
-                    ?address,
-                    "external address discovered, publishing new router info",
-                );
-
-                self.external_address = Some(address);
-            }
-            (Some(old_address), new_address) if old_address != new_address => {
-                tracing::info!(
-                    target: LOG_TARGET,
-                    ?old_address,
-                    ?new_address,
-                    "new external address discovered, publishing new router info",
-                );
+        let old_address = match address {
+            IpAddr::V4(address) => self.external_ipv4_address.replace(address).map(IpAddr::V4),
+            IpAddr::V6(address) => self.external_ipv6_address.replace(address).map(IpAddr::V6),
+        };
 
-                self.external_address = Some(address);
-            }
+        match old_address {
+            None => tracing::info!(
+                target: LOG_TARGET,
+                ?address,
+                "external address discovered, publishing new router info",
+            ),
+            Some(old_address) if old_address != address => tracing::info!(
+                target: LOG_TARGET,
+                ?old_address,
+                new_address = ?address,
+                "new external address discovered, publishing new router info",
+            ),
             _ => return,
-        };
+        }
 
         match &self.ntcp2_config {
-            Some(Ntcp2Config {
-                port,
-                host,
-                publish: true,
-                key,
-                iv,
-            }) => match (host, address) {
-                (None, address) => {
-                    self.local_router_info.addresses.insert(
-                        TransportKind::Ntcp2,
-                        RouterAddress::new_published_ntcp2(*key, *iv, *port, address),
-                    );
+            Some((
+                _,
+                Ntcp2Config {
+                    port,
+                    ipv4_host,
+                    ipv6_host,
+                    ipv4,
+                    ipv6,
+                    publish: true,
+                    key,
+                    iv,
+                },
+            )) => {
+                let (enabled, host, kind) = match address {
+                    IpAddr::V4(_) => (*ipv4, ipv4_host.map(IpAddr::V4), TransportKind::Ntcp2V4),
+                    IpAddr::V6(_) => (*ipv6, ipv6_host.map(IpAddr::V6), TransportKind::Ntcp2V6),
+                };
+
+                match (enabled, host) {
+                    (false, _) => tracing::trace!(
+                        target: LOG_TARGET,
+                        ?address,
+                        "address family not enabled for ntcp2, router address not updated",
+                    ),
+                    (true, None) => {
+                        self.local_router_info.addresses.insert(
+                            kind,
+                            RouterAddress::new_published_ntcp2(*key, *iv, *port, address),
+                        );
+                    }
+                    (true, Some(published)) if published == address => {}
+                    (true, Some(published)) => tracing::warn!(
+                        target: LOG_TARGET,
+                        ?published,
+                        ?address,
+                        "external address doesn't match published address, router address not updated",
+                    ),
                 }
-                (Some(published), address) if published == &address => {}
-                (Some(published), address) => tracing::warn!(
-                    target: LOG_TARGET,
-                    ?published,
-                    ?address,
-                    "external address doesn't match published address, router address not updated",
-                ),
-            },
+            }
             _ => tracing::trace!(
                 target: LOG_TARGET,
                 "ntcp2 not active or unpublished, router address not updated",
@@ -680,27 +766,50 @@ impl<R: Runtime> TransportManager<R> {
         }
 
         match &self.ssu2_config {
-            Some(Ssu2Config {
-                port,
-                host,
-                publish: true,
-                static_key,
-                intro_key,
-            }) => match (host, address) {
-                (None, address) => {
-                    self.local_router_info.addresses.insert(
-                        TransportKind::Ssu2,
-                        RouterAddress::new_published_ssu2(*static_key, *intro_key, *port, address),
-                    );
+            Some((
+                _,
+                Ssu2Config {
+                    port,
+                    ipv4_host,
+                    ipv6_host,
+                    ipv4,
+                    ipv6,
+                    publish: true,
+                    static_key,
+                    intro_key,
+                },
+            )) => {
+                let (enabled, host, kind) = match address {
+                    IpAddr::V4(_) => (*ipv4, ipv4_host.map(IpAddr::V4), TransportKind::Ssu2V4),
+                    IpAddr::V6(_) => (*ipv6, ipv6_host.map(IpAddr::V6), TransportKind::Ssu2V6),
+                };
+
+                match (enabled, host) {
+                    (false, _) => tracing::trace!(
+                        target: LOG_TARGET,
+                        ?address,
+                        "address family not enabled for ssu2, router address not updated",
+                    ),
+                    (true, None) => {
+                        self.local_router_info.addresses.insert(
+                            kind,
+                            RouterAddress::new_published_ssu2(
+                                *static_key,
+                                *intro_key,
+                                *port,
+                                address,
+                            ),
+                        );
+                    }
+                    (true, Some(published)) if published == address => {}
+                    (true, Some(published)) => tracing::warn!(
+                        target: LOG_TARGET,
+                        ?published,
+                        ?address,
+                        "external address doesn't match published ssu2 address, router address not updated",
+                    ),
                 }
-                (Some(published), address) if published == &address => {}
-                (Some(published), address) => tracing::warn!(
-                    target: LOG_TARGET,
-                    ?published,
-                    ?address,
-                    "external address doesn't match published ssu2 address, router address not updated",
-                ),
-            },
+            }
             _ => tracing::trace!(
                 target: LOG_TARGET,
                 "ssu2 not active or unpublished, router address not updated",
@@ -708,6 +817,87 @@ impl<R: Runtime> TransportManager<R> {
         }
     }
 
+    /// Handle firewall status change reported by a transport.
+    ///
+    /// The reachability capability of the local router info is updated to match `status` and
+    /// the new capabilities are published when the router info is republished.
+    fn on_firewall_status_changed(&mut self, status: FirewallStatus) {
+        tracing::info!(
+            target: LOG_TARGET,
+            ?status,
+            "firewall status changed",
+        );
+
+        self.event_handle.firewall_status(status);
+
+        let reachability = match status {
+            FirewallStatus::Unknown => return,
+            FirewallStatus::Ok => 'R',
+            FirewallStatus::Firewalled | FirewallStatus::SymmetricNat => 'U',
+        };
+        let caps = self
+            .local_router_info
+            .options
+            .get(&Str::from("caps"))
+            .map_or("", |caps| caps)
+            .chars()
+            .filter(|cap| *cap != 'R' && *cap != 'U')
+            .chain(core::iter::once(reachability))
+            .collect::<String>();
+        let caps = Str::from(caps);
+
+        self.local_router_info.capabilities = Capabilities::parse(&caps).expect("to succeed");
+        self.local_router_info.options.insert(Str::from("caps"), caps);
+    }
+
+    /// Handle introducer change reported by SSU2.
+    ///
+    /// The introducers are set for all SSU2 addresses of the local router info and they are
+    /// published when the router info is republished.
+    fn on_introducers_changed(&mut self, introducers: Vec<Introducer>) {
+        tracing::debug!(
+            target: LOG_TARGET,
+            num_introducers = ?introducers.len(),
+            "introducers changed",
+        );
+
+        for kind in [TransportKind::Ssu2V4, TransportKind::Ssu2V6] {
+            if let Some(address) = self.local_router_info.addresses.get_mut(&kind) {
+                address.set_introducers(&introducers);
+            }
+        }
+    }
+
+    /// Select transport which is used to dial `router_info`.
+    ///
+    /// NTCP2 is preferred over SSU2 if the router is reachable over both transports and IPv4 is
+    /// preferred over IPv6 if the router is reachable over both address families.
+    ///
+    /// Returns index of the transport in `transports` or `None` if the router isn't reachable over
+    /// any of the enabled transports.
+    fn select_transport(&self, router_info: &RouterInfo) -> Option<usize> {
+        // transports were registered without configuration, dial using the first transport
+        if self.ntcp2_config.is_none() && self.ssu2_config.is_none() {
+            return (!self.transports.is_empty()).then_some(0usize);
+        }
+
+        if let Some((index, Ntcp2Config { ipv4, ipv6, .. })) = &self.ntcp2_config {
+            if router_info.ntcp2_address(*ipv4, *ipv6).is_some() {
+                return Some(*index);
+            }
+        }
+
+        if let Some((index, Ssu2Config { ipv4, ipv6, .. })) = &self.ssu2_config {
+            if router_info.ssu2_address(*ipv4, *ipv6).is_some()
+                || router_info.ssu2_introduced_address(*ipv4, *ipv6).is_some()
+            {
+                return Some(*index);
+            }
+        }
+
+        None
+    }
+
     /// Attempt to dial `router_id`.
     ///
     /// If `router_id` is not found in local storage, send [`RouterInfo`] query for `router_id` to
@@ -720,6 +910,19 @@ impl<R: Runtime> TransportManager<R> {
         }
 
         match self.router_ctx.profile_storage().get(&router_id) {
+            Some(router_info) if self.router_ctx.blocklist().is_blocked(&router_info) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    %router_id,
+                    "cannot dial router, router is blocked",
+                );
+
+                // report connection failure to subsystems
+                let mut handle = self.subsystem_handle.clone();
+                R::spawn(async move {
+                    handle.report_connection_failure(router_id).await;
+                });
+            }
             Some(router_info) => {
                 // even though `TransportService` prevents dialing the same router from the same
                 // subsystem twice, the notion of a "pending router", i.e., it being dialed, is not
@@ -739,13 +942,12 @@ impl<R: Runtime> TransportManager<R> {
                     return;
                 }
 
-                // TODO: ssu2 support
-                if self.ntcp2_config.is_some() && !router_info.is_reachable_ntcp2() {
+                let Some(index) = self.select_transport(&router_info) else {
                     tracing::debug!(
                         target: LOG_TARGET,
                         %router_id,
                         caps = %router_info.capabilities,
-                        "cannot dial router, ntcp2 address is not reachable",
+                        "cannot dial router, no reachable address for enabled transports",
                     );
                     self.pending_connections.remove(&router_id);
 
@@ -756,7 +958,7 @@ impl<R: Runtime> TransportManager<R> {
                     });
 
                     return;
-                }
+                };
 
                 tracing::trace!(
                     target: LOG_TARGET,
@@ -764,8 +966,7 @@ impl<R: Runtime> TransportManager<R> {
                     "start dialing router",
                 );
 
-                // TODO: compare transport costs
-                self.transports[0].connect(router_info);
+                self.transports[index].connect(router_info);
             }
             None => {
                 tracing::debug!(
@@ -838,6 +1039,16 @@ impl<R: Runtime> Future for TransportManager<R> {
                         direction,
                         router_id,
                     })) => match direction {
+                        _ if self.router_ctx.blocklist().is_router_blocked(&router_id) => {
+                            tracing::debug!(
+                                target: LOG_TARGET,
+                                %router_id,
+                                ?direction,
+                                "router is blocked, rejecting connection",
+                            );
+                            self.transports[index].reject(&router_id);
+                            self.pending_connections.remove(&router_id);
+                        }
                         Direction::Inbound if self.pending_connections.contains(&router_id) => {
                             tracing::debug!(
                                 target: LOG_TARGET,
@@ -917,6 +1128,10 @@ impl<R: Runtime> Future for TransportManager<R> {
                         self.router_ctx.profile_storage().dial_failed(&router_id);
                         self.pending_connections.remove(&router_id);
                     }
+                    Poll::Ready(Some(TransportEvent::FirewallStatusChanged { status })) =>
+                        self.on_firewall_status_changed(status),
+                    Poll::Ready(Some(TransportEvent::IntroducersChanged { introducers })) =>
+                        self.on_introducers_changed(introducers),
                 }
             }
 
@@ -988,7 +1203,11 @@ impl<R: Runtime> Future for TransportManager<R> {
                     "publishing router info with `G`",
                 );
 
-                self.local_router_info.options.insert(Str::from("caps"), Str::from("GR"));
+                let caps = match self.local_router_info.capabilities.is_reachable() {
+                    true => Str::from("GR"),
+                    false => Str::from("GU"),
+                };
+                self.local_router_info.options.insert(Str::from("caps"), caps);
             }
 
             let serialized =
@@ -1066,7 +1285,10 @@ mod tests {
     async fn external_address_discovered_ntcp2() {
         let context = Ntcp2Transport::<MockRuntime>::initialize(Some(Ntcp2Config {
             port: 0,
-            host: Some("192.168.0.1".parse().unwrap()),
+            ipv4_host: Some("192.168.0.1".parse().unwrap()),
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             key: [0u8; 32],
             iv: [0u8; 16],
@@ -1085,7 +1307,7 @@ mod tests {
             manager
                 .local_router_info
                 .addresses
-                .get(&TransportKind::Ntcp2)
+                .get(&TransportKind::Ntcp2V4)
                 .unwrap()
                 .options
                 .get(&Str::from("host")),
@@ -1094,7 +1316,7 @@ mod tests {
         assert!(manager
             .local_router_info
             .addresses
-            .get(&TransportKind::Ntcp2)
+            .get(&TransportKind::Ntcp2V4)
             .unwrap()
             .options
             .get(&Str::from("s"))
@@ -1107,7 +1329,7 @@ mod tests {
             manager
                 .local_router_info
                 .addresses
-                .get(&TransportKind::Ntcp2)
+                .get(&TransportKind::Ntcp2V4)
                 .unwrap()
                 .options
                 .get(&Str::from("host")),
@@ -1116,18 +1338,95 @@ mod tests {
         assert!(manager
             .local_router_info
             .addresses
-            .get(&TransportKind::Ntcp2)
+            .get(&TransportKind::Ntcp2V4)
             .unwrap()
             .options
             .get(&Str::from("s"))
             .is_some());
     }
 
+    #[tokio::test]
+    async fn external_ipv6_address_discovered() {
+        let context = Ntcp2Transport::<MockRuntime>::initialize(Some(Ntcp2Config {
+            port: 0,
+            ipv4_host: None,
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: true,
+            publish: true,
+            key: [0u8; 32],
+            iv: [0u8; 16],
+        }))
+        .await
+        .unwrap()
+        .0
+        .unwrap();
+        let ssu2_context = Ssu2Transport::<MockRuntime>::initialize(Some(Ssu2Config {
+            port: 0,
+            ipv4_host: None,
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
+            publish: true,
+            static_key: [0u8; 32],
+            intro_key: [0u8; 32],
+        }))
+        .await
+        .unwrap()
+        .0
+        .unwrap();
+        let mut builder =
+            make_transport_manager(Some(context.config()), Some(ssu2_context.config()));
+        builder.register_ntcp2(context);
+        builder.register_ssu2(ssu2_context);
+        let mut manager = builder.build();
+
+        // no published addresses
+        assert!(manager
+            .local_router_info
+            .addresses
+            .values()
+            .all(|address| { address.options.get(&Str::from("host")).is_none() }));
+
+        manager.add_external_address("192.168.0.1".parse().unwrap());
+        manager.add_external_address("2001:db8::1".parse().unwrap());
+
+        // ipv4 address published for both transports
+        for kind in [TransportKind::Ntcp2V4, TransportKind::Ssu2V4] {
+            assert_eq!(
+                manager
+                    .local_router_info
+                    .addresses
+                    .get(&kind)
+                    .unwrap()
+                    .options
+                    .get(&Str::from("host")),
+                Some(&Str::from("192.168.0.1"))
+            );
+        }
+
+        // ipv6 address published only for ntcp2 since ssu2 doesn't have ipv6 enabled
+        assert_eq!(
+            manager
+                .local_router_info
+                .addresses
+                .get(&TransportKind::Ntcp2V6)
+                .unwrap()
+                .options
+                .get(&Str::from("host")),
+            Some(&Str::from("2001:db8::1"))
+        );
+        assert!(manager.local_router_info.addresses.get(&TransportKind::Ssu2V6).is_none());
+    }
+
     #[tokio::test]
     async fn external_address_discovered_ntcp2_unpublished() {
         let context = Ntcp2Transport::<MockRuntime>::initialize(Some(Ntcp2Config {
             port: 0,
-            host: None,
+            ipv4_host: None,
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: false,
             key: [0u8; 32],
             iv: [0u8; 16],
@@ -1146,7 +1445,7 @@ mod tests {
         assert!(manager
             .local_router_info
             .addresses
-            .get(&TransportKind::Ntcp2)
+            .get(&TransportKind::Ntcp2V4)
             .unwrap()
             .options
             .get(&Str::from("host"))
@@ -1154,7 +1453,7 @@ mod tests {
         assert!(manager
             .local_router_info
             .addresses
-            .get(&TransportKind::Ntcp2)
+            .get(&TransportKind::Ntcp2V4)
             .unwrap()
             .options
             .get(&Str::from("i"))
@@ -1166,7 +1465,7 @@ mod tests {
         assert!(manager
             .local_router_info
             .addresses
-            .get(&TransportKind::Ntcp2)
+            .get(&TransportKind::Ntcp2V4)
             .unwrap()
             .options
             .get(&Str::from("host"))
@@ -1174,7 +1473,7 @@ mod tests {
         assert!(manager
             .local_router_info
             .addresses
-            .get(&TransportKind::Ntcp2)
+            .get(&TransportKind::Ntcp2V4)
             .unwrap()
             .options
             .get(&Str::from("i"))
@@ -1185,7 +1484,10 @@ mod tests {
     async fn external_address_discovered_ssu2() {
         let ssu2 = Ssu2Config {
             port: 0,
-            host: Some("192.168.0.1".parse().unwrap()),
+            ipv4_host: Some("192.168.0.1".parse().unwrap()),
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             static_key: [0u8; 32],
             intro_key: [1u8; 32],
@@ -1202,7 +1504,7 @@ mod tests {
             manager
                 .local_router_info
                 .addresses
-                .get(&TransportKind::Ssu2)
+                .get(&TransportKind::Ssu2V4)
                 .unwrap()
                 .options
                 .get(&Str::from("host")),
@@ -1216,7 +1518,7 @@ mod tests {
             manager
                 .local_router_info
                 .addresses
-                .get(&TransportKind::Ssu2)
+                .get(&TransportKind::Ssu2V4)
                 .unwrap()
                 .options
                 .get(&Str::from("host")),
@@ -1228,7 +1530,10 @@ mod tests {
     async fn external_address_discovered_ssu2_unpublished() {
         let context = Ssu2Transport::<MockRuntime>::initialize(Some(Ssu2Config {
             port: 0,
-            host: None,
+            ipv4_host: None,
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: false,
             static_key: [0u8; 32],
             intro_key: [1u8; 32],
@@ -1247,7 +1552,7 @@ mod tests {
         assert!(manager
             .local_router_info
             .addresses
-            .get(&TransportKind::Ssu2)
+            .get(&TransportKind::Ssu2V4)
             .unwrap()
             .options
             .get(&Str::from("host"))
@@ -1259,7 +1564,7 @@ mod tests {
         assert!(manager
             .local_router_info
             .addresses
-            .get(&TransportKind::Ssu2)
+            .get(&TransportKind::Ssu2V4)
             .unwrap()
             .options
             .get(&Str::from("host"))
@@ -1270,7 +1575,10 @@ mod tests {
     async fn new_external_address_discovered() {
         let ssu2_context = Ssu2Transport::<MockRuntime>::initialize(Some(Ssu2Config {
             port: 0,
-            host: None,
+            ipv4_host: None,
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             static_key: [0u8; 32],
             intro_key: [1u8; 32],
@@ -1281,7 +1589,10 @@ mod tests {
         .unwrap();
         let ntcp2_context = Ntcp2Transport::<MockRuntime>::initialize(Some(Ntcp2Config {
             port: 0,
-            host: None,
+            ipv4_host: None,
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             key: [0u8; 32],
             iv: [0u8; 16],
@@ -1303,7 +1614,7 @@ mod tests {
         assert!(manager
             .local_router_info
             .addresses
-            .get(&TransportKind::Ssu2)
+            .get(&TransportKind::Ssu2V4)
             .unwrap()
             .options
             .get(&Str::from("host"))
@@ -1311,7 +1622,7 @@ mod tests {
         assert!(manager
             .local_router_info
             .addresses
-            .get(&TransportKind::Ntcp2)
+            .get(&TransportKind::Ntcp2V4)
             .unwrap()
             .options
             .get(&Str::from("host"))
@@ -1319,7 +1630,7 @@ mod tests {
         assert!(manager
             .local_router_info
             .addresses
-            .get(&TransportKind::Ntcp2)
+            .get(&TransportKind::Ntcp2V4)
             .unwrap()
             .options
             .get(&Str::from("i"))
@@ -1332,7 +1643,7 @@ mod tests {
             manager
                 .local_router_info
                 .addresses
-                .get(&TransportKind::Ssu2)
+                .get(&TransportKind::Ssu2V4)
                 .unwrap()
                 .options
                 .get(&Str::from("host")),
@@ -1342,7 +1653,7 @@ mod tests {
             manager
                 .local_router_info
                 .addresses
-                .get(&TransportKind::Ntcp2)
+                .get(&TransportKind::Ntcp2V4)
                 .unwrap()
                 .options
                 .get(&Str::from("host")),
@@ -1351,7 +1662,7 @@ mod tests {
         assert!(manager
             .local_router_info
             .addresses
-            .get(&TransportKind::Ntcp2)
+            .get(&TransportKind::Ntcp2V4)
             .unwrap()
             .options
             .get(&Str::from("s"))
@@ -1362,7 +1673,10 @@ mod tests {
     async fn discovered_address_doesnt_match_published_address_ntcp2() {
         let context = Ntcp2Transport::<MockRuntime>::initialize(Some(Ntcp2Config {
             port: 0,
-            host: Some("192.168.0.1".parse().unwrap()),
+            ipv4_host: Some("192.168.0.1".parse().unwrap()),
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             key: [0u8; 32],
             iv: [0u8; 16],
@@ -1381,7 +1695,7 @@ mod tests {
             manager
                 .local_router_info
                 .addresses
-                .get(&TransportKind::Ntcp2)
+                .get(&TransportKind::Ntcp2V4)
                 .unwrap()
                 .options
                 .get(&Str::from("host")),
@@ -1390,7 +1704,7 @@ mod tests {
         assert!(manager
             .local_router_info
             .addresses
-            .get(&TransportKind::Ntcp2)
+            .get(&TransportKind::Ntcp2V4)
             .unwrap()
             .options
             .get(&Str::from("s"))
@@ -1403,7 +1717,7 @@ mod tests {
             manager
                 .local_router_info
                 .addresses
-                .get(&TransportKind::Ntcp2)
+                .get(&TransportKind::Ntcp2V4)
                 .unwrap()
                 .options
                 .get(&Str::from("host")),
@@ -1412,7 +1726,7 @@ mod tests {
         assert!(manager
             .local_router_info
             .addresses
-            .get(&TransportKind::Ntcp2)
+            .get(&TransportKind::Ntcp2V4)
             .unwrap()
             .options
             .get(&Str::from("s"))
@@ -1423,7 +1737,10 @@ mod tests {
     async fn discovered_address_doesnt_match_published_address_ssu2() {
         let context = Ssu2Transport::<MockRuntime>::initialize(Some(Ssu2Config {
             port: 0,
-            host: Some("192.168.1.1".parse().unwrap()),
+            ipv4_host: Some("192.168.1.1".parse().unwrap()),
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             static_key: [0u8; 32],
             intro_key: [1u8; 32],
@@ -1443,7 +1760,7 @@ mod tests {
             manager
                 .local_router_info
                 .addresses
-                .get(&TransportKind::Ssu2)
+                .get(&TransportKind::Ssu2V4)
                 .unwrap()
                 .options
                 .get(&Str::from("host")),
@@ -1457,7 +1774,7 @@ mod tests {
             manager
                 .local_router_info
                 .addresses
-                .get(&TransportKind::Ssu2)
+                .get(&TransportKind::Ssu2V4)
                 .unwrap()
                 .options
                 .get(&Str::from("host")),
@@ -1854,7 +2171,10 @@ mod tests {
         );
         let context = Ntcp2Transport::<MockRuntime>::initialize(Some(Ntcp2Config {
             port: 0,
-            host: Some("192.168.0.1".parse().unwrap()),
+            ipv4_host: Some("192.168.0.1".parse().unwrap()),
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             key: [0u8; 32],
             iv: [0u8; 16],
@@ -1910,7 +2230,10 @@ mod tests {
         );
         let context = Ntcp2Transport::<MockRuntime>::initialize(Some(Ntcp2Config {
             port: 0,
-            host: Some("192.168.0.1".parse().unwrap()),
+            ipv4_host: Some("192.168.0.1".parse().unwrap()),
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             key: [0u8; 32],
             iv: [0u8; 16],
@@ -1939,6 +2262,54 @@ mod tests {
         .is_usable());
     }
 
+    #[tokio::test]
+    async fn firewall_status_updates_caps() {
+        let (router_info, static_key, signing_key) = RouterInfoBuilder::default().build();
+        let serialized = Bytes::from(router_info.serialize(&signing_key));
+        let storage = ProfileStorage::<MockRuntime>::new(&[], &[]);
+        let (handle, _netdb_rx) = NetDbHandle::create();
+        let (_event_mgr, _event_subscriber, event_handle) = EventManager::new(None);
+        let ctx = RouterContext::new(
+            MockRuntime::register_metrics(vec![], None),
+            storage.clone(),
+            router_info.identity.id(),
+            serialized.clone(),
+            static_key,
+            signing_key,
+            2u8,
+            event_handle.clone(),
+        );
+
+        let mut builder = TransportManagerBuilder::<MockRuntime>::new(ctx, router_info, true);
+        let _handle = builder.register_subsystem(SubsystemKind::NetDb);
+        builder.register_netdb_handle(handle);
+
+        let mut manager = builder.build();
+
+        // router is firewalled
+        manager.on_firewall_status_changed(FirewallStatus::Firewalled);
+        let caps = manager.local_router_info.options.get(&Str::from("caps")).unwrap().clone();
+
+        assert!(caps.contains('U'));
+        assert!(!caps.contains('R'));
+        assert!(!manager.local_router_info.capabilities.is_reachable());
+
+        // router is reachable
+        manager.on_firewall_status_changed(FirewallStatus::Ok);
+        let caps = manager.local_router_info.options.get(&Str::from("caps")).unwrap().clone();
+
+        assert!(caps.contains('R'));
+        assert!(!caps.contains('U'));
+        assert!(manager.local_router_info.capabilities.is_reachable());
+
+        // unknown status doesn't modify the capabilities
+        manager.on_firewall_status_changed(FirewallStatus::Unknown);
+        assert_eq!(
+            manager.local_router_info.options.get(&Str::from("caps")).unwrap(),
+            &caps
+        );
+    }
+
     #[tokio::test]
     async fn router_info_query_fails() {
         let (router_info, static_key, signing_key) = RouterInfoBuilder::default().build();
@@ -2047,7 +2418,10 @@ mod tests {
         let (remote_router_info, _, _) = RouterInfoBuilder::default()
             .with_ssu2(Ssu2Config {
                 port: 888u16,
-                host: Some("127.0.0.1".parse().unwrap()),
+                ipv4_host: Some("127.0.0.1".parse().unwrap()),
+                ipv6_host: None,
+                ipv4: true,
+                ipv6: false,
                 publish: true,
                 static_key: [1u8; 32],
                 intro_key: [2u8; 32],
@@ -2074,7 +2448,10 @@ mod tests {
         let mut handle = builder.register_subsystem(SubsystemKind::NetDb);
         let context = Ntcp2Transport::<MockRuntime>::initialize(Some(Ntcp2Config {
             port: 0,
-            host: Some("192.168.0.1".parse().unwrap()),
+            ipv4_host: Some("192.168.0.1".parse().unwrap()),
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             key: [0u8; 32],
             iv: [0u8; 16],
@@ -2149,4 +2526,47 @@ mod tests {
             _ => panic!("invalid event"),
         }
     }
+
+    #[tokio::test]
+    async fn bandwidth_class_updated() {
+        let mut builder = make_transport_manager(None, None);
+        builder.with_default_bandwidth(Some(Bandwidth::L));
+        let mut manager = builder.build();
+        let caps = |manager: &TransportManager<MockRuntime>| {
+            manager.local_router_info.options.get(&Str::from("caps")).unwrap().to_string()
+        };
+        assert_eq!(caps(&manager), "L");
+
+        // limit bandwidth and verify that the bandwidth class is derived from the limits
+        manager.router_ctx.bandwidth_limiter().update(Some(&crate::BandwidthConfig {
+            inbound: None,
+            outbound: Some(1000),
+            share: Some(100),
+        }));
+        manager.update_bandwidth_class();
+
+        assert_eq!(caps(&manager), "P");
+        assert!(manager.local_router_info.capabilities.is_fast());
+
+        // remove limits and verify that the default bandwidth class is published again
+        manager.router_ctx.bandwidth_limiter().update(None);
+        manager.update_bandwidth_class();
+
+        assert_eq!(caps(&manager), "L");
+        assert!(!manager.local_router_info.capabilities.is_fast());
+
+        // bandwidth class is not published if transit tunnels are disabled
+        let mut builder = make_transport_manager(None, None);
+        builder.with_transit_tunnels_disabled(true);
+        let mut manager = builder.build();
+
+        manager.router_ctx.bandwidth_limiter().update(Some(&crate::BandwidthConfig {
+            inbound: Some(10),
+            outbound: None,
+            share: None,
+        }));
+        manager.update_bandwidth_class();
+
+        assert_eq!(caps(&manager), "L");
+    }
 }
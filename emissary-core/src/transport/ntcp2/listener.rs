@@ -17,9 +17,10 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
+    blocklist::Blocklist,
     runtime::{Runtime, TcpListener},
     transport::ntcp2::LOG_TARGET,
-    util::is_global,
+    util::{is_global, is_global_ipv6},
 };
 
 use futures::Stream;
@@ -30,43 +31,70 @@ use core::{
     task::{Context, Poll},
 };
 
-// TODO: support both ipv4 and ipv6
-
 /// NTCP2 listener.
+///
+/// Accepts connections over IPv4, IPv6 or both, depending on which listeners were bound.
 pub struct Ntcp2Listener<R: Runtime> {
     /// Allow local addresses.
     allow_local: bool,
 
-    /// TCP Listener.
-    listener: R::TcpListener,
+    /// Blocklist.
+    blocklist: Blocklist,
+
+    /// IPv4 TCP listener.
+    ipv4: Option<R::TcpListener>,
+
+    /// IPv6 TCP listener.
+    ipv6: Option<R::TcpListener>,
 }
 
 impl<R: Runtime> Ntcp2Listener<R> {
-    /// Create new [`Ntcp2Listener`] from a TCP listener.
-    pub fn new(listener: R::TcpListener, allow_local: bool) -> Self {
+    /// Create new [`Ntcp2Listener`] from IPv4 and IPv6 TCP listeners.
+    pub fn new(
+        ipv4: Option<R::TcpListener>,
+        ipv6: Option<R::TcpListener>,
+        allow_local: bool,
+        blocklist: Blocklist,
+    ) -> Self {
         Self {
             allow_local,
-            listener,
+            blocklist,
+            ipv4,
+            ipv6,
         }
     }
 
     /// Get local address of the TCP listener.
+    ///
+    /// Returns the address of the IPv4 listener if it exists.
     #[cfg(test)]
     pub fn local_address(&self) -> SocketAddr {
-        self.listener.local_address().expect("to succeed")
+        self.ipv4
+            .as_ref()
+            .or(self.ipv6.as_ref())
+            .and_then(|listener| listener.local_address())
+            .expect("to succeed")
     }
-}
 
-impl<R: Runtime> Stream for Ntcp2Listener<R> {
-    type Item = R::TcpStream;
-
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    /// Poll `listener` for an inbound connection, ignoring connections from blocked addresses and
+    /// from local addresses if they have been disabled.
+    fn poll_listener(
+        listener: &mut R::TcpListener,
+        allow_local: bool,
+        blocklist: &Blocklist,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<R::TcpStream>> {
         loop {
-            match self.listener.poll_accept(cx) {
+            match listener.poll_accept(cx) {
                 Poll::Pending => return Poll::Pending,
                 Poll::Ready(None) => return Poll::Ready(None),
-                Poll::Ready(Some((stream, address))) => match address {
-                    SocketAddr::V4(address) if !is_global(*address.ip()) && !self.allow_local => {
+                Poll::Ready(Some((stream, address))) => {
+                    let is_global = match address {
+                        SocketAddr::V4(address) => is_global(*address.ip()),
+                        SocketAddr::V6(address) => is_global_ipv6(*address.ip()),
+                    };
+
+                    if !is_global && !allow_local {
                         tracing::warn!(
                             target: LOG_TARGET,
                             ?address,
@@ -74,9 +102,45 @@ impl<R: Runtime> Stream for Ntcp2Listener<R> {
                         );
                         continue;
                     }
-                    _ => return Poll::Ready(Some(stream)),
-                },
+
+                    if blocklist.is_address_blocked(&address.ip()) {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            ?address,
+                            "incoming connection from blocked address",
+                        );
+                        continue;
+                    }
+
+                    return Poll::Ready(Some(stream));
+                }
             }
         }
     }
 }
+
+impl<R: Runtime> Stream for Ntcp2Listener<R> {
+    type Item = R::TcpStream;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        if let Some(listener) = this.ipv4.as_mut() {
+            if let Poll::Ready(event) =
+                Self::poll_listener(listener, this.allow_local, &this.blocklist, cx)
+            {
+                return Poll::Ready(event);
+            }
+        }
+
+        if let Some(listener) = this.ipv6.as_mut() {
+            if let Poll::Ready(event) =
+                Self::poll_listener(listener, this.allow_local, &this.blocklist, cx)
+            {
+                return Poll::Ready(event);
+            }
+        }
+
+        Poll::Pending
+    }
+}
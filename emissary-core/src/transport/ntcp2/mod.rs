@@ -36,9 +36,9 @@ use crate::{
 use futures::{Stream, StreamExt};
 use hashbrown::{hash_map::Entry, HashMap};
 
-use alloc::{format, vec::Vec};
+use alloc::{vec, vec::Vec};
 use core::{
-    net::SocketAddr,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     pin::Pin,
     task::{Context, Poll, Waker},
 };
@@ -56,17 +56,20 @@ pub struct Ntcp2Context<R: Runtime> {
     /// NTCP2 configuration.
     config: Ntcp2Config,
 
-    /// NTCP2 listener.
-    listener: R::TcpListener,
+    /// IPv4 listener, if IPv4 was enabled.
+    ipv4_listener: Option<R::TcpListener>,
 
-    /// Socket address.
-    socket_address: SocketAddr,
+    /// IPv6 listener, if IPv6 was enabled.
+    ipv6_listener: Option<R::TcpListener>,
+
+    /// Port where the listeners are bound to.
+    port: u16,
 }
 
 impl<R: Runtime> Ntcp2Context<R> {
     /// Get the port where [`Ntcp2Listener`] is bound to.
     pub fn port(&self) -> u16 {
-        self.socket_address.port()
+        self.port
     }
 
     /// Get copy of [`Ntcp2Config`].
@@ -114,27 +117,36 @@ impl<R: Runtime> Ntcp2Transport<R> {
     ) -> Self {
         let Ntcp2Context {
             config,
-            listener,
-            socket_address,
+            ipv4_listener,
+            ipv6_listener,
+            port,
         } = context;
 
-        let session_manager = SessionManager::new(
+        let mut session_manager = SessionManager::new(
             config.key,
             config.iv,
             router_ctx.clone(),
             subsystem_handle,
             allow_local,
         );
+        session_manager.with_address_families(ipv4_listener.is_some(), ipv6_listener.is_some());
 
         tracing::info!(
             target: LOG_TARGET,
-            listen_address = ?socket_address,
+            ?port,
+            ipv4 = ?ipv4_listener.is_some(),
+            ipv6 = ?ipv6_listener.is_some(),
             ?allow_local,
             "starting ntcp2",
         );
 
         Ntcp2Transport {
-            listener: Ntcp2Listener::new(listener, allow_local),
+            listener: Ntcp2Listener::new(
+                ipv4_listener,
+                ipv6_listener,
+                allow_local,
+                router_ctx.blocklist().clone(),
+            ),
             open_connections: R::join_set(),
             pending_connections: HashMap::new(),
             pending_handshakes: R::join_set(),
@@ -149,66 +161,99 @@ impl<R: Runtime> Ntcp2Transport<R> {
         metrics
     }
 
+    /// Bind TCP listener to `address` and return the listener and the port it was bound to.
+    async fn bind_listener(address: SocketAddr) -> crate::Result<(R::TcpListener, u16)> {
+        let listener = R::TcpListener::bind(address).await.ok_or_else(|| {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?address,
+                "ntcp2 port in use, select another port for the transport",
+            );
+
+            Error::Connection(ConnectionError::BindFailure)
+        })?;
+
+        let socket_address = listener.local_address().ok_or_else(|| {
+            tracing::warn!(
+                target: LOG_TARGET,
+                "failed to get local address of the ntcp2 listener",
+            );
+
+            Error::Connection(ConnectionError::BindFailure)
+        })?;
+
+        Ok((listener, socket_address.port()))
+    }
+
     /// Initialize [`Ntcp2Transport`].
     ///
-    /// If NTCP2 has been enabled, create a router address using the configuration that was provided
-    /// and bind a TCP listener to the port that was specified.
+    /// If NTCP2 has been enabled, create router addresses using the configuration that was
+    /// provided and bind TCP listeners for the enabled address families to the port that was
+    /// specified. If both IPv4 and IPv6 are enabled, the listeners are bound to the same port.
     ///
-    /// Returns a [`RouterAddress`] of the transport and an [`Ntcp2Context`] that needs to be passed
-    /// to [`Ntcp2Transport::new()`] when constructing the transport.
+    /// Returns the [`RouterAddress`]es of the transport and an [`Ntcp2Context`] that needs to be
+    /// passed to [`Ntcp2Transport::new()`] when constructing the transport.
     pub async fn initialize(
         config: Option<Ntcp2Config>,
-    ) -> crate::Result<(Option<Ntcp2Context<R>>, Option<RouterAddress>)> {
+    ) -> crate::Result<(Option<Ntcp2Context<R>>, Vec<RouterAddress>)> {
         let Some(config) = config else {
-            return Ok((None, None));
+            return Ok((None, Vec::new()));
         };
 
-        let listener =
-            R::TcpListener::bind(format!("0.0.0.0:{}", config.port).parse().expect("to succeed"))
-                .await
-                .ok_or_else(|| {
-                    tracing::warn!(
-                        target: LOG_TARGET,
-                        port = %config.port,
-                        "ntcp2 port in use, select another port for the transport",
-                    );
-
-                    Error::Connection(ConnectionError::BindFailure)
-                })?;
-
-        let socket_address = listener.local_address().ok_or_else(|| {
+        if !config.ipv4 && !config.ipv6 {
             tracing::warn!(
                 target: LOG_TARGET,
-                "failed to get local address of the ntcp2 listener",
+                "ntcp2 enabled but both ipv4 and ipv6 are disabled",
             );
+            return Err(Error::Connection(ConnectionError::BindFailure));
+        }
 
-            Error::Connection(ConnectionError::BindFailure)
-        })?;
+        // if the port is selected by the os, bind the ipv6 listener to the same port
+        let (ipv4_listener, port) = match config.ipv4 {
+            true => Self::bind_listener(SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), config.port))
+                .await
+                .map(|(listener, port)| (Some(listener), port))?,
+            false => (None, config.port),
+        };
+        let (ipv6_listener, port) = match config.ipv6 {
+            true => Self::bind_listener(SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), port))
+                .await
+                .map(|(listener, port)| (Some(listener), port))?,
+            false => (None, port),
+        };
 
-        let address = match (config.publish, config.host) {
-            (true, Some(host)) => RouterAddress::new_published_ntcp2(
-                config.key,
-                config.iv,
-                socket_address.port(),
-                host,
-            ),
-            (true, None) => {
+        let hosts = [
+            config.ipv4_host.filter(|_| config.ipv4).map(IpAddr::V4),
+            config.ipv6_host.filter(|_| config.ipv6).map(IpAddr::V6),
+        ];
+
+        let addresses = match (config.publish, hosts) {
+            (true, [None, None]) => {
                 tracing::debug!(
                     target: LOG_TARGET,
                     "ntcp2 requested to be published but no host provided",
                 );
-                RouterAddress::new_unpublished_ntcp2(config.key, socket_address.port())
+
+                vec![RouterAddress::new_unpublished_ntcp2(config.key, port)
+                    .with_address_families(config.ipv4, config.ipv6)]
             }
-            (_, _) => RouterAddress::new_unpublished_ntcp2(config.key, socket_address.port()),
+            (true, hosts) => hosts
+                .into_iter()
+                .flatten()
+                .map(|host| RouterAddress::new_published_ntcp2(config.key, config.iv, port, host))
+                .collect(),
+            (false, _) => vec![RouterAddress::new_unpublished_ntcp2(config.key, port)
+                .with_address_families(config.ipv4, config.ipv6)],
         };
 
         Ok((
             Some(Ntcp2Context {
                 config,
-                listener,
-                socket_address,
+                ipv4_listener,
+                ipv6_listener,
+                port,
             }),
-            Some(address),
+            addresses,
         ))
     }
 }
@@ -378,19 +423,26 @@ impl<R: Runtime> Stream for Ntcp2Transport<R> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{primitives::Str, runtime::mock::MockRuntime};
+    use crate::{
+        primitives::{Str, TransportKind},
+        runtime::mock::MockRuntime,
+    };
 
     #[tokio::test]
     async fn publish_ntcp() {
         let config = Some(Ntcp2Config {
             port: 0u16,
-            host: Some("8.8.8.8".parse().unwrap()),
+            ipv4_host: Some("8.8.8.8".parse().unwrap()),
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             key: [0xaa; 32],
             iv: [0xbb; 16],
         });
-        let (context, address) = Ntcp2Transport::<MockRuntime>::initialize(config).await.unwrap();
-        let port = context.as_ref().unwrap().socket_address.port().to_string();
+        let (context, addresses) = Ntcp2Transport::<MockRuntime>::initialize(config).await.unwrap();
+        let address = addresses.first();
+        let port = context.as_ref().unwrap().port().to_string();
 
         assert_eq!(
             address.as_ref().unwrap().options.get(&Str::from("host")),
@@ -409,12 +461,16 @@ mod tests {
     async fn dont_publish_ntcp() {
         let config = Some(Ntcp2Config {
             port: 0u16,
-            host: None,
+            ipv4_host: None,
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: false,
             key: [0xaa; 32],
             iv: [0xbb; 16],
         });
-        let (context, address) = Ntcp2Transport::<MockRuntime>::initialize(config).await.unwrap();
+        let (context, addresses) = Ntcp2Transport::<MockRuntime>::initialize(config).await.unwrap();
+        let address = addresses.first();
 
         assert!(address.as_ref().unwrap().options.get(&Str::from("host")).is_none());
         assert!(address.as_ref().unwrap().options.get(&Str::from("port")).is_none());
@@ -427,12 +483,16 @@ mod tests {
     async fn dont_publish_ntcp_host_specified() {
         let config = Some(Ntcp2Config {
             port: 0u16,
-            host: Some("8.8.8.8".parse().unwrap()),
+            ipv4_host: Some("8.8.8.8".parse().unwrap()),
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: false,
             key: [0xaa; 32],
             iv: [0xbb; 16],
         });
-        let (context, address) = Ntcp2Transport::<MockRuntime>::initialize(config).await.unwrap();
+        let (context, addresses) = Ntcp2Transport::<MockRuntime>::initialize(config).await.unwrap();
+        let address = addresses.first();
 
         assert!(address.as_ref().unwrap().options.get(&Str::from("host")).is_none());
         assert!(address.as_ref().unwrap().options.get(&Str::from("port")).is_none());
@@ -445,12 +505,16 @@ mod tests {
     async fn publish_ntcp_but_no_host() {
         let config = Some(Ntcp2Config {
             port: 0u16,
-            host: None,
+            ipv4_host: None,
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             key: [0xaa; 32],
             iv: [0xbb; 16],
         });
-        let (context, address) = Ntcp2Transport::<MockRuntime>::initialize(config).await.unwrap();
+        let (context, addresses) = Ntcp2Transport::<MockRuntime>::initialize(config).await.unwrap();
+        let address = addresses.first();
 
         assert!(address.as_ref().unwrap().options.get(&Str::from("host")).is_none());
         assert!(address.as_ref().unwrap().options.get(&Str::from("port")).is_none());
@@ -463,12 +527,16 @@ mod tests {
     async fn bind_to_random_port() {
         let config = Some(Ntcp2Config {
             port: 0u16,
-            host: None,
+            ipv4_host: None,
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             key: [0xaa; 32],
             iv: [0xbb; 16],
         });
-        let (context, address) = Ntcp2Transport::<MockRuntime>::initialize(config).await.unwrap();
+        let (context, addresses) = Ntcp2Transport::<MockRuntime>::initialize(config).await.unwrap();
+        let address = addresses.first();
 
         assert!(address.as_ref().unwrap().options.get(&Str::from("host")).is_none());
         assert!(address.as_ref().unwrap().options.get(&Str::from("port")).is_none());
@@ -485,12 +553,16 @@ mod tests {
     async fn publish_random_port() {
         let config = Some(Ntcp2Config {
             port: 0u16,
-            host: Some("8.8.8.8".parse().unwrap()),
+            ipv4_host: Some("8.8.8.8".parse().unwrap()),
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             key: [0xaa; 32],
             iv: [0xbb; 16],
         });
-        let (context, address) = Ntcp2Transport::<MockRuntime>::initialize(config).await.unwrap();
+        let (context, addresses) = Ntcp2Transport::<MockRuntime>::initialize(config).await.unwrap();
+        let address = addresses.first();
 
         let published_port = address
             .as_ref()
@@ -512,8 +584,84 @@ mod tests {
 
     #[tokio::test]
     async fn ntcp2_not_enabled() {
-        let (context, address) = Ntcp2Transport::<MockRuntime>::initialize(None).await.unwrap();
+        let (context, addresses) = Ntcp2Transport::<MockRuntime>::initialize(None).await.unwrap();
         assert!(context.is_none());
-        assert!(address.is_none());
+        assert!(addresses.is_empty());
+    }
+
+    #[tokio::test]
+    async fn publish_ipv4_and_ipv6() {
+        let config = Some(Ntcp2Config {
+            port: 0u16,
+            ipv4_host: Some("8.8.8.8".parse().unwrap()),
+            ipv6_host: Some("2001:4860:4860::8888".parse().unwrap()),
+            ipv4: true,
+            ipv6: true,
+            publish: true,
+            key: [0xaa; 32],
+            iv: [0xbb; 16],
+        });
+        let (context, addresses) = Ntcp2Transport::<MockRuntime>::initialize(config).await.unwrap();
+        let context = context.unwrap();
+
+        assert!(context.ipv4_listener.is_some());
+        assert!(context.ipv6_listener.is_some());
+        assert_eq!(addresses.len(), 2);
+
+        // both addresses are published and use the same port
+        assert_eq!(addresses[0].transport, TransportKind::Ntcp2V4);
+        assert_eq!(
+            addresses[0].socket_address,
+            Some(SocketAddr::new("8.8.8.8".parse().unwrap(), context.port()))
+        );
+        assert_eq!(addresses[1].transport, TransportKind::Ntcp2V6);
+        assert_eq!(
+            addresses[1].socket_address,
+            Some(SocketAddr::new(
+                "2001:4860:4860::8888".parse().unwrap(),
+                context.port()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn ipv6_only() {
+        let config = Some(Ntcp2Config {
+            port: 0u16,
+            ipv4_host: None,
+            ipv6_host: None,
+            ipv4: false,
+            ipv6: true,
+            publish: false,
+            key: [0xaa; 32],
+            iv: [0xbb; 16],
+        });
+        let (context, addresses) = Ntcp2Transport::<MockRuntime>::initialize(config).await.unwrap();
+        let context = context.unwrap();
+
+        assert!(context.ipv4_listener.is_none());
+        assert!(context.ipv6_listener.is_some());
+        assert_eq!(addresses.len(), 1);
+        assert_eq!(addresses[0].transport, TransportKind::Ntcp2V6);
+        assert_eq!(
+            addresses[0].options.get(&Str::from("caps")),
+            Some(&Str::from("6"))
+        );
+    }
+
+    #[tokio::test]
+    async fn ipv4_and_ipv6_disabled() {
+        let config = Some(Ntcp2Config {
+            port: 0u16,
+            ipv4_host: None,
+            ipv6_host: None,
+            ipv4: false,
+            ipv6: false,
+            publish: false,
+            key: [0xaa; 32],
+            iv: [0xbb; 16],
+        });
+
+        assert!(Ntcp2Transport::<MockRuntime>::initialize(config).await.is_err());
     }
 }
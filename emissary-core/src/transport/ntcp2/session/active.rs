@@ -21,6 +21,7 @@
 //! https://geti2p.net/spec/ntcp2#data-phase
 
 use crate::{
+    bandwidth::BandwidthLimiter,
     crypto::{chachapoly::ChaChaPoly, siphash::SipHash},
     events::EventHandle,
     primitives::{RouterId, RouterInfo},
@@ -99,6 +100,9 @@ enum WriteState {
 
 /// Active NTCP2 session.
 pub struct Ntcp2Session<R: Runtime> {
+    /// Bandwidth limiter.
+    bandwidth_limiter: BandwidthLimiter<R>,
+
     /// RX channel for receiving messages from subsystems.
     cmd_rx: Receiver<SubsystemCommand>,
 
@@ -117,6 +121,9 @@ pub struct Ntcp2Session<R: Runtime> {
     /// Read buffer.
     read_buffer: Vec<u8>,
 
+    /// Read throttle timer, set if inbound bandwidth has been exhausted.
+    read_throttle: Option<R::Timer>,
+
     /// Read state.
     read_state: ReadState,
 
@@ -146,6 +153,9 @@ pub struct Ntcp2Session<R: Runtime> {
 
     /// Write state.
     write_state: WriteState,
+
+    /// Write throttle timer, set if outbound bandwidth has been exhausted.
+    write_throttle: Option<R::Timer>,
 }
 
 impl<R: Runtime> Ntcp2Session<R> {
@@ -158,6 +168,7 @@ impl<R: Runtime> Ntcp2Session<R> {
         subsystem_handle: SubsystemHandle,
         direction: Direction,
         event_handle: EventHandle<R>,
+        bandwidth_limiter: BandwidthLimiter<R>,
     ) -> Self {
         let KeyContext {
             send_key,
@@ -168,6 +179,7 @@ impl<R: Runtime> Ntcp2Session<R> {
         let (cmd_tx, cmd_rx) = channel(512);
 
         Self {
+            bandwidth_limiter,
             cmd_rx,
             cmd_tx,
             direction,
@@ -175,6 +187,7 @@ impl<R: Runtime> Ntcp2Session<R> {
             bandwidth: 0usize,
             read_buffer: vec![0u8; 0xffff],
             read_state: ReadState::ReadSize { offset: 0usize },
+            read_throttle: None,
             recv_cipher: ChaChaPoly::new(&recv_key),
             role,
             router: router_info.identity.id(),
@@ -184,6 +197,7 @@ impl<R: Runtime> Ntcp2Session<R> {
             stream,
             subsystem_handle,
             write_state: WriteState::GetMessage,
+            write_throttle: None,
         }
     }
 
@@ -232,6 +246,14 @@ impl<R: Runtime> Future for Ntcp2Session<R> {
         let mut stream = Pin::new(&mut this.stream);
 
         loop {
+            // reading is paused until inbound bandwidth has been refilled
+            if let Some(timer) = &mut this.read_throttle {
+                if timer.poll_unpin(cx).is_pending() {
+                    break;
+                }
+                this.read_throttle = None;
+            }
+
             match this.read_state {
                 ReadState::ReadSize { offset } => {
                     match stream.as_mut().poll_read(cx, &mut this.read_buffer[offset..2]) {
@@ -285,6 +307,11 @@ impl<R: Runtime> Future for Ntcp2Session<R> {
                                 continue;
                             }
                             this.bandwidth += this.read_buffer[..size].len();
+                            this.bandwidth_limiter.inbound(size + 2);
+
+                            if let Some(delay) = this.bandwidth_limiter.inbound_delay() {
+                                this.read_throttle = Some(R::timer(delay));
+                            }
 
                             let data_block =
                                 match this.recv_cipher.decrypt(this.read_buffer[..size].to_vec()) {
@@ -376,28 +403,45 @@ impl<R: Runtime> Future for Ntcp2Session<R> {
             match mem::replace(&mut this.write_state, WriteState::Poisoned) {
                 // TODO: poll messages until `Poll::Pending` is returned
                 // or there's enough messages to fill one ntcp2 message?
-                WriteState::GetMessage => match this.cmd_rx.poll_recv(cx) {
-                    Poll::Pending => {
-                        this.write_state = WriteState::GetMessage;
-                        break;
+                WriteState::GetMessage => {
+                    // writing is paused until outbound bandwidth has been refilled
+                    if let Some(timer) = &mut this.write_throttle {
+                        if timer.poll_unpin(cx).is_pending() {
+                            this.write_state = WriteState::GetMessage;
+                            break;
+                        }
+                        this.write_throttle = None;
                     }
-                    Poll::Ready(None) => return Poll::Ready(TerminationReason::Unspecified),
-                    Poll::Ready(Some(SubsystemCommand::Dummy)) => unreachable!(),
-                    Poll::Ready(Some(SubsystemCommand::SendMessage { message })) => {
-                        assert!(message.len() as u16 <= u16::MAX, "too large message");
 
-                        // TODO: in-place?
-                        let test = MessageBlock::new_i2np_message(&message);
-                        let data_block = this.send_cipher.encrypt(&test).unwrap();
-                        let size = this.sip.obfuscate(data_block.len() as u16);
+                    match this.cmd_rx.poll_recv(cx) {
+                        Poll::Pending => {
+                            this.write_state = WriteState::GetMessage;
+                            break;
+                        }
+                        Poll::Ready(None) => return Poll::Ready(TerminationReason::Unspecified),
+                        Poll::Ready(Some(SubsystemCommand::Dummy)) => unreachable!(),
+                        Poll::Ready(Some(SubsystemCommand::SendMessage { message })) => {
+                            assert!(message.len() as u16 <= u16::MAX, "too large message");
+
+                            // TODO: in-place?
+                            let test = MessageBlock::new_i2np_message(&message);
+                            let data_block = this.send_cipher.encrypt(&test).unwrap();
+                            let size = this.sip.obfuscate(data_block.len() as u16);
 
-                        this.write_state = WriteState::SendSize {
-                            size: size.to_be_bytes().to_vec(),
-                            offset: 0usize,
-                            message: data_block,
-                        };
+                            this.bandwidth_limiter.outbound(data_block.len() + 2);
+
+                            if let Some(delay) = this.bandwidth_limiter.outbound_delay() {
+                                this.write_throttle = Some(R::timer(delay));
+                            }
+
+                            this.write_state = WriteState::SendSize {
+                                size: size.to_be_bytes().to_vec(),
+                                offset: 0usize,
+                                message: data_block,
+                            };
+                        }
                     }
-                },
+                }
                 WriteState::SendSize {
                     offset,
                     size,
@@ -31,10 +31,11 @@
 //! and responder can be found from `initiator.rs` and `responder.rs`.
 
 use crate::{
+    bandwidth::BandwidthLimiter,
     crypto::{noise::NoiseContext, sha256::Sha256, siphash::SipHash, StaticPrivateKey},
     error::Error,
     events::EventHandle,
-    primitives::{RouterId, RouterInfo, TransportKind},
+    primitives::{RouterId, RouterInfo},
     profile::ProfileStorage,
     router::context::RouterContext,
     runtime::{Runtime, TcpStream},
@@ -42,7 +43,7 @@ use crate::{
         ntcp2::session::{initiator::Initiator, responder::Responder},
         Direction, SubsystemHandle,
     },
-    util::{is_global, AsyncReadExt, AsyncWriteExt},
+    util::{is_global, is_global_ipv6, AsyncReadExt, AsyncWriteExt},
 };
 
 use bytes::Bytes;
@@ -111,6 +112,12 @@ pub struct SessionManager<R: Runtime> {
     /// State that is common for all inbound connections.
     inbound_initial_state: [u8; 32],
 
+    /// Dial routers over IPv4.
+    ipv4: bool,
+
+    /// Dial routers over IPv6.
+    ipv6: bool,
+
     /// Local NTCP2 IV.
     local_iv: [u8; 16],
 
@@ -155,6 +162,8 @@ impl<R: Runtime> SessionManager<R> {
             allow_local,
             chaining_key,
             inbound_initial_state,
+            ipv4: true,
+            ipv6: false,
             local_iv,
             local_key,
             outbound_initial_state,
@@ -163,6 +172,15 @@ impl<R: Runtime> SessionManager<R> {
         }
     }
 
+    /// Specify which address families are used to dial routers.
+    ///
+    /// By default, only IPv4 is used.
+    pub fn with_address_families(&mut self, ipv4: bool, ipv6: bool) -> &mut Self {
+        self.ipv4 = ipv4;
+        self.ipv6 = ipv6;
+        self
+    }
+
     /// Called by [`SessionManager::create_session()`] to open outbound session to `router`.
     async fn create_session_inner(
         router: RouterInfo,
@@ -171,8 +189,10 @@ impl<R: Runtime> SessionManager<R> {
         local_key: StaticPrivateKey,
         noise_ctx: NoiseContext,
         allow_local: bool,
+        (ipv4, ipv6): (bool, bool),
         subsystem_handle: SubsystemHandle,
         event_handle: EventHandle<R>,
+        bandwidth_limiter: BandwidthLimiter<R>,
     ) -> crate::Result<Ntcp2Session<R>> {
         let router_id = router.identity.id();
 
@@ -186,27 +206,31 @@ impl<R: Runtime> SessionManager<R> {
                 Error::InvalidData
             })?;
 
-            // transprot must exist since static key and iv were found
             let socket_address = router
-                .addresses
-                .get(&TransportKind::Ntcp2)
-                .expect("to exist")
-                .socket_address
+                .ntcp2_address(ipv4, ipv6)
+                .and_then(|address| address.socket_address)
                 .ok_or_else(|| {
-                    tracing::debug!(target: LOG_TARGET, "router doesn't have socket address");
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        ?ipv4,
+                        ?ipv6,
+                        "router doesn't have a socket address for enabled address families",
+                    );
                     Error::InvalidData
                 })?;
 
-            match socket_address.ip() {
-                IpAddr::V4(address) if !is_global(address) && !allow_local => {
-                    tracing::warn!(
-                        target: LOG_TARGET,
-                        ?address,
-                        "tried to dial local address but local addresses were disabled",
-                    );
-                    return Err(Error::InvalidData);
-                }
-                _ => {}
+            let is_global = match socket_address.ip() {
+                IpAddr::V4(address) => is_global(address),
+                IpAddr::V6(address) => is_global_ipv6(address),
+            };
+
+            if !is_global && !allow_local {
+                tracing::warn!(
+                    target: LOG_TARGET,
+                    address = ?socket_address.ip(),
+                    "tried to dial local address but local addresses were disabled",
+                );
+                return Err(Error::InvalidData);
             }
 
             (static_key, iv, socket_address)
@@ -274,6 +298,7 @@ impl<R: Runtime> SessionManager<R> {
             subsystem_handle,
             Direction::Outbound,
             event_handle,
+            bandwidth_limiter,
         ))
     }
 
@@ -294,8 +319,10 @@ impl<R: Runtime> SessionManager<R> {
         let outbound_initial_state = self.outbound_initial_state;
         let chaining_key = self.chaining_key;
         let allow_local = self.allow_local;
+        let address_families = (self.ipv4, self.ipv6);
         let mut subsystem_handle = self.subsystem_handle.clone();
         let event_handle = self.router_ctx.event_handle().clone();
+        let bandwidth_limiter = self.router_ctx.bandwidth_limiter().clone();
         let router_id = router.identity.id();
 
         async move {
@@ -306,8 +333,10 @@ impl<R: Runtime> SessionManager<R> {
                 local_key,
                 NoiseContext::new(chaining_key, outbound_initial_state),
                 allow_local,
+                address_families,
                 subsystem_handle.clone(),
                 event_handle,
+                bandwidth_limiter,
             )
             .await
             {
@@ -338,6 +367,7 @@ impl<R: Runtime> SessionManager<R> {
         iv: [u8; 16],
         profile_storage: ProfileStorage<R>,
         event_handle: EventHandle<R>,
+        bandwidth_limiter: BandwidthLimiter<R>,
     ) -> crate::Result<Ntcp2Session<R>> {
         tracing::trace!(
             target: LOG_TARGET,
@@ -392,6 +422,7 @@ impl<R: Runtime> SessionManager<R> {
                     subsystem_handle,
                     Direction::Inbound,
                     event_handle,
+                    bandwidth_limiter,
                 ))
             }
             Err(error) => {
@@ -421,6 +452,7 @@ impl<R: Runtime> SessionManager<R> {
         let iv = self.local_iv;
         let profile_storage = self.router_ctx.profile_storage().clone();
         let event_handle = self.router_ctx.event_handle().clone();
+        let bandwidth_limiter = self.router_ctx.bandwidth_limiter().clone();
 
         async move {
             Self::accept_session_inner(
@@ -433,6 +465,7 @@ impl<R: Runtime> SessionManager<R> {
                 iv,
                 profile_storage,
                 event_handle,
+                bandwidth_limiter,
             )
             .await
             .map_err(|error| (None, error))
@@ -444,13 +477,11 @@ impl<R: Runtime> SessionManager<R> {
 mod tests {
     use super::*;
     use crate::{
+        blocklist::Blocklist,
         crypto::{SigningPrivateKey, StaticPrivateKey},
         events::EventManager,
         i2np::{MessageBuilder, MessageType, I2NP_MESSAGE_EXPIRATION},
-        primitives::{
-            Capabilities, Date, Mapping, RouterAddress, RouterIdentity, RouterInfo, Str,
-            TransportKind,
-        },
+        primitives::{Capabilities, Date, Mapping, RouterAddress, RouterIdentity, RouterInfo, Str},
         profile::ProfileStorage,
         runtime::{
             mock::{MockRuntime, MockTcpListener, MockTcpStream},
@@ -510,24 +541,31 @@ mod tests {
             self
         }
 
+        fn with_ipv6_router_address(mut self, port: u16) -> Self {
+            self.router_address = Some(RouterAddress::new_published_ntcp2(
+                self.ntcp2_key.clone(),
+                self.ntcp2_iv,
+                port,
+                "::1".parse().unwrap(),
+            ));
+            self
+        }
+
         fn build(mut self) -> Ntcp2 {
             let signing_key = SigningPrivateKey::random(thread_rng());
             let static_key = StaticPrivateKey::random(thread_rng());
             let identity =
                 RouterIdentity::from_keys::<MockRuntime>(&static_key, &signing_key).unwrap();
+            let router_address = self.router_address.take().unwrap_or(
+                RouterAddress::new_unpublished_ntcp2(self.ntcp2_key.clone(), 8888),
+            );
             let router_info = RouterInfo {
                 identity,
                 published: Date::new(
                     (MockRuntime::time_since_epoch() - Duration::from_secs(2 * 60)).as_millis()
                         as u64,
                 ),
-                addresses: HashMap::from_iter([(
-                    TransportKind::Ntcp2,
-                    self.router_address.take().unwrap_or(RouterAddress::new_unpublished_ntcp2(
-                        self.ntcp2_key.clone(),
-                        8888,
-                    )),
-                )]),
+                addresses: HashMap::from_iter([(router_address.transport, router_address)]),
                 options: Mapping::from_iter([
                     (Str::from("netId"), Str::from(self.net_id.to_string())),
                     (Str::from("caps"), Str::from("L")),
@@ -614,6 +652,96 @@ mod tests {
         assert!(res2.unwrap().is_ok());
     }
 
+    #[tokio::test]
+    async fn connection_succeeds_ipv6() {
+        let (_event_mgr, _event_subscriber, event_handle) = EventManager::new(None);
+        let local = Ntcp2Builder::new().build();
+        let mut local_manager = SessionManager::new(
+            local.ntcp2_key,
+            local.ntcp2_iv,
+            RouterContext::new(
+                MockRuntime::register_metrics(Vec::new(), None),
+                ProfileStorage::<MockRuntime>::new(&[], &[]),
+                local.router_info.identity.id(),
+                Bytes::from(local.router_info.serialize(&local.signing_key)),
+                local.static_key,
+                local.signing_key,
+                2u8,
+                event_handle.clone(),
+            ),
+            SubsystemHandle::new(),
+            true,
+        );
+        local_manager.with_address_families(true, true);
+
+        let listener = TcpListener::bind("[::1]:0").await.unwrap();
+        let remote = Ntcp2Builder::new()
+            .with_ipv6_router_address(listener.local_addr().unwrap().port())
+            .build();
+        let remote_manager = SessionManager::new(
+            remote.ntcp2_key,
+            remote.ntcp2_iv,
+            RouterContext::new(
+                MockRuntime::register_metrics(Vec::new(), None),
+                ProfileStorage::<MockRuntime>::new(&[], &[]),
+                remote.router_info.identity.id(),
+                Bytes::from(remote.router_info.serialize(&remote.signing_key)),
+                remote.static_key,
+                remote.signing_key,
+                2u8,
+                event_handle.clone(),
+            ),
+            SubsystemHandle::new(),
+            true,
+        );
+
+        let handle =
+            tokio::spawn(
+                async move { local_manager.create_session(remote.router_info.clone()).await },
+            );
+
+        let stream = MockTcpStream::new(
+            tokio::time::timeout(Duration::from_secs(5), listener.accept())
+                .await
+                .unwrap()
+                .unwrap()
+                .0,
+        );
+        let (res1, res2) = tokio::join!(remote_manager.accept_session(stream), handle);
+
+        assert!(res1.is_ok());
+        assert!(res2.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn ipv6_address_not_dialed_if_ipv6_disabled() {
+        let (_event_mgr, _event_subscriber, event_handle) = EventManager::new(None);
+        let local = Ntcp2Builder::new().build();
+        let local_manager = SessionManager::new(
+            local.ntcp2_key,
+            local.ntcp2_iv,
+            RouterContext::new(
+                MockRuntime::register_metrics(Vec::new(), None),
+                ProfileStorage::<MockRuntime>::new(&[], &[]),
+                local.router_info.identity.id(),
+                Bytes::from(local.router_info.serialize(&local.signing_key)),
+                local.static_key,
+                local.signing_key,
+                2u8,
+                event_handle.clone(),
+            ),
+            SubsystemHandle::new(),
+            true,
+        );
+
+        let listener = TcpListener::bind("[::1]:0").await.unwrap();
+        let remote = Ntcp2Builder::new()
+            .with_ipv6_router_address(listener.local_addr().unwrap().port())
+            .build();
+
+        assert!(local_manager.create_session(remote.router_info.clone()).await.is_err());
+    }
+
     #[tokio::test]
     async fn invalid_network_id_initiator() {
         let (_event_mgr, _event_subscriber, event_handle) = EventManager::new(None);
@@ -808,7 +936,8 @@ mod tests {
         );
 
         let listener = MockTcpListener::bind("127.0.0.1:0".parse().unwrap()).await.unwrap();
-        let mut listener = Ntcp2Listener::<MockRuntime>::new(listener, false);
+        let mut listener =
+            Ntcp2Listener::<MockRuntime>::new(Some(listener), None, false, Blocklist::default());
         let remote = Ntcp2Builder::new()
             .with_net_id(128)
             .with_router_address(listener.local_address().port())
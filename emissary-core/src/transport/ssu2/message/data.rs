@@ -21,7 +21,10 @@ use crate::{
     i2np::MessageType as I2npMessageType,
     runtime::Runtime,
     transport::{
-        ssu2::{message::*, session::KeyContext},
+        ssu2::{
+            message::{peer_test::PeerTestMessage, *},
+            session::KeyContext,
+        },
         TerminationReason,
     },
 };
@@ -30,6 +33,7 @@ use bytes::{BufMut, BytesMut};
 use rand_core::RngCore;
 
 use alloc::{vec, vec::Vec};
+use core::net::SocketAddr;
 
 /// Minimum size for an ACK block.
 const ACK_BLOCK_MIN_SIZE: usize = 8usize;
@@ -97,6 +101,9 @@ pub struct DataMessageBuilder<'a> {
     /// Should the immediate ACK bit be set.
     immediate_ack: bool,
 
+    /// Serialized control block(s) and serialized router info, if any.
+    control_block: Option<(BytesMut, Option<&'a [u8]>)>,
+
     /// Packet number.
     ///
     /// Set only if `message` is `None`.
@@ -150,6 +157,50 @@ impl<'a> DataMessageBuilder<'a> {
         self
     }
 
+    /// Add peer test block and optionally a router info block.
+    pub fn with_peer_test(
+        mut self,
+        message: &'a PeerTestMessage,
+        router_info: Option<&'a [u8]>,
+    ) -> Self {
+        self.control_block = Some((message.serialize(), router_info));
+        self
+    }
+
+    /// Add relay block and optionally a router info block.
+    ///
+    /// `block` must be one of the relay blocks.
+    pub fn with_relay(mut self, block: Block, router_info: Option<&'a [u8]>) -> Self {
+        debug_assert!(core::matches!(
+            block,
+            Block::RelayRequest { .. }
+                | Block::RelayResponse { .. }
+                | Block::RelayIntro { .. }
+                | Block::RelayTagRequest {}
+                | Block::RelayTag { .. }
+        ));
+
+        self.control_block = Some((block.serialize(), router_info));
+        self
+    }
+
+    /// Add path challenge block together with an address block containing `address`.
+    ///
+    /// `address` is the socket address of the remote router the challenge is sent to.
+    pub fn with_path_challenge(mut self, challenge: Vec<u8>, address: SocketAddr) -> Self {
+        let mut out = Block::Address { address }.serialize();
+        out.unsplit(Block::PathChallenge { challenge }.serialize());
+
+        self.control_block = Some((out, None));
+        self
+    }
+
+    /// Add path response block.
+    pub fn with_path_response(mut self, response: Vec<u8>) -> Self {
+        self.control_block = Some((Block::PathResponse { response }.serialize(), None));
+        self
+    }
+
     /// Add termination block.
     pub fn with_termination(mut self, termination_reason: TerminationReason) -> Self {
         self.termination_reason = Some(termination_reason);
@@ -224,6 +275,19 @@ impl<'a> DataMessageBuilder<'a> {
                     out.put_slice(fragment);
                 }
             }
+            if let Some((block, router_info)) = self.control_block.take() {
+                // router info is sent only if it fits into the packet with the control block
+                if let Some(router_info) = router_info {
+                    if router_info.len() + block.len() + 5 < bytes_left.saturating_sub(out.len()) {
+                        out.put_u8(BlockType::RouterInfo.as_u8());
+                        out.put_u16((2 + router_info.len()) as u16);
+                        out.put_u8(0u8); // flag
+                        out.put_u8(1u8); // fragment 0 of 1
+                        out.put_slice(router_info);
+                    }
+                }
+                out.put_slice(&block);
+            }
             bytes_left = bytes_left.saturating_sub(out.len());
 
             match self.acks.take() {
@@ -371,4 +435,47 @@ mod tests {
             _ => panic!("invalid type"),
         }
     }
+
+    #[test]
+    fn path_challenge() {
+        let mut pkt = DataMessageBuilder::default()
+            .with_dst_id(1337u64)
+            .with_pkt_num(1337)
+            .with_key_context(
+                [1u8; 32],
+                &KeyContext {
+                    k_data: [2u8; 32],
+                    k_header_2: [3u8; 32],
+                },
+            )
+            .with_path_challenge(
+                vec![1, 2, 3, 4, 5, 6, 7, 8],
+                "127.0.0.1:8888".parse().unwrap(),
+            )
+            .build::<MockRuntime>()
+            .to_vec();
+
+        let mut reader = HeaderReader::new([1u8; 32], &mut pkt).unwrap();
+        assert_eq!(reader.dst_id(), 1337u64);
+
+        let pkt_num = match reader.parse([3u8; 32]) {
+            Ok(HeaderKind::Data { pkt_num, .. }) => pkt_num,
+            _ => panic!("invalid type"),
+        };
+
+        let mut payload = pkt[16..].to_vec();
+        ChaChaPoly::with_nonce(&[2u8; 32], pkt_num as u64)
+            .decrypt_with_ad(&pkt[..16], &mut payload)
+            .unwrap();
+
+        let blocks = Block::parse(&payload).unwrap();
+        assert!(blocks.iter().any(|block| match block {
+            Block::Address { address } => address == &"127.0.0.1:8888".parse().unwrap(),
+            _ => false,
+        }));
+        assert!(blocks.iter().any(|block| match block {
+            Block::PathChallenge { challenge } => challenge == &vec![1, 2, 3, 4, 5, 6, 7, 8],
+            _ => false,
+        }));
+    }
 }
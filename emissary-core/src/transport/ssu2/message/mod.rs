@@ -25,9 +25,13 @@ use crate::{
     error::Ssu2Error,
     i2np::{Message, MessageType as I2npMessageType},
     primitives::{MessageId, RouterInfo},
+    transport::ssu2::message::{
+        peer_test::PeerTestMessage,
+        relay::{RelayIntro, RelayRequest, RelayResponse},
+    },
 };
 
-use bytes::{BufMut, BytesMut};
+use bytes::{BufMut, Bytes, BytesMut};
 use nom::{
     bytes::complete::take,
     error::{make_error, ErrorKind},
@@ -38,12 +42,14 @@ use nom::{
 use alloc::{boxed::Box, vec::Vec};
 use core::{
     fmt,
-    net::{IpAddr, SocketAddr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     ops::{Deref, Range},
 };
 
 pub mod data;
 pub mod handshake;
+pub mod peer_test;
+pub mod relay;
 
 /// Logging target for the file.
 const LOG_TARGET: &str = "emissary::ssu2::message";
@@ -202,6 +208,9 @@ pub enum Block {
     RouterInfo {
         /// Router info.
         router_info: Box<RouterInfo>,
+
+        /// Serialized router info.
+        serialized: Bytes,
     },
 
     /// I2NP message.
@@ -250,16 +259,28 @@ pub enum Block {
     },
 
     /// Relay request.
-    RelayRequest {},
+    RelayRequest {
+        /// Relay request.
+        message: RelayRequest,
+    },
 
     /// Relay response.
-    RelayResponse {},
+    RelayResponse {
+        /// Relay response.
+        message: RelayResponse,
+    },
 
     /// Relay intro.
-    RelayIntro {},
+    RelayIntro {
+        /// Relay intro.
+        message: RelayIntro,
+    },
 
     /// Peer test.
-    PeerTest {},
+    PeerTest {
+        /// Peer test message.
+        message: PeerTestMessage,
+    },
 
     /// Next nonce.
     NextNonce {},
@@ -288,7 +309,10 @@ pub enum Block {
     RelayTagRequest {},
 
     /// Relay tag.
-    RelayTag {},
+    RelayTag {
+        /// Relay tag.
+        tag: u32,
+    },
 
     /// New token.
     NewToken {
@@ -422,6 +446,18 @@ impl fmt::Debug for Block {
                 .finish(),
             Self::Congestion { flag } =>
                 f.debug_struct("Block::Congestion").field("flag", &flag).finish(),
+            Self::PeerTest { message } =>
+                f.debug_struct("Block::PeerTest").field("message", &message).finish(),
+            Self::RelayRequest { message } =>
+                f.debug_struct("Block::RelayRequest").field("message", &message).finish(),
+            Self::RelayResponse { message } =>
+                f.debug_struct("Block::RelayResponse").field("message", &message).finish(),
+            Self::RelayIntro { message } =>
+                f.debug_struct("Block::RelayIntro").field("message", &message).finish(),
+            Self::RelayTagRequest {} => f.debug_struct("Block::RelayTagRequest").finish(),
+            Self::RelayTag { tag } => f.debug_struct("Block::RelayTag").field("tag", &tag).finish(),
+            Self::Address { address } =>
+                f.debug_struct("Block::Address").field("address", &address).finish(),
             _ => f.debug_struct("Unsupported").finish(),
         }
     }
@@ -482,7 +518,7 @@ impl Block {
         }
         let (rest, flag) = be_u8(rest)?;
         let (rest, _frag) = be_u8(rest)?;
-        let (rest, router_info) = take(size - 2)(rest)?;
+        let (rest, serialized) = take(size - 2)(rest)?;
 
         if flag & 1 == 1 {
             tracing::warn!(
@@ -499,7 +535,7 @@ impl Block {
             return Err(Err::Error(make_error(input, ErrorKind::Fail)));
         }
 
-        let router_info = RouterInfo::parse(router_info).ok_or_else(|| {
+        let router_info = RouterInfo::parse(serialized).ok_or_else(|| {
             tracing::warn!(
                 target: LOG_TARGET,
                 "malformed router info",
@@ -511,6 +547,7 @@ impl Block {
             rest,
             Block::RouterInfo {
                 router_info: Box::new(router_info),
+                serialized: Bytes::from(serialized.to_vec()),
             },
         ))
     }
@@ -717,6 +754,81 @@ impl Block {
         Ok((rest, Block::FirstPacketNumber { first_pkt_num }))
     }
 
+    /// Parse [`MessageBlock::PeerTest`].
+    fn parse_peer_test(input: &[u8]) -> IResult<&[u8], Block> {
+        let (rest, message) = PeerTestMessage::parse_frame(input)?;
+
+        Ok((rest, Block::PeerTest { message }))
+    }
+
+    /// Parse [`MessageBlock::RelayRequest`].
+    fn parse_relay_request(input: &[u8]) -> IResult<&[u8], Block> {
+        let (rest, message) = RelayRequest::parse_frame(input)?;
+
+        Ok((rest, Block::RelayRequest { message }))
+    }
+
+    /// Parse [`MessageBlock::RelayResponse`].
+    fn parse_relay_response(input: &[u8]) -> IResult<&[u8], Block> {
+        let (rest, message) = RelayResponse::parse_frame(input)?;
+
+        Ok((rest, Block::RelayResponse { message }))
+    }
+
+    /// Parse [`MessageBlock::RelayIntro`].
+    fn parse_relay_intro(input: &[u8]) -> IResult<&[u8], Block> {
+        let (rest, message) = RelayIntro::parse_frame(input)?;
+
+        Ok((rest, Block::RelayIntro { message }))
+    }
+
+    /// Parse [`MessageBlock::RelayTagRequest`].
+    fn parse_relay_tag_request(input: &[u8]) -> IResult<&[u8], Block> {
+        let (rest, size) = be_u16(input)?;
+        let (rest, _) = take(size)(rest)?;
+
+        Ok((rest, Block::RelayTagRequest {}))
+    }
+
+    /// Parse [`MessageBlock::RelayTag`].
+    fn parse_relay_tag(input: &[u8]) -> IResult<&[u8], Block> {
+        let (rest, _size) = be_u16(input)?;
+        let (rest, tag) = be_u32(rest)?;
+
+        Ok((rest, Block::RelayTag { tag }))
+    }
+
+    /// Parse [`MessageBlock::Address`].
+    fn parse_address(input: &[u8]) -> IResult<&[u8], Block> {
+        let (rest, size) = be_u16(input)?;
+        let (rest, port) = be_u16(rest)?;
+
+        match size {
+            6 => {
+                let (rest, address) = be_u32(rest)?;
+
+                Ok((
+                    rest,
+                    Block::Address {
+                        address: SocketAddr::new(IpAddr::V4(Ipv4Addr::from(address)), port),
+                    },
+                ))
+            }
+            18 => {
+                let (rest, address) = take(16usize)(rest)?;
+                let address = TryInto::<[u8; 16]>::try_into(address).expect("to succeed");
+
+                Ok((
+                    rest,
+                    Block::Address {
+                        address: SocketAddr::new(IpAddr::V6(Ipv6Addr::from(address)), port),
+                    },
+                ))
+            }
+            _ => Err(Err::Error(make_error(input, ErrorKind::Fail))),
+        }
+    }
+
     /// Parse [`MessageBlock::Congestion`].
     fn parse_congestion(input: &[u8]) -> IResult<&[u8], Block> {
         let (rest, _size) = be_u16(input)?;
@@ -752,6 +864,13 @@ impl Block {
             Some(BlockType::PathResponse) => Self::parse_path_response(rest),
             Some(BlockType::FirstPacketNumber) => Self::parse_first_packet_number(rest),
             Some(BlockType::Congestion) => Self::parse_congestion(rest),
+            Some(BlockType::PeerTest) => Self::parse_peer_test(rest),
+            Some(BlockType::RelayRequest) => Self::parse_relay_request(rest),
+            Some(BlockType::RelayResponse) => Self::parse_relay_response(rest),
+            Some(BlockType::RelayIntro) => Self::parse_relay_intro(rest),
+            Some(BlockType::RelayTagRequest) => Self::parse_relay_tag_request(rest),
+            Some(BlockType::RelayTag) => Self::parse_relay_tag(rest),
+            Some(BlockType::Address) => Self::parse_address(rest),
             Some(BlockType::Padding) => Self::parse_padding(rest),
             Some(block_type) => {
                 tracing::warn!(
@@ -795,7 +914,7 @@ impl Block {
             + match self {
                 Block::DateTime { .. } => 4usize,
                 Block::Options { .. } => OPTIONS_MIN_SIZE as usize,
-                Block::RouterInfo { .. } => todo!(),
+                Block::RouterInfo { serialized, .. } => 2usize + serialized.len(), // flag + frag
                 Block::I2Np { message } => message.serialized_len_short(),
                 Block::FirstFragment { fragment, .. } => fragment
                     .len()
@@ -820,6 +939,12 @@ impl Block {
                     IpAddr::V4(_) => 2usize + 4usize, // port + address
                     IpAddr::V6(_) => 2usize + 16usize, // port + address
                 },
+                Block::PeerTest { message } => message.serialized_len(),
+                Block::RelayRequest { message } => message.serialized_len(),
+                Block::RelayResponse { message } => message.serialized_len(),
+                Block::RelayIntro { message } => message.serialized_len(),
+                Block::RelayTagRequest {} => 0usize,
+                Block::RelayTag { .. } => 4usize, // relay tag
                 block_type => todo!("unsupported block type: {block_type:?}"),
             }
     }
@@ -854,6 +979,46 @@ impl Block {
 
                 out
             }
+            Self::RouterInfo { serialized, .. } => {
+                out.put_u8(BlockType::RouterInfo.as_u8());
+                out.put_u16((2 + serialized.len()) as u16);
+                out.put_u8(0u8); // flag
+                out.put_u8(1u8); // fragment 0 of 1
+                out.put_slice(&serialized);
+
+                out
+            }
+            Self::PeerTest { message } => message.serialize(),
+            Self::RelayRequest { message } => message.serialize(),
+            Self::RelayResponse { message } => message.serialize(),
+            Self::RelayIntro { message } => message.serialize(),
+            Self::RelayTagRequest {} => {
+                out.put_u8(BlockType::RelayTagRequest.as_u8());
+                out.put_u16(0u16);
+
+                out
+            }
+            Self::RelayTag { tag } => {
+                out.put_u8(BlockType::RelayTag.as_u8());
+                out.put_u16(4u16);
+                out.put_u32(tag);
+
+                out
+            }
+            Self::PathChallenge { challenge } => {
+                out.put_u8(BlockType::PathChallenge.as_u8());
+                out.put_u16(challenge.len() as u16);
+                out.put_slice(&challenge);
+
+                out
+            }
+            Self::PathResponse { response } => {
+                out.put_u8(BlockType::PathResponse.as_u8());
+                out.put_u16(response.len() as u16);
+                out.put_slice(&response);
+
+                out
+            }
             Self::Padding { padding } => {
                 out.put_u8(BlockType::Padding.as_u8());
                 out.put_u16(padding.len() as u16);
@@ -975,6 +1140,9 @@ pub enum HeaderKind {
         /// Packet number.
         pkt_num: u32,
 
+        /// Source connection ID.
+        src_id: u64,
+
         /// Token
         token: u64,
     },
@@ -999,6 +1167,24 @@ pub enum HeaderKind {
         /// Packet number.
         pkt_num: u32,
     },
+
+    /// Out-of-session peer test.
+    PeerTest {
+        /// Network ID.
+        net_id: u8,
+
+        /// Packet number.
+        pkt_num: u32,
+    },
+
+    /// Out-of-session hole punch.
+    HolePunch {
+        /// Network ID.
+        net_id: u8,
+
+        /// Packet number.
+        pkt_num: u32,
+    },
 }
 
 impl fmt::Debug for HeaderKind {
@@ -1028,12 +1214,14 @@ impl fmt::Debug for HeaderKind {
             Self::SessionRequest {
                 net_id,
                 pkt_num,
+                src_id,
                 token,
                 ..
             } => f
                 .debug_struct("HeaderKind::SessionRequest")
                 .field("net_id", &net_id)
                 .field("pkt_num", &pkt_num)
+                .field("src_id", &src_id)
                 .field("token", &token)
                 .finish_non_exhaustive(),
             Self::TokenRequest {
@@ -1054,6 +1242,16 @@ impl fmt::Debug for HeaderKind {
                 .field("pkt_num", &pkt_num)
                 .field("immediate_ack", &immediate_ack)
                 .finish(),
+            Self::PeerTest { net_id, pkt_num } => f
+                .debug_struct("HeaderKind::PeerTest")
+                .field("net_id", &net_id)
+                .field("pkt_num", &pkt_num)
+                .finish(),
+            Self::HolePunch { net_id, pkt_num } => f
+                .debug_struct("HeaderKind::HolePunch")
+                .field("net_id", &net_id)
+                .field("pkt_num", &pkt_num)
+                .finish(),
         }
     }
 }
@@ -1144,6 +1342,9 @@ impl<'a> HeaderReader<'a> {
                 let pkt_num = u32::from_be(header as u32);
 
                 // these are expected to succeed as the packet has been confirmed to be long enough
+                let src_id = u64::from_le_bytes(
+                    TryInto::<[u8; 8]>::try_into(&self.pkt[16..24]).expect("to succeed"),
+                );
                 let token = u64::from_le_bytes(
                     TryInto::<[u8; 8]>::try_into(&self.pkt[24..32]).expect("to succeed"),
                 );
@@ -1154,6 +1355,7 @@ impl<'a> HeaderReader<'a> {
                     ephemeral_key,
                     net_id,
                     pkt_num,
+                    src_id,
                     token,
                 })
             }
@@ -1250,13 +1452,37 @@ impl<'a> HeaderReader<'a> {
                     src_id,
                 })
             }
-            message_type => {
-                tracing::warn!(
-                    target: LOG_TARGET,
-                    ?message_type,
-                    "unsupported message type",
-                );
-                Err(Ssu2Error::UnexpectedMessage)
+            MessageType::PeerTest => {
+                if ((header >> 40) as u8) != PROTOCOL_VERSION {
+                    return Err(Ssu2Error::InvalidVersion);
+                }
+
+                if self.pkt.len() < 32 {
+                    return Err(Ssu2Error::NotEnoughBytes);
+                }
+
+                ChaCha::with_iv(k_header_2, [0u8; 12]).decrypt_ref(&mut self.pkt[16..32]);
+
+                Ok(HeaderKind::PeerTest {
+                    net_id: ((header >> 48) & 0xff) as u8,
+                    pkt_num: u32::from_be(header as u32),
+                })
+            }
+            MessageType::HolePunch => {
+                if ((header >> 40) as u8) != PROTOCOL_VERSION {
+                    return Err(Ssu2Error::InvalidVersion);
+                }
+
+                if self.pkt.len() < 32 {
+                    return Err(Ssu2Error::NotEnoughBytes);
+                }
+
+                ChaCha::with_iv(k_header_2, [0u8; 12]).decrypt_ref(&mut self.pkt[16..32]);
+
+                Ok(HeaderKind::HolePunch {
+                    net_id: ((header >> 48) & 0xff) as u8,
+                    pkt_num: u32::from_be(header as u32),
+                })
             }
         }
     }
@@ -0,0 +1,589 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! SSU2 peer test block and out-of-session peer test messages.
+//!
+//! https://geti2p.net/spec/ssu2#peer-test
+
+use crate::{
+    crypto::chachapoly::{ChaCha, ChaChaPoly},
+    primitives::RouterId,
+    runtime::Runtime,
+    transport::ssu2::message::*,
+};
+
+use bytes::{BufMut, BytesMut};
+use nom::{
+    bytes::complete::take,
+    error::{make_error, ErrorKind},
+    number::complete::{be_u16, be_u32, be_u8},
+    Err, IResult,
+};
+use rand_core::RngCore;
+
+use alloc::{vec, vec::Vec};
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// Length of the router hash included in messages 2 and 4.
+const ROUTER_HASH_LEN: usize = 32usize;
+
+/// Peer test status code.
+///
+/// https://geti2p.net/spec/ssu2#peer-test
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PeerTestCode {
+    /// Peer test accepted.
+    #[default]
+    Accept,
+
+    /// Rejected by Bob, unspecified reason.
+    BobUnspecified,
+
+    /// Rejected by Bob, no Charlie available.
+    BobNoCharlieAvailable,
+
+    /// Rejected by Bob, limit exceeded.
+    BobLimitExceeded,
+
+    /// Rejected by Bob, signature failure.
+    BobSignatureFailure,
+
+    /// Rejected by Charlie, unspecified reason.
+    CharlieUnspecified,
+
+    /// Rejected by Charlie, unsupported address.
+    CharlieUnsupportedAddress,
+
+    /// Rejected by Charlie, limit exceeded.
+    CharlieLimitExceeded,
+
+    /// Rejected by Charlie, signature failure.
+    CharlieSignatureFailure,
+
+    /// Rejected by Charlie, Alice is already connected.
+    CharlieAliceConnected,
+
+    /// Rejected by Charlie, Alice is banned.
+    CharlieAliceBanned,
+
+    /// Rejected by Charlie, Alice is unknown.
+    CharlieAliceUnknown,
+
+    /// Rejected, unspecified reason.
+    Unspecified,
+}
+
+impl PeerTestCode {
+    /// Serialize [`PeerTestCode`].
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            Self::Accept => 0u8,
+            Self::BobUnspecified => 1u8,
+            Self::BobNoCharlieAvailable => 2u8,
+            Self::BobLimitExceeded => 3u8,
+            Self::BobSignatureFailure => 4u8,
+            Self::CharlieUnspecified => 64u8,
+            Self::CharlieUnsupportedAddress => 65u8,
+            Self::CharlieLimitExceeded => 66u8,
+            Self::CharlieSignatureFailure => 67u8,
+            Self::CharlieAliceConnected => 68u8,
+            Self::CharlieAliceBanned => 69u8,
+            Self::CharlieAliceUnknown => 70u8,
+            Self::Unspecified => 128u8,
+        }
+    }
+
+    /// Deserialize [`PeerTestCode`].
+    ///
+    /// Reserved codes are mapped to the unspecified rejection of the router that sent them.
+    pub fn from_u8(code: u8) -> Self {
+        match code {
+            0u8 => Self::Accept,
+            2u8 => Self::BobNoCharlieAvailable,
+            3u8 => Self::BobLimitExceeded,
+            4u8 => Self::BobSignatureFailure,
+            1u8..=63u8 => Self::BobUnspecified,
+            65u8 => Self::CharlieUnsupportedAddress,
+            66u8 => Self::CharlieLimitExceeded,
+            67u8 => Self::CharlieSignatureFailure,
+            68u8 => Self::CharlieAliceConnected,
+            69u8 => Self::CharlieAliceBanned,
+            70u8 => Self::CharlieAliceUnknown,
+            64u8..=127u8 => Self::CharlieUnspecified,
+            _ => Self::Unspecified,
+        }
+    }
+
+    /// Returns `true` if the peer test was accepted.
+    pub fn is_accept(&self) -> bool {
+        core::matches!(self, Self::Accept)
+    }
+}
+
+/// Peer test message.
+///
+/// Carried inside a `PeerTest` block, either in-session (messages 1 - 4) or in an out-of-session
+/// `PeerTest` packet (messages 5 - 7).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PeerTestMessage {
+    /// Message number, 1 - 7.
+    pub message_num: u8,
+
+    /// Status code.
+    ///
+    /// Only meaningful for messages 3 and 4.
+    pub code: PeerTestCode,
+
+    /// Router hash of Alice (message 2) or Charlie (message 4).
+    ///
+    /// `None` for other messages and for message 4 if Bob rejected the peer test.
+    pub router_hash: Option<RouterId>,
+
+    /// Test nonce.
+    pub nonce: u32,
+
+    /// Timestamp, seconds since UNIX epoch.
+    pub timestamp: u32,
+
+    /// Alice's socket address.
+    pub address: Option<SocketAddr>,
+
+    /// Signature.
+    ///
+    /// Signed by Alice for messages 1 - 2 and by Charlie for messages 3 - 4. Empty for messages
+    /// 5 - 7.
+    pub signature: Vec<u8>,
+}
+
+impl PeerTestMessage {
+    /// Returns `true` if the message carries a router hash.
+    fn has_router_hash(message_num: u8) -> bool {
+        message_num == 2 || message_num == 4
+    }
+
+    /// Attempt to parse [`PeerTestMessage`] from `input`, excluding block type and size.
+    fn parse_inner(input: &[u8]) -> IResult<&[u8], PeerTestMessage> {
+        let (rest, message_num) = be_u8(input)?;
+        let (rest, code) = be_u8(rest)?;
+        let (rest, _flag) = be_u8(rest)?;
+
+        let (rest, router_hash) = match Self::has_router_hash(message_num) {
+            true => {
+                let (rest, router_hash) = take(ROUTER_HASH_LEN)(rest)?;
+
+                match router_hash.iter().all(|byte| byte == &0u8) {
+                    true => (rest, None),
+                    false => (rest, Some(RouterId::from(router_hash))),
+                }
+            }
+            false => (rest, None),
+        };
+
+        let (rest, version) = be_u8(rest)?;
+        if version != PROTOCOL_VERSION {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?version,
+                "unsupported peer test version",
+            );
+            return Err(Err::Error(make_error(input, ErrorKind::Fail)));
+        }
+
+        let (rest, nonce) = be_u32(rest)?;
+        let (rest, timestamp) = be_u32(rest)?;
+        let (rest, address_size) = be_u8(rest)?;
+
+        let (rest, address) = match address_size {
+            0 => (rest, None),
+            6 => {
+                let (rest, port) = be_u16(rest)?;
+                let (rest, address) = be_u32(rest)?;
+
+                (
+                    rest,
+                    Some(SocketAddr::new(IpAddr::V4(Ipv4Addr::from(address)), port)),
+                )
+            }
+            18 => {
+                let (rest, port) = be_u16(rest)?;
+                let (rest, address) = take(16usize)(rest)?;
+                let address = TryInto::<[u8; 16]>::try_into(address).expect("to succeed");
+
+                (
+                    rest,
+                    Some(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(address)), port)),
+                )
+            }
+            _ => return Err(Err::Error(make_error(input, ErrorKind::Fail))),
+        };
+
+        Ok((
+            &[],
+            PeerTestMessage {
+                message_num,
+                code: PeerTestCode::from_u8(code),
+                router_hash,
+                nonce,
+                timestamp,
+                address,
+                signature: rest.to_vec(),
+            },
+        ))
+    }
+
+    /// Attempt to parse [`PeerTestMessage`] from `input`.
+    ///
+    /// `input` points to the size field of the block.
+    pub fn parse_frame(input: &[u8]) -> IResult<&[u8], PeerTestMessage> {
+        let (rest, size) = be_u16(input)?;
+        let (rest, block) = take(size)(rest)?;
+        let (_, message) = Self::parse_inner(block)?;
+
+        Ok((rest, message))
+    }
+
+    /// Get the message data of the peer test which is covered by the signature.
+    pub fn data(&self) -> BytesMut {
+        let mut out = BytesMut::with_capacity(28usize);
+
+        out.put_u8(PROTOCOL_VERSION);
+        out.put_u32(self.nonce);
+        out.put_u32(self.timestamp);
+
+        match self.address {
+            None => out.put_u8(0u8),
+            Some(SocketAddr::V4(address)) => {
+                out.put_u8(6u8);
+                out.put_u16(address.port());
+                out.put_slice(&address.ip().octets());
+            }
+            Some(SocketAddr::V6(address)) => {
+                out.put_u8(18u8);
+                out.put_u16(address.port());
+                out.put_slice(&address.ip().octets());
+            }
+        }
+
+        out
+    }
+
+    /// Get the bytes that are signed by Alice (messages 1 - 2) or Charlie (messages 3 - 4).
+    ///
+    /// `alice` must be specified for the messages that are signed by Charlie.
+    pub fn signed_data(&self, bob: &RouterId, alice: Option<&RouterId>) -> Vec<u8> {
+        let data = self.data();
+        let mut out = Vec::with_capacity(16 + 2 * ROUTER_HASH_LEN + data.len());
+
+        out.extend_from_slice(b"PeerTestValidate");
+        out.extend_from_slice(&bob.to_vec());
+        if let Some(alice) = alice {
+            out.extend_from_slice(&alice.to_vec());
+        }
+        out.extend_from_slice(&data);
+
+        out
+    }
+
+    /// Get serialized length of the [`PeerTestMessage`] when placed inside a block.
+    pub fn serialized_len(&self) -> usize {
+        let router_hash_len = match Self::has_router_hash(self.message_num) {
+            true => ROUTER_HASH_LEN,
+            false => 0usize,
+        };
+
+        3usize // message number + code + flag
+            + router_hash_len
+            + self.data().len()
+            + self.signature.len()
+    }
+
+    /// Serialize [`PeerTestMessage`] into a `PeerTest` block.
+    pub fn serialize(&self) -> BytesMut {
+        let mut out = BytesMut::with_capacity(3usize + self.serialized_len());
+
+        out.put_u8(BlockType::PeerTest.as_u8());
+        out.put_u16(self.serialized_len() as u16);
+        out.put_u8(self.message_num);
+        out.put_u8(self.code.as_u8());
+        out.put_u8(0u8); // flag
+
+        if Self::has_router_hash(self.message_num) {
+            match &self.router_hash {
+                Some(router_hash) => out.put_slice(&router_hash.to_vec()),
+                None => out.put_slice(&[0u8; ROUTER_HASH_LEN]),
+            }
+        }
+
+        out.put_slice(&self.data());
+        out.put_slice(&self.signature);
+
+        out
+    }
+}
+
+/// Builder for out-of-session `PeerTest` messages (messages 5 - 7).
+pub struct PeerTestBuilder {
+    /// Address block.
+    address: Option<SocketAddr>,
+
+    /// Remote router's intro key.
+    intro_key: Option<[u8; 32]>,
+
+    /// Peer test message.
+    message: Option<PeerTestMessage>,
+
+    /// Network ID.
+    ///
+    /// Defaults to 2.
+    net_id: u8,
+}
+
+impl Default for PeerTestBuilder {
+    fn default() -> Self {
+        Self {
+            address: None,
+            intro_key: None,
+            message: None,
+            net_id: 2u8,
+        }
+    }
+}
+
+impl PeerTestBuilder {
+    /// Specify the socket address that is sent in the `Address` block.
+    pub fn with_address(mut self, address: SocketAddr) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Specify remote router's intro key.
+    pub fn with_intro_key(mut self, intro_key: [u8; 32]) -> Self {
+        self.intro_key = Some(intro_key);
+        self
+    }
+
+    /// Specify peer test message.
+    pub fn with_message(mut self, message: PeerTestMessage) -> Self {
+        self.message = Some(message);
+        self
+    }
+
+    /// Specify network ID.
+    pub fn with_net_id(mut self, net_id: u8) -> Self {
+        self.net_id = net_id;
+        self
+    }
+
+    /// Build [`PeerTestBuilder`] into a byte vector.
+    ///
+    /// Connection IDs of the packet are derived from the test nonce.
+    pub fn build<R: Runtime>(mut self) -> BytesMut {
+        let intro_key = self.intro_key.take().expect("to exist");
+        let message = self.message.take().expect("to exist");
+        let mut rng = R::rng();
+        let padding = {
+            let padding_len = rng.next_u32() % MAX_PADDING as u32 + 8;
+            let mut padding = vec![0u8; padding_len as usize];
+            rng.fill_bytes(&mut padding);
+
+            padding
+        };
+
+        let (mut header, pkt_num) = {
+            let mut out = BytesMut::with_capacity(LONG_HEADER_LEN);
+            let pkt_num = rng.next_u32();
+            let nonce = message.nonce as u64;
+
+            out.put_u64_le(nonce | ((!nonce & 0xffffffff) << 32));
+            out.put_u32(pkt_num);
+            out.put_u8(*MessageType::PeerTest);
+            out.put_u8(PROTOCOL_VERSION);
+            out.put_u8(self.net_id);
+            out.put_u8(0u8); // flag
+            out.put_u64_le((!nonce & 0xffffffff) | (nonce << 32));
+            out.put_u64(0u64);
+
+            (out, pkt_num)
+        };
+
+        let mut payload = Vec::with_capacity(
+            7 + 21 + 3 + message.serialized_len() + padding.len() + POLY13055_MAC_LEN,
+        );
+        payload.extend_from_slice(
+            &Block::DateTime {
+                timestamp: R::time_since_epoch().as_secs() as u32,
+            }
+            .serialize(),
+        );
+        if let Some(address) = self.address.take() {
+            payload.extend_from_slice(&Block::Address { address }.serialize());
+        }
+        payload.extend_from_slice(&message.serialize());
+        payload.extend_from_slice(&Block::Padding { padding }.serialize());
+
+        // must succeed since all the parameters are controlled by us
+        ChaChaPoly::with_nonce(&intro_key, pkt_num as u64)
+            .encrypt_with_ad_new(&header, &mut payload)
+            .expect("to succeed");
+
+        // encrypt first 16 bytes of the long header
+        //
+        // https://geti2p.net/spec/ssu2#header-encryption-kdf
+        payload[payload.len() - 2 * IV_SIZE..]
+            .chunks(IV_SIZE)
+            .zip(header.chunks_mut(8usize))
+            .zip([intro_key, intro_key])
+            .for_each(|((chunk, header_chunk), key)| {
+                ChaCha::with_iv(
+                    key,
+                    TryInto::<[u8; IV_SIZE]>::try_into(chunk).expect("to succeed"),
+                )
+                .decrypt([0u8; 8])
+                .iter()
+                .zip(header_chunk.iter_mut())
+                .for_each(|(mask_byte, header_byte)| {
+                    *header_byte ^= mask_byte;
+                });
+            });
+
+        // encrypt last 16 bytes of the header
+        ChaCha::with_iv(intro_key, [0u8; IV_SIZE]).encrypt_ref(&mut header[16..32]);
+
+        let mut out = BytesMut::with_capacity(LONG_HEADER_LEN + payload.len());
+        out.put_slice(&header);
+        out.put_slice(&payload);
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::mock::MockRuntime;
+
+    #[test]
+    fn serialize_deserialize_peer_test_block() {
+        let messages = [
+            PeerTestMessage {
+                message_num: 1,
+                code: PeerTestCode::Accept,
+                router_hash: None,
+                nonce: 1337,
+                timestamp: 1338,
+                address: Some("127.0.0.1:8888".parse().unwrap()),
+                signature: vec![1u8; 64],
+            },
+            PeerTestMessage {
+                message_num: 2,
+                code: PeerTestCode::Accept,
+                router_hash: Some(RouterId::random()),
+                nonce: 1337,
+                timestamp: 1338,
+                address: Some("[::1]:8888".parse().unwrap()),
+                signature: vec![2u8; 64],
+            },
+            PeerTestMessage {
+                message_num: 4,
+                code: PeerTestCode::BobNoCharlieAvailable,
+                router_hash: None,
+                nonce: 1337,
+                timestamp: 1338,
+                address: Some("127.0.0.1:8888".parse().unwrap()),
+                signature: vec![3u8; 64],
+            },
+            PeerTestMessage {
+                message_num: 7,
+                code: PeerTestCode::Accept,
+                router_hash: None,
+                nonce: 1337,
+                timestamp: 1338,
+                address: Some("127.0.0.1:9999".parse().unwrap()),
+                signature: Vec::new(),
+            },
+        ];
+
+        for message in messages {
+            let serialized = message.serialize();
+
+            match Block::parse(&serialized).unwrap().as_slice() {
+                [Block::PeerTest { message: parsed }] => assert_eq!(parsed, &message),
+                _ => panic!("invalid block"),
+            }
+        }
+    }
+
+    #[test]
+    fn reserved_codes() {
+        assert_eq!(PeerTestCode::from_u8(5), PeerTestCode::BobUnspecified);
+        assert_eq!(PeerTestCode::from_u8(100), PeerTestCode::CharlieUnspecified);
+        assert_eq!(PeerTestCode::from_u8(200), PeerTestCode::Unspecified);
+
+        for code in 0..=255u8 {
+            assert_eq!(
+                PeerTestCode::from_u8(PeerTestCode::from_u8(code).as_u8()),
+                PeerTestCode::from_u8(code)
+            );
+        }
+    }
+
+    #[test]
+    fn build_and_parse_out_of_session_message() {
+        let message = PeerTestMessage {
+            message_num: 5,
+            code: PeerTestCode::Accept,
+            router_hash: None,
+            nonce: 0xdeadbeef,
+            timestamp: 1338,
+            address: Some("127.0.0.1:8888".parse().unwrap()),
+            signature: Vec::new(),
+        };
+        let mut pkt = PeerTestBuilder::default()
+            .with_intro_key([1u8; 32])
+            .with_address("127.0.0.1:8888".parse().unwrap())
+            .with_message(message.clone())
+            .build::<MockRuntime>()
+            .to_vec();
+
+        let mut reader = HeaderReader::new([1u8; 32], &mut pkt).unwrap();
+        let _dst_id = reader.dst_id();
+
+        let pkt_num = match reader.parse([1u8; 32]).unwrap() {
+            HeaderKind::PeerTest { net_id, pkt_num } => {
+                assert_eq!(net_id, 2u8);
+                pkt_num
+            }
+            _ => panic!("invalid type"),
+        };
+
+        let mut payload = pkt[LONG_HEADER_LEN..].to_vec();
+        ChaChaPoly::with_nonce(&[1u8; 32], pkt_num as u64)
+            .decrypt_with_ad(&pkt[..LONG_HEADER_LEN], &mut payload)
+            .unwrap();
+
+        let blocks = Block::parse(&payload).unwrap();
+        assert!(blocks.iter().any(|block| match block {
+            Block::Address { address } => address == &"127.0.0.1:8888".parse().unwrap(),
+            _ => false,
+        }));
+        assert!(blocks.iter().any(|block| match block {
+            Block::PeerTest { message: parsed } => parsed == &message,
+            _ => false,
+        }));
+    }
+}
@@ -0,0 +1,757 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! SSU2 relay blocks and out-of-session `HolePunch` messages.
+//!
+//! https://geti2p.net/spec/ssu2#introduction
+
+use crate::{
+    crypto::chachapoly::{ChaCha, ChaChaPoly},
+    primitives::RouterId,
+    runtime::Runtime,
+    transport::ssu2::message::*,
+};
+
+use bytes::{BufMut, BytesMut};
+use nom::{
+    bytes::complete::take,
+    error::{make_error, ErrorKind},
+    number::complete::{be_u16, be_u32, be_u64, be_u8},
+    Err, IResult,
+};
+use rand_core::RngCore;
+
+use alloc::{vec, vec::Vec};
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// Length of the router hash included in `RelayIntro`.
+const ROUTER_HASH_LEN: usize = 32usize;
+
+/// Length of the token included in an accepted `RelayResponse`.
+const TOKEN_LEN: usize = 8usize;
+
+/// Relay response code.
+///
+/// https://geti2p.net/spec/ssu2#relayresponse
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RelayCode {
+    /// Relay accepted.
+    #[default]
+    Accept,
+
+    /// Rejected by Bob, unspecified reason.
+    BobUnspecified,
+
+    /// Rejected by Bob, Charlie is banned.
+    BobCharlieBanned,
+
+    /// Rejected by Bob, limit exceeded.
+    BobLimitExceeded,
+
+    /// Rejected by Bob, signature failure.
+    BobSignatureFailure,
+
+    /// Rejected by Bob, relay tag not found.
+    BobRelayTagNotFound,
+
+    /// Rejected by Charlie, unspecified reason.
+    CharlieUnspecified,
+
+    /// Rejected by Charlie, unsupported address.
+    CharlieUnsupportedAddress,
+
+    /// Rejected by Charlie, limit exceeded.
+    CharlieLimitExceeded,
+
+    /// Rejected by Charlie, signature failure.
+    CharlieSignatureFailure,
+
+    /// Rejected by Charlie, Alice is already connected.
+    CharlieAliceConnected,
+
+    /// Rejected by Charlie, Alice is banned.
+    CharlieAliceBanned,
+
+    /// Rejected by Charlie, Alice is unknown.
+    CharlieAliceUnknown,
+
+    /// Rejected, unspecified reason.
+    Unspecified,
+}
+
+impl RelayCode {
+    /// Serialize [`RelayCode`].
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            Self::Accept => 0u8,
+            Self::BobUnspecified => 1u8,
+            Self::BobCharlieBanned => 2u8,
+            Self::BobLimitExceeded => 3u8,
+            Self::BobSignatureFailure => 4u8,
+            Self::BobRelayTagNotFound => 5u8,
+            Self::CharlieUnspecified => 64u8,
+            Self::CharlieUnsupportedAddress => 65u8,
+            Self::CharlieLimitExceeded => 66u8,
+            Self::CharlieSignatureFailure => 67u8,
+            Self::CharlieAliceConnected => 68u8,
+            Self::CharlieAliceBanned => 69u8,
+            Self::CharlieAliceUnknown => 70u8,
+            Self::Unspecified => 128u8,
+        }
+    }
+
+    /// Deserialize [`RelayCode`].
+    ///
+    /// Reserved codes are mapped to the unspecified rejection of the router that sent them.
+    pub fn from_u8(code: u8) -> Self {
+        match code {
+            0u8 => Self::Accept,
+            2u8 => Self::BobCharlieBanned,
+            3u8 => Self::BobLimitExceeded,
+            4u8 => Self::BobSignatureFailure,
+            5u8 => Self::BobRelayTagNotFound,
+            1u8..=63u8 => Self::BobUnspecified,
+            65u8 => Self::CharlieUnsupportedAddress,
+            66u8 => Self::CharlieLimitExceeded,
+            67u8 => Self::CharlieSignatureFailure,
+            68u8 => Self::CharlieAliceConnected,
+            69u8 => Self::CharlieAliceBanned,
+            70u8 => Self::CharlieAliceUnknown,
+            64u8..=127u8 => Self::CharlieUnspecified,
+            _ => Self::Unspecified,
+        }
+    }
+
+    /// Returns `true` if the relay was accepted.
+    pub fn is_accept(&self) -> bool {
+        core::matches!(self, Self::Accept)
+    }
+
+    /// Returns `true` if the relay was rejected by Bob.
+    pub fn is_bob_rejection(&self) -> bool {
+        (1u8..=63u8).contains(&self.as_u8())
+    }
+}
+
+/// Parse size-prefixed socket address from `input`.
+///
+/// Address size of zero is interpreted as a missing address.
+fn parse_socket_address(input: &[u8]) -> IResult<&[u8], Option<SocketAddr>> {
+    let (rest, address_size) = be_u8(input)?;
+
+    match address_size {
+        0 => Ok((rest, None)),
+        6 => {
+            let (rest, port) = be_u16(rest)?;
+            let (rest, address) = be_u32(rest)?;
+
+            Ok((
+                rest,
+                Some(SocketAddr::new(IpAddr::V4(Ipv4Addr::from(address)), port)),
+            ))
+        }
+        18 => {
+            let (rest, port) = be_u16(rest)?;
+            let (rest, address) = take(16usize)(rest)?;
+            let address = TryInto::<[u8; 16]>::try_into(address).expect("to succeed");
+
+            Ok((
+                rest,
+                Some(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(address)), port)),
+            ))
+        }
+        _ => Err(Err::Error(make_error(input, ErrorKind::Fail))),
+    }
+}
+
+/// Serialize size-prefixed socket address into `out`.
+fn put_socket_address(out: &mut BytesMut, address: Option<&SocketAddr>) {
+    match address {
+        None => out.put_u8(0u8),
+        Some(SocketAddr::V4(address)) => {
+            out.put_u8(6u8);
+            out.put_u16(address.port());
+            out.put_slice(&address.ip().octets());
+        }
+        Some(SocketAddr::V6(address)) => {
+            out.put_u8(18u8);
+            out.put_u16(address.port());
+            out.put_slice(&address.ip().octets());
+        }
+    }
+}
+
+/// Parse and validate protocol version from `input`.
+fn parse_version(input: &[u8]) -> IResult<&[u8], u8> {
+    let (rest, version) = be_u8(input)?;
+
+    if version != PROTOCOL_VERSION {
+        tracing::debug!(
+            target: LOG_TARGET,
+            ?version,
+            "unsupported relay version",
+        );
+        return Err(Err::Error(make_error(input, ErrorKind::Fail)));
+    }
+
+    Ok((rest, version))
+}
+
+/// Relay request, sent by Alice to Bob.
+///
+/// Also carried inside [`RelayIntro`] when Bob forwards the request to Charlie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayRequest {
+    /// Relay nonce.
+    pub nonce: u32,
+
+    /// Relay tag Charlie received from Bob.
+    pub relay_tag: u32,
+
+    /// Timestamp, seconds since UNIX epoch.
+    pub timestamp: u32,
+
+    /// Alice's socket address.
+    pub address: SocketAddr,
+
+    /// Alice's signature.
+    pub signature: Vec<u8>,
+}
+
+impl Default for RelayRequest {
+    fn default() -> Self {
+        Self {
+            nonce: 0u32,
+            relay_tag: 0u32,
+            timestamp: 0u32,
+            address: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0u16),
+            signature: Vec::new(),
+        }
+    }
+}
+
+impl RelayRequest {
+    /// Attempt to parse [`RelayRequest`] from `input`, excluding block type, size and flag.
+    fn parse_inner(input: &[u8]) -> IResult<&[u8], RelayRequest> {
+        let (rest, nonce) = be_u32(input)?;
+        let (rest, relay_tag) = be_u32(rest)?;
+        let (rest, timestamp) = be_u32(rest)?;
+        let (rest, _version) = parse_version(rest)?;
+        let (rest, address) = parse_socket_address(rest)?;
+        let address = address.ok_or_else(|| Err::Error(make_error(input, ErrorKind::Fail)))?;
+
+        Ok((
+            &[],
+            RelayRequest {
+                nonce,
+                relay_tag,
+                timestamp,
+                address,
+                signature: rest.to_vec(),
+            },
+        ))
+    }
+
+    /// Attempt to parse [`RelayRequest`] from `input`.
+    ///
+    /// `input` points to the size field of the block.
+    pub fn parse_frame(input: &[u8]) -> IResult<&[u8], RelayRequest> {
+        let (rest, size) = be_u16(input)?;
+        let (rest, block) = take(size)(rest)?;
+        let (block, _flag) = be_u8(block)?;
+        let (_, message) = Self::parse_inner(block)?;
+
+        Ok((rest, message))
+    }
+
+    /// Get the message data of the relay request which is covered by the signature.
+    pub fn data(&self) -> BytesMut {
+        let mut out = BytesMut::with_capacity(32usize);
+
+        out.put_u32(self.nonce);
+        out.put_u32(self.relay_tag);
+        out.put_u32(self.timestamp);
+        out.put_u8(PROTOCOL_VERSION);
+        put_socket_address(&mut out, Some(&self.address));
+
+        out
+    }
+
+    /// Get the bytes that are signed by Alice.
+    pub fn signed_data(&self, bob: &RouterId, charlie: &RouterId) -> Vec<u8> {
+        let data = self.data();
+        let mut out = Vec::with_capacity(16 + 2 * ROUTER_HASH_LEN + data.len());
+
+        out.extend_from_slice(b"RelayRequestData");
+        out.extend_from_slice(&bob.to_vec());
+        out.extend_from_slice(&charlie.to_vec());
+        out.extend_from_slice(&data);
+
+        out
+    }
+
+    /// Get serialized length of the [`RelayRequest`] when placed inside a block.
+    pub fn serialized_len(&self) -> usize {
+        1usize // flag
+            + self.data().len()
+            + self.signature.len()
+    }
+
+    /// Serialize [`RelayRequest`] into a `RelayRequest` block.
+    pub fn serialize(&self) -> BytesMut {
+        let mut out = BytesMut::with_capacity(3usize + self.serialized_len());
+
+        out.put_u8(BlockType::RelayRequest.as_u8());
+        out.put_u16(self.serialized_len() as u16);
+        out.put_u8(0u8); // flag
+        out.put_slice(&self.data());
+        out.put_slice(&self.signature);
+
+        out
+    }
+}
+
+/// Relay intro, sent by Bob to Charlie.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RelayIntro {
+    /// Router ID of Alice.
+    pub alice: RouterId,
+
+    /// Relay request received from Alice.
+    pub request: RelayRequest,
+}
+
+impl RelayIntro {
+    /// Attempt to parse [`RelayIntro`] from `input`.
+    ///
+    /// `input` points to the size field of the block.
+    pub fn parse_frame(input: &[u8]) -> IResult<&[u8], RelayIntro> {
+        let (rest, size) = be_u16(input)?;
+        let (rest, block) = take(size)(rest)?;
+        let (block, _flag) = be_u8(block)?;
+        let (block, alice) = take(ROUTER_HASH_LEN)(block)?;
+        let (_, request) = RelayRequest::parse_inner(block)?;
+
+        Ok((
+            rest,
+            RelayIntro {
+                alice: RouterId::from(alice),
+                request,
+            },
+        ))
+    }
+
+    /// Get serialized length of the [`RelayIntro`] when placed inside a block.
+    pub fn serialized_len(&self) -> usize {
+        ROUTER_HASH_LEN + self.request.serialized_len()
+    }
+
+    /// Serialize [`RelayIntro`] into a `RelayIntro` block.
+    pub fn serialize(&self) -> BytesMut {
+        let mut out = BytesMut::with_capacity(3usize + self.serialized_len());
+
+        out.put_u8(BlockType::RelayIntro.as_u8());
+        out.put_u16(self.serialized_len() as u16);
+        out.put_u8(0u8); // flag
+        out.put_slice(&self.alice.to_vec());
+        out.put_slice(&self.request.data());
+        out.put_slice(&self.request.signature);
+
+        out
+    }
+}
+
+/// Relay response, sent by Charlie to Bob and relayed by Bob to Alice.
+///
+/// Also sent by Bob to Alice if Bob rejects the relay request.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RelayResponse {
+    /// Response code.
+    pub code: RelayCode,
+
+    /// Relay nonce.
+    pub nonce: u32,
+
+    /// Timestamp, seconds since UNIX epoch.
+    pub timestamp: u32,
+
+    /// Charlie's socket address.
+    ///
+    /// `None` if Bob rejected the relay request.
+    pub address: Option<SocketAddr>,
+
+    /// Signature of Charlie or Bob, if the relay was rejected by Bob.
+    pub signature: Vec<u8>,
+
+    /// Token Alice uses in `SessionRequest` sent to Charlie.
+    ///
+    /// Only present if Charlie accepted the relay request.
+    pub token: Option<u64>,
+}
+
+impl RelayResponse {
+    /// Attempt to parse [`RelayResponse`] from `input`.
+    ///
+    /// `input` points to the size field of the block.
+    pub fn parse_frame(input: &[u8]) -> IResult<&[u8], RelayResponse> {
+        let (rest, size) = be_u16(input)?;
+        let (rest, block) = take(size)(rest)?;
+        let (block, _flag) = be_u8(block)?;
+        let (block, code) = be_u8(block)?;
+        let (block, nonce) = be_u32(block)?;
+        let (block, timestamp) = be_u32(block)?;
+        let (block, _version) = parse_version(block)?;
+        let (block, address) = parse_socket_address(block)?;
+
+        let code = RelayCode::from_u8(code);
+        let (signature, token) = match code.is_accept() {
+            true => {
+                if block.len() < TOKEN_LEN {
+                    return Err(Err::Error(make_error(input, ErrorKind::Fail)));
+                }
+                let (signature, token) = block.split_at(block.len() - TOKEN_LEN);
+                let (_, token) = be_u64(token)?;
+
+                (signature, Some(token))
+            }
+            false => (block, None),
+        };
+
+        Ok((
+            rest,
+            RelayResponse {
+                code,
+                nonce,
+                timestamp,
+                address,
+                signature: signature.to_vec(),
+                token,
+            },
+        ))
+    }
+
+    /// Get the message data of the relay response which is covered by the signature.
+    pub fn data(&self) -> BytesMut {
+        let mut out = BytesMut::with_capacity(28usize);
+
+        out.put_u32(self.nonce);
+        out.put_u32(self.timestamp);
+        out.put_u8(PROTOCOL_VERSION);
+        put_socket_address(&mut out, self.address.as_ref());
+
+        out
+    }
+
+    /// Get the bytes that are signed by Charlie, or by Bob if he rejected the relay request.
+    pub fn signed_data(&self, bob: &RouterId) -> Vec<u8> {
+        let data = self.data();
+        let mut out = Vec::with_capacity(16 + ROUTER_HASH_LEN + data.len());
+
+        out.extend_from_slice(b"RelayAgreementOK");
+        out.extend_from_slice(&bob.to_vec());
+        out.extend_from_slice(&data);
+
+        out
+    }
+
+    /// Get serialized length of the [`RelayResponse`] when placed inside a block.
+    pub fn serialized_len(&self) -> usize {
+        2usize // flag + code
+            + self.data().len()
+            + self.signature.len()
+            + self.token.map_or(0usize, |_| TOKEN_LEN)
+    }
+
+    /// Serialize [`RelayResponse`] into a `RelayResponse` block.
+    pub fn serialize(&self) -> BytesMut {
+        let mut out = BytesMut::with_capacity(3usize + self.serialized_len());
+
+        out.put_u8(BlockType::RelayResponse.as_u8());
+        out.put_u16(self.serialized_len() as u16);
+        out.put_u8(0u8); // flag
+        out.put_u8(self.code.as_u8());
+        out.put_slice(&self.data());
+        out.put_slice(&self.signature);
+
+        if let Some(token) = self.token {
+            out.put_u64(token);
+        }
+
+        out
+    }
+}
+
+/// Builder for out-of-session `HolePunch` messages, sent by Charlie to Alice.
+pub struct HolePunchBuilder {
+    /// Alice's socket address, as seen by Charlie.
+    address: Option<SocketAddr>,
+
+    /// Alice's intro key.
+    intro_key: Option<[u8; 32]>,
+
+    /// Network ID.
+    ///
+    /// Defaults to 2.
+    net_id: u8,
+
+    /// Relay response.
+    response: Option<RelayResponse>,
+}
+
+impl Default for HolePunchBuilder {
+    fn default() -> Self {
+        Self {
+            address: None,
+            intro_key: None,
+            net_id: 2u8,
+            response: None,
+        }
+    }
+}
+
+impl HolePunchBuilder {
+    /// Specify Alice's socket address.
+    pub fn with_address(mut self, address: SocketAddr) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Specify Alice's intro key.
+    pub fn with_intro_key(mut self, intro_key: [u8; 32]) -> Self {
+        self.intro_key = Some(intro_key);
+        self
+    }
+
+    /// Specify network ID.
+    pub fn with_net_id(mut self, net_id: u8) -> Self {
+        self.net_id = net_id;
+        self
+    }
+
+    /// Specify relay response.
+    pub fn with_response(mut self, response: RelayResponse) -> Self {
+        self.response = Some(response);
+        self
+    }
+
+    /// Build [`HolePunchBuilder`] into a byte vector.
+    ///
+    /// Connection IDs of the packet are derived from the relay nonce.
+    pub fn build<R: Runtime>(mut self) -> BytesMut {
+        let intro_key = self.intro_key.take().expect("to exist");
+        let response = self.response.take().expect("to exist");
+        let mut rng = R::rng();
+        let padding = {
+            let padding_len = rng.next_u32() % MAX_PADDING as u32 + 8;
+            let mut padding = vec![0u8; padding_len as usize];
+            rng.fill_bytes(&mut padding);
+
+            padding
+        };
+
+        let (mut header, pkt_num) = {
+            let mut out = BytesMut::with_capacity(LONG_HEADER_LEN);
+            let pkt_num = rng.next_u32();
+            let nonce = response.nonce as u64;
+
+            out.put_u64_le(nonce | ((!nonce & 0xffffffff) << 32));
+            out.put_u32(pkt_num);
+            out.put_u8(*MessageType::HolePunch);
+            out.put_u8(PROTOCOL_VERSION);
+            out.put_u8(self.net_id);
+            out.put_u8(0u8); // flag
+            out.put_u64_le((!nonce & 0xffffffff) | (nonce << 32));
+            out.put_u64(0u64);
+
+            (out, pkt_num)
+        };
+
+        let mut payload = Vec::with_capacity(
+            7 + 21 + 3 + response.serialized_len() + padding.len() + POLY13055_MAC_LEN,
+        );
+        payload.extend_from_slice(
+            &Block::DateTime {
+                timestamp: R::time_since_epoch().as_secs() as u32,
+            }
+            .serialize(),
+        );
+        if let Some(address) = self.address.take() {
+            payload.extend_from_slice(&Block::Address { address }.serialize());
+        }
+        payload.extend_from_slice(&response.serialize());
+        payload.extend_from_slice(&Block::Padding { padding }.serialize());
+
+        // must succeed since all the parameters are controlled by us
+        ChaChaPoly::with_nonce(&intro_key, pkt_num as u64)
+            .encrypt_with_ad_new(&header, &mut payload)
+            .expect("to succeed");
+
+        // encrypt first 16 bytes of the long header
+        //
+        // https://geti2p.net/spec/ssu2#header-encryption-kdf
+        payload[payload.len() - 2 * IV_SIZE..]
+            .chunks(IV_SIZE)
+            .zip(header.chunks_mut(8usize))
+            .zip([intro_key, intro_key])
+            .for_each(|((chunk, header_chunk), key)| {
+                ChaCha::with_iv(
+                    key,
+                    TryInto::<[u8; IV_SIZE]>::try_into(chunk).expect("to succeed"),
+                )
+                .decrypt([0u8; 8])
+                .iter()
+                .zip(header_chunk.iter_mut())
+                .for_each(|(mask_byte, header_byte)| {
+                    *header_byte ^= mask_byte;
+                });
+            });
+
+        // encrypt last 16 bytes of the header
+        ChaCha::with_iv(intro_key, [0u8; IV_SIZE]).encrypt_ref(&mut header[16..32]);
+
+        let mut out = BytesMut::with_capacity(LONG_HEADER_LEN + payload.len());
+        out.put_slice(&header);
+        out.put_slice(&payload);
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::mock::MockRuntime;
+
+    #[test]
+    fn serialize_deserialize_relay_blocks() {
+        let request = RelayRequest {
+            nonce: 1337,
+            relay_tag: 1338,
+            timestamp: 1339,
+            address: "127.0.0.1:8888".parse().unwrap(),
+            signature: vec![1u8; 64],
+        };
+
+        match Block::parse(&request.serialize()).unwrap().as_slice() {
+            [Block::RelayRequest { message }] => assert_eq!(message, &request),
+            _ => panic!("invalid block"),
+        }
+
+        let intro = RelayIntro {
+            alice: RouterId::random(),
+            request: RelayRequest {
+                address: "[::1]:8888".parse().unwrap(),
+                ..request
+            },
+        };
+
+        match Block::parse(&intro.serialize()).unwrap().as_slice() {
+            [Block::RelayIntro { message }] => assert_eq!(message, &intro),
+            _ => panic!("invalid block"),
+        }
+
+        let responses = [
+            RelayResponse {
+                code: RelayCode::Accept,
+                nonce: 1337,
+                timestamp: 1338,
+                address: Some("127.0.0.1:9999".parse().unwrap()),
+                signature: vec![2u8; 64],
+                token: Some(0xdeadbeef),
+            },
+            RelayResponse {
+                code: RelayCode::BobRelayTagNotFound,
+                nonce: 1337,
+                timestamp: 1338,
+                address: None,
+                signature: vec![3u8; 64],
+                token: None,
+            },
+        ];
+
+        for response in responses {
+            match Block::parse(&response.serialize()).unwrap().as_slice() {
+                [Block::RelayResponse { message }] => assert_eq!(message, &response),
+                _ => panic!("invalid block"),
+            }
+        }
+    }
+
+    #[test]
+    fn reserved_codes() {
+        assert_eq!(RelayCode::from_u8(6), RelayCode::BobUnspecified);
+        assert_eq!(RelayCode::from_u8(100), RelayCode::CharlieUnspecified);
+        assert_eq!(RelayCode::from_u8(200), RelayCode::Unspecified);
+        assert!(RelayCode::BobRelayTagNotFound.is_bob_rejection());
+        assert!(!RelayCode::CharlieAliceUnknown.is_bob_rejection());
+
+        for code in 0..=255u8 {
+            assert_eq!(
+                RelayCode::from_u8(RelayCode::from_u8(code).as_u8()),
+                RelayCode::from_u8(code)
+            );
+        }
+    }
+
+    #[test]
+    fn build_and_parse_hole_punch() {
+        let response = RelayResponse {
+            code: RelayCode::Accept,
+            nonce: 0xdeadbeef,
+            timestamp: 1338,
+            address: Some("127.0.0.1:9999".parse().unwrap()),
+            signature: vec![2u8; 64],
+            token: Some(1337),
+        };
+        let mut pkt = HolePunchBuilder::default()
+            .with_intro_key([1u8; 32])
+            .with_address("127.0.0.1:8888".parse().unwrap())
+            .with_response(response.clone())
+            .build::<MockRuntime>()
+            .to_vec();
+
+        let mut reader = HeaderReader::new([1u8; 32], &mut pkt).unwrap();
+        assert_eq!(
+            reader.dst_id(),
+            0xdeadbeef | (!0xdeadbeefu64 & 0xffffffff) << 32
+        );
+
+        let pkt_num = match reader.parse([1u8; 32]).unwrap() {
+            HeaderKind::HolePunch { net_id, pkt_num } => {
+                assert_eq!(net_id, 2u8);
+                pkt_num
+            }
+            kind => panic!("invalid header kind: {kind:?}"),
+        };
+
+        let mut payload = pkt[32..].to_vec();
+        ChaChaPoly::with_nonce(&[1u8; 32], pkt_num as u64)
+            .decrypt_with_ad(&pkt[..32], &mut payload)
+            .unwrap();
+
+        let blocks = Block::parse(&payload).unwrap();
+        assert!(blocks.iter().any(|block| match block {
+            Block::Address { address } => address == &"127.0.0.1:8888".parse().unwrap(),
+            _ => false,
+        }));
+        assert!(blocks.iter().any(|block| match block {
+            Block::RelayResponse { message } => message == &response,
+            _ => false,
+        }));
+    }
+}
@@ -29,15 +29,17 @@ use crate::{
 
 use futures::{Stream, StreamExt};
 
-use alloc::{format, vec::Vec};
+use alloc::{vec, vec::Vec};
 use core::{
-    net::{IpAddr, Ipv4Addr, SocketAddr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     pin::Pin,
     task::{Context, Poll},
 };
 
 mod message;
 mod metrics;
+mod peer_test;
+mod relay;
 mod session;
 mod socket;
 
@@ -67,17 +69,20 @@ pub struct Ssu2Context<R: Runtime> {
     /// SSU configuration.
     config: Ssu2Config,
 
-    /// UDP socket.
-    socket: R::UdpSocket,
+    /// IPv4 UDP socket, if IPv4 was enabled.
+    ipv4_socket: Option<R::UdpSocket>,
 
-    /// Socket address.
-    socket_address: SocketAddr,
+    /// IPv6 UDP socket, if IPv6 was enabled.
+    ipv6_socket: Option<R::UdpSocket>,
+
+    /// Port where the sockets are bound to.
+    port: u16,
 }
 
 impl<R: Runtime> Ssu2Context<R> {
     /// Get the port where [`Ssu2Socket`] is bound to.
     pub fn port(&self) -> u16 {
-        self.socket_address.port()
+        self.port
     }
 
     /// Get copy of [`Ssu2Config`].
@@ -101,21 +106,25 @@ impl<R: Runtime> Ssu2Transport<R> {
         subsystem_handle: SubsystemHandle,
     ) -> Self {
         let Ssu2Context {
-            socket_address,
-            socket,
             config,
+            ipv4_socket,
+            ipv6_socket,
+            port,
         } = context;
 
         tracing::info!(
             target: LOG_TARGET,
-            listen_address = ?socket_address,
+            ?port,
+            ipv4 = ?ipv4_socket.is_some(),
+            ipv6 = ?ipv6_socket.is_some(),
             ?allow_local,
             "starting ssu2",
         );
 
         Self {
             socket: Ssu2Socket::<R>::new(
-                socket,
+                ipv4_socket,
+                ipv6_socket,
                 StaticPrivateKey::from(config.static_key),
                 config.intro_key,
                 subsystem_handle,
@@ -129,18 +138,43 @@ impl<R: Runtime> Ssu2Transport<R> {
         metrics::register_metrics(metrics)
     }
 
+    /// Bind UDP socket to `address` and return the socket and the port it was bound to.
+    async fn bind_socket(address: SocketAddr) -> crate::Result<(R::UdpSocket, u16)> {
+        let socket = R::UdpSocket::bind(address).await.ok_or_else(|| {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?address,
+                "ssu2 port in use, select another port for the transport",
+            );
+
+            Error::Connection(ConnectionError::BindFailure)
+        })?;
+
+        let socket_address = socket.local_address().ok_or_else(|| {
+            tracing::warn!(
+                target: LOG_TARGET,
+                "failed to get local address of the ssu2 listener",
+            );
+
+            Error::Connection(ConnectionError::BindFailure)
+        })?;
+
+        Ok((socket, socket_address.port()))
+    }
+
     /// Initialize [`SsU2Transport`].
     ///
-    /// If SSU2 has been enabled, create a router address using the configuration that was provided
-    /// and bind a UDP socket to the port that was specified.
+    /// If SSU2 has been enabled, create router addresses using the configuration that was provided
+    /// and bind UDP sockets for the enabled address families to the port that was specified. If
+    /// both IPv4 and IPv6 are enabled, the sockets are bound to the same port.
     ///
-    /// Returns a [`RouterAddress`] of the transport and an [`SsU2Context`] that needs to be passed
-    /// to [`SsU2Transport::new()`] when constructing the transport.
+    /// Returns the [`RouterAddress`]es of the transport and an [`SsU2Context`] that needs to be
+    /// passed to [`SsU2Transport::new()`] when constructing the transport.
     pub async fn initialize(
         config: Option<Ssu2Config>,
-    ) -> crate::Result<(Option<Ssu2Context<R>>, Option<RouterAddress>)> {
+    ) -> crate::Result<(Option<Ssu2Context<R>>, Vec<RouterAddress>)> {
         let Some(config) = config else {
-            return Ok((None, None));
+            return Ok((None, Vec::new()));
         };
 
         tracing::warn!(
@@ -148,60 +182,73 @@ impl<R: Runtime> Ssu2Transport<R> {
             "ssu2 support is experimental and not recommend for general use",
         );
 
-        let socket =
-            R::UdpSocket::bind(format!("0.0.0.0:{}", config.port).parse().expect("to succeed"))
-                .await
-                .ok_or_else(|| {
-                    tracing::warn!(
-                        target: LOG_TARGET,
-                        port = %config.port,
-                        "ssu2 port in use, select another port for the transport",
-                    );
-
-                    Error::Connection(ConnectionError::BindFailure)
-                })?;
-
-        let socket_address = socket.local_address().ok_or_else(|| {
+        if !config.ipv4 && !config.ipv6 {
             tracing::warn!(
                 target: LOG_TARGET,
-                "failed to get local address of the ssu2 listener",
+                "ssu2 enabled but both ipv4 and ipv6 are disabled",
             );
+            return Err(Error::Connection(ConnectionError::BindFailure));
+        }
 
-            Error::Connection(ConnectionError::BindFailure)
-        })?;
+        // if the port is selected by the os, bind the ipv6 socket to the same port
+        let (ipv4_socket, port) = match config.ipv4 {
+            true => Self::bind_socket(SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), config.port))
+                .await
+                .map(|(socket, port)| (Some(socket), port))?,
+            false => (None, config.port),
+        };
+        let (ipv6_socket, port) = match config.ipv6 {
+            true => Self::bind_socket(SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), port))
+                .await
+                .map(|(socket, port)| (Some(socket), port))?,
+            false => (None, port),
+        };
 
-        let address = match (config.publish, config.host) {
-            (true, Some(host)) => RouterAddress::new_published_ssu2(
-                config.static_key,
-                config.intro_key,
-                socket_address.port(),
-                host,
-            ),
-            (true, None) => {
+        let hosts = [
+            config.ipv4_host.filter(|_| config.ipv4).map(IpAddr::V4),
+            config.ipv6_host.filter(|_| config.ipv6).map(IpAddr::V6),
+        ];
+
+        let addresses = match (config.publish, hosts) {
+            (true, [None, None]) => {
                 tracing::debug!(
                     target: LOG_TARGET,
                     "ssu2 requested to be published but no host provided",
                 );
-                RouterAddress::new_unpublished_ssu2(
-                    config.static_key,
-                    config.intro_key,
-                    socket_address.port(),
-                )
+
+                vec![
+                    RouterAddress::new_unpublished_ssu2(config.static_key, config.intro_key, port)
+                        .with_address_families(config.ipv4, config.ipv6),
+                ]
+            }
+            (true, hosts) => hosts
+                .into_iter()
+                .flatten()
+                .map(|host| {
+                    RouterAddress::new_published_ssu2(
+                        config.static_key,
+                        config.intro_key,
+                        port,
+                        host,
+                    )
+                })
+                .collect(),
+            (false, _) => {
+                vec![
+                    RouterAddress::new_unpublished_ssu2(config.static_key, config.intro_key, port)
+                        .with_address_families(config.ipv4, config.ipv6),
+                ]
             }
-            (_, _) => RouterAddress::new_unpublished_ssu2(
-                config.static_key,
-                config.intro_key,
-                socket_address.port(),
-            ),
         };
 
         Ok((
             Some(Ssu2Context {
                 config,
-                socket,
-                socket_address,
+                ipv4_socket,
+                ipv6_socket,
+                port,
             }),
-            Some(address),
+            addresses,
         ))
     }
 }
@@ -232,7 +279,9 @@ impl<R: Runtime> Stream for Ssu2Transport<R> {
 mod tests {
     use super::*;
     use crate::{
-        crypto::SigningPrivateKey, events::EventManager, profile::ProfileStorage,
+        crypto::SigningPrivateKey,
+        events::{EventManager, FirewallStatus},
+        profile::ProfileStorage,
         runtime::mock::MockRuntime,
     };
     use bytes::Bytes;
@@ -244,7 +293,10 @@ mod tests {
         let (_event_mgr, _event_subscriber, event_handle) = EventManager::new(None);
         let (ctx1, address1) = Ssu2Transport::<MockRuntime>::initialize(Some(Ssu2Config {
             port: 0u16,
-            host: Some("127.0.0.1".parse().unwrap()),
+            ipv4_host: Some("127.0.0.1".parse().unwrap()),
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             static_key: [0xaa; 32],
             intro_key: [0xbb; 32],
@@ -253,7 +305,10 @@ mod tests {
         .unwrap();
         let (ctx2, address2) = Ssu2Transport::<MockRuntime>::initialize(Some(Ssu2Config {
             port: 0u16,
-            host: Some("127.0.0.1".parse().unwrap()),
+            ipv4_host: Some("127.0.0.1".parse().unwrap()),
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             static_key: [0xcc; 32],
             intro_key: [0xdd; 32],
@@ -271,7 +326,6 @@ mod tests {
         );
         let router_info1 = RouterInfo::new::<MockRuntime>(
             &Default::default(),
-            None,
             address1,
             &static1,
             &signing1,
@@ -279,7 +333,6 @@ mod tests {
         );
         let router_info2 = RouterInfo::new::<MockRuntime>(
             &Default::default(),
-            None,
             address2,
             &static2,
             &signing2,
@@ -364,7 +417,10 @@ mod tests {
         let (_event_mgr, _event_subscriber, event_handle) = EventManager::new(None);
         let (ctx1, address1) = Ssu2Transport::<MockRuntime>::initialize(Some(Ssu2Config {
             port: 0u16,
-            host: Some("127.0.0.1".parse().unwrap()),
+            ipv4_host: Some("127.0.0.1".parse().unwrap()),
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             static_key: [0xaa; 32],
             intro_key: [0xbb; 32],
@@ -373,7 +429,10 @@ mod tests {
         .unwrap();
         let (ctx2, address2) = Ssu2Transport::<MockRuntime>::initialize(Some(Ssu2Config {
             port: 0u16,
-            host: Some("127.0.0.1".parse().unwrap()),
+            ipv4_host: Some("127.0.0.1".parse().unwrap()),
+            ipv6_host: None,
+            ipv4: true,
+            ipv6: false,
             publish: true,
             static_key: [0xcc; 32],
             intro_key: [0xdd; 32],
@@ -391,7 +450,6 @@ mod tests {
         );
         let router_info1 = RouterInfo::new::<MockRuntime>(
             &Default::default(),
-            None,
             address1,
             &static1,
             &signing1,
@@ -399,7 +457,6 @@ mod tests {
         );
         let router_info2 = RouterInfo::new::<MockRuntime>(
             &Default::default(),
-            None,
             address2,
             &static2,
             &signing2,
@@ -467,4 +524,236 @@ mod tests {
             Ok(()) => {}
         }
     }
+
+    #[tokio::test]
+    async fn connect_ssu2_ipv6() {
+        let (_event_mgr, _event_subscriber, event_handle) = EventManager::new(None);
+        let (ctx1, address1) = Ssu2Transport::<MockRuntime>::initialize(Some(Ssu2Config {
+            port: 0u16,
+            ipv4_host: None,
+            ipv6_host: Some("::1".parse().unwrap()),
+            ipv4: false,
+            ipv6: true,
+            publish: true,
+            static_key: [0xaa; 32],
+            intro_key: [0xbb; 32],
+        }))
+        .await
+        .unwrap();
+        let (ctx2, address2) = Ssu2Transport::<MockRuntime>::initialize(Some(Ssu2Config {
+            port: 0u16,
+            ipv4_host: None,
+            ipv6_host: Some("::1".parse().unwrap()),
+            ipv4: false,
+            ipv6: true,
+            publish: true,
+            static_key: [0xcc; 32],
+            intro_key: [0xdd; 32],
+        }))
+        .await
+        .unwrap();
+
+        let (static1, signing1) = (
+            StaticPrivateKey::random(MockRuntime::rng()),
+            SigningPrivateKey::random(MockRuntime::rng()),
+        );
+        let (static2, signing2) = (
+            StaticPrivateKey::random(MockRuntime::rng()),
+            SigningPrivateKey::random(MockRuntime::rng()),
+        );
+        let router_info1 = RouterInfo::new::<MockRuntime>(
+            &Default::default(),
+            address1,
+            &static1,
+            &signing1,
+            false,
+        );
+        let router_info2 = RouterInfo::new::<MockRuntime>(
+            &Default::default(),
+            address2,
+            &static2,
+            &signing2,
+            false,
+        );
+        let (handle1, _event_rx1) = {
+            let (tx, rx) = channel(64);
+            let mut handle = SubsystemHandle::new();
+            handle.register_subsystem(tx);
+
+            (handle, rx)
+        };
+        let (handle2, _event_rx2) = {
+            let (tx, rx) = channel(64);
+            let mut handle = SubsystemHandle::new();
+            handle.register_subsystem(tx);
+
+            (handle, rx)
+        };
+
+        let mut transport1 = Ssu2Transport::<MockRuntime>::new(
+            ctx1.unwrap(),
+            true,
+            RouterContext::new(
+                MockRuntime::register_metrics(Vec::new(), None),
+                ProfileStorage::<MockRuntime>::new(&[], &[]),
+                router_info1.identity.id(),
+                Bytes::from(router_info1.serialize(&signing1)),
+                static1,
+                signing1,
+                2u8,
+                event_handle.clone(),
+            ),
+            handle1,
+        );
+        let mut transport2 = Ssu2Transport::<MockRuntime>::new(
+            ctx2.unwrap(),
+            true,
+            RouterContext::new(
+                MockRuntime::register_metrics(Vec::new(), None),
+                ProfileStorage::<MockRuntime>::new(&[], &[]),
+                router_info2.identity.id(),
+                Bytes::from(router_info2.serialize(&signing2)),
+                static2,
+                signing2,
+                2u8,
+                event_handle.clone(),
+            ),
+            handle2,
+        );
+        tokio::spawn(async move {
+            loop {
+                match transport2.next().await.unwrap() {
+                    TransportEvent::ConnectionEstablished { router_id, .. } =>
+                        transport2.accept(&router_id),
+                    _ => {}
+                }
+            }
+        });
+
+        transport1.connect(router_info2);
+        let future = async move {
+            loop {
+                match transport1.next().await.unwrap() {
+                    TransportEvent::ConnectionEstablished { router_id, .. } => {
+                        transport1.accept(&router_id);
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        };
+
+        match tokio::time::timeout(Duration::from_secs(15), future).await {
+            Err(_) => panic!("timeout"),
+            Ok(()) => {}
+        }
+    }
+
+    #[tokio::test]
+    async fn peer_test() {
+        let (_event_mgr, _event_subscriber, event_handle) = EventManager::new(None);
+
+        let mut routers = Vec::new();
+        for (static_key, intro_key) in [(0x01, 0x02), (0x03, 0x04), (0x05, 0x06)] {
+            let (ctx, address) = Ssu2Transport::<MockRuntime>::initialize(Some(Ssu2Config {
+                port: 0u16,
+                ipv4_host: Some("127.0.0.1".parse().unwrap()),
+                ipv6_host: None,
+                ipv4: true,
+                ipv6: false,
+                publish: true,
+                static_key: [static_key; 32],
+                intro_key: [intro_key; 32],
+            }))
+            .await
+            .unwrap();
+
+            let (static_key, signing_key) = (
+                StaticPrivateKey::random(MockRuntime::rng()),
+                SigningPrivateKey::random(MockRuntime::rng()),
+            );
+            let router_info = RouterInfo::new::<MockRuntime>(
+                &Default::default(),
+                address,
+                &static_key,
+                &signing_key,
+                false,
+            );
+            let (handle, event_rx) = {
+                let (tx, rx) = channel(64);
+                let mut handle = SubsystemHandle::new();
+                handle.register_subsystem(tx);
+
+                (handle, rx)
+            };
+
+            let transport = Ssu2Transport::<MockRuntime>::new(
+                ctx.unwrap(),
+                true,
+                RouterContext::new(
+                    MockRuntime::register_metrics(Vec::new(), None),
+                    ProfileStorage::<MockRuntime>::new(&[], &[]),
+                    router_info.identity.id(),
+                    Bytes::from(router_info.serialize(&signing_key)),
+                    static_key,
+                    signing_key,
+                    2u8,
+                    event_handle.clone(),
+                ),
+                handle,
+            );
+
+            routers.push((transport, router_info, event_rx));
+        }
+
+        let (mut charlie, _charlie_info, _charlie_rx) = routers.pop().unwrap();
+        let (mut bob, bob_info, _bob_rx) = routers.pop().unwrap();
+        let (mut alice, _alice_info, _alice_rx) = routers.pop().unwrap();
+
+        tokio::spawn(async move {
+            while let Some(event) = bob.next().await {
+                if let TransportEvent::ConnectionEstablished { router_id, .. } = event {
+                    bob.accept(&router_id);
+                }
+            }
+        });
+
+        // connect charlie to bob so bob has a router he can relay alice's peer test to
+        charlie.connect(bob_info.clone());
+        let future = async {
+            while let Some(event) = charlie.next().await {
+                if let TransportEvent::ConnectionEstablished { router_id, .. } = event {
+                    charlie.accept(&router_id);
+                    break;
+                }
+            }
+        };
+
+        match tokio::time::timeout(Duration::from_secs(15), future).await {
+            Err(_) => panic!("timeout"),
+            Ok(()) => {}
+        }
+
+        tokio::spawn(async move { while charlie.next().await.is_some() {} });
+
+        // connect alice to bob which starts a peer test
+        alice.connect(bob_info);
+        let future = async move {
+            while let Some(event) = alice.next().await {
+                match event {
+                    TransportEvent::ConnectionEstablished { router_id, .. } =>
+                        alice.accept(&router_id),
+                    TransportEvent::FirewallStatusChanged { status } => return status,
+                    _ => {}
+                }
+            }
+
+            panic!("transport exited");
+        };
+
+        match tokio::time::timeout(Duration::from_secs(15), future).await {
+            Err(_) => panic!("timeout"),
+            Ok(status) => assert_eq!(status, FirewallStatus::Ok),
+        }
+    }
 }
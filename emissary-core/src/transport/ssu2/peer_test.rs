@@ -0,0 +1,1652 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! SSU2 peer test implementation.
+//!
+//! The router can act in any of the three roles of a peer test:
+//!  * Alice, the router whose reachability is tested
+//!  * Bob, the router which relays the peer test between Alice and Charlie
+//!  * Charlie, the router which tests Alice's reachability
+//!
+//! Messages 1 - 4 are sent inside active sessions, messages 5 - 7 are sent out-of-session between
+//! Alice and Charlie.
+//!
+//! https://geti2p.net/spec/ssu2#peer-test
+
+use crate::{
+    crypto::chachapoly::ChaChaPoly,
+    events::FirewallStatus,
+    primitives::{RouterId, RouterInfo},
+    router::context::RouterContext,
+    runtime::{Instant, Runtime},
+    transport::ssu2::{
+        message::{
+            peer_test::{PeerTestBuilder, PeerTestCode, PeerTestMessage},
+            Block,
+        },
+        Packet,
+    },
+};
+
+use bytes::Bytes;
+use futures::{FutureExt, Stream};
+use hashbrown::HashMap;
+use rand_core::RngCore;
+use thingbuf::mpsc::{channel, Receiver, Sender};
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::ssu2::peer-test";
+
+/// Command channel size.
+const CMD_CHANNEL_SIZE: usize = 16usize;
+
+/// Event channel size.
+const EVENT_CHANNEL_SIZE: usize = 256usize;
+
+/// Long header length.
+const LONG_HEADER_LEN: usize = 32usize;
+
+/// Maintenance interval.
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long is message 5 waited for before message 6 is sent to Charlie.
+const MESSAGE_5_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Peer test timeout.
+const PEER_TEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// How long is a relayed peer test kept in memory.
+const RELAYED_TEST_EXPIRATION: Duration = Duration::from_secs(60);
+
+/// Retry interval for a peer test if the firewall status is unknown.
+const RETRY_INTERVAL: Duration = Duration::from_secs(2 * 60);
+
+/// Interval for retesting the firewall status once it's known.
+const RETEST_INTERVAL: Duration = Duration::from_secs(20 * 60);
+
+/// Command sent by [`PeerTestManager`] to an active session.
+#[derive(Debug, Default, Clone)]
+pub enum PeerTestCommand {
+    /// Send peer test message to remote router.
+    Send {
+        /// Peer test message.
+        message: PeerTestMessage,
+
+        /// Serialized router info which is sent together with the message, if any.
+        router_info: Option<Bytes>,
+    },
+
+    #[default]
+    Dummy,
+}
+
+/// Event sent by an active session to [`PeerTestManager`].
+#[derive(Debug, Default, Clone)]
+pub enum PeerTestEvent {
+    /// Peer test message received from remote router.
+    Message {
+        /// ID of the remote router.
+        router_id: RouterId,
+
+        /// Peer test message.
+        message: PeerTestMessage,
+
+        /// Router info that was received together with the message, if any.
+        router_info: Option<Box<RouterInfo>>,
+    },
+
+    /// Remote router's socket address changed after successful path validation.
+    AddressChanged {
+        /// ID of the remote router.
+        router_id: RouterId,
+
+        /// New socket address of the remote router.
+        address: SocketAddr,
+    },
+
+    #[default]
+    Dummy,
+}
+
+/// Peer test handle given to an active session.
+pub struct PeerTestHandle {
+    /// RX channel for receiving commands from [`PeerTestManager`].
+    pub cmd_rx: Receiver<PeerTestCommand>,
+
+    /// TX channel for sending events to [`PeerTestManager`].
+    pub event_tx: Sender<PeerTestEvent>,
+}
+
+/// Session context of an active session.
+struct SessionContext {
+    /// Socket address of the remote router.
+    address: SocketAddr,
+
+    /// TX channel for sending commands to the session.
+    cmd_tx: Sender<PeerTestCommand>,
+
+    /// Our socket address, as seen by the remote router.
+    external_address: Option<SocketAddr>,
+
+    /// Router info of the remote router, if known.
+    router_info: Option<RouterInfo>,
+
+    /// Serialized router info of the remote router, if known.
+    serialized: Option<Bytes>,
+}
+
+/// Charlie's context, as seen by Alice.
+#[derive(Clone, Copy)]
+struct CharlieContext {
+    /// Charlie's socket address.
+    address: SocketAddr,
+
+    /// Charlie's intro key.
+    intro_key: [u8; 32],
+}
+
+/// Active peer test where we act as Alice.
+struct ActivePeerTest<R: Runtime> {
+    /// Our socket address, as seen by Bob.
+    address: SocketAddr,
+
+    /// ID of Bob.
+    bob: RouterId,
+
+    /// Charlie's context, set after Bob has accepted the peer test.
+    charlie: Option<CharlieContext>,
+
+    /// Has message 5 been received from Charlie.
+    message_5_received: bool,
+
+    /// Has message 6 been sent to Charlie.
+    message_6_sent: bool,
+
+    /// Timer for sending message 6 if message 5 is not received.
+    message_6_timer: Option<R::Timer>,
+
+    /// Test nonce.
+    nonce: u32,
+
+    /// When was the peer test started.
+    started: R::Instant,
+}
+
+/// Peer test relayed by us between Alice and Charlie.
+struct RelayedPeerTest<R: Runtime> {
+    /// ID of Alice.
+    alice: RouterId,
+
+    /// ID of Charlie.
+    charlie: RouterId,
+
+    /// When was the peer test relayed.
+    started: R::Instant,
+}
+
+/// Peer test where we act as Charlie.
+struct PendingPeerTest<R: Runtime> {
+    /// Alice's intro key.
+    intro_key: [u8; 32],
+
+    /// When was the peer test accepted.
+    started: R::Instant,
+}
+
+/// Peer test manager.
+pub struct PeerTestManager<R: Runtime> {
+    /// Active peer test, if any.
+    active: Option<ActivePeerTest<R>>,
+
+    /// RX channel for receiving events from active sessions.
+    event_rx: Receiver<PeerTestEvent>,
+
+    /// TX channel given to active sessions.
+    event_tx: Sender<PeerTestEvent>,
+
+    /// Our intro key.
+    intro_key: [u8; 32],
+
+    /// When was the last peer test finished.
+    last_test: Option<R::Instant>,
+
+    /// Maintenance timer.
+    maintenance_timer: R::Timer,
+
+    /// Peer tests where we act as Charlie.
+    pending: HashMap<u32, PendingPeerTest<R>>,
+
+    /// Pending firewall status update.
+    pending_status: Option<FirewallStatus>,
+
+    /// TX channel for sending out-of-session packets to `Ssu2Socket`.
+    pkt_tx: Sender<Packet>,
+
+    /// Peer tests relayed by us.
+    relayed: HashMap<u32, RelayedPeerTest<R>>,
+
+    /// Router context.
+    router_ctx: RouterContext<R>,
+
+    /// Active sessions.
+    sessions: HashMap<RouterId, SessionContext>,
+
+    /// Current firewall status.
+    status: FirewallStatus,
+
+    /// Waker.
+    waker: Option<Waker>,
+}
+
+impl<R: Runtime> PeerTestManager<R> {
+    /// Create new [`PeerTestManager`].
+    pub fn new(intro_key: [u8; 32], pkt_tx: Sender<Packet>, router_ctx: RouterContext<R>) -> Self {
+        let (event_tx, event_rx) = channel(EVENT_CHANNEL_SIZE);
+
+        Self {
+            active: None,
+            event_rx,
+            event_tx,
+            intro_key,
+            last_test: None,
+            maintenance_timer: R::timer(MAINTENANCE_INTERVAL),
+            pending: HashMap::new(),
+            pending_status: None,
+            pkt_tx,
+            relayed: HashMap::new(),
+            router_ctx,
+            sessions: HashMap::new(),
+            status: FirewallStatus::Unknown,
+            waker: None,
+        }
+    }
+
+    /// Register new active session to `router_id`.
+    ///
+    /// `external_address` is our socket address as seen by the remote router and it's known only
+    /// for outbound sessions. If the firewall status is not known, a peer test is started.
+    pub fn add_session(
+        &mut self,
+        router_id: RouterId,
+        address: SocketAddr,
+        external_address: Option<SocketAddr>,
+        router_info: Option<RouterInfo>,
+        serialized: Option<Bytes>,
+    ) -> PeerTestHandle {
+        let (cmd_tx, cmd_rx) = channel(CMD_CHANNEL_SIZE);
+
+        self.sessions.insert(
+            router_id,
+            SessionContext {
+                address,
+                cmd_tx,
+                external_address,
+                router_info,
+                serialized,
+            },
+        );
+
+        if self.status == FirewallStatus::Unknown && self.last_test.is_none() {
+            self.start_peer_test();
+        }
+
+        PeerTestHandle {
+            cmd_rx,
+            event_tx: self.event_tx.clone(),
+        }
+    }
+
+    /// Remove active session of `router_id`.
+    pub fn remove_session(&mut self, router_id: &RouterId) {
+        self.sessions.remove(router_id);
+    }
+
+    /// Send peer test `message` to `router_id` over an active session.
+    fn send_message(
+        &self,
+        router_id: &RouterId,
+        message: PeerTestMessage,
+        router_info: Option<Bytes>,
+    ) {
+        let Some(session) = self.sessions.get(router_id) else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %router_id,
+                message_num = ?message.message_num,
+                "session doesn't exist, cannot send peer test message",
+            );
+            return;
+        };
+
+        if let Err(error) = session.cmd_tx.try_send(PeerTestCommand::Send {
+            message,
+            router_info,
+        }) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %router_id,
+                ?error,
+                "failed to send peer test message to session",
+            );
+        }
+    }
+
+    /// Send out-of-session peer test `message` to `address`.
+    fn send_out_of_session(
+        &self,
+        address: SocketAddr,
+        intro_key: [u8; 32],
+        message: PeerTestMessage,
+        observed: SocketAddr,
+    ) {
+        let pkt = PeerTestBuilder::default()
+            .with_intro_key(intro_key)
+            .with_net_id(self.router_ctx.net_id())
+            .with_address(observed)
+            .with_message(message)
+            .build::<R>();
+
+        if let Err(error) = self.pkt_tx.try_send(Packet {
+            pkt: pkt.to_vec(),
+            address,
+        }) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?address,
+                ?error,
+                "failed to send out-of-session peer test message",
+            );
+        }
+    }
+
+    /// Attempt to start a new peer test.
+    ///
+    /// Bob is selected from the connected routers which support peer testing and which have told
+    /// us our external address.
+    fn start_peer_test(&mut self) {
+        if self.active.is_some() {
+            return;
+        }
+
+        let Some((bob, address)) = self.sessions.iter().find_map(|(router_id, session)| {
+            let address = session.external_address?;
+            let router_address = session
+                .router_info
+                .as_ref()?
+                .ssu2_address(address.is_ipv4(), address.is_ipv6())?;
+
+            router_address.supports_peer_test().then(|| (router_id.clone(), address))
+        }) else {
+            tracing::trace!(
+                target: LOG_TARGET,
+                "no router available for peer test",
+            );
+            return;
+        };
+
+        let mut message = PeerTestMessage {
+            message_num: 1u8,
+            code: PeerTestCode::Accept,
+            router_hash: None,
+            nonce: R::rng().next_u32(),
+            timestamp: R::time_since_epoch().as_secs() as u32,
+            address: Some(address),
+            signature: Vec::new(),
+        };
+        message.signature = self.router_ctx.signing_key().sign(&message.signed_data(&bob, None));
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            %bob,
+            nonce = ?message.nonce,
+            ?address,
+            "start peer test",
+        );
+
+        self.active = Some(ActivePeerTest {
+            address,
+            bob: bob.clone(),
+            charlie: None,
+            message_5_received: false,
+            message_6_sent: false,
+            message_6_timer: None,
+            nonce: message.nonce,
+            started: R::now(),
+        });
+        self.send_message(&bob, message, None);
+    }
+
+    /// Finish active peer test with `status`.
+    ///
+    /// If `status` is `None`, the result of the peer test was inconclusive and the firewall status
+    /// is not changed.
+    fn finish_peer_test(&mut self, status: Option<FirewallStatus>) {
+        let Some(test) = self.active.take() else {
+            return;
+        };
+        self.last_test = Some(R::now());
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            nonce = ?test.nonce,
+            ?status,
+            "peer test finished",
+        );
+
+        match status {
+            Some(status) if status != self.status => {
+                self.status = status;
+                self.pending_status = Some(status);
+
+                if let Some(waker) = self.waker.take() {
+                    waker.wake_by_ref();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Send message 6 to Charlie.
+    fn send_message_6(&mut self) {
+        let Some(test) = self.active.as_mut() else {
+            return;
+        };
+        let Some(CharlieContext { address, intro_key }) = test.charlie else {
+            return;
+        };
+
+        if test.message_6_sent {
+            return;
+        }
+        test.message_6_sent = true;
+        test.message_6_timer = None;
+
+        let message = PeerTestMessage {
+            message_num: 6u8,
+            code: PeerTestCode::Accept,
+            router_hash: None,
+            nonce: test.nonce,
+            timestamp: R::time_since_epoch().as_secs() as u32,
+            address: Some(test.address),
+            signature: Vec::new(),
+        };
+
+        self.send_out_of_session(address, intro_key, message, address);
+    }
+
+    /// Create message that rejects the peer test of `message`.
+    ///
+    /// The rejection is signed by us.
+    fn rejection(
+        &self,
+        message_num: u8,
+        code: PeerTestCode,
+        message: &PeerTestMessage,
+        bob: &RouterId,
+        alice: &RouterId,
+    ) -> PeerTestMessage {
+        let mut rejection = PeerTestMessage {
+            message_num,
+            code,
+            router_hash: None,
+            nonce: message.nonce,
+            timestamp: message.timestamp,
+            address: message.address,
+            signature: Vec::new(),
+        };
+        rejection.signature =
+            self.router_ctx.signing_key().sign(&rejection.signed_data(bob, Some(alice)));
+
+        rejection
+    }
+
+    /// Verify signature of `message`.
+    ///
+    /// The signature was created by `signer` and `alice` is specified only if the message was
+    /// signed by Charlie.
+    fn verify_signature(
+        message: &PeerTestMessage,
+        signer: &RouterInfo,
+        bob: &RouterId,
+        alice: Option<&RouterId>,
+    ) -> bool {
+        signer
+            .identity
+            .signing_key()
+            .verify(&message.signed_data(bob, alice), &message.signature)
+            .is_ok()
+    }
+
+    /// Handle message 1, sent by Alice to us.
+    fn on_message_1(&mut self, alice: RouterId, message: PeerTestMessage) {
+        let bob = self.router_ctx.router_id().clone();
+
+        let Some(session) = self.sessions.get(&alice) else {
+            return;
+        };
+        let Some(address) = message.address else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %alice,
+                "peer test request doesn't specify an address",
+            );
+            return;
+        };
+
+        let code = match &session.router_info {
+            None => Some(PeerTestCode::BobUnspecified),
+            Some(router_info) if !Self::verify_signature(&message, router_info, &bob, None) =>
+                Some(PeerTestCode::BobSignatureFailure),
+            Some(_) if address.ip() != session.address.ip() => Some(PeerTestCode::BobUnspecified),
+            Some(_) if self.relayed.contains_key(&message.nonce) =>
+                Some(PeerTestCode::BobLimitExceeded),
+            Some(_) => None,
+        };
+
+        if let Some(code) = code {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %alice,
+                ?code,
+                "rejecting peer test request",
+            );
+            let rejection = self.rejection(4u8, code, &message, &bob, &alice);
+            return self.send_message(&alice, rejection, None);
+        }
+
+        // select charlie from those routers that support peer testing over the address family
+        // of alice and who are not alice
+        let charlie = self.sessions.iter().find_map(|(router_id, session)| {
+            if router_id == &alice {
+                return None;
+            }
+
+            session
+                .router_info
+                .as_ref()?
+                .ssu2_address(address.is_ipv4(), address.is_ipv6())?
+                .supports_peer_test()
+                .then(|| router_id.clone())
+        });
+
+        let Some(charlie) = charlie else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %alice,
+                "no charlie available for peer test",
+            );
+            let rejection = self.rejection(
+                4u8,
+                PeerTestCode::BobNoCharlieAvailable,
+                &message,
+                &bob,
+                &alice,
+            );
+            return self.send_message(&alice, rejection, None);
+        };
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            %alice,
+            %charlie,
+            nonce = ?message.nonce,
+            "relay peer test request to charlie",
+        );
+
+        let router_info = session.serialized.clone();
+        self.relayed.insert(
+            message.nonce,
+            RelayedPeerTest {
+                alice: alice.clone(),
+                charlie: charlie.clone(),
+                started: R::now(),
+            },
+        );
+        self.send_message(
+            &charlie,
+            PeerTestMessage {
+                message_num: 2u8,
+                router_hash: Some(alice),
+                ..message
+            },
+            router_info,
+        );
+    }
+
+    /// Handle message 2, sent by Bob to us.
+    fn on_message_2(
+        &mut self,
+        bob: RouterId,
+        message: PeerTestMessage,
+        router_info: Option<Box<RouterInfo>>,
+    ) {
+        let Some(alice) = message.router_hash.clone() else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %bob,
+                "peer test relay request doesn't specify alice",
+            );
+            return;
+        };
+        let router_info = router_info
+            .map(|router_info| *router_info)
+            .filter(|router_info| router_info.identity.id() == alice)
+            .or_else(|| self.router_ctx.profile_storage().get(&alice));
+
+        let (code, intro_key) = match (&router_info, message.address) {
+            (None, _) => (PeerTestCode::CharlieAliceUnknown, None),
+            (_, None) => (PeerTestCode::CharlieUnsupportedAddress, None),
+            (Some(router_info), Some(_))
+                if !Self::verify_signature(&message, router_info, &bob, None) =>
+                (PeerTestCode::CharlieSignatureFailure, None),
+            (Some(_), Some(_)) if self.sessions.contains_key(&alice) =>
+                (PeerTestCode::CharlieAliceConnected, None),
+            (Some(_), Some(_)) if self.pending.contains_key(&message.nonce) =>
+                (PeerTestCode::CharlieLimitExceeded, None),
+            (Some(router_info), Some(_)) => match router_info.ssu2_intro_key() {
+                None => (PeerTestCode::CharlieUnsupportedAddress, None),
+                Some(intro_key) => (PeerTestCode::Accept, Some(intro_key)),
+            },
+        };
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            %bob,
+            %alice,
+            nonce = ?message.nonce,
+            ?code,
+            "handle peer test relay request",
+        );
+
+        let response = self.rejection(3u8, code, &message, &bob, &alice);
+        self.send_message(&bob, response, None);
+
+        let (Some(intro_key), Some(address)) = (intro_key, message.address) else {
+            return;
+        };
+
+        self.pending.insert(
+            message.nonce,
+            PendingPeerTest {
+                intro_key,
+                started: R::now(),
+            },
+        );
+        self.send_out_of_session(
+            address,
+            intro_key,
+            PeerTestMessage {
+                message_num: 5u8,
+                code: PeerTestCode::Accept,
+                router_hash: None,
+                nonce: message.nonce,
+                timestamp: R::time_since_epoch().as_secs() as u32,
+                address: Some(address),
+                signature: Vec::new(),
+            },
+            address,
+        );
+    }
+
+    /// Handle message 3, sent by Charlie to us.
+    fn on_message_3(&mut self, charlie: RouterId, message: PeerTestMessage) {
+        let Some(test) = self.relayed.remove(&message.nonce) else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %charlie,
+                nonce = ?message.nonce,
+                "peer test response for unknown test",
+            );
+            return;
+        };
+
+        if test.charlie != charlie {
+            tracing::warn!(
+                target: LOG_TARGET,
+                expected = %test.charlie,
+                received = %charlie,
+                nonce = ?message.nonce,
+                "peer test response from unexpected router",
+            );
+            return;
+        }
+
+        let router_info =
+            self.sessions.get(&charlie).and_then(|session| session.serialized.clone());
+
+        self.send_message(
+            &test.alice,
+            PeerTestMessage {
+                message_num: 4u8,
+                router_hash: Some(charlie),
+                ..message
+            },
+            router_info,
+        );
+    }
+
+    /// Handle message 4, sent by Bob to us.
+    fn on_message_4(
+        &mut self,
+        bob: RouterId,
+        message: PeerTestMessage,
+        router_info: Option<Box<RouterInfo>>,
+    ) {
+        let Some(test) = self.active.as_mut() else {
+            return;
+        };
+
+        if test.nonce != message.nonce || test.bob != bob {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %bob,
+                nonce = ?message.nonce,
+                "peer test response for unknown test",
+            );
+            return;
+        }
+
+        if !message.code.is_accept() {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %bob,
+                code = ?message.code,
+                "peer test rejected",
+            );
+            return self.finish_peer_test(None);
+        }
+
+        let charlie = message.router_hash.as_ref().and_then(|charlie| {
+            router_info
+                .map(|router_info| *router_info)
+                .filter(|router_info| &router_info.identity.id() == charlie)
+                .or_else(|| self.router_ctx.profile_storage().get(charlie))
+        });
+        let Some(charlie) = charlie else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %bob,
+                "router info for charlie not found",
+            );
+            return self.finish_peer_test(None);
+        };
+
+        if !Self::verify_signature(&message, &charlie, &bob, Some(self.router_ctx.router_id())) {
+            tracing::warn!(
+                target: LOG_TARGET,
+                %bob,
+                charlie = %charlie.identity.id(),
+                "invalid signature for peer test response",
+            );
+            return self.finish_peer_test(None);
+        }
+
+        let context = charlie
+            .ssu2_address(test.address.is_ipv4(), test.address.is_ipv6())
+            .and_then(|address| address.socket_address)
+            .zip(charlie.ssu2_intro_key())
+            .map(|(address, intro_key)| CharlieContext { address, intro_key });
+
+        let Some(context) = context else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %bob,
+                charlie = %charlie.identity.id(),
+                "charlie doesn't have a usable ssu2 address",
+            );
+            return self.finish_peer_test(None);
+        };
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            %bob,
+            charlie = %charlie.identity.id(),
+            message_5_received = ?test.message_5_received,
+            "peer test accepted",
+        );
+
+        test.charlie = Some(context);
+
+        match test.message_5_received {
+            true => self.send_message_6(),
+            false => test.message_6_timer = Some(R::timer(MESSAGE_5_TIMEOUT)),
+        }
+    }
+
+    /// Handle message 5, sent by Charlie to us.
+    fn on_message_5(&mut self, message: PeerTestMessage) {
+        let Some(test) = self.active.as_mut() else {
+            return;
+        };
+
+        if test.nonce != message.nonce {
+            return;
+        }
+        test.message_5_received = true;
+
+        self.send_message_6();
+    }
+
+    /// Handle message 6, sent by Alice to us.
+    fn on_message_6(&mut self, message: PeerTestMessage, address: SocketAddr) {
+        let Some(test) = self.pending.remove(&message.nonce) else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?address,
+                nonce = ?message.nonce,
+                "peer test message for unknown test",
+            );
+            return;
+        };
+
+        self.send_out_of_session(
+            address,
+            test.intro_key,
+            PeerTestMessage {
+                message_num: 7u8,
+                code: PeerTestCode::Accept,
+                router_hash: None,
+                nonce: message.nonce,
+                timestamp: R::time_since_epoch().as_secs() as u32,
+                address: Some(address),
+                signature: Vec::new(),
+            },
+            address,
+        );
+    }
+
+    /// Handle message 7, sent by Charlie to us.
+    fn on_message_7(&mut self, message: PeerTestMessage) {
+        let Some(test) = self.active.as_ref() else {
+            return;
+        };
+
+        if test.nonce != message.nonce || !test.message_6_sent {
+            return;
+        }
+
+        let status = match message.address {
+            None => None,
+            Some(address) if address != test.address => Some(FirewallStatus::SymmetricNat),
+            Some(_) if test.message_5_received => Some(FirewallStatus::Ok),
+            Some(_) => Some(FirewallStatus::Firewalled),
+        };
+
+        self.finish_peer_test(status);
+    }
+
+    /// Handle event received from an active session.
+    fn on_event(&mut self, event: PeerTestEvent) {
+        let (router_id, message, router_info) = match event {
+            PeerTestEvent::Message {
+                router_id,
+                message,
+                router_info,
+            } => (router_id, message, router_info),
+            PeerTestEvent::AddressChanged { router_id, address } => {
+                if let Some(session) = self.sessions.get_mut(&router_id) {
+                    session.address = address;
+                }
+                return;
+            }
+            PeerTestEvent::Dummy => return,
+        };
+
+        match message.message_num {
+            1 => self.on_message_1(router_id, message),
+            2 => self.on_message_2(router_id, message, router_info),
+            3 => self.on_message_3(router_id, message),
+            4 => self.on_message_4(router_id, message, router_info),
+            message_num => tracing::debug!(
+                target: LOG_TARGET,
+                %router_id,
+                ?message_num,
+                "unexpected in-session peer test message",
+            ),
+        }
+    }
+
+    /// Handle out-of-session peer test packet.
+    ///
+    /// The header of `pkt` has already been decrypted by `Ssu2Socket`.
+    pub fn handle_packet(&mut self, mut pkt: Vec<u8>, pkt_num: u32, address: SocketAddr) {
+        if pkt.len() <= LONG_HEADER_LEN {
+            return;
+        }
+
+        let mut payload = pkt.split_off(LONG_HEADER_LEN);
+        if let Err(error) = ChaChaPoly::with_nonce(&self.intro_key, pkt_num as u64)
+            .decrypt_with_ad(&pkt, &mut payload)
+        {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?address,
+                ?error,
+                "failed to decrypt peer test message",
+            );
+            return;
+        }
+
+        let Some(message) = Block::parse(&payload).and_then(|blocks| {
+            blocks.into_iter().find_map(|block| match block {
+                Block::PeerTest { message } => Some(message),
+                _ => None,
+            })
+        }) else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?address,
+                "peer test packet doesn't contain a peer test block",
+            );
+            return;
+        };
+
+        match message.message_num {
+            5 => self.on_message_5(message),
+            6 => self.on_message_6(message, address),
+            7 => self.on_message_7(message),
+            message_num => tracing::debug!(
+                target: LOG_TARGET,
+                ?address,
+                ?message_num,
+                "unexpected out-of-session peer test message",
+            ),
+        }
+    }
+
+    /// Expire stale peer tests and start a new peer test if needed.
+    fn maintain(&mut self) {
+        self.relayed.retain(|_, test| test.started.elapsed() < RELAYED_TEST_EXPIRATION);
+        self.pending.retain(|_, test| test.started.elapsed() < RELAYED_TEST_EXPIRATION);
+
+        if let Some(test) = &self.active {
+            if test.started.elapsed() > PEER_TEST_TIMEOUT {
+                // if message 5 was received but message 7 wasn't, the router is reachable but
+                // the status of its nat is not known
+                let status = test.message_5_received.then_some(FirewallStatus::Ok);
+
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    nonce = ?test.nonce,
+                    message_5_received = ?test.message_5_received,
+                    "peer test timed out",
+                );
+                self.finish_peer_test(status);
+            }
+
+            return;
+        }
+
+        let interval = match self.status {
+            FirewallStatus::Unknown => RETRY_INTERVAL,
+            _ => RETEST_INTERVAL,
+        };
+
+        if self.last_test.is_none_or(|last_test| last_test.elapsed() > interval) {
+            self.start_peer_test();
+        }
+    }
+}
+
+impl<R: Runtime> Stream for PeerTestManager<R> {
+    type Item = FirewallStatus;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match self.event_rx.poll_recv(cx) {
+                Poll::Pending => break,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Ready(Some(event)) => self.on_event(event),
+            }
+        }
+
+        let message_6_timer_expired = self
+            .active
+            .as_mut()
+            .and_then(|test| test.message_6_timer.as_mut())
+            .is_some_and(|timer| timer.poll_unpin(cx).is_ready());
+
+        if message_6_timer_expired {
+            self.send_message_6();
+        }
+
+        if self.maintenance_timer.poll_unpin(cx).is_ready() {
+            self.maintain();
+
+            self.maintenance_timer = R::timer(MAINTENANCE_INTERVAL);
+            let _ = self.maintenance_timer.poll_unpin(cx);
+        }
+
+        if let Some(status) = self.pending_status.take() {
+            return Poll::Ready(Some(status));
+        }
+
+        self.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        events::EventManager,
+        primitives::RouterInfoBuilder,
+        profile::ProfileStorage,
+        runtime::mock::MockRuntime,
+        transport::ssu2::message::{HeaderKind, HeaderReader},
+    };
+    use futures::StreamExt;
+    use std::net::Ipv4Addr;
+
+    /// Router taking part in a peer test.
+    struct TestRouter {
+        /// Socket address of the router.
+        address: SocketAddr,
+
+        /// Peer test handles of active sessions.
+        handles: HashMap<RouterId, PeerTestHandle>,
+
+        /// Intro key of the router.
+        intro_key: [u8; 32],
+
+        /// Peer test manager.
+        manager: PeerTestManager<MockRuntime>,
+
+        /// RX channel for receiving out-of-session packets.
+        pkt_rx: Receiver<Packet>,
+
+        /// ID of the router.
+        router_id: RouterId,
+
+        /// Router info of the router.
+        router_info: RouterInfo,
+
+        /// Serialized router info of the router.
+        serialized: Bytes,
+    }
+
+    impl TestRouter {
+        fn new(port: u16) -> Self {
+            let intro_key = {
+                let mut intro_key = [0u8; 32];
+                MockRuntime::rng().fill_bytes(&mut intro_key);
+                intro_key
+            };
+            let (router_info, static_key, signing_key) = RouterInfoBuilder::default()
+                .with_ssu2(crate::Ssu2Config {
+                    port,
+                    ipv4_host: Some(Ipv4Addr::new(127, 0, 0, 1)),
+                    ipv6_host: None,
+                    ipv4: true,
+                    ipv6: false,
+                    publish: true,
+                    static_key: [port as u8; 32],
+                    intro_key,
+                })
+                .build();
+            let serialized = Bytes::from(router_info.serialize(&signing_key));
+            let (_event_mgr, _event_subscriber, event_handle) = EventManager::new(None);
+            let (pkt_tx, pkt_rx) = channel(64);
+
+            Self {
+                address: SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), port),
+                handles: HashMap::new(),
+                intro_key,
+                manager: PeerTestManager::new(
+                    intro_key,
+                    pkt_tx,
+                    RouterContext::new(
+                        MockRuntime::register_metrics(vec![], None),
+                        ProfileStorage::new(&[], &[]),
+                        router_info.identity.id(),
+                        serialized.clone(),
+                        static_key,
+                        signing_key,
+                        2u8,
+                        event_handle,
+                    ),
+                ),
+                pkt_rx,
+                router_id: router_info.identity.id(),
+                router_info,
+                serialized,
+            }
+        }
+    }
+
+    /// Register an active session between `first` and `second`.
+    fn connect(first: &mut TestRouter, second: &mut TestRouter) {
+        let handle = first.manager.add_session(
+            second.router_id.clone(),
+            second.address,
+            Some(first.address),
+            Some(second.router_info.clone()),
+            Some(second.serialized.clone()),
+        );
+        first.handles.insert(second.router_id.clone(), handle);
+
+        let handle = second.manager.add_session(
+            first.router_id.clone(),
+            first.address,
+            Some(second.address),
+            Some(first.router_info.clone()),
+            Some(first.serialized.clone()),
+        );
+        second.handles.insert(first.router_id.clone(), handle);
+    }
+
+    /// Receive the next in-session message `from` sent to `to`.
+    fn recv(from: &TestRouter, to: &TestRouter) -> (PeerTestMessage, Option<Bytes>) {
+        match from.handles[&to.router_id].cmd_rx.try_recv().unwrap() {
+            PeerTestCommand::Send {
+                message,
+                router_info,
+            } => (message, router_info),
+            PeerTestCommand::Dummy => panic!("invalid command"),
+        }
+    }
+
+    /// Send in-session `message` from `from` to `to`.
+    fn send(
+        from: &TestRouter,
+        to: &mut TestRouter,
+        message: PeerTestMessage,
+        router_info: Option<Bytes>,
+    ) {
+        to.manager.on_event(PeerTestEvent::Message {
+            router_id: from.router_id.clone(),
+            message,
+            router_info: router_info
+                .map(|router_info| Box::new(RouterInfo::parse(router_info).unwrap())),
+        });
+    }
+
+    /// Deliver the next in-session message `from` sent to `to` and return the message.
+    fn deliver(from: &TestRouter, to: &mut TestRouter) -> PeerTestMessage {
+        let (message, router_info) = recv(from, to);
+        send(from, to, message.clone(), router_info);
+
+        message
+    }
+
+    /// Deliver the next out-of-session packet `from` sent to `to`, as if it was received from
+    /// `address`, and return the destination of the packet.
+    fn deliver_packet(from: &TestRouter, to: &mut TestRouter, address: SocketAddr) -> SocketAddr {
+        let Packet {
+            mut pkt,
+            address: destination,
+        } = from.pkt_rx.try_recv().unwrap();
+
+        let mut reader = HeaderReader::new(to.intro_key, &mut pkt).unwrap();
+        let _dst_id = reader.dst_id();
+
+        let pkt_num = match reader.parse(to.intro_key).unwrap() {
+            HeaderKind::PeerTest { pkt_num, .. } => pkt_num,
+            _ => panic!("invalid type"),
+        };
+        to.manager.handle_packet(pkt, pkt_num, address);
+
+        destination
+    }
+
+    /// Create Alice, Bob and Charlie and start a peer test from Alice.
+    ///
+    /// Bob is connected to both Alice and Charlie, and Bob and Charlie know their firewall status.
+    fn setup() -> (TestRouter, TestRouter, TestRouter) {
+        let mut alice = TestRouter::new(8888);
+        let mut bob = TestRouter::new(9999);
+        let mut charlie = TestRouter::new(7777);
+
+        bob.manager.status = FirewallStatus::Ok;
+        charlie.manager.status = FirewallStatus::Ok;
+
+        connect(&mut bob, &mut charlie);
+        connect(&mut alice, &mut bob);
+        assert!(alice.manager.active.is_some());
+
+        (alice, bob, charlie)
+    }
+
+    /// Relay the peer test request of Alice through Bob to Charlie and return message 4.
+    fn relay(
+        alice: &mut TestRouter,
+        bob: &mut TestRouter,
+        charlie: &mut TestRouter,
+    ) -> PeerTestMessage {
+        assert_eq!(deliver(alice, bob).message_num, 1);
+        assert_eq!(deliver(bob, charlie).message_num, 2);
+        assert_eq!(deliver(charlie, bob).message_num, 3);
+
+        let message = deliver(bob, alice);
+        assert_eq!(message.message_num, 4);
+
+        message
+    }
+
+    /// Assert that the active peer test of `router` finished inconclusively.
+    fn assert_inconclusive(router: &TestRouter) {
+        assert!(router.manager.active.is_none());
+        assert!(router.manager.last_test.is_some());
+        assert_eq!(router.manager.status, FirewallStatus::Unknown);
+        assert!(router.manager.pending_status.is_none());
+    }
+
+    #[tokio::test]
+    async fn peer_test_ok() {
+        let (mut alice, mut bob, mut charlie) = setup();
+
+        assert_eq!(
+            relay(&mut alice, &mut bob, &mut charlie).code,
+            PeerTestCode::Accept
+        );
+        assert!(alice.manager.active.as_ref().unwrap().message_6_timer.is_some());
+
+        // message 5
+        assert_eq!(
+            deliver_packet(&charlie, &mut alice, charlie.address),
+            alice.address
+        );
+
+        // message 6
+        assert_eq!(
+            deliver_packet(&alice, &mut charlie, alice.address),
+            charlie.address
+        );
+        assert!(charlie.manager.pending.is_empty());
+
+        // message 7
+        assert_eq!(
+            deliver_packet(&charlie, &mut alice, charlie.address),
+            alice.address
+        );
+        assert!(alice.manager.active.is_none());
+        assert_eq!(alice.manager.status, FirewallStatus::Ok);
+        assert_eq!(alice.manager.next().await, Some(FirewallStatus::Ok));
+    }
+
+    #[tokio::test]
+    async fn firewalled_if_message_5_not_received() {
+        let (mut alice, mut bob, mut charlie) = setup();
+
+        assert_eq!(
+            relay(&mut alice, &mut bob, &mut charlie).code,
+            PeerTestCode::Accept
+        );
+
+        // drop message 5 and verify that message 6 is sent after a timeout
+        let _ = charlie.pkt_rx.try_recv().unwrap();
+        assert!(alice.pkt_rx.try_recv().is_err());
+        assert!(tokio::time::timeout(
+            MESSAGE_5_TIMEOUT + Duration::from_secs(1),
+            alice.manager.next()
+        )
+        .await
+        .is_err());
+
+        assert_eq!(
+            deliver_packet(&alice, &mut charlie, alice.address),
+            charlie.address
+        );
+        assert_eq!(
+            deliver_packet(&charlie, &mut alice, charlie.address),
+            alice.address
+        );
+        assert_eq!(alice.manager.status, FirewallStatus::Firewalled);
+        assert_eq!(
+            alice.manager.pending_status,
+            Some(FirewallStatus::Firewalled)
+        );
+    }
+
+    #[tokio::test]
+    async fn inconclusive_if_messages_5_and_7_not_received() {
+        let (mut alice, mut bob, mut charlie) = setup();
+
+        assert_eq!(
+            relay(&mut alice, &mut bob, &mut charlie).code,
+            PeerTestCode::Accept
+        );
+
+        // drop message 5 and send message 6 as if the timer had expired but drop message 7
+        let _ = charlie.pkt_rx.try_recv().unwrap();
+        alice.manager.send_message_6();
+        assert_eq!(
+            deliver_packet(&alice, &mut charlie, alice.address),
+            charlie.address
+        );
+        let _ = charlie.pkt_rx.try_recv().unwrap();
+
+        alice.manager.maintain();
+        assert!(alice.manager.active.is_some());
+
+        alice.manager.active.as_mut().unwrap().started =
+            MockRuntime::now().subtract(PEER_TEST_TIMEOUT + Duration::from_secs(1));
+        alice.manager.maintain();
+        assert_inconclusive(&alice);
+    }
+
+    #[tokio::test]
+    async fn ok_if_message_7_not_received() {
+        let (mut alice, mut bob, mut charlie) = setup();
+
+        assert_eq!(
+            relay(&mut alice, &mut bob, &mut charlie).code,
+            PeerTestCode::Accept
+        );
+        assert_eq!(
+            deliver_packet(&charlie, &mut alice, charlie.address),
+            alice.address
+        );
+
+        // drop message 6
+        let _ = alice.pkt_rx.try_recv().unwrap();
+
+        alice.manager.active.as_mut().unwrap().started =
+            MockRuntime::now().subtract(PEER_TEST_TIMEOUT + Duration::from_secs(1));
+        alice.manager.maintain();
+
+        assert!(alice.manager.active.is_none());
+        assert_eq!(alice.manager.status, FirewallStatus::Ok);
+    }
+
+    #[tokio::test]
+    async fn symmetric_nat() {
+        // message 6 is received by charlie from a different address or a different port
+        for address in ["127.0.0.2:8888", "127.0.0.1:8889"] {
+            let address: SocketAddr = address.parse().unwrap();
+            let (mut alice, mut bob, mut charlie) = setup();
+
+            assert_eq!(
+                relay(&mut alice, &mut bob, &mut charlie).code,
+                PeerTestCode::Accept
+            );
+            assert_eq!(
+                deliver_packet(&charlie, &mut alice, charlie.address),
+                alice.address
+            );
+            assert_eq!(
+                deliver_packet(&alice, &mut charlie, address),
+                charlie.address
+            );
+
+            // message 7 is sent to the address message 6 was received from
+            assert_eq!(
+                deliver_packet(&charlie, &mut alice, charlie.address),
+                address
+            );
+            assert_eq!(alice.manager.status, FirewallStatus::SymmetricNat);
+            assert_eq!(
+                alice.manager.pending_status,
+                Some(FirewallStatus::SymmetricNat)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn bob_no_charlie_available() {
+        let mut alice = TestRouter::new(8888);
+        let mut bob = TestRouter::new(9999);
+        bob.manager.status = FirewallStatus::Ok;
+
+        connect(&mut alice, &mut bob);
+        assert_eq!(deliver(&alice, &mut bob).message_num, 1);
+
+        let message = deliver(&bob, &mut alice);
+        assert_eq!(message.code, PeerTestCode::BobNoCharlieAvailable);
+        assert!(bob.manager.relayed.is_empty());
+        assert_inconclusive(&alice);
+    }
+
+    #[tokio::test]
+    async fn bob_signature_failure() {
+        let (mut alice, mut bob, _charlie) = setup();
+
+        let (mut message, _) = recv(&alice, &bob);
+        message.signature[0] ^= 1;
+        send(&alice, &mut bob, message, None);
+
+        let message = deliver(&bob, &mut alice);
+        assert_eq!(message.code, PeerTestCode::BobSignatureFailure);
+        assert_inconclusive(&alice);
+    }
+
+    #[tokio::test]
+    async fn bob_address_mismatch() {
+        let (mut alice, mut bob, _charlie) = setup();
+
+        let (mut message, _) = recv(&alice, &bob);
+        message.address = Some("127.0.0.2:8888".parse().unwrap());
+        message.signature = alice
+            .manager
+            .router_ctx
+            .signing_key()
+            .sign(&message.signed_data(&bob.router_id, None));
+        send(&alice, &mut bob, message, None);
+
+        let message = deliver(&bob, &mut alice);
+        assert_eq!(message.code, PeerTestCode::BobUnspecified);
+        assert_inconclusive(&alice);
+    }
+
+    #[tokio::test]
+    async fn bob_alice_router_info_unknown() {
+        let (mut alice, mut bob, _charlie) = setup();
+        bob.manager.sessions.get_mut(&alice.router_id).unwrap().router_info = None;
+
+        assert_eq!(deliver(&alice, &mut bob).message_num, 1);
+
+        let message = deliver(&bob, &mut alice);
+        assert_eq!(message.code, PeerTestCode::BobUnspecified);
+        assert_inconclusive(&alice);
+    }
+
+    #[tokio::test]
+    async fn bob_limit_exceeded() {
+        let (mut alice, mut bob, charlie) = setup();
+
+        // message 1 with the same nonce is received twice
+        let message = deliver(&alice, &mut bob);
+        send(&alice, &mut bob, message, None);
+        assert_eq!(recv(&bob, &charlie).0.message_num, 2);
+
+        let message = deliver(&bob, &mut alice);
+        assert_eq!(message.code, PeerTestCode::BobLimitExceeded);
+        assert_inconclusive(&alice);
+    }
+
+    #[tokio::test]
+    async fn charlie_alice_connected() {
+        let (mut alice, mut bob, mut charlie) = setup();
+        connect(&mut alice, &mut charlie);
+
+        assert_eq!(
+            relay(&mut alice, &mut bob, &mut charlie).code,
+            PeerTestCode::CharlieAliceConnected
+        );
+        assert!(charlie.manager.pending.is_empty());
+        assert!(charlie.pkt_rx.try_recv().is_err());
+        assert!(bob.manager.relayed.is_empty());
+        assert_inconclusive(&alice);
+    }
+
+    #[tokio::test]
+    async fn charlie_rejects_relay_request() {
+        // alice's router info is not known
+        {
+            let (alice, mut bob, mut charlie) = setup();
+            assert_eq!(deliver(&alice, &mut bob).message_num, 1);
+
+            let (message, _) = recv(&bob, &charlie);
+            send(&bob, &mut charlie, message, None);
+
+            assert_eq!(
+                recv(&charlie, &bob).0.code,
+                PeerTestCode::CharlieAliceUnknown
+            );
+            assert!(charlie.manager.pending.is_empty());
+        }
+
+        // invalid signature
+        {
+            let (alice, mut bob, mut charlie) = setup();
+            assert_eq!(deliver(&alice, &mut bob).message_num, 1);
+
+            let (mut message, router_info) = recv(&bob, &charlie);
+            message.signature[0] ^= 1;
+            send(&bob, &mut charlie, message, router_info);
+
+            assert_eq!(
+                recv(&charlie, &bob).0.code,
+                PeerTestCode::CharlieSignatureFailure
+            );
+            assert!(charlie.manager.pending.is_empty());
+        }
+
+        // address not specified
+        {
+            let (alice, mut bob, mut charlie) = setup();
+            assert_eq!(deliver(&alice, &mut bob).message_num, 1);
+
+            let (message, router_info) = recv(&bob, &charlie);
+            send(
+                &bob,
+                &mut charlie,
+                PeerTestMessage {
+                    address: None,
+                    ..message
+                },
+                router_info,
+            );
+
+            assert_eq!(
+                recv(&charlie, &bob).0.code,
+                PeerTestCode::CharlieUnsupportedAddress
+            );
+            assert!(charlie.manager.pending.is_empty());
+        }
+
+        // message 2 with the same nonce is received twice
+        {
+            let (alice, mut bob, mut charlie) = setup();
+            assert_eq!(deliver(&alice, &mut bob).message_num, 1);
+
+            let (message, router_info) = recv(&bob, &charlie);
+            send(&bob, &mut charlie, message.clone(), router_info.clone());
+            send(&bob, &mut charlie, message, router_info);
+
+            assert_eq!(recv(&charlie, &bob).0.code, PeerTestCode::Accept);
+            assert_eq!(
+                recv(&charlie, &bob).0.code,
+                PeerTestCode::CharlieLimitExceeded
+            );
+            assert_eq!(charlie.manager.pending.len(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn retry_after_inconclusive_test() {
+        let mut alice = TestRouter::new(8888);
+        let mut bob = TestRouter::new(9999);
+        bob.manager.status = FirewallStatus::Ok;
+
+        connect(&mut alice, &mut bob);
+        assert_eq!(deliver(&alice, &mut bob).message_num, 1);
+        assert_eq!(
+            deliver(&bob, &mut alice).code,
+            PeerTestCode::BobNoCharlieAvailable
+        );
+        assert_inconclusive(&alice);
+
+        alice.manager.maintain();
+        assert!(alice.manager.active.is_none());
+
+        alice.manager.last_test =
+            Some(MockRuntime::now().subtract(RETRY_INTERVAL + Duration::from_secs(1)));
+        alice.manager.maintain();
+        assert!(alice.manager.active.is_some());
+        assert_eq!(recv(&alice, &bob).0.message_num, 1);
+    }
+
+    #[tokio::test]
+    async fn retest_after_known_status() {
+        let (mut alice, mut bob, mut charlie) = setup();
+
+        assert_eq!(
+            relay(&mut alice, &mut bob, &mut charlie).code,
+            PeerTestCode::Accept
+        );
+        deliver_packet(&charlie, &mut alice, charlie.address);
+        deliver_packet(&alice, &mut charlie, alice.address);
+        deliver_packet(&charlie, &mut alice, charlie.address);
+        assert_eq!(alice.manager.status, FirewallStatus::Ok);
+
+        // status is known so the retry interval is not used
+        alice.manager.last_test =
+            Some(MockRuntime::now().subtract(RETRY_INTERVAL + Duration::from_secs(1)));
+        alice.manager.maintain();
+        assert!(alice.manager.active.is_none());
+
+        alice.manager.last_test =
+            Some(MockRuntime::now().subtract(RETEST_INTERVAL + Duration::from_secs(1)));
+        alice.manager.maintain();
+        assert!(alice.manager.active.is_some());
+        assert_eq!(recv(&alice, &bob).0.message_num, 1);
+    }
+
+    #[tokio::test]
+    async fn bob_removed_during_peer_test() {
+        let (mut alice, mut bob, _charlie) = setup();
+
+        assert_eq!(deliver(&alice, &mut bob).message_num, 1);
+        alice.manager.remove_session(&bob.router_id);
+
+        alice.manager.active.as_mut().unwrap().started =
+            MockRuntime::now().subtract(PEER_TEST_TIMEOUT + Duration::from_secs(1));
+        alice.manager.maintain();
+        assert_inconclusive(&alice);
+
+        // no router is available for a new peer test
+        alice.manager.last_test =
+            Some(MockRuntime::now().subtract(RETRY_INTERVAL + Duration::from_secs(1)));
+        alice.manager.maintain();
+        assert!(alice.manager.active.is_none());
+    }
+
+    #[tokio::test]
+    async fn alice_removed_during_relayed_test() {
+        let (alice, mut bob, mut charlie) = setup();
+
+        assert_eq!(deliver(&alice, &mut bob).message_num, 1);
+        assert_eq!(deliver(&bob, &mut charlie).message_num, 2);
+        bob.manager.remove_session(&alice.router_id);
+
+        // response from charlie is not relayed to alice
+        assert_eq!(deliver(&charlie, &mut bob).message_num, 3);
+        assert!(bob.manager.relayed.is_empty());
+        assert!(alice.handles[&bob.router_id].cmd_rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,1956 @@
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! SSU2 relay implementation.
+//!
+//! The router can act in any of the three roles of a relayed connection:
+//!  * Alice, the router which wants to connect to a firewalled router
+//!  * Bob, the introducer which has issued a relay tag to the firewalled router
+//!  * Charlie, the firewalled router which publishes its introducers in its router info
+//!
+//! `RelayTagRequest`, `RelayTag`, `RelayRequest`, `RelayIntro` and `RelayResponse` are sent inside
+//! active sessions, `HolePunch` is sent out-of-session by Charlie to Alice.
+//!
+//! https://geti2p.net/spec/ssu2#introduction
+
+use crate::{
+    crypto::chachapoly::ChaChaPoly,
+    events::FirewallStatus,
+    primitives::{Introducer, RouterId, RouterInfo},
+    router::context::RouterContext,
+    runtime::{Instant, Runtime},
+    transport::ssu2::{
+        message::{
+            relay::{HolePunchBuilder, RelayCode, RelayIntro, RelayRequest, RelayResponse},
+            Block,
+        },
+        Packet,
+    },
+};
+
+use bytes::Bytes;
+use futures::{FutureExt, Stream};
+use hashbrown::HashMap;
+use rand_core::RngCore;
+use thingbuf::mpsc::{channel, Receiver, Sender};
+
+use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
+use core::{
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+/// Logging target for the file.
+const LOG_TARGET: &str = "emissary::ssu2::relay";
+
+/// Command channel size.
+const CMD_CHANNEL_SIZE: usize = 16usize;
+
+/// Event channel size.
+const EVENT_CHANNEL_SIZE: usize = 256usize;
+
+/// Long header length.
+const LONG_HEADER_LEN: usize = 32usize;
+
+/// Maximum number of introducers.
+const MAX_INTRODUCERS: usize = 3usize;
+
+/// How long are the introducers published for.
+const INTRODUCER_EXPIRATION: Duration = Duration::from_secs(60 * 60);
+
+/// How long before the expiration are introducers replaced.
+const INTRODUCER_REFRESH_THRESHOLD: Duration = Duration::from_secs(5 * 60);
+
+/// Maintenance interval.
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Relay timeout.
+///
+/// How long is Alice willing to wait for a response from Bob or Charlie and how long does Bob
+/// keep a relayed request in memory.
+const RELAY_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How long is a token issued by Charlie valid for.
+const TOKEN_EXPIRATION: Duration = Duration::from_secs(60);
+
+/// Relay message sent or received inside an active session.
+#[derive(Debug, Clone)]
+pub enum RelayMessage {
+    /// Relay tag request, sent by Charlie to Bob.
+    TagRequest,
+
+    /// Relay tag, sent by Bob to Charlie.
+    Tag {
+        /// Relay tag.
+        tag: u32,
+    },
+
+    /// Relay request, sent by Alice to Bob.
+    Request(RelayRequest),
+
+    /// Relay intro, sent by Bob to Charlie.
+    Intro(RelayIntro),
+
+    /// Relay response, sent by Charlie to Bob and by Bob to Alice.
+    Response(RelayResponse),
+}
+
+impl RelayMessage {
+    /// Convert [`RelayMessage`] into an SSU2 message block.
+    pub fn into_block(self) -> Block {
+        match self {
+            Self::TagRequest => Block::RelayTagRequest {},
+            Self::Tag { tag } => Block::RelayTag { tag },
+            Self::Request(message) => Block::RelayRequest { message },
+            Self::Intro(message) => Block::RelayIntro { message },
+            Self::Response(message) => Block::RelayResponse { message },
+        }
+    }
+}
+
+/// Command sent by [`RelayManager`] to an active session.
+#[derive(Debug, Default, Clone)]
+pub enum RelayCommand {
+    /// Send relay message to remote router.
+    Send {
+        /// Relay message.
+        message: RelayMessage,
+
+        /// Serialized router info which is sent together with the message, if any.
+        router_info: Option<Bytes>,
+    },
+
+    #[default]
+    Dummy,
+}
+
+/// Event sent by an active session to [`RelayManager`].
+#[derive(Debug, Default, Clone)]
+pub enum RelayEvent {
+    /// Relay message received from remote router.
+    Message {
+        /// ID of the remote router.
+        router_id: RouterId,
+
+        /// Relay message.
+        message: RelayMessage,
+
+        /// Router info that was received together with the message, if any.
+        router_info: Option<Box<RouterInfo>>,
+    },
+
+    #[default]
+    Dummy,
+}
+
+/// Relay handle given to an active session.
+pub struct RelayHandle {
+    /// RX channel for receiving commands from [`RelayManager`].
+    pub cmd_rx: Receiver<RelayCommand>,
+
+    /// TX channel for sending events to [`RelayManager`].
+    pub event_tx: Sender<RelayEvent>,
+}
+
+/// Event emitted by [`RelayManager`].
+#[derive(Debug)]
+pub enum RelayManagerEvent {
+    /// Published introducers have changed.
+    IntroducersChanged {
+        /// New introducers.
+        introducers: Vec<Introducer>,
+    },
+
+    /// Connect to introducer.
+    ///
+    /// Emitted when Alice doesn't have an active session to any of Charlie's introducers.
+    ConnectIntroducer {
+        /// Router info of the introducer.
+        router_info: Box<RouterInfo>,
+    },
+
+    /// Connect to Charlie using the token received in `RelayResponse`.
+    Connect {
+        /// Router info of Charlie.
+        router_info: Box<RouterInfo>,
+
+        /// Charlie's socket address.
+        address: SocketAddr,
+
+        /// Token received from Charlie.
+        token: u64,
+    },
+
+    /// Relayed connection to Charlie failed.
+    ConnectionFailure {
+        /// ID of Charlie.
+        router_id: RouterId,
+    },
+}
+
+/// Session context of an active session.
+struct SessionContext {
+    /// TX channel for sending commands to the session.
+    cmd_tx: Sender<RelayCommand>,
+
+    /// Our socket address, as seen by the remote router.
+    external_address: Option<SocketAddr>,
+
+    /// Router info of the remote router, if known.
+    router_info: Option<RouterInfo>,
+
+    /// Serialized router info of the remote router, if known.
+    serialized: Option<Bytes>,
+}
+
+/// Relayed connection where we act as Alice and which waits for a session to Bob.
+struct PendingIntroduction<R: Runtime> {
+    /// ID of Bob.
+    bob: RouterId,
+
+    /// Router info of Charlie.
+    router_info: RouterInfo,
+
+    /// Relay tag Charlie received from Bob.
+    tag: u32,
+
+    /// When was the connection started.
+    started: R::Instant,
+}
+
+/// Relayed connection where we act as Alice and which waits for a response from Bob or Charlie.
+struct PendingRelay<R: Runtime> {
+    /// ID of Bob.
+    bob: RouterId,
+
+    /// Router info of Charlie.
+    router_info: RouterInfo,
+
+    /// When was the relay request sent.
+    started: R::Instant,
+}
+
+/// Relay request relayed by us between Alice and Charlie.
+struct RelayedRequest<R: Runtime> {
+    /// ID of Alice.
+    alice: RouterId,
+
+    /// ID of Charlie.
+    charlie: RouterId,
+
+    /// When was the request relayed.
+    started: R::Instant,
+}
+
+/// Relay manager.
+pub struct RelayManager<R: Runtime> {
+    /// RX channel for receiving events from active sessions.
+    event_rx: Receiver<RelayEvent>,
+
+    /// TX channel given to active sessions.
+    event_tx: Sender<RelayEvent>,
+
+    /// Our intro key.
+    intro_key: [u8; 32],
+
+    /// Introducers of the local router.
+    introducers: HashMap<RouterId, Introducer>,
+
+    /// Relay tags issued by us, mapped to Charlies who received them.
+    issued_tags: HashMap<u32, RouterId>,
+
+    /// Maintenance timer.
+    maintenance_timer: R::Timer,
+
+    /// Pending events.
+    pending_events: VecDeque<RelayManagerEvent>,
+
+    /// Relayed connections waiting for a session to Bob, indexed by Charlie's router ID.
+    pending_introductions: HashMap<RouterId, PendingIntroduction<R>>,
+
+    /// Relayed connections waiting for a response, indexed by relay nonce.
+    pending_relays: HashMap<u32, PendingRelay<R>>,
+
+    /// TX channel for sending out-of-session packets to `Ssu2Socket`.
+    pkt_tx: Sender<Packet>,
+
+    /// Relay requests relayed by us, indexed by relay nonce.
+    relayed: HashMap<u32, RelayedRequest<R>>,
+
+    /// Pending relay tag requests, indexed by Bob's router ID.
+    requested_tags: HashMap<RouterId, R::Instant>,
+
+    /// Router context.
+    router_ctx: RouterContext<R>,
+
+    /// Active sessions.
+    sessions: HashMap<RouterId, SessionContext>,
+
+    /// Current firewall status.
+    status: FirewallStatus,
+
+    /// Tokens issued by us as Charlie.
+    tokens: HashMap<u64, R::Instant>,
+
+    /// Waker.
+    waker: Option<Waker>,
+}
+
+impl<R: Runtime> RelayManager<R> {
+    /// Create new [`RelayManager`].
+    pub fn new(intro_key: [u8; 32], pkt_tx: Sender<Packet>, router_ctx: RouterContext<R>) -> Self {
+        let (event_tx, event_rx) = channel(EVENT_CHANNEL_SIZE);
+
+        Self {
+            event_rx,
+            event_tx,
+            intro_key,
+            introducers: HashMap::new(),
+            issued_tags: HashMap::new(),
+            maintenance_timer: R::timer(MAINTENANCE_INTERVAL),
+            pending_events: VecDeque::new(),
+            pending_introductions: HashMap::new(),
+            pending_relays: HashMap::new(),
+            pkt_tx,
+            relayed: HashMap::new(),
+            requested_tags: HashMap::new(),
+            router_ctx,
+            sessions: HashMap::new(),
+            status: FirewallStatus::Unknown,
+            tokens: HashMap::new(),
+            waker: None,
+        }
+    }
+
+    /// Register new active session to `router_id`.
+    ///
+    /// If a relayed connection was waiting for a session to `router_id`, the relay request is sent
+    /// and if we're firewalled, a relay tag is requested from the router.
+    pub fn add_session(
+        &mut self,
+        router_id: RouterId,
+        external_address: Option<SocketAddr>,
+        router_info: Option<RouterInfo>,
+        serialized: Option<Bytes>,
+    ) -> RelayHandle {
+        let (cmd_tx, cmd_rx) = channel(CMD_CHANNEL_SIZE);
+
+        self.sessions.insert(
+            router_id.clone(),
+            SessionContext {
+                cmd_tx,
+                external_address,
+                router_info,
+                serialized,
+            },
+        );
+
+        let charlies = self
+            .pending_introductions
+            .iter()
+            .filter(|(_, pending)| pending.bob == router_id)
+            .map(|(charlie, _)| charlie.clone())
+            .collect::<Vec<_>>();
+
+        for charlie in charlies {
+            if let Some(PendingIntroduction {
+                bob,
+                router_info,
+                tag,
+                ..
+            }) = self.pending_introductions.remove(&charlie)
+            {
+                self.send_relay_request(bob, router_info, tag);
+            }
+        }
+
+        self.request_tags();
+
+        RelayHandle {
+            cmd_rx,
+            event_tx: self.event_tx.clone(),
+        }
+    }
+
+    /// Remove active session of `router_id`.
+    ///
+    /// Relay tags issued to the router are forgotten and if the router was our introducer, the
+    /// introducers are updated.
+    pub fn remove_session(&mut self, router_id: &RouterId) {
+        self.sessions.remove(router_id);
+        self.issued_tags.retain(|_, charlie| charlie != router_id);
+        self.requested_tags.remove(router_id);
+
+        if self.introducers.remove(router_id).is_some() {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %router_id,
+                "session to introducer closed",
+            );
+
+            self.report_introducers();
+            self.request_tags();
+        }
+    }
+
+    /// Set the firewall status of the local router.
+    ///
+    /// Relay tags are requested if the router is firewalled and introducers are removed if the
+    /// router is reachable.
+    pub fn set_firewall_status(&mut self, status: FirewallStatus) {
+        self.status = status;
+
+        match status {
+            FirewallStatus::Firewalled | FirewallStatus::SymmetricNat => self.request_tags(),
+            FirewallStatus::Ok | FirewallStatus::Unknown => {
+                self.requested_tags.clear();
+
+                if !self.introducers.is_empty() {
+                    self.introducers.clear();
+                    self.report_introducers();
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if `token` was issued by us and it hasn't expired.
+    pub fn has_token(&self, token: u64) -> bool {
+        self.tokens
+            .get(&token)
+            .is_some_and(|issued| issued.elapsed() < TOKEN_EXPIRATION)
+    }
+
+    /// Remove `token` after it has been used by Alice.
+    pub fn remove_token(&mut self, token: u64) {
+        self.tokens.remove(&token);
+    }
+
+    /// Connect to `router_info` through one of its introducers.
+    ///
+    /// `RelayRequest` is sent to an introducer we have an active session with and if there are no
+    /// such introducers, a connection to an introducer found in the profile storage is opened.
+    pub fn connect(&mut self, router_info: RouterInfo, ipv4: bool, ipv6: bool) {
+        let router_id = router_info.identity.id();
+        let now = R::time_since_epoch().as_secs() as u32;
+        let introducers = router_info
+            .ssu2_introduced_address(ipv4, ipv6)
+            .map(|address| address.introducers())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|introducer| introducer.expires > now)
+            .collect::<Vec<_>>();
+
+        if let Some(introducer) = introducers.iter().find(|introducer| {
+            self.sessions
+                .get(&introducer.router_id)
+                .is_some_and(|session| session.external_address.is_some())
+        }) {
+            return self.send_relay_request(
+                introducer.router_id.clone(),
+                router_info,
+                introducer.tag,
+            );
+        }
+
+        let introducer = introducers.into_iter().find_map(|introducer| {
+            let bob = self.router_ctx.profile_storage().get(&introducer.router_id)?;
+            bob.ssu2_address(ipv4, ipv6)?;
+
+            Some((introducer, bob))
+        });
+
+        match introducer {
+            Some((
+                Introducer {
+                    router_id: bob,
+                    tag,
+                    ..
+                },
+                bob_router_info,
+            )) => {
+                tracing::trace!(
+                    target: LOG_TARGET,
+                    charlie = %router_id,
+                    %bob,
+                    "connect to introducer",
+                );
+
+                self.pending_introductions.insert(
+                    router_id,
+                    PendingIntroduction {
+                        bob,
+                        router_info,
+                        tag,
+                        started: R::now(),
+                    },
+                );
+                self.push_event(RelayManagerEvent::ConnectIntroducer {
+                    router_info: Box::new(bob_router_info),
+                });
+            }
+            None => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    charlie = %router_id,
+                    "no usable introducer for router",
+                );
+
+                self.push_event(RelayManagerEvent::ConnectionFailure { router_id });
+            }
+        }
+    }
+
+    /// Push `event` to pending events and wake the event loop.
+    fn push_event(&mut self, event: RelayManagerEvent) {
+        self.pending_events.push_back(event);
+
+        if let Some(waker) = self.waker.take() {
+            waker.wake_by_ref();
+        }
+    }
+
+    /// Report current introducers.
+    fn report_introducers(&mut self) {
+        let introducers = self.introducers.values().cloned().collect();
+
+        self.push_event(RelayManagerEvent::IntroducersChanged { introducers });
+    }
+
+    /// Send relay `message` to `router_id` over an active session.
+    fn send_message(
+        &self,
+        router_id: &RouterId,
+        message: RelayMessage,
+        router_info: Option<Bytes>,
+    ) {
+        let Some(session) = self.sessions.get(router_id) else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %router_id,
+                ?message,
+                "session doesn't exist, cannot send relay message",
+            );
+            return;
+        };
+
+        if let Err(error) = session.cmd_tx.try_send(RelayCommand::Send {
+            message,
+            router_info,
+        }) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %router_id,
+                ?error,
+                "failed to send relay message to session",
+            );
+        }
+    }
+
+    /// Request relay tags if we're firewalled and don't have enough introducers.
+    ///
+    /// Introducers are selected from the connected routers which support relaying over the
+    /// address family of our external address.
+    fn request_tags(&mut self) {
+        if !core::matches!(
+            self.status,
+            FirewallStatus::Firewalled | FirewallStatus::SymmetricNat
+        ) {
+            return;
+        }
+
+        let num_needed = MAX_INTRODUCERS
+            .saturating_sub(self.introducers.len())
+            .saturating_sub(self.requested_tags.len());
+
+        let candidates = self
+            .sessions
+            .iter()
+            .filter(|(router_id, _)| {
+                !self.introducers.contains_key(*router_id)
+                    && !self.requested_tags.contains_key(*router_id)
+            })
+            .filter_map(|(router_id, session)| {
+                let address = session.external_address?;
+                let router_address = session
+                    .router_info
+                    .as_ref()?
+                    .ssu2_address(address.is_ipv4(), address.is_ipv6())?;
+
+                router_address.supports_relay().then(|| router_id.clone())
+            })
+            .take(num_needed)
+            .collect::<Vec<_>>();
+
+        for router_id in candidates {
+            tracing::trace!(
+                target: LOG_TARGET,
+                %router_id,
+                "request relay tag",
+            );
+
+            self.requested_tags.insert(router_id.clone(), R::now());
+            self.send_message(&router_id, RelayMessage::TagRequest, None);
+        }
+    }
+
+    /// Send `RelayRequest` for Charlie to Bob.
+    fn send_relay_request(&mut self, bob: RouterId, router_info: RouterInfo, tag: u32) {
+        let charlie = router_info.identity.id();
+        let Some(address) = self.sessions.get(&bob).and_then(|session| session.external_address)
+        else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %charlie,
+                %bob,
+                "external address not known, cannot send relay request",
+            );
+            return self.push_event(RelayManagerEvent::ConnectionFailure { router_id: charlie });
+        };
+
+        let mut request = RelayRequest {
+            nonce: R::rng().next_u32(),
+            relay_tag: tag,
+            timestamp: R::time_since_epoch().as_secs() as u32,
+            address,
+            signature: Vec::new(),
+        };
+        request.signature =
+            self.router_ctx.signing_key().sign(&request.signed_data(&bob, &charlie));
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            %charlie,
+            %bob,
+            nonce = ?request.nonce,
+            ?tag,
+            "send relay request",
+        );
+
+        self.pending_relays.insert(
+            request.nonce,
+            PendingRelay {
+                bob: bob.clone(),
+                router_info,
+                started: R::now(),
+            },
+        );
+        self.send_message(&bob, RelayMessage::Request(request), None);
+    }
+
+    /// Create response that rejects `request`.
+    ///
+    /// The rejection is signed by us.
+    fn rejection(&self, code: RelayCode, request: &RelayRequest, bob: &RouterId) -> RelayResponse {
+        let mut response = RelayResponse {
+            code,
+            nonce: request.nonce,
+            timestamp: R::time_since_epoch().as_secs() as u32,
+            address: None,
+            signature: Vec::new(),
+            token: None,
+        };
+        response.signature = self.router_ctx.signing_key().sign(&response.signed_data(bob));
+
+        response
+    }
+
+    /// Handle `RelayTagRequest`, sent by Charlie to us.
+    fn on_tag_request(&mut self, charlie: RouterId) {
+        let tag = match self.issued_tags.iter().find(|(_, router_id)| *router_id == &charlie) {
+            Some((tag, _)) => *tag,
+            None => loop {
+                let tag = R::rng().next_u32();
+
+                if tag != 0 && !self.issued_tags.contains_key(&tag) {
+                    self.issued_tags.insert(tag, charlie.clone());
+                    break tag;
+                }
+            },
+        };
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            %charlie,
+            ?tag,
+            "issue relay tag",
+        );
+
+        self.send_message(&charlie, RelayMessage::Tag { tag }, None);
+    }
+
+    /// Handle `RelayTag`, sent by Bob to us.
+    fn on_tag(&mut self, bob: RouterId, tag: u32) {
+        if self.requested_tags.remove(&bob).is_none() {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %bob,
+                ?tag,
+                "unrequested relay tag",
+            );
+            return;
+        }
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            %bob,
+            ?tag,
+            "relay tag received",
+        );
+
+        self.introducers.insert(
+            bob.clone(),
+            Introducer {
+                router_id: bob,
+                tag,
+                expires: (R::time_since_epoch() + INTRODUCER_EXPIRATION).as_secs() as u32,
+            },
+        );
+        self.report_introducers();
+    }
+
+    /// Handle `RelayRequest`, sent by Alice to us.
+    fn on_request(&mut self, alice: RouterId, request: RelayRequest) {
+        let bob = self.router_ctx.router_id().clone();
+
+        let Some(session) = self.sessions.get(&alice) else {
+            return;
+        };
+        let charlie = self.issued_tags.get(&request.relay_tag).cloned();
+
+        let code = match (&session.router_info, &charlie) {
+            (_, None) => Some(RelayCode::BobRelayTagNotFound),
+            (None, _) => Some(RelayCode::BobUnspecified),
+            (Some(router_info), Some(charlie))
+                if router_info
+                    .identity
+                    .signing_key()
+                    .verify(&request.signed_data(&bob, charlie), &request.signature)
+                    .is_err() =>
+                Some(RelayCode::BobSignatureFailure),
+            (Some(_), Some(charlie)) if !self.sessions.contains_key(charlie) =>
+                Some(RelayCode::BobRelayTagNotFound),
+            (Some(_), Some(_)) if self.relayed.contains_key(&request.nonce) =>
+                Some(RelayCode::BobLimitExceeded),
+            (Some(_), Some(_)) => None,
+        };
+
+        if let Some(code) = code {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %alice,
+                ?code,
+                "rejecting relay request",
+            );
+            let rejection = self.rejection(code, &request, &bob);
+            return self.send_message(&alice, RelayMessage::Response(rejection), None);
+        }
+
+        let charlie = charlie.expect("to exist");
+        let router_info = session.serialized.clone();
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            %alice,
+            %charlie,
+            nonce = ?request.nonce,
+            "relay request to charlie",
+        );
+
+        self.relayed.insert(
+            request.nonce,
+            RelayedRequest {
+                alice: alice.clone(),
+                charlie: charlie.clone(),
+                started: R::now(),
+            },
+        );
+        self.send_message(
+            &charlie,
+            RelayMessage::Intro(RelayIntro { alice, request }),
+            router_info,
+        );
+    }
+
+    /// Handle `RelayIntro`, sent by Bob to us.
+    fn on_intro(&mut self, bob: RouterId, intro: RelayIntro, router_info: Option<Box<RouterInfo>>) {
+        let RelayIntro { alice, request } = intro;
+        let charlie = self.router_ctx.router_id().clone();
+        let router_info = router_info
+            .map(|router_info| *router_info)
+            .filter(|router_info| router_info.identity.id() == alice)
+            .or_else(|| self.router_ctx.profile_storage().get(&alice));
+        let address = self.sessions.get(&bob).and_then(|session| session.external_address);
+
+        let (code, intro_key) = match (&router_info, address) {
+            _ if self
+                .introducers
+                .get(&bob)
+                .is_none_or(|introducer| introducer.tag != request.relay_tag) =>
+                (RelayCode::CharlieUnspecified, None),
+            (None, _) => (RelayCode::CharlieAliceUnknown, None),
+            (_, None) => (RelayCode::CharlieUnsupportedAddress, None),
+            (Some(router_info), Some(_))
+                if router_info
+                    .identity
+                    .signing_key()
+                    .verify(&request.signed_data(&bob, &charlie), &request.signature)
+                    .is_err() =>
+                (RelayCode::CharlieSignatureFailure, None),
+            (Some(_), Some(_)) if self.sessions.contains_key(&alice) =>
+                (RelayCode::CharlieAliceConnected, None),
+            (Some(router_info), Some(_)) => match router_info.ssu2_intro_key() {
+                None => (RelayCode::CharlieUnsupportedAddress, None),
+                Some(intro_key) => (RelayCode::Accept, Some(intro_key)),
+            },
+        };
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            %bob,
+            %alice,
+            nonce = ?request.nonce,
+            ?code,
+            "handle relay intro",
+        );
+
+        let token = intro_key.map(|_| loop {
+            let token = R::rng().next_u64();
+
+            if !self.tokens.contains_key(&token) {
+                self.tokens.insert(token, R::now());
+                break token;
+            }
+        });
+        let mut response = RelayResponse {
+            code,
+            nonce: request.nonce,
+            timestamp: R::time_since_epoch().as_secs() as u32,
+            address: token.and(address),
+            signature: Vec::new(),
+            token,
+        };
+        response.signature = self.router_ctx.signing_key().sign(&response.signed_data(&bob));
+
+        self.send_message(&bob, RelayMessage::Response(response.clone()), None);
+
+        let Some(intro_key) = intro_key else {
+            return;
+        };
+
+        let pkt = HolePunchBuilder::default()
+            .with_intro_key(intro_key)
+            .with_net_id(self.router_ctx.net_id())
+            .with_address(request.address)
+            .with_response(response)
+            .build::<R>();
+
+        if let Err(error) = self.pkt_tx.try_send(Packet {
+            pkt: pkt.to_vec(),
+            address: request.address,
+        }) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                %alice,
+                address = ?request.address,
+                ?error,
+                "failed to send hole punch",
+            );
+        }
+    }
+
+    /// Handle `RelayResponse`, sent either by Charlie to us as Bob or by Bob to us as Alice.
+    fn on_response(&mut self, router_id: RouterId, response: RelayResponse) {
+        match self.relayed.remove(&response.nonce) {
+            Some(request) => {
+                if request.charlie != router_id {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        expected = %request.charlie,
+                        received = %router_id,
+                        nonce = ?response.nonce,
+                        "relay response from unexpected router",
+                    );
+                    return;
+                }
+
+                self.send_message(&request.alice, RelayMessage::Response(response), None);
+            }
+            None => self.on_relay_response(Some(router_id), response),
+        }
+    }
+
+    /// Handle `RelayResponse` to our relay request.
+    ///
+    /// The response was received either from Bob inside an active session or from Charlie in a
+    /// `HolePunch`, in which case `bob` is `None`. Whichever response is received first is used.
+    fn on_relay_response(&mut self, bob: Option<RouterId>, response: RelayResponse) {
+        let Some(relay) = self.pending_relays.get(&response.nonce) else {
+            tracing::trace!(
+                target: LOG_TARGET,
+                nonce = ?response.nonce,
+                "relay response for unknown request",
+            );
+            return;
+        };
+
+        if bob.is_some_and(|bob| bob != relay.bob) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                nonce = ?response.nonce,
+                "relay response from unexpected router",
+            );
+            return;
+        }
+
+        let signer = match response.code.is_bob_rejection() {
+            true => self.sessions.get(&relay.bob).and_then(|session| session.router_info.as_ref()),
+            false => Some(&relay.router_info),
+        };
+        let valid = signer.is_some_and(|signer| {
+            signer
+                .identity
+                .signing_key()
+                .verify(&response.signed_data(&relay.bob), &response.signature)
+                .is_ok()
+        });
+
+        if !valid {
+            tracing::warn!(
+                target: LOG_TARGET,
+                bob = %relay.bob,
+                charlie = %relay.router_info.identity.id(),
+                nonce = ?response.nonce,
+                "invalid signature for relay response",
+            );
+            return;
+        }
+
+        let PendingRelay {
+            bob, router_info, ..
+        } = self.pending_relays.remove(&response.nonce).expect("to exist");
+        let charlie = router_info.identity.id();
+
+        match (response.code, response.address, response.token) {
+            (RelayCode::Accept, Some(address), Some(token)) => {
+                tracing::trace!(
+                    target: LOG_TARGET,
+                    %bob,
+                    %charlie,
+                    ?address,
+                    "relay accepted",
+                );
+
+                self.push_event(RelayManagerEvent::Connect {
+                    router_info: Box::new(router_info),
+                    address,
+                    token,
+                });
+            }
+            (code, _, _) => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    %bob,
+                    %charlie,
+                    ?code,
+                    "relay rejected",
+                );
+
+                self.push_event(RelayManagerEvent::ConnectionFailure { router_id: charlie });
+            }
+        }
+    }
+
+    /// Handle relay message received from an active session.
+    fn on_event(&mut self, event: RelayEvent) {
+        let RelayEvent::Message {
+            router_id,
+            message,
+            router_info,
+        } = event
+        else {
+            return;
+        };
+
+        match message {
+            RelayMessage::TagRequest => self.on_tag_request(router_id),
+            RelayMessage::Tag { tag } => self.on_tag(router_id, tag),
+            RelayMessage::Request(request) => self.on_request(router_id, request),
+            RelayMessage::Intro(intro) => self.on_intro(router_id, intro, router_info),
+            RelayMessage::Response(response) => self.on_response(router_id, response),
+        }
+    }
+
+    /// Handle out-of-session `HolePunch` packet.
+    ///
+    /// The header of `pkt` has already been decrypted by `Ssu2Socket`.
+    pub fn handle_packet(&mut self, mut pkt: Vec<u8>, pkt_num: u32, address: SocketAddr) {
+        if pkt.len() <= LONG_HEADER_LEN {
+            return;
+        }
+
+        let mut payload = pkt.split_off(LONG_HEADER_LEN);
+        if let Err(error) = ChaChaPoly::with_nonce(&self.intro_key, pkt_num as u64)
+            .decrypt_with_ad(&pkt, &mut payload)
+        {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?address,
+                ?error,
+                "failed to decrypt hole punch",
+            );
+            return;
+        }
+
+        let Some(response) = Block::parse(&payload).and_then(|blocks| {
+            blocks.into_iter().find_map(|block| match block {
+                Block::RelayResponse { message } => Some(message),
+                _ => None,
+            })
+        }) else {
+            tracing::debug!(
+                target: LOG_TARGET,
+                ?address,
+                "hole punch doesn't contain a relay response block",
+            );
+            return;
+        };
+
+        self.on_relay_response(None, response);
+    }
+
+    /// Expire stale relay state and refresh introducers.
+    fn maintain(&mut self) {
+        self.relayed.retain(|_, request| request.started.elapsed() < RELAY_TIMEOUT);
+        self.tokens.retain(|_, issued| issued.elapsed() < TOKEN_EXPIRATION);
+        self.requested_tags.retain(|_, requested| requested.elapsed() < RELAY_TIMEOUT);
+
+        let expired = self
+            .pending_relays
+            .iter()
+            .filter_map(|(nonce, relay)| {
+                (relay.started.elapsed() > RELAY_TIMEOUT).then_some(*nonce)
+            })
+            .collect::<Vec<_>>();
+
+        for nonce in expired {
+            let relay = self.pending_relays.remove(&nonce).expect("to exist");
+
+            tracing::debug!(
+                target: LOG_TARGET,
+                bob = %relay.bob,
+                charlie = %relay.router_info.identity.id(),
+                "relay request timed out",
+            );
+            self.push_event(RelayManagerEvent::ConnectionFailure {
+                router_id: relay.router_info.identity.id(),
+            });
+        }
+
+        let expired = self
+            .pending_introductions
+            .iter()
+            .filter(|(_, pending)| pending.started.elapsed() > RELAY_TIMEOUT)
+            .map(|(charlie, _)| charlie.clone())
+            .collect::<Vec<_>>();
+
+        for charlie in expired {
+            self.pending_introductions.remove(&charlie);
+            self.push_event(RelayManagerEvent::ConnectionFailure { router_id: charlie });
+        }
+
+        // replace introducers which are about to expire
+        let threshold = (R::time_since_epoch() + INTRODUCER_REFRESH_THRESHOLD).as_secs() as u32;
+        let num_introducers = self.introducers.len();
+        self.introducers.retain(|_, introducer| introducer.expires > threshold);
+
+        if self.introducers.len() != num_introducers {
+            self.report_introducers();
+        }
+
+        self.request_tags();
+    }
+}
+
+impl<R: Runtime> Stream for RelayManager<R> {
+    type Item = RelayManagerEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match self.event_rx.poll_recv(cx) {
+                Poll::Pending => break,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Ready(Some(event)) => self.on_event(event),
+            }
+        }
+
+        if self.maintenance_timer.poll_unpin(cx).is_ready() {
+            self.maintain();
+
+            self.maintenance_timer = R::timer(MAINTENANCE_INTERVAL);
+            let _ = self.maintenance_timer.poll_unpin(cx);
+        }
+
+        if let Some(event) = self.pending_events.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+
+        self.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crypto::base64_encode,
+        events::EventManager,
+        primitives::{RouterInfoBuilder, Str, TransportKind},
+        profile::ProfileStorage,
+        runtime::mock::MockRuntime,
+        transport::ssu2::message::{HeaderKind, HeaderReader},
+    };
+    use futures::StreamExt;
+    use std::net::Ipv4Addr;
+
+    /// Router taking part in a relayed connection.
+    struct TestRouter {
+        /// Socket address of the router.
+        address: SocketAddr,
+
+        /// Relay handles of active sessions.
+        handles: HashMap<RouterId, RelayHandle>,
+
+        /// Intro key of the router.
+        intro_key: [u8; 32],
+
+        /// Relay manager.
+        manager: RelayManager<MockRuntime>,
+
+        /// RX channel for receiving out-of-session packets.
+        pkt_rx: Receiver<Packet>,
+
+        /// ID of the router.
+        router_id: RouterId,
+
+        /// Router info of the router.
+        router_info: RouterInfo,
+
+        /// Serialized router info of the router.
+        serialized: Bytes,
+    }
+
+    impl TestRouter {
+        /// Create new [`TestRouter`].
+        ///
+        /// `routers` are the serialized router infos stored in the router's profile storage.
+        fn new(port: u16, publish: bool, routers: &[Vec<u8>]) -> Self {
+            let intro_key = {
+                let mut intro_key = [0u8; 32];
+                MockRuntime::rng().fill_bytes(&mut intro_key);
+                intro_key
+            };
+            let (router_info, static_key, signing_key) = RouterInfoBuilder::default()
+                .with_ssu2(crate::Ssu2Config {
+                    port,
+                    ipv4_host: Some(Ipv4Addr::new(127, 0, 0, 1)),
+                    ipv6_host: None,
+                    ipv4: true,
+                    ipv6: false,
+                    publish,
+                    static_key: [port as u8; 32],
+                    intro_key,
+                })
+                .build();
+            let serialized = Bytes::from(router_info.serialize(&signing_key));
+            let (_event_mgr, _event_subscriber, event_handle) = EventManager::new(None);
+            let (pkt_tx, pkt_rx) = channel(64);
+
+            Self {
+                address: SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), port),
+                handles: HashMap::new(),
+                intro_key,
+                manager: RelayManager::new(
+                    intro_key,
+                    pkt_tx,
+                    RouterContext::new(
+                        MockRuntime::register_metrics(vec![], None),
+                        ProfileStorage::new(routers, &[]),
+                        router_info.identity.id(),
+                        serialized.clone(),
+                        static_key,
+                        signing_key,
+                        2u8,
+                        event_handle,
+                    ),
+                ),
+                pkt_rx,
+                router_id: router_info.identity.id(),
+                router_info,
+                serialized,
+            }
+        }
+
+        /// Get the next event emitted by the relay manager, if any.
+        fn event(&mut self) -> Option<RelayManagerEvent> {
+            self.manager.pending_events.pop_front()
+        }
+
+        /// Get router info of the router with `introducers` published, as seen by other routers.
+        fn introduced_router_info(&self, introducers: &[Introducer]) -> RouterInfo {
+            let mut router_info = self.router_info.clone();
+            router_info
+                .addresses
+                .get_mut(&TransportKind::Ssu2V4)
+                .unwrap()
+                .set_introducers(introducers);
+
+            RouterInfo::parse(router_info.serialize(self.manager.router_ctx.signing_key())).unwrap()
+        }
+    }
+
+    /// Register an active session to `second` for `first`.
+    fn add_session(
+        first: &mut TestRouter,
+        second: &TestRouter,
+        external_address: Option<SocketAddr>,
+        router_info: Option<RouterInfo>,
+    ) {
+        let handle = first.manager.add_session(
+            second.router_id.clone(),
+            external_address,
+            router_info,
+            Some(second.serialized.clone()),
+        );
+        first.handles.insert(second.router_id.clone(), handle);
+    }
+
+    /// Register an active session between `first` and `second`.
+    fn connect(first: &mut TestRouter, second: &mut TestRouter) {
+        let (address, router_info) = (first.address, first.router_info.clone());
+
+        add_session(
+            first,
+            second,
+            Some(address),
+            Some(second.router_info.clone()),
+        );
+        add_session(second, first, Some(second.address), Some(router_info));
+    }
+
+    /// Receive the next in-session message `from` sent to `to`.
+    fn recv(from: &TestRouter, to: &TestRouter) -> (RelayMessage, Option<Bytes>) {
+        match from.handles[&to.router_id].cmd_rx.try_recv().unwrap() {
+            RelayCommand::Send {
+                message,
+                router_info,
+            } => (message, router_info),
+            RelayCommand::Dummy => panic!("invalid command"),
+        }
+    }
+
+    /// Send in-session `message` from `from` to `to`.
+    fn send(
+        from: &TestRouter,
+        to: &mut TestRouter,
+        message: RelayMessage,
+        router_info: Option<Bytes>,
+    ) {
+        to.manager.on_event(RelayEvent::Message {
+            router_id: from.router_id.clone(),
+            message,
+            router_info: router_info
+                .map(|router_info| Box::new(RouterInfo::parse(router_info).unwrap())),
+        });
+    }
+
+    /// Deliver the next in-session message `from` sent to `to` and return the message.
+    fn deliver(from: &TestRouter, to: &mut TestRouter) -> RelayMessage {
+        let (message, router_info) = recv(from, to);
+        send(from, to, message.clone(), router_info);
+
+        message
+    }
+
+    /// Deliver the next hole punch `from` sent to `to` and return the destination of the packet.
+    fn deliver_hole_punch(from: &TestRouter, to: &mut TestRouter) -> SocketAddr {
+        let Packet {
+            mut pkt,
+            address: destination,
+        } = from.pkt_rx.try_recv().unwrap();
+
+        let mut reader = HeaderReader::new(to.intro_key, &mut pkt).unwrap();
+        let _dst_id = reader.dst_id();
+
+        let pkt_num = match reader.parse(to.intro_key).unwrap() {
+            HeaderKind::HolePunch { pkt_num, .. } => pkt_num,
+            kind => panic!("invalid header kind: {kind:?}"),
+        };
+        to.manager.handle_packet(pkt, pkt_num, from.address);
+
+        destination
+    }
+
+    /// Issue relay tag from `bob` to `charlie` and return the tag.
+    fn issue_tag(bob: &mut TestRouter, charlie: &mut TestRouter) -> u32 {
+        assert!(core::matches!(
+            deliver(charlie, bob),
+            RelayMessage::TagRequest
+        ));
+
+        match deliver(bob, charlie) {
+            RelayMessage::Tag { tag } => tag,
+            message => panic!("invalid message: {message:?}"),
+        }
+    }
+
+    /// Get the introducers reported by `router`.
+    fn introducers(router: &mut TestRouter) -> Vec<Introducer> {
+        match router.event() {
+            Some(RelayManagerEvent::IntroducersChanged { introducers }) => introducers,
+            event => panic!("invalid event: {event:?}"),
+        }
+    }
+
+    /// Create Alice, Bob and Charlie where Charlie is firewalled and has received a relay tag
+    /// from Bob.
+    ///
+    /// Returns the routers and Charlie's router info with Bob as the introducer.
+    fn setup() -> (TestRouter, TestRouter, TestRouter, RouterInfo) {
+        let mut alice = TestRouter::new(8888, true, &[]);
+        let mut bob = TestRouter::new(9999, true, &[]);
+        let mut charlie = TestRouter::new(7777, false, &[]);
+
+        connect(&mut alice, &mut bob);
+        connect(&mut bob, &mut charlie);
+        charlie.manager.set_firewall_status(FirewallStatus::Firewalled);
+        issue_tag(&mut bob, &mut charlie);
+
+        let introducers = introducers(&mut charlie);
+        let router_info = charlie.introduced_router_info(&introducers);
+
+        (alice, bob, charlie, router_info)
+    }
+
+    /// Send relay request from Alice to Bob and relay it to Charlie.
+    ///
+    /// Returns the response Charlie sent to Bob.
+    fn relay(
+        alice: &mut TestRouter,
+        bob: &mut TestRouter,
+        charlie: &mut TestRouter,
+        router_info: RouterInfo,
+    ) -> RelayResponse {
+        alice.manager.connect(router_info, true, false);
+
+        assert!(core::matches!(
+            deliver(alice, bob),
+            RelayMessage::Request(_)
+        ));
+        assert!(core::matches!(
+            deliver(bob, charlie),
+            RelayMessage::Intro(_)
+        ));
+
+        match deliver(charlie, bob) {
+            RelayMessage::Response(response) => response,
+            message => panic!("invalid message: {message:?}"),
+        }
+    }
+
+    /// Assert that the relayed connection of `alice` to `charlie` failed.
+    fn assert_connection_failure(alice: &mut TestRouter, charlie: &RouterId) {
+        match alice.event() {
+            Some(RelayManagerEvent::ConnectionFailure { router_id }) =>
+                assert_eq!(&router_id, charlie),
+            event => panic!("invalid event: {event:?}"),
+        }
+        assert!(alice.manager.pending_relays.is_empty());
+    }
+
+    #[tokio::test]
+    async fn relay_tag_issued() {
+        let mut bob = TestRouter::new(9999, true, &[]);
+        let mut charlie = TestRouter::new(7777, false, &[]);
+
+        connect(&mut bob, &mut charlie);
+        assert!(charlie.handles[&bob.router_id].cmd_rx.try_recv().is_err());
+
+        charlie.manager.set_firewall_status(FirewallStatus::Firewalled);
+        let tag = issue_tag(&mut bob, &mut charlie);
+        assert_ne!(tag, 0);
+        assert_eq!(bob.manager.issued_tags.get(&tag), Some(&charlie.router_id));
+
+        match charlie.manager.next().await {
+            Some(RelayManagerEvent::IntroducersChanged { introducers }) => {
+                assert_eq!(introducers.len(), 1);
+                assert_eq!(introducers[0].router_id, bob.router_id);
+                assert_eq!(introducers[0].tag, tag);
+                assert_eq!(
+                    introducers[0].expires,
+                    (MockRuntime::time_since_epoch() + INTRODUCER_EXPIRATION).as_secs() as u32
+                );
+            }
+            event => panic!("invalid event: {event:?}"),
+        }
+
+        // same tag is issued if charlie requests a tag again
+        send(&charlie, &mut bob, RelayMessage::TagRequest, None);
+        assert!(core::matches!(
+            recv(&bob, &charlie).0,
+            RelayMessage::Tag { tag: reissued } if reissued == tag
+        ));
+        assert_eq!(bob.manager.issued_tags.len(), 1);
+
+        // unrequested tag is ignored
+        send(&bob, &mut charlie, RelayMessage::Tag { tag: tag + 1 }, None);
+        assert_eq!(charlie.manager.introducers[&bob.router_id].tag, tag);
+        assert!(charlie.event().is_none());
+
+        // issued tag is forgotten when the session to charlie is closed
+        bob.manager.remove_session(&charlie.router_id);
+        assert!(bob.manager.issued_tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn introducer_selection() {
+        let mut charlie = TestRouter::new(7777, false, &[]);
+        let mut bobs = (0..4).map(|i| TestRouter::new(9000 + i, true, &[])).collect::<Vec<_>>();
+        charlie.manager.set_firewall_status(FirewallStatus::Firewalled);
+
+        // routers without router info, without an external address or without relay support
+        // are not selected as introducers
+        let no_router_info = TestRouter::new(8000, true, &[]);
+        let no_external_address = TestRouter::new(8001, true, &[]);
+        let no_relay = TestRouter::new(8002, true, &[]);
+        let mut router_info = no_relay.router_info.clone();
+        router_info
+            .addresses
+            .get_mut(&TransportKind::Ssu2V4)
+            .unwrap()
+            .options
+            .insert(Str::from("caps"), Str::from("B"));
+
+        let address = charlie.address;
+        add_session(&mut charlie, &no_router_info, Some(address), None);
+        add_session(
+            &mut charlie,
+            &no_external_address,
+            None,
+            Some(no_external_address.router_info.clone()),
+        );
+        add_session(&mut charlie, &no_relay, Some(address), Some(router_info));
+        assert!(charlie.manager.requested_tags.is_empty());
+
+        // at most three introducers are requested
+        for bob in &mut bobs {
+            connect(&mut charlie, bob);
+        }
+        assert_eq!(charlie.manager.requested_tags.len(), MAX_INTRODUCERS);
+
+        let (mut requested, mut spare): (Vec<_>, Vec<_>) = bobs
+            .into_iter()
+            .partition(|bob| charlie.manager.requested_tags.contains_key(&bob.router_id));
+
+        for bob in &mut requested {
+            issue_tag(bob, &mut charlie);
+            let _ = introducers(&mut charlie);
+        }
+        assert_eq!(charlie.manager.introducers.len(), MAX_INTRODUCERS);
+        assert!(charlie.manager.requested_tags.is_empty());
+
+        // introducer is replaced when its session is closed
+        charlie.manager.remove_session(&requested[0].router_id);
+        assert_eq!(introducers(&mut charlie).len(), MAX_INTRODUCERS - 1);
+
+        issue_tag(&mut spare[0], &mut charlie);
+        let introducers = introducers(&mut charlie);
+        assert_eq!(introducers.len(), MAX_INTRODUCERS);
+        assert!(introducers.iter().any(|introducer| introducer.router_id == spare[0].router_id));
+
+        // introducers are removed once the router is reachable
+        charlie.manager.set_firewall_status(FirewallStatus::Ok);
+        match charlie.event() {
+            Some(RelayManagerEvent::IntroducersChanged { introducers }) =>
+                assert!(introducers.is_empty()),
+            event => panic!("invalid event: {event:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn introducers_expire_and_refresh() {
+        let now = MockRuntime::time_since_epoch();
+        let mut bob = TestRouter::new(9999, true, &[]);
+        let mut charlie = TestRouter::new(7777, false, &[]);
+
+        connect(&mut bob, &mut charlie);
+        charlie.manager.set_firewall_status(FirewallStatus::Firewalled);
+        let tag = issue_tag(&mut bob, &mut charlie);
+        let expires = introducers(&mut charlie)[0].expires;
+
+        // introducer is kept until it's about to expire
+        MockRuntime::set_time(Some(
+            now + INTRODUCER_EXPIRATION - INTRODUCER_REFRESH_THRESHOLD - Duration::from_secs(10),
+        ));
+        charlie.manager.maintain();
+        assert!(charlie.event().is_none());
+        assert!(charlie.handles[&bob.router_id].cmd_rx.try_recv().is_err());
+
+        // introducer is removed and a new relay tag is requested
+        MockRuntime::set_time(Some(
+            now + INTRODUCER_EXPIRATION - INTRODUCER_REFRESH_THRESHOLD + Duration::from_secs(10),
+        ));
+        charlie.manager.maintain();
+        assert!(introducers(&mut charlie).is_empty());
+        assert_eq!(issue_tag(&mut bob, &mut charlie), tag);
+
+        let introducers = introducers(&mut charlie);
+        assert_eq!(introducers.len(), 1);
+        assert!(introducers[0].expires > expires);
+
+        MockRuntime::set_time(None);
+    }
+
+    #[tokio::test]
+    async fn introducers_published() {
+        let mut bobs = (0..2).map(|i| TestRouter::new(9000 + i, true, &[])).collect::<Vec<_>>();
+        let mut charlie = TestRouter::new(7777, false, &[]);
+
+        for bob in &mut bobs {
+            connect(bob, &mut charlie);
+        }
+        charlie.manager.set_firewall_status(FirewallStatus::Firewalled);
+
+        let mut introducers = Vec::new();
+        for bob in &mut bobs {
+            issue_tag(bob, &mut charlie);
+            introducers = self::introducers(&mut charlie);
+        }
+        assert_eq!(introducers.len(), 2);
+
+        let router_info = charlie.introduced_router_info(&introducers);
+        let address = router_info.ssu2_introduced_address(true, false).unwrap();
+
+        for (i, introducer) in introducers.iter().enumerate() {
+            assert_eq!(
+                address.options.get(&Str::from(format!("ih{i}"))),
+                Some(&Str::from(base64_encode(introducer.router_id.to_vec())))
+            );
+            assert_eq!(
+                address.options.get(&Str::from(format!("itag{i}"))),
+                Some(&Str::from(introducer.tag.to_string()))
+            );
+            assert_eq!(
+                address.options.get(&Str::from(format!("iexp{i}"))),
+                Some(&Str::from(introducer.expires.to_string()))
+            );
+        }
+        assert!(address.options.get(&Str::from("ih2")).is_none());
+        assert_eq!(address.introducers(), introducers);
+
+        // unpublishing the introducers makes the router undialable
+        let router_info = charlie.introduced_router_info(&[]);
+        assert!(router_info.ssu2_introduced_address(true, false).is_none());
+        assert!(router_info.ssu2_address(true, false).is_none());
+    }
+
+    #[tokio::test]
+    async fn relay_accepted_with_hole_punch() {
+        let (mut alice, mut bob, mut charlie, router_info) = setup();
+
+        let response = relay(&mut alice, &mut bob, &mut charlie, router_info);
+        assert_eq!(response.code, RelayCode::Accept);
+        assert_eq!(response.address, Some(charlie.address));
+        assert!(charlie.manager.has_token(response.token.unwrap()));
+
+        // hole punch is sent to alice's address
+        assert_eq!(deliver_hole_punch(&charlie, &mut alice), alice.address);
+
+        match alice.manager.next().await {
+            Some(RelayManagerEvent::Connect {
+                router_info,
+                address,
+                token,
+            }) => {
+                assert_eq!(router_info.identity.id(), charlie.router_id);
+                assert_eq!(address, charlie.address);
+                assert_eq!(Some(token), response.token);
+            }
+            event => panic!("invalid event: {event:?}"),
+        }
+
+        // response relayed by bob is ignored as the hole punch was received first
+        assert!(core::matches!(
+            deliver(&bob, &mut alice),
+            RelayMessage::Response(_)
+        ));
+        assert!(bob.manager.relayed.is_empty());
+        assert!(alice.event().is_none());
+    }
+
+    #[tokio::test]
+    async fn relay_accepted_through_bob() {
+        let (mut alice, mut bob, mut charlie, router_info) = setup();
+
+        let response = relay(&mut alice, &mut bob, &mut charlie, router_info);
+        assert!(core::matches!(
+            deliver(&bob, &mut alice),
+            RelayMessage::Response(relayed) if relayed == response
+        ));
+
+        match alice.event() {
+            Some(RelayManagerEvent::Connect { address, token, .. }) => {
+                assert_eq!(address, charlie.address);
+                assert_eq!(Some(token), response.token);
+            }
+            event => panic!("invalid event: {event:?}"),
+        }
+
+        // hole punch is ignored as the response was received from bob first
+        deliver_hole_punch(&charlie, &mut alice);
+        assert!(alice.event().is_none());
+    }
+
+    #[tokio::test]
+    async fn connect_through_introducer() {
+        let (_, mut bob, mut charlie, router_info) = setup();
+        let mut alice = TestRouter::new(8888, true, &[bob.serialized.to_vec()]);
+
+        // alice doesn't have a session to bob
+        alice.manager.connect(router_info, true, false);
+        match alice.event() {
+            Some(RelayManagerEvent::ConnectIntroducer { router_info }) =>
+                assert_eq!(router_info.identity.id(), bob.router_id),
+            event => panic!("invalid event: {event:?}"),
+        }
+        assert!(alice.manager.pending_introductions.contains_key(&charlie.router_id));
+
+        // relay request is sent once the session to bob is opened
+        connect(&mut alice, &mut bob);
+        assert!(alice.manager.pending_introductions.is_empty());
+        assert!(core::matches!(
+            deliver(&alice, &mut bob),
+            RelayMessage::Request(_)
+        ));
+        assert!(core::matches!(
+            deliver(&bob, &mut charlie),
+            RelayMessage::Intro(_)
+        ));
+        assert!(core::matches!(
+            deliver(&charlie, &mut bob),
+            RelayMessage::Response(_)
+        ));
+
+        deliver_hole_punch(&charlie, &mut alice);
+        assert!(core::matches!(
+            alice.event(),
+            Some(RelayManagerEvent::Connect { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn connect_through_introducer_timeout() {
+        let (_, bob, charlie, router_info) = setup();
+        let mut alice = TestRouter::new(8888, true, &[bob.serialized.to_vec()]);
+
+        alice.manager.connect(router_info, true, false);
+        assert!(core::matches!(
+            alice.event(),
+            Some(RelayManagerEvent::ConnectIntroducer { .. })
+        ));
+
+        alice.manager.pending_introductions.get_mut(&charlie.router_id).unwrap().started =
+            MockRuntime::now().subtract(RELAY_TIMEOUT + Duration::from_secs(1));
+        alice.manager.maintain();
+        assert!(alice.manager.pending_introductions.is_empty());
+        assert_connection_failure(&mut alice, &charlie.router_id);
+    }
+
+    #[tokio::test]
+    async fn no_usable_introducer() {
+        let (mut alice, _bob, charlie, _) = setup();
+
+        // introducer has expired
+        let router_info = charlie.introduced_router_info(
+            &charlie
+                .manager
+                .introducers
+                .values()
+                .map(|introducer| Introducer {
+                    expires: MockRuntime::time_since_epoch().as_secs() as u32 - 1,
+                    ..introducer.clone()
+                })
+                .collect::<Vec<_>>(),
+        );
+        alice.manager.connect(router_info, true, false);
+        assert_connection_failure(&mut alice, &charlie.router_id);
+
+        // introducer is not known
+        let mut charlie = TestRouter::new(7778, false, &[]);
+        let router_info = charlie.introduced_router_info(&[Introducer {
+            router_id: RouterId::random(),
+            tag: 1337,
+            expires: (MockRuntime::time_since_epoch() + INTRODUCER_EXPIRATION).as_secs() as u32,
+        }]);
+        alice.manager.connect(router_info, true, false);
+        assert_connection_failure(&mut alice, &charlie.router_id);
+        assert!(charlie.event().is_none());
+    }
+
+    #[tokio::test]
+    async fn relay_timeout() {
+        let (mut alice, mut bob, charlie, router_info) = setup();
+
+        alice.manager.connect(router_info, true, false);
+        assert!(core::matches!(
+            deliver(&alice, &mut bob),
+            RelayMessage::Request(_)
+        ));
+        assert_eq!(bob.manager.relayed.len(), 1);
+
+        for relay in alice.manager.pending_relays.values_mut() {
+            relay.started = MockRuntime::now().subtract(RELAY_TIMEOUT + Duration::from_secs(1));
+        }
+        alice.manager.maintain();
+        assert_connection_failure(&mut alice, &charlie.router_id);
+
+        for request in bob.manager.relayed.values_mut() {
+            request.started = MockRuntime::now().subtract(RELAY_TIMEOUT + Duration::from_secs(1));
+        }
+        bob.manager.maintain();
+        assert!(bob.manager.relayed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn token_expiration() {
+        let (mut alice, mut bob, mut charlie, router_info) = setup();
+
+        let token = relay(&mut alice, &mut bob, &mut charlie, router_info).token.unwrap();
+        assert!(charlie.manager.has_token(token));
+
+        *charlie.manager.tokens.get_mut(&token).unwrap() =
+            MockRuntime::now().subtract(TOKEN_EXPIRATION + Duration::from_secs(1));
+        assert!(!charlie.manager.has_token(token));
+
+        charlie.manager.maintain();
+        assert!(charlie.manager.tokens.is_empty());
+
+        // token is removed after it has been used
+        let (mut alice, mut bob, mut charlie, router_info) = setup();
+        let token = relay(&mut alice, &mut bob, &mut charlie, router_info).token.unwrap();
+
+        charlie.manager.remove_token(token);
+        assert!(!charlie.manager.has_token(token));
+    }
+
+    #[tokio::test]
+    async fn bob_rejects_relay_request() {
+        // relay tag not found
+        {
+            let (mut alice, mut bob, charlie, _) = setup();
+            let router_info = charlie.introduced_router_info(&[Introducer {
+                tag: charlie.manager.introducers[&bob.router_id].tag.wrapping_add(1),
+                ..charlie.manager.introducers[&bob.router_id].clone()
+            }]);
+
+            alice.manager.connect(router_info, true, false);
+            deliver(&alice, &mut bob);
+            assert!(core::matches!(
+                deliver(&bob, &mut alice),
+                RelayMessage::Response(RelayResponse {
+                    code: RelayCode::BobRelayTagNotFound,
+                    ..
+                })
+            ));
+            assert_connection_failure(&mut alice, &charlie.router_id);
+        }
+
+        // charlie is not connected
+        {
+            let (mut alice, mut bob, charlie, router_info) = setup();
+            bob.manager.remove_session(&charlie.router_id);
+
+            alice.manager.connect(router_info, true, false);
+            deliver(&alice, &mut bob);
+            assert!(core::matches!(
+                deliver(&bob, &mut alice),
+                RelayMessage::Response(RelayResponse {
+                    code: RelayCode::BobRelayTagNotFound,
+                    ..
+                })
+            ));
+            assert_connection_failure(&mut alice, &charlie.router_id);
+        }
+
+        // invalid signature
+        {
+            let (mut alice, mut bob, charlie, router_info) = setup();
+
+            alice.manager.connect(router_info, true, false);
+            let RelayMessage::Request(mut request) = recv(&alice, &bob).0 else {
+                panic!("invalid message");
+            };
+            request.signature[0] ^= 1;
+            send(&alice, &mut bob, RelayMessage::Request(request), None);
+
+            assert!(core::matches!(
+                deliver(&bob, &mut alice),
+                RelayMessage::Response(RelayResponse {
+                    code: RelayCode::BobSignatureFailure,
+                    ..
+                })
+            ));
+            assert_connection_failure(&mut alice, &charlie.router_id);
+        }
+
+        // relay request with the same nonce is received twice
+        {
+            let (mut alice, mut bob, charlie, router_info) = setup();
+
+            alice.manager.connect(router_info, true, false);
+            let message = deliver(&alice, &mut bob);
+            send(&alice, &mut bob, message, None);
+            assert!(core::matches!(
+                recv(&bob, &charlie).0,
+                RelayMessage::Intro(_)
+            ));
+
+            assert!(core::matches!(
+                deliver(&bob, &mut alice),
+                RelayMessage::Response(RelayResponse {
+                    code: RelayCode::BobLimitExceeded,
+                    ..
+                })
+            ));
+            assert_connection_failure(&mut alice, &charlie.router_id);
+        }
+    }
+
+    #[tokio::test]
+    async fn charlie_rejects_relay_intro() {
+        // alice is connected to charlie
+        {
+            let (mut alice, mut bob, mut charlie, router_info) = setup();
+            connect(&mut alice, &mut charlie);
+
+            let response = relay(&mut alice, &mut bob, &mut charlie, router_info);
+            assert_eq!(response.code, RelayCode::CharlieAliceConnected);
+            assert_eq!(response.token, None);
+            assert!(charlie.manager.tokens.is_empty());
+            assert!(charlie.pkt_rx.try_recv().is_err());
+
+            deliver(&bob, &mut alice);
+            assert_connection_failure(&mut alice, &charlie.router_id);
+        }
+
+        // alice's router info is not known
+        {
+            let (mut alice, mut bob, mut charlie, router_info) = setup();
+
+            alice.manager.connect(router_info, true, false);
+            deliver(&alice, &mut bob);
+            let (message, _) = recv(&bob, &charlie);
+            send(&bob, &mut charlie, message, None);
+
+            assert!(core::matches!(
+                recv(&charlie, &bob).0,
+                RelayMessage::Response(RelayResponse {
+                    code: RelayCode::CharlieAliceUnknown,
+                    ..
+                })
+            ));
+            assert!(charlie.pkt_rx.try_recv().is_err());
+        }
+
+        // invalid signature
+        {
+            let (mut alice, mut bob, mut charlie, router_info) = setup();
+
+            alice.manager.connect(router_info, true, false);
+            deliver(&alice, &mut bob);
+            let (RelayMessage::Intro(mut intro), router_info) = recv(&bob, &charlie) else {
+                panic!("invalid message");
+            };
+            intro.request.signature[0] ^= 1;
+            send(&bob, &mut charlie, RelayMessage::Intro(intro), router_info);
+
+            assert!(core::matches!(
+                recv(&charlie, &bob).0,
+                RelayMessage::Response(RelayResponse {
+                    code: RelayCode::CharlieSignatureFailure,
+                    ..
+                })
+            ));
+            assert!(charlie.pkt_rx.try_recv().is_err());
+        }
+
+        // relay tag wasn't issued by bob
+        {
+            let (mut alice, mut bob, mut charlie, router_info) = setup();
+
+            alice.manager.connect(router_info, true, false);
+            deliver(&alice, &mut bob);
+            let (RelayMessage::Intro(mut intro), router_info) = recv(&bob, &charlie) else {
+                panic!("invalid message");
+            };
+            intro.request.relay_tag = intro.request.relay_tag.wrapping_add(1);
+            send(&bob, &mut charlie, RelayMessage::Intro(intro), router_info);
+
+            assert!(core::matches!(
+                recv(&charlie, &bob).0,
+                RelayMessage::Response(RelayResponse {
+                    code: RelayCode::CharlieUnspecified,
+                    ..
+                })
+            ));
+            assert!(charlie.pkt_rx.try_recv().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_relay_response_ignored() {
+        let (mut alice, mut bob, mut charlie, router_info) = setup();
+
+        let response = relay(&mut alice, &mut bob, &mut charlie, router_info);
+        let _ = bob.handles[&alice.router_id].cmd_rx.try_recv().unwrap();
+
+        // response with an invalid signature
+        let mut invalid = response.clone();
+        invalid.signature[0] ^= 1;
+        send(&bob, &mut alice, RelayMessage::Response(invalid), None);
+        assert!(alice.event().is_none());
+
+        // response from a router other than bob
+        send(
+            &charlie,
+            &mut alice,
+            RelayMessage::Response(response.clone()),
+            None,
+        );
+        assert!(alice.event().is_none());
+        assert_eq!(alice.manager.pending_relays.len(), 1);
+
+        send(&bob, &mut alice, RelayMessage::Response(response), None);
+        assert!(core::matches!(
+            alice.event(),
+            Some(RelayManagerEvent::Connect { .. })
+        ));
+    }
+}
@@ -101,6 +101,11 @@ impl RemoteAckManager {
         }
     }
 
+    /// Get highest seen packet number.
+    pub fn highest_seen(&self) -> u32 {
+        self.highest_seen
+    }
+
     /// Register ACK-eliciting packet.
     pub fn register_pkt(&mut self, pkt_num: u32) {
         // next expected packet number
@@ -17,16 +17,22 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
+    bandwidth::BandwidthLimiter,
     crypto::chachapoly::ChaChaPoly,
     error::Ssu2Error,
     i2np::Message,
-    primitives::RouterId,
+    primitives::{RouterId, RouterInfo},
     runtime::{Counter, MetricsHandle, Runtime},
     subsystem::{SubsystemCommand, SubsystemHandle},
     transport::{
         ssu2::{
-            message::{data::DataMessageBuilder, Block, HeaderKind, HeaderReader},
+            message::{
+                data::DataMessageBuilder, peer_test::PeerTestMessage, Block, HeaderKind,
+                HeaderReader,
+            },
             metrics::*,
+            peer_test::{PeerTestCommand, PeerTestEvent, PeerTestHandle},
+            relay::{RelayCommand, RelayEvent, RelayHandle, RelayMessage},
             session::{
                 active::{
                     ack::{AckInfo, RemoteAckManager},
@@ -43,10 +49,12 @@ use crate::{
     },
 };
 
+use bytes::Bytes;
 use futures::FutureExt;
+use rand_core::RngCore;
 use thingbuf::mpsc::{channel, Receiver, Sender};
 
-use alloc::{sync::Arc, vec, vec::Vec};
+use alloc::{boxed::Box, sync::Arc, vec, vec::Vec};
 use core::{
     cmp::min,
     future::Future,
@@ -84,6 +92,32 @@ const MAX_ACK_TIMEOUT: Duration = Duration::from_millis(150);
 /// How often should an immediate ACK be bundled in a message.
 const IMMEDIATE_ACK_INTERVAL: u32 = 10u32;
 
+/// Path challenge size.
+const PATH_CHALLENGE_SIZE: usize = 8usize;
+
+/// How long is a path challenge waited for a response before it's resent.
+const PATH_CHALLENGE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Maximum number of path challenges sent before path validation is abandoned.
+const MAX_PATH_CHALLENGES: usize = 3usize;
+
+/// Path validation for a new socket address of the remote router.
+///
+/// Packets are sent to the old address until the new address has been validated.
+struct PathValidation<R: Runtime> {
+    /// Socket address that is being validated.
+    address: SocketAddr,
+
+    /// Challenge sent to `address`.
+    challenge: Vec<u8>,
+
+    /// Number of path challenges sent.
+    num_sent: usize,
+
+    /// Retry timer.
+    timer: R::Timer,
+}
+
 /// ACK timer.
 ///
 /// Keeps track and allows scheduling both while respecting the priority of an immediate ACK.
@@ -182,6 +216,9 @@ pub struct Ssu2Session<R: Runtime> {
     /// Socket address of the remote router.
     address: SocketAddr,
 
+    /// Bandwidth limiter.
+    bandwidth_limiter: BandwidthLimiter<R>,
+
     /// RX channel for receiving messages from subsystems.
     cmd_rx: Receiver<SubsystemCommand>,
 
@@ -208,6 +245,12 @@ pub struct Ssu2Session<R: Runtime> {
     /// Metrics handle.
     metrics: R::MetricsHandle,
 
+    /// Peer test handle.
+    peer_test_handle: PeerTestHandle,
+
+    /// Path validation, if the remote router's address is being migrated.
+    path_validation: Option<PathValidation<R>>,
+
     /// Next packet number.
     pkt_num: Arc<AtomicU32>,
 
@@ -219,9 +262,15 @@ pub struct Ssu2Session<R: Runtime> {
     // TODO: `R::UdpSocket` should be clonable
     pkt_tx: Sender<Packet>,
 
+    /// Read throttle timer, set if inbound bandwidth has been exhausted.
+    read_throttle: Option<R::Timer>,
+
     /// Key context for inbound packets.
     recv_key_ctx: KeyContext,
 
+    /// Relay handle.
+    relay_handle: RelayHandle,
+
     /// Remote ACK manager.
     remote_ack: RemoteAckManager,
 
@@ -239,6 +288,9 @@ pub struct Ssu2Session<R: Runtime> {
 
     /// Transmission manager.
     transmission: TransmissionManager<R>,
+
+    /// Write throttle timer, set if outbound bandwidth has been exhausted.
+    write_throttle: Option<R::Timer>,
 }
 
 impl<R: Runtime> Ssu2Session<R> {
@@ -247,6 +299,9 @@ impl<R: Runtime> Ssu2Session<R> {
         context: Ssu2SessionContext,
         pkt_tx: Sender<Packet>,
         subsystem_handle: SubsystemHandle,
+        peer_test_handle: PeerTestHandle,
+        relay_handle: RelayHandle,
+        bandwidth_limiter: BandwidthLimiter<R>,
         metrics: R::MetricsHandle,
     ) -> Self {
         let (cmd_tx, cmd_rx) = channel(CMD_CHANNEL_SIZE);
@@ -262,6 +317,7 @@ impl<R: Runtime> Ssu2Session<R> {
         Self {
             ack_timer: AckTimer::<R>::new(),
             address: context.address,
+            bandwidth_limiter,
             cmd_rx,
             cmd_tx,
             dst_id: context.dst_id,
@@ -270,16 +326,21 @@ impl<R: Runtime> Ssu2Session<R> {
             intro_key: context.intro_key,
             last_immediate_ack: 0u32,
             metrics: metrics.clone(),
+            path_validation: None,
+            peer_test_handle,
             pkt_num: Arc::clone(&pkt_num),
             pkt_rx: context.pkt_rx,
             pkt_tx,
+            read_throttle: None,
             recv_key_ctx: context.recv_key_ctx,
+            relay_handle,
             remote_ack: RemoteAckManager::new(),
             resend_timer: None,
             router_id: context.router_id.clone(),
             send_key_ctx: context.send_key_ctx,
             subsystem_handle,
             transmission: TransmissionManager::<R>::new(context.router_id, pkt_num, metrics),
+            write_throttle: None,
         }
     }
 
@@ -327,7 +388,7 @@ impl<R: Runtime> Ssu2Session<R> {
 
     /// Handle received `pkt` for this session.
     fn handle_packet(&mut self, pkt: Packet) -> Result<(), Ssu2Error> {
-        let Packet { mut pkt, .. } = pkt;
+        let Packet { mut pkt, address } = pkt;
 
         let (pkt_num, immediate_ack) = match HeaderReader::new(self.intro_key, &mut pkt)?
             .parse(self.recv_key_ctx.k_header_2)?
@@ -365,6 +426,13 @@ impl<R: Runtime> Ssu2Session<R> {
             self.ack_timer.schedule_immediate_ack(self.transmission.round_trip_time());
         }
 
+        // only an authenticated packet with the highest packet number seen so far
+        // can start path validation so reordered or replayed packets are ignored
+        let is_newest = pkt_num > self.remote_ack.highest_seen();
+        let mut peer_test = None;
+        let mut relay = None;
+        let mut router_info = None;
+
         for block in Block::parse(&payload).ok_or(Ssu2Error::Malformed)? {
             match block {
                 Block::Termination {
@@ -480,6 +548,57 @@ impl<R: Runtime> Ssu2Session<R> {
                 Block::Address { .. } | Block::DateTime { .. } | Block::Padding { .. } => {
                     self.remote_ack.register_non_ack_eliciting_pkt(pkt_num);
                 }
+                Block::PeerTest { message } => {
+                    self.remote_ack.register_pkt(pkt_num);
+                    self.ack_timer.schedule_ack(self.transmission.round_trip_time());
+
+                    peer_test = Some(message);
+                }
+                Block::RelayTagRequest {} => {
+                    self.remote_ack.register_pkt(pkt_num);
+                    self.ack_timer.schedule_ack(self.transmission.round_trip_time());
+
+                    relay = Some(RelayMessage::TagRequest);
+                }
+                Block::RelayTag { tag } => {
+                    self.remote_ack.register_pkt(pkt_num);
+                    self.ack_timer.schedule_ack(self.transmission.round_trip_time());
+
+                    relay = Some(RelayMessage::Tag { tag });
+                }
+                Block::RelayRequest { message } => {
+                    self.remote_ack.register_pkt(pkt_num);
+                    self.ack_timer.schedule_ack(self.transmission.round_trip_time());
+
+                    relay = Some(RelayMessage::Request(message));
+                }
+                Block::RelayIntro { message } => {
+                    self.remote_ack.register_pkt(pkt_num);
+                    self.ack_timer.schedule_ack(self.transmission.round_trip_time());
+
+                    relay = Some(RelayMessage::Intro(message));
+                }
+                Block::RelayResponse { message } => {
+                    self.remote_ack.register_pkt(pkt_num);
+                    self.ack_timer.schedule_ack(self.transmission.round_trip_time());
+
+                    relay = Some(RelayMessage::Response(message));
+                }
+                Block::PathChallenge { challenge } => {
+                    self.remote_ack.register_pkt(pkt_num);
+                    self.send_path_response(challenge, address);
+                }
+                Block::PathResponse { response } => {
+                    self.remote_ack.register_pkt(pkt_num);
+                    self.ack_timer.schedule_ack(self.transmission.round_trip_time());
+                    self.on_path_response(response, address);
+                }
+                Block::RouterInfo {
+                    router_info: info, ..
+                } => {
+                    self.remote_ack.register_pkt(pkt_num);
+                    router_info = Some(info);
+                }
                 block => {
                     tracing::debug!(
                         target: LOG_TARGET,
@@ -492,9 +611,278 @@ impl<R: Runtime> Ssu2Session<R> {
             }
         }
 
+        match (peer_test, relay) {
+            (Some(message), _) => self.handle_peer_test(message, router_info),
+            (None, Some(message)) => self.handle_relay(message, router_info),
+            (None, None) => {}
+        }
+
+        if is_newest && address != self.address {
+            self.start_path_validation(address);
+        }
+
         Ok(())
     }
 
+    /// Start validating `address` which the remote router started sending packets from.
+    ///
+    /// If `address` is already being validated, the call is a no-op.
+    fn start_path_validation(&mut self, address: SocketAddr) {
+        if self
+            .path_validation
+            .as_ref()
+            .is_some_and(|validation| validation.address == address)
+        {
+            return;
+        }
+
+        tracing::debug!(
+            target: LOG_TARGET,
+            router_id = %self.router_id,
+            old_address = ?self.address,
+            new_address = ?address,
+            "remote router changed address, start path validation",
+        );
+
+        let mut challenge = vec![0u8; PATH_CHALLENGE_SIZE];
+        R::rng().fill_bytes(&mut challenge);
+
+        self.path_validation = Some(PathValidation {
+            address,
+            challenge,
+            num_sent: 0usize,
+            timer: R::timer(PATH_CHALLENGE_TIMEOUT),
+        });
+        self.send_path_challenge();
+    }
+
+    /// Send path challenge to the address that is being validated.
+    fn send_path_challenge(&mut self) {
+        let Some(validation) = &mut self.path_validation else {
+            return;
+        };
+        let AckInfo {
+            highest_seen,
+            num_acks,
+            ranges,
+        } = self.remote_ack.ack_info();
+
+        let pkt = DataMessageBuilder::default()
+            .with_dst_id(self.dst_id)
+            .with_key_context(self.intro_key, &self.send_key_ctx)
+            .with_pkt_num(self.pkt_num.fetch_add(1u32, Ordering::Relaxed))
+            .with_path_challenge(validation.challenge.clone(), validation.address)
+            .with_ack(highest_seen, num_acks, ranges)
+            .build::<R>();
+
+        validation.num_sent += 1;
+        validation.timer = R::timer(PATH_CHALLENGE_TIMEOUT);
+
+        if let Err(error) = self.pkt_tx.try_send(Packet {
+            pkt: pkt.to_vec(),
+            address: validation.address,
+        }) {
+            tracing::warn!(
+                target: LOG_TARGET,
+                router_id = %self.router_id,
+                ?error,
+                "failed to send path challenge",
+            );
+            self.metrics.counter(NUM_DROPS_CHANNEL_FULL).increment(1);
+        }
+    }
+
+    /// Respond to path challenge received from `address`.
+    ///
+    /// The response is sent to the address the challenge was received from, even if it's not yet
+    /// the validated address of the remote router.
+    fn send_path_response(&mut self, challenge: Vec<u8>, address: SocketAddr) {
+        let AckInfo {
+            highest_seen,
+            num_acks,
+            ranges,
+        } = self.remote_ack.ack_info();
+
+        let pkt = DataMessageBuilder::default()
+            .with_dst_id(self.dst_id)
+            .with_key_context(self.intro_key, &self.send_key_ctx)
+            .with_pkt_num(self.pkt_num.fetch_add(1u32, Ordering::Relaxed))
+            .with_path_response(challenge)
+            .with_ack(highest_seen, num_acks, ranges)
+            .build::<R>();
+
+        if let Err(error) = self.pkt_tx.try_send(Packet {
+            pkt: pkt.to_vec(),
+            address,
+        }) {
+            tracing::warn!(
+                target: LOG_TARGET,
+                router_id = %self.router_id,
+                ?error,
+                "failed to send path response",
+            );
+            self.metrics.counter(NUM_DROPS_CHANNEL_FULL).increment(1);
+        }
+    }
+
+    /// Handle path response received from `address`.
+    ///
+    /// If the response matches the pending challenge, the session is migrated to `address`.
+    fn on_path_response(&mut self, response: Vec<u8>, address: SocketAddr) {
+        match self.path_validation.take() {
+            Some(validation)
+                if validation.address == address && validation.challenge == response =>
+            {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    router_id = %self.router_id,
+                    old_address = ?self.address,
+                    new_address = ?address,
+                    "path validated, migrating session",
+                );
+                self.address = address;
+
+                if let Err(error) =
+                    self.peer_test_handle.event_tx.try_send(PeerTestEvent::AddressChanged {
+                        router_id: self.router_id.clone(),
+                        address,
+                    })
+                {
+                    tracing::debug!(
+                        target: LOG_TARGET,
+                        router_id = %self.router_id,
+                        ?error,
+                        "failed to report address change",
+                    );
+                }
+            }
+            validation => {
+                tracing::debug!(
+                    target: LOG_TARGET,
+                    router_id = %self.router_id,
+                    ?address,
+                    "unexpected path response",
+                );
+                self.path_validation = validation;
+            }
+        }
+    }
+
+    /// Forward received relay `message` to `RelayManager`.
+    ///
+    /// `router_info` is the router info of Alice, sent by Bob together with `RelayIntro`.
+    fn handle_relay(&mut self, message: RelayMessage, router_info: Option<Box<RouterInfo>>) {
+        tracing::trace!(
+            target: LOG_TARGET,
+            router_id = %self.router_id,
+            ?message,
+            "received relay message",
+        );
+
+        if let Err(error) = self.relay_handle.event_tx.try_send(RelayEvent::Message {
+            router_id: self.router_id.clone(),
+            message,
+            router_info,
+        }) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                router_id = %self.router_id,
+                ?error,
+                "failed to forward relay message",
+            );
+        }
+    }
+
+    /// Send relay `message` to remote router.
+    ///
+    /// The message is sent in a packet of its own, together with an ACK block.
+    fn send_relay(&mut self, message: RelayMessage, router_info: Option<Bytes>) {
+        let AckInfo {
+            highest_seen,
+            num_acks,
+            ranges,
+        } = self.remote_ack.ack_info();
+
+        let pkt = DataMessageBuilder::default()
+            .with_dst_id(self.dst_id)
+            .with_key_context(self.intro_key, &self.send_key_ctx)
+            .with_pkt_num(self.pkt_num.fetch_add(1u32, Ordering::Relaxed))
+            .with_relay(message.into_block(), router_info.as_deref())
+            .with_ack(highest_seen, num_acks, ranges)
+            .build::<R>();
+
+        if let Err(error) = self.pkt_tx.try_send(Packet {
+            pkt: pkt.to_vec(),
+            address: self.address,
+        }) {
+            tracing::warn!(
+                target: LOG_TARGET,
+                router_id = %self.router_id,
+                ?error,
+                "failed to send relay packet",
+            );
+            self.metrics.counter(NUM_DROPS_CHANNEL_FULL).increment(1);
+        }
+    }
+
+    /// Forward received peer test `message` to `PeerTestManager`.
+    ///
+    /// `router_info` is the router info of Alice or Charlie, sent by Bob together with `message`.
+    fn handle_peer_test(&mut self, message: PeerTestMessage, router_info: Option<Box<RouterInfo>>) {
+        tracing::trace!(
+            target: LOG_TARGET,
+            router_id = %self.router_id,
+            message_num = ?message.message_num,
+            code = ?message.code,
+            "received peer test message",
+        );
+
+        if let Err(error) = self.peer_test_handle.event_tx.try_send(PeerTestEvent::Message {
+            router_id: self.router_id.clone(),
+            message,
+            router_info,
+        }) {
+            tracing::debug!(
+                target: LOG_TARGET,
+                router_id = %self.router_id,
+                ?error,
+                "failed to forward peer test message",
+            );
+        }
+    }
+
+    /// Send peer test `message` to remote router.
+    ///
+    /// The message is sent in a packet of its own, together with an ACK block.
+    fn send_peer_test(&mut self, message: PeerTestMessage, router_info: Option<Bytes>) {
+        let AckInfo {
+            highest_seen,
+            num_acks,
+            ranges,
+        } = self.remote_ack.ack_info();
+
+        let pkt = DataMessageBuilder::default()
+            .with_dst_id(self.dst_id)
+            .with_key_context(self.intro_key, &self.send_key_ctx)
+            .with_pkt_num(self.pkt_num.fetch_add(1u32, Ordering::Relaxed))
+            .with_peer_test(&message, router_info.as_deref())
+            .with_ack(highest_seen, num_acks, ranges)
+            .build::<R>();
+
+        if let Err(error) = self.pkt_tx.try_send(Packet {
+            pkt: pkt.to_vec(),
+            address: self.address,
+        }) {
+            tracing::warn!(
+                target: LOG_TARGET,
+                router_id = %self.router_id,
+                ?error,
+                "failed to send peer test packet",
+            );
+            self.metrics.counter(NUM_DROPS_CHANNEL_FULL).increment(1);
+        }
+    }
+
     /// Send `message` to remote router.
     fn send_message(&mut self, message: Vec<u8>) {
         // TODO: this makes no sense, get unserialized message from subsystem
@@ -635,6 +1023,14 @@ impl<R: Runtime> Future for Ssu2Session<R> {
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         loop {
+            // reading is paused until inbound bandwidth has been refilled
+            if let Some(timer) = &mut self.read_throttle {
+                if timer.poll_unpin(cx).is_pending() {
+                    break;
+                }
+                self.read_throttle = None;
+            }
+
             match self.pkt_rx.poll_recv(cx) {
                 Poll::Pending => break,
                 Poll::Ready(None) => return Poll::Ready(TerminationReason::Unspecified),
@@ -661,9 +1057,21 @@ impl<R: Runtime> Future for Ssu2Session<R> {
                     ),
                 },
             }
+
+            if let Some(delay) = self.bandwidth_limiter.inbound_delay() {
+                self.read_throttle = Some(R::timer(delay));
+            }
         }
 
         while self.transmission.has_capacity() {
+            // writing is paused until outbound bandwidth has been refilled
+            if let Some(timer) = &mut self.write_throttle {
+                if timer.poll_unpin(cx).is_pending() {
+                    break;
+                }
+                self.write_throttle = None;
+            }
+
             match self.cmd_rx.poll_recv(cx) {
                 Poll::Pending => break,
                 Poll::Ready(None) => return Poll::Ready(TerminationReason::Timeout),
@@ -671,6 +1079,32 @@ impl<R: Runtime> Future for Ssu2Session<R> {
                     self.send_message(message),
                 Poll::Ready(Some(SubsystemCommand::Dummy)) => {}
             }
+
+            if let Some(delay) = self.bandwidth_limiter.outbound_delay() {
+                self.write_throttle = Some(R::timer(delay));
+            }
+        }
+
+        loop {
+            match self.peer_test_handle.cmd_rx.poll_recv(cx) {
+                Poll::Pending | Poll::Ready(None) => break,
+                Poll::Ready(Some(PeerTestCommand::Send {
+                    message,
+                    router_info,
+                })) => self.send_peer_test(message, router_info),
+                Poll::Ready(Some(PeerTestCommand::Dummy)) => {}
+            }
+        }
+
+        loop {
+            match self.relay_handle.cmd_rx.poll_recv(cx) {
+                Poll::Pending | Poll::Ready(None) => break,
+                Poll::Ready(Some(RelayCommand::Send {
+                    message,
+                    router_info,
+                })) => self.send_relay(message, router_info),
+                Poll::Ready(Some(RelayCommand::Dummy)) => {}
+            }
         }
 
         loop {
@@ -697,6 +1131,27 @@ impl<R: Runtime> Future for Ssu2Session<R> {
             }
         }
 
+        while let Some(validation) = &mut self.path_validation {
+            if validation.timer.poll_unpin(cx).is_pending() {
+                break;
+            }
+
+            if validation.num_sent < MAX_PATH_CHALLENGES {
+                self.send_path_challenge();
+                continue;
+            }
+
+            let address = validation.address;
+            self.path_validation = None;
+
+            tracing::debug!(
+                target: LOG_TARGET,
+                router_id = %self.router_id,
+                ?address,
+                "path validation timed out",
+            );
+        }
+
         if self.ack_timer.poll_unpin(cx).is_ready() {
             let AckInfo {
                 highest_seen,
@@ -775,6 +1230,10 @@ mod tests {
                 k_header_2: [2u8; 32],
             },
         };
+        let (_peer_test_cmd_tx, peer_test_rx) = channel(16);
+        let (peer_test_tx, _peer_test_event_rx) = channel(16);
+        let (_relay_cmd_tx, relay_rx) = channel(16);
+        let (relay_tx, _relay_event_rx) = channel(16);
 
         let cmd_tx = {
             // register one subsystem, start active session andn poll command handle
@@ -791,6 +1250,15 @@ mod tests {
                     ctx,
                     to_socket_tx,
                     handle,
+                    PeerTestHandle {
+                        cmd_rx: peer_test_rx,
+                        event_tx: peer_test_tx,
+                    },
+                    RelayHandle {
+                        cmd_rx: relay_rx,
+                        event_tx: relay_tx,
+                    },
+                    BandwidthLimiter::default(),
                     MockRuntime::register_metrics(vec![], None),
                 )
                 .run(),
@@ -933,6 +1401,10 @@ mod tests {
                 k_header_2: [2u8; 32],
             },
         };
+        let (_peer_test_cmd_tx, peer_test_rx) = channel(16);
+        let (peer_test_tx, _peer_test_event_rx) = channel(16);
+        let (_relay_cmd_tx, relay_rx) = channel(16);
+        let (relay_tx, _relay_event_rx) = channel(16);
 
         let (cmd_tx, handle) = {
             // register one subsystem, start active session andn poll command handle
@@ -949,6 +1421,15 @@ mod tests {
                     ctx,
                     to_socket_tx,
                     handle,
+                    PeerTestHandle {
+                        cmd_rx: peer_test_rx,
+                        event_tx: peer_test_tx,
+                    },
+                    RelayHandle {
+                        cmd_rx: relay_rx,
+                        event_tx: relay_tx,
+                    },
+                    BandwidthLimiter::default(),
                     MockRuntime::register_metrics(vec![], None),
                 )
                 .run(),
@@ -992,4 +1473,249 @@ mod tests {
             Err(_) => panic!("timeout"),
         }
     }
+
+    /// Start active session with test keys and return handles for interacting with it.
+    async fn start_session() -> (Sender<Packet>, Receiver<Packet>, Sender<SubsystemCommand>) {
+        let (from_socket_tx, from_socket_rx) = channel(128);
+        let (to_socket_tx, to_socket_rx) = channel(128);
+
+        let ctx = Ssu2SessionContext {
+            address: "127.0.0.1:8888".parse().unwrap(),
+            dst_id: 1337u64,
+            intro_key: [1u8; 32],
+            pkt_rx: from_socket_rx,
+            recv_key_ctx: KeyContext {
+                k_data: [2u8; 32],
+                k_header_2: [3u8; 32],
+            },
+            router_id: RouterId::random(),
+            send_key_ctx: KeyContext {
+                k_data: [3u8; 32],
+                k_header_2: [2u8; 32],
+            },
+        };
+        let (_peer_test_cmd_tx, peer_test_rx) = channel(16);
+        let (peer_test_tx, _peer_test_event_rx) = channel(16);
+        let (_relay_cmd_tx, relay_rx) = channel(16);
+        let (relay_tx, _relay_event_rx) = channel(16);
+
+        let (handle, cmd_rx) = {
+            let (cmd_tx, cmd_rx) = channel(16);
+            let mut handle = SubsystemHandle::new();
+            handle.register_subsystem(cmd_tx);
+
+            (handle, cmd_rx)
+        };
+
+        tokio::spawn(
+            Ssu2Session::<MockRuntime>::new(
+                ctx,
+                to_socket_tx,
+                handle,
+                PeerTestHandle {
+                    cmd_rx: peer_test_rx,
+                    event_tx: peer_test_tx,
+                },
+                RelayHandle {
+                    cmd_rx: relay_rx,
+                    event_tx: relay_tx,
+                },
+                BandwidthLimiter::default(),
+                MockRuntime::register_metrics(vec![], None),
+            )
+            .run(),
+        );
+
+        match cmd_rx.recv().await.unwrap() {
+            crate::subsystem::InnerSubsystemEvent::ConnectionEstablished { tx, .. } =>
+                (from_socket_tx, to_socket_rx, tx),
+            _ => panic!("invalid event"),
+        }
+    }
+
+    /// Build packet sent by the remote router, containing `block`.
+    fn remote_packet(pkt_num: u32, block: Option<Block>) -> Vec<u8> {
+        let key_ctx = KeyContext {
+            k_data: [2u8; 32],
+            k_header_2: [3u8; 32],
+        };
+        let builder = DataMessageBuilder::default()
+            .with_dst_id(1337u64)
+            .with_pkt_num(pkt_num)
+            .with_key_context([1u8; 32], &key_ctx)
+            .with_ack(0, 0, None);
+
+        let mut pkt = match block {
+            Some(Block::PathChallenge { challenge }) =>
+                builder.with_path_challenge(challenge, "127.0.0.1:8888".parse().unwrap()),
+            Some(Block::PathResponse { response }) => builder.with_path_response(response),
+            _ => builder,
+        }
+        .build::<MockRuntime>()
+        .to_vec();
+
+        // `Ssu2Socket` decrypts the destination connection ID before dispatching the packet
+        let mut reader = HeaderReader::new([1u8; 32], &mut pkt).unwrap();
+        let _dst_id = reader.dst_id();
+
+        pkt
+    }
+
+    /// Decrypt packet sent by the session and parse its blocks.
+    fn parse_packet(mut pkt: Vec<u8>) -> Vec<Block> {
+        let mut reader = HeaderReader::new([1u8; 32], &mut pkt).unwrap();
+        let _dst_id = reader.dst_id();
+
+        let pkt_num = match reader.parse([2u8; 32]) {
+            Ok(HeaderKind::Data { pkt_num, .. }) => pkt_num,
+            _ => panic!("invalid packet"),
+        };
+
+        let mut payload = pkt[16..].to_vec();
+        ChaChaPoly::with_nonce(&[3u8; 32], pkt_num as u64)
+            .decrypt_with_ad(&pkt[..16], &mut payload)
+            .unwrap();
+
+        Block::parse(&payload).unwrap()
+    }
+
+    #[tokio::test]
+    async fn session_migrated_after_path_validation() {
+        let (from_socket_tx, to_socket_rx, cmd_tx) = start_session().await;
+        let new_address: SocketAddr = "127.0.0.2:9999".parse().unwrap();
+
+        // send packet from a new address and verify that a path challenge is sent to it
+        from_socket_tx
+            .try_send(Packet {
+                pkt: remote_packet(1, None),
+                address: new_address,
+            })
+            .unwrap();
+
+        let Packet { pkt, address } = to_socket_rx.recv().await.unwrap();
+        assert_eq!(address, new_address);
+
+        let challenge = parse_packet(pkt)
+            .into_iter()
+            .find_map(|block| match block {
+                Block::PathChallenge { challenge } => Some(challenge),
+                _ => None,
+            })
+            .expect("path challenge");
+
+        // messages are sent to the old address until the path has been validated
+        cmd_tx
+            .try_send(SubsystemCommand::SendMessage {
+                message: Message {
+                    message_type: MessageType::Data,
+                    message_id: *MessageId::random(),
+                    expiration: MockRuntime::time_since_epoch() + I2NP_MESSAGE_EXPIRATION,
+                    payload: vec![1, 2, 3, 4],
+                }
+                .serialize_short(),
+            })
+            .unwrap();
+
+        let Packet { address, .. } = to_socket_rx.recv().await.unwrap();
+        assert_eq!(address, "127.0.0.1:8888".parse().unwrap());
+
+        // send path response from the new address and verify the session is migrated
+        from_socket_tx
+            .try_send(Packet {
+                pkt: remote_packet(
+                    2,
+                    Some(Block::PathResponse {
+                        response: challenge,
+                    }),
+                ),
+                address: new_address,
+            })
+            .unwrap();
+        cmd_tx
+            .try_send(SubsystemCommand::SendMessage {
+                message: Message {
+                    message_type: MessageType::Data,
+                    message_id: *MessageId::random(),
+                    expiration: MockRuntime::time_since_epoch() + I2NP_MESSAGE_EXPIRATION,
+                    payload: vec![1, 2, 3, 4],
+                }
+                .serialize_short(),
+            })
+            .unwrap();
+
+        let Packet { address, .. } = to_socket_rx.recv().await.unwrap();
+        assert_eq!(address, new_address);
+    }
+
+    #[tokio::test]
+    async fn path_validation_times_out() {
+        let (from_socket_tx, to_socket_rx, cmd_tx) = start_session().await;
+        let new_address: SocketAddr = "127.0.0.2:9999".parse().unwrap();
+
+        from_socket_tx
+            .try_send(Packet {
+                pkt: remote_packet(1, None),
+                address: new_address,
+            })
+            .unwrap();
+
+        // verify that the challenge is resent until the validation is abandoned
+        for _ in 0..MAX_PATH_CHALLENGES {
+            let Packet { pkt, address } =
+                tokio::time::timeout(PATH_CHALLENGE_TIMEOUT * 2, to_socket_rx.recv())
+                    .await
+                    .expect("no timeout")
+                    .unwrap();
+
+            assert_eq!(address, new_address);
+            assert!(parse_packet(pkt)
+                .iter()
+                .any(|block| core::matches!(block, Block::PathChallenge { .. })));
+        }
+        assert!(
+            tokio::time::timeout(PATH_CHALLENGE_TIMEOUT * 2, to_socket_rx.recv())
+                .await
+                .is_err()
+        );
+
+        // verify that the session still uses the old address
+        cmd_tx
+            .try_send(SubsystemCommand::SendMessage {
+                message: Message {
+                    message_type: MessageType::Data,
+                    message_id: *MessageId::random(),
+                    expiration: MockRuntime::time_since_epoch() + I2NP_MESSAGE_EXPIRATION,
+                    payload: vec![1, 2, 3, 4],
+                }
+                .serialize_short(),
+            })
+            .unwrap();
+
+        let Packet { address, .. } = to_socket_rx.recv().await.unwrap();
+        assert_eq!(address, "127.0.0.1:8888".parse().unwrap());
+    }
+
+    #[tokio::test]
+    async fn path_challenge_answered() {
+        let (from_socket_tx, to_socket_rx, _cmd_tx) = start_session().await;
+        let address: SocketAddr = "127.0.0.1:8888".parse().unwrap();
+
+        from_socket_tx
+            .try_send(Packet {
+                pkt: remote_packet(
+                    1,
+                    Some(Block::PathChallenge {
+                        challenge: vec![1, 3, 3, 7, 1, 3, 3, 7],
+                    }),
+                ),
+                address,
+            })
+            .unwrap();
+
+        let Packet { pkt, .. } = to_socket_rx.recv().await.unwrap();
+        assert!(parse_packet(pkt).into_iter().any(|block| match block {
+            Block::PathResponse { response } => response == vec![1, 3, 3, 7, 1, 3, 3, 7],
+            _ => false,
+        }));
+    }
 }
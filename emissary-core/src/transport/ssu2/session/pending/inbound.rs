@@ -19,7 +19,7 @@
 use crate::{
     crypto::{
         chachapoly::ChaChaPoly, hmac::Hmac, noise::NoiseContext, EphemeralPrivateKey,
-        StaticPrivateKey, StaticPublicKey,
+        EphemeralPublicKey, StaticPrivateKey, StaticPublicKey,
     },
     error::Ssu2Error,
     runtime::Runtime,
@@ -269,6 +269,62 @@ impl<R: Runtime> InboundSsu2Session<R> {
         })
     }
 
+    /// Create new [`InboundSsu2Session`] for a relayed connection.
+    ///
+    /// Alice received the token from us in a `RelayResponse` and skipped the `TokenRequest`, so
+    /// `context.pkt` is a `SessionRequest` whose header has already been decrypted by
+    /// `Ssu2Socket`.
+    pub fn new_relayed(
+        context: InboundSsu2Context,
+        ephemeral_key: EphemeralPublicKey,
+        token: u64,
+    ) -> Result<Self, Ssu2Error> {
+        let InboundSsu2Context {
+            address,
+            chaining_key,
+            dst_id,
+            intro_key,
+            net_id,
+            pkt,
+            pkt_num,
+            pkt_tx,
+            rx,
+            src_id,
+            state,
+            static_key,
+        } = context;
+
+        tracing::trace!(
+            target: LOG_TARGET,
+            ?dst_id,
+            ?src_id,
+            ?pkt_num,
+            ?token,
+            "handle relayed `SessionRequest`",
+        );
+
+        let mut session = Self {
+            address,
+            dst_id,
+            intro_key,
+            net_id,
+            noise_ctx: NoiseContext::new(
+                TryInto::<[u8; 32]>::try_into(chaining_key.to_vec()).expect("to succeed"),
+                TryInto::<[u8; 32]>::try_into(state.to_vec()).expect("to succeed"),
+            ),
+            pkt_retransmitter: PacketRetransmitter::inactive(SESSION_REQUEST_TIMEOUT),
+            pkt_tx,
+            rx: Some(rx),
+            src_id,
+            started: R::now(),
+            state: PendingSessionState::AwaitingSessionRequest { token },
+            static_key,
+        };
+        session.handle_session_request(pkt, ephemeral_key)?;
+
+        Ok(session)
+    }
+
     /// Handle `SessionRequest` message.
     ///
     /// Attempt to parse `pkt` into `SessionRequest` and if it succeeds, verify that the token it
@@ -289,6 +345,7 @@ impl<R: Runtime> InboundSsu2Session<R> {
                     net_id,
                     pkt_num,
                     token,
+                    ..
                 } => {
                     if self.net_id != net_id {
                         return Err(Ssu2Error::NetworkMismatch);
@@ -380,6 +437,18 @@ impl<R: Runtime> InboundSsu2Session<R> {
             return Err(Ssu2Error::TokenMismatch);
         }
 
+        self.handle_session_request(pkt, ephemeral_key)
+    }
+
+    /// Handle `SessionRequest` whose header has been decrypted and whose token has been verified.
+    ///
+    /// Derive the handshake keys, send `SessionCreated` to remote router and transition the
+    /// inbound state to [`PendingSessionState::AwaitingSessionConfirmed`].
+    fn handle_session_request(
+        &mut self,
+        pkt: Vec<u8>,
+        ephemeral_key: EphemeralPublicKey,
+    ) -> Result<Option<PendingSsu2SessionStatus<R>>, Ssu2Error> {
         // MixHash(header), MiXHash(aepk)
         self.noise_ctx.mix_hash(&pkt[..32]).mix_hash(&pkt[32..64]);
 
@@ -541,8 +610,10 @@ impl<R: Runtime> InboundSsu2Session<R> {
             return Err(Ssu2Error::Malformed);
         };
 
-        let Some(Block::RouterInfo { router_info }) =
-            blocks.iter().find(|block| core::matches!(block, Block::RouterInfo { .. }))
+        let Some(Block::RouterInfo {
+            router_info,
+            serialized,
+        }) = blocks.into_iter().find(|block| core::matches!(block, Block::RouterInfo { .. }))
         else {
             tracing::warn!(
                 target: LOG_TARGET,
@@ -607,6 +678,8 @@ impl<R: Runtime> InboundSsu2Session<R> {
             },
             dst_id: self.dst_id,
             pkt,
+            router_info,
+            serialized,
             started: self.started,
             target: self.address,
         }))
@@ -785,7 +858,10 @@ mod tests {
         let (router_info, _, signing_key) = RouterInfoBuilder::default()
             .with_ssu2(crate::Ssu2Config {
                 port: 8889,
-                host: Some(Ipv4Addr::new(127, 0, 0, 1)),
+                ipv4_host: Some(Ipv4Addr::new(127, 0, 0, 1)),
+                ipv6_host: None,
+                ipv4: true,
+                ipv6: false,
                 publish: true,
                 static_key: TryInto::<[u8; 32]>::try_into(outbound_static_key.as_ref().to_vec())
                     .unwrap(),
@@ -809,6 +885,7 @@ mod tests {
             state: inbound_state.clone(),
             static_key: inbound_static_key.public(),
             subsystem_handle: SubsystemHandle::new(),
+            token: None,
         });
 
         let (pkt, pkt_num, dst_id, src_id) = {
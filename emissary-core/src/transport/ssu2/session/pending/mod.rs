@@ -17,15 +17,15 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    primitives::RouterId,
+    primitives::{RouterId, RouterInfo},
     runtime::{Instant, Runtime},
     transport::ssu2::session::active::Ssu2SessionContext,
 };
 
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
 use futures::FutureExt;
 
-use alloc::{collections::VecDeque, vec::Vec};
+use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
 use core::{
     fmt,
     future::Future,
@@ -54,6 +54,12 @@ pub enum PendingSsu2SessionStatus<R: Runtime> {
         /// ACK for `SessionConfirmed`.
         pkt: BytesMut,
 
+        /// Router info of the remote router.
+        router_info: Box<RouterInfo>,
+
+        /// Serialized router info of the remote router.
+        serialized: Bytes,
+
         /// When was the handshake started.
         started: R::Instant,
 
@@ -66,6 +72,9 @@ pub enum PendingSsu2SessionStatus<R: Runtime> {
         /// Context for the active session.
         context: Ssu2SessionContext,
 
+        /// Our socket address, as seen by the remote router.
+        external_address: Option<SocketAddr>,
+
         /// Source connection ID.
         src_id: u64,
 
@@ -28,7 +28,7 @@ use crate::{
     transport::ssu2::{
         message::{
             handshake::{SessionConfirmedBuilder, SessionRequestBuilder, TokenRequestBuilder},
-            HeaderKind, HeaderReader,
+            Block, HeaderKind, HeaderReader,
         },
         session::{
             active::Ssu2SessionContext,
@@ -52,6 +52,7 @@ use core::{
     net::SocketAddr,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
 /// Logging target for the file.
@@ -103,6 +104,11 @@ pub struct OutboundSsu2Context {
 
     /// Subsystem handle.
     pub subsystem_handle: SubsystemHandle,
+
+    /// Token received from Charlie in a `RelayResponse`, if the connection was relayed.
+    ///
+    /// If set, `TokenRequest` is not sent and the handshake starts with `SessionRequest`.
+    pub token: Option<u64>,
 }
 
 /// State for a pending outbound SSU2 session.
@@ -162,6 +168,11 @@ pub struct OutboundSsu2Session<R: Runtime> {
     /// Destination connection ID.
     dst_id: u64,
 
+    /// Our socket address, as seen by the remote router.
+    ///
+    /// Received in an `Address` block of `SessionCreated`.
+    external_address: Option<SocketAddr>,
+
     /// Local router intro key.
     local_intro_key: [u8; 32],
 
@@ -218,8 +229,45 @@ impl<R: Runtime> OutboundSsu2Session<R> {
             state,
             static_key,
             subsystem_handle,
+            token,
         } = context;
 
+        if let Some(token) = token {
+            tracing::trace!(
+                target: LOG_TARGET,
+                %router_id,
+                ?dst_id,
+                ?src_id,
+                ?token,
+                "relayed connection, send `SessionRequest`",
+            );
+
+            let mut session = Self {
+                address,
+                dst_id,
+                external_address: None,
+                local_intro_key,
+                net_id,
+                noise_ctx: NoiseContext::new(
+                    TryInto::<[u8; 32]>::try_into(chaining_key.to_vec()).expect("to succeed"),
+                    TryInto::<[u8; 32]>::try_into(state.to_vec()).expect("to succeed"),
+                ),
+                // replaced by `send_session_request()`
+                pkt_retransmitter: PacketRetransmitter::inactive(Duration::ZERO),
+                pkt_tx,
+                remote_intro_key,
+                router_id,
+                rx: Some(rx),
+                src_id,
+                started: R::now(),
+                state: PendingSessionState::Poisoned,
+                subsystem_handle,
+            };
+            session.send_session_request(token, local_static_key, router_info, static_key);
+
+            return session;
+        }
+
         tracing::trace!(
             target: LOG_TARGET,
             %router_id,
@@ -251,6 +299,7 @@ impl<R: Runtime> OutboundSsu2Session<R> {
         Self {
             address,
             dst_id,
+            external_address: None,
             local_intro_key,
             net_id,
             noise_ctx: NoiseContext::new(
@@ -330,6 +379,23 @@ impl<R: Runtime> OutboundSsu2Session<R> {
         ChaChaPoly::with_nonce(&self.remote_intro_key, pkt_num as u64)
             .decrypt_with_ad(&pkt[..32], &mut payload)?;
 
+        self.send_session_request(token, local_static_key, router_info, static_key);
+
+        Ok(None)
+    }
+
+    /// Send `SessionRequest` to remote router using `token`.
+    ///
+    /// The token was either received in a `Retry` message or, for relayed connections, from
+    /// Charlie in a `RelayResponse`. The state of the outbound connection proceeds to
+    /// `AwaitingSessionCreated`.
+    fn send_session_request(
+        &mut self,
+        token: u64,
+        local_static_key: StaticPrivateKey,
+        router_info: Bytes,
+        static_key: StaticPublicKey,
+    ) {
         // MixKey(DH())
         let ephemeral_key = EphemeralPrivateKey::random(R::rng());
         let cipher_key = self.noise_ctx.mix_key(&ephemeral_key, &static_key);
@@ -374,8 +440,6 @@ impl<R: Runtime> OutboundSsu2Session<R> {
             local_static_key,
             router_info,
         };
-
-        Ok(None)
     }
 
     /// Handle `SessionCreated`.
@@ -454,7 +518,12 @@ impl<R: Runtime> OutboundSsu2Session<R> {
         self.noise_ctx.mix_hash(&pkt[64..]);
 
         // TODO: validate datetime
-        // TODO: get our address
+        self.external_address = Block::parse(&payload).and_then(|blocks| {
+            blocks.into_iter().find_map(|block| match block {
+                Block::Address { address } => Some(address),
+                _ => None,
+            })
+        });
 
         let temp_key = Hmac::new(self.noise_ctx.chaining_key()).update([]).finalize();
         let k_header_2 =
@@ -599,6 +668,7 @@ impl<R: Runtime> OutboundSsu2Session<R> {
                 router_id: self.router_id.clone(),
                 pkt_rx: self.rx.take().expect("to exist"),
             },
+            external_address: self.external_address,
             src_id: self.src_id,
             started: self.started,
         }))
@@ -820,7 +890,10 @@ mod tests {
         let (router_info, _, signing_key) = RouterInfoBuilder::default()
             .with_ssu2(crate::Ssu2Config {
                 port: 8889,
-                host: Some(Ipv4Addr::new(127, 0, 0, 1)),
+                ipv4_host: Some(Ipv4Addr::new(127, 0, 0, 1)),
+                ipv6_host: None,
+                ipv4: true,
+                ipv6: false,
                 publish: true,
                 static_key: TryInto::<[u8; 32]>::try_into(outbound_static_key.as_ref().to_vec())
                     .unwrap(),
@@ -844,6 +917,7 @@ mod tests {
             state: inbound_state.clone(),
             static_key: inbound_static_key.public(),
             subsystem_handle,
+            token: None,
         });
 
         let (pkt, pkt_num, dst_id, src_id) = {
@@ -19,7 +19,7 @@
 use crate::{
     crypto::{sha256::Sha256, StaticPrivateKey},
     error::{ChannelError, Ssu2Error},
-    primitives::{RouterId, RouterInfo, TransportKind},
+    primitives::{RouterId, RouterInfo},
     router::context::RouterContext,
     runtime::{Counter, Gauge, Histogram, JoinSet, MetricsHandle, Runtime},
     subsystem::SubsystemHandle,
@@ -27,6 +27,8 @@ use crate::{
         ssu2::{
             message::{HeaderKind, HeaderReader},
             metrics::*,
+            peer_test::PeerTestManager,
+            relay::{RelayManager, RelayManagerEvent},
             session::{
                 active::{Ssu2Session, Ssu2SessionContext},
                 pending::{
@@ -49,7 +51,7 @@ use hashbrown::HashMap;
 use rand_core::RngCore;
 use thingbuf::mpsc::{channel, errors::TrySendError, Receiver, Sender};
 
-use alloc::{collections::VecDeque, vec::Vec};
+use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
 use core::{
     fmt, mem,
     net::SocketAddr,
@@ -91,6 +93,12 @@ enum PendingSessionKind {
         /// This is the connection ID selected by the remote router and is used to remove pending
         /// session context in case it's rejected by the `TransportManager`.
         dst_id: u64,
+
+        /// Router info of the remote router.
+        router_info: Box<RouterInfo>,
+
+        /// Serialized router info of the remote router.
+        serialized: Bytes,
     },
 
     /// Pending outbound session.
@@ -101,6 +109,9 @@ enum PendingSessionKind {
         /// Session context.
         context: Ssu2SessionContext,
 
+        /// Our socket address, as seen by the remote router.
+        external_address: Option<SocketAddr>,
+
         /// Source connection ID.
         ///
         /// This is the connection ID selected by us which the remote router uses to send us
@@ -128,6 +139,7 @@ impl fmt::Debug for PendingSessionKind {
                 address,
                 context,
                 src_id,
+                ..
             } => f
                 .debug_struct("PendingSessionKind::Outbound")
                 .field("address", &address)
@@ -172,6 +184,12 @@ pub struct Ssu2Socket<R: Runtime> {
     /// Introduction key.
     intro_key: [u8; 32],
 
+    /// Handle to IPv4 UDP socket, if IPv4 was enabled.
+    ipv4_socket_handle: Option<UdpSocketHandle>,
+
+    /// Handle to IPv6 UDP socket, if IPv6 was enabled.
+    ipv6_socket_handle: Option<UdpSocketHandle>,
+
     /// Outbound state.
     outbound_state: Bytes,
 
@@ -180,27 +198,33 @@ pub struct Ssu2Socket<R: Runtime> {
     /// Remote routers' intro keys indexed by their socket addresses.
     pending_outbound: HashMap<SocketAddr, [u8; 32]>,
 
+    /// Router infos of the remote routers of pending outbound sessions.
+    pending_router_infos: HashMap<RouterId, RouterInfo>,
+
     /// Pending outbound packets.
     pending_pkts: VecDeque<(BytesMut, SocketAddr)>,
 
     /// Pending SSU2 sessions.
     pending_sessions: R::JoinSet<PendingSsu2SessionStatus<R>>,
 
+    /// Peer test manager.
+    peer_test_manager: PeerTestManager<R>,
+
     /// RX channel for receiving packets from active sessions.
     pkt_rx: Receiver<Packet>,
 
     /// TX channel given to active sessions.
     pkt_tx: Sender<Packet>,
 
+    /// Relay manager.
+    relay_manager: RelayManager<R>,
+
     /// Router context.
     router_ctx: RouterContext<R>,
 
     /// SSU2 sessions.
     sessions: HashMap<u64, Sender<Packet>>,
 
-    /// UDP socket handle.
-    socket_handle: UdpSocketHandle,
-
     /// Static key.
     static_key: StaticPrivateKey,
 
@@ -223,7 +247,8 @@ pub struct Ssu2Socket<R: Runtime> {
 impl<R: Runtime> Ssu2Socket<R> {
     /// Create new [`Ssu2Socket`].
     pub fn new(
-        socket: R::UdpSocket,
+        ipv4_socket: Option<R::UdpSocket>,
+        ipv6_socket: Option<R::UdpSocket>,
         static_key: StaticPrivateKey,
         intro_key: [u8; 32],
         subsystem_handle: SubsystemHandle,
@@ -243,23 +268,33 @@ impl<R: Runtime> Ssu2Socket<R> {
         // TODO: implement `Clone` for `R::UdpSocket`
         let (pkt_tx, pkt_rx) = channel(PKT_CHANNEL_SIZE);
 
-        let (socket, socket_handle) = UdpSocket::<R>::new(socket);
-        R::spawn(socket.run());
+        let mut socket_handles = [ipv4_socket, ipv6_socket].map(|socket| {
+            socket.map(|socket| {
+                let (socket, socket_handle) = UdpSocket::<R>::new(socket);
+                R::spawn(socket.run());
+
+                socket_handle
+            })
+        });
 
         Self {
             active_sessions: R::join_set(),
             chaining_key: Bytes::from(chaining_key),
             inbound_state: Bytes::from(inbound_state),
             intro_key,
+            ipv4_socket_handle: socket_handles[0].take(),
+            ipv6_socket_handle: socket_handles[1].take(),
             outbound_state: Bytes::from(outbound_state),
             pending_outbound: HashMap::new(),
             pending_pkts: VecDeque::new(),
+            pending_router_infos: HashMap::new(),
             pending_sessions: R::join_set(),
+            peer_test_manager: PeerTestManager::new(intro_key, pkt_tx.clone(), router_ctx.clone()),
             pkt_rx,
+            relay_manager: RelayManager::new(intro_key, pkt_tx.clone(), router_ctx.clone()),
             pkt_tx,
             router_ctx,
             sessions: HashMap::new(),
-            socket_handle,
             static_key,
             subsystem_handle,
             terminating_session: R::join_set(),
@@ -269,6 +304,23 @@ impl<R: Runtime> Ssu2Socket<R> {
         }
     }
 
+    /// Poll IPv4 and IPv6 sockets for inbound datagrams.
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<(Vec<u8>, SocketAddr)>> {
+        for socket_handle in [
+            self.ipv4_socket_handle.as_mut(),
+            self.ipv6_socket_handle.as_mut(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if let Poll::Ready(event) = socket_handle.poll_next_unpin(cx) {
+                return Poll::Ready(event);
+            }
+        }
+
+        Poll::Pending
+    }
+
     /// Handle packet.
     //
     // TODO: needs as lot of refactoring
@@ -302,6 +354,16 @@ impl<R: Runtime> Ssu2Socket<R> {
             }
         }
 
+        // only new sessions are rejected, packets of active sessions were handled above
+        if self.router_ctx.blocklist().is_address_blocked(&address.ip()) {
+            tracing::trace!(
+                target: LOG_TARGET,
+                ?address,
+                "packet from blocked address, ignoring",
+            );
+            return Ok(());
+        }
+
         match reader.parse(self.intro_key) {
             Ok(HeaderKind::TokenRequest {
                 net_id,
@@ -339,8 +401,82 @@ impl<R: Runtime> Ssu2Socket<R> {
 
                 Ok(())
             }
+            Ok(HeaderKind::PeerTest { net_id, pkt_num }) => {
+                if net_id != self.router_ctx.net_id() {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        our_net_id = ?self.router_ctx.net_id(),
+                        their_net_id = ?net_id,
+                        "network id mismatch",
+                    );
+                    return Err(Ssu2Error::NetworkMismatch);
+                }
+
+                self.peer_test_manager.handle_packet(datagram, pkt_num, address);
+                Ok(())
+            }
+            Ok(HeaderKind::HolePunch { net_id, pkt_num }) => {
+                if net_id != self.router_ctx.net_id() {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        our_net_id = ?self.router_ctx.net_id(),
+                        their_net_id = ?net_id,
+                        "network id mismatch",
+                    );
+                    return Err(Ssu2Error::NetworkMismatch);
+                }
+
+                self.relay_manager.handle_packet(datagram, pkt_num, address);
+                Ok(())
+            }
+            Ok(HeaderKind::SessionRequest {
+                ephemeral_key,
+                net_id,
+                pkt_num,
+                src_id,
+                token,
+            }) if self.relay_manager.has_token(token) => {
+                if net_id != self.router_ctx.net_id() {
+                    tracing::warn!(
+                        target: LOG_TARGET,
+                        our_net_id = ?self.router_ctx.net_id(),
+                        their_net_id = ?net_id,
+                        "network id mismatch",
+                    );
+                    return Err(Ssu2Error::NetworkMismatch);
+                }
+
+                // alice received the token from us in a relay response and sent `SessionRequest`
+                // without requesting a token first
+                self.relay_manager.remove_token(token);
+
+                let (tx, rx) = channel(CHANNEL_SIZE);
+                let session = InboundSsu2Session::<R>::new_relayed(
+                    InboundSsu2Context {
+                        address,
+                        chaining_key: self.chaining_key.clone(),
+                        dst_id: connection_id,
+                        intro_key: self.intro_key,
+                        net_id: self.router_ctx.net_id(),
+                        pkt_num,
+                        pkt: datagram,
+                        pkt_tx: self.pkt_tx.clone(),
+                        rx,
+                        src_id,
+                        state: self.inbound_state.clone(),
+                        static_key: self.static_key.clone(),
+                    },
+                    ephemeral_key,
+                    token,
+                )?;
+
+                self.sessions.insert(connection_id, tx);
+                self.pending_sessions.push(session);
+
+                Ok(())
+            }
             _ => match self.pending_outbound.get(&address) {
-                Some(intro_key) =>
+                Some(intro_key) => {
                     match self.sessions.get_mut(&reader.reset_key(*intro_key).dst_id()) {
                         Some(tx) => tx
                             .try_send(Packet {
@@ -356,7 +492,8 @@ impl<R: Runtime> Ssu2Socket<R> {
                             );
                             Ok(())
                         }
-                    },
+                    }
+                }
                 None => {
                     tracing::trace!(
                         target: LOG_TARGET,
@@ -369,18 +506,35 @@ impl<R: Runtime> Ssu2Socket<R> {
         }
     }
 
+    /// Connect to `router_info`.
+    ///
+    /// If the router doesn't have a directly reachable SSU2 address, the connection is relayed
+    /// through one of its introducers.
     pub fn connect(&mut self, router_info: RouterInfo) {
+        let (ipv4, ipv6) = (
+            self.ipv4_socket_handle.is_some(),
+            self.ipv6_socket_handle.is_some(),
+        );
+
+        // `TransportManager` has ensured `router_info` contains either a valid and reachable ssu2
+        // router address or an address with introducers
+        match router_info.ssu2_address(ipv4, ipv6).and_then(|address| address.socket_address) {
+            Some(address) => self.dial(router_info, address, None),
+            None => self.relay_manager.connect(router_info, ipv4, ipv6),
+        }
+    }
+
+    /// Dial `router_info` at `address`.
+    ///
+    /// `token` is specified if the connection was relayed and Charlie gave us a token.
+    fn dial(&mut self, router_info: RouterInfo, address: SocketAddr, token: Option<u64>) {
         // must succeed since `TransportManager` has ensured `router_info` contains
-        // a valid and reachable ssu2 router address
+        // a valid ssu2 router address
         let router_id = router_info.identity.id();
         let intro_key = router_info.ssu2_intro_key().expect("to succeed");
         let static_key = router_info.ssu2_static_key().expect("to succeed");
-        let address = router_info
-            .addresses
-            .get(&TransportKind::Ssu2)
-            .expect("to exist")
-            .socket_address
-            .expect("to exist");
+
+        self.pending_router_infos.insert(router_id.clone(), router_info);
 
         let router_info = self.router_ctx.router_info();
         let state = Sha256::new().update(&self.outbound_state).update(&static_key).finalize();
@@ -418,6 +572,7 @@ impl<R: Runtime> Ssu2Socket<R> {
                 state,
                 static_key,
                 subsystem_handle,
+                token,
             })
             .run(),
         );
@@ -443,11 +598,13 @@ impl<R: Runtime> Ssu2Socket<R> {
             return;
         };
 
-        let context = match kind {
+        let (context, peer_test_handle, relay_handle) = match kind {
             PendingSessionKind::Inbound {
                 pkt,
                 address,
                 context,
+                router_info,
+                serialized,
                 ..
             } => {
                 tracing::trace!(
@@ -459,10 +616,28 @@ impl<R: Runtime> Ssu2Socket<R> {
 
                 // TODO: retransmissiosn?
                 self.pending_pkts.push_back((pkt, address));
-                context
+
+                let relay_handle = self.relay_manager.add_session(
+                    router_id.clone(),
+                    None,
+                    Some((*router_info).clone()),
+                    Some(serialized.clone()),
+                );
+                let handle = self.peer_test_manager.add_session(
+                    router_id.clone(),
+                    address,
+                    None,
+                    Some(*router_info),
+                    Some(serialized),
+                );
+
+                (context, handle, relay_handle)
             }
             PendingSessionKind::Outbound {
-                address, context, ..
+                address,
+                context,
+                external_address,
+                ..
             } => {
                 tracing::trace!(
                     target: LOG_TARGET,
@@ -472,7 +647,23 @@ impl<R: Runtime> Ssu2Socket<R> {
                 );
 
                 self.pending_outbound.remove(&address);
-                context
+
+                let router_info = self.pending_router_infos.remove(router_id);
+                let relay_handle = self.relay_manager.add_session(
+                    router_id.clone(),
+                    external_address,
+                    router_info.clone(),
+                    None,
+                );
+                let handle = self.peer_test_manager.add_session(
+                    router_id.clone(),
+                    address,
+                    external_address,
+                    router_info,
+                    None,
+                );
+
+                (context, handle, relay_handle)
             }
         };
 
@@ -481,6 +672,9 @@ impl<R: Runtime> Ssu2Socket<R> {
                 context,
                 self.pkt_tx.clone(),
                 self.subsystem_handle.clone(),
+                peer_test_handle,
+                relay_handle,
+                self.router_ctx.bandwidth_limiter().clone(),
                 self.router_ctx.metrics_handle().clone(),
             )
             .run(),
@@ -494,6 +688,8 @@ impl<R: Runtime> Ssu2Socket<R> {
 
     /// Reject inbound/outbound connection to `router_id`.
     pub fn reject(&mut self, router_id: &RouterId) {
+        self.pending_router_infos.remove(router_id);
+
         let Some(kind) = self.unvalidated_sessions.remove(router_id) else {
             tracing::warn!(
                 target: LOG_TARGET,
@@ -521,6 +717,7 @@ impl<R: Runtime> Ssu2Socket<R> {
                 address,
                 context,
                 src_id,
+                ..
             } => {
                 tracing::debug!(
                     target: LOG_TARGET,
@@ -545,7 +742,7 @@ impl<R: Runtime> Stream for Ssu2Socket<R> {
         let this = &mut *self;
 
         loop {
-            match this.socket_handle.poll_next_unpin(cx) {
+            match this.poll_recv(cx) {
                 Poll::Pending => break,
                 Poll::Ready(None) => {
                     tracing::warn!(
@@ -555,6 +752,7 @@ impl<R: Runtime> Stream for Ssu2Socket<R> {
                     return Poll::Ready(None);
                 }
                 Poll::Ready(Some((datagram, from))) => {
+                    this.router_ctx.bandwidth_limiter().inbound(datagram.len());
                     this.router_ctx
                         .metrics_handle()
                         .counter(INBOUND_BANDWIDTH)
@@ -600,6 +798,8 @@ impl<R: Runtime> Stream for Ssu2Socket<R> {
                         "terminate active ssu2 session",
                     );
 
+                    this.peer_test_manager.remove_session(&termination_ctx.router_id);
+                    this.relay_manager.remove_session(&termination_ctx.router_id);
                     this.terminating_session
                         .push(TerminatingSsu2Session::<R>::new(termination_ctx));
                     this.router_ctx.metrics_handle().gauge(NUM_CONNECTIONS).decrement(1);
@@ -654,6 +854,8 @@ impl<R: Runtime> Stream for Ssu2Socket<R> {
                             context,
                             dst_id,
                             pkt,
+                            router_info,
+                            serialized,
                             started: _,
                             target,
                         } => {
@@ -675,6 +877,8 @@ impl<R: Runtime> Stream for Ssu2Socket<R> {
                                             address: target,
                                             context,
                                             dst_id,
+                                            router_info,
+                                            serialized,
                                         },
                                     );
 
@@ -696,6 +900,7 @@ impl<R: Runtime> Stream for Ssu2Socket<R> {
                         }
                         PendingSsu2SessionStatus::NewOutboundSession {
                             context,
+                            external_address,
                             src_id,
                             started: _,
                         } => {
@@ -713,6 +918,7 @@ impl<R: Runtime> Stream for Ssu2Socket<R> {
                                 PendingSessionKind::Outbound {
                                     address: context.address,
                                     context,
+                                    external_address,
                                     src_id,
                                 },
                             ) {
@@ -751,6 +957,7 @@ impl<R: Runtime> Stream for Ssu2Socket<R> {
                                     "pending outbound session terminated",
                                 );
                                 debug_assert!(false);
+                                this.pending_router_infos.remove(&router_id);
                                 return Poll::Ready(Some(TransportEvent::ConnectionFailure {
                                     router_id,
                                 }));
@@ -775,6 +982,7 @@ impl<R: Runtime> Stream for Ssu2Socket<R> {
                                     ?connection_id,
                                     "pending outbound session timed out",
                                 );
+                                this.pending_router_infos.remove(&router_id);
                                 return Poll::Ready(Some(TransportEvent::ConnectionFailure {
                                     router_id,
                                 }));
@@ -786,6 +994,29 @@ impl<R: Runtime> Stream for Ssu2Socket<R> {
             }
         }
 
+        if let Poll::Ready(Some(status)) = this.peer_test_manager.poll_next_unpin(cx) {
+            this.relay_manager.set_firewall_status(status);
+            return Poll::Ready(Some(TransportEvent::FirewallStatusChanged { status }));
+        }
+
+        loop {
+            match this.relay_manager.poll_next_unpin(cx) {
+                Poll::Pending => break,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Ready(Some(RelayManagerEvent::IntroducersChanged { introducers })) =>
+                    return Poll::Ready(Some(TransportEvent::IntroducersChanged { introducers })),
+                Poll::Ready(Some(RelayManagerEvent::ConnectIntroducer { router_info })) =>
+                    this.connect(*router_info),
+                Poll::Ready(Some(RelayManagerEvent::Connect {
+                    router_info,
+                    address,
+                    token,
+                })) => this.dial(*router_info, address, Some(token)),
+                Poll::Ready(Some(RelayManagerEvent::ConnectionFailure { router_id })) =>
+                    return Poll::Ready(Some(TransportEvent::ConnectionFailure { router_id })),
+            }
+        }
+
         loop {
             match this.pkt_rx.poll_recv(cx) {
                 Poll::Pending => break,
@@ -809,9 +1040,24 @@ impl<R: Runtime> Stream for Ssu2Socket<R> {
                 },
                 WriteState::SendPacket { pkt, target } => {
                     let nwritten = pkt.len();
+                    let socket_handle = match target {
+                        SocketAddr::V4(_) => this.ipv4_socket_handle.as_mut(),
+                        SocketAddr::V6(_) => this.ipv6_socket_handle.as_mut(),
+                    };
+
+                    let Some(socket_handle) = socket_handle else {
+                        tracing::debug!(
+                            target: LOG_TARGET,
+                            ?target,
+                            "no socket for the address family of target, dropping packet",
+                        );
+                        this.write_state = WriteState::GetPacket;
+                        continue;
+                    };
 
-                    match this.socket_handle.try_send_to(pkt.to_vec(), target) {
+                    match socket_handle.try_send_to(pkt.to_vec(), target) {
                         Ok(()) => {
+                            this.router_ctx.bandwidth_limiter().outbound(nwritten);
                             this.router_ctx
                                 .metrics_handle()
                                 .counter(OUTBOUND_BANDWIDTH)
@@ -824,7 +1070,10 @@ impl<R: Runtime> Stream for Ssu2Socket<R> {
                             this.write_state = WriteState::GetPacket;
                         }
                         Err(ChannelError::Closed) => return Poll::Ready(None),
-                        Err(ChannelError::DoesntExist | ChannelError::Full) => {}
+                        Err(ChannelError::DoesntExist | ChannelError::Full) => {
+                            this.write_state = WriteState::SendPacket { pkt, target };
+                            break;
+                        }
                     }
                 }
                 WriteState::Poisoned => unreachable!(),
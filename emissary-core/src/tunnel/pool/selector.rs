@@ -164,12 +164,12 @@ impl<R: Runtime> ExploratorySelector<R> {
                         reader
                             .router_info(&router_id)?
                             .addresses
-                            .get(&TransportKind::Ntcp2)
+                            .get(&TransportKind::Ntcp2V4)
                             .and_then(|address| address.socket_address),
                         reader
                             .router_info(&router_id)?
                             .addresses
-                            .get(&TransportKind::Ssu2)
+                            .get(&TransportKind::Ssu2V4)
                             .and_then(|address| address.socket_address),
                     ];
 
@@ -1102,7 +1102,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("LR")).unwrap();
                 info.addresses = HashMap::from_iter([(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_published_ntcp2(
                         [1u8; 32],
                         [1u8; 16],
@@ -1119,7 +1119,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("XfR")).unwrap();
                 info.addresses = HashMap::from_iter([(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_published_ntcp2(
                         [1u8; 32],
                         [1u8; 16],
@@ -1282,7 +1282,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("LR")).unwrap();
                 info.addresses = HashMap::from_iter([(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_published_ntcp2(
                         [1u8; 32],
                         [1u8; 16],
@@ -1300,7 +1300,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("LR")).unwrap();
                 info.addresses = HashMap::from_iter([(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_published_ntcp2(
                         [1u8; 32],
                         [1u8; 16],
@@ -1335,7 +1335,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("LR")).unwrap();
                 info.addresses = HashMap::from_iter([(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_published_ntcp2(
                         [1u8; 32],
                         [1u8; 16],
@@ -1353,7 +1353,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("XfR")).unwrap();
                 info.addresses = HashMap::from_iter([(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_published_ntcp2(
                         [1u8; 32],
                         [1u8; 16],
@@ -1389,7 +1389,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("LU")).unwrap();
                 info.addresses.insert(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_unpublished_ntcp2([i as u8; 32], 2000 + i),
                 );
                 info
@@ -1436,7 +1436,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("OU")).unwrap();
                 info.addresses.insert(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_unpublished_ntcp2([i as u8; 32], 2000 + i),
                 );
                 info
@@ -1466,7 +1466,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("LR")).unwrap();
                 info.addresses = HashMap::from_iter([(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_published_ntcp2(
                         [1u8; 32],
                         [1u8; 16],
@@ -1484,7 +1484,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("LR")).unwrap();
                 info.addresses = HashMap::from_iter([(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_published_ntcp2(
                         [1u8; 32],
                         [1u8; 16],
@@ -1523,7 +1523,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("LR")).unwrap();
                 info.addresses = HashMap::from_iter([(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_published_ntcp2(
                         [1u8; 32],
                         [1u8; 16],
@@ -1541,7 +1541,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("XfR")).unwrap();
                 info.addresses = HashMap::from_iter([(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_published_ntcp2(
                         [1u8; 32],
                         [1u8; 16],
@@ -1581,7 +1581,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("LR")).unwrap();
                 info.addresses = HashMap::from_iter([(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_published_ntcp2(
                         [1u8; 32],
                         [1u8; 16],
@@ -1599,7 +1599,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("XfR")).unwrap();
                 info.addresses = HashMap::from_iter([(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_published_ntcp2(
                         [1u8; 32],
                         [1u8; 16],
@@ -1662,7 +1662,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("LR")).unwrap();
                 info.addresses = HashMap::from_iter([(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_published_ntcp2(
                         [1u8; 32],
                         [1u8; 16],
@@ -1680,7 +1680,7 @@ mod tests {
                 let mut info = RouterInfoBuilder::default().build().0;
                 info.capabilities = Capabilities::parse(&Str::from("XfR")).unwrap();
                 info.addresses = HashMap::from_iter([(
-                    TransportKind::Ntcp2,
+                    TransportKind::Ntcp2V4,
                     RouterAddress::new_published_ntcp2(
                         [1u8; 32],
                         [1u8; 16],
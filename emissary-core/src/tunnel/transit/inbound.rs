@@ -17,6 +17,7 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
+    bandwidth::BandwidthLimiter,
     crypto::aes::{cbc, ecb},
     error::Error,
     events::EventHandle,
@@ -57,6 +58,9 @@ const PAYLOAD_OFFSET: RangeFrom<usize> = 20..;
 
 /// Inbound gateway.
 pub struct InboundGateway<R: Runtime> {
+    /// Bandwidth limiter.
+    bandwidth_limiter: BandwidthLimiter<R>,
+
     /// Event handle.
     event_handle: EventHandle<R>,
 
@@ -163,6 +167,7 @@ impl<R: Runtime> TransitTunnel<R> for InboundGateway<R> {
         metrics_handle: R::MetricsHandle,
         message_rx: Receiver<Message>,
         event_handle: EventHandle<R>,
+        bandwidth_limiter: BandwidthLimiter<R>,
     ) -> Self {
         // generate random padding bytes used in `TunnelData` messages
         let padding_bytes = {
@@ -181,6 +186,7 @@ impl<R: Runtime> TransitTunnel<R> for InboundGateway<R> {
         };
 
         InboundGateway {
+            bandwidth_limiter,
             event_handle,
             expiration_timer: R::timer(TRANSIT_TUNNEL_EXPIRATION),
             bandwidth: 0usize,
@@ -211,7 +217,17 @@ impl<R: Runtime> Future for InboundGateway<R> {
                     return Poll::Ready(self.tunnel_id);
                 }
                 Some(message) => {
-                    self.bandwidth += message.serialized_len_short();
+                    let message_len = message.serialized_len_short();
+                    self.bandwidth += message_len;
+
+                    if !self.bandwidth_limiter.transit(message_len) {
+                        tracing::trace!(
+                            target: LOG_TARGET,
+                            tunnel_id = %self.tunnel_id,
+                            "transit share exceeded, dropping message",
+                        );
+                        continue;
+                    }
 
                     let MessageType::TunnelGateway = message.message_type else {
                         tracing::warn!(
@@ -403,6 +419,7 @@ mod tests {
             MockRuntime::register_metrics(vec![], None),
             msg_rx,
             event_handle.clone(),
+            BandwidthLimiter::default(),
         );
 
         let message = MessageBuilder::standard()
@@ -523,6 +540,7 @@ mod tests {
             MockRuntime::register_metrics(vec![], None),
             msg_rx,
             event_handle.clone(),
+            BandwidthLimiter::default(),
         );
 
         let tunnel_gateway = TunnelGateway {
@@ -17,6 +17,7 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
+    bandwidth::BandwidthLimiter,
     crypto::sha256::Sha256,
     error::{Error, RejectionReason, TunnelError},
     events::EventHandle,
@@ -54,6 +55,9 @@ const LOG_TARGET: &str = "emissary::tunnel::transit::obep";
 
 /// Outbound endpoint.
 pub struct OutboundEndpoint<R: Runtime> {
+    /// Bandwidth limiter.
+    bandwidth_limiter: BandwidthLimiter<R>,
+
     /// Event handle.
     event_handle: EventHandle<R>,
 
@@ -297,8 +301,10 @@ impl<R: Runtime> TransitTunnel<R> for OutboundEndpoint<R> {
         metrics_handle: R::MetricsHandle,
         message_rx: Receiver<Message>,
         event_handle: EventHandle<R>,
+        bandwidth_limiter: BandwidthLimiter<R>,
     ) -> Self {
         OutboundEndpoint {
+            bandwidth_limiter,
             event_handle,
             expiration_timer: R::timer(TRANSIT_TUNNEL_EXPIRATION),
             fragment: FragmentHandler::new(),
@@ -327,7 +333,17 @@ impl<R: Runtime> Future for OutboundEndpoint<R> {
                     return Poll::Ready(self.tunnel_id);
                 }
                 Some(message) => {
-                    self.bandwidth += message.serialized_len_short();
+                    let message_len = message.serialized_len_short();
+                    self.bandwidth += message_len;
+
+                    if !self.bandwidth_limiter.transit(message_len) {
+                        tracing::trace!(
+                            target: LOG_TARGET,
+                            tunnel_id = %self.tunnel_id,
+                            "transit share exceeded, dropping message",
+                        );
+                        continue;
+                    }
 
                     let MessageType::TunnelData = message.message_type else {
                         tracing::warn!(
@@ -520,6 +536,7 @@ mod tests {
             MockRuntime::register_metrics(vec![], None),
             rx,
             event_handle.clone(),
+            BandwidthLimiter::default(),
         );
 
         let (router_id, message) = tunnel.handle_tunnel_data(&parsed).unwrap().next().unwrap();
@@ -634,6 +651,7 @@ mod tests {
             MockRuntime::register_metrics(vec![], None),
             rx,
             event_handle.clone(),
+            BandwidthLimiter::default(),
         );
         assert!(tunnel.handle_tunnel_data(&parsed).unwrap().collect::<Vec<_>>().is_empty());
     }
@@ -737,6 +755,7 @@ mod tests {
             MockRuntime::register_metrics(vec![], None),
             rx,
             event_handle.clone(),
+            BandwidthLimiter::default(),
         );
 
         let (_to_router, messages) = obgw.send_to_router(obep_router_id.clone(), message);
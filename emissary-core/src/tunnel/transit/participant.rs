@@ -17,6 +17,7 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
+    bandwidth::BandwidthLimiter,
     events::EventHandle,
     i2np::{tunnel::data::EncryptedTunnelData, Message, MessageBuilder, MessageType},
     primitives::{RouterId, TunnelId},
@@ -49,6 +50,9 @@ const LOG_TARGET: &str = "emissary::tunnel::transit::participant";
 /// Only accepts and handles `TunnelData` messages,
 /// all other message types are rejected as invalid.
 pub struct Participant<R: Runtime> {
+    /// Bandwidth limiter.
+    bandwidth_limiter: BandwidthLimiter<R>,
+
     /// Event handle.
     event_handle: EventHandle<R>,
 
@@ -127,8 +131,10 @@ impl<R: Runtime> TransitTunnel<R> for Participant<R> {
         metrics_handle: R::MetricsHandle,
         message_rx: Receiver<Message>,
         event_handle: EventHandle<R>,
+        bandwidth_limiter: BandwidthLimiter<R>,
     ) -> Self {
         Participant {
+            bandwidth_limiter,
             event_handle,
             expiration_timer: R::timer(TRANSIT_TUNNEL_EXPIRATION),
             bandwidth: 0usize,
@@ -158,7 +164,17 @@ impl<R: Runtime> Future for Participant<R> {
                     return Poll::Ready(self.tunnel_id);
                 }
                 Some(message) => {
-                    self.bandwidth += message.serialized_len_short();
+                    let message_len = message.serialized_len_short();
+                    self.bandwidth += message_len;
+
+                    if !self.bandwidth_limiter.transit(message_len) {
+                        tracing::trace!(
+                            target: LOG_TARGET,
+                            tunnel_id = %self.tunnel_id,
+                            "transit share exceeded, dropping message",
+                        );
+                        continue;
+                    }
 
                     match message.message_type {
                         MessageType::TunnelData => {
@@ -27,7 +27,7 @@ use rand_core::RngCore;
 
 use core::{
     future::Future,
-    net::Ipv4Addr,
+    net::{Ipv4Addr, Ipv6Addr},
     pin::Pin,
     task::{Context, Poll},
     time::Duration,
@@ -203,3 +203,19 @@ pub fn is_global(address: Ipv4Addr) -> bool {
         || (address >= Ipv4Addr::new(198, 18, 0, 0) && address <= Ipv4Addr::new(198, 19, 255, 255))
         || address.is_broadcast())
 }
+
+/// Check if an IPv6 address is globally routable.
+pub fn is_global_ipv6(address: Ipv6Addr) -> bool {
+    if let Some(address) = address.to_ipv4_mapped() {
+        return is_global(address);
+    }
+
+    let segments = address.segments();
+
+    !(address.is_loopback()
+        || address.is_unspecified()
+        || address.is_multicast()
+        || (segments[0] & 0xfe00) == 0xfc00 // unique local, fc00::/7
+        || (segments[0] & 0xffc0) == 0xfe80 // link local, fe80::/10
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8)) // documentation, 2001:db8::/32
+}
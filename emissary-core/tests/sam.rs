@@ -62,7 +62,10 @@ async fn make_router(
                     thread_rng().fill_bytes(&mut key);
                     key
                 },
-                host: Some("127.0.0.1".parse().unwrap()),
+                ipv4_host: Some("127.0.0.1".parse().unwrap()),
+                ipv6_host: None,
+                ipv4: true,
+                ipv6: false,
                 publish: true,
             }),
             None,
@@ -71,7 +74,10 @@ async fn make_router(
             None,
             Some(Ssu2Config {
                 port: 0u16,
-                host: Some("127.0.0.1".parse().unwrap()),
+                ipv4_host: Some("127.0.0.1".parse().unwrap()),
+                ipv6_host: None,
+                ipv4: true,
+                ipv6: false,
                 publish: true,
 
                 static_key: {
@@ -1623,7 +1629,10 @@ async fn host_lookup(kind: TransportKind) {
                     thread_rng().fill_bytes(&mut key);
                     key
                 },
-                host: Some("127.0.0.1".parse().unwrap()),
+                ipv4_host: Some("127.0.0.1".parse().unwrap()),
+                ipv6_host: None,
+                ipv4: true,
+                ipv6: false,
                 publish: true,
             }),
             None,
@@ -1632,7 +1641,10 @@ async fn host_lookup(kind: TransportKind) {
             None,
             Some(Ssu2Config {
                 port: 0u16,
-                host: Some("127.0.0.1".parse().unwrap()),
+                ipv4_host: Some("127.0.0.1".parse().unwrap()),
+                ipv6_host: None,
+                ipv4: true,
+                ipv6: false,
                 publish: true,
 
                 static_key: {
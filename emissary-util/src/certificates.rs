@@ -19,7 +19,9 @@
 use rsa::{BigUint, RsaPublicKey};
 use x509_parser::public_key::PublicKey;
 
-use std::{collections::HashMap, sync::LazyLock};
+use anyhow::anyhow;
+
+use std::{collections::HashMap, path::Path, sync::LazyLock};
 
 const ACETONE: &str = include_str!(concat!(
     env!("CARGO_MANIFEST_DIR"),
@@ -100,31 +102,77 @@ pub const CERTIFICATES: &[(&str, &str); 13] = &[
 pub static PUBLIC_KEYS: LazyLock<HashMap<&'static str, RsaPublicKey>> = LazyLock::new(|| {
     CERTIFICATES
         .iter()
-        .filter_map(|(key, value)| {
-            let cert = pem::parse(value).ok()?.into_contents();
-            let (_, cert) = x509_parser::parse_x509_certificate(&cert).ok()?;
-
-            if !cert.tbs_certificate.validity.is_valid() {
-                tracing::warn!(
-                    target: "emissary-util::certificate",
-                    %key,
-                    not_before = ?cert.tbs_certificate.validity.not_before,
-                    not_after = ?cert.tbs_certificate.validity.not_after,
-                    "self-signed certificate is no longer valid",
-                );
-
-                return None;
-            }
+        .filter_map(|(key, value)| Some((*key, parse_public_key(key, value)?)))
+        .collect()
+});
+
+/// Parse the RSA public key of PEM-encoded `certificate` of `signer_id`.
+///
+/// Returns `None` if the certificate is invalid, has expired or doesn't contain an RSA key.
+pub fn parse_public_key(signer_id: &str, certificate: &str) -> Option<RsaPublicKey> {
+    let cert = pem::parse(certificate).ok()?.into_contents();
+    let (_, cert) = x509_parser::parse_x509_certificate(&cert).ok()?;
+
+    if !cert.tbs_certificate.validity.is_valid() {
+        tracing::warn!(
+            target: "emissary-util::certificate",
+            %signer_id,
+            not_before = ?cert.tbs_certificate.validity.not_before,
+            not_after = ?cert.tbs_certificate.validity.not_after,
+            "self-signed certificate is no longer valid",
+        );
+
+        return None;
+    }
 
-            match cert.public_key().parsed().ok()? {
-                PublicKey::RSA(public_key) => {
-                    let modulus = BigUint::from_bytes_be(public_key.modulus);
-                    let exponent = BigUint::from_bytes_be(public_key.exponent);
+    match cert.public_key().parsed().ok()? {
+        PublicKey::RSA(public_key) => {
+            let modulus = BigUint::from_bytes_be(public_key.modulus);
+            let exponent = BigUint::from_bytes_be(public_key.exponent);
 
-                    Some((*key, RsaPublicKey::new(modulus, exponent).ok()?))
-                }
-                _ => None,
+            RsaPublicKey::new(modulus, exponent).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Load PEM-encoded certificate from `path` and parse its RSA public key.
+///
+/// The signer ID of a certificate is derived from its file name, e.g., `user_at_mail.i2p.crt` is
+/// the certificate of `user@mail.i2p`.
+///
+/// Returns the signer ID, the certificate and its public key, if the certificate is valid and
+/// contains an RSA key.
+pub fn load_certificate(path: &str) -> anyhow::Result<(String, String, Option<RsaPublicKey>)> {
+    let certificate = std::fs::read_to_string(path)?;
+    let signer_id = Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.strip_suffix(".crt").unwrap_or(name).replace("_at_", "@"))
+        .ok_or_else(|| anyhow!("invalid certificate path: {path}"))?;
+    let public_key = parse_public_key(&signer_id, &certificate);
+
+    Ok((signer_id, certificate, public_key))
+}
+
+/// Load certificates from `paths` and return their RSA public keys, indexed by signer ID.
+///
+/// Certificates which don't contain a valid RSA key are ignored.
+pub fn load_public_keys(paths: &[String]) -> anyhow::Result<HashMap<String, RsaPublicKey>> {
+    let mut keys = HashMap::new();
+
+    for path in paths {
+        match load_certificate(path)? {
+            (signer_id, _, Some(key)) => {
+                keys.insert(signer_id, key);
             }
-        })
-        .collect()
-});
+            (_, _, None) => tracing::warn!(
+                target: "emissary-util::certificate",
+                %path,
+                "certificate doesn't contain a valid rsa key, ignoring",
+            ),
+        }
+    }
+
+    Ok(keys)
+}
@@ -18,7 +18,7 @@
 
 #![allow(clippy::manual_async_fn)]
 
-mod certificates;
+pub mod certificates;
 
 pub mod reseeder;
 pub mod runtime;
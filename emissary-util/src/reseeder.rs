@@ -17,7 +17,7 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::{
-    certificates::CREATIVECOWPAT_SSL,
+    certificates::{load_certificate, CREATIVECOWPAT_SSL},
     su3::{ReseedRouterInfo, Su3},
 };
 
@@ -25,11 +25,13 @@ use anyhow::anyhow;
 use rand::{thread_rng, Rng};
 use reqwest::{
     header::{HeaderMap, HeaderValue, CONNECTION, USER_AGENT},
-    Certificate, ClientBuilder,
+    Certificate, ClientBuilder, Proxy,
 };
+use rsa::RsaPublicKey;
 
 use std::{
     collections::{HashMap, HashSet},
+    path::Path,
     time::Duration,
 };
 
@@ -39,6 +41,12 @@ const LOG_TARGET: &str = "emissary::reseeder";
 /// How many times is reseeding retried before giving up.
 const NUM_RETRIES: usize = 5usize;
 
+/// Zip magic.
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// SU3 magic.
+const SU3_MAGIC: &[u8] = b"I2Psu3";
+
 /// How many routers should [`Reseeder`] find before terminating the process.
 const MIN_ROUTER_INFOS_TO_DOWNLOAD: usize = 100usize;
 
@@ -62,24 +70,78 @@ const RESEED_SERVERS: &[&str] = &[
 pub struct Reseeder;
 
 impl Reseeder {
+    /// Load additional reseed certificates from `paths`.
+    ///
+    /// The signer ID of a certificate is derived from its file name, e.g., `user_at_mail.i2p.crt`
+    /// is the certificate of `user@mail.i2p`. The certificates are also trusted as TLS
+    /// certificates of reseed servers.
+    fn load_certificates(
+        paths: &[String],
+    ) -> anyhow::Result<(Vec<Certificate>, HashMap<String, RsaPublicKey>)> {
+        let mut certificates = Vec::new();
+        let mut keys = HashMap::new();
+
+        for path in paths {
+            let (signer_id, contents, key) = load_certificate(path)?;
+
+            match key {
+                Some(key) => {
+                    keys.insert(signer_id, key);
+                }
+                None => tracing::warn!(
+                    target: LOG_TARGET,
+                    %path,
+                    "certificate doesn't contain a valid rsa key, not used for su3 verification",
+                ),
+            }
+
+            certificates.push(Certificate::from_pem(contents.as_bytes())?);
+        }
+
+        Ok((certificates, keys))
+    }
+
     /// Attempt to reseed from `hosts` and parse response into a vector of serialized router infos.
+    ///
+    /// If `proxy` is specified, all requests are made through it.
     async fn reseed_inner(
         hosts: &[&str],
         force_ipv4: bool,
+        proxy: Option<&str>,
+        certificates: &[String],
     ) -> anyhow::Result<Vec<ReseedRouterInfo>> {
-        let client = if force_ipv4 {
+        let (certificates, keys) = Self::load_certificates(certificates)?;
+        let builder = if force_ipv4 {
             ClientBuilder::new().local_address("0.0.0.0:0".parse().ok())
         } else {
             ClientBuilder::new()
-        }
-        .add_root_certificate(
-            Certificate::from_pem_bundle(CREATIVECOWPAT_SSL.as_bytes())
-                .expect("to succeed")
-                .pop()
-                .expect("to exist"),
-        )
-        .timeout(Duration::from_secs(15))
-        .build()?;
+        };
+        let builder = match proxy {
+            None => builder,
+            Some(proxy) => {
+                tracing::info!(
+                    target: LOG_TARGET,
+                    %proxy,
+                    "reseed through proxy",
+                );
+
+                builder.proxy(Proxy::all(proxy)?)
+            }
+        };
+
+        let builder = certificates.into_iter().fold(builder, |builder, certificate| {
+            builder.add_root_certificate(certificate)
+        });
+
+        let client = builder
+            .add_root_certificate(
+                Certificate::from_pem_bundle(CREATIVECOWPAT_SSL.as_bytes())
+                    .expect("to succeed")
+                    .pop()
+                    .expect("to exist"),
+            )
+            .timeout(Duration::from_secs(15))
+            .build()?;
 
         let headers = HeaderMap::from_iter([
             (USER_AGENT, HeaderValue::from_static("Wget/1.11.4")),
@@ -90,7 +152,7 @@ impl Reseeder {
         let mut already_tried = HashSet::<usize>::new();
         let mut routers = HashMap::<String, ReseedRouterInfo>::new();
 
-        for _ in 0..NUM_RETRIES {
+        for _ in 0..NUM_RETRIES.min(hosts.len()) {
             let server = loop {
                 let server = thread_rng().gen_range(0..hosts.len());
 
@@ -133,7 +195,7 @@ impl Reseeder {
             }
 
             match response.bytes().await {
-                Ok(bytes) => match Su3::parse_reseed(&bytes, true) {
+                Ok(bytes) => match Su3::parse_reseed_with_keys(&bytes, &keys) {
                     None => continue,
                     Some(downloaded) => {
                         tracing::info!(
@@ -176,18 +238,137 @@ impl Reseeder {
     }
 
     /// Reseed from `hosts` or from `RESEED_SERVERS` if `hosts` are not specified.
+    ///
+    /// `proxy` is the URL of an HTTP or SOCKS proxy, such as `socks5h://127.0.0.1:9050`, through
+    /// which the reseed servers are contacted.
+    ///
+    /// `certificates` are paths to certificates of reseed servers which are trusted in addition to
+    /// the built-in certificates. See [`Reseeder::load_certificates()`] for more details.
     pub async fn reseed(
         hosts: Option<Vec<String>>,
         force_ipv4: bool,
+        proxy: Option<String>,
+        certificates: Option<Vec<String>>,
     ) -> anyhow::Result<Vec<ReseedRouterInfo>> {
+        let certificates = certificates.unwrap_or_default();
+
         match hosts {
-            None => Self::reseed_inner(RESEED_SERVERS, force_ipv4).await,
+            None =>
+                Self::reseed_inner(RESEED_SERVERS, force_ipv4, proxy.as_deref(), &certificates)
+                    .await,
             Some(hosts) =>
                 Self::reseed_inner(
                     &hosts.iter().map(AsRef::as_ref).collect::<Vec<_>>(),
                     force_ipv4,
+                    proxy.as_deref(),
+                    &certificates,
                 )
                 .await,
         }
     }
+
+    /// Reseed from a local file.
+    ///
+    /// The file can either be a signed `.su3` file, which is verified the same way as files
+    /// downloaded from reseed servers, or a plain `.zip` file of router infos.
+    pub fn reseed_from_file(
+        path: impl AsRef<Path>,
+        certificates: Option<Vec<String>>,
+    ) -> anyhow::Result<Vec<ReseedRouterInfo>> {
+        let contents = std::fs::read(path.as_ref())?;
+        let (_, keys) = Self::load_certificates(&certificates.unwrap_or_default())?;
+
+        let routers = if contents.starts_with(SU3_MAGIC) {
+            Su3::parse_reseed_with_keys(&contents, &keys)
+        } else if contents.starts_with(ZIP_MAGIC) {
+            Su3::parse_reseed_zip(&contents)
+        } else {
+            return Err(anyhow!("reseed file is neither an su3 nor a zip file"));
+        };
+
+        match routers {
+            Some(routers) if !routers.is_empty() => {
+                tracing::info!(
+                    target: LOG_TARGET,
+                    path = %path.as_ref().display(),
+                    num_routers = ?routers.len(),
+                    "reseeded from file",
+                );
+
+                Ok(routers)
+            }
+            _ => Err(anyhow!("failed to parse reseed file")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const SU3: &[u8] = include_bytes!("../assets/i2pseeds.su3");
+
+    #[test]
+    fn reseed_from_su3_file() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("i2pseeds.su3"), SU3).unwrap();
+
+        let routers = Reseeder::reseed_from_file(dir.path().join("i2pseeds.su3"), None).unwrap();
+        assert!(!routers.is_empty());
+    }
+
+    #[test]
+    fn reseed_from_invalid_su3_file() {
+        let dir = tempdir().unwrap();
+        let mut bytes = SU3.to_vec();
+        let len = bytes.len();
+        bytes[len - 1] = bytes[len - 1].overflowing_add(1).0;
+        std::fs::write(dir.path().join("i2pseeds.su3"), bytes).unwrap();
+
+        assert!(Reseeder::reseed_from_file(dir.path().join("i2pseeds.su3"), None).is_err());
+    }
+
+    #[test]
+    fn reseed_from_unknown_file() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("routers.txt"), b"hello, world").unwrap();
+
+        assert!(Reseeder::reseed_from_file(dir.path().join("routers.txt"), None).is_err());
+        assert!(Reseeder::reseed_from_file(dir.path().join("missing.su3"), None).is_err());
+    }
+
+    #[test]
+    fn reseed_from_zip_file() {
+        let dir = tempdir().unwrap();
+
+        // content of the su3 file, excluding the 512-byte signature
+        let start = SU3.windows(ZIP_MAGIC.len()).position(|window| window == ZIP_MAGIC).unwrap();
+        std::fs::write(dir.path().join("routers.zip"), &SU3[start..SU3.len() - 512]).unwrap();
+
+        let routers = Reseeder::reseed_from_file(dir.path().join("routers.zip"), None).unwrap();
+        assert!(!routers.is_empty());
+    }
+
+    #[test]
+    fn reseed_from_file_with_certificate() {
+        const KEY: &str = include_str!("../assets/test/test_at_mail.i2p.pem");
+        const CERTIFICATE: &str = include_str!("../assets/test/test_at_mail.i2p.crt");
+
+        let dir = tempdir().unwrap();
+        let routers = Su3::parse_reseed(SU3, true).unwrap();
+        let su3 = Su3::create_reseed(&routers, "test@mail.i2p", KEY).unwrap();
+        let certificate = dir.path().join("test_at_mail.i2p.crt").display().to_string();
+
+        std::fs::write(dir.path().join("i2pseeds.su3"), su3).unwrap();
+        std::fs::write(&certificate, CERTIFICATE).unwrap();
+
+        assert!(Reseeder::reseed_from_file(dir.path().join("i2pseeds.su3"), None).is_err());
+        assert_eq!(
+            Reseeder::reseed_from_file(dir.path().join("i2pseeds.su3"), Some(vec![certificate]))
+                .unwrap()
+                .len(),
+            routers.len()
+        );
+    }
 }
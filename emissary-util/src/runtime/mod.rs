@@ -16,8 +16,59 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use socket2::{Domain, Protocol, Socket, Type};
+
+use std::{io, net::SocketAddr};
+
 #[cfg(feature = "tokio")]
 pub mod tokio;
 
 #[cfg(feature = "smol")]
 pub mod smol;
+
+/// Create TCP listener bound to `address`.
+///
+/// IPv6 listeners are created with `IPV6_V6ONLY` so that IPv4 and IPv6 listeners can be bound to
+/// the same port without conflicting with each other.
+#[allow(unused)]
+fn bind_tcp(address: SocketAddr) -> io::Result<std::net::TcpListener> {
+    let socket = Socket::new(
+        Domain::for_address(address),
+        Type::STREAM,
+        Some(Protocol::TCP),
+    )?;
+
+    if address.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+
+    #[cfg(not(windows))]
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&address.into())?;
+    socket.listen(1024)?;
+
+    Ok(socket.into())
+}
+
+/// Create UDP socket bound to `address`.
+///
+/// IPv6 sockets are created with `IPV6_V6ONLY` so that IPv4 and IPv6 sockets can be bound to the
+/// same port without conflicting with each other.
+#[allow(unused)]
+fn bind_udp(address: SocketAddr) -> io::Result<std::net::UdpSocket> {
+    let socket = Socket::new(
+        Domain::for_address(address),
+        Type::DGRAM,
+        Some(Protocol::UDP),
+    )?;
+
+    if address.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+
+    socket.set_nonblocking(true)?;
+    socket.bind(&address.into())?;
+
+    Ok(socket.into())
+}
@@ -169,9 +169,9 @@ impl TcpStream for SmolTcpStream {
 pub struct SmolTcpListener(Async<std::net::TcpListener>);
 
 impl TcpListener<SmolTcpStream> for SmolTcpListener {
-    // TODO: can be made sync with `socket2`
     async fn bind(address: SocketAddr) -> Option<Self> {
-        Async::<std::net::TcpListener>::bind(address)
+        super::bind_tcp(address)
+            .and_then(Async::new)
             .map_err(|error| {
                 tracing::debug!(
                     target: LOG_TARGET,
@@ -236,7 +236,8 @@ pub struct SmolUdpSocket(Arc<Async<std::net::UdpSocket>>);
 impl UdpSocket for SmolUdpSocket {
     fn bind(address: SocketAddr) -> impl Future<Output = Option<Self>> {
         async move {
-            Async::<std::net::UdpSocket>::bind(address)
+            super::bind_udp(address)
+                .and_then(Async::new)
                 .ok()
                 .map(|socket| Self(Arc::new(socket)))
         }
@@ -158,10 +158,9 @@ impl TcpStream for TokioTcpStream {
 pub struct TokioTcpListener(net::TcpListener);
 
 impl TcpListener<TokioTcpStream> for TokioTcpListener {
-    // TODO: can be made sync with `socket2`
     async fn bind(address: SocketAddr) -> Option<Self> {
-        net::TcpListener::bind(&address)
-            .await
+        super::bind_tcp(address)
+            .and_then(net::TcpListener::from_std)
             .map_err(|error| {
                 tracing::debug!(
                     target: LOG_TARGET,
@@ -205,7 +204,12 @@ pub struct TokioUdpSocket(Arc<net::UdpSocket>);
 
 impl UdpSocket for TokioUdpSocket {
     fn bind(address: SocketAddr) -> impl Future<Output = Option<Self>> {
-        async move { net::UdpSocket::bind(address).await.ok().map(|socket| Self(Arc::new(socket))) }
+        async move {
+            super::bind_udp(address)
+                .and_then(net::UdpSocket::from_std)
+                .ok()
+                .map(|socket| Self(Arc::new(socket)))
+        }
     }
 
     #[inline]
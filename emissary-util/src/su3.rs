@@ -23,6 +23,9 @@
 
 use crate::certificates::PUBLIC_KEYS;
 
+use anyhow::anyhow;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
 use nom::{
     bytes::complete::{tag, take},
     error::{make_error, ErrorKind},
@@ -30,14 +33,20 @@ use nom::{
     Err, IResult,
 };
 use rsa::{
+    pkcs1::DecodeRsaPrivateKey,
+    pkcs8::DecodePrivateKey,
     sha2::{Digest, Sha512},
-    traits::SignatureScheme,
+    traits::{PublicKeyParts, SignatureScheme},
+    Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey,
 };
 use tempfile::TempDir;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
 
 use std::{
+    collections::HashMap,
     fs::File,
-    io::{copy, Write},
+    io::{copy, Cursor, Read, Write},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 /// Logging target for the file.
@@ -46,6 +55,21 @@ const LOG_TARGET: &str = "emissary::su3";
 /// SU3 magic.
 const SU3_MAGIC: &[u8] = b"I2Psu3";
 
+/// Size of the SU3 header.
+const SU3_HEADER_LEN: usize = 40usize;
+
+/// Minimum length of the SU3 version.
+const MIN_VERSION_LEN: usize = 16usize;
+
+/// Size of an RSA-4096 signature.
+const RSA4096_SIGNATURE_LEN: usize = 512usize;
+
+/// Maximum size of a decompressed blocklist.
+const MAX_BLOCKLIST_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Maximum size of a decompressed news feed.
+const MAX_NEWS_FEED_SIZE: u64 = 4 * 1024 * 1024;
+
 /// Router info.
 pub struct ReseedRouterInfo {
     /// File name.
@@ -98,6 +122,20 @@ pub enum FileKind {
     Exe,
 }
 
+impl From<FileKind> for u8 {
+    fn from(value: FileKind) -> Self {
+        match value {
+            FileKind::Zip => 0x00,
+            FileKind::Xml => 0x01,
+            FileKind::Html => 0x02,
+            FileKind::XmlGz => 0x03,
+            FileKind::TxtGz => 0x04,
+            FileKind::Dmg => 0x05,
+            FileKind::Exe => 0x06,
+        }
+    }
+}
+
 impl TryFrom<u8> for FileKind {
     type Error = ();
 
@@ -126,6 +164,19 @@ pub enum ContentKind {
     BlocklistFeed,
 }
 
+impl From<ContentKind> for u8 {
+    fn from(value: ContentKind) -> Self {
+        match value {
+            ContentKind::Unknown => 0x00,
+            ContentKind::RouterUpdate => 0x01,
+            ContentKind::PluginUpdate => 0x02,
+            ContentKind::ReseedData => 0x03,
+            ContentKind::NewsFeed => 0x04,
+            ContentKind::BlocklistFeed => 0x05,
+        }
+    }
+}
+
 impl TryFrom<u8> for ContentKind {
     type Error = ();
 
@@ -229,42 +280,68 @@ impl<'a> Su3<'a> {
         ))
     }
 
+    /// Verify the signature of [`Su3`] using the public key returned by `key` for the signer ID.
+    fn verify<'k>(&self, key: impl FnOnce(&str) -> Option<&'k RsaPublicKey>) -> Option<()> {
+        let Ok(signer_id) = std::str::from_utf8(self.signer_id) else {
+            tracing::warn!(
+                target: LOG_TARGET,
+                "invalid signer id",
+            );
+            return None;
+        };
+
+        let SignatureKind::Rsa4096Sha512 = self.signature_kind else {
+            tracing::warn!(
+                target: LOG_TARGET,
+                %signer_id,
+                kind = ?self.signature_kind,
+                "signature kind not supported",
+            );
+            return None;
+        };
+
+        let Some(key) = key(signer_id) else {
+            tracing::warn!(
+                target: LOG_TARGET,
+                ?signer_id,
+                "public key for signer id not found",
+            );
+            return None;
+        };
+
+        // taken from ire
+        rsa::Pkcs1v15Sign::new_unprefixed()
+            .verify(key, &Sha512::digest(self.message), self.signature)
+            .ok()
+    }
+
     /// Attempt to parse reseed data from `input`.
     pub fn parse_reseed(input: &'a [u8], verify: bool) -> Option<Vec<ReseedRouterInfo>> {
-        let (_, su3) = Self::parse_inner(input).ok()?;
+        let keys = HashMap::new();
 
-        if verify {
-            let Ok(signer_id) = std::str::from_utf8(su3.signer_id) else {
-                tracing::warn!(
-                    target: LOG_TARGET,
-                    "invalid signer id",
-                );
-                return None;
-            };
+        Self::parse_reseed_inner(input, verify.then_some(&keys))
+    }
 
-            let SignatureKind::Rsa4096Sha512 = su3.signature_kind else {
-                tracing::warn!(
-                    target: LOG_TARGET,
-                    %signer_id,
-                    kind = ?su3.signature_kind,
-                    "signature kind not supported",
-                );
-                return None;
-            };
+    /// Attempt to parse reseed data from `input` and verify it using either the built-in public
+    /// keys of reseed servers or additional `keys`, indexed by signer ID.
+    pub fn parse_reseed_with_keys(
+        input: &'a [u8],
+        keys: &HashMap<String, RsaPublicKey>,
+    ) -> Option<Vec<ReseedRouterInfo>> {
+        Self::parse_reseed_inner(input, Some(keys))
+    }
 
-            let Some(key) = PUBLIC_KEYS.get(signer_id) else {
-                tracing::warn!(
-                    target: LOG_TARGET,
-                    ?signer_id,
-                    "public key for signer id not found",
-                );
-                return None;
-            };
+    /// Attempt to parse reseed data from `input`.
+    ///
+    /// Signature is verified if `keys` is specified.
+    fn parse_reseed_inner(
+        input: &'a [u8],
+        keys: Option<&HashMap<String, RsaPublicKey>>,
+    ) -> Option<Vec<ReseedRouterInfo>> {
+        let (_, su3) = Self::parse_inner(input).ok()?;
 
-            // taken from ire
-            rsa::Pkcs1v15Sign::new_unprefixed()
-                .verify(key, &Sha512::digest(su3.message), su3.signature)
-                .ok()?;
+        if let Some(keys) = keys {
+            su3.verify(|signer_id| keys.get(signer_id).or_else(|| PUBLIC_KEYS.get(signer_id)))?;
         }
 
         match (su3.file_kind, su3.content_kind) {
@@ -280,9 +357,16 @@ impl<'a> Su3<'a> {
             }
         }
 
+        Self::parse_reseed_zip(su3.content)
+    }
+
+    /// Attempt to parse reseed data from a zip file of router infos.
+    ///
+    /// The zip file is not signed so the router infos must be verified by the caller.
+    pub fn parse_reseed_zip(input: &[u8]) -> Option<Vec<ReseedRouterInfo>> {
         let temp_dir = TempDir::new().ok()?;
         let mut zip_file = File::create_new(temp_dir.path().join("routers.zip")).ok()?;
-        File::write_all(&mut zip_file, su3.content).ok()?;
+        File::write_all(&mut zip_file, input).ok()?;
 
         let mut archive = zip::ZipArchive::new(zip_file).ok()?;
         let router_infos = (0..archive.len())
@@ -313,6 +397,183 @@ impl<'a> Su3<'a> {
 
         Some(router_infos)
     }
+
+    /// Create signed reseed data from `router_infos`.
+    ///
+    /// `signing_key` must be a PEM-encoded 4096-bit RSA private key and `signer_id` the ID the
+    /// corresponding certificate is known by, e.g., `user@mail.i2p`.
+    pub fn create_reseed(
+        router_infos: &[ReseedRouterInfo],
+        signer_id: &str,
+        signing_key: &str,
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        for ReseedRouterInfo { name, router_info } in router_infos {
+            writer.start_file(name.as_str(), options)?;
+            writer.write_all(router_info)?;
+        }
+
+        let content = writer.finish()?.into_inner();
+
+        Self::create(
+            &content,
+            FileKind::Zip,
+            ContentKind::ReseedData,
+            signer_id,
+            signing_key,
+        )
+    }
+
+    /// Attempt to parse blocklist feed from `input` and verify it using `keys`, indexed by signer
+    /// ID.
+    ///
+    /// Unlike reseed data, blocklist feeds signed by the built-in reseed keys are not accepted.
+    pub fn parse_blocklist(
+        input: &'a [u8],
+        keys: &HashMap<String, RsaPublicKey>,
+    ) -> Option<String> {
+        Self::parse_compressed(
+            input,
+            keys,
+            FileKind::TxtGz,
+            ContentKind::BlocklistFeed,
+            MAX_BLOCKLIST_SIZE,
+        )
+    }
+
+    /// Attempt to parse news feed from `input` and verify it using `keys`, indexed by signer ID.
+    ///
+    /// Returns the decompressed Atom feed.
+    pub fn parse_news(input: &'a [u8], keys: &HashMap<String, RsaPublicKey>) -> Option<String> {
+        Self::parse_compressed(
+            input,
+            keys,
+            FileKind::XmlGz,
+            ContentKind::NewsFeed,
+            MAX_NEWS_FEED_SIZE,
+        )
+    }
+
+    /// Parse SU3 file from `input`, verify it using `keys` and if it's of expected kind,
+    /// decompress its content into a string of at most `max_size` bytes.
+    fn parse_compressed(
+        input: &'a [u8],
+        keys: &HashMap<String, RsaPublicKey>,
+        expected_file_kind: FileKind,
+        expected_content_kind: ContentKind,
+        max_size: u64,
+    ) -> Option<String> {
+        let (_, su3) = Self::parse_inner(input).ok()?;
+        su3.verify(|signer_id| keys.get(signer_id))?;
+
+        if su3.file_kind != expected_file_kind || su3.content_kind != expected_content_kind {
+            tracing::warn!(
+                target: LOG_TARGET,
+                file_kind = ?su3.file_kind,
+                content_kind = ?su3.content_kind,
+                ?expected_content_kind,
+                "failed to parse su3, invalid file/content kind",
+            );
+            return None;
+        }
+
+        let mut content = String::new();
+        GzDecoder::new(su3.content).take(max_size).read_to_string(&mut content).ok()?;
+
+        Some(content)
+    }
+
+    /// Create signed blocklist feed from `blocklist`.
+    ///
+    /// `signing_key` must be a PEM-encoded 4096-bit RSA private key and `signer_id` the ID the
+    /// corresponding certificate is known by.
+    pub fn create_blocklist(
+        blocklist: &str,
+        signer_id: &str,
+        signing_key: &str,
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(blocklist.as_bytes())?;
+
+        Self::create(
+            &encoder.finish()?,
+            FileKind::TxtGz,
+            ContentKind::BlocklistFeed,
+            signer_id,
+            signing_key,
+        )
+    }
+
+    /// Create signed news feed from `feed`, an Atom feed.
+    ///
+    /// `signing_key` must be a PEM-encoded 4096-bit RSA private key and `signer_id` the ID the
+    /// corresponding certificate is known by.
+    pub fn create_news(feed: &str, signer_id: &str, signing_key: &str) -> anyhow::Result<Vec<u8>> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(feed.as_bytes())?;
+
+        Self::create(
+            &encoder.finish()?,
+            FileKind::XmlGz,
+            ContentKind::NewsFeed,
+            signer_id,
+            signing_key,
+        )
+    }
+
+    /// Create signed SU3 file from `content`.
+    ///
+    /// The version of the file is the current UNIX timestamp.
+    fn create(
+        content: &[u8],
+        file_kind: FileKind,
+        content_kind: ContentKind,
+        signer_id: &str,
+        signing_key: &str,
+    ) -> anyhow::Result<Vec<u8>> {
+        let signing_key = RsaPrivateKey::from_pkcs8_pem(signing_key)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(signing_key))
+            .map_err(|_| anyhow!("invalid rsa private key"))?;
+
+        if signing_key.size() != RSA4096_SIGNATURE_LEN {
+            return Err(anyhow!("signing key must be a 4096-bit rsa key"));
+        }
+
+        if signer_id.is_empty() || signer_id.len() > u8::MAX as usize {
+            return Err(anyhow!("signer id must be 1-255 bytes long"));
+        }
+
+        let mut version =
+            SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs().to_string().into_bytes();
+        version.resize(version.len().max(MIN_VERSION_LEN), 0u8);
+
+        let mut out = Vec::with_capacity(
+            SU3_HEADER_LEN
+                + version.len()
+                + signer_id.len()
+                + content.len()
+                + RSA4096_SIGNATURE_LEN,
+        );
+
+        out.extend_from_slice(SU3_MAGIC);
+        out.extend_from_slice(&[0x00, 0x00]); // unused, su3 file format version
+        out.extend_from_slice(&0x0006u16.to_be_bytes()); // rsa-4096-sha512
+        out.extend_from_slice(&(RSA4096_SIGNATURE_LEN as u16).to_be_bytes());
+        out.extend_from_slice(&[0x00, version.len() as u8, 0x00, signer_id.len() as u8]);
+        out.extend_from_slice(&(content.len() as u64).to_be_bytes());
+        out.extend_from_slice(&[0x00, file_kind.into(), 0x00, content_kind.into()]);
+        out.extend_from_slice(&[0u8; 12]);
+        out.extend_from_slice(&version);
+        out.extend_from_slice(signer_id.as_bytes());
+        out.extend_from_slice(content);
+
+        let signature = signing_key.sign(Pkcs1v15Sign::new_unprefixed(), &Sha512::digest(&out))?;
+        out.extend_from_slice(&signature);
+
+        Ok(out)
+    }
 }
 
 #[cfg(test)]
@@ -345,4 +606,101 @@ mod tests {
 
         assert!(Su3::parse_reseed(&bytes, false).is_some());
     }
+
+    #[test]
+    fn parse_zip() {
+        let (_, su3) = Su3::parse_inner(SU3).unwrap();
+        let routers = Su3::parse_reseed_zip(su3.content).unwrap();
+
+        assert!(!routers.is_empty());
+        assert_eq!(routers.len(), Su3::parse_reseed(SU3, true).unwrap().len());
+    }
+
+    #[test]
+    fn parse_zip_invalid() {
+        assert!(Su3::parse_reseed_zip(b"hello, world").is_none());
+    }
+
+    #[test]
+    fn create_reseed() {
+        const KEY: &str = include_str!("../assets/test/test_at_mail.i2p.pem");
+        const CERTIFICATE: &str = include_str!("../assets/test/test_at_mail.i2p.crt");
+
+        let routers = Su3::parse_reseed(SU3, true).unwrap();
+        let su3 = Su3::create_reseed(&routers, "test@mail.i2p", KEY).unwrap();
+
+        // signer is not known
+        assert!(Su3::parse_reseed(&su3, true).is_none());
+
+        let keys = HashMap::from_iter([(
+            String::from("test@mail.i2p"),
+            crate::certificates::parse_public_key("test@mail.i2p", CERTIFICATE).unwrap(),
+        )]);
+        let parsed = Su3::parse_reseed_with_keys(&su3, &keys).unwrap();
+
+        assert_eq!(parsed.len(), routers.len());
+        assert!(parsed.iter().zip(routers.iter()).all(|(parsed, router)| {
+            parsed.name == router.name && parsed.router_info == router.router_info
+        }));
+
+        // built-in keys are still used
+        assert!(Su3::parse_reseed_with_keys(SU3, &keys).is_some());
+
+        // signer id doesn't match the signing key
+        let keys = HashMap::from_iter([(
+            String::from("test@mail.i2p"),
+            PUBLIC_KEYS.values().next().unwrap().clone(),
+        )]);
+        assert!(Su3::parse_reseed_with_keys(&su3, &keys).is_none());
+    }
+
+    #[test]
+    fn create_reseed_invalid_key() {
+        assert!(Su3::create_reseed(&[], "test@mail.i2p", "hello, world").is_err());
+    }
+
+    #[test]
+    fn create_blocklist() {
+        const KEY: &str = include_str!("../assets/test/test_at_mail.i2p.pem");
+        const CERTIFICATE: &str = include_str!("../assets/test/test_at_mail.i2p.crt");
+
+        let keys = HashMap::from_iter([(
+            String::from("test@mail.i2p"),
+            crate::certificates::parse_public_key("test@mail.i2p", CERTIFICATE).unwrap(),
+        )]);
+        let su3 = Su3::create_blocklist("1.2.3.4\n10.0.0.0/8\n", "test@mail.i2p", KEY).unwrap();
+
+        assert_eq!(
+            Su3::parse_blocklist(&su3, &keys).as_deref(),
+            Some("1.2.3.4\n10.0.0.0/8\n")
+        );
+
+        // signer is not known
+        assert!(Su3::parse_blocklist(&su3, &HashMap::new()).is_none());
+
+        // reseed data is not a blocklist
+        let su3 = Su3::create_reseed(&[], "test@mail.i2p", KEY).unwrap();
+        assert!(Su3::parse_blocklist(&su3, &keys).is_none());
+    }
+
+    #[test]
+    fn create_news() {
+        const KEY: &str = include_str!("../assets/test/test_at_mail.i2p.pem");
+        const CERTIFICATE: &str = include_str!("../assets/test/test_at_mail.i2p.crt");
+        const FEED: &str = "<feed xmlns=\"http://www.w3.org/2005/Atom\"></feed>";
+
+        let keys = HashMap::from_iter([(
+            String::from("test@mail.i2p"),
+            crate::certificates::parse_public_key("test@mail.i2p", CERTIFICATE).unwrap(),
+        )]);
+        let su3 = Su3::create_news(FEED, "test@mail.i2p", KEY).unwrap();
+
+        assert_eq!(Su3::parse_news(&su3, &keys).as_deref(), Some(FEED));
+
+        // news feed is not a blocklist and vice versa
+        assert!(Su3::parse_blocklist(&su3, &keys).is_none());
+
+        let su3 = Su3::create_blocklist("1.2.3.4\n", "test@mail.i2p", KEY).unwrap();
+        assert!(Su3::parse_news(&su3, &keys).is_none());
+    }
 }